@@ -18,13 +18,18 @@ use std::time::Duration;
 /// # Examples
 ///
 /// ```rust,no_run
-/// # use client_api::{Client, ClientConfig};
+/// # use client_api::{AuthOutcome, Client, ClientConfig};
 /// # async fn _doctest() -> Result<(), Box<dyn std::error::Error>> {
 /// # // ⚠ 本示例需要真实后端，仅作 API 参考。
 /// let client = Client::new(ClientConfig::new("http://127.0.0.1:8080"))?;
 ///
-/// // 登录
-/// let login = client.login("admin@example.com", "password123", false, None::<String>).await?;
+/// // 登录（启用两步验证的账号需再提交验证码）
+/// let login = match client.login("admin@example.com", "password123", false, None::<String>).await? {
+///     AuthOutcome::Authenticated(login) => login,
+///     AuthOutcome::TwoFactorRequired(challenge) => {
+///         client.login_two_factor(challenge.challenge_token, "123456").await?
+///     }
+/// };
 /// client.set_token(login.token);
 ///
 /// // 列出用户
@@ -126,7 +131,7 @@ impl Client {
         password: impl Into<String>,
         remember: bool,
         captcha_code: Option<String>,
-    ) -> Result<LoginResponse, ClientError> {
+    ) -> Result<AuthOutcome<LoginResponse>, ClientError> {
         let body = LoginRequest {
             email: email.into(),
             password: password.into(),
@@ -137,6 +142,65 @@ impl Client {
             .await
     }
 
+    /// 两步验证登录 — `POST /api/public/auth/login/2fa`
+    ///
+    /// 用 [`AuthOutcome::TwoFactorRequired`] 中的 `challenge_token` 与验证器动态码
+    /// （或恢复码）换取会话。挑战一次有效，错误次数过多后以 `401` 失效，需重新登录。
+    pub async fn login_two_factor(
+        &self,
+        challenge_token: impl Into<String>,
+        code: impl Into<String>,
+    ) -> Result<LoginResponse, ClientError> {
+        let body = TwoFactorLoginRequest {
+            challenge_token: challenge_token.into(),
+            code: code.into(),
+        };
+        self.post_json_no_auth("/api/public/auth/login/2fa", &body)
+            .await
+    }
+
+    /// 可用的第三方登录提供方 — `GET /api/public/auth/oidc/providers`
+    pub async fn oidc_providers(&self) -> Result<OidcProvidersResponse, ClientError> {
+        self.get_json_no_auth("/api/public/auth/oidc/providers")
+            .await
+    }
+
+    /// 第三方登录入口地址 — `GET /api/public/auth/oidc/{provider}/authorize`
+    ///
+    /// 由浏览器整页跳转打开（不是 API 调用）：服务端重定向到提供方，回调后写入会话
+    /// cookie 并跳回 Web 前端。
+    pub fn oidc_authorize_url(&self, provider: &str, remember: bool) -> String {
+        self.inner.config.build_url(&format!(
+            "/api/public/auth/oidc/{provider}/authorize?remember={remember}"
+        ))
+    }
+
+    /// 通行密钥登录选项 — `POST /api/public/auth/passkey/options`
+    ///
+    /// 返回 `PublicKeyCredentialRequestOptionsJSON`，交给浏览器的
+    /// `navigator.credentials.get()`；挑战一次有效。
+    pub async fn passkey_login_options(&self) -> Result<serde_json::Value, ClientError> {
+        self.post_json_no_auth("/api/public/auth/passkey/options", &serde_json::json!({}))
+            .await
+    }
+
+    /// 通行密钥登录 — `POST /api/public/auth/passkey/login`
+    ///
+    /// `credential` 为断言的 `credential.toJSON()`。验证器未做用户验证（UV）
+    /// 且账号启用了两步验证时，得到 [`AuthOutcome::TwoFactorRequired`]。
+    pub async fn passkey_login(
+        &self,
+        credential: serde_json::Value,
+        remember: bool,
+    ) -> Result<AuthOutcome<LoginResponse>, ClientError> {
+        let body = PasskeyLoginRequest {
+            credential,
+            remember,
+        };
+        self.post_json_no_auth("/api/public/auth/passkey/login", &body)
+            .await
+    }
+
     /// 注册 — `POST /api/public/auth/register`
     pub async fn register(
         &self,
@@ -210,7 +274,8 @@ impl Client {
     /// 提交 6 位验证码并重置密码 — `POST /api/public/auth/reset-password`
     ///
     /// 成功时服务端原子地 `token_version += 1` 并返回全新 JWT，
-    /// 客户端应将 `resp.token` 写入 `AuthState`（等价于登录成功）。
+    /// 客户端应将 `resp.token` 写入 `AuthState`（等价于登录成功）；
+    /// 启用两步验证的账号得到挑战，需再调用 [`Client::login_two_factor`]。
     ///
     /// `code` 是邮件中的 6 位数字验证码。失败时统一以 `400` 返回，
     /// **不区分**"验证码错误 / 已过期 / 暴力尝试上限"——防止 enumeration。
@@ -219,7 +284,7 @@ impl Client {
         email: impl Into<String>,
         code: impl Into<String>,
         new_password: impl Into<String>,
-    ) -> Result<ResetPasswordResponse, ClientError> {
+    ) -> Result<AuthOutcome<ResetPasswordResponse>, ClientError> {
         let body = ResetPasswordRequest {
             email: email.into(),
             code: code.into(),
@@ -242,7 +307,8 @@ impl Client {
     ///
     /// 用户从微信公众号获取验证码后，传入 code 进行登录。
     /// 如果 WeChat 账号未绑定任何用户，请先用 email/password 登录并在设置页绑定。
-    pub async fn wx_login(&self, code: &str) -> Result<WxLoginResponse, ClientError> {
+    /// 启用两步验证的账号同样得到挑战。
+    pub async fn wx_login(&self, code: &str) -> Result<AuthOutcome<WxLoginResponse>, ClientError> {
         let body = WxLoginRequest {
             code: code.to_string(),
         };
@@ -334,6 +400,170 @@ impl Client {
             .await
     }
 
+    /// 两步验证状态 — `GET /api/users/me/2fa`（任意已认证用户）
+    pub async fn two_factor_status(&self) -> Result<TwoFactorStatus, ClientError> {
+        self.get_json("/api/users/me/2fa", None).await
+    }
+
+    /// 开始启用两步验证 — `POST /api/users/me/2fa/enroll`（任意已认证用户）
+    ///
+    /// 需要当前密码；返回的密钥在 [`Client::confirm_two_factor`] 之前不生效，
+    /// 重复调用会替换尚未确认的密钥。
+    pub async fn enroll_two_factor(
+        &self,
+        password: impl Into<String>,
+    ) -> Result<TwoFactorEnrollment, ClientError> {
+        let body = EnrollTwoFactorRequest {
+            password: password.into(),
+        };
+        self.post_json("/api/users/me/2fa/enroll", &body, None)
+            .await
+    }
+
+    /// 用首个验证码确认启用 — `POST /api/users/me/2fa/confirm`（任意已认证用户）
+    pub async fn confirm_two_factor(
+        &self,
+        code: impl Into<String>,
+    ) -> Result<RecoveryCodesResponse, ClientError> {
+        let body = TwoFactorCodeRequest { code: code.into() };
+        self.post_json("/api/users/me/2fa/confirm", &body, None)
+            .await
+    }
+
+    /// 停用两步验证 — `POST /api/users/me/2fa/disable`（任意已认证用户）
+    pub async fn disable_two_factor(
+        &self,
+        password: impl Into<String>,
+        code: impl Into<String>,
+    ) -> Result<DisableTwoFactorResponse, ClientError> {
+        let body = DisableTwoFactorRequest {
+            password: password.into(),
+            code: code.into(),
+        };
+        self.post_json("/api/users/me/2fa/disable", &body, None)
+            .await
+    }
+
+    /// 重新生成恢复码 — `POST /api/users/me/2fa/recovery-codes`（任意已认证用户）
+    ///
+    /// 需要验证器动态码（不接受恢复码）；旧恢复码全部作废。
+    pub async fn regenerate_recovery_codes(
+        &self,
+        code: impl Into<String>,
+    ) -> Result<RecoveryCodesResponse, ClientError> {
+        let body = TwoFactorCodeRequest { code: code.into() };
+        self.post_json("/api/users/me/2fa/recovery-codes", &body, None)
+            .await
+    }
+
+    /// 当前用户的通行密钥 — `GET /api/users/me/passkeys`（任意已认证用户）
+    pub async fn list_passkeys(&self) -> Result<PasskeyListResponse, ClientError> {
+        self.get_json("/api/users/me/passkeys", None).await
+    }
+
+    /// 开始注册通行密钥 — `POST /api/users/me/passkeys/options`（任意已认证用户）
+    ///
+    /// 需要当前密码；返回 `PublicKeyCredentialCreationOptionsJSON`，交给浏览器的
+    /// `navigator.credentials.create()`。
+    pub async fn passkey_registration_options(
+        &self,
+        password: impl Into<String>,
+    ) -> Result<serde_json::Value, ClientError> {
+        let body = PasskeyOptionsRequest {
+            password: password.into(),
+        };
+        self.post_json("/api/users/me/passkeys/options", &body, None)
+            .await
+    }
+
+    /// 完成注册通行密钥 — `POST /api/users/me/passkeys`（任意已认证用户）
+    ///
+    /// `credential` 为新凭据的 `credential.toJSON()`。
+    pub async fn register_passkey(
+        &self,
+        name: Option<String>,
+        credential: serde_json::Value,
+    ) -> Result<PasskeyInfo, ClientError> {
+        let body = RegisterPasskeyRequest { name, credential };
+        self.post_json("/api/users/me/passkeys", &body, None).await
+    }
+
+    /// 撤销通行密钥 — `DELETE /api/users/me/passkeys/{id}`（任意已认证用户）
+    pub async fn delete_passkey(&self, id: i64) -> Result<DeletePasskeyResponse, ClientError> {
+        self.delete_json(&format!("/api/users/me/passkeys/{}", id), None)
+            .await
+    }
+
+    /// 当前用户的个人访问令牌 — `GET /api/users/me/tokens`（任意已登录会话）
+    pub async fn list_api_tokens(&self) -> Result<ApiTokenListResponse, ClientError> {
+        self.get_json("/api/users/me/tokens", None).await
+    }
+
+    /// 创建个人访问令牌 — `POST /api/users/me/tokens`（任意已登录会话）
+    ///
+    /// 返回的 `token` 只出现这一次；`expires_in_days` 为 `None` 时永不过期。
+    pub async fn create_api_token(
+        &self,
+        name: impl Into<String>,
+        scopes: Vec<String>,
+        expires_in_days: Option<u32>,
+    ) -> Result<CreatedApiToken, ClientError> {
+        let body = CreateApiTokenRequest {
+            name: name.into(),
+            scopes,
+            expires_in_days,
+        };
+        self.post_json("/api/users/me/tokens", &body, None).await
+    }
+
+    /// 撤销个人访问令牌 — `DELETE /api/users/me/tokens/{id}`（任意已登录会话）
+    pub async fn delete_api_token(&self, id: i64) -> Result<DeleteApiTokenResponse, ClientError> {
+        self.delete_json(&format!("/api/users/me/tokens/{}", id), None)
+            .await
+    }
+
+    /// 已关联的第三方账号 — `GET /api/users/me/identities`（任意已认证用户）
+    pub async fn list_identities(&self) -> Result<IdentityListResponse, ClientError> {
+        self.get_json("/api/users/me/identities", None).await
+    }
+
+    /// 解除第三方账号关联 — `DELETE /api/users/me/identities/{id}`（任意已认证用户）
+    pub async fn delete_identity(&self, id: i64) -> Result<DeleteIdentityResponse, ClientError> {
+        self.delete_json(&format!("/api/users/me/identities/{}", id), None)
+            .await
+    }
+
+    /// 第三方应用授权请求详情 — `POST /api/oauth/authorize/details`（任意已认证用户）
+    ///
+    /// `request` 为授权同意页 URL 中的同名参数。
+    pub async fn oauth_authorization_details(
+        &self,
+        request: impl Into<String>,
+    ) -> Result<AuthorizationDetails, ClientError> {
+        let body = AuthorizationDecisionRequest {
+            request: request.into(),
+            approve: None,
+        };
+        self.post_json("/api/oauth/authorize/details", &body, None)
+            .await
+    }
+
+    /// 同意或拒绝第三方应用授权 — `POST /api/oauth/authorize/decision`（任意已认证用户）
+    ///
+    /// 返回的 `redirect_to` 需由浏览器整页打开（回到第三方应用）。
+    pub async fn oauth_authorization_decision(
+        &self,
+        request: impl Into<String>,
+        approve: bool,
+    ) -> Result<AuthorizationDecisionResponse, ClientError> {
+        let body = AuthorizationDecisionRequest {
+            request: request.into(),
+            approve: Some(approve),
+        };
+        self.post_json("/api/oauth/authorize/decision", &body, None)
+            .await
+    }
+
     /// 创建用户 — `POST /api/users`（需要 admin 角色）
     ///
     /// `role` 仅在当前用户为 system 时生效；admin 创建时强制为 "user"。
@@ -397,6 +627,17 @@ impl Client {
             .await
     }
 
+    /// 重置用户的两步验证 — `DELETE /api/users/{id}/2fa`（需要 admin 角色）
+    ///
+    /// 用于丢失设备的用户：删除密钥、恢复码与未完成的登录挑战。
+    pub async fn reset_two_factor(
+        &self,
+        id: String,
+    ) -> Result<ResetTwoFactorResponse, ClientError> {
+        self.delete_json(&format!("/api/users/{}/2fa", id), None)
+            .await
+    }
+
     // ──────────────────────────────────────────
     //  Internal HTTP helpers
     // ──────────────────────────────────────────
@@ -1,3 +1,75 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// 单个字段的校验错误，对应 problem 体 `errors` 中的一项。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FieldError {
+    /// 失败的规则（如 `email`、`length`、`weak_password`、`mismatch`）
+    pub code: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// 服务端返回的 RFC 7807 错误体（`application/problem+json`）。
+///
+/// 除标准成员外，`error` 为错误类别（如 `validation_error`），`code` 为更细的
+/// 稳定错误码（如 `email_taken`），`errors` 按字段名给出校验错误，便于表单逐项高亮。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type", default)]
+    pub problem_type: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub status: u16,
+    #[serde(default)]
+    pub detail: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub errors: BTreeMap<String, Vec<FieldError>>,
+    /// 其余扩展成员（如 `request_id`）
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
+}
+
+impl ProblemDetails {
+    /// 解析响应体；缺少数值 `status` 成员的 JSON（旧版 `{"error", "message"}`）返回 `None`。
+    pub fn parse(body: &str) -> Option<Self> {
+        serde_json::from_str(body).ok()
+    }
+
+    /// 人类可读说明：`detail` → `message` → `title`。
+    pub fn description(&self) -> &str {
+        self.detail
+            .as_deref()
+            .or(self.message.as_deref())
+            .or(self.title.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// 机器可读错误码：优先 `code`，否则回退到错误类别 `error`。
+    pub fn error_code(&self) -> &str {
+        self.code
+            .as_deref()
+            .or(self.error.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// 指定字段的第一条错误（用于表单高亮），无 message 时返回规则码。
+    pub fn field_error(&self, field: &str) -> Option<&str> {
+        self.errors
+            .get(field)
+            .and_then(|errs| errs.first())
+            .map(|e| e.message.as_deref().unwrap_or(&e.code))
+    }
+}
+
 /// 客户端错误类型
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 #[non_exhaustive]
@@ -25,18 +97,46 @@ pub enum ClientError {
     #[error("Deserialization error: {0}")]
     Deserialization(String),
 
-    /// 其他 HTTP 错误（4xx 客户端错误等）
+    /// 服务端以 problem+json 描述的 4xx 错误（429 除外）
+    #[error("HTTP {}: {}", .0.status, .0.description())]
+    Problem(Box<ProblemDetails>),
+
+    /// 其他 HTTP 错误（响应体不是 problem+json 的 4xx 等）
     #[error("HTTP {0}: {1}")]
     Other(u16, String),
 }
 
 impl ClientError {
     /// 根据 HTTP 状态码和响应体创建对应的错误类型
+    ///
+    /// 429 / 5xx 保留原始响应体（参与重试判定）；其余状态码的 problem+json
+    /// 响应体解析为 [`ClientError::Problem`]，无法解析时归为 `Other`。
     pub fn from_status(status: u16, body: String) -> Self {
         match status {
             429 => Self::RateLimited(body),
             500..=599 => Self::ServerError(status, body),
-            _ => Self::Other(status, body),
+            _ => match ProblemDetails::parse(&body) {
+                Some(problem) => Self::Problem(Box::new(problem)),
+                None => Self::Other(status, body),
+            },
+        }
+    }
+
+    /// 服务端返回的 problem details（仅 `Problem` 变体）。
+    pub fn problem(&self) -> Option<&ProblemDetails> {
+        match self {
+            Self::Problem(problem) => Some(problem),
+            _ => None,
+        }
+    }
+
+    /// HTTP 状态码，非 HTTP 错误返回 `None`。
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Other(s, _) | Self::ServerError(s, _) => Some(*s),
+            Self::Problem(problem) => Some(problem.status),
+            Self::RateLimited(_) => Some(429),
+            _ => None,
         }
     }
 
@@ -46,6 +146,7 @@ impl ClientError {
     pub fn status_or_label(&self) -> String {
         match self {
             Self::Other(s, _) | Self::ServerError(s, _) => s.to_string(),
+            Self::Problem(problem) => problem.status.to_string(),
             _ => "ERR".to_string(),
         }
     }
@@ -82,3 +183,76 @@ impl From<serde_json::Error> for ClientError {
         Self::Deserialization(format!("Failed to parse response: {}", e))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALIDATION_PROBLEM: &str = r#"{
+        "type": "about:blank", "title": "Bad Request", "status": 400,
+        "detail": "email: must be a valid email address",
+        "error": "validation_error", "message": "email: must be a valid email address",
+        "errors": {
+            "email": [{ "code": "email", "message": "must be a valid email address" }],
+            "password": [{ "code": "weak_password" }]
+        },
+        "request_id": "req-1"
+    }"#;
+
+    #[test]
+    fn from_status_parses_problem_json() {
+        let err = ClientError::from_status(400, VALIDATION_PROBLEM.to_string());
+        let problem = err.problem().expect("problem details");
+        assert_eq!(problem.status, 400);
+        assert_eq!(problem.error_code(), "validation_error");
+        assert_eq!(
+            problem.field_error("email"),
+            Some("must be a valid email address")
+        );
+        assert_eq!(problem.field_error("password"), Some("weak_password"));
+        assert_eq!(problem.field_error("name"), None);
+        assert_eq!(problem.extensions["request_id"], "req-1");
+        assert_eq!(err.status(), Some(400));
+        assert_eq!(err.status_or_label(), "400");
+        assert_eq!(
+            err.to_string(),
+            "HTTP 400: email: must be a valid email address"
+        );
+    }
+
+    #[test]
+    fn code_takes_precedence_over_error_type() {
+        let err = ClientError::from_status(
+            409,
+            r#"{"status":409,"error":"conflict","code":"email_taken","detail":"Email already registered"}"#
+                .to_string(),
+        );
+        assert_eq!(err.problem().unwrap().error_code(), "email_taken");
+    }
+
+    #[test]
+    fn legacy_and_plain_bodies_stay_other() {
+        let legacy = r#"{"error":"not_found","message":"User not found"}"#;
+        assert_eq!(
+            ClientError::from_status(404, legacy.to_string()),
+            ClientError::Other(404, legacy.to_string())
+        );
+        assert!(matches!(
+            ClientError::from_status(400, "Bad Request".into()),
+            ClientError::Other(400, _)
+        ));
+    }
+
+    #[test]
+    fn rate_limit_and_server_errors_keep_raw_body() {
+        let body = r#"{"status":503,"error":"service_unavailable"}"#.to_string();
+        assert!(matches!(
+            ClientError::from_status(503, body.clone()),
+            ClientError::ServerError(503, _)
+        ));
+        assert!(matches!(
+            ClientError::from_status(429, body),
+            ClientError::RateLimited(_)
+        ));
+    }
+}
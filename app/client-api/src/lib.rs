@@ -3,7 +3,7 @@
 //! # 快速开始
 //!
 //! ```rust,no_run
-//! # use client_api::{Client, ClientConfig};
+//! # use client_api::{AuthOutcome, Client, ClientConfig};
 //! # async fn _doctest() -> Result<(), Box<dyn std::error::Error>> {
 //! # // ⚠ 本示例需要真实后端，仅作 API 参考。
 //! // 原生平台：指定后端地址（本地开发用 localhost，生产用域名）
@@ -12,8 +12,13 @@
 //! // 注意：空 base_url（相对路径）仅在 WASM（浏览器）环境下有效，
 //! // 原生平台（桌面/移动端）必须使用完整的 http:// 或 https:// URL。
 //!
-//! // 登录
-//! let login = client.login("admin@example.com", "password123", false, None::<String>).await?;
+//! // 登录（启用两步验证的账号需再提交验证码）
+//! let login = match client.login("admin@example.com", "password123", false, None::<String>).await? {
+//!     AuthOutcome::Authenticated(login) => login,
+//!     AuthOutcome::TwoFactorRequired(challenge) => {
+//!         client.login_two_factor(challenge.challenge_token, "123456").await?
+//!     }
+//! };
 //! client.set_token(login.token);
 //!
 //! // 列出用户（admin 权限）
@@ -43,5 +48,5 @@ pub mod types;
 
 pub use client::Client;
 pub use config::ClientConfig;
-pub use error::ClientError;
+pub use error::{ClientError, FieldError, ProblemDetails};
 pub use types::*;
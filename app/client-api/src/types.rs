@@ -31,6 +31,36 @@ pub struct LoginResponse {
     pub refresh_expires_in: Option<u64>,
 }
 
+/// 密码校验通过、但账号启用了两步验证时的 `202` 响应（不设置 Cookie）
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwoFactorChallenge {
+    /// 恒为 `true`，用于与登录响应区分
+    pub two_factor_required: bool,
+    /// 交给 [`Client::login_two_factor`](crate::Client::login_two_factor)
+    pub challenge_token: String,
+    /// 挑战有效期（秒）
+    pub expires_in: u64,
+}
+
+/// 会签发会话的端点（登录、重置密码、微信登录）的结果
+///
+/// 启用两步验证的账号得到 `TwoFactorRequired`，需再提交验证码完成登录。
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum AuthOutcome<T> {
+    // 顺序有意为之：untagged 按顺序尝试，挑战体字段更特殊须先匹配
+    TwoFactorRequired(TwoFactorChallenge),
+    Authenticated(T),
+}
+
+/// Two-factor login request body
+#[derive(Debug, Serialize)]
+pub struct TwoFactorLoginRequest {
+    pub challenge_token: String,
+    /// 验证器 App 的 6 位动态码，或一次性恢复码
+    pub code: String,
+}
+
 /// Register request body
 #[derive(Debug, Serialize)]
 pub struct RegisterRequest {
@@ -122,7 +152,7 @@ pub struct ResetPasswordRequest {
 ///
 /// 与 `LoginResponse` 同形（多一个 `message`），因为服务端在事务内
 /// 原子地 `token_version += 1` 后直接签发新 JWT —— 验证码校验通过
-/// 即等于登录。
+/// 即等于登录（启用两步验证的账号除外，见 [`AuthOutcome`]）。
 #[derive(Debug, Deserialize)]
 pub struct ResetPasswordResponse {
     pub message: String,
@@ -244,6 +274,237 @@ pub struct ChangePasswordResponse {
     pub new_token: String,
 }
 
+// ──────────────────────────────────────────────
+//  Two-factor types
+// ──────────────────────────────────────────────
+
+/// Two-factor status (`GET /api/users/me/2fa`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    /// 已开始启用但尚未用首个验证码确认
+    pub pending_enrollment: bool,
+    pub recovery_codes_remaining: u64,
+}
+
+/// Start enrollment request body
+#[derive(Debug, Serialize)]
+pub struct EnrollTwoFactorRequest {
+    pub password: String,
+}
+
+/// Pending TOTP secret — 添加到验证器 App 后用首个验证码确认
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwoFactorEnrollment {
+    /// Base32 密钥，供手动输入
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// Request body carrying an authenticator code
+#[derive(Debug, Serialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+/// Disable two-factor request body
+#[derive(Debug, Serialize)]
+pub struct DisableTwoFactorRequest {
+    pub password: String,
+    /// 验证器动态码或恢复码
+    pub code: String,
+}
+
+/// 一次性恢复码 —— 仅此一次返回明文，服务端只保存哈希
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Disable two-factor response
+#[derive(Debug, Deserialize)]
+pub struct DisableTwoFactorResponse {
+    pub message: String,
+}
+
+/// Admin two-factor reset response
+#[derive(Debug, Deserialize)]
+pub struct ResetTwoFactorResponse {
+    /// 用户原本没有两步验证（或未完成的启用）时为 `false`
+    pub removed: bool,
+}
+
+// ──────────────────────────────────────────────
+//  OpenID Connect types
+// ──────────────────────────────────────────────
+
+/// A login provider offered on the login page
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OidcProvider {
+    /// 授权地址中使用的提供方 key
+    pub id: String,
+    pub name: String,
+}
+
+/// Login provider list response
+#[derive(Debug, Deserialize)]
+pub struct OidcProvidersResponse {
+    pub providers: Vec<OidcProvider>,
+}
+
+/// An external account linked to the current user (`GET /api/users/me/identities`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IdentityInfo {
+    pub id: i64,
+    pub provider: String,
+    pub provider_name: String,
+    pub email: Option<String>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Linked account list response
+#[derive(Debug, Deserialize)]
+pub struct IdentityListResponse {
+    pub identities: Vec<IdentityInfo>,
+}
+
+/// Unlink account response
+#[derive(Debug, Deserialize)]
+pub struct DeleteIdentityResponse {
+    pub message: String,
+}
+
+// ──────────────────────────────────────────────
+//  OAuth consent types
+// ──────────────────────────────────────────────
+
+/// A pending third-party authorization request (`POST /api/oauth/authorize/details`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AuthorizationDetails {
+    pub client_id: String,
+    pub client_name: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    /// 为 false 时无需再次询问用户（信任的客户端或已同意过这些权限）
+    pub consent_required: bool,
+}
+
+/// Request body shared by the consent page's details and decision calls
+#[derive(Debug, Serialize)]
+pub struct AuthorizationDecisionRequest {
+    pub request: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approve: Option<bool>,
+}
+
+/// Allow / deny response: where to send the browser next
+#[derive(Debug, Deserialize)]
+pub struct AuthorizationDecisionResponse {
+    pub redirect_to: String,
+}
+
+// ──────────────────────────────────────────────
+//  Passkey types
+// ──────────────────────────────────────────────
+
+/// Request body carrying the current password (start passkey registration)
+#[derive(Debug, Serialize)]
+pub struct PasskeyOptionsRequest {
+    pub password: String,
+}
+
+/// Finish passkey registration request body
+#[derive(Debug, Serialize)]
+pub struct RegisterPasskeyRequest {
+    /// 列表中显示的名称，缺省为 "Passkey"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 浏览器 `credential.toJSON()` 的结果，原样转发
+    pub credential: serde_json::Value,
+}
+
+/// Passkey login request body
+#[derive(Debug, Serialize)]
+pub struct PasskeyLoginRequest {
+    /// 浏览器 `credential.toJSON()` 的结果，原样转发
+    pub credential: serde_json::Value,
+    #[serde(default)]
+    pub remember: bool,
+}
+
+/// A registered passkey (`GET /api/users/me/passkeys`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PasskeyInfo {
+    pub id: i64,
+    pub name: String,
+    pub transports: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Passkey list response
+#[derive(Debug, Deserialize)]
+pub struct PasskeyListResponse {
+    pub passkeys: Vec<PasskeyInfo>,
+}
+
+/// Delete passkey response
+#[derive(Debug, Deserialize)]
+pub struct DeletePasskeyResponse {
+    pub message: String,
+}
+
+// ──────────────────────────────────────────────
+//  Personal access token types
+// ──────────────────────────────────────────────
+
+/// Create personal access token request body
+#[derive(Debug, Serialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    /// 授予的权限范围，如 `["users:read"]`
+    pub scopes: Vec<String>,
+    /// 有效天数（1-365），缺省为永不过期
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_days: Option<u32>,
+}
+
+/// A personal access token (`GET /api/users/me/tokens`); never includes the secret
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ApiTokenInfo {
+    pub id: i64,
+    pub name: String,
+    /// 令牌开头部分，用于区分不同令牌
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub last_used_ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Newly created token; `token` is only returned this once
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub info: ApiTokenInfo,
+    pub token: String,
+}
+
+/// Personal access token list response
+#[derive(Debug, Deserialize)]
+pub struct ApiTokenListResponse {
+    pub tokens: Vec<ApiTokenInfo>,
+}
+
+/// Delete personal access token response
+#[derive(Debug, Deserialize)]
+pub struct DeleteApiTokenResponse {
+    pub message: String,
+}
+
 // ──────────────────────────────────────────────
 //  WeChat captcha-login types
 // ──────────────────────────────────────────────
@@ -5,10 +5,10 @@
 use wiremock::matchers::{body_json, header, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
-use client_api::ClientError;
+use client_api::AuthOutcome;
 
 mod common;
-use common::{create_test_client, fixtures};
+use common::{coded_problem, create_test_client, field_problem, fixtures};
 
 // ──────────────────────────────────────────────
 //  Login tests
@@ -35,7 +35,9 @@ async fn test_login_success() {
         .await;
 
     assert!(result.is_ok());
-    let resp = result.unwrap();
+    let AuthOutcome::Authenticated(resp) = result.unwrap() else {
+        panic!("expected a session, got a two-factor challenge");
+    };
     assert_eq!(resp.token, fixtures::TEST_TOKEN);
     assert_eq!(resp.token_type, "Bearer");
     assert_eq!(resp.expires_in, 3600);
@@ -69,7 +71,9 @@ async fn test_login_admin_role() {
         .await;
 
     assert!(result.is_ok());
-    let resp = result.unwrap();
+    let AuthOutcome::Authenticated(resp) = result.unwrap() else {
+        panic!("expected a session, got a two-factor challenge");
+    };
     assert_eq!(resp.role, "admin");
     assert_eq!(resp.expires_in, 7200);
 }
@@ -80,10 +84,12 @@ async fn test_login_invalid_credentials() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/login"))
-        .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
-            "error": "unauthorized",
-            "message": "Invalid email or password",
-        })))
+        .respond_with(coded_problem(
+            401,
+            "unauthorized",
+            "invalid_credentials",
+            "Invalid email or password",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -91,12 +97,11 @@ async fn test_login_invalid_credentials() {
         .login("wrong@example.com", "wrongpassword", false, None)
         .await;
 
-    match result.unwrap_err() {
-        ClientError::Other(401, msg) => {
-            assert!(msg.contains("unauthorized") || msg.contains("Invalid"));
-        }
-        other => panic!("Expected Other(401, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.status, 401);
+    assert_eq!(problem.error_code(), "invalid_credentials");
+    assert_eq!(problem.description(), "Invalid email or password");
 }
 
 #[tokio::test]
@@ -127,6 +132,134 @@ async fn test_login_body_matches_request() {
     assert!(result.is_ok());
 }
 
+#[tokio::test]
+async fn test_login_two_factor_challenge() {
+    let (client, mock_server) = create_test_client().await;
+
+    // 启用两步验证的账号：密码步骤返回 202 + 挑战，不签发 JWT
+    Mock::given(method("POST"))
+        .and(path("/api/public/auth/login"))
+        .respond_with(ResponseTemplate::new(202).set_body_json(serde_json::json!({
+            "two_factor_required": true,
+            "challenge_token": "challenge-abc",
+            "expires_in": 300,
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/public/auth/login/2fa"))
+        .and(body_json(serde_json::json!({
+            "challenge_token": "challenge-abc",
+            "code": "123456",
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "token": fixtures::TEST_TOKEN,
+            "token_type": "Bearer",
+            "expires_in": 3600,
+            "user_id": fixtures::TEST_USER_ID,
+            "role": "user",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let outcome = client
+        .login(fixtures::TEST_EMAIL, fixtures::TEST_PASSWORD, false, None)
+        .await
+        .unwrap();
+    let AuthOutcome::TwoFactorRequired(challenge) = outcome else {
+        panic!("expected a two-factor challenge, got a session");
+    };
+    assert_eq!(challenge.expires_in, 300);
+
+    let resp = client
+        .login_two_factor(challenge.challenge_token, "123456")
+        .await
+        .unwrap();
+    assert_eq!(resp.token, fixtures::TEST_TOKEN);
+}
+
+#[tokio::test]
+async fn test_passkey_login() {
+    let (client, mock_server) = create_test_client().await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/public/auth/passkey/options"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "challenge": "q2n0aW9u",
+            "timeout": 300000,
+            "rpId": "localhost",
+            "allowCredentials": [],
+            "userVerification": "preferred",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    // 断言原样转发给服务端，客户端不解析其内容
+    let credential = serde_json::json!({
+        "id": "Y3JlZA",
+        "type": "public-key",
+        "response": {
+            "clientDataJSON": "e30",
+            "authenticatorData": "AAAA",
+            "signature": "MEUC",
+            "userHandle": "AAAAAAAAAAE",
+        },
+    });
+    Mock::given(method("POST"))
+        .and(path("/api/public/auth/passkey/login"))
+        .and(body_json(serde_json::json!({
+            "credential": credential.clone(),
+            "remember": true,
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "token": fixtures::TEST_TOKEN,
+            "token_type": "Bearer",
+            "expires_in": 3600,
+            "user_id": fixtures::TEST_USER_ID,
+            "role": "user",
+            "refresh_expires_in": 2592000,
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let options = client.passkey_login_options().await.unwrap();
+    assert_eq!(options["rpId"], "localhost");
+
+    let outcome = client.passkey_login(credential, true).await.unwrap();
+    let AuthOutcome::Authenticated(resp) = outcome else {
+        panic!("expected a session, got a two-factor challenge");
+    };
+    assert_eq!(resp.user_id, fixtures::TEST_USER_ID);
+}
+
+#[tokio::test]
+async fn test_oidc_providers_and_authorize_url() {
+    let (client, mock_server) = create_test_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/public/auth/oidc/providers"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "providers": [{ "id": "google", "name": "Google" }],
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let resp = client.oidc_providers().await.unwrap();
+    assert_eq!(resp.providers.len(), 1);
+    assert_eq!(resp.providers[0].id, "google");
+    assert_eq!(resp.providers[0].name, "Google");
+
+    // 入口地址由浏览器整页打开，不发请求
+    assert_eq!(
+        client.oidc_authorize_url("google", true),
+        format!(
+            "{}/api/public/auth/oidc/google/authorize?remember=true",
+            mock_server.uri()
+        )
+    );
+}
+
 // ──────────────────────────────────────────────
 //  Register tests
 // ──────────────────────────────────────────────
@@ -173,10 +306,12 @@ async fn test_register_email_conflict() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/register"))
-        .respond_with(ResponseTemplate::new(409).set_body_json(serde_json::json!({
-            "error": "conflict",
-            "message": "Email already registered",
-        })))
+        .respond_with(coded_problem(
+            409,
+            "conflict",
+            "email_taken",
+            "Email already registered",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -190,12 +325,10 @@ async fn test_register_email_conflict() {
         )
         .await;
 
-    match result.unwrap_err() {
-        ClientError::Other(409, msg) => {
-            assert!(msg.contains("conflict") || msg.contains("already registered"));
-        }
-        other => panic!("Expected Other(409, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.status, 409);
+    assert_eq!(problem.error_code(), "email_taken");
 }
 
 #[tokio::test]
@@ -204,10 +337,11 @@ async fn test_register_validation_error() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/register"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "validation_error",
-            "message": "password must be at least 8 characters",
-        })))
+        .respond_with(field_problem(
+            "password",
+            "length",
+            "password must be at least 8 characters",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -215,12 +349,14 @@ async fn test_register_validation_error() {
         .register("test@example.com", "short", "Test User", false, "short")
         .await;
 
-    match result.unwrap_err() {
-        ClientError::Other(400, msg) => {
-            assert!(msg.contains("validation_error") || msg.contains("password"));
-        }
-        other => panic!("Expected Other(400, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.status, 400);
+    assert_eq!(problem.error_code(), "validation_error");
+    assert_eq!(
+        problem.field_error("password"),
+        Some("password must be at least 8 characters")
+    );
 }
 
 // ──────────────────────────────────────────────
@@ -255,21 +391,21 @@ async fn test_verify_email_invalid_code() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/verify-email"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "bad_request",
-            "message": "Invalid or expired verification code",
-        })))
+        .respond_with(coded_problem(
+            400,
+            "bad_request",
+            "invalid_code",
+            "Invalid or expired verification code",
+        ))
         .mount(&mock_server)
         .await;
 
     let result = client.verify_email("newuser@example.com", "000000").await;
 
-    match result.unwrap_err() {
-        ClientError::Other(400, msg) => {
-            assert!(msg.contains("Invalid") || msg.contains("expired"));
-        }
-        other => panic!("Expected Other(400, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.status, 400);
+    assert_eq!(problem.error_code(), "invalid_code");
 }
 
 #[tokio::test]
@@ -278,19 +414,19 @@ async fn test_verify_email_validation_error() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/verify-email"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "validation_error",
-            "message": "code must be 6 digits",
-        })))
+        .respond_with(field_problem("code", "length", "code must be 6 characters"))
         .mount(&mock_server)
         .await;
 
     let result = client.verify_email("user@example.com", "abc").await;
 
-    match result.unwrap_err() {
-        ClientError::Other(400, msg) => assert!(msg.contains("validation_error")),
-        other => panic!("Expected Other(400, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.error_code(), "validation_error");
+    assert_eq!(
+        problem.field_error("code"),
+        Some("code must be 6 characters")
+    );
 }
 
 #[tokio::test]
@@ -322,19 +458,21 @@ async fn test_resend_code_too_soon() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/resend-code"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "bad_request",
-            "message": "Please wait before requesting a new code",
-        })))
+        .respond_with(coded_problem(
+            400,
+            "bad_request",
+            "resend_too_soon",
+            "Please wait before requesting a new code",
+        ))
         .mount(&mock_server)
         .await;
 
     let result = client.resend_code("newuser@example.com").await;
 
-    match result.unwrap_err() {
-        ClientError::Other(400, msg) => assert!(msg.contains("wait")),
-        other => panic!("Expected Other(400, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.error_code(), "resend_too_soon");
+    assert!(problem.description().contains("wait"));
 }
 
 #[tokio::test]
@@ -444,10 +582,13 @@ async fn test_login_and_use_token_for_authenticated_request() {
         .mount(&mock_server)
         .await;
 
-    let login = client
+    let AuthOutcome::Authenticated(login) = client
         .login(fixtures::TEST_EMAIL, fixtures::TEST_PASSWORD, false, None)
         .await
-        .unwrap();
+        .unwrap()
+    else {
+        panic!("expected a session, got a two-factor challenge");
+    };
     client.set_token(&login.token);
     assert!(client.is_authenticated());
 
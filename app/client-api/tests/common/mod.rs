@@ -3,7 +3,8 @@
 //! 提供 Wiremock Mock 服务器和测试辅助函数/夹具。
 
 use client_api::{Client, ClientConfig};
-use wiremock::MockServer;
+use serde_json::{Value, json};
+use wiremock::{MockServer, ResponseTemplate};
 
 /// 创建带 Mock 服务器的测试客户端。
 ///
@@ -18,6 +19,47 @@ pub async fn create_test_client() -> (Client, MockServer) {
     (client, mock_server)
 }
 
+/// 服务端错误响应（`application/problem+json`），成员与服务端 `HttpError::to_problem_json` 一致。
+#[allow(dead_code)]
+pub fn problem(status: u16, error: &str, detail: &str) -> ResponseTemplate {
+    problem_response(status, problem_body(status, error, detail))
+}
+
+/// 带稳定错误码（`code` 成员）的 problem 响应，如 409 `email_taken`。
+#[allow(dead_code)]
+pub fn coded_problem(status: u16, error: &str, code: &str, detail: &str) -> ResponseTemplate {
+    let mut body = problem_body(status, error, detail);
+    body["code"] = code.into();
+    problem_response(status, body)
+}
+
+/// 单字段校验失败（400 `validation_error`），与 `ApiError::invalid_field` 的输出一致。
+#[allow(dead_code)]
+pub fn field_problem(field: &str, code: &str, message: &str) -> ResponseTemplate {
+    let mut body = problem_body(400, "validation_error", &format!("{field}: {message}"));
+    body["errors"] = json!({ field: [{ "code": code, "message": message }] });
+    problem_response(400, body)
+}
+
+fn problem_body(status: u16, error: &str, detail: &str) -> Value {
+    let title = reqwest::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("Error");
+    json!({
+        "type": "about:blank",
+        "title": title,
+        "status": status,
+        "detail": detail,
+        "error": error,
+        "message": detail,
+    })
+}
+
+fn problem_response(status: u16, body: Value) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_raw(body.to_string(), "application/problem+json")
+}
+
 /// 测试用常量夹具
 #[allow(dead_code)]
 pub mod fixtures {
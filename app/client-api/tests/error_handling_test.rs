@@ -9,7 +9,7 @@ use wiremock::matchers::{method, path};
 use wiremock::{Mock, Request, Respond, ResponseTemplate};
 
 mod common;
-use common::{create_test_client, fixtures};
+use common::{coded_problem, create_test_client, field_problem, fixtures, problem};
 
 // ──────────────────────────────────────────────
 //  HTTP error status codes
@@ -21,10 +21,12 @@ async fn test_401_unauthorized() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/login"))
-        .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
-            "error": "unauthorized",
-            "message": "Invalid email or password",
-        })))
+        .respond_with(coded_problem(
+            401,
+            "unauthorized",
+            "invalid_credentials",
+            "Invalid email or password",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -34,11 +36,12 @@ async fn test_401_unauthorized() {
         .unwrap_err();
 
     match err {
-        ClientError::Other(status, msg) => {
-            assert_eq!(status, 401);
-            assert!(msg.contains("unauthorized") || msg.contains("Invalid"));
+        ClientError::Problem(problem) => {
+            assert_eq!(problem.status, 401);
+            assert_eq!(problem.error_code(), "invalid_credentials");
+            assert_eq!(problem.description(), "Invalid email or password");
         }
-        other => panic!("Expected Other(401, ...), got {:?}", other),
+        other => panic!("Expected Problem(401), got {:?}", other),
     }
 }
 
@@ -51,21 +54,19 @@ async fn test_404_not_found() {
 
     Mock::given(method("GET"))
         .and(path(format!("/api/users/{}", id)))
-        .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
-            "error": "not_found",
-            "message": "User not found",
-        })))
+        .respond_with(problem(404, "not_found", "User not found"))
         .mount(&mock_server)
         .await;
 
     let err = client.get_user(id).await.unwrap_err();
 
     match err {
-        ClientError::Other(status, msg) => {
-            assert_eq!(status, 404);
-            assert!(msg.contains("not_found") || msg.contains("User not found"));
+        ClientError::Problem(problem) => {
+            assert_eq!(problem.status, 404);
+            assert_eq!(problem.error_code(), "not_found");
+            assert_eq!(problem.description(), "User not found");
         }
-        other => panic!("Expected Other(404, ...), got {:?}", other),
+        other => panic!("Expected Problem(404), got {:?}", other),
     }
 }
 
@@ -76,10 +77,12 @@ async fn test_409_conflict() {
 
     Mock::given(method("POST"))
         .and(path("/api/users"))
-        .respond_with(ResponseTemplate::new(409).set_body_json(serde_json::json!({
-            "error": "conflict",
-            "message": "Email already registered",
-        })))
+        .respond_with(coded_problem(
+            409,
+            "conflict",
+            "email_taken",
+            "Email already registered",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -89,11 +92,11 @@ async fn test_409_conflict() {
         .unwrap_err();
 
     match err {
-        ClientError::Other(status, msg) => {
-            assert_eq!(status, 409);
-            assert!(msg.contains("conflict"));
+        ClientError::Problem(problem) => {
+            assert_eq!(problem.status, 409);
+            assert_eq!(problem.error_code(), "email_taken");
         }
-        other => panic!("Expected Other(409, ...), got {:?}", other),
+        other => panic!("Expected Problem(409), got {:?}", other),
     }
 }
 
@@ -103,10 +106,7 @@ async fn test_500_server_error() {
 
     Mock::given(method("GET"))
         .and(path("/api/health"))
-        .respond_with(ResponseTemplate::new(500).set_body_json(serde_json::json!({
-            "error": "internal_error",
-            "message": "Internal server error",
-        })))
+        .respond_with(problem(500, "internal_error", "Internal server error"))
         .mount(&mock_server)
         .await;
 
@@ -128,12 +128,8 @@ async fn test_503_service_unavailable() {
     Mock::given(method("GET"))
         .and(path("/api/health"))
         .respond_with(
-            ResponseTemplate::new(503)
-                .insert_header("Retry-After", "120")
-                .set_body_json(serde_json::json!({
-                    "error": "service_unavailable",
-                    "message": "Database connection failed",
-                })),
+            problem(503, "service_unavailable", "Database connection failed")
+                .insert_header("Retry-After", "120"),
         )
         .mount(&mock_server)
         .await;
@@ -155,12 +151,7 @@ async fn test_429_rate_limited() {
     Mock::given(method("POST"))
         .and(path("/api/public/auth/login"))
         .respond_with(
-            ResponseTemplate::new(429)
-                .insert_header("Retry-After", "60")
-                .set_body_json(serde_json::json!({
-                    "error": "rate_limited",
-                    "message": "Too many requests",
-                })),
+            problem(429, "rate_limited", "Too many requests").insert_header("Retry-After", "60"),
         )
         .mount(&mock_server)
         .await;
@@ -229,18 +220,18 @@ async fn test_invalid_json_response() {
 // ──────────────────────────────────────────────
 
 #[tokio::test]
-async fn test_structured_error_body_raw_json() {
+async fn test_structured_error_body_problem_json() {
     let (client, mock_server) = create_test_client().await;
 
-    // 后端返回结构化错误 {"error": "...", "message": "..."}
-    // Client::handle_response 不再预先格式化为 "[code] message"，
-    // 而是将原始 JSON body 原样传递给调用方，由 humanize_error 自行解析。
+    // 后端以 application/problem+json 返回错误，字段级校验失败带 `errors` 成员。
+    // Client::handle_response 将其解析为 ClientError::Problem，调用方无需再解析原始 body。
     Mock::given(method("POST"))
         .and(path("/api/public/auth/login"))
-        .respond_with(ResponseTemplate::new(422).set_body_json(serde_json::json!({
-            "error": "validation_error",
-            "message": "email must be a valid email address",
-        })))
+        .respond_with(field_problem(
+            "email",
+            "email",
+            "must be a valid email address",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -249,31 +240,23 @@ async fn test_structured_error_body_raw_json() {
         .await
         .unwrap_err();
 
-    match err {
-        ClientError::Other(status, msg) => {
-            assert_eq!(status, 422);
-            // 验证传递的是原始 JSON body，而非预格式化的 "[code] message"。
-            // 调用方 (如 humanize_error) 可自行反序列化 ErrorBody。
-            assert!(msg.starts_with('{'), "Expected raw JSON body, got: {msg:?}");
-            assert!(
-                msg.contains(r#""validation_error""#),
-                "Raw JSON should contain error code, got: {msg:?}"
-            );
-            assert!(
-                msg.contains(r#""email must be a valid email address""#),
-                "Raw JSON should contain error message, got: {msg:?}"
+    match &err {
+        ClientError::Problem(problem) => {
+            assert_eq!(problem.status, 400);
+            assert_eq!(problem.error_code(), "validation_error");
+            assert_eq!(
+                problem.description(),
+                "email: must be a valid email address"
             );
-            // 额外验证：不应包含旧格式的方括号前缀
-            assert!(
-                !msg.starts_with('['),
-                "Should NOT contain pre-formatted '[code]' pattern, got: {msg:?}"
+            assert_eq!(
+                problem.field_error("email"),
+                Some("must be a valid email address")
             );
+            assert_eq!(problem.field_error("password"), None);
         }
-        other => panic!(
-            "Expected Other(422, ...) with raw JSON body, got {:?}",
-            other
-        ),
+        other => panic!("Expected Problem(400), got {:?}", other),
     }
+    assert_eq!(err.status(), Some(400));
 }
 
 // ──────────────────────────────────────────────
@@ -409,10 +392,7 @@ async fn test_no_retry_on_4xx() {
     // 返回 404 — 不应重试，mock 期望仅命中 1 次
     Mock::given(method("GET"))
         .and(path("/api/health"))
-        .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
-            "error": "not_found",
-            "message": "Resource not found",
-        })))
+        .respond_with(problem(404, "not_found", "Resource not found"))
         .expect(1)
         .mount(&mock_server)
         .await;
@@ -421,9 +401,9 @@ async fn test_no_retry_on_4xx() {
     assert!(result.is_err());
 
     match result.unwrap_err() {
-        ClientError::Other(status, _) => {
-            assert_eq!(status, 404);
+        ClientError::Problem(problem) => {
+            assert_eq!(problem.status, 404);
         }
-        other => panic!("Expected Other(404), got {:?}", other),
+        other => panic!("Expected Problem(404), got {:?}", other),
     }
 }
@@ -4,7 +4,7 @@ use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
 
 mod common;
-use common::create_test_client;
+use common::{create_test_client, problem};
 
 #[tokio::test]
 async fn test_health_check_ok() {
@@ -50,10 +50,11 @@ async fn test_health_check_down() {
 
     Mock::given(method("GET"))
         .and(path("/api/health"))
-        .respond_with(ResponseTemplate::new(503).set_body_json(serde_json::json!({
-            "error": "service_unavailable",
-            "message": "Database connection failed",
-        })))
+        .respond_with(problem(
+            503,
+            "service_unavailable",
+            "Database connection failed",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -4,13 +4,15 @@
 //!   唯一真实错误码是 503（邮件服务未配置）。
 //! - `reset_password`：服务端把 "token 不存在 / 已过期 / 错误 / 已被消费 /
 //!   暴力尝试上限 / 弱密码" 全部统一 400 + 通用文案（anti-enumeration +
-//!   凭证探测防护）。成功路径会签发新 JWT。
+//!   凭证探测防护）。成功路径会签发新 JWT（启用两步验证的账号返回 202 挑战）。
 
 use wiremock::matchers::{body_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
+use client_api::AuthOutcome;
+
 mod common;
-use common::{create_test_client, fixtures};
+use common::{coded_problem, create_test_client, field_problem, fixtures, problem};
 
 /// 模拟合法的 6 位重置验证码。
 const VALID_RESET_CODE: &str = "483921";
@@ -69,26 +71,22 @@ async fn test_forgot_password_email_service_unavailable() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/forgot-password"))
-        .respond_with(ResponseTemplate::new(503).set_body_json(serde_json::json!({
-            "error": "service_unavailable",
-            "message": "Password reset is currently unavailable",
-        })))
+        .respond_with(problem(
+            503,
+            "service_unavailable",
+            "Password reset is currently unavailable",
+        ))
         .mount(&mock_server)
         .await;
 
     let result = client.forgot_password(fixtures::TEST_EMAIL).await;
 
-    // 503 走 from_status 的 500..=599 分支，归为 ServerError 而非 Other。
-    // 客户端 humanize_error 须兼容两种变体。
+    // 503 走 from_status 的 500..=599 分支，归为 ServerError（保留原始 problem 体）。
     match result.unwrap_err() {
-        client_api::ClientError::ServerError(503, msg)
-        | client_api::ClientError::Other(503, msg) => {
-            assert!(msg.contains("unavailable") || msg.contains("service_unavailable"));
+        client_api::ClientError::ServerError(503, body) => {
+            assert!(body.contains(r#""error":"service_unavailable""#));
         }
-        other => panic!(
-            "Expected ServerError(503, ...) or Other(503, ...), got {:?}",
-            other
-        ),
+        other => panic!("Expected ServerError(503, ...), got {:?}", other),
     }
 }
 
@@ -98,18 +96,21 @@ async fn test_forgot_password_validation_error() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/forgot-password"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "validation_error",
-            "message": "email: invalid format",
-        })))
+        .respond_with(field_problem(
+            "email",
+            "email",
+            "must be a valid email address",
+        ))
         .mount(&mock_server)
         .await;
 
-    let result = client.forgot_password("not-an-email").await;
-    assert!(matches!(
-        result.unwrap_err(),
-        client_api::ClientError::Other(400, _)
-    ));
+    let err = client.forgot_password("not-an-email").await.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.error_code(), "validation_error");
+    assert_eq!(
+        problem.field_error("email"),
+        Some("must be a valid email address")
+    );
 }
 
 // ──────────────────────────────────────────────
@@ -138,10 +139,13 @@ async fn test_reset_password_success_issues_fresh_jwt() {
         .mount(&mock_server)
         .await;
 
-    let resp = client
+    let AuthOutcome::Authenticated(resp) = client
         .reset_password(fixtures::TEST_EMAIL, VALID_RESET_CODE, "NewPass456!")
         .await
-        .unwrap();
+        .unwrap()
+    else {
+        panic!("expected a session, got a two-factor challenge");
+    };
 
     assert_eq!(resp.message, "Password reset successfully");
     assert_eq!(resp.token, fixtures::TEST_TOKEN);
@@ -159,10 +163,12 @@ async fn test_reset_password_invalid_code_generic_400() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/reset-password"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "bad_request",
-            "message": "Invalid or expired reset code",
-        })))
+        .respond_with(coded_problem(
+            400,
+            "bad_request",
+            "invalid_code",
+            "Invalid or expired reset code",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -170,12 +176,10 @@ async fn test_reset_password_invalid_code_generic_400() {
         .reset_password(fixtures::TEST_EMAIL, "000000", "NewPass456!")
         .await;
 
-    match result.unwrap_err() {
-        client_api::ClientError::Other(400, msg) => {
-            assert!(msg.contains("Invalid") || msg.contains("expired"));
-        }
-        other => panic!("Expected Other(400, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.status, 400);
+    assert_eq!(problem.error_code(), "invalid_code");
 }
 
 #[tokio::test]
@@ -185,10 +189,11 @@ async fn test_reset_password_weak_password() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/reset-password"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "bad_request",
-            "message": "Password too weak",
-        })))
+        .respond_with(field_problem(
+            "new_password",
+            "weak_password",
+            "Password too weak",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -196,12 +201,13 @@ async fn test_reset_password_weak_password() {
         .reset_password(fixtures::TEST_EMAIL, VALID_RESET_CODE, "weak")
         .await;
 
-    match result.unwrap_err() {
-        client_api::ClientError::Other(400, msg) => {
-            assert!(msg.contains("weak") || msg.contains("validation_error"));
-        }
-        other => panic!("Expected Other(400, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.status, 400);
+    assert_eq!(
+        problem.field_error("new_password"),
+        Some("Password too weak")
+    );
 }
 
 #[tokio::test]
@@ -212,10 +218,12 @@ async fn test_reset_password_brute_force_lockout() {
 
     Mock::given(method("POST"))
         .and(path("/api/public/auth/reset-password"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "bad_request",
-            "message": "Invalid or expired reset code",
-        })))
+        .respond_with(coded_problem(
+            400,
+            "bad_request",
+            "invalid_code",
+            "Invalid or expired reset code",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -223,9 +231,9 @@ async fn test_reset_password_brute_force_lockout() {
         .reset_password(fixtures::TEST_EMAIL, "111111", "NewPass456!")
         .await;
 
-    // 与"invalid code"分支的 status 码完全一致 —— 反 enumeration 一致性检查。
-    assert!(matches!(
-        result.unwrap_err(),
-        client_api::ClientError::Other(400, _)
-    ));
+    // 与"invalid code"分支的响应完全一致 —— 反 enumeration 一致性检查。
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.status, 400);
+    assert_eq!(problem.error_code(), "invalid_code");
 }
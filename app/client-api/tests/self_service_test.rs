@@ -3,12 +3,17 @@
 //! - `change_password`：自改密码后 `new_token` 必须被消费（服务端原子地
 //!   `token_version += 1`，旧 JWT 永久失效 —— 客户端必须用新 token 替换）
 //! - `get_me`：当前登录用户的资料读取（用于会话恢复后填充 name/email）
+//! - 两步验证：enroll → confirm 返回一次性恢复码
+//! - 通行密钥：options → register → list → delete
+//! - 第三方账号：list → delete
+//! - 个人访问令牌：create → list → delete
+//! - 第三方应用授权：details → decision
 
 use wiremock::matchers::{body_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
 mod common;
-use common::{create_test_client, fixtures};
+use common::{coded_problem, create_test_client, field_problem, fixtures, problem};
 
 const ID: &str = "550e8400-e29b-41d4-a716-446655440000";
 const TS: &str = "2024-06-15T10:00:00Z";
@@ -58,21 +63,21 @@ async fn test_change_password_wrong_current_password() {
 
     Mock::given(method("POST"))
         .and(path("/api/users/me/password"))
-        .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
-            "error": "unauthorized",
-            "message": "Current password is incorrect",
-        })))
+        .respond_with(coded_problem(
+            401,
+            "unauthorized",
+            "invalid_current_password",
+            "Current password is incorrect",
+        ))
         .mount(&mock_server)
         .await;
 
     let result = client.change_password("WrongOldPass!", "NewPass456!").await;
 
-    match result.unwrap_err() {
-        client_api::ClientError::Other(401, msg) => {
-            assert!(msg.contains("Current password") || msg.contains("unauthorized"));
-        }
-        other => panic!("Expected Other(401, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.status, 401);
+    assert_eq!(problem.error_code(), "invalid_current_password");
 }
 
 #[tokio::test]
@@ -82,21 +87,23 @@ async fn test_change_password_weak_new_password() {
 
     Mock::given(method("POST"))
         .and(path("/api/users/me/password"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "bad_request",
-            "message": "Password too weak",
-        })))
+        .respond_with(field_problem(
+            "new_password",
+            "weak_password",
+            "Password too weak",
+        ))
         .mount(&mock_server)
         .await;
 
     let result = client.change_password("OldPass123!", "weak").await;
 
-    match result.unwrap_err() {
-        client_api::ClientError::Other(400, msg) => {
-            assert!(msg.contains("weak") || msg.contains("validation_error"));
-        }
-        other => panic!("Expected Other(400, ...), got {:?}", other),
-    }
+    let err = result.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.status, 400);
+    assert_eq!(
+        problem.field_error("new_password"),
+        Some("Password too weak")
+    );
 }
 
 #[tokio::test]
@@ -106,18 +113,21 @@ async fn test_change_password_validation_error() {
 
     Mock::given(method("POST"))
         .and(path("/api/users/me/password"))
-        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
-            "error": "validation_error",
-            "message": "current_password must be at least 1 characters",
-        })))
+        .respond_with(field_problem(
+            "current_password",
+            "length",
+            "current password is required",
+        ))
         .mount(&mock_server)
         .await;
 
-    let result = client.change_password("", "NewPass456!").await;
-    assert!(matches!(
-        result.unwrap_err(),
-        client_api::ClientError::Other(400, _)
-    ));
+    let err = client.change_password("", "NewPass456!").await.unwrap_err();
+    let problem = err.problem().expect("problem details");
+    assert_eq!(problem.error_code(), "validation_error");
+    assert_eq!(
+        problem.field_error("current_password"),
+        Some("current password is required")
+    );
 }
 
 // ──────────────────────────────────────────────
@@ -164,16 +174,293 @@ async fn test_get_me_unauthorized() {
 
     Mock::given(method("GET"))
         .and(path("/api/users/me"))
-        .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
-            "error": "unauthorized",
-            "message": "Invalid or expired token",
+        .respond_with(problem(401, "unauthorized", "Invalid or expired token"))
+        .mount(&mock_server)
+        .await;
+
+    let err = client.get_me().await.unwrap_err();
+    assert_eq!(err.problem().map(|p| p.status), Some(401));
+}
+
+// ──────────────────────────────────────────────
+//  Two-factor
+// ──────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_two_factor_enroll_and_confirm() {
+    let (client, mock_server) = create_test_client().await;
+    client.set_token(fixtures::TEST_TOKEN);
+
+    Mock::given(method("POST"))
+        .and(path("/api/users/me/2fa/enroll"))
+        .and(body_json(serde_json::json!({ "password": "OldPass123!" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "secret": "JBSWY3DPEHPK3PXP",
+            "otpauth_uri": "otpauth://totp/WebShelf:user%40example.com?secret=JBSWY3DPEHPK3PXP&issuer=WebShelf",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/users/me/2fa/confirm"))
+        .and(body_json(serde_json::json!({ "code": "123456" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "recovery_codes": ["abcde-fghjk", "mnpqr-stuvw"],
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let enrollment = client.enroll_two_factor("OldPass123!").await.unwrap();
+    assert_eq!(enrollment.secret, "JBSWY3DPEHPK3PXP");
+    assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/"));
+
+    let codes = client.confirm_two_factor("123456").await.unwrap();
+    assert_eq!(codes.recovery_codes.len(), 2);
+}
+
+#[tokio::test]
+async fn test_two_factor_status() {
+    let (client, mock_server) = create_test_client().await;
+    client.set_token(fixtures::TEST_TOKEN);
+
+    Mock::given(method("GET"))
+        .and(path("/api/users/me/2fa"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "enabled": true,
+            "enabled_at": TS,
+            "pending_enrollment": false,
+            "recovery_codes_remaining": 9,
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let status = client.two_factor_status().await.unwrap();
+    assert!(status.enabled);
+    assert!(status.enabled_at.is_some());
+    assert_eq!(status.recovery_codes_remaining, 9);
+}
+
+// ──────────────────────────────────────────────
+//  Passkeys
+// ──────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_passkey_register_list_and_delete() {
+    let (client, mock_server) = create_test_client().await;
+    client.set_token(fixtures::TEST_TOKEN);
+
+    Mock::given(method("POST"))
+        .and(path("/api/users/me/passkeys/options"))
+        .and(body_json(serde_json::json!({ "password": "OldPass123!" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "rp": { "id": "localhost", "name": "WebShelf" },
+            "user": { "id": "AAAAAAAAAAE", "name": "user@example.com", "displayName": "User" },
+            "challenge": "q2n0aW9u",
+            "pubKeyCredParams": [{ "type": "public-key", "alg": -7 }],
+            "timeout": 300000,
+            "excludeCredentials": [],
+            "authenticatorSelection": { "residentKey": "required", "userVerification": "preferred" },
+            "attestation": "none",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let credential = serde_json::json!({
+        "id": "Y3JlZA",
+        "type": "public-key",
+        "response": { "clientDataJSON": "e30", "attestationObject": "oA", "transports": ["internal"] },
+    });
+    let passkey = serde_json::json!({
+        "id": 7,
+        "name": "Laptop",
+        "transports": ["internal"],
+        "created_at": TS,
+        "last_used_at": null,
+    });
+    Mock::given(method("POST"))
+        .and(path("/api/users/me/passkeys"))
+        .and(body_json(serde_json::json!({
+            "name": "Laptop",
+            "credential": credential.clone(),
         })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(passkey.clone()))
         .mount(&mock_server)
         .await;
 
-    let result = client.get_me().await;
-    assert!(matches!(
-        result.unwrap_err(),
-        client_api::ClientError::Other(401, _)
-    ));
+    Mock::given(method("GET"))
+        .and(path("/api/users/me/passkeys"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "passkeys": [passkey] })),
+        )
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("DELETE"))
+        .and(path("/api/users/me/passkeys/7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "message": "Passkey revoked",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let options = client
+        .passkey_registration_options("OldPass123!")
+        .await
+        .unwrap();
+    assert_eq!(options["authenticatorSelection"]["residentKey"], "required");
+
+    let created = client
+        .register_passkey(Some("Laptop".to_string()), credential)
+        .await
+        .unwrap();
+    assert_eq!(created.id, 7);
+    assert!(created.last_used_at.is_none());
+
+    let list = client.list_passkeys().await.unwrap();
+    assert_eq!(list.passkeys, vec![created]);
+
+    let resp = client.delete_passkey(7).await.unwrap();
+    assert_eq!(resp.message, "Passkey revoked");
+}
+
+#[tokio::test]
+async fn test_identity_list_and_delete() {
+    let (client, mock_server) = create_test_client().await;
+    client.set_token(fixtures::TEST_TOKEN);
+
+    Mock::given(method("GET"))
+        .and(path("/api/users/me/identities"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "identities": [{
+                "id": 3,
+                "provider": "google",
+                "provider_name": "Google",
+                "email": "user@example.com",
+                "last_login_at": TS,
+                "created_at": TS,
+            }],
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("DELETE"))
+        .and(path("/api/users/me/identities/3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "message": "Account unlinked",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let list = client.list_identities().await.unwrap();
+    assert_eq!(list.identities.len(), 1);
+    assert_eq!(list.identities[0].provider_name, "Google");
+    assert_eq!(
+        list.identities[0].email.as_deref(),
+        Some("user@example.com")
+    );
+
+    let resp = client.delete_identity(3).await.unwrap();
+    assert_eq!(resp.message, "Account unlinked");
+}
+
+#[tokio::test]
+async fn test_oauth_authorization_details_and_decision() {
+    let (client, mock_server) = create_test_client().await;
+    client.set_token(fixtures::TEST_TOKEN);
+
+    Mock::given(method("POST"))
+        .and(path("/api/oauth/authorize/details"))
+        .and(body_json(serde_json::json!({ "request": "req-1" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "client_id": "wsc_abc",
+            "client_name": "Reader",
+            "redirect_uri": "https://reader.example.com/callback",
+            "scopes": ["openid", "profile"],
+            "consent_required": true,
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/oauth/authorize/decision"))
+        .and(body_json(
+            serde_json::json!({ "request": "req-1", "approve": true }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "redirect_to": "https://reader.example.com/callback?code=xyz",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let details = client.oauth_authorization_details("req-1").await.unwrap();
+    assert_eq!(details.client_name, "Reader");
+    assert_eq!(details.scopes, vec!["openid", "profile"]);
+    assert!(details.consent_required);
+
+    let resp = client
+        .oauth_authorization_decision("req-1", true)
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.redirect_to,
+        "https://reader.example.com/callback?code=xyz"
+    );
+}
+
+#[tokio::test]
+async fn test_api_token_create_list_and_delete() {
+    let (client, mock_server) = create_test_client().await;
+    client.set_token(fixtures::TEST_TOKEN);
+
+    let info = serde_json::json!({
+        "id": 5,
+        "name": "CI",
+        "prefix": "wst_0123abcd",
+        "scopes": ["users:read"],
+        "expires_at": null,
+        "last_used_at": null,
+        "last_used_ip": null,
+        "created_at": TS,
+    });
+    let mut created = info.clone();
+    created["token"] = serde_json::json!("wst_0123abcd4567");
+
+    Mock::given(method("POST"))
+        .and(path("/api/users/me/tokens"))
+        .and(body_json(serde_json::json!({
+            "name": "CI",
+            "scopes": ["users:read"],
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(created))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/users/me/tokens"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "tokens": [info] })),
+        )
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("DELETE"))
+        .and(path("/api/users/me/tokens/5"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "message": "Token revoked",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let token = client
+        .create_api_token("CI", vec!["users:read".to_string()], None)
+        .await
+        .unwrap();
+    assert_eq!(token.token, "wst_0123abcd4567");
+    assert_eq!(token.info.prefix, "wst_0123abcd");
+
+    let list = client.list_api_tokens().await.unwrap();
+    assert_eq!(list.tokens, vec![token.info]);
+
+    let resp = client.delete_api_token(5).await.unwrap();
+    assert_eq!(resp.message, "Token revoked");
 }
@@ -6,7 +6,7 @@ use wiremock::matchers::{body_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
 mod common;
-use common::{create_test_client, fixtures};
+use common::{coded_problem, create_test_client, fixtures, problem};
 
 const ID1: &str = "1903487293645824000";
 const ID2: &str = "1903487293645824001";
@@ -141,10 +141,12 @@ async fn test_create_user_duplicate_email() {
 
     Mock::given(method("POST"))
         .and(path("/api/users"))
-        .respond_with(ResponseTemplate::new(409).set_body_json(serde_json::json!({
-            "error": "conflict",
-            "message": "Email already registered",
-        })))
+        .respond_with(coded_problem(
+            409,
+            "conflict",
+            "email_taken",
+            "Email already registered",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -152,7 +154,8 @@ async fn test_create_user_duplicate_email() {
         .create_user(fixtures::TEST_EMAIL, "SecurePass123!", "Dup", None)
         .await;
 
-    assert!(result.is_err());
+    let err = result.unwrap_err();
+    assert_eq!(err.problem().map(|p| p.error_code()), Some("email_taken"));
 }
 
 // ──────────────────────────────────────────────
@@ -195,15 +198,12 @@ async fn test_get_user_not_found() {
 
     Mock::given(method("GET"))
         .and(path(format!("/api/users/{}", id)))
-        .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
-            "error": "not_found",
-            "message": "User not found",
-        })))
+        .respond_with(problem(404, "not_found", "User not found"))
         .mount(&mock_server)
         .await;
 
-    let result = client.get_user(id).await;
-    assert!(result.is_err());
+    let err = client.get_user(id).await.unwrap_err();
+    assert_eq!(err.problem().map(|p| p.status), Some(404));
 }
 
 // ──────────────────────────────────────────────
@@ -292,15 +292,12 @@ async fn test_update_user_not_found() {
 
     Mock::given(method("PUT"))
         .and(path(format!("/api/users/{}", id)))
-        .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
-            "error": "not_found",
-            "message": "User not found",
-        })))
+        .respond_with(problem(404, "not_found", "User not found"))
         .mount(&mock_server)
         .await;
 
     let result = client.update_user(id, None, Some("New".into()), None).await;
-    assert!(result.is_err());
+    assert_eq!(result.unwrap_err().problem().map(|p| p.status), Some(404));
 }
 
 // ──────────────────────────────────────────────
@@ -335,13 +332,10 @@ async fn test_delete_user_not_found() {
 
     Mock::given(method("DELETE"))
         .and(path(format!("/api/users/{}", id)))
-        .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
-            "error": "not_found",
-            "message": "User not found",
-        })))
+        .respond_with(problem(404, "not_found", "User not found"))
         .mount(&mock_server)
         .await;
 
-    let result = client.delete_user(id).await;
-    assert!(result.is_err());
+    let err = client.delete_user(id).await.unwrap_err();
+    assert_eq!(err.problem().map(|p| p.status), Some(404));
 }
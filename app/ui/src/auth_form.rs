@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use dioxus::prelude::*;
 
 use crate::button::{Button, ButtonType};
@@ -40,6 +42,9 @@ pub fn AuthForm(
     #[props(default)] remember: Option<Signal<bool>>,
     #[props(default = false)] loading: bool,
     #[props(default)] error: Option<String>,
+    /// 服务端返回的字段级错误（字段名 → 提示），显示在对应输入框下方。
+    #[props(default)]
+    field_errors: BTreeMap<String, String>,
     /// 点击「忘记凭证?」链接时由调用方注入的处理（如导航到密码重置路由）。
     /// 不传则降级为默认行为（链接不可点击）。
     #[props(default)]
@@ -100,6 +105,7 @@ pub fn AuthForm(
                         required: true,
                         disabled: loading,
                         name: Some("name".to_string()),
+                        error: field_errors.get("name").cloned(),
                         autocomplete: Some("username".to_string()),
                     }
                 }
@@ -118,6 +124,7 @@ pub fn AuthForm(
                     required: true,
                     disabled: loading,
                     name: Some("email".to_string()),
+                    error: field_errors.get("email").cloned(),
                     autocomplete: Some("email".to_string()),
                 }
 
@@ -129,6 +136,7 @@ pub fn AuthForm(
                     required: true,
                     disabled: loading,
                     name: Some("password".to_string()),
+                    error: field_errors.get("password").cloned(),
                     autocomplete: Some(
                         if *mode.read() == AuthMode::Login {
                             "current-password".to_string()
@@ -148,6 +156,7 @@ pub fn AuthForm(
                         required: true,
                         disabled: loading,
                         name: Some("password_confirm".to_string()),
+                        error: field_errors.get("password_confirm").cloned(),
                         autocomplete: Some("new-password".to_string()),
                     }
                 }
@@ -164,6 +173,7 @@ pub fn AuthForm(
                             required: true,
                             disabled: loading,
                             name: Some("captcha_code".to_string()),
+                        error: field_errors.get("captcha_code").cloned(),
                             autocomplete: Some("off".to_string()),
                         }
                     }
@@ -5,9 +5,12 @@
 //! - `handle_unauth(err, auth, nav, log_bus)` 检测到 401 时执行 logout + 跳转 `/auth`
 //!   并写入 `LogKind::Important` 日志，与 `TokenExpiryGuard::fire_expiry` 行为对齐。
 //! - `humanize_error(err, ctx, lang)` 将 API 错误翻译为当前语言提示。
+//! - `field_errors(err)` 提取 problem+json 中的字段级错误，供表单逐项高亮。
 
 mod client;
 
+use std::collections::BTreeMap;
+
 pub use client::make_client;
 
 use client_api::ClientError;
@@ -30,6 +33,14 @@ pub enum ErrorContext {
     /// 密码重置页面：与 verify-email 类似，服务端对所有失败分支统一
     /// 400 + 通用文案以防 enumeration / 凭证探测。
     PasswordReset,
+    /// 两步验证（登录第二步与设置页）：按服务端 `code` 区分密码错误 /
+    /// 动态码错误 / 状态冲突；401 为挑战或会话失效。
+    TwoFactor,
+    /// 通行密钥（登录与设置页）：按服务端 `code` 区分挑战过期 / 验证失败 /
+    /// 未注册的密钥；登录页的 401 为 `unknown_passkey`，不是会话失效。
+    Passkey,
+    /// 个人访问令牌（设置页）：按服务端 `code` 区分未知权限范围 / 数量上限。
+    ApiToken,
 }
 
 /// 将 `ClientError` 翻译为当前语言提示，根据 `ctx` 差异化状态码文案。
@@ -43,16 +54,13 @@ pub fn humanize_error(err: &ClientError, ctx: ErrorContext, lang: Language) -> S
             Language::En => format!("Server error (HTTP {status}): {body}"),
             Language::Zh => format!("服务器错误 (HTTP {status}): {body}"),
         },
-        ClientError::Other(status, body) => {
-            let json = serde_json::from_str::<serde_json::Value>(body).ok();
-            let code = json
-                .as_ref()
-                .and_then(|v| v.get("error").and_then(|c| c.as_str().map(String::from)))
+        ClientError::Other(..) | ClientError::Problem(_) => {
+            let (status, code, msg) = response_error_parts(err);
+            // 细分错误码（如 `invalid_two_factor_code`），仅 problem+json 携带
+            let detail_code = err
+                .problem()
+                .and_then(|p| p.code.clone())
                 .unwrap_or_default();
-            let msg = json
-                .as_ref()
-                .and_then(|v| v.get("message").and_then(|m| m.as_str().map(String::from)))
-                .unwrap_or_else(|| body.clone());
             match lang {
                 Language::En => match ctx {
                     ErrorContext::Auth => match (status, code.as_str()) {
@@ -82,6 +90,48 @@ pub fn humanize_error(err: &ClientError, ctx: ErrorContext, lang: Language) -> S
                         (503, _) => "Password reset is currently unavailable".to_string(),
                         _ => format!("Request failed (HTTP {status}): {msg}"),
                     },
+                    ErrorContext::TwoFactor => match (status, detail_code.as_str()) {
+                        (401, _) => "Verification expired, please log in again".to_string(),
+                        (_, "invalid_password") => "Incorrect password".to_string(),
+                        (_, "invalid_two_factor_code") => "Invalid authentication code".to_string(),
+                        (_, "two_factor_enabled") => {
+                            "Two-factor authentication is already enabled".to_string()
+                        }
+                        (_, "two_factor_not_enabled" | "no_pending_enrollment") => {
+                            "Two-factor setup has changed, please reload the page".to_string()
+                        }
+                        _ => format!("Request failed (HTTP {status}): {msg}"),
+                    },
+                    ErrorContext::Passkey => match (status, detail_code.as_str()) {
+                        (_, "invalid_password") => "Incorrect password".to_string(),
+                        (_, "unknown_passkey") => {
+                            "This passkey is not registered or has been revoked".to_string()
+                        }
+                        (_, "invalid_passkey_challenge") => {
+                            "Passkey request expired, please try again".to_string()
+                        }
+                        (_, "passkey_verification_failed") => {
+                            "Passkey verification failed".to_string()
+                        }
+                        (_, "unsupported_passkey_algorithm") => {
+                            "This authenticator is not supported".to_string()
+                        }
+                        (_, "passkey_exists") => "This passkey is already registered".to_string(),
+                        (_, "too_many_passkeys") => {
+                            "Passkey limit reached, revoke one first".to_string()
+                        }
+                        (404, _) => "Passkey not found".to_string(),
+                        _ => format!("Request failed (HTTP {status}): {msg}"),
+                    },
+                    ErrorContext::ApiToken => match (status, detail_code.as_str()) {
+                        (_, "invalid_scope") => "Unknown or missing scope".to_string(),
+                        (_, "too_many_tokens") => {
+                            "Token limit reached, revoke one first".to_string()
+                        }
+                        (404, _) => "Token not found".to_string(),
+                        _ if code == "validation_error" => format!("Validation error: {msg}"),
+                        _ => format!("Request failed (HTTP {status}): {msg}"),
+                    },
                 },
                 Language::Zh => match ctx {
                     ErrorContext::Auth => match (status, code.as_str()) {
@@ -108,6 +158,36 @@ pub fn humanize_error(err: &ClientError, ctx: ErrorContext, lang: Language) -> S
                         (503, _) => "密码重置功能暂不可用".to_string(),
                         _ => format!("请求失败 (HTTP {status}): {msg}"),
                     },
+                    ErrorContext::TwoFactor => match (status, detail_code.as_str()) {
+                        (401, _) => "验证已过期，请重新登录".to_string(),
+                        (_, "invalid_password") => "密码错误".to_string(),
+                        (_, "invalid_two_factor_code") => "动态码错误".to_string(),
+                        (_, "two_factor_enabled") => "两步验证已启用".to_string(),
+                        (_, "two_factor_not_enabled" | "no_pending_enrollment") => {
+                            "两步验证状态已变化，请刷新页面".to_string()
+                        }
+                        _ => format!("请求失败 (HTTP {status}): {msg}"),
+                    },
+                    ErrorContext::Passkey => match (status, detail_code.as_str()) {
+                        (_, "invalid_password") => "密码错误".to_string(),
+                        (_, "unknown_passkey") => "该通行密钥未注册或已被撤销".to_string(),
+                        (_, "invalid_passkey_challenge") => {
+                            "通行密钥请求已过期，请重试".to_string()
+                        }
+                        (_, "passkey_verification_failed") => "通行密钥验证失败".to_string(),
+                        (_, "unsupported_passkey_algorithm") => "不支持该验证器".to_string(),
+                        (_, "passkey_exists") => "该通行密钥已注册".to_string(),
+                        (_, "too_many_passkeys") => "通行密钥数量已达上限，请先撤销".to_string(),
+                        (404, _) => "通行密钥不存在".to_string(),
+                        _ => format!("请求失败 (HTTP {status}): {msg}"),
+                    },
+                    ErrorContext::ApiToken => match (status, detail_code.as_str()) {
+                        (_, "invalid_scope") => "权限范围无效或未选择".to_string(),
+                        (_, "too_many_tokens") => "令牌数量已达上限，请先撤销".to_string(),
+                        (404, _) => "令牌不存在".to_string(),
+                        _ if code == "validation_error" => format!("参数错误: {msg}"),
+                        _ => format!("请求失败 (HTTP {status}): {msg}"),
+                    },
                 },
             }
         }
@@ -130,9 +210,61 @@ pub fn humanize_error(err: &ClientError, ctx: ErrorContext, lang: Language) -> S
     }
 }
 
+/// 拆出 4xx 错误的（状态码、错误类别、说明）。
+///
+/// problem+json 取 `error` / `detail`；旧版 JSON 取 `error` / `message`，
+/// 非 JSON 响应体原样作为说明。
+fn response_error_parts(err: &ClientError) -> (u16, String, String) {
+    if let Some(problem) = err.problem() {
+        return (
+            problem.status,
+            problem.error.clone().unwrap_or_default(),
+            problem.description().to_string(),
+        );
+    }
+    let ClientError::Other(status, body) = err else {
+        return (0, String::new(), err.to_string());
+    };
+    let json = serde_json::from_str::<serde_json::Value>(body).ok();
+    let code = json
+        .as_ref()
+        .and_then(|v| v.get("error").and_then(|c| c.as_str().map(String::from)))
+        .unwrap_or_default();
+    let msg = json
+        .as_ref()
+        .and_then(|v| v.get("message").and_then(|m| m.as_str().map(String::from)))
+        .unwrap_or_else(|| body.clone());
+    (*status, code, msg)
+}
+
+/// 提取字段级错误（字段名 → 提示），非 problem+json 错误返回空表。
+pub fn field_errors(err: &ClientError) -> BTreeMap<String, String> {
+    let Some(problem) = err.problem() else {
+        return BTreeMap::new();
+    };
+    problem
+        .errors
+        .keys()
+        .filter_map(|field| {
+            problem
+                .field_error(field)
+                .map(|msg| (field.clone(), msg.to_string()))
+        })
+        .collect()
+}
+
+/// 4xx 响应的状态码（`Other` 或 `Problem`）。
+fn client_error_status(err: &ClientError) -> Option<u16> {
+    match err {
+        ClientError::Other(status, _) => Some(*status),
+        ClientError::Problem(problem) => Some(problem.status),
+        _ => None,
+    }
+}
+
 /// 判定一个 `ClientError` 是否代表 token 失效（HTTP 401）。
 pub fn is_unauth(err: &ClientError) -> bool {
-    matches!(err, ClientError::Other(401, _))
+    client_error_status(err) == Some(401)
 }
 
 /// 若 `err` 为 401 或 403，执行相应导航并返回 `true`：
@@ -166,7 +298,7 @@ pub async fn handle_unauth(
         auth.logout_async().await;
         nav.replace(Route::LoginLanding {});
         true
-    } else if matches!(err, ClientError::Other(403, _)) {
+    } else if client_error_status(err) == Some(403) {
         log_bus.push(
             HttpMethod::Post,
             "/auth/forbidden (JWT admin scope mismatch)".to_string(),
@@ -445,4 +577,112 @@ mod tests {
         let msg = humanize_error(&err, ErrorContext::PasswordReset, Language::En);
         assert_eq!(msg, "Password reset is currently unavailable");
     }
+
+    // ── humanize_error: TwoFactor context ────────────────
+
+    #[test]
+    fn humanize_two_factor_code_by_detail_code() {
+        let err = ClientError::from_status(
+            400,
+            r#"{"status":400,"error":"bad_request","code":"invalid_two_factor_code"}"#.into(),
+        );
+        let msg = humanize_error(&err, ErrorContext::TwoFactor, Language::Zh);
+        assert_eq!(msg, "动态码错误");
+        let err = ClientError::from_status(
+            400,
+            r#"{"status":400,"error":"bad_request","code":"invalid_password"}"#.into(),
+        );
+        let msg = humanize_error(&err, ErrorContext::TwoFactor, Language::En);
+        assert_eq!(msg, "Incorrect password");
+    }
+
+    #[test]
+    fn humanize_two_factor_401_en() {
+        let err = ClientError::from_status(
+            401,
+            r#"{"status":401,"error":"unauthorized","code":"invalid_challenge"}"#.into(),
+        );
+        let msg = humanize_error(&err, ErrorContext::TwoFactor, Language::En);
+        assert_eq!(msg, "Verification expired, please log in again");
+    }
+
+    #[test]
+    fn humanize_passkey_by_detail_code() {
+        let err = ClientError::from_status(
+            401,
+            r#"{"status":401,"error":"unauthorized","code":"unknown_passkey"}"#.into(),
+        );
+        let msg = humanize_error(&err, ErrorContext::Passkey, Language::Zh);
+        assert_eq!(msg, "该通行密钥未注册或已被撤销");
+        let err = ClientError::from_status(
+            409,
+            r#"{"status":409,"error":"conflict","code":"passkey_exists"}"#.into(),
+        );
+        let msg = humanize_error(&err, ErrorContext::Passkey, Language::En);
+        assert_eq!(msg, "This passkey is already registered");
+    }
+
+    #[test]
+    fn humanize_api_token_by_detail_code() {
+        let err = ClientError::from_status(
+            400,
+            r#"{"status":400,"error":"bad_request","code":"invalid_scope"}"#.into(),
+        );
+        let msg = humanize_error(&err, ErrorContext::ApiToken, Language::En);
+        assert_eq!(msg, "Unknown or missing scope");
+        let err = ClientError::from_status(
+            409,
+            r#"{"status":409,"error":"conflict","code":"too_many_tokens"}"#.into(),
+        );
+        let msg = humanize_error(&err, ErrorContext::ApiToken, Language::Zh);
+        assert_eq!(msg, "令牌数量已达上限，请先撤销");
+    }
+
+    // ── problem+json ─────────────────────────────────────
+
+    const REGISTER_PROBLEM: &str = r#"{
+        "type": "about:blank", "title": "Bad Request", "status": 400,
+        "detail": "email: must be a valid email address",
+        "error": "validation_error",
+        "errors": {
+            "email": [{ "code": "email", "message": "must be a valid email address" }],
+            "password_confirm": [{ "code": "mismatch", "message": "passwords do not match" }]
+        }
+    }"#;
+
+    #[test]
+    fn humanize_problem_validation_en() {
+        let err = ClientError::from_status(400, REGISTER_PROBLEM.into());
+        let msg = humanize_error(&err, ErrorContext::Auth, Language::En);
+        assert_eq!(
+            msg,
+            "Validation error: email: must be a valid email address"
+        );
+    }
+
+    #[test]
+    fn humanize_problem_401_zh() {
+        let err = ClientError::from_status(
+            401,
+            r#"{"status":401,"error":"unauthorized","code":"invalid_credentials"}"#.into(),
+        );
+        assert!(is_unauth(&err));
+        let msg = humanize_error(&err, ErrorContext::Auth, Language::Zh);
+        assert_eq!(msg, "邮箱或密码错误");
+    }
+
+    #[test]
+    fn field_errors_from_problem() {
+        let err = ClientError::from_status(400, REGISTER_PROBLEM.into());
+        let fields = field_errors(&err);
+        assert_eq!(fields["email"], "must be a valid email address");
+        assert_eq!(fields["password_confirm"], "passwords do not match");
+        assert_eq!(fields.len(), 2);
+    }
+
+    #[test]
+    fn field_errors_empty_for_legacy_body() {
+        let err = ClientError::Other(400, r#"{"error":"validation_error"}"#.into());
+        assert!(field_errors(&err).is_empty());
+    }
 }
@@ -12,8 +12,10 @@ mod state;
 mod storage;
 
 pub use jwt::{JwtPayload, decode_payload};
-#[allow(unused_imports)] // PendingRegistration is part of the public API surface
-// (referenced by `AuthState::pending_registration` field type),
-// even if no view consumes it directly.
-pub use state::{AuthState, CurrentUser, PendingRegistration, RegisterOutcome};
+#[allow(unused_imports)] // PendingRegistration / PendingTwoFactor are part of the public API
+// surface (referenced by the `AuthState::pending_*` field types),
+// even if no view consumes them directly.
+pub use state::{
+    AuthState, CurrentUser, LoginOutcome, PendingRegistration, PendingTwoFactor, RegisterOutcome,
+};
 pub use storage::{clear_jwt, clear_token, load_jwt, load_token, save_jwt, save_token};
@@ -11,7 +11,10 @@
 //! Client 的 `auth_token` 仍保留用于 Authorization 头（非浏览器场景兼容），
 //! 但浏览器请求主要通过 httpOnly cookie 认证。
 
-use client_api::{Client, ClientConfig, ClientError, LoginResponse, RegisterResponse};
+use client_api::{
+    AuthOutcome, Client, ClientConfig, ClientError, LoginResponse, RegisterResponse,
+    TwoFactorChallenge,
+};
 use dioxus::prelude::*;
 
 use crate::api::make_client;
@@ -28,6 +31,21 @@ pub enum RegisterOutcome {
     NeedsVerification { email: String },
 }
 
+/// 登录流程的结果。
+#[derive(Debug, Clone)]
+pub enum LoginOutcome {
+    /// 会话已建立。
+    LoggedIn,
+    /// 账号启用了两步验证：挑战已存入 `pending_two_factor`，等待动态码。
+    TwoFactorRequired,
+}
+
+/// 两步验证登录中、尚未提交动态码的挑战 —— 仅内存，不写 cookie。
+#[derive(Debug, Clone)]
+pub struct PendingTwoFactor {
+    pub challenge_token: String,
+}
+
 /// 注册期间的临时会话状态 —— 仅内存，不写 cookie。
 #[derive(Debug, Clone)]
 pub struct PendingRegistration {
@@ -38,7 +56,8 @@ pub struct PendingRegistration {
 
 /// 判定一个 `ClientError` 是否为鉴权失败（401/403）。
 fn is_auth_failure(err: &ClientError) -> bool {
-    matches!(err, ClientError::Other(401, _) | ClientError::Other(403, _))
+    matches!(err, ClientError::Other(401 | 403, _))
+        || err.problem().is_some_and(|p| matches!(p.status, 401 | 403))
 }
 
 #[cfg(test)]
@@ -65,6 +84,12 @@ mod tests {
         assert!(is_auth_failure(&ClientError::Other(403, "x".into())));
     }
 
+    #[test]
+    fn auth_failure_problem_401() {
+        let err = ClientError::from_status(401, r#"{"status":401,"error":"unauthorized"}"#.into());
+        assert!(is_auth_failure(&err));
+    }
+
     #[test]
     fn auth_failure_400_is_not() {
         assert!(!is_auth_failure(&ClientError::Other(400, "x".into())));
@@ -159,6 +184,8 @@ pub struct AuthState {
     pub token_expires_at: Signal<Option<u64>>,
     pub initialized: Signal<bool>,
     pub pending_registration: Signal<Option<PendingRegistration>>,
+    /// 密码步骤已通过、等待两步验证动态码的登录。
+    pub pending_two_factor: Signal<Option<PendingTwoFactor>>,
     /// 静默刷新进行中标志——防止并发 refresh 请求。
     refreshing: Signal<bool>,
 }
@@ -175,6 +202,7 @@ impl AuthState {
             token_expires_at: Signal::new(None),
             initialized: Signal::new(false),
             pending_registration: Signal::new(None),
+            pending_two_factor: Signal::new(None),
             refreshing: Signal::new(false),
         }
     }
@@ -354,17 +382,95 @@ impl AuthState {
     ///
     /// `captcha_code` 是可选的微信验证码 —— 当后端启用微信验证码登录时，
     /// 需传入用户在微信公众号获取的验证码才能完成登录。
+    ///
+    /// 启用两步验证的账号返回 `TwoFactorRequired`，随后由
+    /// [`AuthState::complete_two_factor`] 提交动态码完成登录。
     pub async fn login(
         &mut self,
         email: &str,
         password: &str,
         remember: bool,
         captcha_code: Option<String>,
-    ) -> Result<LoginResponse, ClientError> {
-        let resp = self
+    ) -> Result<LoginOutcome, ClientError> {
+        match self
             .client
             .login(email, password, remember, captcha_code)
-            .await?;
+            .await?
+        {
+            AuthOutcome::Authenticated(resp) => {
+                self.apply_login(&resp).await?;
+                Ok(LoginOutcome::LoggedIn)
+            }
+            AuthOutcome::TwoFactorRequired(challenge) => {
+                self.begin_two_factor(challenge);
+                Ok(LoginOutcome::TwoFactorRequired)
+            }
+        }
+    }
+
+    /// 通行密钥登录：提交浏览器断言（`credential.toJSON()`）。
+    ///
+    /// 验证器未做用户验证且账号启用了两步验证时，与密码登录一样进入第二步。
+    pub async fn login_with_passkey(
+        &mut self,
+        credential: serde_json::Value,
+        remember: bool,
+    ) -> Result<LoginOutcome, ClientError> {
+        match self.client.passkey_login(credential, remember).await? {
+            AuthOutcome::Authenticated(resp) => {
+                self.apply_login(&resp).await?;
+                Ok(LoginOutcome::LoggedIn)
+            }
+            AuthOutcome::TwoFactorRequired(challenge) => {
+                self.begin_two_factor(challenge);
+                Ok(LoginOutcome::TwoFactorRequired)
+            }
+        }
+    }
+
+    /// 记录待完成的两步验证挑战（登录、重置密码返回 `202` 时）。
+    pub fn begin_two_factor(&mut self, challenge: TwoFactorChallenge) {
+        self.pending_two_factor.set(Some(PendingTwoFactor {
+            challenge_token: challenge.challenge_token,
+        }));
+    }
+
+    /// 放弃待完成的两步验证，回到登录表单。
+    pub fn cancel_two_factor(&mut self) {
+        self.pending_two_factor.set(None);
+    }
+
+    /// 提交两步验证动态码（或恢复码）完成登录。
+    ///
+    /// 挑战失效（401：过期、已使用或错误次数过多）时一并清除 pending，
+    /// 用户需重新输入密码。
+    pub async fn complete_two_factor(&mut self, code: &str) -> Result<(), ClientError> {
+        let Some(pending) = self.pending_two_factor.read().clone() else {
+            return Err(ClientError::Other(
+                401,
+                "Two-factor challenge expired".to_string(),
+            ));
+        };
+        match self
+            .client
+            .login_two_factor(pending.challenge_token, code)
+            .await
+        {
+            Ok(resp) => {
+                self.pending_two_factor.set(None);
+                self.apply_login(&resp).await
+            }
+            Err(err) => {
+                if is_auth_failure(&err) {
+                    self.pending_two_factor.set(None);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// 应用登录响应：设置 token、持久化过期时间并拉取用户资料。
+    async fn apply_login(&mut self, resp: &LoginResponse) -> Result<(), ClientError> {
         let expires_at = now_unix_secs() + resp.expires_in;
         self.client.set_token(&resp.token);
         self.token_expires_at.set(Some(expires_at));
@@ -402,7 +508,7 @@ impl AuthState {
                 }
             }
         }
-        Ok(resp)
+        Ok(())
     }
 
     /// 注册。
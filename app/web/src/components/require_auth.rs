@@ -7,6 +7,9 @@
 //! 1. **渲染时检查**：未登录用户不渲染 `Outlet`，杜绝首次渲染的闪烁。
 //! 2. **effect 重定向**：`auth.user` 变化时触发 `nav.replace()`，确保路由 URL 同步。
 //!
+//! 登录前从授权同意页过来的用户（见 `views::oauth_consent`），登录后在此被送回
+//! 该页继续授权。
+//!
 //! 注意：必须同时检查 `initialized` 和 `authenticated`，避免 AuthState 尚未从
 //! cookie 恢复会话时（`restore_from_storage_async` 进行中）误判为未登录，
 //! 导致「记住登录」用户首屏被踢到登录页再跳回的闪烁问题。
@@ -15,6 +18,7 @@ use dioxus::prelude::*;
 
 use crate::Route;
 use crate::auth::AuthState;
+use crate::oauth;
 
 #[component]
 pub fn RequireAuth() -> Element {
@@ -29,6 +33,10 @@ pub fn RequireAuth() -> Element {
         // 仅在初始化完成且未登录时才重定向，防止「记住登录」用户首屏被误踢
         if *auth.initialized.read() && !auth.is_authenticated() {
             let _ = nav.replace(Route::LoginLanding {});
+        } else if *auth.initialized.read()
+            && let Some(request) = oauth::take_pending_request()
+        {
+            let _ = nav.replace(Route::OAuthConsent { request });
         }
     });
 
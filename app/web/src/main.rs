@@ -5,14 +5,17 @@ use components::{AppShellLayout, LogBus, RequireAuth};
 use i18n::Language;
 use ui::I18nContext;
 use views::{
-    Auth, Dashboard, ForgotPassword, LoginLanding, NotFound, ResetPassword, Settings, Users,
-    VerifyEmail,
+    Auth, Dashboard, ForgotPassword, LoginLanding, NotFound, OAuthConsent, ResetPassword, Settings,
+    Users, VerifyEmail,
 };
 
 mod api;
 mod auth;
 mod balance;
 mod components;
+mod oauth;
+mod oidc;
+mod passkey;
 mod views;
 #[derive(Debug, Clone, Routable, PartialEq)]
 #[rustfmt::skip]
@@ -29,6 +32,9 @@ enum Route {
     ResetPassword { email: Option<String> },
     #[route("/verify-email/:email")]
     VerifyEmail { email: String },
+    // 第三方应用授权同意页：自行处理未登录（暂存请求后转到登录页）
+    #[route("/oauth/consent?:request")]
+    OAuthConsent { request: String },
 
     // ── 受保护路由（需登录）──
     #[layout(RequireAuth)]
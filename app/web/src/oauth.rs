@@ -0,0 +1,87 @@
+//! 第三方应用授权（本站作为 OAuth 2.0 授权服务器）。
+//!
+//! 服务端 `/api/public/oauth/authorize` 校验请求后把浏览器送到
+//! `/oauth/consent?request=<id>`。未登录时先把 `request` 暂存到 sessionStorage，
+//! 登录完成后由登录页取回并回到授权同意页。
+
+use i18n::Translations;
+
+/// sessionStorage 中待继续的授权请求键名。
+#[allow(dead_code)]
+const PENDING_REQUEST_KEY: &str = "webshelf_oauth_request";
+
+/// 暂存授权请求，登录后继续。
+pub fn save_pending_request(request: &str) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        if let Some(window) = web_sys::window() {
+            if let Ok(Some(storage)) = window.session_storage() {
+                let _ = storage.set_item(PENDING_REQUEST_KEY, request);
+            }
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = request;
+    }
+}
+
+/// 取出并清除暂存的授权请求。
+pub fn take_pending_request() -> Option<String> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let storage = web_sys::window()?.session_storage().ok()??;
+        let request = storage.get_item(PENDING_REQUEST_KEY).ok()??;
+        let _ = storage.remove_item(PENDING_REQUEST_KEY);
+        Some(request).filter(|r| !r.is_empty())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        None
+    }
+}
+
+/// 权限范围对应的说明文案；未知范围原样显示。
+pub fn scope_description<'a>(scope: &'a str, t: &Translations) -> &'a str {
+    match scope {
+        "openid" => t.oauth_scope_openid,
+        "profile" => t.oauth_scope_profile,
+        "email" => t.oauth_scope_email,
+        "offline_access" => t.oauth_scope_offline_access,
+        "users:read" => t.oauth_scope_users_read,
+        "users:write" => t.oauth_scope_users_write,
+        other => other,
+    }
+}
+
+/// 回调地址的主机部分（含端口），用于提示用户授权后跳转到哪里。
+pub fn redirect_host(uri: &str) -> &str {
+    let rest = uri.split_once("://").map_or(uri, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    // 去掉 userinfo（`user@host`）
+    authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirect_host_strips_scheme_path_and_userinfo() {
+        assert_eq!(
+            redirect_host("https://reader.example.com/callback?x=1"),
+            "reader.example.com"
+        );
+        assert_eq!(redirect_host("http://127.0.0.1:9000/cb"), "127.0.0.1:9000");
+        assert_eq!(redirect_host("https://a@evil.example/cb"), "evil.example");
+    }
+
+    #[test]
+    fn scope_description_falls_back_to_scope() {
+        let t = &i18n::EN;
+        assert_eq!(scope_description("email", t), t.oauth_scope_email);
+        assert_eq!(scope_description("custom:scope", t), "custom:scope");
+    }
+}
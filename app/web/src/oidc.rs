@@ -0,0 +1,118 @@
+//! 第三方登录（OpenID Connect）回跳处理。
+//!
+//! 登录按钮是指向服务端 `/api/public/auth/oidc/{provider}/authorize` 的整页跳转；
+//! 登录成功时服务端写入会话 cookie 并跳到 `/dashboard`，由会话恢复接手。失败或需要
+//! 两步验证时回到登录页，结果放在 URL 片段里：`#oidc_error=<code>` 或
+//! `#two_factor=<challenge>`。本模块读取并清除该片段。
+
+use dioxus::prelude::*;
+use i18n::Translations;
+
+/// 登录页 URL 片段携带的第三方登录结果。
+#[derive(Debug, Clone, PartialEq)]
+pub enum OidcRedirect {
+    /// 登录失败，附带服务端错误 `code`（如 `oidc_email_in_use`）
+    Error(String),
+    /// 账号启用了两步验证，附带挑战令牌
+    TwoFactor(String),
+}
+
+/// 解析 `location.hash`（可带或不带前导 `#`），无法识别时返回 `None`。
+pub fn parse_fragment(hash: &str) -> Option<OidcRedirect> {
+    let (key, value) = hash.strip_prefix('#').unwrap_or(hash).split_once('=')?;
+    let value = percent_decode(value)?;
+    if value.is_empty() {
+        return None;
+    }
+    match key {
+        "oidc_error" => Some(OidcRedirect::Error(value)),
+        "two_factor" => Some(OidcRedirect::TwoFactor(value)),
+        _ => None,
+    }
+}
+
+/// 服务端用 `application/x-www-form-urlencoded` 编码片段值
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value.get(i + 1..i + 3)?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// 错误 `code` 对应的提示文案；未知代码统一为通用提示。
+pub fn error_message(code: &str, t: &Translations) -> &'static str {
+    match code {
+        "oidc_denied" => t.oidc_error_denied,
+        "oidc_email_not_verified" => t.oidc_error_email_not_verified,
+        "oidc_email_in_use" => t.oidc_error_email_in_use,
+        "oidc_signup_disabled" => t.oidc_error_signup_disabled,
+        _ => t.oidc_error_generic,
+    }
+}
+
+// 读取后用 replaceState 去掉片段，刷新页面不会重复处理
+const TAKE_FRAGMENT_JS: &str = r#"
+const hash = window.location.hash || "";
+if (hash) {
+  history.replaceState(null, "", window.location.pathname + window.location.search);
+}
+return hash;
+"#;
+
+/// 读取并清除当前页面的 URL 片段。
+pub async fn take_redirect() -> Option<OidcRedirect> {
+    let hash: String = document::eval(TAKE_FRAGMENT_JS).join().await.ok()?;
+    parse_fragment(&hash)
+}
+
+/// 整页跳转到授权地址（离开单页应用，由服务端重定向到提供方）。
+pub fn navigate(url: String) {
+    let eval = document::eval("window.location.assign(await dioxus.recv());");
+    let _ = eval.send(url);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fragment_recognizes_error_and_challenge() {
+        assert_eq!(
+            parse_fragment("#oidc_error=oidc_email_in_use"),
+            Some(OidcRedirect::Error("oidc_email_in_use".into()))
+        );
+        assert_eq!(
+            parse_fragment("two_factor=abc.def%2Dghi"),
+            Some(OidcRedirect::TwoFactor("abc.def-ghi".into()))
+        );
+        assert_eq!(parse_fragment(""), None);
+        assert_eq!(parse_fragment("#section"), None);
+        assert_eq!(parse_fragment("#two_factor="), None);
+        assert_eq!(parse_fragment("#other=value"), None);
+        assert_eq!(parse_fragment("#oidc_error=%ZZ"), None);
+    }
+
+    #[test]
+    fn error_message_falls_back_to_generic() {
+        let t = &i18n::EN;
+        assert_eq!(error_message("oidc_denied", t), t.oidc_error_denied);
+        assert_eq!(error_message("invalid_oidc_state", t), t.oidc_error_generic);
+    }
+}
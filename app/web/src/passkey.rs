@@ -0,0 +1,114 @@
+//! 浏览器 WebAuthn 调用 —— 通过 `document::eval` 调用 `navigator.credentials`。
+//!
+//! 服务端返回的选项是 `PublicKeyCredential*OptionsJSON`，在浏览器中用
+//! `PublicKeyCredential.parse*OptionsFromJSON` 还原，结果用 `credential.toJSON()`
+//! 序列化后原样交回服务端，Rust 侧不解析凭据内容。
+
+use dioxus::prelude::*;
+use serde::Deserialize;
+
+/// 浏览器侧失败原因（服务端错误走 `ClientError`）。
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasskeyPromptError {
+    /// 浏览器不支持 WebAuthn JSON 序列化（或非安全上下文）
+    Unsupported,
+    /// 用户取消、超时，或没有可用的通行密钥（`NotAllowedError`）
+    Cancelled,
+    /// 其他异常（如 `InvalidStateError`），附带浏览器给出的说明
+    Failed(String),
+}
+
+#[derive(Deserialize)]
+struct PromptResult {
+    credential: Option<serde_json::Value>,
+    error: Option<String>,
+    message: Option<String>,
+}
+
+// 选项通过 dioxus.recv() 传入，避免把服务端数据拼接进脚本
+const PROMPT_JS: &str = r#"
+const [mode, options] = await dioxus.recv();
+const PKC = window.PublicKeyCredential;
+if (!PKC || !PKC.parseCreationOptionsFromJSON || !PKC.parseRequestOptionsFromJSON) {
+  return { error: "unsupported" };
+}
+try {
+  const credential = mode === "create"
+    ? await navigator.credentials.create({ publicKey: PKC.parseCreationOptionsFromJSON(options) })
+    : await navigator.credentials.get({ publicKey: PKC.parseRequestOptionsFromJSON(options) });
+  if (!credential) {
+    return { error: "NotAllowedError" };
+  }
+  return { credential: credential.toJSON() };
+} catch (e) {
+  return { error: e.name || "Error", message: String(e.message || e) };
+}
+"#;
+
+async fn prompt(
+    mode: &str,
+    options: serde_json::Value,
+) -> Result<serde_json::Value, PasskeyPromptError> {
+    let eval = document::eval(PROMPT_JS);
+    eval.send((mode, options))
+        .map_err(|e| PasskeyPromptError::Failed(e.to_string()))?;
+    let result: PromptResult = eval
+        .join()
+        .await
+        .map_err(|e| PasskeyPromptError::Failed(e.to_string()))?;
+    match (result.credential, result.error) {
+        (Some(credential), None) => Ok(credential),
+        (_, error) => Err(prompt_error(error.as_deref(), result.message)),
+    }
+}
+
+fn prompt_error(name: Option<&str>, message: Option<String>) -> PasskeyPromptError {
+    match name {
+        Some("unsupported" | "NotSupportedError" | "SecurityError") => {
+            PasskeyPromptError::Unsupported
+        }
+        Some("NotAllowedError" | "AbortError") => PasskeyPromptError::Cancelled,
+        other => PasskeyPromptError::Failed(
+            message.unwrap_or_else(|| other.unwrap_or("Error").to_string()),
+        ),
+    }
+}
+
+/// 注册：`navigator.credentials.create()`，返回新凭据的 `toJSON()`。
+pub async fn create_credential(
+    options: serde_json::Value,
+) -> Result<serde_json::Value, PasskeyPromptError> {
+    prompt("create", options).await
+}
+
+/// 登录：`navigator.credentials.get()`，返回断言的 `toJSON()`。
+pub async fn get_credential(
+    options: serde_json::Value,
+) -> Result<serde_json::Value, PasskeyPromptError> {
+    prompt("get", options).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_error_maps_dom_exception_names() {
+        assert_eq!(
+            prompt_error(Some("unsupported"), None),
+            PasskeyPromptError::Unsupported
+        );
+        assert_eq!(
+            prompt_error(Some("NotAllowedError"), Some("timed out".into())),
+            PasskeyPromptError::Cancelled
+        );
+        assert_eq!(
+            prompt_error(Some("InvalidStateError"), Some("already registered".into())),
+            PasskeyPromptError::Failed("already registered".into())
+        );
+        assert_eq!(
+            prompt_error(None, None),
+            PasskeyPromptError::Failed("Error".into())
+        );
+    }
+}
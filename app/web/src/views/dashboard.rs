@@ -381,6 +381,10 @@ fn error_summary(err: &ClientError, t: &Translations) -> (String, String) {
         ClientError::Other(s, _) | ClientError::ServerError(s, _) => {
             (s.to_string(), format!("HTTP {s}"))
         }
+        ClientError::Problem(problem) => {
+            let s = problem.status;
+            (s.to_string(), format!("HTTP {s}"))
+        }
         ClientError::Network(_) => ("NET".to_string(), t.dashboard_error_network.to_string()),
         ClientError::RateLimited(_) => (
             "429".to_string(),
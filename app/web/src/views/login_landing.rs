@@ -1,15 +1,25 @@
 //! LoginLanding 视图 —— `/` 根路由。
 //!
 //! 左侧为登录/注册表单（复用 AuthForm），右侧展示公众号二维码、版权声明与 GitHub 项目地址。
-//! 已登录用户自动跳转到 `/dashboard`。
+//! 已登录用户自动跳转到 `/dashboard`；密码通过但需要两步验证时，表单换成动态码输入。
+//! 登录模式下表单下方提供「使用通行密钥登录」与已启用的第三方登录（OpenID Connect）；
+//! 第三方登录失败或需要两步验证时，服务端带着 URL 片段跳回本页。
 
+use std::collections::BTreeMap;
+
+use client_api::OidcProvider;
 use dioxus::prelude::*;
-use ui::{AuthForm, AuthMode, AuthPayload, I18nContext, LanguageSwitcher, LanguageSwitcherVariant};
+use ui::{
+    AuthForm, AuthMode, AuthPayload, Button, ButtonType, I18nContext, LanguageSwitcher,
+    LanguageSwitcherVariant, TextInput, tf,
+};
 
 use crate::Route;
-use crate::api::{ErrorContext, humanize_error};
-use crate::auth::{AuthState, RegisterOutcome};
+use crate::api::{ErrorContext, field_errors, humanize_error};
+use crate::auth::{AuthState, PendingTwoFactor, RegisterOutcome};
 use crate::components::{HttpMethod, LogBus, push_log_result};
+use crate::oidc::{self, OidcRedirect};
+use crate::passkey::{PasskeyPromptError, get_credential};
 
 const QRCODE_IMG: Asset = asset!("/assets/qrcode-op.jpg");
 
@@ -70,6 +80,7 @@ pub fn LoginLanding() -> Element {
     let remember = use_signal(|| false);
     let mut loading = use_signal(|| false);
     let mut error_msg = use_signal(|| Option::<String>::None);
+    let mut field_error_map = use_signal(BTreeMap::<String, String>::new);
     let mut wechat_enabled = use_signal(|| false);
 
     // 检查服务端是否启用了 WeChat 验证码登录功能
@@ -85,6 +96,26 @@ pub fn LoginLanding() -> Element {
         });
     }
 
+    // 第三方登录回跳：失败原因或两步验证挑战在 URL 片段里
+    {
+        let auth = auth.clone();
+        use_effect(move || {
+            let mut auth = auth.clone();
+            spawn(async move {
+                match oidc::take_redirect().await {
+                    Some(OidcRedirect::Error(code)) => {
+                        error_msg.set(Some(oidc::error_message(&code, i18n.t()).to_string()));
+                    }
+                    Some(OidcRedirect::TwoFactor(challenge_token)) => {
+                        auth.pending_two_factor
+                            .set(Some(PendingTwoFactor { challenge_token }));
+                    }
+                    None => {}
+                }
+            });
+        });
+    }
+
     // 每次切换登录/注册/验证码模式时清空表单与状态
     use_effect(move || {
         let _ = mode();
@@ -94,9 +125,12 @@ pub fn LoginLanding() -> Element {
         password_confirm.set(String::new());
         captcha_code.set(String::new());
         error_msg.set(None);
+        field_error_map.set(BTreeMap::new());
         loading.set(false);
     });
 
+    let two_factor_pending = auth.pending_two_factor.read().is_some();
+
     rsx! {
         document::Link {
             rel: "stylesheet",
@@ -109,133 +143,144 @@ pub fn LoginLanding() -> Element {
                     h1 { class: "ws-landing__brand-title", "WebShelf" }
                     p { class: "ws-landing__brand-subtitle", {t.login_brand_subtitle} }
                 }
-                AuthForm {
-                    mode,
-                    name,
-                    email,
-                    password,
-                    password_confirm,
-                    captcha_code: Some(captcha_code),
-                    remember: Some(remember),
-                    loading: *loading.read(),
-                    error: error_msg.read().clone(),
-                    show_captcha_input: *wechat_enabled.read(),
-                    on_forgot: move |_: MouseEvent| {
-                        nav.push(Route::ForgotPassword {});
-                    },
-                    on_submit: move |payload: AuthPayload| {
-                        if *loading.read() {
-                            return;
-                        }
-                        // 前端表单校验
-                        if payload.mode == AuthMode::Login && *wechat_enabled.read()
-                            && payload.captcha_code.trim().is_empty()
-                        {
-                            error_msg.set(Some(t.login_captcha_empty.to_string()));
-                            return;
-                        }
-                        if payload.email.trim().is_empty() {
-                            error_msg.set(Some(t.login_email_empty.to_string()));
-                            return;
-                        }
-                        if payload.password.is_empty() {
-                            error_msg.set(Some(t.login_password_empty.to_string()));
-                            return;
-                        }
-                        if payload.mode == AuthMode::Register {
-                            let name_trimmed = payload.name.trim();
-                            if name_trimmed.is_empty() {
-                                error_msg.set(Some(t.login_name_empty.to_string()));
+                if two_factor_pending {
+                    TwoFactorPrompt {}
+                } else {
+                    AuthForm {
+                        mode,
+                        name,
+                        email,
+                        password,
+                        password_confirm,
+                        captcha_code: Some(captcha_code),
+                        remember: Some(remember),
+                        loading: *loading.read(),
+                        error: error_msg.read().clone(),
+                        field_errors: field_error_map.read().clone(),
+                        show_captcha_input: *wechat_enabled.read(),
+                        on_forgot: move |_: MouseEvent| {
+                            nav.push(Route::ForgotPassword {});
+                        },
+                        on_submit: move |payload: AuthPayload| {
+                            if *loading.read() {
                                 return;
                             }
-                            if name_trimmed.len() < 6 || name_trimmed.len() > 50 {
-                                error_msg.set(Some(t.login_name_length.to_string()));
+                            // 前端表单校验
+                            if payload.mode == AuthMode::Login && *wechat_enabled.read()
+                                && payload.captcha_code.trim().is_empty()
+                            {
+                                error_msg.set(Some(t.login_captcha_empty.to_string()));
                                 return;
                             }
-                            if payload.password != payload.password_confirm {
-                                error_msg.set(Some(t.login_password_mismatch.to_string()));
+                            if payload.email.trim().is_empty() {
+                                error_msg.set(Some(t.login_email_empty.to_string()));
                                 return;
                             }
-                        }
-                        let payload_email = payload.email.clone();
-                        let payload_password = payload.password.clone();
-                        let payload_name = payload.name.clone();
-                        let payload_captcha_code = payload.captcha_code.clone();
-                        let payload_mode = payload.mode;
-                        let payload_remember = payload.remember;
-
-                        let mut auth_async = auth.clone();
-                        let bus_async = log_bus;
-                        let nav_async = nav;
-
-                        loading.set(true);
-                        error_msg.set(None);
-
-                        let mode_check = mode;
-
-                        spawn(async move {
-                            let result: Result<SubmitAction, client_api::ClientError> = match payload_mode {
-                                AuthMode::Login => {
-                                    let path = "/api/public/auth/login".to_string();
-                                    let captcha = if payload_captcha_code.is_empty() {
-                                        None
-                                    } else {
-                                        Some(payload_captcha_code.clone())
-                                    };
-                                    let res = auth_async
-                                        .login(
-                                            &payload_email,
-                                            &payload_password,
-                                            payload_remember,
-                                            captcha,
-                                        )
-                                        .await;
-                                    if *mode_check.read() == AuthMode::Login {
-                                        push_log_result(bus_async, HttpMethod::Post, &path, &res);
-                                    }
-                                    res.map(|_| SubmitAction::Nothing)
+                            if payload.password.is_empty() {
+                                error_msg.set(Some(t.login_password_empty.to_string()));
+                                return;
+                            }
+                            if payload.mode == AuthMode::Register {
+                                let name_trimmed = payload.name.trim();
+                                if name_trimmed.is_empty() {
+                                    error_msg.set(Some(t.login_name_empty.to_string()));
+                                    return;
                                 }
-                                AuthMode::Register => {
-                                    let path = "/api/public/auth/register".to_string();
-                                    let res = auth_async
-                                        .register(
-                                            &payload_email,
-                                            &payload_password, // 导航由 auth.user 变化触发的 use_effect 统一处理
-                                            &payload_name,
-                                            payload_remember,
-                                            &payload.password_confirm,
-                                        )
-                                        .await;
-                                    if *mode_check.read() == AuthMode::Register {
-                                        push_log_result(bus_async, HttpMethod::Post, &path, &res);
+                                if name_trimmed.len() < 6 || name_trimmed.len() > 50 {
+                                    error_msg.set(Some(t.login_name_length.to_string()));
+                                    return;
+                                }
+                                if payload.password != payload.password_confirm {
+                                    error_msg.set(Some(t.login_password_mismatch.to_string()));
+                                    return;
+                                }
+                            }
+                            let payload_email = payload.email.clone();
+                            let payload_password = payload.password.clone();
+                            let payload_name = payload.name.clone();
+                            let payload_captcha_code = payload.captcha_code.clone();
+                            let payload_mode = payload.mode;
+                            let payload_remember = payload.remember;
+
+                            let mut auth_async = auth.clone();
+                            let bus_async = log_bus;
+                            let nav_async = nav;
+
+                            loading.set(true);
+                            error_msg.set(None);
+                            field_error_map.set(BTreeMap::new());
+
+                            let mode_check = mode;
+
+                            spawn(async move {
+                                let result: Result<SubmitAction, client_api::ClientError> = match payload_mode {
+                                    AuthMode::Login => {
+                                        let path = "/api/public/auth/login".to_string();
+                                        let captcha = if payload_captcha_code.is_empty() {
+                                            None
+                                        } else {
+                                            Some(payload_captcha_code.clone())
+                                        };
+                                        let res = auth_async
+                                            .login(
+                                                &payload_email,
+                                                &payload_password,
+                                                payload_remember,
+                                                captcha,
+                                            )
+                                            .await;
+                                        if *mode_check.read() == AuthMode::Login {
+                                            push_log_result(bus_async, HttpMethod::Post, &path, &res);
+                                        }
+                                        res.map(|_| SubmitAction::Nothing)
                                     }
-                                    res.map(|outcome| match outcome {
-                                        RegisterOutcome::LoggedIn => SubmitAction::Nothing,
-                                        RegisterOutcome::NeedsVerification { email } => {
-                                            SubmitAction::NavigateToVerify {
-                                                email,
+                                    AuthMode::Register => {
+                                        let path = "/api/public/auth/register".to_string();
+                                        let res = auth_async
+                                            .register(
+                                                &payload_email,
+                                                &payload_password, // 导航由 auth.user 变化触发的 use_effect 统一处理
+                                                &payload_name,
+                                                payload_remember,
+                                                &payload.password_confirm,
+                                            )
+                                            .await;
+                                        if *mode_check.read() == AuthMode::Register {
+                                            push_log_result(bus_async, HttpMethod::Post, &path, &res);
+                                        }
+                                        res.map(|outcome| match outcome {
+                                            RegisterOutcome::LoggedIn => SubmitAction::Nothing,
+                                            RegisterOutcome::NeedsVerification { email } => {
+                                                SubmitAction::NavigateToVerify {
+                                                    email,
+                                                }
                                             }
+                                        })
+                                    }
+                                };
+                                loading.set(false);
+                                match result {
+                                    Ok(SubmitAction::Nothing) => {}
+                                    Ok(SubmitAction::NavigateToVerify { email }) => {
+                                        nav_async.push(Route::VerifyEmail { email });
+                                    }
+                                    Err(err) => {
+                                        if *mode_check.read() == payload_mode {
+                                            field_error_map.set(field_errors(&err));
+                                            error_msg
+                                                .set(
+                                                    Some(humanize_error(&err, ErrorContext::Auth, i18n.lang())),
+                                                );
                                         }
-                                    })
-                                }
-                            };
-                            loading.set(false);
-                            match result {
-                                Ok(SubmitAction::Nothing) => {}
-                                Ok(SubmitAction::NavigateToVerify { email }) => {
-                                    nav_async.push(Route::VerifyEmail { email });
-                                }
-                                Err(err) => {
-                                    if *mode_check.read() == payload_mode {
-                                        error_msg
-                                            .set(
-                                                Some(humanize_error(&err, ErrorContext::Auth, i18n.lang())),
-                                            );
                                     }
                                 }
-                            }
-                        });
-                    },
+                            });
+                        },
+                    }
+                    if *mode.read() == AuthMode::Login {
+                        PasskeyLogin { remember }
+                        OidcLogin { remember }
+                    }
                 }
             }
 
@@ -291,3 +336,201 @@ pub fn LoginLanding() -> Element {
         LanguageSwitcher { variant: LanguageSwitcherVariant::Floating }
     }
 }
+
+/// 通行密钥登录：取登录选项 → `navigator.credentials.get()` → 提交断言。
+///
+/// 浏览器列出本站可用的通行密钥，无需先输入邮箱；成功后同密码登录一样由
+/// `LoginLanding` 的 effect 跳转，需要两步验证时切换到 [`TwoFactorPrompt`]。
+#[component]
+fn PasskeyLogin(remember: Signal<bool>) -> Element {
+    let i18n = use_context::<I18nContext>();
+    let t = i18n.t();
+    let auth = use_context::<AuthState>();
+    let log_bus = use_context::<LogBus>();
+
+    let mut busy = use_signal(|| false);
+    let mut error_msg = use_signal(|| Option::<String>::None);
+
+    rsx! {
+        div { class: "ws-landing__passkey",
+            div { class: "ws-landing__passkey-divider",
+                span { {t.login_passkey_divider} }
+            }
+            Button {
+                full_width: true,
+                disabled: *busy.read(),
+                loading: *busy.read(),
+                onclick: move |_| {
+                    if *busy.read() {
+                        return;
+                    }
+                    let mut auth_async = auth.clone();
+                    let remember_value = *remember.read();
+                    busy.set(true);
+                    error_msg.set(None);
+                    spawn(async move {
+                        let options = auth_async.client.passkey_login_options().await;
+                        push_log_result(
+                            log_bus,
+                            HttpMethod::Post,
+                            "/api/public/auth/passkey/options",
+                            &options,
+                        );
+                        let options = match options {
+                            Ok(options) => options,
+                            Err(err) => {
+                                busy.set(false);
+                                error_msg.set(Some(humanize_error(&err, ErrorContext::Passkey, i18n.lang())));
+                                return;
+                            }
+                        };
+                        let credential = match get_credential(options).await {
+                            Ok(credential) => credential,
+                            Err(err) => {
+                                busy.set(false);
+                                error_msg.set(Some(match err {
+                                    PasskeyPromptError::Unsupported => t.passkey_unsupported.to_string(),
+                                    PasskeyPromptError::Cancelled => t.passkey_cancelled.to_string(),
+                                    PasskeyPromptError::Failed(msg) => msg,
+                                }));
+                                return;
+                            }
+                        };
+                        let res = auth_async.login_with_passkey(credential, remember_value).await;
+                        push_log_result(log_bus, HttpMethod::Post, "/api/public/auth/passkey/login", &res);
+                        busy.set(false);
+                        if let Err(err) = res {
+                            error_msg.set(Some(humanize_error(&err, ErrorContext::Passkey, i18n.lang())));
+                        }
+                    });
+                },
+                {t.login_passkey_btn}
+            }
+            if let Some(err) = error_msg.read().as_ref() {
+                p { class: "ws-form-error", "{err}" }
+            }
+        }
+    }
+}
+
+/// 第三方登录按钮：每个已启用的提供方一个，点击后整页跳转到授权地址。
+///
+/// 服务端未配置提供方时不渲染任何内容。
+#[component]
+fn OidcLogin(remember: Signal<bool>) -> Element {
+    let i18n = use_context::<I18nContext>();
+    let t = i18n.t();
+    let auth = use_context::<AuthState>();
+    let mut providers = use_signal(Vec::<OidcProvider>::new);
+
+    {
+        let client = auth.client.clone();
+        use_effect(move || {
+            let client = client.clone();
+            spawn(async move {
+                if let Ok(resp) = client.oidc_providers().await {
+                    providers.set(resp.providers);
+                }
+            });
+        });
+    }
+
+    if providers.read().is_empty() {
+        return rsx! {
+            Fragment {}
+        };
+    }
+
+    rsx! {
+        div { class: "ws-landing__oidc",
+            for provider in providers.read().clone() {
+                Button {
+                    key: "{provider.id}",
+                    full_width: true,
+                    onclick: {
+                        let client = auth.client.clone();
+                        move |_| oidc::navigate(client.oidc_authorize_url(&provider.id, *remember.read()))
+                    },
+                    {tf(t.login_oidc_btn, &[("name", &provider.name)])}
+                }
+            }
+        }
+    }
+}
+
+/// 两步验证第二步：提交验证器动态码或恢复码。
+///
+/// 成功后 `auth.user` 被设置，由 `LoginLanding` 的 effect 跳转到 dashboard；
+/// 挑战失效时 `AuthState` 清除 pending，回到登录表单。
+#[component]
+fn TwoFactorPrompt() -> Element {
+    let i18n = use_context::<I18nContext>();
+    let t = i18n.t();
+    let auth = use_context::<AuthState>();
+    let log_bus = use_context::<LogBus>();
+
+    let code = use_signal(String::new);
+    let mut submitting = use_signal(|| false);
+    let mut error_msg = use_signal(|| Option::<String>::None);
+    let mut auth_for_back = auth.clone();
+
+    rsx! {
+        form {
+            class: "ws-landing__two-factor",
+            onsubmit: move |e| {
+                e.prevent_default();
+                if *submitting.read() {
+                    return;
+                }
+                let code_value = code.read().trim().to_string();
+                if code_value.is_empty() {
+                    error_msg.set(Some(t.login_2fa_code_empty.to_string()));
+                    return;
+                }
+                let mut auth_async = auth.clone();
+                let bus_async = log_bus;
+                submitting.set(true);
+                error_msg.set(None);
+                spawn(async move {
+                    let path = "/api/public/auth/login/2fa".to_string();
+                    let res = auth_async.complete_two_factor(&code_value).await;
+                    push_log_result(bus_async, HttpMethod::Post, &path, &res);
+                    submitting.set(false);
+                    if let Err(err) = res {
+                        error_msg.set(Some(humanize_error(&err, ErrorContext::TwoFactor, i18n.lang())));
+                    }
+                });
+            },
+            h2 { class: "ws-landing__two-factor-title", {t.login_2fa_title} }
+            p { class: "ws-landing__two-factor-hint", {t.login_2fa_hint} }
+            TextInput {
+                label: t.login_2fa_code_label.to_string(),
+                placeholder: Some("000000".to_string()),
+                value: code,
+                required: true,
+                disabled: *submitting.read(),
+                name: Some("code".to_string()),
+                autocomplete: Some("one-time-code".to_string()),
+            }
+            if let Some(err) = error_msg.read().as_ref() {
+                p { class: "ws-form-error", "{err}" }
+            }
+            Button {
+                button_type: ButtonType::Submit,
+                full_width: true,
+                disabled: *submitting.read(),
+                loading: *submitting.read(),
+                {t.login_2fa_submit}
+            }
+            a {
+                class: "ws-landing__two-factor-back",
+                href: "#",
+                onclick: move |e| {
+                    e.prevent_default();
+                    auth_for_back.cancel_two_factor();
+                },
+                {t.login_2fa_back}
+            }
+        }
+    }
+}
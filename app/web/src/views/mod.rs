@@ -3,6 +3,7 @@ mod dashboard;
 mod forgot_password;
 mod login_landing;
 mod not_found;
+mod oauth_consent;
 mod reset_password;
 mod settings;
 mod users;
@@ -13,6 +14,7 @@ pub use dashboard::Dashboard;
 pub use forgot_password::ForgotPassword;
 pub use login_landing::LoginLanding;
 pub use not_found::NotFound;
+pub use oauth_consent::OAuthConsent;
 pub use reset_password::ResetPassword;
 pub use settings::Settings;
 pub use users::Users;
@@ -0,0 +1,209 @@
+//! 第三方应用授权同意页 —— `/oauth/consent?request=<id>`。
+//!
+//! 由服务端授权端点跳转而来。未登录时暂存 `request` 并转到登录页，登录后经
+//! `RequireAuth` 回到本页。已登录时拉取请求详情：信任的客户端或已同意过的权限
+//! 直接通过，否则展示应用名称与权限列表，由用户允许或拒绝；结果是回到第三方
+//! 应用的整页跳转。
+
+use client_api::{AuthorizationDetails, Client, ClientError};
+use dioxus::prelude::*;
+use ui::{Button, I18nContext, tf};
+
+use crate::Route;
+use crate::api::{handle_unauth, is_unauth};
+use crate::auth::AuthState;
+use crate::components::{HttpMethod, LogBus, push_log_result};
+use crate::oauth;
+use crate::oidc;
+
+/// 提交允许 / 拒绝，成功后离开单页应用回到第三方应用。
+async fn submit_decision(
+    client: Client,
+    request: String,
+    approve: bool,
+    log_bus: LogBus,
+) -> Result<(), ClientError> {
+    let res = client.oauth_authorization_decision(request, approve).await;
+    push_log_result(
+        log_bus,
+        HttpMethod::Post,
+        "/api/oauth/authorize/decision",
+        &res,
+    );
+    oidc::navigate(res?.redirect_to);
+    Ok(())
+}
+
+#[component]
+pub fn OAuthConsent(request: String) -> Element {
+    let i18n = use_context::<I18nContext>();
+    let t = i18n.t();
+    let auth = use_context::<AuthState>();
+    let log_bus = use_context::<LogBus>();
+    let nav = use_navigator();
+
+    let mut details = use_signal(|| Option::<AuthorizationDetails>::None);
+    let mut error_msg = use_signal(|| Option::<String>::None);
+    let mut submitting = use_signal(|| false);
+    let mut redirecting = use_signal(|| false);
+
+    {
+        let auth = auth.clone();
+        let request = request.clone();
+        use_effect(move || {
+            if !*auth.initialized.read() {
+                return;
+            }
+            // 未登录：记下请求，登录完成后继续
+            if !auth.is_authenticated() {
+                oauth::save_pending_request(&request);
+                nav.replace(Route::LoginLanding {});
+                return;
+            }
+            let auth = auth.clone();
+            let request = request.clone();
+            spawn(async move {
+                let res = auth
+                    .client
+                    .oauth_authorization_details(request.clone())
+                    .await;
+                push_log_result(
+                    log_bus,
+                    HttpMethod::Post,
+                    "/api/oauth/authorize/details",
+                    &res,
+                );
+                let result = match res {
+                    // 无需再次询问：直接同意
+                    Ok(d) if !d.consent_required => {
+                        redirecting.set(true);
+                        submit_decision(auth.client.clone(), request.clone(), true, log_bus).await
+                    }
+                    Ok(d) => {
+                        details.set(Some(d));
+                        Ok(())
+                    }
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    redirecting.set(false);
+                    if is_unauth(&err) {
+                        oauth::save_pending_request(&request);
+                    }
+                    if !handle_unauth(&err, auth, nav, log_bus).await {
+                        error_msg.set(Some(t.oauth_consent_invalid.to_string()));
+                    }
+                }
+            });
+        });
+    }
+
+    if !*auth.initialized.read() || !auth.is_authenticated() {
+        return rsx! {
+            Fragment {}
+        };
+    }
+
+    let decide = {
+        let auth = auth.clone();
+        let request = request.clone();
+        move |approve: bool| {
+            if *submitting.read() {
+                return;
+            }
+            submitting.set(true);
+            let client = auth.client.clone();
+            let request = request.clone();
+            spawn(async move {
+                match submit_decision(client, request, approve, log_bus).await {
+                    Ok(()) => redirecting.set(true),
+                    Err(_) => {
+                        details.set(None);
+                        error_msg.set(Some(t.oauth_consent_invalid.to_string()));
+                    }
+                }
+                submitting.set(false);
+            });
+        }
+    };
+
+    let body = if let Some(err) = error_msg.read().as_ref() {
+        rsx! {
+            p { class: "ws-consent__error", "{err}" }
+            a {
+                class: "ws-consent__back",
+                href: "#",
+                onclick: move |e| {
+                    e.prevent_default();
+                    nav.replace(Route::Dashboard {});
+                },
+                {t.not_found_back_to_dashboard}
+            }
+        }
+    } else if *redirecting.read() {
+        rsx! {
+            p { class: "ws-consent__subtitle", {t.oauth_consent_redirecting} }
+        }
+    } else if let Some(d) = details.read().as_ref() {
+        let title = tf(t.oauth_consent_title, &[("name", &d.client_name)]);
+        let subtitle = tf(t.oauth_consent_subtitle, &[("name", &d.client_name)]);
+        let hint = tf(
+            t.oauth_consent_redirect_hint,
+            &[("host", &oauth::redirect_host(&d.redirect_uri))],
+        );
+        let busy = *submitting.read();
+        rsx! {
+            h1 { class: "ws-consent__title", "{title}" }
+            p { class: "ws-consent__subtitle", "{subtitle}" }
+            p { class: "ws-consent__label", {t.oauth_consent_scopes_label} }
+            ul { class: "ws-consent__scopes",
+                for scope in d.scopes.iter() {
+                    li { key: "{scope}", class: "ws-consent__scope",
+                        {oauth::scope_description(scope, t).to_string()}
+                    }
+                }
+            }
+            p { class: "ws-consent__hint", "{hint}" }
+            div { class: "ws-consent__actions",
+                button {
+                    class: "ws-consent__deny",
+                    r#type: "button",
+                    disabled: busy,
+                    onclick: {
+                        let mut decide = decide.clone();
+                        move |_| decide(false)
+                    },
+                    {t.oauth_consent_deny}
+                }
+                Button {
+                    disabled: busy,
+                    loading: busy,
+                    onclick: {
+                        let mut decide = decide.clone();
+                        move |_| decide(true)
+                    },
+                    {t.oauth_consent_allow}
+                }
+            }
+        }
+    } else {
+        rsx! {
+            Fragment {}
+        }
+    };
+
+    rsx! {
+        document::Link {
+            rel: "stylesheet",
+            href: asset!("/assets/styling/oauth_consent.css"),
+        }
+        div { class: "ws-consent",
+            div { class: "ws-consent__orb ws-consent__orb--indigo" }
+            div { class: "ws-consent__orb ws-consent__orb--cyan" }
+            div { class: "ws-consent__card",
+                div { class: "ws-consent__icon" }
+                {body}
+            }
+        }
+    }
+}
@@ -2,10 +2,12 @@
 //!
 //! 流程：用户输入邮箱 + 6 位验证码 + 新密码 → 提交到
 //! `POST /api/public/auth/reset-password`。成功时服务端原子地
-//! `token_version += 1` 并返回全新 JWT，前端按登录流程接管会话。
+//! `token_version += 1` 并返回全新 JWT，前端按登录流程接管会话；
+//! 启用两步验证的账号改为跳回登录页输入动态码。
 //!
 //! 失败统一展示"验证码无效或已过期"或"密码强度不足"（服务端 anti-enumeration）。
 
+use client_api::AuthOutcome;
 use dioxus::prelude::*;
 
 use ui::{Button, ButtonType, I18nContext, InputType, TextInput};
@@ -109,11 +111,16 @@ pub fn ResetPassword(email: Option<String>) -> Element {
                             push_log_result(bus_async, HttpMethod::Post, &path, &res);
                             submitting.set(false);
                             match res {
-                                Ok(resp) => {
+                                Ok(AuthOutcome::Authenticated(resp)) => {
                                     let _ = resp.message;
                                     auth_async.persist_session_async(&resp.token, was_remembered).await;
                                     nav.replace(Route::Dashboard {});
                                 }
+                                Ok(AuthOutcome::TwoFactorRequired(challenge)) => {
+                                    // 密码已重置，但登录仍需动态码 —— 登录页渲染两步验证表单
+                                    auth_async.begin_two_factor(challenge);
+                                    nav.replace(Route::LoginLanding {});
+                                }
                                 Err(err) => {
                                     let msg = humanize_error(
                                         &err,
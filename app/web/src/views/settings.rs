@@ -1,15 +1,25 @@
-//! 个人设置视图 —— 任何已认证用户可修改自己的密码。
+//! 个人设置视图 —— 任何已认证用户可修改自己的密码、管理两步验证与通行密钥。
 //!
 //! 流程：填写当前密码 + 新密码 + 确认新密码 → 提交到 POST /api/users/me/password。
+//! 两步验证：输入密码 → `enroll` 返回密钥 / otpauth 链接 → 输入首个动态码 `confirm`
+//! → 展示一次性恢复码。
+//! 通行密钥：输入密码 → 取创建选项 → `navigator.credentials.create()` → 提交凭据；
+//! 列表中可逐个撤销。
+//! 第三方账号：列出首次用 OpenID Connect 登录时绑定的账号，可逐个解除绑定。
+//! API 令牌：填写名称、勾选权限、选择有效期 → 创建后仅展示一次完整令牌；列表中可逐个撤销。
 
+use chrono::{DateTime, Utc};
+use client_api::{ApiTokenInfo, IdentityInfo, PasskeyInfo, TwoFactorEnrollment, TwoFactorStatus};
 use dioxus::prelude::*;
-use ui::{Button, ButtonType, I18nContext, InputType, TextInput, Translations};
+use ui::{Button, ButtonType, I18nContext, InputType, TextInput, Translations, tf};
 
 use crate::Route;
 use crate::api::{ErrorContext, handle_unauth, humanize_error};
 use crate::auth::AuthState;
 use crate::balance::format_balance;
 use crate::components::{ConfirmDialog, HttpMethod, LogBus, LogKind, push_log_result};
+use crate::oauth::scope_description;
+use crate::passkey::{PasskeyPromptError, create_credential};
 
 #[component]
 pub fn Settings() -> Element {
@@ -225,6 +235,14 @@ pub fn Settings() -> Element {
                 }
             }
 
+            TwoFactorPanel {}
+
+            PasskeyPanel {}
+
+            IdentityPanel {}
+
+            ApiTokenPanel {}
+
             section { class: "ws-settings__section",
                 h2 { class: "ws-settings__section-title", "{t.settings_session_title}" }
                 p { class: "ws-settings__desc", "{t.settings_session_desc}" }
@@ -272,6 +290,956 @@ pub fn Settings() -> Element {
     }
 }
 
+/// 两步验证面板：状态、启用（密钥 → 首个动态码 → 恢复码）、停用与重新生成恢复码。
+#[component]
+fn TwoFactorPanel() -> Element {
+    let auth = use_context::<AuthState>();
+    let log_bus = use_context::<LogBus>();
+    let nav = use_navigator();
+    let i18n = use_context::<I18nContext>();
+    let t = i18n.t();
+
+    let mut status = use_signal(|| Option::<TwoFactorStatus>::None);
+    // 每次变更后 +1，触发状态重新加载
+    let mut reload = use_signal(|| 0u32);
+    let mut enrollment = use_signal(|| Option::<TwoFactorEnrollment>::None);
+    let mut recovery_codes = use_signal(|| Option::<Vec<String>>::None);
+    let mut password = use_signal(String::new);
+    let mut code = use_signal(String::new);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(|| Option::<String>::None);
+    let mut success = use_signal(|| Option::<String>::None);
+
+    {
+        let client = auth.client.clone();
+        use_effect(move || {
+            let _ = reload();
+            let client = client.clone();
+            spawn(async move {
+                if let Ok(s) = client.two_factor_status().await {
+                    status.set(Some(s));
+                }
+            });
+        });
+    }
+
+    // 统一的请求收尾：401/403 走 handle_unauth，其余错误写入 error
+    let auth_for_run = auth.clone();
+    let run = move |path: &'static str,
+                    action: std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Option<String>, client_api::ClientError>>>,
+    >| {
+        let auth_async = auth_for_run.clone();
+        busy.set(true);
+        error.set(None);
+        success.set(None);
+        spawn(async move {
+            let res = action.await;
+            if let Err(err) = &res
+                && handle_unauth(err, auth_async, nav, log_bus).await
+            {
+                busy.set(false);
+                return;
+            }
+            push_log_result(log_bus, HttpMethod::Post, path, &res);
+            busy.set(false);
+            match res {
+                Ok(msg) => {
+                    success.set(msg);
+                    password.set(String::new());
+                    code.set(String::new());
+                    *reload.write() += 1;
+                }
+                Err(err) => {
+                    error.set(Some(humanize_error(
+                        &err,
+                        ErrorContext::TwoFactor,
+                        i18n.lang(),
+                    )));
+                }
+            }
+        });
+    };
+
+    let client = auth.client.clone();
+    let mut on_enroll_run = run.clone();
+    let on_enroll = move |_| {
+        if password.read().is_empty() {
+            error.set(Some(t.settings_validation_current_empty.to_string()));
+            return;
+        }
+        let client = client.clone();
+        let pw = password.read().clone();
+        on_enroll_run(
+            "/api/users/me/2fa/enroll",
+            Box::pin(async move {
+                enrollment.set(Some(client.enroll_two_factor(pw).await?));
+                Ok(None)
+            }),
+        );
+    };
+
+    let client = auth.client.clone();
+    let mut on_confirm_run = run.clone();
+    let on_confirm = move |_| {
+        if code.read().trim().is_empty() {
+            error.set(Some(t.settings_2fa_code_empty.to_string()));
+            return;
+        }
+        let client = client.clone();
+        let c = code.read().trim().to_string();
+        on_confirm_run(
+            "/api/users/me/2fa/confirm",
+            Box::pin(async move {
+                let resp = client.confirm_two_factor(c).await?;
+                enrollment.set(None);
+                recovery_codes.set(Some(resp.recovery_codes));
+                Ok(Some(t.settings_2fa_enabled_msg.to_string()))
+            }),
+        );
+    };
+
+    let client = auth.client.clone();
+    let mut on_disable_run = run.clone();
+    let on_disable = move |_| {
+        if password.read().is_empty() {
+            error.set(Some(t.settings_validation_current_empty.to_string()));
+            return;
+        }
+        if code.read().trim().is_empty() {
+            error.set(Some(t.settings_2fa_code_empty.to_string()));
+            return;
+        }
+        let client = client.clone();
+        let pw = password.read().clone();
+        let c = code.read().trim().to_string();
+        on_disable_run(
+            "/api/users/me/2fa/disable",
+            Box::pin(async move {
+                client.disable_two_factor(pw, c).await?;
+                Ok(Some(t.settings_2fa_disabled_msg.to_string()))
+            }),
+        );
+    };
+
+    let client = auth.client.clone();
+    let mut on_regenerate_run = run.clone();
+    let on_regenerate = move |_| {
+        if code.read().trim().is_empty() {
+            error.set(Some(t.settings_2fa_code_empty.to_string()));
+            return;
+        }
+        let client = client.clone();
+        let c = code.read().trim().to_string();
+        on_regenerate_run(
+            "/api/users/me/2fa/recovery-codes",
+            Box::pin(async move {
+                let resp = client.regenerate_recovery_codes(c).await?;
+                recovery_codes.set(Some(resp.recovery_codes));
+                Ok(None)
+            }),
+        );
+    };
+
+    let enabled = status.read().as_ref().is_some_and(|s| s.enabled);
+    let status_text = match status.read().as_ref() {
+        Some(s) if s.enabled => format!(
+            "{} · {}",
+            t.settings_2fa_status_enabled,
+            tf(
+                t.settings_2fa_recovery_remaining,
+                &[("count", &s.recovery_codes_remaining.to_string())],
+            )
+        ),
+        _ => t.settings_2fa_status_disabled.to_string(),
+    };
+    let is_busy = *busy.read();
+
+    rsx! {
+        section { class: "ws-settings__section",
+            h2 { class: "ws-settings__section-title", "{t.settings_2fa_title}" }
+            p { class: "ws-settings__desc", "{t.settings_2fa_desc}" }
+            div { class: "ws-settings__identity",
+                div { class: "ws-settings__identity-row",
+                    span { class: "ws-settings__identity-label", "{t.settings_2fa_status_label}" }
+                    span { class: "ws-settings__identity-value", "{status_text}" }
+                }
+            }
+
+            div { class: "ws-settings__form",
+                if let Some(codes) = recovery_codes.read().clone() {
+                    // 恢复码只在此处展示一次
+                    h3 { class: "ws-settings__subsection-title", "{t.settings_2fa_codes_title}" }
+                    p { class: "ws-settings__desc", "{t.settings_2fa_codes_hint}" }
+                    ul { class: "ws-settings__recovery-codes",
+                        for c in codes {
+                            li { key: "{c}", code { "{c}" } }
+                        }
+                    }
+                    Button {
+                        full_width: true,
+                        onclick: move |_| recovery_codes.set(None),
+                        "{t.settings_2fa_codes_done}"
+                    }
+                } else if let Some(pending) = enrollment.read().clone() {
+                    p { class: "ws-settings__desc", "{t.settings_2fa_setup_hint}" }
+                    div { class: "ws-settings__identity",
+                        div { class: "ws-settings__identity-row",
+                            span { class: "ws-settings__identity-label", "{t.settings_2fa_secret_label}" }
+                            code { class: "ws-settings__identity-value", "{pending.secret}" }
+                        }
+                    }
+                    a { class: "ws-settings__link", href: "{pending.otpauth_uri}", "{t.settings_2fa_uri_label}" }
+                    TextInput {
+                        label: t.settings_2fa_code_label.to_string(),
+                        placeholder: Some("000000".to_string()),
+                        value: code,
+                        required: true,
+                        disabled: is_busy,
+                        name: Some("code".to_string()),
+                        autocomplete: Some("one-time-code".to_string()),
+                    }
+                    Button {
+                        full_width: true,
+                        disabled: is_busy,
+                        loading: is_busy,
+                        onclick: on_confirm,
+                        "{t.settings_2fa_confirm_btn} [POST /api/users/me/2fa/confirm]"
+                    }
+                    Button {
+                        full_width: true,
+                        disabled: is_busy,
+                        onclick: move |_| {
+                            enrollment.set(None);
+                            code.set(String::new());
+                        },
+                        "{t.settings_2fa_cancel_btn}"
+                    }
+                } else if enabled {
+                    TextInput {
+                        label: t.settings_current_password_label.to_string(),
+                        placeholder: Some(t.settings_current_password_placeholder.to_string()),
+                        value: password,
+                        input_type: InputType::Password,
+                        disabled: is_busy,
+                        name: Some("two_factor_password".to_string()),
+                        autocomplete: Some("current-password".to_string()),
+                    }
+                    TextInput {
+                        label: t.settings_2fa_code_or_recovery_label.to_string(),
+                        placeholder: Some("000000".to_string()),
+                        value: code,
+                        disabled: is_busy,
+                        name: Some("code".to_string()),
+                        autocomplete: Some("one-time-code".to_string()),
+                    }
+                    Button {
+                        full_width: true,
+                        disabled: is_busy,
+                        loading: is_busy,
+                        onclick: on_regenerate,
+                        "{t.settings_2fa_regenerate_btn} [POST /api/users/me/2fa/recovery-codes]"
+                    }
+                    Button {
+                        button_type: ButtonType::Danger,
+                        full_width: true,
+                        disabled: is_busy,
+                        loading: is_busy,
+                        onclick: on_disable,
+                        "{t.settings_2fa_disable_btn} [POST /api/users/me/2fa/disable]"
+                    }
+                } else {
+                    TextInput {
+                        label: t.settings_current_password_label.to_string(),
+                        placeholder: Some(t.settings_current_password_placeholder.to_string()),
+                        value: password,
+                        input_type: InputType::Password,
+                        disabled: is_busy,
+                        name: Some("two_factor_password".to_string()),
+                        autocomplete: Some("current-password".to_string()),
+                    }
+                    Button {
+                        full_width: true,
+                        disabled: is_busy,
+                        loading: is_busy,
+                        onclick: on_enroll,
+                        "{t.settings_2fa_enable_btn} [POST /api/users/me/2fa/enroll]"
+                    }
+                }
+                if let Some(err) = error.read().as_ref() {
+                    p { class: "ws-form-error", "{err}" }
+                }
+                if let Some(msg) = success.read().as_ref() {
+                    p { class: "ws-form-success", "{msg}" }
+                }
+            }
+        }
+    }
+}
+
+/// 通行密钥面板：列表（名称、添加 / 最近使用时间）、添加与撤销。
+#[component]
+fn PasskeyPanel() -> Element {
+    let auth = use_context::<AuthState>();
+    let log_bus = use_context::<LogBus>();
+    let nav = use_navigator();
+    let i18n = use_context::<I18nContext>();
+    let t = i18n.t();
+
+    let mut passkeys = use_signal(Vec::<PasskeyInfo>::new);
+    // 每次变更后 +1，触发列表重新加载
+    let mut reload = use_signal(|| 0u32);
+    let mut name = use_signal(String::new);
+    let mut password = use_signal(String::new);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(|| Option::<String>::None);
+    let mut success = use_signal(|| Option::<String>::None);
+    let mut revoke_target = use_signal(|| Option::<PasskeyInfo>::None);
+
+    {
+        let client = auth.client.clone();
+        use_effect(move || {
+            let _ = reload();
+            let client = client.clone();
+            spawn(async move {
+                if let Ok(resp) = client.list_passkeys().await {
+                    passkeys.set(resp.passkeys);
+                }
+            });
+        });
+    }
+
+    let auth_for_add = auth.clone();
+    let on_add = move |_| {
+        if *busy.read() {
+            return;
+        }
+        if password.read().is_empty() {
+            error.set(Some(t.settings_validation_current_empty.to_string()));
+            return;
+        }
+        let auth_async = auth_for_add.clone();
+        let client = auth_async.client.clone();
+        let pw = password.read().clone();
+        let label = Some(name.read().trim().to_string()).filter(|n| !n.is_empty());
+        busy.set(true);
+        error.set(None);
+        success.set(None);
+        spawn(async move {
+            let path = "/api/users/me/passkeys/options";
+            let options = client.passkey_registration_options(pw).await;
+            if let Err(err) = &options
+                && handle_unauth(err, auth_async.clone(), nav, log_bus).await
+            {
+                busy.set(false);
+                return;
+            }
+            push_log_result(log_bus, HttpMethod::Post, path, &options);
+            let options = match options {
+                Ok(options) => options,
+                Err(err) => {
+                    busy.set(false);
+                    error.set(Some(humanize_error(
+                        &err,
+                        ErrorContext::Passkey,
+                        i18n.lang(),
+                    )));
+                    return;
+                }
+            };
+            let credential = match create_credential(options).await {
+                Ok(credential) => credential,
+                Err(err) => {
+                    busy.set(false);
+                    error.set(Some(match err {
+                        PasskeyPromptError::Unsupported => t.passkey_unsupported.to_string(),
+                        PasskeyPromptError::Cancelled => t.passkey_cancelled.to_string(),
+                        PasskeyPromptError::Failed(msg) => msg,
+                    }));
+                    return;
+                }
+            };
+            let res = client.register_passkey(label, credential).await;
+            if let Err(err) = &res
+                && handle_unauth(err, auth_async, nav, log_bus).await
+            {
+                busy.set(false);
+                return;
+            }
+            push_log_result(log_bus, HttpMethod::Post, "/api/users/me/passkeys", &res);
+            busy.set(false);
+            match res {
+                Ok(_) => {
+                    success.set(Some(t.settings_passkey_added_msg.to_string()));
+                    name.set(String::new());
+                    password.set(String::new());
+                    *reload.write() += 1;
+                }
+                Err(err) => {
+                    error.set(Some(humanize_error(
+                        &err,
+                        ErrorContext::Passkey,
+                        i18n.lang(),
+                    )));
+                }
+            }
+        });
+    };
+
+    let auth_for_revoke = auth.clone();
+    let on_revoke_confirm = move |_| {
+        let Some(target) = revoke_target.read().clone() else {
+            return;
+        };
+        let auth_async = auth_for_revoke.clone();
+        let client = auth_async.client.clone();
+        busy.set(true);
+        error.set(None);
+        success.set(None);
+        spawn(async move {
+            let path = format!("/api/users/me/passkeys/{}", target.id);
+            let res = client.delete_passkey(target.id).await;
+            revoke_target.set(None);
+            if let Err(err) = &res
+                && handle_unauth(err, auth_async, nav, log_bus).await
+            {
+                busy.set(false);
+                return;
+            }
+            push_log_result(log_bus, HttpMethod::Delete, &path, &res);
+            busy.set(false);
+            match res {
+                Ok(_) => {
+                    success.set(Some(t.settings_passkey_revoked_msg.to_string()));
+                    *reload.write() += 1;
+                }
+                Err(err) => {
+                    error.set(Some(humanize_error(
+                        &err,
+                        ErrorContext::Passkey,
+                        i18n.lang(),
+                    )));
+                }
+            }
+        });
+    };
+
+    let is_busy = *busy.read();
+    let confirm_msg = revoke_target
+        .read()
+        .as_ref()
+        .map(|p| tf(t.settings_passkey_confirm_msg, &[("name", &p.name)]))
+        .unwrap_or_default();
+
+    rsx! {
+        section { class: "ws-settings__section",
+            h2 { class: "ws-settings__section-title", "{t.settings_passkey_title}" }
+            p { class: "ws-settings__desc", "{t.settings_passkey_desc}" }
+            if passkeys.read().is_empty() {
+                p { class: "ws-settings__desc", "{t.settings_passkey_empty}" }
+            } else {
+                ul { class: "ws-settings__passkeys",
+                    for passkey in passkeys.read().clone() {
+                        li { key: "{passkey.id}", class: "ws-settings__passkey",
+                            div { class: "ws-settings__passkey-info",
+                                span { class: "ws-settings__passkey-name", "{passkey.name}" }
+                                span { class: "ws-settings__passkey-meta",
+                                    {passkey_meta(&passkey, t)}
+                                }
+                            }
+                            Button {
+                                button_type: ButtonType::Danger,
+                                disabled: is_busy,
+                                onclick: move |_| revoke_target.set(Some(passkey.clone())),
+                                "{t.settings_passkey_revoke_btn}"
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { class: "ws-settings__form",
+                TextInput {
+                    label: t.settings_passkey_name_label.to_string(),
+                    placeholder: Some(t.settings_passkey_name_placeholder.to_string()),
+                    value: name,
+                    disabled: is_busy,
+                    name: Some("passkey_name".to_string()),
+                }
+                TextInput {
+                    label: t.settings_current_password_label.to_string(),
+                    placeholder: Some(t.settings_current_password_placeholder.to_string()),
+                    value: password,
+                    input_type: InputType::Password,
+                    disabled: is_busy,
+                    name: Some("passkey_password".to_string()),
+                    autocomplete: Some("current-password".to_string()),
+                }
+                Button {
+                    full_width: true,
+                    disabled: is_busy,
+                    loading: is_busy,
+                    onclick: on_add,
+                    "{t.settings_passkey_add_btn} [POST /api/users/me/passkeys]"
+                }
+                if let Some(err) = error.read().as_ref() {
+                    p { class: "ws-form-error", "{err}" }
+                }
+                if let Some(msg) = success.read().as_ref() {
+                    p { class: "ws-form-success", "{msg}" }
+                }
+            }
+
+            ConfirmDialog {
+                open: revoke_target.read().is_some(),
+                title: t.settings_passkey_confirm_title.to_string(),
+                message: confirm_msg,
+                danger: true,
+                loading: is_busy,
+                on_confirm: on_revoke_confirm,
+                on_cancel: move |_| revoke_target.set(None),
+            }
+        }
+    }
+}
+
+fn passkey_meta(passkey: &PasskeyInfo, t: &Translations) -> String {
+    let created = tf(
+        t.settings_passkey_created,
+        &[("date", &format_dt(&passkey.created_at))],
+    );
+    let used = match &passkey.last_used_at {
+        Some(at) => tf(t.settings_passkey_last_used, &[("date", &format_dt(at))]),
+        None => t.settings_passkey_never_used.to_string(),
+    };
+    format!("{created} · {used}")
+}
+
+/// 第三方账号面板：列表（提供方、邮箱、绑定 / 最近登录时间）与解除绑定。
+#[component]
+fn IdentityPanel() -> Element {
+    let auth = use_context::<AuthState>();
+    let log_bus = use_context::<LogBus>();
+    let nav = use_navigator();
+    let i18n = use_context::<I18nContext>();
+    let t = i18n.t();
+
+    let mut identities = use_signal(Vec::<IdentityInfo>::new);
+    // 每次变更后 +1，触发列表重新加载
+    let mut reload = use_signal(|| 0u32);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(|| Option::<String>::None);
+    let mut success = use_signal(|| Option::<String>::None);
+    let mut unlink_target = use_signal(|| Option::<IdentityInfo>::None);
+
+    {
+        let client = auth.client.clone();
+        use_effect(move || {
+            let _ = reload();
+            let client = client.clone();
+            spawn(async move {
+                if let Ok(resp) = client.list_identities().await {
+                    identities.set(resp.identities);
+                }
+            });
+        });
+    }
+
+    let auth_for_unlink = auth.clone();
+    let on_unlink_confirm = move |_| {
+        let Some(target) = unlink_target.read().clone() else {
+            return;
+        };
+        let auth_async = auth_for_unlink.clone();
+        let client = auth_async.client.clone();
+        busy.set(true);
+        error.set(None);
+        success.set(None);
+        spawn(async move {
+            let path = format!("/api/users/me/identities/{}", target.id);
+            let res = client.delete_identity(target.id).await;
+            unlink_target.set(None);
+            if let Err(err) = &res
+                && handle_unauth(err, auth_async, nav, log_bus).await
+            {
+                busy.set(false);
+                return;
+            }
+            push_log_result(log_bus, HttpMethod::Delete, &path, &res);
+            busy.set(false);
+            match res {
+                Ok(_) => {
+                    success.set(Some(t.settings_identity_unlinked_msg.to_string()));
+                    *reload.write() += 1;
+                }
+                Err(err) => {
+                    error.set(Some(humanize_error(
+                        &err,
+                        ErrorContext::UserManagement,
+                        i18n.lang(),
+                    )));
+                }
+            }
+        });
+    };
+
+    let is_busy = *busy.read();
+    let confirm_msg = unlink_target
+        .read()
+        .as_ref()
+        .map(|i| {
+            tf(
+                t.settings_identity_confirm_msg,
+                &[("name", &i.provider_name)],
+            )
+        })
+        .unwrap_or_default();
+
+    rsx! {
+        section { class: "ws-settings__section",
+            h2 { class: "ws-settings__section-title", "{t.settings_identity_title}" }
+            p { class: "ws-settings__desc", "{t.settings_identity_desc}" }
+            if identities.read().is_empty() {
+                p { class: "ws-settings__desc", "{t.settings_identity_empty}" }
+            } else {
+                ul { class: "ws-settings__passkeys",
+                    for identity in identities.read().clone() {
+                        li { key: "{identity.id}", class: "ws-settings__passkey",
+                            div { class: "ws-settings__passkey-info",
+                                span { class: "ws-settings__passkey-name", "{identity.provider_name}" }
+                                span { class: "ws-settings__passkey-meta",
+                                    {identity_meta(&identity, t)}
+                                }
+                            }
+                            Button {
+                                button_type: ButtonType::Danger,
+                                disabled: is_busy,
+                                onclick: move |_| unlink_target.set(Some(identity.clone())),
+                                "{t.settings_identity_unlink_btn}"
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(err) = error.read().as_ref() {
+                p { class: "ws-form-error", "{err}" }
+            }
+            if let Some(msg) = success.read().as_ref() {
+                p { class: "ws-form-success", "{msg}" }
+            }
+
+            ConfirmDialog {
+                open: unlink_target.read().is_some(),
+                title: t.settings_identity_confirm_title.to_string(),
+                message: confirm_msg,
+                danger: true,
+                loading: is_busy,
+                on_confirm: on_unlink_confirm,
+                on_cancel: move |_| unlink_target.set(None),
+            }
+        }
+    }
+}
+
+fn identity_meta(identity: &IdentityInfo, t: &Translations) -> String {
+    let mut parts = Vec::new();
+    if let Some(email) = &identity.email {
+        parts.push(email.clone());
+    }
+    parts.push(tf(
+        t.settings_identity_linked,
+        &[("date", &format_dt(&identity.created_at))],
+    ));
+    if let Some(at) = &identity.last_login_at {
+        parts.push(tf(
+            t.settings_identity_last_used,
+            &[("date", &format_dt(at))],
+        ));
+    }
+    parts.join(" · ")
+}
+
+/// 个人访问令牌可选的权限范围（与服务端 `API_SCOPES` 一致）。
+const TOKEN_SCOPES: [&str; 2] = ["users:read", "users:write"];
+
+/// 有效期选项（天）；`None` 为永不过期。
+const TOKEN_EXPIRY_DAYS: [Option<u32>; 4] = [Some(30), Some(90), Some(365), None];
+
+/// API 令牌面板：创建（名称、权限、有效期，完整令牌只展示一次）、列表与撤销。
+#[component]
+fn ApiTokenPanel() -> Element {
+    let auth = use_context::<AuthState>();
+    let log_bus = use_context::<LogBus>();
+    let nav = use_navigator();
+    let i18n = use_context::<I18nContext>();
+    let t = i18n.t();
+
+    let mut tokens = use_signal(Vec::<ApiTokenInfo>::new);
+    // 每次变更后 +1，触发列表重新加载
+    let mut reload = use_signal(|| 0u32);
+    let mut name = use_signal(String::new);
+    let mut scopes = use_signal(|| vec![TOKEN_SCOPES[0].to_string()]);
+    let mut expires_in_days = use_signal(|| Some(90u32));
+    let mut created_token = use_signal(|| Option::<String>::None);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(|| Option::<String>::None);
+    let mut success = use_signal(|| Option::<String>::None);
+    let mut revoke_target = use_signal(|| Option::<ApiTokenInfo>::None);
+
+    {
+        let client = auth.client.clone();
+        use_effect(move || {
+            let _ = reload();
+            let client = client.clone();
+            spawn(async move {
+                if let Ok(resp) = client.list_api_tokens().await {
+                    tokens.set(resp.tokens);
+                }
+            });
+        });
+    }
+
+    let auth_for_create = auth.clone();
+    let on_create = move |_| {
+        if *busy.read() {
+            return;
+        }
+        let label = name.read().trim().to_string();
+        if label.is_empty() {
+            error.set(Some(t.settings_token_validation_name.to_string()));
+            return;
+        }
+        if scopes.read().is_empty() {
+            error.set(Some(t.settings_token_validation_scopes.to_string()));
+            return;
+        }
+        let auth_async = auth_for_create.clone();
+        let client = auth_async.client.clone();
+        let granted = scopes.read().clone();
+        let days = *expires_in_days.read();
+        busy.set(true);
+        error.set(None);
+        success.set(None);
+        spawn(async move {
+            let res = client.create_api_token(label, granted, days).await;
+            if let Err(err) = &res
+                && handle_unauth(err, auth_async, nav, log_bus).await
+            {
+                busy.set(false);
+                return;
+            }
+            push_log_result(log_bus, HttpMethod::Post, "/api/users/me/tokens", &res);
+            busy.set(false);
+            match res {
+                Ok(created) => {
+                    created_token.set(Some(created.token));
+                    name.set(String::new());
+                    *reload.write() += 1;
+                }
+                Err(err) => {
+                    error.set(Some(humanize_error(
+                        &err,
+                        ErrorContext::ApiToken,
+                        i18n.lang(),
+                    )));
+                }
+            }
+        });
+    };
+
+    let auth_for_revoke = auth.clone();
+    let on_revoke_confirm = move |_| {
+        let Some(target) = revoke_target.read().clone() else {
+            return;
+        };
+        let auth_async = auth_for_revoke.clone();
+        let client = auth_async.client.clone();
+        busy.set(true);
+        error.set(None);
+        success.set(None);
+        spawn(async move {
+            let path = format!("/api/users/me/tokens/{}", target.id);
+            let res = client.delete_api_token(target.id).await;
+            revoke_target.set(None);
+            if let Err(err) = &res
+                && handle_unauth(err, auth_async, nav, log_bus).await
+            {
+                busy.set(false);
+                return;
+            }
+            push_log_result(log_bus, HttpMethod::Delete, &path, &res);
+            busy.set(false);
+            match res {
+                Ok(_) => {
+                    success.set(Some(t.settings_token_revoked_msg.to_string()));
+                    *reload.write() += 1;
+                }
+                Err(err) => {
+                    error.set(Some(humanize_error(
+                        &err,
+                        ErrorContext::ApiToken,
+                        i18n.lang(),
+                    )));
+                }
+            }
+        });
+    };
+
+    let is_busy = *busy.read();
+    let confirm_msg = revoke_target
+        .read()
+        .as_ref()
+        .map(|token| tf(t.settings_token_confirm_msg, &[("name", &token.name)]))
+        .unwrap_or_default();
+
+    rsx! {
+        section { class: "ws-settings__section",
+            h2 { class: "ws-settings__section-title", "{t.settings_token_title}" }
+            p { class: "ws-settings__desc", "{t.settings_token_desc}" }
+            if tokens.read().is_empty() {
+                p { class: "ws-settings__desc", "{t.settings_token_empty}" }
+            } else {
+                ul { class: "ws-settings__passkeys",
+                    for token in tokens.read().clone() {
+                        li { key: "{token.id}", class: "ws-settings__passkey",
+                            div { class: "ws-settings__passkey-info",
+                                span { class: "ws-settings__passkey-name", "{token.name}" }
+                                span { class: "ws-settings__passkey-meta",
+                                    {token_meta(&token, t)}
+                                }
+                            }
+                            Button {
+                                button_type: ButtonType::Danger,
+                                disabled: is_busy,
+                                onclick: move |_| revoke_target.set(Some(token.clone())),
+                                "{t.settings_token_revoke_btn}"
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { class: "ws-settings__form",
+                if let Some(secret) = created_token.read().clone() {
+                    // 完整令牌只在此处展示一次
+                    h3 { class: "ws-settings__subsection-title", "{t.settings_token_created_title}" }
+                    p { class: "ws-settings__desc", "{t.settings_token_created_hint}" }
+                    p { class: "ws-settings__token-secret", "{secret}" }
+                    Button {
+                        full_width: true,
+                        onclick: move |_| created_token.set(None),
+                        "{t.settings_token_done}"
+                    }
+                } else {
+                    TextInput {
+                        label: t.settings_token_name_label.to_string(),
+                        placeholder: Some(t.settings_token_name_placeholder.to_string()),
+                        value: name,
+                        disabled: is_busy,
+                        name: Some("token_name".to_string()),
+                    }
+                    div { class: "ws-settings__field",
+                        span { class: "ws-settings__field-label", "{t.settings_token_scopes_label}" }
+                        for scope in TOKEN_SCOPES {
+                            label { key: "{scope}", class: "ws-settings__scope",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: scopes.read().iter().any(|s| s == scope),
+                                    disabled: is_busy,
+                                    onchange: move |e| {
+                                        let mut list = scopes.write();
+                                        list.retain(|s| s != scope);
+                                        if e.checked() {
+                                            list.push(scope.to_string());
+                                        }
+                                    },
+                                }
+                                {scope_description(scope, t)}
+                                code { "{scope}" }
+                            }
+                        }
+                    }
+                    div { class: "ws-settings__field",
+                        span { class: "ws-settings__field-label", "{t.settings_token_expiry_label}" }
+                        select {
+                            class: "ws-settings__select",
+                            disabled: is_busy,
+                            onchange: move |e| expires_in_days.set(e.value().parse().ok()),
+                            for days in TOKEN_EXPIRY_DAYS {
+                                option {
+                                    value: days.map(|d| d.to_string()).unwrap_or_default(),
+                                    selected: days == *expires_in_days.read(),
+                                    {expiry_label(days, t)}
+                                }
+                            }
+                        }
+                    }
+                    Button {
+                        full_width: true,
+                        disabled: is_busy,
+                        loading: is_busy,
+                        onclick: on_create,
+                        "{t.settings_token_create_btn} [POST /api/users/me/tokens]"
+                    }
+                }
+                if let Some(err) = error.read().as_ref() {
+                    p { class: "ws-form-error", "{err}" }
+                }
+                if let Some(msg) = success.read().as_ref() {
+                    p { class: "ws-form-success", "{msg}" }
+                }
+            }
+
+            ConfirmDialog {
+                open: revoke_target.read().is_some(),
+                title: t.settings_token_confirm_title.to_string(),
+                message: confirm_msg,
+                danger: true,
+                loading: is_busy,
+                on_confirm: on_revoke_confirm,
+                on_cancel: move |_| revoke_target.set(None),
+            }
+        }
+    }
+}
+
+fn expiry_label(days: Option<u32>, t: &Translations) -> String {
+    match days {
+        Some(days) => tf(t.settings_token_expiry_days, &[("days", &days.to_string())]),
+        None => t.settings_token_expiry_never.to_string(),
+    }
+}
+
+fn token_meta(token: &ApiTokenInfo, t: &Translations) -> String {
+    let mut parts = vec![
+        format!("{}…", token.prefix),
+        token.scopes.join(", "),
+        tf(
+            t.settings_token_created,
+            &[("date", &format_dt(&token.created_at))],
+        ),
+    ];
+    if let Some(at) = &token.expires_at {
+        parts.push(if *at <= Utc::now() {
+            t.settings_token_expired.to_string()
+        } else {
+            tf(t.settings_token_expires, &[("date", &format_dt(at))])
+        });
+    }
+    match (&token.last_used_at, &token.last_used_ip) {
+        (Some(at), ip) => {
+            parts.push(tf(t.settings_token_last_used, &[("date", &format_dt(at))]));
+            parts.extend(ip.clone());
+        }
+        (None, _) => parts.push(t.settings_token_never_used.to_string()),
+    }
+    parts.join(" · ")
+}
+
+fn format_dt(dt: &DateTime<Utc>) -> String {
+    dt.format("%Y-%m-%d %H:%M").to_string()
+}
+
 fn render_identity(auth: AuthState, t: &Translations) -> Element {
     let snapshot = auth.user.read().clone();
     match snapshot {
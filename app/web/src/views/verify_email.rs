@@ -204,7 +204,7 @@ pub fn VerifyEmail(email: String) -> Element {
                 Err(err) => {
                     let msg = humanize_error(&err, ErrorContext::EmailVerification, i18n.lang());
                     // 若服务端提示冷却未到，强制倒计时为 10s 避免用户立刻再点
-                    if matches!(err, client_api::ClientError::Other(400, _)) {
+                    if err.status() == Some(400) {
                         countdown.set(10);
                     }
                     error_msg.set(Some(msg));
@@ -61,6 +61,22 @@ translate! {
     login_name_length: "Username must be between 6 and 50 characters" => "用户名长度为 6-50 个字符",
     login_password_mismatch: "Passwords do not match" => "两次输入的密码不一致",
     login_captcha_empty: "Captcha code cannot be empty" => "验证码不能为空",
+    login_2fa_title: "Two-Factor Authentication" => "两步验证",
+    login_2fa_hint: "Enter the 6-digit code from your authenticator app, or one of your recovery codes" => "请输入验证器 App 中的 6 位动态码，或一个恢复码",
+    login_2fa_code_label: "Authentication Code" => "动态码",
+    login_2fa_submit: "Verify & Log In" => "验证并登录",
+    login_2fa_back: "← Back to Login" => "← 返回登录",
+    login_2fa_code_empty: "Please enter the code" => "请输入动态码",
+    login_passkey_btn: "Sign In with a Passkey" => "使用通行密钥登录",
+    login_passkey_divider: "or" => "或",
+    passkey_unsupported: "This browser does not support passkeys" => "当前浏览器不支持通行密钥",
+    passkey_cancelled: "The passkey request was cancelled or timed out" => "通行密钥操作已取消或超时",
+    login_oidc_btn: "Sign In with {name}" => "使用 {name} 登录",
+    oidc_error_denied: "Sign-in was cancelled at the provider" => "已在第三方登录页取消登录",
+    oidc_error_email_not_verified: "The provider has not verified this account's email address" => "第三方账号的邮箱尚未验证",
+    oidc_error_email_in_use: "An account with this email already exists, please sign in with your password" => "该邮箱已注册账号，请使用密码登录",
+    oidc_error_signup_disabled: "No account is linked to this identity and new sign-ups are disabled" => "该第三方账号未绑定任何账号，且当前不允许注册",
+    oidc_error_generic: "Third-party sign-in failed, please try again" => "第三方登录失败，请重试",
     auth_captcha_tab: "Captcha" => "验证码",
     auth_captcha_hint: "Send \"验证码\" to our WeChat Official Account, then enter the code you received below" => "发送「验证码」至微信公众号，将收到的验证码填入下方",
     auth_captcha_label: "Captcha Code" => "验证码",
@@ -184,6 +200,75 @@ translate! {
     settings_validation_new_short: "New password must be at least 8 characters" => "新密码至少需要 8 个字符",
     settings_validation_new_mismatch: "New passwords do not match" => "两次输入的新密码不一致",
     settings_validation_new_same_as_current: "New password must differ from current" => "新密码不能与当前密码相同",
+    settings_2fa_title: "Two-Factor Authentication" => "两步验证",
+    settings_2fa_desc: "Require a code from an authenticator app (TOTP) in addition to your password when logging in." => "登录时除密码外，还需输入验证器 App（TOTP）生成的动态码。",
+    settings_2fa_status_enabled: "Enabled" => "已启用",
+    settings_2fa_status_disabled: "Not enabled" => "未启用",
+    settings_2fa_status_label: "Status" => "状态",
+    settings_2fa_recovery_remaining: "{count} recovery codes left" => "剩余 {count} 个恢复码",
+    settings_2fa_enable_btn: "Set Up Two-Factor" => "启用两步验证",
+    settings_2fa_setup_hint: "Add this key to your authenticator app (or open the link on your phone), then enter the 6-digit code it shows." => "将下方密钥添加到验证器 App（或在手机上打开链接），然后输入其显示的 6 位动态码。",
+    settings_2fa_secret_label: "Secret Key" => "密钥",
+    settings_2fa_uri_label: "Open in authenticator app" => "在验证器 App 中打开",
+    settings_2fa_code_label: "Authentication Code" => "动态码",
+    settings_2fa_code_or_recovery_label: "Code or Recovery Code" => "动态码或恢复码",
+    settings_2fa_confirm_btn: "Confirm & Enable" => "确认并启用",
+    settings_2fa_cancel_btn: "Cancel" => "取消",
+    settings_2fa_codes_title: "Recovery Codes" => "恢复码",
+    settings_2fa_codes_hint: "Store these codes somewhere safe. If you lose your device, each one works once. They will not be shown again." => "请妥善保存以下恢复码。丢失设备时每个恢复码可使用一次，此后不会再次显示。",
+    settings_2fa_codes_done: "I Have Saved Them" => "我已保存",
+    settings_2fa_disable_btn: "Disable Two-Factor" => "停用两步验证",
+    settings_2fa_regenerate_btn: "Regenerate Recovery Codes" => "重新生成恢复码",
+    settings_2fa_code_empty: "Please enter the code" => "请输入动态码",
+    settings_2fa_enabled_msg: "Two-factor authentication enabled" => "两步验证已启用",
+    settings_2fa_disabled_msg: "Two-factor authentication disabled" => "两步验证已停用",
+    settings_passkey_title: "Passkeys" => "通行密钥",
+    settings_passkey_desc: "Sign in without a password using your device's fingerprint, face or screen lock, or a security key." => "无需密码，使用设备的指纹、面容、屏幕锁或安全密钥登录。",
+    settings_passkey_empty: "No passkeys yet" => "尚未添加通行密钥",
+    settings_passkey_name_label: "Passkey Name" => "通行密钥名称",
+    settings_passkey_name_placeholder: "e.g. My Laptop" => "例如：我的笔记本",
+    settings_passkey_add_btn: "Add Passkey" => "添加通行密钥",
+    settings_passkey_added_msg: "Passkey added" => "通行密钥已添加",
+    settings_passkey_revoke_btn: "Revoke" => "撤销",
+    settings_passkey_revoked_msg: "Passkey revoked" => "通行密钥已撤销",
+    settings_passkey_created: "Added {date}" => "添加于 {date}",
+    settings_passkey_last_used: "last used {date}" => "最近使用 {date}",
+    settings_passkey_never_used: "never used" => "从未使用",
+    settings_passkey_confirm_title: "Revoke Passkey" => "撤销通行密钥",
+    settings_passkey_confirm_msg: "Revoke \"{name}\"? It can no longer be used to sign in." => "确定撤销「{name}」？撤销后将无法再用它登录。",
+    settings_identity_title: "Linked Accounts" => "第三方账号",
+    settings_identity_desc: "Third-party accounts you can sign in with." => "可用于登录的第三方账号。",
+    settings_identity_empty: "No linked accounts" => "尚未绑定第三方账号",
+    settings_identity_unlink_btn: "Unlink" => "解除绑定",
+    settings_identity_unlinked_msg: "Account unlinked" => "已解除绑定",
+    settings_identity_linked: "Linked {date}" => "绑定于 {date}",
+    settings_identity_last_used: "last used {date}" => "最近使用 {date}",
+    settings_identity_confirm_title: "Unlink Account" => "解除绑定",
+    settings_identity_confirm_msg: "Unlink \"{name}\"? It can no longer be used to sign in." => "确定解除「{name}」的绑定？解除后将无法再用它登录。",
+    settings_token_title: "API Tokens" => "API 令牌",
+    settings_token_desc: "Personal access tokens let scripts call the API as you, limited to the chosen scopes. Changing your password keeps them; signing out everywhere deletes them." => "个人访问令牌可让脚本以你的身份调用 API，仅限所选权限。修改密码不会使其失效；退出所有设备会将其删除。",
+    settings_token_empty: "No API tokens yet" => "尚未创建 API 令牌",
+    settings_token_name_label: "Token Name" => "令牌名称",
+    settings_token_name_placeholder: "e.g. CI deploy" => "例如：CI 部署",
+    settings_token_scopes_label: "Scopes" => "权限范围",
+    settings_token_expiry_label: "Expires" => "有效期",
+    settings_token_expiry_days: "{days} days" => "{days} 天",
+    settings_token_expiry_never: "Never" => "永不过期",
+    settings_token_create_btn: "Create Token" => "创建令牌",
+    settings_token_created_title: "Your New Token" => "新令牌",
+    settings_token_created_hint: "Copy it now; it will not be shown again." => "请立即复制，之后将不再显示。",
+    settings_token_done: "I've copied it" => "我已复制",
+    settings_token_revoke_btn: "Revoke" => "撤销",
+    settings_token_revoked_msg: "Token revoked" => "令牌已撤销",
+    settings_token_created: "Created {date}" => "创建于 {date}",
+    settings_token_expires: "expires {date}" => "{date} 过期",
+    settings_token_expired: "expired" => "已过期",
+    settings_token_last_used: "last used {date}" => "最近使用 {date}",
+    settings_token_never_used: "never used" => "从未使用",
+    settings_token_confirm_title: "Revoke Token" => "撤销令牌",
+    settings_token_confirm_msg: "Revoke \"{name}\"? Anything using it stops working immediately." => "确定撤销「{name}」？使用它的程序将立即失效。",
+    settings_token_validation_name: "Please enter a token name" => "请输入令牌名称",
+    settings_token_validation_scopes: "Select at least one scope" => "请至少选择一项权限",
 
     // forgot_password.rs
     forgot_pw_title: "Forgot Password" => "找回密码",
@@ -234,6 +319,22 @@ translate! {
     verify_email_too_many_attempts: "Too many attempts, click below to resend" => "尝试次数过多，请点击下方按钮重新发送验证码",
     verify_email_back_to_login: "Back to Login" => "返回登录",
 
+    // oauth_consent.rs
+    oauth_consent_title: "Authorize {name}" => "授权 {name}",
+    oauth_consent_subtitle: "{name} wants to access your WebShelf account." => "{name} 请求访问你的 WebShelf 账号。",
+    oauth_consent_scopes_label: "This app will be able to:" => "该应用将可以：",
+    oauth_consent_redirect_hint: "After you decide you will be sent to {host}" => "完成后将跳转到 {host}",
+    oauth_consent_allow: "Allow" => "允许",
+    oauth_consent_deny: "Deny" => "拒绝",
+    oauth_consent_invalid: "This authorization request is invalid or has expired. Go back to the app and try again." => "授权请求无效或已过期，请返回应用重新发起。",
+    oauth_consent_redirecting: "Returning to the app…" => "正在返回应用…",
+    oauth_scope_openid: "Confirm who you are" => "确认你的身份",
+    oauth_scope_profile: "See your name" => "查看你的名字",
+    oauth_scope_email: "See your email address" => "查看你的邮箱地址",
+    oauth_scope_offline_access: "Keep access while you are away" => "在你离开后保持访问",
+    oauth_scope_users_read: "Read your account (and user records, for administrators)" => "读取你的账号信息（管理员还可读取用户资料）",
+    oauth_scope_users_write: "Manage users on your behalf (administrators only)" => "代你管理用户（仅限管理员）",
+
     // not_found.rs
     not_found_page: "The page you visited does not exist." => "你访问的页面不存在。",
     not_found_back_to_dashboard: "Back to Dashboard" => "返回控制中心",
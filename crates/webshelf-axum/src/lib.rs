@@ -3,15 +3,19 @@ pub mod path_params;
 mod request;
 mod route;
 mod runtime;
+mod tls;
+mod ws;
 
 pub use runtime::AxumRuntime;
 
 // 统一 Request/Response 适配
 pub use request::UnifiedRequest;
-pub use route::{UnifiedError, UnifiedResponse, delete, get, post, put, response_to_axum};
+pub use route::{
+    UnifiedError, UnifiedResponse, delete, get, post, put, response_from_axum, response_to_axum,
+};
 // middleware free functions — 直接 re-export axum 原生函数，不二次封装
 pub use axum::middleware::{from_fn, from_fn_with_state};
-pub use middleware::with_rate_limit_layer;
+pub use middleware::{with_middleware, with_rate_limit_layer};
 
 // ── Re-export axum 生态类型 ───────────────────────
 pub use axum::body::{self, Body};
@@ -154,7 +158,13 @@ mod tests {
 
     #[test]
     fn axum_runtime_serve_returns_send_future() {
-        let fut = AxumRuntime::<TestState>::serve(crate::Router::new(), TestState, "0.0.0.0:0");
+        let fut = AxumRuntime::<TestState>::serve(
+            crate::Router::new(),
+            TestState,
+            "0.0.0.0:0",
+            None,
+            Default::default(),
+        );
         fn assert_send<T: Send>(_t: T) {}
         assert_send(fut);
     }
@@ -1,103 +1,138 @@
 //! Adapter-level middleware for webshelf-axum.
 //!
-//! These middleware functions are framework-specific (use axum `Request`, `Next`, `State`).
-//! Framework-agnostic business logic (JWT validation, token version checking) is
-//! delegated to `webshelf_runtime::middleware` and `webshelf_runtime::auth`.
+//! Cross-cutting concerns (request ID, metrics, security headers, JWT auth, admin guard, rate limiting)
+//! are implemented
+//! once as framework-agnostic [`webshelf_runtime::Middleware`]s; the functions here are thin
+//! axum `from_fn` wrappers around them. [`run_middleware`] / [`with_middleware`] bridge any
+//! custom `Middleware` into an axum middleware stack.
+
+use std::sync::Arc;
 
 use axum::{
     Json,
     body::Body,
-    extract::{ConnectInfo, Request, State},
-    http::StatusCode,
+    extract::{Request, State},
+    http::{Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
+use bytes::Bytes;
 use http_body_util::BodyExt;
 use serde_json::json;
-use std::net::SocketAddr;
 
-use webshelf_runtime::{AuthUser, MiddlewareState, RateLimitGuard, validate_jwt};
+use tracing::Instrument;
+use webshelf_runtime::{
+    AdminGuard, AuthGuard, MetricsMiddleware, Middleware, MiddlewareState, RateLimitGuard,
+    RequestIdMiddleware, SecurityHeadersMiddleware,
+};
+
+use crate::{UnifiedRequest, response_from_axum, response_to_axum};
+
+/// Run a framework-agnostic middleware inside an axum middleware stack.
+///
+/// The request body is buffered into a [`UnifiedRequest`] only when
+/// [`Middleware::needs_body`] says so (and never for GET/HEAD, as in `FromRequest`); otherwise
+/// the original body streams downstream untouched. When the middleware calls `next.run(req)`
+/// the request is rebuilt from the (possibly modified) parts, so data injected via `set_data`
+/// reaches downstream handlers.
+pub async fn run_middleware<M: Middleware>(
+    middleware: &M,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = request.into_parts();
+    // 未读取的 body 原样交给下游，避免每层中间件重复缓冲
+    let (bytes, unread) = if !middleware.needs_body()
+        || parts.method == Method::GET
+        || parts.method == Method::HEAD
+    {
+        (Bytes::new(), Some(body))
+    } else {
+        match body.collect().await {
+            Ok(collected) => (collected.to_bytes(), None),
+            Err(e) => return crate::request::body_read_rejection(&e).into_response(),
+        }
+    };
+
+    let unified_next = webshelf_runtime::Next::new(move |req: UnifiedRequest| async move {
+        let (parts, body) = req.into_parts();
+        let body = unread.unwrap_or(body);
+        let response = next.run(Request::from_parts(parts, body)).await;
+        response_from_axum(response).await
+    });
+
+    let response = middleware
+        .handle(UnifiedRequest::new(parts, bytes), unified_next)
+        .await;
+    response_to_axum(response)
+}
+
+/// `from_fn_with_state` adapter for [`run_middleware`] (state is the shared middleware).
+pub async fn unified_middleware<M: Middleware>(
+    State(middleware): State<Arc<M>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    run_middleware(middleware.as_ref(), request, next).await
+}
+
+/// Apply a framework-agnostic middleware to a router (generic over router state type `S`).
+///
+/// Salvo equivalent: `webshelf_salvo::with_middleware_hoop`.
+pub fn with_middleware<S, M>(route: axum::Router<S>, middleware: M) -> axum::Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    M: Middleware,
+{
+    route.layer(axum::middleware::from_fn_with_state(
+        Arc::new(middleware),
+        unified_middleware::<M>,
+    ))
+}
 
 /// Authentication middleware — validates JWT from `Authorization` header or `webshelf_jwt` cookie.
 /// Generic over `S: MiddlewareState` to avoid circular dependency on `AppState`.
 /// Skips authentication for `/health` (which is inside a `/api` nest, so path is `/health`).
+///
+/// Delegates to [`AuthGuard`]; the router state is injected into request extensions
+/// so the guard can read it through `RequestContext::get_data`.
 pub async fn auth_middleware<S: MiddlewareState + 'static>(
     State(state): State<S>,
     mut request: Request,
     next: Next,
 ) -> Response {
-    let path = request.uri().path();
-
-    // Skip authentication for public health endpoint.
-    // Check both with and without the /api prefix to make the middleware
-    // position-independent: when layered inside the /api nest, axum
-    // strips the prefix so path is /health; when layered outside, the
-    // prefix is preserved so path is /api/health.
-    if path == "/health" || path == "/api/health" {
-        return next.run(request).await;
-    }
-
-    let jwt_secret = state.jwt_secret();
-
-    // Extract token from Authorization header or webshelf_jwt cookie
-    let token = match extract_bearer_token(&request) {
-        Some(token) => token,
-        None => match extract_jwt_cookie(&request) {
-            Some(token) => token,
-            None => return unauthorized_response("Missing or invalid Authorization header"),
-        },
-    };
-
-    // Validate token
-    match validate_jwt(&token, jwt_secret) {
-        Ok(claims) => {
-            let user_id: i64 = match claims.sub.parse() {
-                Ok(id) => id,
-                Err(_) => {
-                    tracing::warn!("Invalid user ID format in token: {}", claims.sub);
-                    return unauthorized_response("Invalid or expired token");
-                }
-            };
-
-            match state
-                .check_token_version(user_id, claims.token_version)
-                .await
-            {
-                Ok(()) => {
-                    let auth_user = AuthUser::from(claims);
-                    request.extensions_mut().insert(auth_user);
-                    next.run(request).await
-                }
-                Err(e) => {
-                    tracing::warn!("Token version validation failed: {}", e);
-                    unauthorized_response("Invalid or expired token")
-                }
-            }
-        }
-        Err(e) => {
-            tracing::warn!("Token validation failed: {}", e);
-            unauthorized_response("Invalid or expired token")
-        }
-    }
+    request.extensions_mut().insert(state);
+    run_middleware(&AuthGuard::<S>::new(), request, next).await
 }
 
 /// Require admin role middleware — returns 403 if the authenticated user is not an admin or system.
 pub async fn require_admin(request: Request, next: Next) -> Response {
-    let auth_user = match request.extensions().get::<AuthUser>() {
-        Some(user) => user,
-        None => return unauthorized_response("Authentication required"),
-    };
+    run_middleware(&AdminGuard, request, next).await
+}
 
-    if auth_user.role != "admin" && auth_user.role != "system" {
-        return forbidden_response("Admin privileges required");
-    }
+/// Request ID + correlated tracing span (see [`RequestIdMiddleware`]).
+pub async fn request_id_middleware(request: Request, next: Next) -> Response {
+    run_middleware(&RequestIdMiddleware, request, next).await
+}
 
-    next.run(request).await
+/// HTTP request count / latency metrics (see [`MetricsMiddleware`]).
+pub async fn metrics_middleware(request: Request, next: Next) -> Response {
+    run_middleware(&MetricsMiddleware, request, next).await
+}
+
+/// Security response headers (see [`SecurityHeadersMiddleware`]); use with `from_fn_with_state`.
+pub async fn security_headers_middleware(
+    State(headers): State<SecurityHeadersMiddleware>,
+    request: Request,
+    next: Next,
+) -> Response {
+    run_middleware(&headers, request, next).await
 }
 
 /// Axum middleware that catches panics and returns 500 Internal Server Error.
 pub async fn panic_middleware(request: Request, next: Next) -> Response {
-    let response = tokio::spawn(async move { next.run(request).await }).await;
+    // The spawned task keeps the caller's span so handler logs stay correlated.
+    let response = tokio::spawn(async move { next.run(request).await }.in_current_span()).await;
 
     match response {
         Ok(resp) => resp,
@@ -138,183 +173,13 @@ where
     ))
 }
 
-/// Generic rate‑limiting middleware for auth endpoints.
+/// Generic rate‑limiting middleware for auth endpoints (delegates to [`RateLimitGuard`]).
 pub async fn rate_limit_middleware(
     State(guard): State<RateLimitGuard>,
     request: Request<Body>,
     next: Next,
 ) -> Response {
-    if !guard.limiter.is_available() {
-        return next.run(request).await;
-    }
-
-    // 1. IP‑based check
-    let ip = extract_client_ip(request.headers()).or_else(|| extract_peer_ip(request.extensions()));
-
-    if let Some(ip) = ip {
-        let ip_key = format!("{}:ip:{}", guard.key_prefix, ip);
-        match guard
-            .limiter
-            .check(&ip_key, guard.ip_max_requests, guard.ip_window_seconds)
-            .await
-        {
-            Ok(true) => {}
-            Ok(false) => {
-                tracing::warn!("Rate limit exceeded (IP) for {}: {}", guard.key_prefix, ip);
-                return rate_limited_response();
-            }
-            Err(e) => {
-                tracing::error!(
-                    "Rate‑limit Redis error (IP) for {}: {:?}",
-                    guard.key_prefix,
-                    e
-                );
-                if !guard.limiter.fail_open() {
-                    return internal_error_response("An unexpected error occurred");
-                }
-            }
-        }
-    }
-
-    // 2. Email‑based check
-    if let Some(email_max) = guard.email_max_requests {
-        let (parts, body) = request.into_parts();
-        let bytes = match body.collect().await {
-            Ok(c) => c.to_bytes(),
-            Err(e) => {
-                tracing::error!(
-                    "Failed to read body for rate limiting ({}): {:?}",
-                    guard.key_prefix,
-                    e
-                );
-                return internal_error_response("An unexpected error occurred");
-            }
-        };
-
-        if let Some(email) = extract_email_from_body(&bytes) {
-            let email_key = format!("{}:email:{}", guard.key_prefix, email);
-            match guard
-                .limiter
-                .check(&email_key, email_max, guard.email_window_seconds)
-                .await
-            {
-                Ok(true) => {}
-                Ok(false) => {
-                    tracing::warn!(
-                        "Rate limit exceeded (email) for {}: {}",
-                        guard.key_prefix,
-                        email
-                    );
-                    return rate_limited_response();
-                }
-                Err(e) => {
-                    tracing::error!(
-                        "Rate‑limit Redis error (email) for {}: {:?}",
-                        guard.key_prefix,
-                        e
-                    );
-                    if !guard.limiter.fail_open() {
-                        return internal_error_response("An unexpected error occurred");
-                    }
-                }
-            }
-        }
-
-        let body = Body::from(bytes);
-        let request = Request::from_parts(parts, body);
-        next.run(request).await
-    } else {
-        next.run(request).await
-    }
-}
-
-fn extract_bearer_token(request: &Request) -> Option<String> {
-    let auth_header = request.headers().get(http::header::AUTHORIZATION)?;
-    let auth_value = auth_header.to_str().ok()?;
-
-    const BEARER_PREFIX: &[u8] = b"bearer ";
-    if auth_value.len() <= BEARER_PREFIX.len() {
-        return None;
-    }
-    if !auth_value.as_bytes()[..BEARER_PREFIX.len()].eq_ignore_ascii_case(BEARER_PREFIX) {
-        return None;
-    }
-    Some(auth_value[BEARER_PREFIX.len()..].to_string())
-}
-
-fn extract_jwt_cookie(request: &Request) -> Option<String> {
-    let cookie_header = request.headers().get(http::header::COOKIE)?;
-    let cookie_str = cookie_header.to_str().ok()?;
-
-    cookie_str
-        .split(';')
-        .map(str::trim)
-        .filter_map(|s| cookie::Cookie::parse(s).ok())
-        .find(|c| c.name() == "webshelf_jwt")
-        .map(|c| c.value().to_string())
-}
-
-fn extract_client_ip(headers: &axum::http::HeaderMap) -> Option<String> {
-    if let Some(value) = headers.get("x-forwarded-for")
-        && let Ok(value) = value.to_str()
-        && let Some(ip) = value.split(',').next()
-    {
-        let ip = ip.trim();
-        if !ip.is_empty() {
-            return Some(ip.to_string());
-        }
-    }
-
-    if let Some(value) = headers.get("x-real-ip")
-        && let Ok(value) = value.to_str()
-    {
-        let ip = value.trim();
-        if !ip.is_empty() {
-            return Some(ip.to_string());
-        }
-    }
-
-    None
-}
-
-fn extract_peer_ip(extensions: &axum::http::Extensions) -> Option<String> {
-    extensions
-        .get::<ConnectInfo<SocketAddr>>()
-        .map(|ConnectInfo(addr)| addr.ip().to_string())
-}
-
-fn extract_email_from_body(bytes: &[u8]) -> Option<String> {
-    // Try JSON first
-    if let Ok(val) = serde_json::from_slice::<serde_json::Value>(bytes)
-        && let Some(email) = val.get("email")?.as_str()
-    {
-        return Some(email.to_lowercase());
-    }
-    // Fallback to form-encoded (login endpoint accepts both content types)
-    serde_urlencoded::from_bytes::<std::collections::HashMap<String, String>>(bytes)
-        .ok()?
-        .remove("email")
-        .map(|e| e.to_lowercase())
-}
-
-fn unauthorized_response(message: &str) -> Response {
-    (
-        StatusCode::UNAUTHORIZED,
-        Json(json!({"error": "unauthorized", "message": message})),
-    )
-        .into_response()
-}
-
-fn forbidden_response(message: &str) -> Response {
-    (
-        StatusCode::FORBIDDEN,
-        Json(json!({"error": "forbidden", "message": message})),
-    )
-        .into_response()
-}
-
-fn rate_limited_response() -> Response {
-    (StatusCode::TOO_MANY_REQUESTS, Json(json!({"error": "rate_limited", "message": "Too many requests. Please try again later."}))).into_response()
+    run_middleware(&guard, request, next).await
 }
 
 fn internal_error_response(message: &str) -> Response {
@@ -328,95 +193,124 @@ fn internal_error_response(message: &str) -> Response {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::body::Body;
-    use axum::http::Request;
-
-    // ── extract_bearer_token tests ───────────────────────────
-
-    #[test]
-    fn extract_bearer_token_success() {
-        let req = Request::builder()
-            .header("authorization", "Bearer my-token")
-            .body(Body::empty())
-            .unwrap();
-        assert_eq!(extract_bearer_token(&req), Some("my-token".to_string()));
+    use tower::ServiceExt;
+    use webshelf_runtime::{HttpError, Next as UnifiedNext, RequestContext};
+
+    /// Injects a marker value and stamps a header on the downstream response.
+    struct Marker;
+
+    #[async_trait::async_trait]
+    impl Middleware for Marker {
+        async fn handle<R: RequestContext + 'static>(
+            &self,
+            mut req: R,
+            next: UnifiedNext<'_, R>,
+        ) -> webshelf_runtime::Response {
+            if req.header("x-block").is_some() {
+                return HttpError::forbidden("blocked").into();
+            }
+            req.set_data(String::from("from-middleware"));
+            let mut resp = next.run(req).await;
+            resp.insert_header("x-marker", "1");
+            resp
+        }
     }
 
-    #[test]
-    fn extract_bearer_token_lowercase_bearer() {
-        let req = Request::builder()
-            .header("authorization", "bearer my-token")
-            .body(Body::empty())
-            .unwrap();
-        assert_eq!(extract_bearer_token(&req), Some("my-token".to_string()));
+    /// Declares it does not read the body; reports what it would have seen.
+    struct Peek;
+
+    #[async_trait::async_trait]
+    impl Middleware for Peek {
+        async fn handle<R: RequestContext + 'static>(
+            &self,
+            mut req: R,
+            next: UnifiedNext<'_, R>,
+        ) -> webshelf_runtime::Response {
+            let seen = req.read_body_bytes().await.unwrap_or_default().len();
+            let mut resp = next.run(req).await;
+            resp.insert_header("x-seen", seen);
+            resp
+        }
+
+        fn needs_body(&self) -> bool {
+            false
+        }
     }
 
-    #[test]
-    fn extract_bearer_token_mixed_case() {
-        let req = Request::builder()
-            .header("authorization", "BEARER token-value")
-            .body(Body::empty())
-            .unwrap();
-        assert_eq!(extract_bearer_token(&req), Some("token-value".to_string()));
+    async fn echo(mut req: UnifiedRequest) -> Result<webshelf_runtime::Response, HttpError> {
+        let marker: String = req.get_data().unwrap_or_default();
+        let body = req.read_body_bytes().await.map_err(HttpError::internal)?;
+        let mut resp = webshelf_runtime::Response::new();
+        resp.set_text_body(format!("{marker}|{}", String::from_utf8_lossy(&body)));
+        Ok(resp)
     }
 
-    #[test]
-    fn extract_bearer_token_missing_header() {
-        let req = Request::builder().body(Body::empty()).unwrap();
-        assert!(extract_bearer_token(&req).is_none());
+    fn app() -> axum::Router {
+        with_middleware(
+            axum::Router::new().route("/echo", crate::post(echo)),
+            Marker,
+        )
     }
 
-    #[test]
-    fn extract_bearer_token_wrong_scheme() {
-        let req = Request::builder()
-            .header("authorization", "Basic dXNlcjpwYXNz")
-            .body(Body::empty())
-            .unwrap();
-        assert!(extract_bearer_token(&req).is_none());
+    async fn body_string(resp: Response) -> String {
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
     }
 
-    #[test]
-    fn extract_bearer_token_empty_value() {
-        // "Bearer " is exactly BEARER_PREFIX.len() -> returns None
+    #[tokio::test]
+    async fn bridge_passes_data_and_body_downstream() {
         let req = Request::builder()
-            .header("authorization", "Bearer ")
-            .body(Body::empty())
+            .method("POST")
+            .uri("/echo")
+            .body(Body::from("payload"))
             .unwrap();
-        assert!(extract_bearer_token(&req).is_none());
+        let resp = app().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("x-marker").unwrap(), "1");
+        assert_eq!(body_string(resp).await, "from-middleware|payload");
     }
 
-    // ── extract_jwt_cookie tests ─────────────────────────────
-
-    #[test]
-    fn extract_jwt_cookie_success() {
+    #[tokio::test]
+    async fn bridge_short_circuits_without_calling_handler() {
         let req = Request::builder()
-            .header("cookie", "webshelf_jwt=my-jwt-token; other=cookie")
+            .method("POST")
+            .uri("/echo")
+            .header("x-block", "1")
             .body(Body::empty())
             .unwrap();
-        assert_eq!(extract_jwt_cookie(&req), Some("my-jwt-token".to_string()));
+        let resp = app().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(resp.headers().get("x-marker").is_none());
     }
 
-    #[test]
-    fn extract_jwt_cookie_multiple_cookies() {
+    #[tokio::test]
+    async fn bridge_streams_body_past_middleware_that_does_not_need_it() {
+        let app = with_middleware(axum::Router::new().route("/echo", crate::post(echo)), Peek);
+        let stream = futures_util::stream::iter(vec![
+            Ok::<_, std::io::Error>(Bytes::from("pay")),
+            Ok(Bytes::from("load")),
+        ]);
         let req = Request::builder()
-            .header("cookie", "session=abc; webshelf_jwt=found-token; lang=en")
-            .body(Body::empty())
+            .method("POST")
+            .uri("/echo")
+            .body(Body::from_stream(stream))
             .unwrap();
-        assert_eq!(extract_jwt_cookie(&req), Some("found-token".to_string()));
-    }
-
-    #[test]
-    fn extract_jwt_cookie_missing_header() {
-        let req = Request::builder().body(Body::empty()).unwrap();
-        assert!(extract_jwt_cookie(&req).is_none());
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.headers().get("x-seen").unwrap(), "0");
+        assert_eq!(body_string(resp).await, "|payload");
     }
 
-    #[test]
-    fn extract_jwt_cookie_no_jwt_cookie() {
+    #[tokio::test]
+    async fn require_admin_without_auth_user_is_unauthorized() {
+        let app = axum::Router::new()
+            .route("/admin", crate::post(echo))
+            .route_layer(axum::middleware::from_fn(require_admin));
         let req = Request::builder()
-            .header("cookie", "session=abc")
+            .method("POST")
+            .uri("/admin")
             .body(Body::empty())
             .unwrap();
-        assert!(extract_jwt_cookie(&req).is_none());
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
     }
 }
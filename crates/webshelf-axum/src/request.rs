@@ -1,26 +1,64 @@
 use axum::{
     Json,
-    extract::{FromRequest, MatchedPath, Request},
+    body::Body,
+    extract::{
+        ConnectInfo, FromRequest, FromRequestParts, MatchedPath, Request, ws::WebSocketUpgrade,
+    },
     http::request::Parts,
     http::{Method, StatusCode},
 };
 use bytes::Bytes;
+use futures_util::TryStreamExt;
 use http_body_util::BodyExt;
 use serde::de::DeserializeOwned;
 use serde_json::json;
+use std::future::Future;
 use std::net::IpAddr;
+use std::sync::{Mutex, PoisonError};
 
-use webshelf_runtime::RequestContext;
+use webshelf_runtime::multipart::is_multipart;
+use webshelf_runtime::{
+    BodyError, HttpError, RequestBodyStream, RequestContext, Response, TrustedProxies, WebSocket,
+};
+
+use crate::response_from_axum;
 
-/// Unified request (Axum) — body is eagerly buffered in `FromRequest`.
+/// Unified request (Axum) — body is eagerly buffered in `FromRequest`, except for
+/// `multipart/form-data` uploads, which stay unread until parsed.
 pub struct UnifiedRequest {
     parts: Parts,
     cached_body: Bytes,
+    // `Body` 不是 Sync；Mutex 仅用于满足 RequestContext 的 Sync 约束，不会被并发访问
+    unread: Option<Mutex<Body>>,
 }
 
 impl UnifiedRequest {
     pub fn new(parts: Parts, cached_body: Bytes) -> Self {
-        Self { parts, cached_body }
+        Self {
+            parts,
+            cached_body,
+            unread: None,
+        }
+    }
+
+    /// Consume the request, returning the (possibly modified) parts and the body.
+    ///
+    /// Used by the middleware bridge to rebuild a native axum `Request` for the downstream chain.
+    pub fn into_parts(self) -> (Parts, Body) {
+        let body = match self.unread {
+            Some(body) => body.into_inner().unwrap_or_else(PoisonError::into_inner),
+            None => Body::from(self.cached_body),
+        };
+        (self.parts, body)
+    }
+
+    /// Buffer a still-unread body on first use (a multipart request read as bytes).
+    async fn buffered(&mut self) -> Result<&Bytes, String> {
+        if let Some(body) = self.unread.take() {
+            let body = body.into_inner().unwrap_or_else(PoisonError::into_inner);
+            self.cached_body = body.collect().await.map_err(|e| e.to_string())?.to_bytes();
+        }
+        Ok(&self.cached_body)
     }
 }
 
@@ -34,28 +72,66 @@ where
     async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
         let (mut parts, body) = req.into_parts();
         parts.extensions.insert(state.clone());
+        // Nested routes only expose their full pattern here, not to outer layers.
+        if let Some(matched) = parts.extensions.get::<MatchedPath>() {
+            webshelf_runtime::request_id::record_route(parts.method.as_str(), matched.as_str());
+        }
 
         // GET/HEAD 请求通常无 body，跳过 eager buffering 以节省不必要的 I/O。
         // 若有意外附带 body，其内容将被忽略（与 HTTP 语义一致）。
-        let bytes = if parts.method == Method::GET || parts.method == Method::HEAD {
-            Bytes::new()
-        } else {
-            body.collect()
-                .await
-                .map_err(|e| {
-                    (
-                        StatusCode::BAD_REQUEST,
-                        Json(json!({"error": "bad_request", "message": e.to_string()})),
-                    )
-                })?
-                .to_bytes()
-        };
+        if parts.method == Method::GET || parts.method == Method::HEAD {
+            return Ok(Self::new(parts, Bytes::new()));
+        }
+        // 文件上传不预读，由 parse_multipart 按需流式解析
+        let multipart = parts
+            .headers
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(is_multipart);
+        if multipart {
+            return Ok(Self {
+                parts,
+                cached_body: Bytes::new(),
+                unread: Some(Mutex::new(body)),
+            });
+        }
 
-        Ok(Self {
-            parts,
-            cached_body: bytes,
-        })
+        let bytes = body
+            .collect()
+            .await
+            .map_err(|e| body_read_rejection(&e))?
+            .to_bytes();
+        Ok(Self::new(parts, bytes))
+    }
+}
+
+/// Map a body read error to a JSON rejection.
+///
+/// `RequestBodyLimitLayer` only rejects oversized `Content-Length` up front; chunked bodies
+/// fail mid-read with `LengthLimitError`, which must still surface as 413 rather than 400.
+pub(crate) fn body_read_rejection(err: &axum::Error) -> (StatusCode, Json<serde_json::Value>) {
+    if let Some(e) = length_limit(err) {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({"error": "payload_too_large", "message": e.to_string()})),
+        );
+    }
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({"error": "bad_request", "message": err.to_string()})),
+    )
+}
+
+/// The `LengthLimitError` in `err`'s source chain, if the body hit the size limit.
+fn length_limit(err: &axum::Error) -> Option<&(dyn std::error::Error + 'static)> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+    while let Some(e) = source {
+        if e.is::<http_body_util::LengthLimitError>() {
+            return Some(e);
+        }
+        source = e.source();
     }
+    None
 }
 
 impl RequestContext for UnifiedRequest {
@@ -68,27 +144,23 @@ impl RequestContext for UnifiedRequest {
     }
 
     fn client_ip(&self) -> Option<IpAddr> {
-        // Check X-Forwarded-For header first (proxy / load balancer support),
-        // then X-Real-IP, then fall back to peer IP from ConnectInfo extension.
-        if let Some(ip) = self
-            .header("x-forwarded-for")
-            .and_then(|v| v.split(',').next().map(|s| s.trim()))
-            .and_then(|s| s.parse::<IpAddr>().ok())
-        {
-            return Some(ip);
-        }
-
-        if let Some(ip) = self
-            .header("x-real-ip")
-            .and_then(|v| v.trim().parse::<IpAddr>().ok())
-        {
-            return Some(ip);
-        }
-
-        self.parts
+        // Peer IP from ConnectInfo; forwarding headers only count when the peer is a
+        // trusted proxy (`TrustedProxies` injected as an extension by the server).
+        let peer = self
+            .parts
             .extensions
-            .get::<std::net::SocketAddr>()
-            .map(|addr| addr.ip())
+            .get::<ConnectInfo<std::net::SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .or_else(|| {
+                self.parts
+                    .extensions
+                    .get::<std::net::SocketAddr>()
+                    .map(|addr| addr.ip())
+            });
+        match self.parts.extensions.get::<TrustedProxies>() {
+            Some(trusted) => trusted.client_ip(peer, &self.parts.headers),
+            None => peer,
+        }
     }
 
     fn header(&self, name: &str) -> Option<&str> {
@@ -148,104 +220,164 @@ impl RequestContext for UnifiedRequest {
     }
 
     async fn parse_json<T: DeserializeOwned>(&mut self) -> Result<T, String> {
-        serde_json::from_slice(&self.cached_body).map_err(|e| e.to_string())
+        serde_json::from_slice(self.buffered().await?).map_err(|e| e.to_string())
     }
 
     async fn read_body_bytes(&mut self) -> Result<Bytes, String> {
-        Ok(self.cached_body.clone())
+        self.buffered().await.cloned()
     }
 
     async fn parse_form<T: DeserializeOwned>(&mut self) -> Result<T, String> {
-        serde_urlencoded::from_bytes(&self.cached_body).map_err(|e| e.to_string())
+        serde_urlencoded::from_bytes(self.buffered().await?).map_err(|e| e.to_string())
+    }
+
+    fn take_body_stream(&mut self) -> Option<RequestBodyStream> {
+        let body = self.unread.take()?;
+        let body = body.into_inner().unwrap_or_else(PoisonError::into_inner);
+        // BodyExt 也有 map_err（作用于 Body），这里需要 Stream 版本
+        let stream = TryStreamExt::map_err(body.into_data_stream(), |e| {
+            if length_limit(&e).is_some() {
+                BodyError::TooLarge
+            } else {
+                BodyError::Read(e.to_string())
+            }
+        });
+        Some(Box::pin(stream))
+    }
+
+    async fn upgrade_websocket<F, Fut>(&mut self, on_upgrade: F) -> Result<Response, HttpError>
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        webshelf_runtime::ws::check_origin(self)?;
+        // hyper 的 OnUpgrade 位于 request extensions 中（经过中间件桥接重建后仍保留）
+        let upgrade = WebSocketUpgrade::from_request_parts(&mut self.parts, &())
+            .await
+            .map_err(|rejection| {
+                let mut err = HttpError::bad_request(rejection.body_text());
+                err.status = rejection.status();
+                err
+            })?;
+        let resp = upgrade.on_upgrade(move |socket| on_upgrade(crate::ws::into_unified(socket)));
+        Ok(response_from_axum(resp).await)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::body::Body;
     use axum::http::Request;
+    use futures_util::StreamExt;
     use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
-    async fn make_unified_request(header_name: &str, header_value: &str) -> UnifiedRequest {
-        let req = Request::builder()
-            .header(header_name, header_value)
-            .body(Body::empty())
-            .unwrap();
-        let (parts, body) = req.into_parts();
-        let bytes = body.collect().await.unwrap().to_bytes();
-        UnifiedRequest::new(parts, bytes)
+    /// Request received from `peer` with the given headers; `trusted` mirrors `server.trusted_proxies`.
+    fn unified_request(
+        peer: Option<[u8; 4]>,
+        trusted: Option<&[&str]>,
+        headers: &[(&str, &str)],
+    ) -> UnifiedRequest {
+        let mut builder = Request::builder();
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let mut req = builder.body(Body::empty()).unwrap();
+        if let Some(peer) = peer {
+            req.extensions_mut()
+                .insert(ConnectInfo(std::net::SocketAddr::from((peer, 4000))));
+        }
+        if let Some(trusted) = trusted {
+            req.extensions_mut()
+                .insert(TrustedProxies::parse(trusted).unwrap());
+        }
+        let (parts, _) = req.into_parts();
+        UnifiedRequest::new(parts, Bytes::new())
     }
 
     #[tokio::test]
-    async fn client_ip_from_x_forwarded_for() {
-        let req = make_unified_request("x-forwarded-for", "203.0.113.1").await;
+    async fn client_ip_ignores_x_forwarded_for_without_trusted_proxies() {
+        let req = unified_request(
+            Some([192, 0, 2, 7]),
+            None,
+            &[("x-forwarded-for", "203.0.113.1")],
+        );
         assert_eq!(
             req.client_ip(),
-            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1)))
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 7)))
         );
     }
 
     #[tokio::test]
-    async fn client_ip_from_x_forwarded_for_multiple() {
-        // X-Forwarded-For may contain a chain of proxies; we take the leftmost.
-        let req = make_unified_request("x-forwarded-for", "198.51.100.1, 10.0.0.1").await;
+    async fn client_ip_ignores_x_forwarded_for_from_untrusted_peer() {
+        let req = unified_request(
+            Some([192, 0, 2, 7]),
+            Some(&["10.0.0.0/8"]),
+            &[("x-forwarded-for", "203.0.113.1")],
+        );
         assert_eq!(
             req.client_ip(),
-            Some(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 1)))
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 7)))
         );
     }
 
     #[tokio::test]
-    async fn client_ip_from_x_real_ip() {
-        let req = make_unified_request("x-real-ip", "192.168.1.42").await;
+    async fn client_ip_from_x_forwarded_for_via_trusted_proxy() {
+        // Rightmost untrusted hop wins; the spoofed leftmost entry is ignored.
+        let req = unified_request(
+            Some([10, 0, 0, 3]),
+            Some(&["10.0.0.0/8"]),
+            &[("x-forwarded-for", "6.6.6.6, 198.51.100.1, 10.0.0.1")],
+        );
         assert_eq!(
             req.client_ip(),
-            Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 42)))
+            Some(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 1)))
         );
     }
 
     #[tokio::test]
-    async fn client_ip_x_forwarded_for_takes_priority() {
-        // When both headers are present, X-Forwarded-For wins.
-        let req = Request::builder()
-            .header("x-forwarded-for", "10.0.0.1")
-            .header("x-real-ip", "192.168.1.100")
-            .body(Body::empty())
-            .unwrap();
-        let (parts, body) = req.into_parts();
-        let bytes = body.collect().await.unwrap().to_bytes();
-        let unified = UnifiedRequest::new(parts, bytes);
+    async fn client_ip_from_x_real_ip_via_trusted_proxy() {
+        let req = unified_request(
+            Some([127, 0, 0, 1]),
+            Some(&["127.0.0.1"]),
+            &[("x-real-ip", "192.168.1.42")],
+        );
         assert_eq!(
-            unified.client_ip(),
-            Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)))
+            req.client_ip(),
+            Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 42)))
         );
     }
 
     #[tokio::test]
-    async fn client_ip_x_forwarded_for_ipv6() {
-        let req = make_unified_request("x-forwarded-for", "::1").await;
+    async fn client_ip_from_forwarded_ipv6_via_trusted_proxy() {
+        let req = unified_request(
+            Some([127, 0, 0, 1]),
+            Some(&["127.0.0.1"]),
+            &[("forwarded", r#"for="[2001:db8::1]:4711""#)],
+        );
         assert_eq!(
             req.client_ip(),
-            Some(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
+            Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)))
         );
     }
 
     #[tokio::test]
-    async fn client_ip_no_headers_returns_none() {
-        // Without ConnectInfo in extensions and without proxy headers, client_ip is None.
-        let req = Request::builder().body(Body::empty()).unwrap();
-        let (parts, body) = req.into_parts();
-        let bytes = body.collect().await.unwrap().to_bytes();
-        let unified = UnifiedRequest::new(parts, bytes);
-        assert!(unified.client_ip().is_none());
+    async fn client_ip_no_peer_returns_none() {
+        // Without ConnectInfo the peer is unknown, so proxy headers cannot be verified.
+        let req = unified_request(
+            None,
+            Some(&["0.0.0.0/0"]),
+            &[("x-forwarded-for", "203.0.113.1")],
+        );
+        assert!(req.client_ip().is_none());
     }
 
     #[tokio::test]
-    async fn client_ip_invalid_x_forwarded_for_ignored() {
-        // Invalid IP in X-Forwarded-For should be ignored (falls through to None).
-        let req = make_unified_request("x-forwarded-for", "not-an-ip").await;
-        assert!(req.client_ip().is_none());
+    async fn client_ip_falls_back_to_connect_info() {
+        let req = unified_request(Some([192, 0, 2, 7]), Some(&["10.0.0.0/8"]), &[]);
+        assert_eq!(
+            req.client_ip(),
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 7)))
+        );
     }
 
     // ── set_data contract tests ─────────────────────────────────
@@ -307,4 +439,121 @@ mod tests {
             "get_data should retrieve the stored value"
         );
     }
+
+    // ── multipart / body limit ──────────────────────────────────
+
+    const MULTIPART_CT: &str = "multipart/form-data; boundary=XB";
+    const MULTIPART_BODY: &str = "--XB\r\n\
+Content-Disposition: form-data; name=\"note\"\r\n\r\n\
+hi\r\n\
+--XB\r\n\
+Content-Disposition: form-data; name=\"file\"; filename=\"a.csv\"\r\n\
+Content-Type: text/csv\r\n\r\n\
+a,b\n1,2\r\n\
+--XB--\r\n";
+
+    /// Lists each field as `name:filename:len`.
+    async fn upload(
+        mut req: UnifiedRequest,
+    ) -> Result<webshelf_runtime::Response, webshelf_runtime::HttpError> {
+        let limits = webshelf_runtime::MultipartLimits::default().field("file", 64);
+        let mut form = req.parse_multipart(limits).await?;
+        let mut out = Vec::new();
+        while let Some(field) = form.next_field().await? {
+            let name = field.name().unwrap_or_default().to_owned();
+            let file_name = field.file_name().unwrap_or_default().to_owned();
+            let len = field.bytes().await?.len();
+            out.push(format!("{name}:{file_name}:{len}"));
+        }
+        let mut resp = webshelf_runtime::Response::new();
+        resp.set_text_body(out.join(","));
+        Ok(resp)
+    }
+
+    fn upload_app(limit: usize) -> axum::Router {
+        axum::Router::new()
+            .route("/upload", crate::post(upload))
+            .layer(tower_http::limit::RequestBodyLimitLayer::new(limit))
+    }
+
+    async fn send(app: axum::Router, body: Body) -> (StatusCode, String) {
+        use tower::ServiceExt;
+        let req = Request::builder()
+            .method("POST")
+            .uri("/upload")
+            .header("content-type", MULTIPART_CT)
+            .body(body)
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        let status = resp.status();
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn parse_multipart_reads_fields_and_files() {
+        let (status, body) = send(upload_app(1024), Body::from(MULTIPART_BODY)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "note::2,file:a.csv:7");
+    }
+
+    #[tokio::test]
+    async fn parse_multipart_field_limit_is_413() {
+        let big = MULTIPART_BODY.replace("a,b\n1,2", &"x".repeat(100));
+        let (status, _) = send(upload_app(4096), Body::from(big)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn parse_multipart_streams_parts_before_the_body_ends() {
+        /// Returns the first field without waiting for the rest of the upload.
+        async fn first_field(
+            mut req: UnifiedRequest,
+        ) -> Result<webshelf_runtime::Response, webshelf_runtime::HttpError> {
+            let mut form = req.parse_multipart(Default::default()).await?;
+            let field = form.next_field().await?.unwrap_or_else(|| unreachable!());
+            let mut resp = webshelf_runtime::Response::new();
+            resp.set_text_body(field.text().await?);
+            Ok(resp)
+        }
+
+        let app = axum::Router::new().route("/upload", crate::post(first_field));
+        // The client never finishes sending: a buffering extractor would hang here.
+        let head = "--XB\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\nhi\r\n--XB\r\n";
+        let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(head))])
+            .chain(futures_util::stream::pending());
+        let sent = send(app, Body::from_stream(stream));
+        let (status, body) = tokio::time::timeout(std::time::Duration::from_secs(5), sent)
+            .await
+            .expect("multipart body was buffered instead of streamed");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "hi");
+    }
+
+    #[tokio::test]
+    async fn multipart_body_can_still_be_read_as_bytes() {
+        async fn len(mut req: UnifiedRequest) -> Result<webshelf_runtime::Response, HttpError> {
+            let body = req
+                .read_body_bytes()
+                .await
+                .map_err(HttpError::bad_request)?;
+            let mut resp = webshelf_runtime::Response::new();
+            resp.set_text_body(body.len().to_string());
+            Ok(resp)
+        }
+
+        let app = axum::Router::new().route("/upload", crate::post(len));
+        let (status, body) = send(app, Body::from(MULTIPART_BODY)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, MULTIPART_BODY.len().to_string());
+    }
+
+    #[tokio::test]
+    async fn chunked_body_over_limit_is_413() {
+        // No Content-Length: the limit is only hit while buffering.
+        let stream =
+            futures_util::stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(MULTIPART_BODY))]);
+        let (status, _) = send(upload_app(16), Body::from_stream(stream)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
 }
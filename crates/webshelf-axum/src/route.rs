@@ -1,6 +1,7 @@
-use axum::body::Body;
+use axum::body::{Body, HttpBody};
 use axum::response::{IntoResponse, Response as AxumResponse};
 use http::StatusCode;
+use http_body_util::BodyExt;
 use std::future::Future;
 use webshelf_runtime::{HttpError, Response, ResponseBody};
 
@@ -42,9 +43,19 @@ pub fn response_to_axum(mut resp: Response) -> AxumResponse {
         }
     });
 
-    let mut builder = AxumResponse::builder()
-        .status(status)
-        .header("content-type", content_type);
+    let mut builder = AxumResponse::builder().status(status);
+    // 101（WebSocket 升级）/ 204 / 304 以及无 body 的响应不设置 Content-Type；
+    // 显式设置的 Content-Type 仍然保留（如 HEAD 响应）
+    let bodiless = matches!(
+        status,
+        StatusCode::SWITCHING_PROTOCOLS | StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+    ) || (resp.body().is_empty() && resp.content_type().is_none());
+    if !bodiless {
+        builder = builder.header("content-type", content_type);
+    }
+    if let Some(extensions) = builder.extensions_mut() {
+        *extensions = resp.take_extensions();
+    }
 
     // Cookie 已统一存储在 headers 中（通过 set-cookie header），
     // 因此只需遍历 headers 即可同时处理普通头部和 cookie。
@@ -57,11 +68,54 @@ pub fn response_to_axum(mut resp: Response) -> AxumResponse {
         builder = builder.header(name.as_str(), &value);
     }
 
-    match resp.read_bytes() {
-        Ok(bytes) => builder.body(Body::from(bytes)).unwrap_or_default(),
+    // 流式 body（大文件导出 / SSE）直接透传，不做缓冲
+    let body = match resp.take_body() {
+        ResponseBody::Stream(stream) => Body::from_stream(stream),
+        body => match body.to_bytes() {
+            Ok(bytes) => Body::from(bytes),
+            Err(e) => {
+                tracing::error!("Failed to serialize response body: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+    };
+    builder.body(body).unwrap_or_default()
+}
+
+/// 将 axum Response 转换为统一 Response（中间件桥接使用）。
+///
+/// 长度已知的 body 会被完整读取；长度未知的 body（流式 / SSE）转为
+/// `ResponseBody::Stream` 透传，不做缓冲。
+/// Content-Type 作为显式覆盖保留，其余 header 与 extensions 原样复制。
+pub async fn response_from_axum(resp: AxumResponse) -> Response {
+    let (parts, body) = resp.into_parts();
+
+    let mut unified = Response::with_status(parts.status);
+    *unified.extensions_mut() = parts.extensions;
+    for (name, value) in &parts.headers {
+        let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+        if name == http::header::CONTENT_TYPE {
+            unified.set_content_type(value);
+        } else {
+            unified.append_header(name.clone(), value);
+        }
+    }
+
+    if body.size_hint().exact().is_none() {
+        unified.set_stream_body(body.into_data_stream());
+        return unified;
+    }
+    match body.collect().await {
+        Ok(collected) => {
+            let bytes = collected.to_bytes();
+            if !bytes.is_empty() {
+                unified.set_bytes_body(bytes);
+            }
+            unified
+        }
         Err(e) => {
-            tracing::error!("Failed to serialize response body: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            tracing::error!("Failed to read downstream response body: {}", e);
+            HttpError::internal("An unexpected error occurred").into()
         }
     }
 }
@@ -151,7 +205,7 @@ mod tests {
     use http_body_util::BodyExt;
     use webshelf_runtime::{HttpError, Response};
 
-    use super::response_to_axum;
+    use super::{response_from_axum, response_to_axum};
 
     /// Helper: extract response body as bytes for assertions.
     async fn body_bytes(resp: AxumResponse) -> Bytes {
@@ -237,6 +291,42 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn bodiless_responses_have_no_content_type() {
+        for status in [StatusCode::NO_CONTENT, StatusCode::NOT_MODIFIED] {
+            let axum_resp = response_to_axum(Response::with_status(status));
+            assert!(
+                axum_resp.headers().get("content-type").is_none(),
+                "{status}"
+            );
+        }
+        // An explicit Content-Type on an empty body is kept (e.g. HEAD)
+        let mut resp = Response::new();
+        resp.set_content_type("application/pdf");
+        let axum_resp = response_to_axum(resp);
+        assert_eq!(
+            axum_resp.headers().get("content-type").unwrap(),
+            "application/pdf"
+        );
+    }
+
+    #[tokio::test]
+    async fn extensions_survive_both_conversions() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Tag(&'static str);
+
+        let mut native = AxumResponse::new(axum::body::Body::empty());
+        native.extensions_mut().insert(Tag("downstream"));
+        let unified = response_from_axum(native).await;
+        assert_eq!(unified.extensions().get::<Tag>(), Some(&Tag("downstream")));
+
+        let axum_resp = response_to_axum(unified);
+        assert_eq!(
+            axum_resp.extensions().get::<Tag>(),
+            Some(&Tag("downstream"))
+        );
+    }
+
     #[tokio::test]
     async fn empty_body_returns_empty_bytes() {
         let resp = Response::new();
@@ -260,9 +350,11 @@ mod tests {
         assert_eq!(axum_resp.status(), StatusCode::NOT_FOUND);
         assert_eq!(
             axum_resp.headers().get("content-type").unwrap(),
-            "application/json; charset=utf-8"
+            "application/problem+json"
         );
         let json = body_json(axum_resp).await;
+        assert_eq!(json["status"], 404);
+        assert_eq!(json["detail"], "resource missing");
         assert_eq!(json["error"], "not_found");
         assert_eq!(json["message"], "resource missing");
     }
@@ -289,6 +381,29 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn response_from_axum_round_trip_preserves_parts() {
+        let native = AxumResponse::builder()
+            .status(StatusCode::ACCEPTED)
+            .header("content-type", "application/json; charset=utf-8")
+            .header("set-cookie", "a=1")
+            .header("set-cookie", "b=2")
+            .body(axum::body::Body::from("{\"ok\":true}"))
+            .unwrap();
+
+        let unified = response_from_axum(native).await;
+        assert_eq!(unified.status(), StatusCode::ACCEPTED);
+        assert_eq!(
+            unified.content_type(),
+            Some("application/json; charset=utf-8")
+        );
+
+        let axum_resp = response_to_axum(unified);
+        assert_eq!(axum_resp.headers().get_all("set-cookie").iter().count(), 2);
+        let json = body_json(axum_resp).await;
+        assert_eq!(json["ok"], true);
+    }
+
     #[tokio::test]
     async fn remove_cookie_works() {
         let mut resp = Response::new();
@@ -299,4 +414,52 @@ mod tests {
         assert!(value.contains("old_session="), "Should clear old_session");
         assert!(value.contains("Max-Age=0"), "Should set Max-Age=0");
     }
+
+    fn chunk_stream() -> impl futures_util::Stream<Item = Result<Bytes, std::io::Error>> {
+        futures_util::stream::iter(vec![
+            Ok(Bytes::from_static(b"id,name\n")),
+            Ok(Bytes::from_static(b"1,alice\n")),
+        ])
+    }
+
+    #[tokio::test]
+    async fn stream_body_is_forwarded_unbuffered() {
+        use axum::body::HttpBody;
+
+        let mut resp = Response::new();
+        resp.set_content_type("text/csv");
+        resp.set_stream_body(chunk_stream());
+        let axum_resp = response_to_axum(resp);
+
+        assert_eq!(axum_resp.headers().get("content-type").unwrap(), "text/csv");
+        assert!(axum_resp.body().size_hint().exact().is_none());
+        assert_eq!(body_bytes(axum_resp).await, "id,name\n1,alice\n");
+    }
+
+    #[tokio::test]
+    async fn sse_response_converts_to_event_stream() {
+        use webshelf_runtime::{Event, Sse};
+
+        let events = futures_util::stream::iter(vec![Event::default().event("tick").data("1")]);
+        let axum_resp = response_to_axum(Sse::new(events).into_response());
+        assert_eq!(
+            axum_resp.headers().get("content-type").unwrap(),
+            "text/event-stream"
+        );
+        assert_eq!(body_bytes(axum_resp).await, "event: tick\ndata: 1\n\n");
+    }
+
+    #[tokio::test]
+    async fn response_from_axum_keeps_unsized_body_streaming() {
+        let axum_resp = AxumResponse::new(axum::body::Body::from_stream(chunk_stream()));
+        let mut unified = response_from_axum(axum_resp).await;
+        assert!(unified.body().is_stream());
+
+        // Round-trips back to the client intact.
+        unified.set_content_type("text/csv");
+        assert_eq!(
+            body_bytes(response_to_axum(unified)).await,
+            "id,name\n1,alice\n"
+        );
+    }
 }
@@ -1,5 +1,11 @@
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
+
 use anyhow::Context;
-use webshelf_runtime::Runtime;
+use axum::serve::ListenerExt;
+use webshelf_runtime::{Runtime, Shutdown, TlsConfig, TlsReloader};
+
+use crate::tls::TlsListener;
 
 /// Axum runtime marker type. S is the shared state type.
 #[derive(Clone, Copy)]
@@ -34,20 +40,55 @@ impl<S: Clone + Send + Sync + 'static> Runtime for AxumRuntime<S> {
         router: Self::Router,
         state: Self::State,
         addr: &str,
+        tls: Option<&TlsConfig>,
+        shutdown: Shutdown,
     ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send {
         let addr = addr.to_string();
+        let tls = tls.cloned();
         async move {
             let listener = tokio::net::TcpListener::bind(&addr)
                 .await
                 .with_context(|| format!("Failed to bind to address: {addr}"))?;
-            tracing::info!("Server is ready to accept connections on {}", addr);
+            // 与 salvo 的 affix_state 对齐：state 也注入 request extensions，
+            // 使统一 Middleware 可通过 `get_data::<S>()` 读取。
             let svc = router
+                .layer(axum::Extension(state.clone()))
                 .with_state(state)
                 .into_make_service_with_connect_info::<std::net::SocketAddr>();
-            axum::serve(listener, svc)
-                .with_graceful_shutdown(webshelf_runtime::shutdown_signal())
-                .await
-                .context("Server failed")?;
+            let stop = {
+                let shutdown = shutdown.clone();
+                async move { shutdown.stopping().await }
+            };
+            let server: Pin<Box<dyn Future<Output = std::io::Result<()>> + Send>> = match tls {
+                Some(tls) => {
+                    // axum 未启用 http2 feature，只协商 HTTP/1.1
+                    let reloader = TlsReloader::start(&tls, &[b"http/1.1"])?;
+                    // tap_io 包装后可复用 axum 对 `Listener::Addr` 的 ConnectInfo 实现
+                    let listener = TlsListener::new(listener, reloader)?.tap_io(|_| {});
+                    tracing::info!("Server is ready to accept connections on https://{}", addr);
+                    Box::pin(
+                        axum::serve(listener, svc)
+                            .with_graceful_shutdown(stop)
+                            .into_future(),
+                    )
+                }
+                None => {
+                    tracing::info!("Server is ready to accept connections on {}", addr);
+                    Box::pin(
+                        axum::serve(listener, svc)
+                            .with_graceful_shutdown(stop)
+                            .into_future(),
+                    )
+                }
+            };
+
+            shutdown.listen_for_signals();
+            tokio::select! {
+                result = server => result.context("Server failed")?,
+                _ = shutdown.drain_deadline() => {
+                    tracing::warn!("Drain timeout exceeded, closing remaining connections");
+                }
+            }
             Ok(())
         }
     }
@@ -58,3 +99,71 @@ impl<S: Clone + Send + Sync + 'static> Default for AxumRuntime<S> {
         Self(std::marker::PhantomData)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use webshelf_runtime::{ShutdownConfig, ShutdownPhase};
+
+    #[derive(Clone)]
+    struct TestState;
+
+    async fn get(addr: &str, path: &str) -> std::io::Result<String> {
+        let mut stream = tokio::net::TcpStream::connect(addr).await?;
+        stream
+            .write_all(
+                format!("GET {path} HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n").as_bytes(),
+            )
+            .await?;
+        let mut buf = String::new();
+        stream.read_to_string(&mut buf).await?;
+        Ok(buf)
+    }
+
+    #[tokio::test]
+    async fn in_flight_requests_complete_after_shutdown() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let shutdown = Shutdown::new(ShutdownConfig {
+            pre_stop_delay_secs: 0,
+            drain_timeout_secs: 5,
+            ..Default::default()
+        });
+        let router = axum::Router::new().route(
+            "/slow",
+            axum::routing::get(|| async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                "done"
+            }),
+        );
+        let server = tokio::spawn({
+            let (addr, shutdown) = (addr.clone(), shutdown.clone());
+            async move { AxumRuntime::serve(router, TestState, &addr, None, shutdown).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let in_flight = tokio::spawn({
+            let addr = addr.clone();
+            async move { get(&addr, "/slow").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.trigger();
+
+        let response = in_flight.await.unwrap().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("done"));
+        tokio::time::timeout(Duration::from_secs(2), server)
+            .await
+            .expect("server stopped after draining")
+            .unwrap()
+            .unwrap();
+        assert_eq!(shutdown.phase(), ShutdownPhase::Stopping);
+        assert!(get(&addr, "/slow").await.is_err());
+    }
+}
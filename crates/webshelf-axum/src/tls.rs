@@ -0,0 +1,205 @@
+//! rustls listener for `axum::serve`.
+//!
+//! Handshakes run in their own tasks so a slow client cannot stall the accept loop; finished
+//! connections are handed to axum through a channel. Each handshake uses the config currently
+//! published by [`TlsReloader`], which is how certificate rotation takes effect.
+
+use std::io;
+use std::net::SocketAddr;
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::server::TlsStream;
+use webshelf_runtime::TlsReloader;
+use webshelf_runtime::tls::HANDSHAKE_TIMEOUT;
+
+/// Completed handshakes waiting for axum to pick them up.
+const ACCEPT_BACKLOG: usize = 128;
+
+pub(crate) struct TlsListener {
+    local_addr: SocketAddr,
+    accepted: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    accept_task: JoinHandle<()>,
+}
+
+impl TlsListener {
+    pub(crate) fn new(listener: TcpListener, reloader: TlsReloader) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (tx, accepted) = mpsc::channel(ACCEPT_BACKLOG);
+        let accept_task = tokio::spawn(accept_loop(listener, reloader, tx));
+        Ok(Self {
+            local_addr,
+            accepted,
+            accept_task,
+        })
+    }
+}
+
+impl Drop for TlsListener {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.accepted.recv().await {
+            Some(conn) => conn,
+            // accept_loop 只会在 listener 被 drop 时退出
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    reloader: TlsReloader,
+    tx: mpsc::Sender<(TlsStream<TcpStream>, SocketAddr)>,
+) {
+    loop {
+        let (tcp, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                // 与 axum 的 TcpListener 相同：EMFILE 等错误时短暂退避后重试
+                tracing::error!("accept error: {e}");
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        let acceptor = TlsAcceptor::from(reloader.current());
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(tcp)).await {
+                Ok(Ok(stream)) => {
+                    let _ = tx.send((stream, peer)).await;
+                }
+                Ok(Err(e)) => tracing::debug!(%peer, "TLS handshake failed: {e}"),
+                Err(_) => tracing::debug!(%peer, "TLS handshake timed out"),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use axum::serve::ListenerExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+    use tokio_rustls::rustls::{self, RootCertStore};
+    use webshelf_runtime::TlsConfig;
+
+    /// Write a fresh self-signed `localhost` certificate, returning its PEM.
+    fn write_cert(dir: &Path) -> String {
+        let issued = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        std::fs::write(dir.join("cert.pem"), issued.cert.pem()).unwrap();
+        std::fs::write(dir.join("key.pem"), issued.signing_key.serialize_pem()).unwrap();
+        issued.cert.pem()
+    }
+
+    async fn connect(
+        addr: SocketAddr,
+        trusted_pem: &str,
+    ) -> io::Result<tokio_rustls::client::TlsStream<TcpStream>> {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from_pem_slice(trusted_pem.as_bytes()).unwrap())
+            .unwrap();
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let tcp = TcpStream::connect(addr).await?;
+        tokio_rustls::TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), tcp)
+            .await
+    }
+
+    async fn get(stream: &mut tokio_rustls::client::TlsStream<TcpStream>, close: bool) -> String {
+        let connection = if close { "close" } else { "keep-alive" };
+        stream
+            .write_all(
+                format!("GET / HTTP/1.1\r\nHost: localhost\r\nConnection: {connection}\r\n\r\n")
+                    .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 1024];
+        while !buf.ends_with(b"hello") {
+            let n = stream.read(&mut chunk).await.unwrap();
+            assert!(n > 0, "connection closed early");
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_https_and_rotates_certificate_without_dropping_connections() {
+        let dir = std::env::temp_dir().join(format!("webshelf-axum-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let first_pem = write_cert(&dir);
+        let config = TlsConfig {
+            enabled: true,
+            cert_path: dir.join("cert.pem").display().to_string(),
+            key_path: dir.join("key.pem").display().to_string(),
+            reload_interval_secs: 1,
+            ..Default::default()
+        };
+
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = tcp.local_addr().unwrap();
+        let reloader = TlsReloader::start(&config, &[b"http/1.1"]).unwrap();
+        let listener = TlsListener::new(tcp, reloader).unwrap().tap_io(|_| {});
+        let app = axum::Router::new().route("/", axum::routing::get(|| async { "hello" }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut established = connect(addr, &first_pem).await.unwrap();
+        assert!(
+            get(&mut established, false)
+                .await
+                .starts_with("HTTP/1.1 200")
+        );
+
+        // mtime 粒度可能为 1s
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let second_pem = write_cert(&dir);
+        let mut rotated = None;
+        for _ in 0..50 {
+            if let Ok(stream) = connect(addr, &second_pem).await {
+                rotated = Some(stream);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let mut rotated = rotated.expect("new certificate served after reload");
+        assert!(get(&mut rotated, true).await.starts_with("HTTP/1.1 200"));
+        assert!(connect(addr, &first_pem).await.is_err());
+
+        // 轮换前建立的连接继续可用
+        assert!(
+            get(&mut established, true)
+                .await
+                .starts_with("HTTP/1.1 200")
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
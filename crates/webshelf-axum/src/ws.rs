@@ -0,0 +1,184 @@
+//! axum WebSocket ↔ `webshelf_runtime::WebSocket` 转换。
+
+use axum::extract::ws::{self, WebSocket as AxumWebSocket};
+use futures_util::{SinkExt, StreamExt};
+use webshelf_runtime::{CloseFrame, Message, WebSocket};
+
+/// 将 axum 原生 WebSocket 包装为统一 WebSocket。
+pub(crate) fn into_unified(socket: AxumWebSocket) -> WebSocket {
+    let (sink, stream) = socket.split();
+    let sink = sink.with(|msg: Message| async move { Ok::<_, axum::Error>(to_axum(msg)) });
+    let stream = stream.map(|item| item.map(from_axum));
+    WebSocket::new(sink, stream)
+}
+
+fn to_axum(msg: Message) -> ws::Message {
+    match msg {
+        Message::Text(text) => ws::Message::Text(text.into()),
+        Message::Binary(bytes) => ws::Message::Binary(bytes),
+        Message::Ping(bytes) => ws::Message::Ping(bytes),
+        Message::Pong(bytes) => ws::Message::Pong(bytes),
+        Message::Close(frame) => ws::Message::Close(frame.map(|f| ws::CloseFrame {
+            code: f.code,
+            reason: f.reason.into(),
+        })),
+    }
+}
+
+fn from_axum(msg: ws::Message) -> Message {
+    match msg {
+        ws::Message::Text(text) => Message::Text(text.as_str().to_owned()),
+        ws::Message::Binary(bytes) => Message::Binary(bytes),
+        ws::Message::Ping(bytes) => Message::Ping(bytes),
+        ws::Message::Pong(bytes) => Message::Pong(bytes),
+        ws::Message::Close(frame) => Message::Close(frame.map(|f| CloseFrame {
+            code: f.code,
+            reason: f.reason.as_str().to_owned(),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use axum::body::Body;
+    use axum::http::Request;
+    use tokio_tungstenite::tungstenite;
+    use tower::ServiceExt;
+    use webshelf_runtime::{
+        HttpError, Middleware, Next, RequestContext, Response, WebSocketOrigins,
+    };
+
+    use super::*;
+    use crate::UnifiedRequest;
+
+    async fn echo(mut req: UnifiedRequest) -> Result<Response, HttpError> {
+        req.upgrade_websocket(|mut ws| async move {
+            while let Some(Ok(msg)) = ws.recv().await {
+                if msg.is_close() || ws.send(msg).await.is_err() {
+                    break;
+                }
+            }
+        })
+        .await
+    }
+
+    /// Stand-in for the JWT guard: rejects upgrades without a session cookie.
+    struct RequireSession;
+
+    #[async_trait::async_trait]
+    impl Middleware for RequireSession {
+        async fn handle<R: RequestContext + 'static>(&self, req: R, next: Next<'_, R>) -> Response {
+            if req.cookie("webshelf_jwt").is_none() {
+                return HttpError::unauthorized("Missing session").into();
+            }
+            next.run(req).await
+        }
+    }
+
+    fn app() -> axum::Router {
+        crate::with_middleware(
+            axum::Router::new().route("/ws", crate::get(echo)),
+            RequireSession,
+        )
+        .layer(axum::Extension(WebSocketOrigins::new([ALLOWED_ORIGIN])))
+    }
+
+    async fn spawn_server() -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app()).await.unwrap() });
+        addr
+    }
+
+    const ALLOWED_ORIGIN: &str = "https://app.example.com";
+
+    fn ws_request(
+        addr: SocketAddr,
+        cookie: Option<&str>,
+    ) -> tungstenite::handshake::client::Request {
+        use tungstenite::client::IntoClientRequest;
+        let mut req = format!("ws://{addr}/ws").into_client_request().unwrap();
+        if let Some(cookie) = cookie {
+            req.headers_mut().insert("cookie", cookie.parse().unwrap());
+        }
+        req
+    }
+
+    #[tokio::test]
+    async fn upgrade_through_middleware_echoes_messages() {
+        let addr = spawn_server().await;
+        let (mut client, resp) =
+            tokio_tungstenite::connect_async(ws_request(addr, Some("webshelf_jwt=t")))
+                .await
+                .unwrap();
+        assert_eq!(resp.status(), http::StatusCode::SWITCHING_PROTOCOLS);
+
+        client
+            .send(tungstenite::Message::text("ping"))
+            .await
+            .unwrap();
+        let reply = client.next().await.unwrap().unwrap();
+        assert_eq!(reply, tungstenite::Message::text("ping"));
+
+        client
+            .send(tungstenite::Message::binary(vec![1u8, 2]))
+            .await
+            .unwrap();
+        let reply = client.next().await.unwrap().unwrap();
+        assert_eq!(reply, tungstenite::Message::binary(vec![1u8, 2]));
+    }
+
+    #[tokio::test]
+    async fn middleware_rejection_blocks_upgrade() {
+        let addr = spawn_server().await;
+        let err = tokio_tungstenite::connect_async(ws_request(addr, None))
+            .await
+            .unwrap_err();
+        match err {
+            tungstenite::Error::Http(resp) => {
+                assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED)
+            }
+            other => panic!("expected HTTP rejection, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn plain_get_is_rejected() {
+        let req = Request::builder()
+            .uri("/ws")
+            .header("cookie", "webshelf_jwt=t")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app().oneshot(req).await.unwrap();
+        assert!(resp.status().is_client_error());
+    }
+
+    #[tokio::test]
+    async fn upgrade_checks_origin() {
+        let addr = spawn_server().await;
+        let with_origin = |origin: &str| {
+            let mut req = ws_request(addr, Some("webshelf_jwt=t"));
+            req.headers_mut().insert("origin", origin.parse().unwrap());
+            req
+        };
+
+        match tokio_tungstenite::connect_async(with_origin("https://evil.example"))
+            .await
+            .unwrap_err()
+        {
+            tungstenite::Error::Http(resp) => {
+                assert_eq!(resp.status(), http::StatusCode::FORBIDDEN)
+            }
+            other => panic!("expected HTTP rejection, got {other:?}"),
+        }
+        // Same-origin pages and configured origins may connect.
+        for origin in [format!("http://{addr}"), ALLOWED_ORIGIN.to_owned()] {
+            let (_, resp) = tokio_tungstenite::connect_async(with_origin(&origin))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), http::StatusCode::SWITCHING_PROTOCOLS);
+        }
+    }
+}
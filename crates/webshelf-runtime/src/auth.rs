@@ -31,6 +31,20 @@ pub struct AuthUser {
     pub token_version: i32,
     /// Whether the original login had "remember me" enabled
     pub remember: bool,
+    /// Scopes of a delegated token (e.g. an OAuth access token); `None` for a first-party
+    /// session, which may use every endpoint
+    #[serde(default)]
+    pub scopes: Option<Vec<String>>,
+}
+
+impl AuthUser {
+    /// Whether the credential grants `scope` (always true for first-party sessions).
+    pub fn has_scope(&self, scope: &str) -> bool {
+        match &self.scopes {
+            None => true,
+            Some(scopes) => scopes.iter().any(|s| s == scope),
+        }
+    }
 }
 
 impl From<JwtClaims> for AuthUser {
@@ -42,6 +56,7 @@ impl From<JwtClaims> for AuthUser {
             iat: claims.iat,
             token_version: claims.token_version,
             remember: claims.remember,
+            scopes: None,
         }
     }
 }
@@ -140,6 +155,15 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn has_scope_is_unrestricted_for_sessions() {
+        let mut user = AuthUser::from(test_claims());
+        assert!(user.has_scope("users:write"));
+        user.scopes = Some(vec!["openid".to_string(), "users:read".to_string()]);
+        assert!(user.has_scope("users:read"));
+        assert!(!user.has_scope("users:write"));
+    }
+
     #[test]
     fn validate_jwt_malformed_token() {
         let result = validate_jwt("not-a-valid-jwt", "my_secret");
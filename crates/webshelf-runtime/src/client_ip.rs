@@ -0,0 +1,362 @@
+//! Client IP resolution behind reverse proxies.
+//!
+//! `X-Forwarded-For` / `Forwarded` / `X-Real-IP` are only honoured when the TCP peer is a
+//! configured trusted proxy. The forwarding chain is then walked right-to-left, skipping
+//! trusted hops; the first untrusted address is the client:
+//!
+//! ```text
+//! X-Forwarded-For: <spoofed>, 203.0.113.7, 10.0.0.2      peer = 10.0.0.3
+//!                             ^ client    ^ trusted       ^ trusted
+//! ```
+//!
+//! 未配置可信代理时（默认）转发头一律忽略，直接使用 TCP 对端地址，
+//! 因此直连服务器的客户端无法伪造 IP 绕过按 IP 的限流。
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+
+use http::HeaderMap;
+use serde::Deserialize;
+
+/// An IP network in CIDR notation (`10.0.0.0/8`, `fd00::/8`). A bare address is a single host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpCidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| format!("invalid trusted proxy `{s}`: not an IP address or CIDR"))?
+            .to_canonical();
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.parse::<u8>().ok().filter(|p| *p <= max),
+            None => Some(max),
+        }
+        .ok_or_else(|| format!("invalid trusted proxy `{s}`: prefix must be 0..={max}"))?;
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// The set of reverse proxies whose forwarding headers are trusted (`server.trusted_proxies`).
+///
+/// Adapters read it from request-scoped data (axum extensions / salvo depot); when it is
+/// absent, no proxy is trusted.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(try_from = "Vec<String>")]
+pub struct TrustedProxies(Vec<IpCidr>);
+
+impl TrustedProxies {
+    /// Parse a list of CIDRs / addresses, failing on the first invalid entry.
+    pub fn parse<I, S>(entries: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        entries
+            .into_iter()
+            .map(|e| e.as_ref().parse())
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(ip))
+    }
+
+    /// Resolve the client address of a request received from `peer`.
+    ///
+    /// Forwarding headers are consulted only when `peer` is trusted; `Forwarded` (RFC 7239)
+    /// takes precedence over `X-Forwarded-For`, and `X-Real-IP` is used only when neither is
+    /// present. An unparseable hop (e.g. `for=unknown`) stops the walk at the last trusted hop.
+    pub fn client_ip(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        let peer = peer?.to_canonical();
+        if !self.contains(peer) {
+            return Some(peer);
+        }
+
+        let hops = forwarded_chain(headers);
+        if hops.is_empty() {
+            let real_ip = headers
+                .get("x-real-ip")
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_node);
+            return Some(real_ip.unwrap_or(peer));
+        }
+
+        let mut client = peer;
+        for hop in hops.into_iter().rev() {
+            let Some(ip) = hop else { break };
+            client = ip;
+            if !self.contains(ip) {
+                break;
+            }
+        }
+        Some(client)
+    }
+}
+
+impl TryFrom<Vec<String>> for TrustedProxies {
+    type Error = String;
+
+    fn try_from(entries: Vec<String>) -> Result<Self, Self::Error> {
+        Self::parse(entries)
+    }
+}
+
+/// Forwarding hops, left (client side) to right (nearest proxy). `None` marks a hop
+/// whose address is unknown or obfuscated.
+fn forwarded_chain(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    // 同名头可能出现多行，按顺序拼接等价于逗号分隔（RFC 9110 §5.3）。
+    let values = |name: &str| -> Vec<&str> {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .collect()
+    };
+
+    let forwarded = values("forwarded");
+    if !forwarded.is_empty() {
+        return forwarded
+            .into_iter()
+            .map(|element| {
+                element
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                    .and_then(|(_, node)| parse_node(node))
+            })
+            .collect();
+    }
+
+    values("x-forwarded-for")
+        .into_iter()
+        .map(parse_node)
+        .collect()
+}
+
+/// Parse a node: `192.0.2.1`, `192.0.2.1:8080`, `2001:db8::1`, `"[2001:db8::1]:4711"`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    let ip = if let Some(rest) = node.strip_prefix('[') {
+        rest.split_once(']')?.0.parse().ok()?
+    } else if let Ok(ip) = node.parse::<IpAddr>() {
+        ip
+    } else {
+        IpAddr::V4(node.split_once(':')?.0.parse::<Ipv4Addr>().ok()?)
+    };
+    Some(ip.to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    fn proxies(entries: &[&str]) -> TrustedProxies {
+        TrustedProxies::parse(entries).unwrap()
+    }
+
+    #[test]
+    fn cidr_parsing_and_matching() {
+        let net: IpCidr = "10.0.0.0/8".parse().unwrap();
+        assert!(net.contains(ip("10.200.1.1")));
+        assert!(!net.contains(ip("11.0.0.1")));
+        assert!(net.contains(ip("::ffff:10.0.0.1")));
+
+        let host: IpCidr = "192.0.2.1".parse().unwrap();
+        assert_eq!(host.to_string(), "192.0.2.1/32");
+        assert!(!host.contains(ip("192.0.2.2")));
+
+        let v6: IpCidr = "fd00::/8".parse().unwrap();
+        assert!(v6.contains(ip("fd12::1")));
+        assert!(!v6.contains(ip("10.0.0.1")));
+
+        let any: IpCidr = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains(ip("203.0.113.9")));
+    }
+
+    #[test]
+    fn invalid_cidrs_are_rejected() {
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("::/129".parse::<IpCidr>().is_err());
+        assert!("proxy.local".parse::<IpCidr>().is_err());
+        assert!(TrustedProxies::parse(["10.0.0.0/8", "nope"]).is_err());
+    }
+
+    #[test]
+    fn deserializes_from_string_list() {
+        let parsed: TrustedProxies = serde_json::from_str(r#"["10.0.0.0/8", "::1"]"#).unwrap();
+        assert!(parsed.contains(ip("10.1.2.3")));
+        assert!(parsed.contains(ip("::1")));
+        assert!(serde_json::from_str::<TrustedProxies>(r#"["10.0.0.0/99"]"#).is_err());
+    }
+
+    #[test]
+    fn headers_ignored_without_trusted_proxies() {
+        let h = headers(&[
+            ("x-forwarded-for", "1.2.3.4"),
+            ("x-real-ip", "5.6.7.8"),
+            ("forwarded", "for=9.9.9.9"),
+        ]);
+        let none = TrustedProxies::default();
+        assert_eq!(
+            none.client_ip(Some(ip("198.51.100.7")), &h),
+            Some(ip("198.51.100.7"))
+        );
+        assert_eq!(none.client_ip(None, &h), None);
+    }
+
+    #[test]
+    fn headers_ignored_from_untrusted_peer() {
+        let h = headers(&[("x-forwarded-for", "1.2.3.4")]);
+        assert_eq!(
+            proxies(&["10.0.0.0/8"]).client_ip(Some(ip("198.51.100.7")), &h),
+            Some(ip("198.51.100.7"))
+        );
+    }
+
+    #[test]
+    fn xff_walk_skips_trusted_hops_right_to_left() {
+        let h = headers(&[("x-forwarded-for", "6.6.6.6, 203.0.113.7, 10.0.0.2")]);
+        assert_eq!(
+            proxies(&["10.0.0.0/8"]).client_ip(Some(ip("10.0.0.3")), &h),
+            Some(ip("203.0.113.7"))
+        );
+    }
+
+    #[test]
+    fn xff_spread_over_multiple_header_lines() {
+        let h = headers(&[
+            ("x-forwarded-for", "6.6.6.6"),
+            ("x-forwarded-for", "203.0.113.7"),
+        ]);
+        assert_eq!(
+            proxies(&["10.0.0.0/8"]).client_ip(Some(ip("10.0.0.3")), &h),
+            Some(ip("203.0.113.7"))
+        );
+    }
+
+    #[test]
+    fn all_hops_trusted_returns_leftmost() {
+        let h = headers(&[("x-forwarded-for", "10.0.0.9, 10.0.0.2")]);
+        assert_eq!(
+            proxies(&["10.0.0.0/8"]).client_ip(Some(ip("10.0.0.3")), &h),
+            Some(ip("10.0.0.9"))
+        );
+    }
+
+    #[test]
+    fn unparseable_hop_stops_at_last_trusted() {
+        let h = headers(&[("x-forwarded-for", "1.2.3.4, garbage, 10.0.0.2")]);
+        assert_eq!(
+            proxies(&["10.0.0.0/8"]).client_ip(Some(ip("10.0.0.3")), &h),
+            Some(ip("10.0.0.2"))
+        );
+    }
+
+    #[test]
+    fn forwarded_header_takes_precedence() {
+        let h = headers(&[
+            ("x-forwarded-for", "1.1.1.1"),
+            (
+                "forwarded",
+                r#"for=6.6.6.6, for="[2001:db8:cafe::17]:4711";proto=https, For=10.0.0.2"#,
+            ),
+        ]);
+        assert_eq!(
+            proxies(&["10.0.0.0/8"]).client_ip(Some(ip("10.0.0.3")), &h),
+            Some(ip("2001:db8:cafe::17"))
+        );
+    }
+
+    #[test]
+    fn forwarded_unknown_node_stops_walk() {
+        let h = headers(&[("forwarded", "for=unknown;proto=http")]);
+        assert_eq!(
+            proxies(&["10.0.0.0/8"]).client_ip(Some(ip("10.0.0.3")), &h),
+            Some(ip("10.0.0.3"))
+        );
+    }
+
+    #[test]
+    fn x_real_ip_used_only_without_forwarding_chain() {
+        let trusted = proxies(&["127.0.0.1"]);
+        let h = headers(&[("x-real-ip", "203.0.113.5")]);
+        assert_eq!(
+            trusted.client_ip(Some(ip("127.0.0.1")), &h),
+            Some(ip("203.0.113.5"))
+        );
+
+        let h = headers(&[("x-forwarded-for", ""), ("x-real-ip", "203.0.113.5")]);
+        assert_eq!(
+            trusted.client_ip(Some(ip("127.0.0.1")), &h),
+            Some(ip("203.0.113.5"))
+        );
+    }
+
+    #[test]
+    fn node_formats() {
+        assert_eq!(parse_node("192.0.2.1:8080"), Some(ip("192.0.2.1")));
+        assert_eq!(parse_node("2001:db8::1"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("\"[2001:db8::1]:443\""), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("::ffff:192.0.2.1"), Some(ip("192.0.2.1")));
+        assert_eq!(parse_node("_hidden"), None);
+    }
+}
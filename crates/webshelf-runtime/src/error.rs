@@ -1,68 +1,176 @@
+use std::collections::BTreeMap;
+
 use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Content-Type of RFC 7807 error responses.
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Problem members that extensions must not override.
+const RESERVED_MEMBERS: &[&str] = &[
+    "type", "title", "status", "detail", "error", "message", "code", "errors",
+];
+
+/// Error of a single input field, e.g. `{"code": "email", "message": "invalid email"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// Machine-readable rule that failed (`length`, `email`, `weak_password`, …).
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl FieldError {
+    pub fn new(code: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: None,
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
 
-/// Unified HTTP error type with JSON-compatible format.
+/// Field name (dotted path for nested inputs) → errors of that field.
+pub type FieldErrors = BTreeMap<String, Vec<FieldError>>;
+
+/// Unified HTTP error type, rendered as RFC 7807 `application/problem+json`:
+///
+/// ```json
+/// {
+///   "type": "about:blank", "title": "Bad Request", "status": 400,
+///   "detail": "email: invalid email",
+///   "error": "validation_error", "message": "email: invalid email",
+///   "errors": { "email": [{ "code": "email", "message": "invalid email" }] }
+/// }
+/// ```
+///
+/// `error` / `message` 与旧版 `{"error", "message"}` 格式保持兼容；
+/// `code`、`errors` 与扩展成员仅在设置时输出。
 #[derive(Debug)]
 pub struct HttpError {
     pub status: StatusCode,
     pub error_type: &'static str,
     pub message: String,
+    /// Stable machine-readable code, finer grained than `error_type` (e.g. `email_taken`).
+    pub code: Option<String>,
+    pub field_errors: FieldErrors,
+    /// Extension members, serialized at the top level of the problem object.
+    pub extensions: Map<String, Value>,
 }
 
 impl HttpError {
-    pub fn bad_request(msg: impl Into<String>) -> Self {
+    pub fn new(status: StatusCode, error_type: &'static str, msg: impl Into<String>) -> Self {
         Self {
-            status: StatusCode::BAD_REQUEST,
-            error_type: "bad_request",
+            status,
+            error_type,
             message: msg.into(),
+            code: None,
+            field_errors: FieldErrors::new(),
+            extensions: Map::new(),
         }
     }
 
+    pub fn bad_request(msg: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "bad_request", msg)
+    }
+
+    /// 400 with `error_type = "validation_error"`; attach field errors with
+    /// [`with_field_error`](Self::with_field_error).
+    pub fn validation(msg: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "validation_error", msg)
+    }
+
     pub fn unauthorized(msg: impl Into<String>) -> Self {
-        Self {
-            status: StatusCode::UNAUTHORIZED,
-            error_type: "unauthorized",
-            message: msg.into(),
-        }
+        Self::new(StatusCode::UNAUTHORIZED, "unauthorized", msg)
     }
 
     pub fn forbidden(msg: impl Into<String>) -> Self {
-        Self {
-            status: StatusCode::FORBIDDEN,
-            error_type: "forbidden",
-            message: msg.into(),
-        }
+        Self::new(StatusCode::FORBIDDEN, "forbidden", msg)
     }
 
     pub fn not_found(msg: impl Into<String>) -> Self {
-        Self {
-            status: StatusCode::NOT_FOUND,
-            error_type: "not_found",
-            message: msg.into(),
-        }
+        Self::new(StatusCode::NOT_FOUND, "not_found", msg)
     }
 
     pub fn conflict(msg: impl Into<String>) -> Self {
-        Self {
-            status: StatusCode::CONFLICT,
-            error_type: "conflict",
-            message: msg.into(),
-        }
+        Self::new(StatusCode::CONFLICT, "conflict", msg)
+    }
+
+    pub fn payload_too_large(msg: impl Into<String>) -> Self {
+        Self::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", msg)
+    }
+
+    pub fn too_many_requests(msg: impl Into<String>) -> Self {
+        Self::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", msg)
     }
 
     pub fn internal(msg: impl Into<String>) -> Self {
-        Self {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-            error_type: "internal_error",
-            message: msg.into(),
-        }
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", msg)
     }
 
     pub fn service_unavailable(msg: impl Into<String>) -> Self {
-        Self {
-            status: StatusCode::SERVICE_UNAVAILABLE,
-            error_type: "service_unavailable",
-            message: msg.into(),
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, "service_unavailable", msg)
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn with_field_error(mut self, field: impl Into<String>, error: FieldError) -> Self {
+        self.field_errors
+            .entry(field.into())
+            .or_default()
+            .push(error);
+        self
+    }
+
+    pub fn with_field_errors(mut self, errors: FieldErrors) -> Self {
+        for (field, errs) in errors {
+            self.field_errors.entry(field).or_default().extend(errs);
         }
+        self
+    }
+
+    /// Add an extension member. Keys of the standard members are ignored.
+    pub fn with_extension(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        let key = key.into();
+        if !RESERVED_MEMBERS.contains(&key.as_str()) {
+            self.extensions.insert(key, value.into());
+        }
+        self
+    }
+
+    /// Render as an RFC 7807 problem details object.
+    pub fn to_problem_json(&self) -> Value {
+        let mut problem = Map::new();
+        problem.insert("type".into(), "about:blank".into());
+        problem.insert(
+            "title".into(),
+            self.status.canonical_reason().unwrap_or("Error").into(),
+        );
+        problem.insert("status".into(), self.status.as_u16().into());
+        problem.insert("detail".into(), self.message.clone().into());
+        problem.insert("error".into(), self.error_type.into());
+        problem.insert("message".into(), self.message.clone().into());
+        if let Some(code) = &self.code {
+            problem.insert("code".into(), code.clone().into());
+        }
+        if !self.field_errors.is_empty() {
+            problem.insert(
+                "errors".into(),
+                serde_json::to_value(&self.field_errors).unwrap_or_default(),
+            );
+        }
+        for (key, value) in &self.extensions {
+            problem.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        Value::Object(problem)
     }
 }
 
@@ -79,3 +187,54 @@ impl std::fmt::Display for HttpError {
 }
 
 impl std::error::Error for HttpError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problem_json_has_standard_and_legacy_members() {
+        let problem = HttpError::not_found("User not found").to_problem_json();
+        assert_eq!(problem["type"], "about:blank");
+        assert_eq!(problem["title"], "Not Found");
+        assert_eq!(problem["status"], 404);
+        assert_eq!(problem["detail"], "User not found");
+        assert_eq!(problem["error"], "not_found");
+        assert_eq!(problem["message"], "User not found");
+        assert!(problem.get("code").is_none());
+        assert!(problem.get("errors").is_none());
+    }
+
+    #[test]
+    fn problem_json_includes_code_fields_and_extensions() {
+        let problem = HttpError::validation("invalid input")
+            .with_code("invalid_input")
+            .with_field_error(
+                "email",
+                FieldError::new("email").with_message("invalid email"),
+            )
+            .with_field_error("email", FieldError::new("length"))
+            .with_extension("retry_after", 60)
+            .to_problem_json();
+        assert_eq!(problem["error"], "validation_error");
+        assert_eq!(problem["code"], "invalid_input");
+        assert_eq!(
+            problem["errors"]["email"],
+            serde_json::json!([
+                { "code": "email", "message": "invalid email" },
+                { "code": "length" }
+            ])
+        );
+        assert_eq!(problem["retry_after"], 60);
+    }
+
+    #[test]
+    fn extensions_cannot_override_standard_members() {
+        let problem = HttpError::forbidden("nope")
+            .with_extension("status", 200)
+            .with_extension("error", "ok")
+            .to_problem_json();
+        assert_eq!(problem["status"], 403);
+        assert_eq!(problem["error"], "forbidden");
+    }
+}
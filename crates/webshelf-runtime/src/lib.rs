@@ -1,20 +1,41 @@
 pub mod auth;
+pub mod client_ip;
 mod error;
+pub mod metrics;
 pub mod middleware;
+pub mod multipart;
+pub mod openapi;
 pub mod rate_limit;
 mod request;
+pub mod request_id;
 mod response;
 mod runtime;
+pub mod security_headers;
+pub mod shutdown;
 mod signal;
+pub mod sse;
+pub mod tls;
+pub mod trace_context;
+pub mod ws;
 
 pub use auth::{AuthUser, JwtClaims, validate_jwt};
-pub use error::HttpError;
-pub use middleware::{MiddlewareState, validate_token};
+pub use client_ip::TrustedProxies;
+pub use error::{FieldError, FieldErrors, HttpError, PROBLEM_JSON_CONTENT_TYPE};
+pub use metrics::{MatchedRoute, MetricsMiddleware};
+pub use middleware::{AdminGuard, AuthGuard, Middleware, MiddlewareState, Next, validate_token};
+pub use multipart::{BodyError, Field, Multipart, MultipartLimits, RequestBodyStream};
+pub use openapi::{OpenApi, Operation};
 pub use rate_limit::RateLimitGuard;
 pub use request::RequestContext;
-pub use response::{Response, ResponseBody};
+pub use request_id::{ACCESS_LOG_TARGET, RequestId, RequestIdMiddleware};
+pub use response::{BodyStream, BoxError, Response, ResponseBody};
 pub use runtime::Runtime;
+pub use security_headers::{CspNonce, SecurityHeadersConfig, SecurityHeadersMiddleware};
+pub use shutdown::{Shutdown, ShutdownConfig, ShutdownPhase};
 pub use signal::shutdown_signal;
+pub use sse::{Event, Sse};
+pub use tls::{ClientAuth, TlsConfig, TlsReloader, TlsVersion};
+pub use ws::{CloseFrame, Message, WebSocket, WebSocketOrigins, WsReceiver, WsSender};
 
 #[cfg(test)]
 mod tests {
@@ -58,6 +79,8 @@ mod tests {
             _router: Self::Router,
             _state: Self::State,
             _addr: &str,
+            _tls: Option<&crate::TlsConfig>,
+            _shutdown: crate::Shutdown,
         ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send {
             async { Ok(()) }
         }
@@ -75,7 +98,7 @@ mod tests {
 
     #[test]
     fn mock_runtime_serve_returns_send_future() {
-        let fut = MockRuntime::serve((), MockState, "0.0.0.0:0");
+        let fut = MockRuntime::serve((), MockState, "0.0.0.0:0", None, Default::default());
         fn assert_send<T: Send>(_t: T) {}
         assert_send(fut);
     }
@@ -0,0 +1,335 @@
+//! Prometheus metrics on top of [`prometheus_client`].
+//!
+//! Every family lives in one process-wide [`Registry`], rendered by the `prometheus_client`
+//! text encoder in the OpenMetrics format. The families used here are labelled counters and
+//! histograms, created once, usually behind a `LazyLock`:
+//!
+//! ```ignore
+//! static LOOKUPS: LazyLock<&CounterVec> = LazyLock::new(|| {
+//!     metrics::counter("cache_lookups_total", "Cache lookups by result", &["result"])
+//! });
+//! LOOKUPS.inc(&["hit"]);
+//! ```
+//!
+//! [`MetricsMiddleware`] records `http_requests_total` and `http_request_duration_seconds`,
+//! labelled by the matched route pattern (not the raw path, which would be unbounded);
+//! requests that match no route share the `unmatched` label. Both runtimes resolve the route
+//! before running middleware, so requests rejected by auth or rate limiting keep their pattern.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::Instant;
+
+use prometheus_client::encoding::text::encode;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::{Family, MetricConstructor};
+use prometheus_client::metrics::histogram::Histogram;
+use prometheus_client::registry::Registry;
+
+use crate::middleware::{Middleware, Next};
+use crate::{RequestContext, Response};
+
+/// `Content-Type` of [`render`]'s output.
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Latency buckets in seconds (same as the official client libraries).
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Route label for requests that matched no route.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Label names paired with the values of one series.
+type LabelSet = Vec<(&'static str, String)>;
+
+fn label_set(names: &'static [&'static str], values: &[&str]) -> LabelSet {
+    debug_assert_eq!(names.len(), values.len(), "label values for {names:?}");
+    names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| (*name, value.to_string()))
+        .collect()
+}
+
+/// A counter family; one series per distinct label value tuple.
+pub struct CounterVec {
+    labels: &'static [&'static str],
+    family: Family<LabelSet, Counter>,
+}
+
+impl CounterVec {
+    /// Increment the series for `labels` (values in the order the family declared them).
+    pub fn inc(&self, labels: &[&str]) {
+        self.inc_by(labels, 1);
+    }
+
+    pub fn inc_by(&self, labels: &[&str], value: u64) {
+        self.family
+            .get_or_create(&label_set(self.labels, labels))
+            .inc_by(value);
+    }
+
+    /// Current value of one series (0 when never incremented).
+    pub fn get(&self, labels: &[&str]) -> u64 {
+        self.family
+            .get(&label_set(self.labels, labels))
+            .map_or(0, |counter| counter.get())
+    }
+}
+
+/// Creates each histogram series with the family's bucket bounds.
+#[derive(Clone, Debug)]
+struct Buckets(&'static [f64]);
+
+impl MetricConstructor<Histogram> for Buckets {
+    fn new_metric(&self) -> Histogram {
+        Histogram::new(self.0.iter().copied())
+    }
+}
+
+/// A histogram family; one series per distinct label value tuple.
+pub struct HistogramVec {
+    labels: &'static [&'static str],
+    family: Family<LabelSet, Histogram, Buckets>,
+}
+
+impl HistogramVec {
+    pub fn observe(&self, labels: &[&str], value: f64) {
+        self.family
+            .get_or_create(&label_set(self.labels, labels))
+            .observe(value);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Registered {
+    Counter(&'static CounterVec),
+    Histogram(&'static HistogramVec),
+}
+
+/// The registry, plus its families by exported name so registering twice returns the same one.
+#[derive(Default)]
+struct Families {
+    registry: Registry,
+    by_name: HashMap<&'static str, Registered>,
+}
+
+static FAMILIES: LazyLock<Mutex<Families>> = LazyLock::new(Default::default);
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Register (or look up) a counter family.
+///
+/// `name` is the exported name; the encoder adds the `_total` suffix itself, so it is
+/// stripped before registering.
+///
+/// # Panics
+/// If `name` is already registered as a histogram.
+pub fn counter(
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+) -> &'static CounterVec {
+    let mut families = lock(&FAMILIES);
+    match families.by_name.get(name) {
+        Some(Registered::Counter(c)) => return c,
+        Some(Registered::Histogram(_)) => {
+            panic!("metric {name} is already registered as a histogram")
+        }
+        None => {}
+    }
+    let counter: &'static CounterVec = Box::leak(Box::new(CounterVec {
+        labels,
+        family: Family::default(),
+    }));
+    families.registry.register(
+        name.strip_suffix("_total").unwrap_or(name),
+        help,
+        counter.family.clone(),
+    );
+    families.by_name.insert(name, Registered::Counter(counter));
+    counter
+}
+
+/// Register (or look up) a histogram family with ascending bucket upper `bounds`.
+///
+/// # Panics
+/// If `name` is already registered as a counter.
+pub fn histogram(
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    bounds: &'static [f64],
+) -> &'static HistogramVec {
+    let mut families = lock(&FAMILIES);
+    match families.by_name.get(name) {
+        Some(Registered::Histogram(h)) => return h,
+        Some(Registered::Counter(_)) => {
+            panic!("metric {name} is already registered as a counter")
+        }
+        None => {}
+    }
+    let histogram: &'static HistogramVec = Box::leak(Box::new(HistogramVec {
+        labels,
+        family: Family::new_with_constructor(Buckets(bounds)),
+    }));
+    families
+        .registry
+        .register(name, help, histogram.family.clone());
+    families
+        .by_name
+        .insert(name, Registered::Histogram(histogram));
+    histogram
+}
+
+/// Render every registered family in the OpenMetrics text format.
+pub fn render() -> String {
+    let mut out = String::new();
+    // 写入 String 不会失败
+    let _ = encode(&mut out, &lock(&FAMILIES).registry);
+    out
+}
+
+// ── HTTP metrics ────────────────────────────────────────────
+
+static HTTP_REQUESTS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    counter(
+        "http_requests_total",
+        "HTTP requests by method, matched route pattern and status",
+        &["method", "route", "status"],
+    )
+});
+
+static HTTP_DURATION: LazyLock<&HistogramVec> = LazyLock::new(|| {
+    histogram(
+        "http_request_duration_seconds",
+        "HTTP request latency by method and matched route pattern",
+        &["method", "route"],
+        DEFAULT_BUCKETS,
+    )
+});
+
+/// Matched route pattern, stored in request data by adapters whose
+/// [`RequestContext::matched_route_pattern`] does not expose it (salvo).
+#[derive(Clone, Debug)]
+pub struct MatchedRoute(pub String);
+
+/// Records request count and latency per method, route pattern and status.
+#[derive(Clone, Copy, Default)]
+pub struct MetricsMiddleware;
+
+#[async_trait::async_trait]
+impl Middleware for MetricsMiddleware {
+    fn needs_body(&self) -> bool {
+        false
+    }
+
+    async fn handle<R: RequestContext + 'static>(&self, req: R, next: Next<'_, R>) -> Response {
+        let method = method_label(req.method());
+        let route = match req
+            .matched_route_pattern()
+            .or_else(|| req.get_data_ref::<MatchedRoute>().map(|r| r.0.as_str()))
+        {
+            Some(pattern) if pattern.starts_with('/') => pattern.to_string(),
+            Some(pattern) => format!("/{pattern}"),
+            None => UNMATCHED_ROUTE.to_string(),
+        };
+
+        let started = Instant::now();
+        let response = next.run(req).await;
+        let elapsed = started.elapsed().as_secs_f64();
+
+        let route = route.as_str();
+        let status = response.status();
+        HTTP_REQUESTS.inc(&[method, route, status.as_str()]);
+        HTTP_DURATION.observe(&[method, route], elapsed);
+        response
+    }
+}
+
+/// Non-standard methods share one label value to keep cardinality bounded.
+fn method_label(method: &str) -> &'static str {
+    const STANDARD: &[&str] = &["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"];
+    STANDARD
+        .iter()
+        .find(|m| m.eq_ignore_ascii_case(method))
+        .copied()
+        .unwrap_or("OTHER")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::tests::MockRequest;
+    use http::StatusCode;
+
+    #[test]
+    fn renders_counters_and_histograms() {
+        let c = counter("test_render_total", "A test counter", &["kind"]);
+        c.inc(&["a"]);
+        c.inc_by(&["plain"], 2);
+        let h = histogram("test_render_seconds", "A histogram", &[], &[0.1, 1.0]);
+        h.observe(&[], 0.0625);
+        h.observe(&[], 0.5);
+        h.observe(&[], 5.0);
+
+        let text = render();
+        assert!(text.contains("# TYPE test_render counter\n"));
+        assert!(text.contains("test_render_total{kind=\"a\"} 1\n"));
+        assert!(text.contains("test_render_total{kind=\"plain\"} 2\n"));
+        assert!(text.contains("# TYPE test_render_seconds histogram\n"));
+        assert!(text.contains("test_render_seconds_bucket{le=\"0.1\"} 1\n"));
+        assert!(text.contains("test_render_seconds_bucket{le=\"1.0\"} 2\n"));
+        assert!(text.contains("test_render_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("test_render_seconds_sum{} 5.5625\n"));
+        assert!(text.contains("test_render_seconds_count{} 3\n"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn registering_twice_returns_the_same_family() {
+        let a = counter("test_dedup_total", "dedup", &[]);
+        let b = counter("test_dedup_total", "dedup", &[]);
+        a.inc(&[]);
+        assert_eq!(b.get(&[]), 1);
+    }
+
+    #[tokio::test]
+    async fn middleware_labels_by_route_pattern() {
+        let mut req = MockRequest::new("/metrics-test/users/42");
+        req.set_data(MatchedRoute("metrics-test/users/{id}".into()));
+        let next =
+            Next::new(
+                |_req: MockRequest| async move { Response::with_status(StatusCode::NOT_FOUND) },
+            );
+        MetricsMiddleware.handle(req, next).await;
+
+        let labels = ["POST", "/metrics-test/users/{id}", "404"];
+        assert_eq!(HTTP_REQUESTS.get(&labels), 1);
+        assert!(render().contains(
+            "http_request_duration_seconds_count{method=\"POST\",route=\"/metrics-test/users/{id}\"} 1\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn unrouted_requests_share_a_label() {
+        let next =
+            Next::new(
+                |_req: MockRequest| async move { Response::with_status(StatusCode::NOT_FOUND) },
+            );
+        let before = HTTP_REQUESTS.get(&["POST", "unmatched", "404"]);
+        MetricsMiddleware
+            .handle(MockRequest::new("/no/such/path"), next)
+            .await;
+        assert_eq!(HTTP_REQUESTS.get(&["POST", "unmatched", "404"]), before + 1);
+    }
+
+    #[test]
+    fn unknown_methods_share_a_label() {
+        assert_eq!(method_label("get"), "GET");
+        assert_eq!(method_label("PROPFIND"), "OTHER");
+    }
+}
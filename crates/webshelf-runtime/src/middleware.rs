@@ -1,4 +1,23 @@
-use crate::JwtClaims;
+//! Framework-agnostic middleware.
+//!
+//! A [`Middleware`] is written once against [`RequestContext`] + [`Next`] and
+//! runs identically on every runtime. Each adapter provides a bridge:
+//! - webshelf-axum: `middleware::run_middleware` / `middleware::with_middleware`
+//! - webshelf-salvo: `middleware::UnifiedMiddleware` / `middleware::with_middleware_hoop`
+//!
+//! The built-in auth / admin / rate-limit middleware are implemented here
+//! ([`AuthGuard`], [`AdminGuard`], [`RateLimitGuard`](crate::RateLimitGuard)); the
+//! adapter-level `auth_middleware` / `AuthMiddleware` etc. are thin wrappers.
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::net::IpAddr;
+use std::pin::Pin;
+
+use crate::{AuthUser, HttpError, JwtClaims, RequestContext, Response};
+
+/// Name of the cookie carrying the JWT (fallback when no `Authorization` header).
+const JWT_COOKIE: &str = "webshelf_jwt";
 
 /// Application state accessor for adapter-level middleware.
 ///
@@ -17,9 +36,630 @@ pub trait MiddlewareState: Clone + Send + Sync + 'static {
     /// Uses Redis cache (30s TTL) with DB fallback.
     /// Must query the **write database** to guarantee read-your-writes consistency.
     async fn check_token_version(&self, user_id: i64, token_version: i32) -> Result<(), String>;
+
+    /// Authenticate a bearer token that is not a session JWT, such as an OAuth access token
+    /// or a personal access token.
+    ///
+    /// Returns `None` when the token is not a kind the application issues. Implementations
+    /// do their own revocation checks and set [`AuthUser::scopes`]; `client_ip` is the
+    /// caller's address, for recording where a token was used. The default accepts none.
+    async fn authenticate_token(
+        &self,
+        _token: &str,
+        _client_ip: Option<IpAddr>,
+    ) -> Option<Result<AuthUser, String>> {
+        None
+    }
 }
 
 /// Validate JWT token using the state's secret.
 pub fn validate_token(state: &impl MiddlewareState, token: &str) -> Result<JwtClaims, String> {
     crate::validate_jwt(token, state.jwt_secret())
 }
+
+/// Framework-agnostic middleware.
+///
+/// Receives the request and a [`Next`] continuation. Call `next.run(req)` to
+/// pass control downstream, or return a [`Response`] directly to short-circuit.
+/// Request-scoped data injected via [`RequestContext::set_data`] is visible to
+/// downstream middleware and handlers on both runtimes.
+#[async_trait::async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    async fn handle<R: RequestContext + 'static>(&self, req: R, next: Next<'_, R>) -> Response;
+
+    /// Whether [`handle`](Self::handle) reads the request body.
+    ///
+    /// The axum and salvo bridges buffer the body only for middleware that returns `true`;
+    /// otherwise the body reaches the handler untouched and body reads inside `handle` see it
+    /// empty.
+    fn needs_body(&self) -> bool {
+        true
+    }
+}
+
+type BoxedContinuation<'a, R> =
+    Box<dyn FnOnce(R) -> Pin<Box<dyn Future<Output = Response> + Send + 'a>> + Send + 'a>;
+
+/// Continuation that runs the rest of the middleware chain and the handler.
+///
+/// Constructed by the adapter bridges; middleware only calls [`Next::run`].
+pub struct Next<'a, R> {
+    inner: BoxedContinuation<'a, R>,
+}
+
+impl<'a, R> Next<'a, R> {
+    pub fn new<F, Fut>(f: F) -> Self
+    where
+        F: FnOnce(R) -> Fut + Send + 'a,
+        Fut: Future<Output = Response> + Send + 'a,
+    {
+        Self {
+            inner: Box::new(move |req| Box::pin(f(req))),
+        }
+    }
+
+    /// Run the downstream chain with the (possibly modified) request.
+    pub async fn run(self, req: R) -> Response {
+        (self.inner)(req).await
+    }
+}
+
+// ── 认证中间件 ─────────────────────────────────────
+
+/// Authentication middleware — validates JWT from `Authorization` header or `webshelf_jwt` cookie,
+/// checks `token_version` against the current user version (logout-all support),
+/// and injects [`AuthUser`] into the request context.
+///
+/// A bearer token that is not a session JWT is passed to
+/// [`MiddlewareState::authenticate_token`] (delegated tokens are never read from the cookie).
+///
+/// The shared state `S` is read from the request context (`get_data::<S>()`), so the
+/// adapter must make it available before this middleware runs.
+/// Skips authentication for `/health` (and `/api/health`, so it is position-independent).
+pub struct AuthGuard<S>(PhantomData<fn() -> S>);
+
+impl<S> AuthGuard<S> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S> Default for AuthGuard<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Clone for AuthGuard<S> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: MiddlewareState> Middleware for AuthGuard<S> {
+    fn needs_body(&self) -> bool {
+        false
+    }
+
+    async fn handle<R: RequestContext + 'static>(&self, mut req: R, next: Next<'_, R>) -> Response {
+        // Skip authentication for public health endpoint.
+        // Check both with and without the /api prefix: axum strips the nest
+        // prefix (path is /health), salvo keeps it (path is /api/health).
+        let path = req.path();
+        if path == "/health" || path == "/api/health" {
+            return next.run(req).await;
+        }
+
+        let Some(state) = req.get_data::<S>() else {
+            tracing::error!("AuthGuard: state not found in request context");
+            return HttpError::internal("An unexpected error occurred").into();
+        };
+
+        let (token, from_header) = match extract_bearer_token(&req) {
+            Some(token) => (token, true),
+            None => match req.cookie(JWT_COOKIE) {
+                Some(token) => (token, false),
+                None => {
+                    return HttpError::unauthorized("Missing or invalid Authorization header")
+                        .into();
+                }
+            },
+        };
+
+        let claims = match validate_token(&state, &token) {
+            Ok(claims) => claims,
+            Err(e) => {
+                if from_header
+                    && let Some(result) = state.authenticate_token(&token, req.client_ip()).await
+                {
+                    return match result {
+                        Ok(user) => {
+                            tracing::Span::current().record("user_id", user.user_id.as_str());
+                            req.set_data(user);
+                            next.run(req).await
+                        }
+                        Err(e) => {
+                            tracing::warn!("Delegated token validation failed: {}", e);
+                            HttpError::unauthorized("Invalid or expired token").into()
+                        }
+                    };
+                }
+                tracing::warn!("Token validation failed: {}", e);
+                return HttpError::unauthorized("Invalid or expired token").into();
+            }
+        };
+
+        let user_id: i64 = match claims.sub.parse() {
+            Ok(id) => id,
+            Err(_) => {
+                tracing::warn!("Invalid user ID format in token: {}", claims.sub);
+                return HttpError::unauthorized("Invalid or expired token").into();
+            }
+        };
+
+        if let Err(e) = state
+            .check_token_version(user_id, claims.token_version)
+            .await
+        {
+            tracing::warn!("Token version validation failed: {}", e);
+            return HttpError::unauthorized("Invalid or expired token").into();
+        }
+
+        // Correlate the request span (see `RequestIdMiddleware`) with the user.
+        tracing::Span::current().record("user_id", user_id);
+        req.set_data(AuthUser::from(claims));
+        next.run(req).await
+    }
+}
+
+// ── 管理员权限守卫 ─────────────────────────────────
+
+/// Admin role guard — returns 403 if the authenticated user is not `admin` or `system`.
+/// Must run after [`AuthGuard`] (requires [`AuthUser`] in the request context).
+#[derive(Clone, Copy, Default)]
+pub struct AdminGuard;
+
+#[async_trait::async_trait]
+impl Middleware for AdminGuard {
+    fn needs_body(&self) -> bool {
+        false
+    }
+
+    async fn handle<R: RequestContext + 'static>(&self, req: R, next: Next<'_, R>) -> Response {
+        let is_admin = match req.get_data_ref::<AuthUser>() {
+            Some(user) => user.role == "admin" || user.role == "system",
+            None => return HttpError::unauthorized("Authentication required").into(),
+        };
+
+        if !is_admin {
+            return HttpError::forbidden("Admin privileges required").into();
+        }
+
+        next.run(req).await
+    }
+}
+
+// ── 辅助函数 ───────────────────────────────────────
+
+fn extract_bearer_token(req: &impl RequestContext) -> Option<String> {
+    let auth_value = req.header("authorization")?;
+
+    const BEARER_PREFIX: &[u8] = b"bearer ";
+    if auth_value.len() <= BEARER_PREFIX.len() {
+        return None;
+    }
+    if !auth_value.as_bytes()[..BEARER_PREFIX.len()].eq_ignore_ascii_case(BEARER_PREFIX) {
+        return None;
+    }
+    Some(auth_value[BEARER_PREFIX.len()..].to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use http::StatusCode;
+    use serde::de::DeserializeOwned;
+    use std::any::{Any, TypeId};
+    use std::collections::HashMap;
+
+    /// Minimal in-memory `RequestContext` for exercising middleware without a runtime.
+    #[derive(Default)]
+    pub(crate) struct MockRequest {
+        pub path: String,
+        pub headers: Vec<(String, String)>,
+        pub body: Bytes,
+        pub client_ip: Option<IpAddr>,
+        data: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    }
+
+    impl MockRequest {
+        pub(crate) fn new(path: &str) -> Self {
+            Self {
+                path: path.to_string(),
+                ..Default::default()
+            }
+        }
+
+        pub(crate) fn with_header(mut self, name: &str, value: &str) -> Self {
+            self.headers.push((name.to_string(), value.to_string()));
+            self
+        }
+    }
+
+    impl RequestContext for MockRequest {
+        fn method(&self) -> &str {
+            "POST"
+        }
+
+        fn path(&self) -> &str {
+            &self.path
+        }
+
+        fn client_ip(&self) -> Option<IpAddr> {
+            self.client_ip
+        }
+
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(h, _)| h.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+
+        fn matched_route_pattern(&self) -> Option<&str> {
+            None
+        }
+
+        fn parse_query<T: DeserializeOwned>(&self) -> Result<T, String> {
+            Err("no query".to_string())
+        }
+
+        async fn parse_json<T: DeserializeOwned>(&mut self) -> Result<T, String> {
+            serde_json::from_slice(&self.body).map_err(|e| e.to_string())
+        }
+
+        async fn parse_form<T: DeserializeOwned>(&mut self) -> Result<T, String> {
+            Err("no form".to_string())
+        }
+
+        async fn read_body_bytes(&mut self) -> Result<Bytes, String> {
+            Ok(self.body.clone())
+        }
+
+        fn get_data<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+            self.get_data_ref::<T>().cloned()
+        }
+
+        fn get_data_ref<T: Send + Sync + 'static>(&self) -> Option<&T> {
+            self.data.get(&TypeId::of::<T>())?.downcast_ref::<T>()
+        }
+
+        fn set_data<T: Clone + Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+            self.data
+                .insert(TypeId::of::<T>(), Box::new(value))
+                .and_then(|old| old.downcast::<T>().ok().map(|b| *b))
+        }
+
+        fn cookie(&self, name: &str) -> Option<String> {
+            self.header("cookie")?
+                .split(';')
+                .map(str::trim)
+                .filter_map(|c| cookie::Cookie::parse(c).ok())
+                .find(|c| c.name() == name)
+                .map(|c| c.value().to_string())
+        }
+    }
+
+    /// Terminal continuation: echoes the authenticated user id (or "anonymous").
+    pub(crate) fn echo_next<'a>() -> Next<'a, MockRequest> {
+        Next::new(|req: MockRequest| async move {
+            let user = req
+                .get_data_ref::<AuthUser>()
+                .map(|u| u.user_id.clone())
+                .unwrap_or_else(|| "anonymous".to_string());
+            let mut resp = Response::new();
+            resp.set_text_body(user);
+            resp
+        })
+    }
+
+    #[derive(Clone)]
+    struct TestState {
+        current_version: i32,
+    }
+
+    #[async_trait::async_trait]
+    impl MiddlewareState for TestState {
+        fn jwt_secret(&self) -> &str {
+            "test_secret"
+        }
+
+        fn cookie_secure(&self) -> bool {
+            false
+        }
+
+        async fn check_token_version(&self, _user_id: i64, version: i32) -> Result<(), String> {
+            if version == self.current_version {
+                Ok(())
+            } else {
+                Err("mismatch".to_string())
+            }
+        }
+
+        async fn authenticate_token(
+            &self,
+            token: &str,
+            client_ip: Option<IpAddr>,
+        ) -> Option<Result<AuthUser, String>> {
+            let user = match token {
+                // 仅接受来自回环地址的调用，用于确认客户端 IP 已传入
+                "delegated-local" if client_ip.is_some_and(|ip| ip.is_loopback()) => Ok(AuthUser {
+                    user_id: "10".to_string(),
+                    role: "user".to_string(),
+                    exp: 0,
+                    iat: 0,
+                    token_version: 1,
+                    remember: false,
+                    scopes: Some(vec![]),
+                }),
+                "delegated-local" => Err("wrong network".to_string()),
+                "delegated" => Ok(AuthUser {
+                    user_id: "9".to_string(),
+                    role: "user".to_string(),
+                    exp: 0,
+                    iat: 0,
+                    token_version: 1,
+                    remember: false,
+                    scopes: Some(vec!["users:read".to_string()]),
+                }),
+                "delegated-revoked" => Err("revoked".to_string()),
+                _ => return None,
+            };
+            Some(user)
+        }
+    }
+
+    fn token(sub: &str, role: &str, version: i32) -> String {
+        use jsonwebtoken::{EncodingKey, Header, encode};
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let claims = JwtClaims {
+            sub: sub.to_string(),
+            exp: now + 3600,
+            iat: now,
+            iss: "webshelf-server".to_string(),
+            aud: "webshelf".to_string(),
+            role: role.to_string(),
+            token_version: version,
+            remember: false,
+        };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(b"test_secret"),
+        )
+        .unwrap()
+    }
+
+    fn authed_request(path: &str, token: &str) -> MockRequest {
+        let mut req =
+            MockRequest::new(path).with_header("authorization", &format!("Bearer {token}"));
+        req.set_data(TestState { current_version: 1 });
+        req
+    }
+
+    fn body_text(resp: &Response) -> String {
+        String::from_utf8(resp.read_bytes().unwrap().to_vec()).unwrap()
+    }
+
+    // ── AuthGuard tests ──────────────────────────────────────
+
+    #[tokio::test]
+    async fn auth_guard_accepts_valid_bearer_token() {
+        let req = authed_request("/users/me", &token("42", "user", 1));
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body_text(&resp), "42");
+    }
+
+    #[tokio::test]
+    async fn auth_guard_accepts_jwt_cookie() {
+        let mut req = MockRequest::new("/users/me").with_header(
+            "cookie",
+            &format!("session=abc; webshelf_jwt={}", token("7", "user", 1)),
+        );
+        req.set_data(TestState { current_version: 1 });
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(body_text(&resp), "7");
+    }
+
+    #[tokio::test]
+    async fn auth_guard_rejects_missing_token() {
+        let mut req = MockRequest::new("/users/me");
+        req.set_data(TestState { current_version: 1 });
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn auth_guard_rejects_stale_token_version() {
+        let req = authed_request("/users/me", &token("42", "user", 0));
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn auth_guard_rejects_non_numeric_subject() {
+        let req = authed_request("/users/me", &token("abc", "user", 1));
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn auth_guard_accepts_delegated_bearer_token() {
+        let req = authed_request("/users/me", "delegated");
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body_text(&resp), "9");
+
+        let req = authed_request("/users/me", "delegated-revoked");
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn auth_guard_passes_client_ip_to_delegated_tokens() {
+        let req = authed_request("/users/me", "delegated-local");
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let mut req = authed_request("/users/me", "delegated-local");
+        req.client_ip = Some(IpAddr::from([127, 0, 0, 1]));
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body_text(&resp), "10");
+    }
+
+    #[tokio::test]
+    async fn auth_guard_ignores_delegated_token_in_cookie() {
+        let mut req = MockRequest::new("/users/me").with_header("cookie", "webshelf_jwt=delegated");
+        req.set_data(TestState { current_version: 1 });
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn auth_guard_skips_health_endpoint() {
+        for path in ["/health", "/api/health"] {
+            let resp = AuthGuard::<TestState>::new()
+                .handle(MockRequest::new(path), echo_next())
+                .await;
+            assert_eq!(resp.status(), StatusCode::OK, "path {path}");
+            assert_eq!(body_text(&resp), "anonymous");
+        }
+    }
+
+    #[tokio::test]
+    async fn auth_guard_without_state_is_internal_error() {
+        let req = MockRequest::new("/users/me").with_header(
+            "authorization",
+            &format!("Bearer {}", token("1", "user", 1)),
+        );
+        let resp = AuthGuard::<TestState>::new().handle(req, echo_next()).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    // ── AdminGuard tests ─────────────────────────────────────
+
+    fn request_with_role(role: &str) -> MockRequest {
+        let mut req = MockRequest::new("/users");
+        req.set_data(AuthUser {
+            user_id: "1".to_string(),
+            role: role.to_string(),
+            exp: 0,
+            iat: 0,
+            token_version: 1,
+            remember: false,
+            scopes: None,
+        });
+        req
+    }
+
+    #[tokio::test]
+    async fn admin_guard_allows_admin_and_system() {
+        for role in ["admin", "system"] {
+            let resp = AdminGuard
+                .handle(request_with_role(role), echo_next())
+                .await;
+            assert_eq!(resp.status(), StatusCode::OK, "role {role}");
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_guard_forbids_regular_user() {
+        let resp = AdminGuard
+            .handle(request_with_role("user"), echo_next())
+            .await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn admin_guard_requires_authentication() {
+        let resp = AdminGuard
+            .handle(MockRequest::new("/users"), echo_next())
+            .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn middleware_can_modify_downstream_response() {
+        struct Stamp;
+
+        #[async_trait::async_trait]
+        impl Middleware for Stamp {
+            async fn handle<R: RequestContext + 'static>(
+                &self,
+                req: R,
+                next: Next<'_, R>,
+            ) -> Response {
+                let mut resp = next.run(req).await;
+                resp.insert_header("x-stamp", "1");
+                resp
+            }
+        }
+
+        let resp = Stamp.handle(MockRequest::new("/"), echo_next()).await;
+        assert_eq!(resp.header("x-stamp"), Some("1"));
+        assert_eq!(body_text(&resp), "anonymous");
+    }
+
+    // ── extract_bearer_token tests ───────────────────────────
+
+    #[test]
+    fn extract_bearer_token_success() {
+        let req = MockRequest::new("/").with_header("authorization", "Bearer my-token");
+        assert_eq!(extract_bearer_token(&req), Some("my-token".to_string()));
+    }
+
+    #[test]
+    fn extract_bearer_token_lowercase_bearer() {
+        let req = MockRequest::new("/").with_header("authorization", "bearer my-token");
+        assert_eq!(extract_bearer_token(&req), Some("my-token".to_string()));
+    }
+
+    #[test]
+    fn extract_bearer_token_mixed_case() {
+        let req = MockRequest::new("/").with_header("authorization", "BEARER token-value");
+        assert_eq!(extract_bearer_token(&req), Some("token-value".to_string()));
+    }
+
+    #[test]
+    fn extract_bearer_token_missing_header() {
+        let req = MockRequest::new("/");
+        assert!(extract_bearer_token(&req).is_none());
+    }
+
+    #[test]
+    fn extract_bearer_token_wrong_scheme() {
+        let req = MockRequest::new("/").with_header("authorization", "Basic dXNlcjpwYXNz");
+        assert!(extract_bearer_token(&req).is_none());
+    }
+
+    #[test]
+    fn extract_bearer_token_empty_value() {
+        // "Bearer " is exactly BEARER_PREFIX.len() -> returns None
+        let req = MockRequest::new("/").with_header("authorization", "Bearer ");
+        assert!(extract_bearer_token(&req).is_none());
+    }
+
+    #[test]
+    fn next_is_send() {
+        fn assert_send<T: Send>(_t: &T) {}
+        let next = echo_next();
+        assert_send(&next);
+    }
+}
@@ -0,0 +1,324 @@
+//! multipart/form-data parsing (file uploads).
+//!
+//! ```ignore
+//! let mut form = req.parse_multipart(MultipartLimits::default().field("avatar", 2 * 1024 * 1024)).await?;
+//! while let Some(mut field) = form.next_field().await? {
+//!     if field.is_file() {
+//!         while let Some(chunk) = field.chunk().await? { /* write chunk */ }
+//!     } else {
+//!         let value = field.text().await?;
+//!     }
+//! }
+//! ```
+//!
+//! Adapters leave `multipart/form-data` bodies unread and hand the connection's
+//! [`RequestBodyStream`] to the parser, so [`Field::chunk`] yields data as it arrives instead of the
+//! whole upload being held in memory. The server-wide body limit (axum `RequestBodyLimitLayer`
+//! / salvo `max_body_size`, 10 MiB) still applies to the stream and surfaces as 413;
+//! [`MultipartLimits`] further tightens individual fields.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::pin::Pin;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use multer::{Constraints, SizeLimit};
+
+use crate::HttpError;
+
+/// Default per-field limit — matches the server-wide 10 MiB body limit.
+pub const DEFAULT_FIELD_LIMIT: u64 = 10 * 1024 * 1024;
+
+/// Request body as delivered by the adapter, chunk by chunk.
+pub type RequestBodyStream = Pin<Box<dyn Stream<Item = Result<Bytes, BodyError>> + Send>>;
+
+/// Failure while reading a [`RequestBodyStream`].
+#[derive(Debug)]
+pub enum BodyError {
+    /// The server-wide body limit was exceeded mid-stream.
+    TooLarge,
+    /// Any other transport error.
+    Read(String),
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge => f.write_str("request body is too large"),
+            Self::Read(msg) => write!(f, "failed to read request body: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for BodyError {}
+
+/// Whether `content_type` announces a `multipart/form-data` body.
+pub fn is_multipart(content_type: &str) -> bool {
+    content_type
+        .split(';')
+        .next()
+        .is_some_and(|essence| essence.trim().eq_ignore_ascii_case("multipart/form-data"))
+}
+
+/// Size limits applied while parsing a multipart body.
+#[derive(Debug, Clone)]
+pub struct MultipartLimits {
+    per_field: u64,
+    fields: Vec<(String, u64)>,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        Self {
+            per_field: DEFAULT_FIELD_LIMIT,
+            fields: Vec::new(),
+        }
+    }
+}
+
+impl MultipartLimits {
+    /// Limit applied to every field without an explicit [`field`](Self::field) limit.
+    pub fn per_field(mut self, limit: u64) -> Self {
+        self.per_field = limit;
+        self
+    }
+
+    /// Limit for the field named `name`.
+    pub fn field(mut self, name: impl Into<String>, limit: u64) -> Self {
+        self.fields.push((name.into(), limit));
+        self
+    }
+
+    fn into_constraints(self) -> Constraints {
+        let mut size = SizeLimit::new().per_field(self.per_field);
+        for (name, limit) in self.fields {
+            size = size.for_field(name, limit);
+        }
+        Constraints::new().size_limit(size)
+    }
+}
+
+/// Parsed multipart body; yields fields in order.
+pub struct Multipart {
+    inner: multer::Multipart<'static>,
+}
+
+impl Multipart {
+    /// Build from an already buffered body and the request's `Content-Type` header.
+    pub fn new(
+        body: Bytes,
+        content_type: &str,
+        limits: MultipartLimits,
+    ) -> Result<Self, HttpError> {
+        let stream = futures_util::stream::once(async move { Ok::<_, Infallible>(body) });
+        Self::build(stream, content_type, limits)
+    }
+
+    /// Build from the unread request body; parts are parsed as the stream is polled.
+    pub fn from_stream(
+        body: RequestBodyStream,
+        content_type: &str,
+        limits: MultipartLimits,
+    ) -> Result<Self, HttpError> {
+        Self::build(body, content_type, limits)
+    }
+
+    fn build<S, E>(
+        stream: S,
+        content_type: &str,
+        limits: MultipartLimits,
+    ) -> Result<Self, HttpError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+    {
+        let boundary = multer::parse_boundary(content_type)
+            .map_err(|_| HttpError::bad_request("Expected multipart/form-data request"))?;
+        Ok(Self {
+            inner: multer::Multipart::with_constraints(stream, boundary, limits.into_constraints()),
+        })
+    }
+
+    /// Next field, or `None` at the end of the body. The previous [`Field`] must be dropped
+    /// (or consumed) first; its unread data is skipped.
+    pub async fn next_field(&mut self) -> Result<Option<Field>, HttpError> {
+        let field = self.inner.next_field().await.map_err(map_error)?;
+        Ok(field.map(|inner| Field { inner }))
+    }
+}
+
+/// A single multipart field — a plain form value or a file part.
+pub struct Field {
+    inner: multer::Field<'static>,
+}
+
+impl Field {
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    /// Client-supplied file name (untrusted — never use it as a filesystem path directly).
+    pub fn file_name(&self) -> Option<&str> {
+        self.inner.file_name()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.inner.content_type().map(|mime| mime.essence_str())
+    }
+
+    /// Whether this part is a file upload (has a `filename` parameter).
+    pub fn is_file(&self) -> bool {
+        self.inner.file_name().is_some()
+    }
+
+    /// Next chunk of the field's data, as read from the body; `None` when the field is
+    /// exhausted.
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, HttpError> {
+        self.inner.chunk().await.map_err(map_error)
+    }
+
+    pub async fn bytes(self) -> Result<Bytes, HttpError> {
+        self.inner.bytes().await.map_err(map_error)
+    }
+
+    pub async fn text(self) -> Result<String, HttpError> {
+        self.inner.text().await.map_err(map_error)
+    }
+}
+
+fn map_error(err: multer::Error) -> HttpError {
+    match err {
+        multer::Error::FieldSizeExceeded { field_name, .. } => HttpError::payload_too_large(
+            format!("Field '{}' is too large", field_name.unwrap_or_default()),
+        ),
+        multer::Error::StreamSizeExceeded { .. } => {
+            HttpError::payload_too_large("Request body is too large")
+        }
+        multer::Error::StreamReadFailed(source)
+            if matches!(source.downcast_ref(), Some(BodyError::TooLarge)) =>
+        {
+            HttpError::payload_too_large("Request body is too large")
+        }
+        other => HttpError::bad_request(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::StatusCode;
+
+    const CT: &str = "multipart/form-data; boundary=XBOUNDARY";
+
+    fn body() -> Bytes {
+        Bytes::from_static(
+            b"--XBOUNDARY\r\n\
+Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+hello\r\n\
+--XBOUNDARY\r\n\
+Content-Disposition: form-data; name=\"avatar\"; filename=\"a.png\"\r\n\
+Content-Type: image/png\r\n\r\n\
+0123456789\r\n\
+--XBOUNDARY--\r\n",
+        )
+    }
+
+    #[tokio::test]
+    async fn parses_text_and_file_fields() {
+        let mut form = Multipart::new(body(), CT, MultipartLimits::default()).unwrap();
+
+        let title = form.next_field().await.unwrap().unwrap();
+        assert_eq!(title.name(), Some("title"));
+        assert!(!title.is_file());
+        assert_eq!(title.text().await.unwrap(), "hello");
+
+        let mut avatar = form.next_field().await.unwrap().unwrap();
+        assert_eq!(avatar.file_name(), Some("a.png"));
+        assert_eq!(avatar.content_type(), Some("image/png"));
+        let mut data = Vec::new();
+        while let Some(chunk) = avatar.chunk().await.unwrap() {
+            data.extend_from_slice(&chunk);
+        }
+        assert_eq!(data, b"0123456789");
+        drop(avatar);
+
+        assert!(form.next_field().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn per_field_limit_returns_413() {
+        let limits = MultipartLimits::default().field("avatar", 4);
+        let mut form = Multipart::new(body(), CT, limits).unwrap();
+        // "title" is within the default limit.
+        form.next_field().await.unwrap().unwrap();
+        let avatar = form.next_field().await.unwrap().unwrap();
+        let err = avatar.bytes().await.unwrap_err();
+        assert_eq!(err.status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    /// `body()` split into small chunks, optionally followed by a read error.
+    fn chunked(fail: Option<BodyError>) -> RequestBodyStream {
+        let mut items: Vec<Result<Bytes, BodyError>> = body()
+            .chunks(7)
+            .map(|c| Ok(Bytes::copy_from_slice(c)))
+            .collect();
+        if let Some(err) = fail {
+            items.truncate(3);
+            items.push(Err(err));
+        }
+        Box::pin(futures_util::stream::iter(items))
+    }
+
+    #[tokio::test]
+    async fn parses_fields_from_a_chunked_stream() {
+        let mut form =
+            Multipart::from_stream(chunked(None), CT, MultipartLimits::default()).unwrap();
+        assert_eq!(
+            form.next_field()
+                .await
+                .unwrap()
+                .unwrap()
+                .text()
+                .await
+                .unwrap(),
+            "hello"
+        );
+        let avatar = form.next_field().await.unwrap().unwrap();
+        assert_eq!(avatar.bytes().await.unwrap(), "0123456789");
+        assert!(form.next_field().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn stream_errors_map_to_status() {
+        for (err, status) in [
+            (BodyError::TooLarge, StatusCode::PAYLOAD_TOO_LARGE),
+            (BodyError::Read("reset".into()), StatusCode::BAD_REQUEST),
+        ] {
+            let mut form =
+                Multipart::from_stream(chunked(Some(err)), CT, MultipartLimits::default()).unwrap();
+            let err = match form.next_field().await {
+                Ok(Some(field)) => field.bytes().await.unwrap_err(),
+                Ok(None) => panic!("stream error was swallowed"),
+                Err(err) => err,
+            };
+            assert_eq!(err.status, status);
+        }
+    }
+
+    #[test]
+    fn detects_multipart_content_type() {
+        assert!(is_multipart(CT));
+        assert!(is_multipart("Multipart/Form-Data"));
+        assert!(!is_multipart("multipart/mixed; boundary=x"));
+        assert!(!is_multipart("application/json"));
+    }
+
+    #[test]
+    fn non_multipart_content_type_is_rejected() {
+        let err = Multipart::new(body(), "application/json", MultipartLimits::default())
+            .err()
+            .unwrap();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,698 @@
+//! OpenAPI 3.1 document generation.
+//!
+//! Operations are described next to route registration with [`Operation`] and collected into
+//! an [`OpenApi`] document; request/response types derive [`JsonSchema`]:
+//!
+//! ```ignore
+//! #[derive(Deserialize, JsonSchema)]
+//! pub struct CreateUserRequest { email: String, name: String }
+//!
+//! let doc = OpenApi::default().post(
+//!     "/users",
+//!     Operation::new("Create user")
+//!         .tag("users")
+//!         .request_body::<CreateUserRequest>()
+//!         .response::<UserResponse>(StatusCode::CREATED, "User created")
+//!         .error(StatusCode::CONFLICT, "Email already registered"),
+//! );
+//! ```
+//!
+//! 文档只依赖本模块构建，与 axum / salvo 无关：无论启用哪个运行时 feature，输出的 JSON 完全一致。
+//! Schema 使用 JSON Schema 2020-12（OpenAPI 3.1 的 schema 方言），具名类型统一放入
+//! `components/schemas` 并通过 `$ref` 引用。
+
+use std::collections::BTreeMap;
+
+use http::{Method, StatusCode};
+use schemars::generate::{SchemaGenerator, SchemaSettings};
+use schemars::{JsonSchema, Schema};
+use serde_json::{Map, Value, json};
+
+pub use schemars;
+
+/// OpenAPI version emitted in the document.
+pub const OPENAPI_VERSION: &str = "3.1.0";
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+fn schema_ref<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    generator.subschema_for::<T>()
+}
+
+fn inline_schema<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    T::json_schema(generator)
+}
+
+/// RFC 7807 body of every [`HttpError`](crate::HttpError) response.
+#[derive(JsonSchema)]
+#[schemars(rename = "Error")]
+#[allow(dead_code)]
+struct ErrorBody {
+    /// Problem type URI (`about:blank`).
+    r#type: String,
+    /// HTTP status reason phrase.
+    title: String,
+    /// HTTP status code.
+    status: u16,
+    /// Human-readable explanation.
+    detail: String,
+    /// Machine-readable error type, e.g. `bad_request`.
+    error: String,
+    /// Human-readable message (same as `detail`).
+    message: String,
+    /// Stable machine-readable error code, e.g. `email_taken`.
+    code: Option<String>,
+    /// Per-field validation errors.
+    errors: Option<BTreeMap<String, Vec<FieldErrorBody>>>,
+}
+
+#[derive(JsonSchema)]
+#[schemars(rename = "FieldError")]
+#[allow(dead_code)]
+struct FieldErrorBody {
+    /// Failed rule, e.g. `length` or `email`.
+    code: String,
+    message: Option<String>,
+}
+
+struct PathParam {
+    name: String,
+    description: String,
+    schema: SchemaFn,
+}
+
+struct ResponseSpec {
+    description: String,
+    content_type: &'static str,
+    schema: Option<SchemaFn>,
+}
+
+/// Schema annotations for a single route (one path + method).
+pub struct Operation {
+    summary: String,
+    description: Option<String>,
+    operation_id: Option<String>,
+    tags: Vec<String>,
+    path_params: Vec<PathParam>,
+    query: Option<SchemaFn>,
+    request_body: Option<SchemaFn>,
+    responses: BTreeMap<u16, ResponseSpec>,
+    /// Security requirements: scheme name and required scopes
+    security: Vec<(String, Vec<String>)>,
+    deprecated: bool,
+}
+
+impl Operation {
+    pub fn new(summary: impl Into<String>) -> Self {
+        Self {
+            summary: summary.into(),
+            description: None,
+            operation_id: None,
+            tags: Vec::new(),
+            path_params: Vec::new(),
+            query: None,
+            request_body: None,
+            responses: BTreeMap::new(),
+            security: Vec::new(),
+            deprecated: false,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Stable identifier used by client generators (e.g. `listUsers`).
+    pub fn operation_id(mut self, id: impl Into<String>) -> Self {
+        self.operation_id = Some(id.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Path parameter `{name}` — always required.
+    pub fn path_param<T: JsonSchema>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.path_params.push(PathParam {
+            name: name.into(),
+            description: description.into(),
+            schema: schema_ref::<T>,
+        });
+        self
+    }
+
+    /// Query string described by a struct — each field becomes one `in: query` parameter.
+    pub fn query<T: JsonSchema>(mut self) -> Self {
+        self.query = Some(inline_schema::<T>);
+        self
+    }
+
+    /// Required JSON request body.
+    pub fn request_body<T: JsonSchema>(mut self) -> Self {
+        self.request_body = Some(schema_ref::<T>);
+        self
+    }
+
+    /// Response with a JSON body of type `T`.
+    pub fn response<T: JsonSchema>(
+        mut self,
+        status: StatusCode,
+        description: impl Into<String>,
+    ) -> Self {
+        self.responses.insert(
+            status.as_u16(),
+            ResponseSpec {
+                description: description.into(),
+                content_type: "application/json",
+                schema: Some(schema_ref::<T>),
+            },
+        );
+        self
+    }
+
+    /// Response without a body.
+    pub fn response_empty(mut self, status: StatusCode, description: impl Into<String>) -> Self {
+        self.responses.insert(
+            status.as_u16(),
+            ResponseSpec {
+                description: description.into(),
+                content_type: "application/json",
+                schema: None,
+            },
+        );
+        self
+    }
+
+    /// Error response using the shared `application/problem+json` body.
+    pub fn error(mut self, status: StatusCode, description: impl Into<String>) -> Self {
+        self.responses.insert(
+            status.as_u16(),
+            ResponseSpec {
+                description: description.into(),
+                content_type: crate::PROBLEM_JSON_CONTENT_TYPE,
+                schema: Some(schema_ref::<ErrorBody>),
+            },
+        );
+        self
+    }
+
+    /// Accept credentials from the named security scheme. Calling it several times lists
+    /// alternatives — any one of them satisfies the requirement.
+    pub fn security(mut self, scheme: impl Into<String>) -> Self {
+        self.security.push((scheme.into(), Vec::new()));
+        self
+    }
+
+    /// Like [`security`](Self::security), for OAuth 2.0 / OpenID Connect schemes whose tokens
+    /// need `scopes`.
+    pub fn security_scopes(mut self, scheme: impl Into<String>, scopes: &[&str]) -> Self {
+        self.security.push((
+            scheme.into(),
+            scopes.iter().map(|s| s.to_string()).collect(),
+        ));
+        self
+    }
+
+    pub fn deprecated(mut self) -> Self {
+        self.deprecated = true;
+        self
+    }
+
+    /// Request-side schemas follow the `Deserialize` contract, response schemas the
+    /// `Serialize` one (so e.g. `#[serde(skip_serializing)]` fields are left out of responses).
+    fn to_json(&self, requests: &mut SchemaGenerator, responses: &mut SchemaGenerator) -> Value {
+        let mut op = Map::new();
+        op.insert("summary".into(), json!(self.summary));
+        if let Some(description) = &self.description {
+            op.insert("description".into(), json!(description));
+        }
+        if let Some(id) = &self.operation_id {
+            op.insert("operationId".into(), json!(id));
+        }
+        if !self.tags.is_empty() {
+            op.insert("tags".into(), json!(self.tags));
+        }
+
+        let mut parameters = Vec::new();
+        for param in &self.path_params {
+            parameters.push(json!({
+                "name": param.name,
+                "in": "path",
+                "required": true,
+                "description": param.description,
+                "schema": (param.schema)(requests),
+            }));
+        }
+        if let Some(query) = self.query {
+            parameters.extend(query_parameters((query)(requests)));
+        }
+        if !parameters.is_empty() {
+            op.insert("parameters".into(), Value::Array(parameters));
+        }
+
+        if let Some(body) = self.request_body {
+            op.insert(
+                "requestBody".into(),
+                json!({
+                    "required": true,
+                    "content": { "application/json": { "schema": (body)(requests) } },
+                }),
+            );
+        }
+
+        let mut response_map = Map::new();
+        for (status, spec) in &self.responses {
+            let mut value = json!({ "description": spec.description });
+            if let Some(schema) = spec.schema {
+                value["content"] = json!({ spec.content_type: { "schema": (schema)(responses) } });
+            }
+            response_map.insert(status.to_string(), value);
+        }
+        op.insert("responses".into(), Value::Object(response_map));
+
+        if !self.security.is_empty() {
+            let requirements: Vec<Value> = self
+                .security
+                .iter()
+                .map(|(scheme, scopes)| json!({ scheme: scopes }))
+                .collect();
+            op.insert("security".into(), Value::Array(requirements));
+        }
+        if self.deprecated {
+            op.insert("deprecated".into(), json!(true));
+        }
+        Value::Object(op)
+    }
+}
+
+/// Expand an object schema into one query parameter per property.
+fn query_parameters(schema: Schema) -> Vec<Value> {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    properties
+        .iter()
+        .map(|(name, prop)| {
+            let mut prop = prop.clone();
+            let description = prop.as_object_mut().and_then(|p| p.remove("description"));
+            let mut value = json!({
+                "name": name,
+                "in": "query",
+                "required": required.contains(&name.as_str()),
+                "schema": prop,
+            });
+            if let Some(description) = description {
+                value["description"] = description;
+            }
+            value
+        })
+        .collect()
+}
+
+/// An OpenAPI 3.1 document under construction.
+///
+/// Route modules build fragments with `OpenApi::default()` using paths relative to their
+/// router, and the fragments are combined with [`nest`](Self::nest) / [`merge`](Self::merge)
+/// the same way the routers are.
+#[derive(Default)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    description: Option<String>,
+    servers: Vec<String>,
+    paths: BTreeMap<String, BTreeMap<String, Operation>>,
+    security_schemes: BTreeMap<String, Value>,
+}
+
+impl OpenApi {
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Base URL entry in `servers` (e.g. `https://api.example.com`).
+    pub fn server(mut self, url: impl Into<String>) -> Self {
+        self.servers.push(url.into());
+        self
+    }
+
+    /// Register a security scheme object under `components/securitySchemes`.
+    pub fn security_scheme(mut self, name: impl Into<String>, scheme: Value) -> Self {
+        self.security_schemes.insert(name.into(), scheme);
+        self
+    }
+
+    /// Describe the route `method path`. A later operation for the same route replaces the earlier one.
+    pub fn operation(mut self, method: Method, path: &str, op: Operation) -> Self {
+        self.paths
+            .entry(path.to_string())
+            .or_default()
+            .insert(method.as_str().to_ascii_lowercase(), op);
+        self
+    }
+
+    pub fn get(self, path: &str, op: Operation) -> Self {
+        self.operation(Method::GET, path, op)
+    }
+
+    pub fn post(self, path: &str, op: Operation) -> Self {
+        self.operation(Method::POST, path, op)
+    }
+
+    pub fn put(self, path: &str, op: Operation) -> Self {
+        self.operation(Method::PUT, path, op)
+    }
+
+    pub fn delete(self, path: &str, op: Operation) -> Self {
+        self.operation(Method::DELETE, path, op)
+    }
+
+    /// Add every operation of `other` under `prefix` (mirrors `Router::nest`).
+    pub fn nest(mut self, prefix: &str, other: OpenApi) -> Self {
+        let prefix = prefix.trim_end_matches('/');
+        for (path, ops) in other.paths {
+            let full = if path == "/" {
+                prefix.to_string()
+            } else {
+                format!("{prefix}{path}")
+            };
+            self.paths.entry(full).or_default().extend(ops);
+        }
+        self.security_schemes.extend(other.security_schemes);
+        self
+    }
+
+    /// Add every operation of `other` at its own path (mirrors `Router::merge`).
+    pub fn merge(self, other: OpenApi) -> Self {
+        self.nest("", other)
+    }
+
+    /// `(method, path)` of every documented operation, sorted by path.
+    pub fn operations(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.paths.iter().flat_map(|(path, ops)| {
+            ops.keys()
+                .map(move |method| (method.as_str(), path.as_str()))
+        })
+    }
+
+    /// Render the document as JSON.
+    pub fn to_json(&self) -> Value {
+        let settings = SchemaSettings::draft2020_12().with(|s| {
+            s.definitions_path = "/components/schemas".into();
+            s.meta_schema = None;
+        });
+        let mut requests = settings.clone().for_deserialize().into_generator();
+        let mut responses = settings.for_serialize().into_generator();
+
+        let mut paths = Map::new();
+        for (path, ops) in &self.paths {
+            let item: Map<String, Value> = ops
+                .iter()
+                .map(|(method, op)| (method.clone(), op.to_json(&mut requests, &mut responses)))
+                .collect();
+            paths.insert(path.clone(), Value::Object(item));
+        }
+
+        let mut info = json!({ "title": self.title, "version": self.version });
+        if let Some(description) = &self.description {
+            info["description"] = json!(description);
+        }
+
+        let mut components = Map::new();
+        // 同名类型在请求/响应两侧只保留一份；响应侧（Serialize 契约）优先。
+        let mut schemas = requests.take_definitions(true);
+        schemas.extend(responses.take_definitions(true));
+        if !schemas.is_empty() {
+            components.insert("schemas".into(), Value::Object(schemas));
+        }
+        if !self.security_schemes.is_empty() {
+            components.insert("securitySchemes".into(), json!(self.security_schemes));
+        }
+
+        let mut doc = json!({
+            "openapi": OPENAPI_VERSION,
+            "info": info,
+            "paths": paths,
+        });
+        if !self.servers.is_empty() {
+            let servers: Vec<Value> = self
+                .servers
+                .iter()
+                .map(|url| json!({ "url": url }))
+                .collect();
+            doc["servers"] = Value::Array(servers);
+        }
+        if !components.is_empty() {
+            doc["components"] = Value::Object(components);
+        }
+        doc
+    }
+}
+
+/// Standalone HTML page rendering the document at `spec_url` with the Scalar API reference UI.
+///
+/// 页面本身内嵌在二进制中，UI 脚本从 jsDelivr CDN 加载。
+/// 传入 `nonce`（见 [`CspNonce`](crate::CspNonce)）时两个 `<script>` 均带 `nonce` 属性，
+/// 以便在启用 CSP 时放行。
+pub fn ui_html(title: &str, spec_url: &str, nonce: Option<&str>) -> String {
+    let nonce_attr = nonce
+        .map(|n| format!(r#" nonce="{}""#, escape_html(n)))
+        .unwrap_or_default();
+    format!(
+        r#"<!doctype html>
+<html>
+  <head>
+    <title>{title}</title>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+  </head>
+  <body>
+    <script id="api-reference" data-url="{spec_url}"{nonce_attr}></script>
+    <script src="https://cdn.jsdelivr.net/npm/@scalar/api-reference"{nonce_attr}></script>
+  </body>
+</html>
+"#,
+        title = escape_html(title),
+        spec_url = escape_html(spec_url),
+    )
+}
+
+fn escape_html(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct CreateItem {
+        /// Display name
+        name: String,
+        tags: Option<Vec<String>>,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Item {
+        id: i64,
+        name: String,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Page {
+        /// Page number (1-based)
+        page: u64,
+        per_page: Option<u64>,
+    }
+
+    fn items_doc() -> OpenApi {
+        OpenApi::default()
+            .get(
+                "/items",
+                Operation::new("List items")
+                    .tag("items")
+                    .query::<Page>()
+                    .response::<Vec<Item>>(StatusCode::OK, "Items"),
+            )
+            .post(
+                "/items",
+                Operation::new("Create item")
+                    .operation_id("createItem")
+                    .request_body::<CreateItem>()
+                    .response::<Item>(StatusCode::CREATED, "Created")
+                    .error(StatusCode::BAD_REQUEST, "Validation failed")
+                    .security("bearerAuth")
+                    .security_scopes("oauth2", &["items:write"]),
+            )
+            .delete(
+                "/items/{id}",
+                Operation::new("Delete item")
+                    .path_param::<i64>("id", "Item id")
+                    .response_empty(StatusCode::NO_CONTENT, "Deleted"),
+            )
+    }
+
+    #[test]
+    fn document_has_openapi_31_header() {
+        let doc = OpenApi::new("Demo", "1.0.0").description("demo").to_json();
+        assert_eq!(doc["openapi"], "3.1.0");
+        assert_eq!(doc["info"]["title"], "Demo");
+        assert_eq!(doc["info"]["version"], "1.0.0");
+        assert_eq!(doc["info"]["description"], "demo");
+        assert!(doc.get("components").is_none());
+    }
+
+    #[test]
+    fn named_types_are_referenced_from_components() {
+        let doc = OpenApi::new("Demo", "1").merge(items_doc()).to_json();
+        let post = &doc["paths"]["/items"]["post"];
+        assert_eq!(post["operationId"], "createItem");
+        assert_eq!(
+            post["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/CreateItem"
+        );
+        assert_eq!(
+            post["responses"]["201"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/Item"
+        );
+        assert_eq!(
+            post["responses"]["400"]["content"]["application/problem+json"]["schema"]["$ref"],
+            "#/components/schemas/Error"
+        );
+        assert_eq!(
+            post["security"],
+            json!([{ "bearerAuth": [] }, { "oauth2": ["items:write"] }])
+        );
+
+        let schemas = &doc["components"]["schemas"];
+        assert_eq!(schemas["CreateItem"]["required"], json!(["name"]));
+        assert_eq!(
+            schemas["CreateItem"]["properties"]["name"]["description"],
+            "Display name"
+        );
+        assert!(schemas["Error"]["properties"]["message"].is_object());
+        assert_eq!(schemas["Error"]["properties"]["status"]["type"], "integer");
+    }
+
+    #[test]
+    fn query_struct_expands_to_parameters() {
+        let doc = OpenApi::new("Demo", "1").merge(items_doc()).to_json();
+        let params = doc["paths"]["/items"]["get"]["parameters"]
+            .as_array()
+            .unwrap();
+        let page = params.iter().find(|p| p["name"] == "page").unwrap();
+        assert_eq!(page["in"], "query");
+        assert_eq!(page["required"], true);
+        assert_eq!(page["description"], "Page number (1-based)");
+        let per_page = params.iter().find(|p| p["name"] == "per_page").unwrap();
+        assert_eq!(per_page["required"], false);
+    }
+
+    #[test]
+    fn path_params_and_empty_responses() {
+        let doc = OpenApi::new("Demo", "1").merge(items_doc()).to_json();
+        let delete = &doc["paths"]["/items/{id}"]["delete"];
+        assert_eq!(delete["parameters"][0]["in"], "path");
+        assert_eq!(delete["parameters"][0]["required"], true);
+        assert_eq!(
+            delete["responses"]["204"],
+            json!({ "description": "Deleted" })
+        );
+    }
+
+    #[test]
+    fn nest_prefixes_paths() {
+        let doc = OpenApi::new("Demo", "1")
+            .nest("/api/", items_doc())
+            .nest("/root", OpenApi::default().get("/", Operation::new("Root")));
+        let ops: Vec<_> = doc.operations().collect();
+        assert_eq!(
+            ops,
+            vec![
+                ("get", "/api/items"),
+                ("post", "/api/items"),
+                ("delete", "/api/items/{id}"),
+                ("get", "/root"),
+            ]
+        );
+    }
+
+    #[test]
+    fn response_schemas_follow_serialize_contract() {
+        #[derive(serde::Serialize, JsonSchema)]
+        #[allow(dead_code)]
+        struct Token {
+            token: String,
+            #[serde(skip_serializing)]
+            refresh_token: String,
+        }
+
+        let doc = OpenApi::new("Demo", "1")
+            .post(
+                "/login",
+                Operation::new("Login").response::<Token>(StatusCode::OK, "Logged in"),
+            )
+            .to_json();
+        let token = &doc["components"]["schemas"]["Token"]["properties"];
+        assert!(token.get("token").is_some());
+        assert!(token.get("refresh_token").is_none());
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let a = OpenApi::new("Demo", "1").merge(items_doc()).to_json();
+        let b = OpenApi::new("Demo", "1").merge(items_doc()).to_json();
+        assert_eq!(
+            serde_json::to_string(&a).unwrap(),
+            serde_json::to_string(&b).unwrap()
+        );
+    }
+
+    #[test]
+    fn ui_html_escapes_values() {
+        let html = ui_html("API <docs>", "/openapi.json?a=1&b=\"2\"", None);
+        assert!(html.contains("<title>API &lt;docs&gt;</title>"));
+        assert!(html.contains(r#"data-url="/openapi.json?a=1&amp;b=&quot;2&quot;""#));
+        assert!(!html.contains("nonce="));
+    }
+
+    #[test]
+    fn ui_html_marks_scripts_with_nonce() {
+        let html = ui_html("API", "/openapi.json", Some("abc123"));
+        assert_eq!(html.matches(r#"nonce="abc123""#).count(), 2);
+    }
+}
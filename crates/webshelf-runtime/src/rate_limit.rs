@@ -1,9 +1,32 @@
+use std::sync::LazyLock;
+
 use distributed_ratelimit::RedisRateLimiter;
 
+use crate::metrics::{self, CounterVec};
+use crate::middleware::{Middleware, Next};
+use crate::{HttpError, RequestContext, Response};
+
+static REJECTIONS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "rate_limit_rejections_total",
+        "Requests rejected by a rate limiter, by key prefix and limiter kind (ip / email)",
+        &["key_prefix", "kind"],
+    )
+});
+
+static ERRORS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "rate_limit_errors_total",
+        "Rate-limit checks that failed on Redis, by key prefix",
+        &["key_prefix"],
+    )
+});
+
 /// Per-endpoint rate-limit parameters.
 ///
 /// Shared between webshelf-axum and webshelf-salvo adapters so that route
 /// definitions (in the server crate) do not need per-framework replicas.
+/// Also implements [`Middleware`], so the same limiter logic runs on both runtimes.
 #[derive(Clone)]
 pub struct RateLimitGuard {
     pub limiter: RedisRateLimiter,
@@ -13,3 +36,193 @@ pub struct RateLimitGuard {
     pub email_window_seconds: u64,
     pub key_prefix: &'static str,
 }
+
+impl RateLimitGuard {
+    /// Check one limiter key. Returns `Some(response)` when the request must be rejected.
+    async fn check_key(&self, kind: &str, key: &str, max: u64, window: u64) -> Option<Response> {
+        match self.limiter.check(key, max, window).await {
+            Ok(true) => None,
+            Ok(false) => {
+                REJECTIONS.inc(&[self.key_prefix, &kind.to_ascii_lowercase()]);
+                tracing::warn!(
+                    "Rate limit exceeded ({}) for {}: {}",
+                    kind,
+                    self.key_prefix,
+                    key
+                );
+                Some(
+                    HttpError::too_many_requests("Too many requests. Please try again later.")
+                        .into(),
+                )
+            }
+            Err(e) => {
+                ERRORS.inc(&[self.key_prefix]);
+                tracing::error!(
+                    "Rate-limit Redis error ({}) for {}: {:?}",
+                    kind,
+                    self.key_prefix,
+                    e
+                );
+                if self.limiter.fail_open() {
+                    None
+                } else {
+                    Some(HttpError::internal("An unexpected error occurred").into())
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RateLimitGuard {
+    // 仅邮箱维度限流需要读取 body
+    fn needs_body(&self) -> bool {
+        self.email_max_requests.is_some()
+    }
+
+    async fn handle<R: RequestContext + 'static>(&self, mut req: R, next: Next<'_, R>) -> Response {
+        if !self.limiter.is_available() {
+            return next.run(req).await;
+        }
+
+        // 1. IP-based check — client_ip() only trusts forwarding headers from trusted proxies
+        if let Some(ip) = req.client_ip() {
+            let ip_key = format!("{}:ip:{}", self.key_prefix, ip);
+            if let Some(rejected) = self
+                .check_key("IP", &ip_key, self.ip_max_requests, self.ip_window_seconds)
+                .await
+            {
+                return rejected;
+            }
+        }
+
+        // 2. Email-based check
+        if let Some(email_max) = self.email_max_requests {
+            let bytes = match req.read_body_bytes().await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to read body for rate limiting ({}): {}",
+                        self.key_prefix,
+                        e
+                    );
+                    return HttpError::internal("An unexpected error occurred").into();
+                }
+            };
+
+            if let Some(email) = extract_email_from_body(&bytes) {
+                let email_key = format!("{}:email:{}", self.key_prefix, email);
+                if let Some(rejected) = self
+                    .check_key("email", &email_key, email_max, self.email_window_seconds)
+                    .await
+                {
+                    return rejected;
+                }
+            }
+        }
+
+        next.run(req).await
+    }
+}
+
+fn extract_email_from_body(bytes: &[u8]) -> Option<String> {
+    // Try JSON first
+    if let Ok(val) = serde_json::from_slice::<serde_json::Value>(bytes)
+        && let Some(email) = val.get("email")?.as_str()
+    {
+        return Some(email.to_lowercase());
+    }
+    // Fallback to form-encoded (login endpoint accepts both content types)
+    serde_urlencoded::from_bytes::<std::collections::HashMap<String, String>>(bytes)
+        .ok()?
+        .remove("email")
+        .map(|e| e.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::tests::{MockRequest, echo_next};
+    use distributed_ratelimit::RateLimitConfig;
+    use http::StatusCode;
+
+    fn disabled_guard() -> RateLimitGuard {
+        RateLimitGuard {
+            limiter: RedisRateLimiter::disabled(RateLimitConfig::default()),
+            ip_max_requests: 1,
+            ip_window_seconds: 60,
+            email_max_requests: Some(1),
+            email_window_seconds: 60,
+            key_prefix: "test",
+        }
+    }
+
+    #[tokio::test]
+    async fn disabled_limiter_passes_through() {
+        let guard = disabled_guard();
+        for _ in 0..3 {
+            let resp = guard.handle(MockRequest::new("/login"), echo_next()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+    }
+
+    // ── extract_email_from_body tests ───────────────────────────
+
+    #[test]
+    fn extract_email_from_json_body() {
+        let body = b"{\"email\": \"user@example.com\"}";
+        assert_eq!(
+            extract_email_from_body(body),
+            Some("user@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn extract_email_from_json_body_normalizes_case() {
+        let body = b"{\"email\": \"User@Example.COM\"}";
+        assert_eq!(
+            extract_email_from_body(body),
+            Some("user@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn extract_email_from_form_body() {
+        let body = b"email=user@example.com&password=secret";
+        assert_eq!(
+            extract_email_from_body(body),
+            Some("user@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn extract_email_from_form_body_normalizes_case() {
+        let body = b"email=User@Example.COM&password=secret";
+        assert_eq!(
+            extract_email_from_body(body),
+            Some("user@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn extract_email_no_email_field_returns_none() {
+        let body = b"{\"name\": \"test\"}";
+        assert!(extract_email_from_body(body).is_none());
+    }
+
+    #[test]
+    fn extract_email_from_empty_body_returns_none() {
+        let body = b"";
+        assert!(extract_email_from_body(body).is_none());
+    }
+
+    #[test]
+    fn extract_email_from_invalid_json_tries_form_fallback() {
+        // Not valid JSON but valid form-encoded
+        let body = b"email=fallback@example.com";
+        assert_eq!(
+            extract_email_from_body(body),
+            Some("fallback@example.com".to_string())
+        );
+    }
+}
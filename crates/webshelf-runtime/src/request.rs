@@ -1,11 +1,18 @@
 use bytes::Bytes;
 use serde::de::DeserializeOwned;
+use std::future::Future;
 use std::net::IpAddr;
 use std::str::FromStr;
 
+use crate::multipart::{Multipart, MultipartLimits, RequestBodyStream};
+use crate::{HttpError, Response, WebSocket};
+
 /// Unified request context — each adapter implements this on its native Request type.
 /// Body parsing methods (parse_json / parse_form / read_body_bytes) are safe to call multiple times.
-#[allow(async_fn_in_trait)]
+///
+/// Async methods are declared as `fn -> impl Future + Send` (adapters may still implement
+/// them with `async fn`) so that code generic over `RequestContext` — e.g.
+/// [`Middleware`](crate::Middleware) implementations — produces `Send` futures.
 pub trait RequestContext: Send + Sync {
     /// HTTP 方法（如 "GET", "POST"）
     fn method(&self) -> &str;
@@ -13,7 +20,7 @@ pub trait RequestContext: Send + Sync {
     /// 请求路径
     fn path(&self) -> &str;
 
-    /// 客户端 IP
+    /// 客户端 IP — 转发头仅在对端为可信代理时生效（见 [`TrustedProxies`](crate::TrustedProxies)）
     fn client_ip(&self) -> Option<IpAddr>;
 
     /// 获取单个请求头
@@ -54,24 +61,68 @@ pub trait RequestContext: Send + Sync {
     fn parse_query<T: DeserializeOwned>(&self) -> Result<T, String>;
 
     /// Parse request body as JSON
-    async fn parse_json<T: DeserializeOwned>(&mut self) -> Result<T, String>;
+    fn parse_json<T: DeserializeOwned>(&mut self)
+    -> impl Future<Output = Result<T, String>> + Send;
 
     /// Parse request body as form data
-    async fn parse_form<T: DeserializeOwned>(&mut self) -> Result<T, String>;
+    fn parse_form<T: DeserializeOwned>(&mut self)
+    -> impl Future<Output = Result<T, String>> + Send;
 
     /// Read raw request body bytes
-    async fn read_body_bytes(&mut self) -> Result<Bytes, String>;
+    fn read_body_bytes(&mut self) -> impl Future<Output = Result<Bytes, String>> + Send;
 
     /// Auto-select JSON or form parsing based on Content-Type
-    async fn parse_json_or_form<T: DeserializeOwned>(&mut self) -> Result<T, String> {
-        let content_type = self
+    fn parse_json_or_form<T: DeserializeOwned>(
+        &mut self,
+    ) -> impl Future<Output = Result<T, String>> + Send {
+        let is_form = self
             .header("content-type")
             .unwrap_or("")
-            .to_ascii_lowercase();
-        if content_type.contains("application/x-www-form-urlencoded") {
-            self.parse_form().await
-        } else {
-            self.parse_json().await
+            .to_ascii_lowercase()
+            .contains("application/x-www-form-urlencoded");
+        async move {
+            if is_form {
+                self.parse_form().await
+            } else {
+                self.parse_json().await
+            }
+        }
+    }
+
+    /// Take the still-unread request body as a stream.
+    ///
+    /// Adapters leave `multipart/form-data` bodies unread so uploads can be parsed as they
+    /// arrive; `None` once the body has been buffered (or taken), in which case callers fall
+    /// back to [`read_body_bytes`](Self::read_body_bytes).
+    fn take_body_stream(&mut self) -> Option<RequestBodyStream> {
+        None
+    }
+
+    /// Parse a `multipart/form-data` body (file uploads).
+    ///
+    /// Parts are streamed from the connection when the body is still unread (see
+    /// [`take_body_stream`](Self::take_body_stream)), so file data is not held in memory; the
+    /// server-wide body limit still applies and `limits` tightens individual fields.
+    /// Oversized bodies or fields fail with 413.
+    fn parse_multipart(
+        &mut self,
+        limits: MultipartLimits,
+    ) -> impl Future<Output = Result<Multipart, HttpError>> + Send {
+        let content_type = self.header("content-type").map(str::to_owned);
+        let stream = self.take_body_stream();
+        async move {
+            let content_type = content_type
+                .ok_or_else(|| HttpError::bad_request("Expected multipart/form-data request"))?;
+            match stream {
+                Some(stream) => Multipart::from_stream(stream, &content_type, limits),
+                None => {
+                    let bytes = self
+                        .read_body_bytes()
+                        .await
+                        .map_err(HttpError::bad_request)?;
+                    Multipart::new(bytes, &content_type, limits)
+                }
+            }
         }
     }
 
@@ -86,4 +137,28 @@ pub trait RequestContext: Send + Sync {
 
     /// Read a request cookie
     fn cookie(&self, name: &str) -> Option<String>;
+
+    /// Upgrade to a WebSocket connection.
+    ///
+    /// Returns the `101 Switching Protocols` response the handler must return unchanged;
+    /// `on_upgrade` is spawned with the [`WebSocket`] once the handshake completes.
+    /// Non-upgrade requests are rejected with a 4xx error, and handshakes from an `Origin` that
+    /// is neither same-origin nor listed in [`WebSocketOrigins`](crate::WebSocketOrigins) with
+    /// 403. The default implementation
+    /// (for contexts without a connection, e.g. test doubles) returns 501.
+    fn upgrade_websocket<F, Fut>(
+        &mut self,
+        on_upgrade: F,
+    ) -> impl Future<Output = Result<Response, HttpError>> + Send
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        drop(on_upgrade);
+        async {
+            let mut err = HttpError::bad_request("WebSocket upgrade is not supported");
+            err.status = http::StatusCode::NOT_IMPLEMENTED;
+            Err(err)
+        }
+    }
 }
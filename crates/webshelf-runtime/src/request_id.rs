@@ -0,0 +1,291 @@
+//! Request ID propagation and per-request tracing span.
+//!
+//! [`RequestIdMiddleware`] accepts a well-formed incoming `X-Request-Id` (or generates a
+//! UUID v4), stores it as [`RequestId`] request data and runs the rest of the chain inside a
+//! `request` span:
+//!
+//! ```text
+//! request{method=PUT request_id=9f0c… route=/api/users/{id} user_id=42}: access: request completed status=200 latency_ms=3
+//! ```
+//!
+//! `route` and `user_id` are recorded once known — the adapters record the matched route
+//! pattern when the handler is entered ([`record_route`]), [`AuthGuard`](crate::AuthGuard)
+//! records the user. When trace export is on, the span continues the caller's W3C trace
+//! ([`trace_context`](crate::trace_context)); `otel.*` fields only shape the exported span.
+//!
+//! The ID is echoed in the `X-Request-Id` response header and added as `request_id` to JSON
+//! error bodies (`{"error": …, "message": …}`).
+
+use std::fmt;
+use std::time::Instant;
+
+use bytes::Bytes;
+use serde_json::Value;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::middleware::{Middleware, Next};
+use crate::{RequestContext, Response, ResponseBody};
+
+/// Header carrying the request ID (request and response).
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Target of the per-request `request completed` event, so log outputs can separate access
+/// logs from application logs.
+pub const ACCESS_LOG_TARGET: &str = "access";
+
+/// Incoming IDs longer than this are replaced with a generated one.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Request ID of the current request, available via `req.get_data_ref::<RequestId>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Generate a new random (UUID v4) ID.
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Accept a client/upstream supplied ID. Only short IDs made of `[A-Za-z0-9._:-]`
+    /// are accepted, so the value is safe to log and to echo in headers.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'));
+        valid.then(|| Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Record the matched route pattern on the current request span, and name the exported
+/// span `{method} {route}`.
+///
+/// Called by the adapters when a handler is entered (axum only knows the full pattern of
+/// nested routes at that point); a no-op outside a request span.
+pub fn record_route(method: &str, pattern: &str) {
+    let route = if pattern.starts_with('/') {
+        pattern.to_string()
+    } else {
+        format!("/{pattern}")
+    };
+    let span = tracing::Span::current();
+    span.record("otel.name", format!("{method} {route}"));
+    span.record("route", route);
+}
+
+/// Assigns a request ID and wraps the request in a correlated tracing span.
+///
+/// Mount it outside the auth / rate-limit middleware so their log lines carry the ID.
+#[derive(Clone, Copy, Default)]
+pub struct RequestIdMiddleware;
+
+#[async_trait::async_trait]
+impl Middleware for RequestIdMiddleware {
+    fn needs_body(&self) -> bool {
+        false
+    }
+
+    async fn handle<R: RequestContext + 'static>(&self, mut req: R, next: Next<'_, R>) -> Response {
+        let id = req
+            .header(REQUEST_ID_HEADER)
+            .and_then(RequestId::parse)
+            .unwrap_or_else(RequestId::generate);
+
+        let span = tracing::info_span!(
+            "request",
+            method = %req.method(),
+            request_id = %id,
+            route = tracing::field::Empty,
+            user_id = tracing::field::Empty,
+            "otel.name" = %req.method(),
+            "otel.kind" = "server",
+        );
+        crate::trace_context::set_remote_parent(&span, &req);
+        if let Some(pattern) = req.matched_route_pattern() {
+            span.in_scope(|| record_route(req.method(), pattern));
+        }
+        req.set_data(id.clone());
+
+        let started = Instant::now();
+        let mut response = async move {
+            let response = next.run(req).await;
+            let status = response.status();
+            let span = tracing::Span::current();
+            span.set_attribute("http.response.status_code", i64::from(status.as_u16()));
+            if status.is_server_error() {
+                span.set_status(opentelemetry::trace::Status::error(status.to_string()));
+            }
+            tracing::info!(
+                target: ACCESS_LOG_TARGET,
+                status = status.as_u16(),
+                latency_ms = started.elapsed().as_millis() as u64,
+                "request completed"
+            );
+            response
+        }
+        .instrument(span)
+        .await;
+
+        attach_request_id(&mut response, &id);
+        response
+    }
+}
+
+/// Echo the ID in the response header and in JSON error bodies.
+fn attach_request_id(response: &mut Response, id: &RequestId) {
+    response.remove_header(REQUEST_ID_HEADER);
+    response.insert_header(REQUEST_ID_HEADER, id);
+
+    // 适配器桥接后下游的 JSON 响应以字节形式出现，需按 Content-Type 识别。
+    let is_json_bytes = matches!(response.body(), ResponseBody::Bytes(_))
+        && response
+            .content_type()
+            .is_some_and(|ct| ct.contains("json"));
+    let mut body = match response.body_mut() {
+        ResponseBody::Json(value) => {
+            insert_request_id(value, id);
+            return;
+        }
+        ResponseBody::Bytes(bytes) if is_json_bytes => {
+            match serde_json::from_slice::<Value>(bytes) {
+                Ok(value) => value,
+                Err(_) => return,
+            }
+        }
+        _ => return,
+    };
+    if insert_request_id(&mut body, id)
+        && let Ok(bytes) = serde_json::to_vec(&body)
+    {
+        response.set_bytes_body(Bytes::from(bytes));
+    }
+}
+
+/// Add `request_id` to an error object. Returns whether the body was changed.
+fn insert_request_id(body: &mut Value, id: &RequestId) -> bool {
+    match body.as_object_mut() {
+        Some(map) if map.contains_key("error") && !map.contains_key("request_id") => {
+            map.insert("request_id".into(), Value::String(id.to_string()));
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpError;
+    use crate::middleware::tests::MockRequest;
+    use http::StatusCode;
+
+    fn respond_with(response: fn() -> Response) -> Next<'static, MockRequest> {
+        Next::new(move |_req: MockRequest| async move { response() })
+    }
+
+    #[test]
+    fn parse_accepts_safe_ids_only() {
+        assert_eq!(
+            RequestId::parse(" abc-123_x.y:z ").unwrap().as_str(),
+            "abc-123_x.y:z"
+        );
+        assert!(RequestId::parse("").is_none());
+        assert!(RequestId::parse("has space").is_none());
+        assert!(RequestId::parse("line\nbreak").is_none());
+        assert!(RequestId::parse(&"a".repeat(MAX_REQUEST_ID_LEN + 1)).is_none());
+    }
+
+    #[test]
+    fn generated_ids_are_unique_uuids() {
+        let a = RequestId::generate();
+        let b = RequestId::generate();
+        assert_ne!(a, b);
+        assert!(uuid::Uuid::parse_str(a.as_str()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn incoming_id_is_stored_and_echoed() {
+        let req = MockRequest::new("/users").with_header("X-Request-Id", "upstream-42");
+        let next = Next::new(|req: MockRequest| async move {
+            let id = req.get_data_ref::<RequestId>().unwrap().clone();
+            let mut resp = Response::new();
+            resp.set_text_body(id.to_string());
+            resp
+        });
+
+        let resp = RequestIdMiddleware.handle(req, next).await;
+        assert_eq!(resp.header(REQUEST_ID_HEADER), Some("upstream-42"));
+        assert_eq!(resp.read_bytes().unwrap(), "upstream-42");
+    }
+
+    #[tokio::test]
+    async fn invalid_incoming_id_is_replaced() {
+        let req = MockRequest::new("/users").with_header("x-request-id", "<script>");
+        let resp = RequestIdMiddleware
+            .handle(req, respond_with(Response::new))
+            .await;
+        let id = resp.header(REQUEST_ID_HEADER).unwrap();
+        assert_ne!(id, "<script>");
+        assert!(RequestId::parse(id).is_some());
+    }
+
+    #[tokio::test]
+    async fn error_json_body_carries_request_id() {
+        let req = MockRequest::new("/users").with_header("x-request-id", "req-1");
+        let resp = RequestIdMiddleware
+            .handle(
+                req,
+                respond_with(|| HttpError::not_found("User not found").into()),
+            )
+            .await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body: Value = serde_json::from_slice(&resp.read_bytes().unwrap()).unwrap();
+        assert_eq!(body["request_id"], "req-1");
+        assert_eq!(body["error"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn error_json_bytes_from_adapter_carry_request_id() {
+        let req = MockRequest::new("/users").with_header("x-request-id", "req-2");
+        let resp = RequestIdMiddleware
+            .handle(
+                req,
+                respond_with(|| {
+                    let mut resp = Response::with_status(StatusCode::BAD_REQUEST);
+                    resp.set_content_type("application/json");
+                    resp.set_bytes_body(Bytes::from_static(br#"{"error":"bad_request"}"#));
+                    resp
+                }),
+            )
+            .await;
+        let body: Value = serde_json::from_slice(&resp.read_bytes().unwrap()).unwrap();
+        assert_eq!(body["request_id"], "req-2");
+    }
+
+    #[tokio::test]
+    async fn success_bodies_are_untouched() {
+        let req = MockRequest::new("/users");
+        let resp = RequestIdMiddleware
+            .handle(
+                req,
+                respond_with(|| Response::json(&serde_json::json!({ "id": 1 })).unwrap()),
+            )
+            .await;
+        let body: Value = serde_json::from_slice(&resp.read_bytes().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({ "id": 1 }));
+        assert!(resp.header(REQUEST_ID_HEADER).is_some());
+    }
+}
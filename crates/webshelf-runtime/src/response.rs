@@ -1,11 +1,15 @@
 use bytes::Bytes;
 use cookie::Cookie;
+use futures_core::Stream;
+use http::Extensions;
 use http::HeaderName;
 use http::StatusCode;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
+use std::borrow::Cow;
+use std::pin::Pin;
 
-use crate::HttpError;
+use crate::{HttpError, PROBLEM_JSON_CONTENT_TYPE};
 
 /// Unified response type — cookies are stored as `set-cookie` headers.
 pub struct Response {
@@ -13,13 +17,24 @@ pub struct Response {
     headers: Vec<(HeaderName, String)>,
     body: ResponseBody,
     /// Explicit Content-Type override (takes priority over auto-detection).
-    content_type: Option<&'static str>,
+    content_type: Option<Cow<'static, str>>,
+    /// Typed response extensions, carried through the middleware bridges.
+    extensions: Extensions,
 }
 
+/// Error type carried by streaming bodies.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Boxed chunk stream used by [`ResponseBody::Stream`].
+pub type BodyStream = Pin<Box<dyn Stream<Item = Result<Bytes, BoxError>> + Send>>;
+
 pub enum ResponseBody {
     Empty,
     Json(JsonValue),
     Bytes(Bytes),
+    /// Chunked body written to the client as the stream yields, never buffered.
+    /// An `Err` item aborts the connection mid-response.
+    Stream(BodyStream),
 }
 
 impl Response {
@@ -29,6 +44,7 @@ impl Response {
             headers: Vec::new(),
             body: ResponseBody::Empty,
             content_type: None,
+            extensions: Extensions::new(),
         }
     }
 
@@ -38,6 +54,7 @@ impl Response {
             headers: Vec::new(),
             body: ResponseBody::Empty,
             content_type: None,
+            extensions: Extensions::new(),
         }
     }
 
@@ -58,19 +75,33 @@ impl Response {
         self.body = ResponseBody::Bytes(bytes);
     }
 
+    /// Set a streaming body (large exports, SSE). Content-Type defaults to
+    /// `text/plain`, so callers should usually also call [`set_content_type`](Self::set_content_type).
+    pub fn set_stream_body<S, E>(&mut self, stream: S)
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<BoxError> + 'static,
+    {
+        use futures_util::TryStreamExt;
+        self.body = ResponseBody::Stream(Box::pin(stream.map_err(Into::into)));
+    }
+
     pub fn set_text_body(&mut self, text: impl Into<String>) {
         let text: String = text.into();
         self.body = ResponseBody::Bytes(Bytes::from(text));
     }
 
     /// Set explicit Content-Type (overrides auto-detection).
-    pub fn set_content_type(&mut self, content_type: &'static str) {
-        self.content_type = Some(content_type);
+    ///
+    /// Accepts `&'static str` as well as owned strings (e.g. a Content-Type
+    /// copied from a framework-native response by a middleware bridge).
+    pub fn set_content_type(&mut self, content_type: impl Into<Cow<'static, str>>) {
+        self.content_type = Some(content_type.into());
     }
 
     /// Check if explicit Content-Type is set.
-    pub fn content_type(&self) -> Option<&'static str> {
-        self.content_type
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
     }
 
     pub fn insert_header(&mut self, name: &'static str, value: impl ToString) {
@@ -79,6 +110,19 @@ impl Response {
         }
     }
 
+    /// Append a header with a runtime-provided name (e.g. copied from a native response).
+    pub fn append_header(&mut self, name: HeaderName, value: impl ToString) {
+        self.headers.push((name, value.to_string()));
+    }
+
+    /// Get the first value of a header (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(h, _)| h.as_str().eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
     pub fn remove_header(&mut self, name: &str) {
         self.headers.retain(|(h, _)| h.as_str() != name);
     }
@@ -95,24 +139,41 @@ impl Response {
         self.insert_header("set-cookie", c.to_string());
     }
 
+    /// Read the whole body. Fails for [`ResponseBody::Stream`] — use [`take_body`](Self::take_body).
     pub fn read_bytes(&self) -> Result<Bytes, String> {
-        match &self.body {
-            ResponseBody::Empty => Ok(Bytes::new()),
-            ResponseBody::Json(val) => serde_json::to_vec(val)
-                .map(Bytes::from)
-                .map_err(|e| e.to_string()),
-            ResponseBody::Bytes(bytes) => Ok(bytes.clone()),
-        }
+        self.body.to_bytes()
+    }
+
+    /// Take the body out, leaving [`ResponseBody::Empty`] (adapters use this to forward streams).
+    pub fn take_body(&mut self) -> ResponseBody {
+        std::mem::replace(&mut self.body, ResponseBody::Empty)
     }
 
     pub fn take_headers(&mut self) -> Vec<(HeaderName, String)> {
         std::mem::take(&mut self.headers)
     }
 
+    /// Typed extensions, e.g. set by a native handler behind a middleware bridge.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    pub fn take_extensions(&mut self) -> Extensions {
+        std::mem::take(&mut self.extensions)
+    }
+
     pub fn body(&self) -> &ResponseBody {
         &self.body
     }
 
+    pub fn body_mut(&mut self) -> &mut ResponseBody {
+        &mut self.body
+    }
+
     /// Build JSON response with status 200 OK.
     pub fn json<T: Serialize>(value: &T) -> Result<Self, HttpError> {
         let mut res = Self::new();
@@ -137,6 +198,22 @@ impl ResponseBody {
     pub fn is_empty(&self) -> bool {
         matches!(self, ResponseBody::Empty)
     }
+
+    pub fn is_stream(&self) -> bool {
+        matches!(self, ResponseBody::Stream(_))
+    }
+
+    /// Serialize a buffered body. Streaming bodies cannot be read synchronously.
+    pub fn to_bytes(&self) -> Result<Bytes, String> {
+        match self {
+            ResponseBody::Empty => Ok(Bytes::new()),
+            ResponseBody::Json(val) => serde_json::to_vec(val)
+                .map(Bytes::from)
+                .map_err(|e| e.to_string()),
+            ResponseBody::Bytes(bytes) => Ok(bytes.clone()),
+            ResponseBody::Stream(_) => Err("streaming body cannot be read as bytes".to_string()),
+        }
+    }
 }
 
 // ── From<HttpError> for Response ──
@@ -144,10 +221,8 @@ impl From<HttpError> for Response {
     fn from(err: HttpError) -> Self {
         let mut res = Response::new();
         res.set_status(err.status);
-        res.set_json_body(serde_json::json!({
-            "error": err.error_type,
-            "message": err.message,
-        }));
+        res.set_content_type(PROBLEM_JSON_CONTENT_TYPE);
+        res.set_json_body(err.to_problem_json());
         res
     }
 }
@@ -191,9 +266,34 @@ mod tests {
     }
 
     #[test]
-    fn from_http_error_does_not_set_content_type() {
+    fn from_http_error_renders_problem_json() {
         let err = HttpError::bad_request("test");
         let resp: Response = err.into();
-        assert!(resp.content_type().is_none());
+        assert_eq!(resp.content_type(), Some(PROBLEM_JSON_CONTENT_TYPE));
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = serde_json::from_slice(&resp.read_bytes().unwrap()).unwrap();
+        assert_eq!(body["status"], 400);
+        assert_eq!(body["error"], "bad_request");
+    }
+
+    #[tokio::test]
+    async fn stream_body_is_taken_not_read() {
+        use futures_util::StreamExt;
+
+        let chunks = futures_util::stream::iter(vec![
+            Ok::<_, std::io::Error>(Bytes::from_static(b"a,b\n")),
+            Ok(Bytes::from_static(b"1,2\n")),
+        ]);
+        let mut resp = Response::new();
+        resp.set_stream_body(chunks);
+        assert!(resp.body().is_stream());
+        assert!(resp.read_bytes().is_err());
+
+        let ResponseBody::Stream(stream) = resp.take_body() else {
+            panic!("expected stream body");
+        };
+        assert!(resp.body().is_empty());
+        let collected: Vec<Bytes> = stream.map(|c| c.unwrap()).collect().await;
+        assert_eq!(collected.concat(), b"a,b\n1,2\n");
     }
 }
@@ -1,3 +1,5 @@
+use crate::{Shutdown, TlsConfig};
+
 /// Runtime trait — abstracts web framework infrastructure operations.
 /// Each adapter (webshelf-axum / webshelf-salvo) implements this trait.
 /// All methods are static (adapters like `AxumRuntime<S>` are ZSTs with no instance state).
@@ -33,9 +35,18 @@ pub trait Runtime: Clone + Send + Sync + Sized + 'static {
 
     /// Bind address and start HTTP service with graceful shutdown.
     /// State is passed separately because adapters manage with_state internally.
+    ///
+    /// With `tls`, connections are terminated with rustls and the certificate files are
+    /// reloaded on change (see [`TlsReloader`](crate::TlsReloader)); otherwise plain HTTP.
+    ///
+    /// SIGTERM / Ctrl+C trigger `shutdown`; the listener closes once it reaches
+    /// [`Stopping`](crate::ShutdownPhase::Stopping) and in-flight requests get
+    /// `drain_timeout_secs` to finish before the remaining connections are dropped.
     fn serve(
         router: Self::Router,
         state: Self::State,
         addr: &str,
+        tls: Option<&TlsConfig>,
+        shutdown: Shutdown,
     ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send;
 }
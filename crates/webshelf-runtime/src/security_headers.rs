@@ -0,0 +1,310 @@
+//! HTTP security response headers.
+//!
+//! [`SecurityHeadersMiddleware`] adds HSTS, `X-Frame-Options`, `X-Content-Type-Options`,
+//! `Referrer-Policy`, `Content-Security-Policy` and `Permissions-Policy` to every response
+//! passing through it, driven by [`SecurityHeadersConfig`] (`[server.security_headers]`).
+//! Headers already set by a handler are left untouched, so a single route can relax or
+//! tighten its own policy.
+//!
+//! # CSP nonce
+//!
+//! When the policy contains the `{nonce}` placeholder, a fresh nonce is generated per request,
+//! stored as [`CspNonce`] request data and substituted into the header. Pages with inline or
+//! third-party scripts mark them with it:
+//!
+//! ```ignore
+//! let nonce = req.get_data_ref::<CspNonce>().map(CspNonce::as_str);
+//! // <script nonce="{nonce}" src="…"></script>
+//! ```
+
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::middleware::{Middleware, Next};
+use crate::{RequestContext, Response};
+
+/// Placeholder replaced by the per-request nonce in `content_security_policy`.
+pub const NONCE_PLACEHOLDER: &str = "{nonce}";
+
+/// `[server.security_headers]` configuration. Empty strings disable the matching header.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SecurityHeadersConfig {
+    /// Master switch (default: true). Disable when a reverse proxy already sets the headers.
+    pub enabled: bool,
+    /// `Strict-Transport-Security` max-age in seconds; 0 disables HSTS (default: 1 year).
+    /// Browsers ignore HSTS received over plain HTTP (RFC 6797), so this is safe in dev.
+    pub hsts_max_age_secs: u64,
+    pub hsts_include_subdomains: bool,
+    pub hsts_preload: bool,
+    /// `X-Frame-Options` (default: `SAMEORIGIN`).
+    pub frame_options: String,
+    /// Send `X-Content-Type-Options: nosniff` (default: true).
+    pub content_type_options: bool,
+    /// `Referrer-Policy` (default: `strict-origin-when-cross-origin`).
+    pub referrer_policy: String,
+    /// `Content-Security-Policy`; may contain [`NONCE_PLACEHOLDER`].
+    pub content_security_policy: String,
+    /// Send the policy as `Content-Security-Policy-Report-Only` (default: false).
+    pub csp_report_only: bool,
+    /// `Permissions-Policy` (default: camera, microphone, geolocation and payment disabled).
+    pub permissions_policy: String,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hsts_max_age_secs: 31_536_000,
+            hsts_include_subdomains: true,
+            hsts_preload: false,
+            frame_options: "SAMEORIGIN".into(),
+            content_type_options: true,
+            referrer_policy: "strict-origin-when-cross-origin".into(),
+            content_security_policy: "default-src 'self'; \
+                script-src 'self' 'wasm-unsafe-eval' 'unsafe-eval' 'nonce-{nonce}'; \
+                style-src 'self' 'unsafe-inline' https://fonts.googleapis.com; \
+                img-src 'self' data:; font-src 'self' https://fonts.gstatic.com; \
+                connect-src 'self'; frame-ancestors 'self'"
+                .into(),
+            csp_report_only: false,
+            permissions_policy: "camera=(), microphone=(), geolocation=(), payment=()".into(),
+        }
+    }
+}
+
+/// Per-request CSP nonce, available via `req.get_data_ref::<CspNonce>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CspNonce(String);
+
+impl CspNonce {
+    fn generate() -> Self {
+        // 128 random bits, hex encoded — a valid base64-value per CSP grammar.
+        Self(uuid::Uuid::new_v4().simple().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Adds the configured security headers to every response.
+///
+/// Header values are rendered once at construction; cloning is cheap.
+#[derive(Clone)]
+pub struct SecurityHeadersMiddleware {
+    inner: Arc<Rendered>,
+}
+
+struct Rendered {
+    enabled: bool,
+    fixed: Vec<(&'static str, String)>,
+    csp_header: &'static str,
+    csp: Option<String>,
+}
+
+impl SecurityHeadersMiddleware {
+    pub fn new(config: &SecurityHeadersConfig) -> Self {
+        let mut fixed = Vec::new();
+        if config.hsts_max_age_secs > 0 {
+            let mut hsts = format!("max-age={}", config.hsts_max_age_secs);
+            if config.hsts_include_subdomains {
+                hsts.push_str("; includeSubDomains");
+            }
+            if config.hsts_preload {
+                hsts.push_str("; preload");
+            }
+            fixed.push(("strict-transport-security", hsts));
+        }
+        if !config.frame_options.is_empty() {
+            fixed.push(("x-frame-options", config.frame_options.clone()));
+        }
+        if config.content_type_options {
+            fixed.push(("x-content-type-options", "nosniff".into()));
+        }
+        if !config.referrer_policy.is_empty() {
+            fixed.push(("referrer-policy", config.referrer_policy.clone()));
+        }
+        if !config.permissions_policy.is_empty() {
+            fixed.push(("permissions-policy", config.permissions_policy.clone()));
+        }
+
+        let csp_header = if config.csp_report_only {
+            "content-security-policy-report-only"
+        } else {
+            "content-security-policy"
+        };
+        let csp = (!config.content_security_policy.is_empty())
+            .then(|| config.content_security_policy.clone());
+
+        Self {
+            inner: Arc::new(Rendered {
+                enabled: config.enabled,
+                fixed,
+                csp_header,
+                csp,
+            }),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for SecurityHeadersMiddleware {
+    fn needs_body(&self) -> bool {
+        false
+    }
+
+    async fn handle<R: RequestContext + 'static>(&self, mut req: R, next: Next<'_, R>) -> Response {
+        let cfg = &self.inner;
+        if !cfg.enabled {
+            return next.run(req).await;
+        }
+
+        let nonce = cfg
+            .csp
+            .as_deref()
+            .filter(|csp| csp.contains(NONCE_PLACEHOLDER))
+            .map(|_| CspNonce::generate());
+        if let Some(nonce) = &nonce {
+            req.set_data(nonce.clone());
+        }
+
+        let mut response = next.run(req).await;
+
+        for &(name, ref value) in &cfg.fixed {
+            if response.header(name).is_none() {
+                response.insert_header(name, value);
+            }
+        }
+        if let Some(csp) = &cfg.csp
+            && response.header(cfg.csp_header).is_none()
+        {
+            match &nonce {
+                Some(nonce) => response.insert_header(
+                    cfg.csp_header,
+                    csp.replace(NONCE_PLACEHOLDER, nonce.as_str()),
+                ),
+                None => response.insert_header(cfg.csp_header, csp),
+            }
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::tests::MockRequest;
+
+    fn run(
+        config: &SecurityHeadersConfig,
+        next: Next<'static, MockRequest>,
+    ) -> impl std::future::Future<Output = Response> {
+        let middleware = SecurityHeadersMiddleware::new(config);
+        async move { middleware.handle(MockRequest::new("/"), next).await }
+    }
+
+    fn ok() -> Next<'static, MockRequest> {
+        Next::new(|_req: MockRequest| async { Response::new() })
+    }
+
+    #[tokio::test]
+    async fn default_headers_are_set() {
+        let resp = run(&SecurityHeadersConfig::default(), ok()).await;
+        assert_eq!(
+            resp.header("strict-transport-security"),
+            Some("max-age=31536000; includeSubDomains")
+        );
+        assert_eq!(resp.header("x-frame-options"), Some("SAMEORIGIN"));
+        assert_eq!(resp.header("x-content-type-options"), Some("nosniff"));
+        assert_eq!(
+            resp.header("referrer-policy"),
+            Some("strict-origin-when-cross-origin")
+        );
+        assert!(
+            resp.header("permissions-policy")
+                .unwrap()
+                .contains("camera=()")
+        );
+        let csp = resp.header("content-security-policy").unwrap();
+        assert!(csp.starts_with("default-src 'self'"));
+        assert!(!csp.contains(NONCE_PLACEHOLDER));
+    }
+
+    #[tokio::test]
+    async fn nonce_is_shared_with_handler_and_unique_per_request() {
+        let next = || {
+            Next::new(|req: MockRequest| async move {
+                let nonce = req.get_data_ref::<CspNonce>().unwrap().clone();
+                let mut resp = Response::new();
+                resp.set_text_body(nonce.as_str().to_string());
+                resp
+            })
+        };
+        let config = SecurityHeadersConfig::default();
+        let first = run(&config, next()).await;
+        let second = run(&config, next()).await;
+
+        let nonce = String::from_utf8(first.read_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(nonce.len(), 32);
+        assert!(
+            first
+                .header("content-security-policy")
+                .unwrap()
+                .contains(&format!("'nonce-{nonce}'"))
+        );
+        assert_ne!(
+            first.header("content-security-policy"),
+            second.header("content-security-policy")
+        );
+    }
+
+    #[tokio::test]
+    async fn handler_headers_take_precedence() {
+        let next = Next::new(|_req: MockRequest| async {
+            let mut resp = Response::new();
+            resp.insert_header("x-frame-options", "DENY");
+            resp.insert_header("content-security-policy", "default-src 'none'");
+            resp
+        });
+        let resp = run(&SecurityHeadersConfig::default(), next).await;
+        assert_eq!(resp.header("x-frame-options"), Some("DENY"));
+        assert_eq!(
+            resp.header("content-security-policy"),
+            Some("default-src 'none'")
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_values_and_report_only() {
+        let config = SecurityHeadersConfig {
+            hsts_max_age_secs: 0,
+            frame_options: String::new(),
+            permissions_policy: String::new(),
+            content_security_policy: "default-src 'self'".into(),
+            csp_report_only: true,
+            ..Default::default()
+        };
+        let resp = run(&config, ok()).await;
+        assert!(resp.header("strict-transport-security").is_none());
+        assert!(resp.header("x-frame-options").is_none());
+        assert!(resp.header("permissions-policy").is_none());
+        assert!(resp.header("content-security-policy").is_none());
+        assert_eq!(
+            resp.header("content-security-policy-report-only"),
+            Some("default-src 'self'")
+        );
+    }
+
+    #[tokio::test]
+    async fn disabled_sets_nothing() {
+        let config = SecurityHeadersConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let resp = run(&config, ok()).await;
+        assert!(resp.header("x-content-type-options").is_none());
+        assert!(resp.header("content-security-policy").is_none());
+    }
+}
@@ -0,0 +1,248 @@
+//! Graceful shutdown coordination.
+//!
+//! On SIGTERM / Ctrl+C (or [`Shutdown::trigger`]) the instance goes through:
+//!
+//! 1. **Draining** — [`Shutdown::is_ready`] turns false so the readiness endpoint fails and the
+//!    load balancer / Kubernetes endpoints stop routing new traffic here. The server keeps
+//!    accepting for `pre_stop_delay_secs`, covering the propagation delay.
+//! 2. **Stopping** — the adapter stops accepting and waits for in-flight requests, at most
+//!    `drain_timeout_secs`; connections still open after that are closed.
+//! 3. Once `serve` has returned, [`Shutdown::stop_background_tasks`] runs the hooks registered
+//!    with [`Shutdown::on_stop`] one by one, in registration order.
+//!
+//! Keep `pre_stop_delay_secs + drain_timeout_secs` (plus the hooks) below the orchestrator's
+//! grace period (`terminationGracePeriodSeconds` in `k8s/webshelf.yml`).
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::sync::watch;
+
+/// `[server.shutdown]` configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// Time between failing readiness and closing the listener (default: 5).
+    pub pre_stop_delay_secs: u64,
+    /// Upper bound for in-flight requests to finish once the listener is closed (default: 20).
+    pub drain_timeout_secs: u64,
+    /// Upper bound for each background-task stop hook (default: 5).
+    pub task_timeout_secs: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            pre_stop_delay_secs: 5,
+            drain_timeout_secs: 20,
+            task_timeout_secs: 5,
+        }
+    }
+}
+
+/// Where the instance is in the shutdown sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShutdownPhase {
+    #[default]
+    Running,
+    /// Readiness fails; still accepting connections.
+    Draining,
+    /// Listener closed; in-flight requests are finishing.
+    Stopping,
+}
+
+type StopHook = (&'static str, Pin<Box<dyn Future<Output = ()> + Send>>);
+
+/// Shutdown coordinator shared by the adapters, the readiness endpoint and bootstrap.
+///
+/// Cloning is cheap; all clones observe the same sequence.
+#[derive(Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: ShutdownConfig,
+    phase: watch::Sender<ShutdownPhase>,
+    hooks: Mutex<Vec<StopHook>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new(ShutdownConfig::default())
+    }
+}
+
+impl Shutdown {
+    pub fn new(config: ShutdownConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                phase: watch::Sender::new(ShutdownPhase::Running),
+                hooks: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    pub fn phase(&self) -> ShutdownPhase {
+        *self.inner.phase.borrow()
+    }
+
+    /// Whether the instance should receive new traffic.
+    pub fn is_ready(&self) -> bool {
+        self.phase() == ShutdownPhase::Running
+    }
+
+    /// Start the shutdown sequence; later calls are no-ops. Must be called inside a Tokio runtime.
+    pub fn trigger(&self) {
+        let started = self.inner.phase.send_if_modified(|phase| {
+            let running = *phase == ShutdownPhase::Running;
+            if running {
+                *phase = ShutdownPhase::Draining;
+            }
+            running
+        });
+        if !started {
+            return;
+        }
+
+        let delay = Duration::from_secs(self.inner.config.pre_stop_delay_secs);
+        tracing::info!(
+            pre_stop_delay_secs = delay.as_secs(),
+            "Shutdown started, readiness now failing"
+        );
+        let inner = self.inner.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            tracing::info!(
+                drain_timeout_secs = inner.config.drain_timeout_secs,
+                "Closing listener, draining in-flight requests"
+            );
+            inner.phase.send_replace(ShutdownPhase::Stopping);
+        });
+    }
+
+    /// Trigger the sequence on SIGTERM / Ctrl+C.
+    pub fn listen_for_signals(&self) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            crate::shutdown_signal().await;
+            this.trigger();
+        });
+    }
+
+    /// Resolves when the listener should stop accepting (graceful-shutdown future of the adapters).
+    pub async fn stopping(&self) {
+        let mut rx = self.inner.phase.subscribe();
+        // Sender 由 self 持有，wait_for 不会因通道关闭而返回 Err
+        let _ = rx.wait_for(|phase| *phase >= ShutdownPhase::Stopping).await;
+    }
+
+    /// Resolves `drain_timeout_secs` after [`stopping`](Self::stopping).
+    pub async fn drain_deadline(&self) {
+        self.stopping().await;
+        tokio::time::sleep(self.drain_timeout()).await;
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.inner.config.drain_timeout_secs)
+    }
+
+    /// Register a background-task stop hook, run by [`stop_background_tasks`](Self::stop_background_tasks).
+    pub fn on_stop(&self, name: &'static str, hook: impl Future<Output = ()> + Send + 'static) {
+        self.inner
+            .hooks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((name, Box::pin(hook)));
+    }
+
+    /// Run the registered stop hooks in registration order, each bounded by `task_timeout_secs`.
+    pub async fn stop_background_tasks(&self) {
+        let hooks =
+            std::mem::take(&mut *self.inner.hooks.lock().unwrap_or_else(|e| e.into_inner()));
+        let timeout = Duration::from_secs(self.inner.config.task_timeout_secs);
+        for (name, hook) in hooks {
+            match tokio::time::timeout(timeout, hook).await {
+                Ok(()) => tracing::info!(task = name, "Background task stopped"),
+                Err(_) => tracing::warn!(task = name, "Background task did not stop in time"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn config(pre_stop: u64, drain: u64) -> ShutdownConfig {
+        ShutdownConfig {
+            pre_stop_delay_secs: pre_stop,
+            drain_timeout_secs: drain,
+            task_timeout_secs: 1,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn readiness_fails_before_listener_closes() {
+        let shutdown = Shutdown::new(config(5, 20));
+        assert!(shutdown.is_ready());
+
+        let stopping = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.stopping().await }
+        });
+        shutdown.trigger();
+        assert!(!shutdown.is_ready());
+        assert_eq!(shutdown.phase(), ShutdownPhase::Draining);
+
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        assert!(!stopping.is_finished());
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        stopping.await.unwrap();
+        assert_eq!(shutdown.phase(), ShutdownPhase::Stopping);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drain_deadline_follows_stopping() {
+        let shutdown = Shutdown::new(config(1, 10));
+        shutdown.trigger();
+        let started = tokio::time::Instant::now();
+        shutdown.drain_deadline().await;
+        assert_eq!(started.elapsed().as_secs(), 11);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn trigger_is_idempotent() {
+        let shutdown = Shutdown::new(config(3, 0));
+        shutdown.trigger();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        shutdown.trigger();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(shutdown.phase(), ShutdownPhase::Stopping);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stop_hooks_run_in_order_with_timeout() {
+        let shutdown = Shutdown::new(config(0, 0));
+        let order = Arc::new(AtomicUsize::new(0));
+        let (first, second) = (order.clone(), order.clone());
+        shutdown.on_stop("first", async move {
+            assert_eq!(first.fetch_add(1, Ordering::SeqCst), 0);
+        });
+        shutdown.on_stop("stuck", std::future::pending());
+        shutdown.on_stop("second", async move {
+            assert_eq!(second.fetch_add(1, Ordering::SeqCst), 1);
+        });
+
+        shutdown.stop_background_tasks().await;
+        assert_eq!(order.load(Ordering::SeqCst), 2);
+        // 已执行的 hook 不会再次运行
+        shutdown.stop_background_tasks().await;
+        assert_eq!(order.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,253 @@
+//! Server-Sent Events on top of [`ResponseBody::Stream`](crate::ResponseBody::Stream).
+//!
+//! ```ignore
+//! let events = rx_stream.map(|update| Event::default().event("update").json_data(&update).unwrap());
+//! Ok(Sse::new(events).keep_alive(Duration::from_secs(15)).into_response())
+//! ```
+//!
+//! 与框架无关：axum 与 salvo 适配器均将流式 body 直接透传给客户端，不做缓冲。
+
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::StreamExt;
+use serde::Serialize;
+
+use crate::Response;
+
+/// Comment line sent when the stream is idle, keeping proxies from closing the connection.
+const KEEP_ALIVE_COMMENT: &[u8] = b":\n\n";
+
+/// A single SSE event. All fields are optional; an event with no fields is a no-op for clients.
+#[derive(Debug, Clone, Default)]
+pub struct Event {
+    buf: String,
+}
+
+impl Event {
+    /// `data:` field. Multi-line data is split into one `data:` line per line.
+    pub fn data(mut self, data: impl AsRef<str>) -> Self {
+        for line in lines(data.as_ref()) {
+            self.field("data", line);
+        }
+        self
+    }
+
+    /// `data:` field holding the serialized JSON value (always a single line).
+    pub fn json_data(self, value: &impl Serialize) -> Result<Self, serde_json::Error> {
+        let json = serde_json::to_string(value)?;
+        Ok(self.data(json))
+    }
+
+    /// `event:` field (event type dispatched to `addEventListener`).
+    pub fn event(mut self, name: impl AsRef<str>) -> Self {
+        self.field("event", &single_line(name.as_ref()));
+        self
+    }
+
+    /// `id:` field (echoed back by the browser as `Last-Event-ID` on reconnect).
+    pub fn id(mut self, id: impl AsRef<str>) -> Self {
+        // The spec ignores ids containing NUL.
+        let id = single_line(id.as_ref()).replace('\0', "");
+        self.field("id", &id);
+        self
+    }
+
+    /// `retry:` hint — how long the client waits before reconnecting.
+    pub fn retry(mut self, delay: Duration) -> Self {
+        self.field("retry", &delay.as_millis().to_string());
+        self
+    }
+
+    /// Comment line (`: text`), ignored by clients.
+    pub fn comment(mut self, text: impl AsRef<str>) -> Self {
+        for line in lines(text.as_ref()) {
+            let _ = writeln!(self.buf, ": {line}");
+        }
+        self
+    }
+
+    /// Wire format of the event, terminated by the blank line.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut out = String::with_capacity(self.buf.len() + 1);
+        out.push_str(&self.buf);
+        out.push('\n');
+        Bytes::from(out)
+    }
+
+    fn field(&mut self, name: &str, value: &str) {
+        let _ = writeln!(self.buf, "{name}: {value}");
+    }
+}
+
+/// Split on every SSE line terminator: `\r\n`, a bare `\r` or `\n`.
+fn lines(value: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut rest = value;
+    while let Some(end) = rest.find(['\r', '\n']) {
+        lines.push(&rest[..end]);
+        let terminator = if rest[end..].starts_with("\r\n") {
+            2
+        } else {
+            1
+        };
+        rest = &rest[end + terminator..];
+    }
+    lines.push(rest);
+    lines
+}
+
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], "")
+}
+
+/// SSE response builder — wraps a stream of [`Event`]s.
+pub struct Sse<S> {
+    events: S,
+    keep_alive: Option<Duration>,
+}
+
+impl<S> Sse<S>
+where
+    S: Stream<Item = Event> + Send + 'static,
+{
+    pub fn new(events: S) -> Self {
+        Self {
+            events,
+            keep_alive: None,
+        }
+    }
+
+    /// Send a keep-alive comment whenever no event was sent for `interval`.
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    pub fn into_response(self) -> Response {
+        let keep_alive = self.keep_alive;
+        let body =
+            futures_util::stream::unfold(Box::pin(self.events), move |mut events| async move {
+                let next = match keep_alive {
+                    Some(interval) => match tokio::time::timeout(interval, events.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            return Some((Ok(Bytes::from_static(KEEP_ALIVE_COMMENT)), events));
+                        }
+                    },
+                    None => events.next().await,
+                };
+                next.map(|event| (Ok::<_, Infallible>(event.to_bytes()), events))
+            });
+
+        let mut resp = Response::new();
+        resp.set_content_type("text/event-stream");
+        resp.insert_header("cache-control", "no-cache");
+        // nginx buffers proxied responses by default, which would hold events back.
+        resp.insert_header("x-accel-buffering", "no");
+        resp.set_stream_body(body);
+        resp
+    }
+}
+
+impl<S> From<Sse<S>> for Response
+where
+    S: Stream<Item = Event> + Send + 'static,
+{
+    fn from(sse: Sse<S>) -> Self {
+        sse.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ResponseBody;
+
+    async fn collect_body(mut resp: Response) -> String {
+        let ResponseBody::Stream(stream) = resp.take_body() else {
+            panic!("expected stream body");
+        };
+        let chunks: Vec<Bytes> = stream.map(|c| c.unwrap()).collect().await;
+        String::from_utf8(chunks.concat()).unwrap()
+    }
+
+    #[test]
+    fn event_formats_all_fields() {
+        let event = Event::default()
+            .event("update")
+            .id("42")
+            .retry(Duration::from_secs(3))
+            .data("hello");
+        assert_eq!(
+            event.to_bytes(),
+            "event: update\nid: 42\nretry: 3000\ndata: hello\n\n"
+        );
+    }
+
+    #[test]
+    fn multiline_data_becomes_multiple_data_lines() {
+        let event = Event::default().data("line1\r\nline2\nline3");
+        assert_eq!(
+            event.to_bytes(),
+            "data: line1\ndata: line2\ndata: line3\n\n"
+        );
+    }
+
+    #[test]
+    fn event_name_and_id_cannot_inject_fields() {
+        let event = Event::default().event("a\ndata: x").id("1\n2\0");
+        assert_eq!(event.to_bytes(), "event: adata: x\nid: 12\n\n");
+    }
+
+    #[test]
+    fn bare_cr_in_data_or_comment_cannot_inject_fields() {
+        let event = Event::default()
+            .data("x\rid: evil\r\nevent: evil")
+            .comment("c\rretry: 1");
+        assert_eq!(
+            event.to_bytes(),
+            "data: x\ndata: id: evil\ndata: event: evil\n: c\n: retry: 1\n\n"
+        );
+    }
+
+    #[test]
+    fn json_data_is_single_line() {
+        let event = Event::default()
+            .json_data(&serde_json::json!({"a": 1}))
+            .unwrap();
+        assert_eq!(event.to_bytes(), "data: {\"a\":1}\n\n");
+    }
+
+    #[test]
+    fn comment_only_event() {
+        assert_eq!(Event::default().comment("ping").to_bytes(), ": ping\n\n");
+    }
+
+    #[tokio::test]
+    async fn sse_response_sets_headers_and_streams_events() {
+        let events = futures_util::stream::iter(vec![
+            Event::default().data("a"),
+            Event::default().data("b"),
+        ]);
+        let resp = Sse::new(events).into_response();
+        assert_eq!(resp.content_type(), Some("text/event-stream"));
+        assert_eq!(resp.header("cache-control"), Some("no-cache"));
+        assert_eq!(collect_body(resp).await, "data: a\n\ndata: b\n\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn keep_alive_comment_sent_while_idle() {
+        let events = futures_util::stream::once(async {
+            tokio::time::sleep(Duration::from_secs(25)).await;
+            Event::default().data("late")
+        });
+        let resp = Sse::new(events)
+            .keep_alive(Duration::from_secs(10))
+            .into_response();
+        assert_eq!(collect_body(resp).await, ":\n\n:\n\ndata: late\n\n");
+    }
+}
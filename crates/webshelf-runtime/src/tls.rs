@@ -0,0 +1,388 @@
+//! Native TLS termination (rustls) with certificate hot-reload.
+//!
+//! [`TlsConfig`] (`[server.tls]`) describes the certificate, key, minimum protocol version and
+//! optional client-certificate authentication. [`TlsReloader`] turns it into a
+//! `rustls::ServerConfig` and polls the files; when they change the config is rebuilt and
+//! published through a `watch` channel. The adapters use the latest config for every new
+//! handshake, so established connections keep running on the certificate they negotiated.
+//!
+//! A reload that fails (half-written file, key not matching the certificate, …) is logged and
+//! retried on the next tick; the previous certificate stays in service meanwhile.
+
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, bail};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig, SupportedProtocolVersion};
+use serde::Deserialize;
+use tokio::sync::watch;
+
+/// Upper bound for a single TLS handshake; slow or stalled clients are dropped.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `[server.tls]` configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// Terminate TLS in the server itself (default: false — plain HTTP, e.g. behind nginx).
+    pub enabled: bool,
+    /// PEM certificate chain, leaf first.
+    pub cert_path: String,
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1).
+    pub key_path: String,
+    /// Lowest accepted protocol version: `"1.2"` (default) or `"1.3"`.
+    pub min_version: TlsVersion,
+    /// Client certificate authentication: `none` (default), `optional` or `required`.
+    pub client_auth: ClientAuth,
+    /// PEM bundle of CAs trusted to issue client certificates (required unless `client_auth = "none"`).
+    pub client_ca_path: String,
+    /// How often the files above are checked for changes; 0 disables hot-reload (default: 30).
+    pub reload_interval_secs: u64,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cert_path: String::new(),
+            key_path: String::new(),
+            min_version: TlsVersion::default(),
+            client_auth: ClientAuth::default(),
+            client_ca_path: String::new(),
+            reload_interval_secs: 30,
+        }
+    }
+}
+
+/// Minimum TLS protocol version.
+///
+/// Deserialized from a string so `WEBSHELF_SERVER__TLS__MIN_VERSION=1.3` works even though
+/// the env source parses it as a number.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum TlsVersion {
+    #[default]
+    Tls12,
+    Tls13,
+}
+
+impl TryFrom<String> for TlsVersion {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.trim().trim_start_matches("TLS").trim() {
+            "1.2" => Ok(Self::Tls12),
+            "1.3" => Ok(Self::Tls13),
+            other => Err(format!(
+                "unsupported TLS version `{other}` (expected \"1.2\" or \"1.3\")"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Tls12 => "1.2",
+            Self::Tls13 => "1.3",
+        })
+    }
+}
+
+impl TlsVersion {
+    fn protocol_versions(self) -> &'static [&'static SupportedProtocolVersion] {
+        static TLS12_AND_UP: &[&SupportedProtocolVersion] =
+            &[&rustls::version::TLS13, &rustls::version::TLS12];
+        static TLS13_ONLY: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13];
+        match self {
+            Self::Tls12 => TLS12_AND_UP,
+            Self::Tls13 => TLS13_ONLY,
+        }
+    }
+}
+
+/// Client certificate (mutual TLS) policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuth {
+    #[default]
+    None,
+    /// Verify a certificate when one is presented, accept anonymous clients too.
+    Optional,
+    /// Reject clients without a certificate issued by `client_ca_path`.
+    Required,
+}
+
+/// Build a `rustls::ServerConfig` from the files referenced by `config`.
+///
+/// `alpn` lists the protocols the adapter can serve, most preferred first.
+pub fn load_server_config(config: &TlsConfig, alpn: &[&[u8]]) -> anyhow::Result<ServerConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+    let certs = CertificateDer::pem_file_iter(&config.cert_path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read TLS certificate: {}", config.cert_path))?;
+    if certs.is_empty() {
+        bail!("No certificate found in {}", config.cert_path);
+    }
+    let key = PrivateKeyDer::from_pem_file(&config.key_path)
+        .with_context(|| format!("Failed to read TLS private key: {}", config.key_path))?;
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(config.min_version.protocol_versions())
+        .context("Unsupported TLS protocol versions")?;
+    let builder = match config.client_auth {
+        ClientAuth::None => builder.with_no_client_auth(),
+        mode => {
+            if config.client_ca_path.is_empty() {
+                bail!("server.tls.client_ca_path is required when client_auth is enabled");
+            }
+            let mut roots = RootCertStore::empty();
+            for cert in
+                CertificateDer::pem_file_iter(&config.client_ca_path).with_context(|| {
+                    format!("Failed to read client CA bundle: {}", config.client_ca_path)
+                })?
+            {
+                roots
+                    .add(cert.context("Invalid PEM in client CA bundle")?)
+                    .context("Invalid client CA certificate")?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider);
+            let verifier = match mode {
+                ClientAuth::Optional => verifier.allow_unauthenticated(),
+                _ => verifier,
+            };
+            builder.with_client_cert_verifier(
+                verifier
+                    .build()
+                    .context("Failed to build client certificate verifier")?,
+            )
+        }
+    };
+
+    let mut server_config = builder
+        .with_single_cert(certs, key)
+        .context("TLS certificate and private key do not match")?;
+    server_config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+    Ok(server_config)
+}
+
+/// Current TLS server config, rebuilt whenever the certificate files change.
+///
+/// Cloning shares the same watcher; it stops once every clone and subscriber is dropped.
+#[derive(Clone)]
+pub struct TlsReloader {
+    rx: watch::Receiver<Arc<ServerConfig>>,
+}
+
+impl TlsReloader {
+    /// Load the certificate and, unless `reload_interval_secs` is 0, start watching the files.
+    ///
+    /// Must be called inside a Tokio runtime. Fails if the initial load fails.
+    pub fn start(config: &TlsConfig, alpn: &[&[u8]]) -> anyhow::Result<Self> {
+        let alpn: Vec<Vec<u8>> = alpn.iter().map(|p| p.to_vec()).collect();
+        let stamp = FileStamp::of(config);
+        let initial = load_server_config(config, &alpn_refs(&alpn))?;
+        tracing::info!(
+            cert = %config.cert_path,
+            min_version = %config.min_version,
+            client_auth = ?config.client_auth,
+            "TLS enabled"
+        );
+
+        let (tx, rx) = watch::channel(Arc::new(initial));
+        if config.reload_interval_secs > 0 {
+            tokio::spawn(watch_files(config.clone(), alpn, tx, stamp));
+        }
+        Ok(Self { rx })
+    }
+
+    /// Config to use for the next handshake.
+    pub fn current(&self) -> Arc<ServerConfig> {
+        self.rx.borrow().clone()
+    }
+
+    /// Receiver notified on every successful reload.
+    pub fn subscribe(&self) -> watch::Receiver<Arc<ServerConfig>> {
+        self.rx.clone()
+    }
+}
+
+fn alpn_refs(alpn: &[Vec<u8>]) -> Vec<&[u8]> {
+    alpn.iter().map(Vec::as_slice).collect()
+}
+
+async fn watch_files(
+    config: TlsConfig,
+    alpn: Vec<Vec<u8>>,
+    tx: watch::Sender<Arc<ServerConfig>>,
+    mut stamp: FileStamp,
+) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.reload_interval_secs));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    ticker.tick().await;
+    loop {
+        tokio::select! {
+            _ = tx.closed() => break,
+            _ = ticker.tick() => {}
+        }
+        let current = FileStamp::of(&config);
+        if current == stamp {
+            continue;
+        }
+        match load_server_config(&config, &alpn_refs(&alpn)) {
+            Ok(server_config) => {
+                stamp = current;
+                tx.send_replace(Arc::new(server_config));
+                tracing::info!(cert = %config.cert_path, "TLS certificate reloaded");
+            }
+            // stamp 不更新：文件可能仍在写入，下一轮重试
+            Err(e) => tracing::warn!(
+                error = format!("{e:#}"),
+                "TLS certificate reload failed, keeping the previous certificate"
+            ),
+        }
+    }
+}
+
+/// Modification time and size of the watched files.
+#[derive(Debug, PartialEq, Eq)]
+struct FileStamp(Vec<Option<(SystemTime, u64)>>);
+
+impl FileStamp {
+    fn of(config: &TlsConfig) -> Self {
+        let stat = |path: &str| {
+            if path.is_empty() {
+                return None;
+            }
+            let meta = std::fs::metadata(Path::new(path)).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        };
+        Self(vec![
+            stat(&config.cert_path),
+            stat(&config.key_path),
+            stat(&config.client_ca_path),
+        ])
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Self-signed certificate for `localhost`, written to a fresh temp dir.
+    pub(crate) struct TestCert {
+        pub dir: PathBuf,
+        pub cert_pem: String,
+    }
+
+    impl TestCert {
+        pub fn generate(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "webshelf-tls-{name}-{}",
+                uuid::Uuid::new_v4().simple()
+            ));
+            std::fs::create_dir_all(&dir).unwrap();
+            let cert = Self {
+                dir,
+                cert_pem: String::new(),
+            };
+            cert.rotate()
+        }
+
+        /// Replace the certificate and key on disk with a new pair.
+        pub fn rotate(mut self) -> Self {
+            let issued = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+            self.cert_pem = issued.cert.pem();
+            std::fs::write(self.dir.join("cert.pem"), &self.cert_pem).unwrap();
+            std::fs::write(self.dir.join("key.pem"), issued.signing_key.serialize_pem()).unwrap();
+            self
+        }
+
+        pub fn config(&self) -> TlsConfig {
+            TlsConfig {
+                enabled: true,
+                cert_path: self.dir.join("cert.pem").display().to_string(),
+                key_path: self.dir.join("key.pem").display().to_string(),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl Drop for TestCert {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn loads_self_signed_certificate() {
+        let cert = TestCert::generate("load");
+        let config = load_server_config(&cert.config(), &[b"http/1.1"]).unwrap();
+        assert_eq!(config.alpn_protocols, vec![b"http/1.1".to_vec()]);
+    }
+
+    #[test]
+    fn missing_or_mismatched_files_are_errors() {
+        let cert = TestCert::generate("missing");
+        let mut config = cert.config();
+        config.cert_path = cert.dir.join("nope.pem").display().to_string();
+        let err = load_server_config(&config, &[]).unwrap_err();
+        assert!(format!("{err:#}").contains("nope.pem"));
+
+        let other = TestCert::generate("other");
+        let mut config = cert.config();
+        config.key_path = other.config().key_path;
+        assert!(load_server_config(&config, &[]).is_err());
+    }
+
+    #[test]
+    fn client_auth_requires_ca_bundle() {
+        let cert = TestCert::generate("mtls");
+        let mut config = cert.config();
+        config.client_auth = ClientAuth::Required;
+        let err = load_server_config(&config, &[]).unwrap_err();
+        assert!(err.to_string().contains("client_ca_path"));
+
+        config.client_ca_path = config.cert_path.clone();
+        assert!(load_server_config(&config, &[]).is_ok());
+    }
+
+    #[test]
+    fn min_version_parses_from_strings() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            v: TlsVersion,
+        }
+        let parse = |json: &str| serde_json::from_str::<Wrapper>(json).map(|w| w.v);
+        assert_eq!(parse(r#"{"v":"1.3"}"#).unwrap(), TlsVersion::Tls13);
+        assert_eq!(parse(r#"{"v":"TLS1.2"}"#).unwrap(), TlsVersion::Tls12);
+        assert!(parse(r#"{"v":"1.1"}"#).is_err());
+        assert_eq!(TlsVersion::Tls13.protocol_versions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reloader_picks_up_rotated_certificate() {
+        let cert = TestCert::generate("reload");
+        let mut config = cert.config();
+        config.reload_interval_secs = 1;
+        let reloader = TlsReloader::start(&config, &[]).unwrap();
+        let mut rx = reloader.subscribe();
+        let before = reloader.current();
+
+        // mtime 粒度可能为 1s，等待后再轮换，确保 stamp 变化
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let _cert = cert.rotate();
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .expect("reload within timeout")
+            .unwrap();
+        assert!(!Arc::ptr_eq(&before, &reloader.current()));
+    }
+}
@@ -0,0 +1,69 @@
+//! W3C Trace Context propagation for incoming requests.
+//!
+//! [`RequestIdMiddleware`](crate::RequestIdMiddleware) parents its request span on the
+//! `traceparent` / `tracestate` headers, so traces started by a caller (gateway, frontend,
+//! another service) continue here. Extraction goes through the global OpenTelemetry
+//! propagator — a no-op until the server installs one together with the trace exporter.
+
+use opentelemetry::propagation::Extractor;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::RequestContext;
+
+struct HeaderExtractor<'a, R>(&'a R);
+
+impl<R: RequestContext> Extractor for HeaderExtractor<'_, R> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.header(key)
+    }
+
+    /// Header names cannot be enumerated through [`RequestContext`]; the W3C propagator only
+    /// looks up known keys.
+    fn keys(&self) -> Vec<&str> {
+        Vec::new()
+    }
+}
+
+/// Parent `span` on the trace context carried by the request headers, if any.
+pub fn set_remote_parent(span: &tracing::Span, req: &impl RequestContext) {
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(req))
+    });
+    // Err 仅表示当前 subscriber 未安装 OpenTelemetry layer（导出未开启），忽略即可
+    let _ = span.set_parent(parent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::tests::MockRequest;
+    use opentelemetry::trace::{TraceContextExt, TracerProvider};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn request_span_continues_incoming_trace() {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let req = MockRequest::new("/").with_header(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            );
+            let span = tracing::info_span!("request");
+            set_remote_parent(&span, &req);
+
+            let context = span.context();
+            let span_context = context.span().span_context().clone();
+            assert_eq!(
+                span_context.trace_id().to_string(),
+                "0af7651916cd43dd8448eb211c80319c"
+            );
+            assert!(span_context.is_sampled());
+        });
+    }
+}
@@ -0,0 +1,287 @@
+//! Runtime-neutral WebSocket API.
+//!
+//! A handler upgrades through [`RequestContext::upgrade_websocket`](crate::RequestContext::upgrade_websocket)
+//! and receives a [`WebSocket`] once the handshake completes:
+//!
+//! ```ignore
+//! pub async fn live(mut req: UnifiedRequest) -> Result<Response, HttpError> {
+//!     req.upgrade_websocket(|ws| async move {
+//!         let (mut tx, mut rx) = ws.split();
+//!         while let Some(Ok(msg)) = rx.recv().await {
+//!             if tx.send(msg).await.is_err() { break; }
+//!         }
+//!     })
+//!     .await
+//! }
+//! ```
+//!
+//! 升级请求本身是普通 GET 请求，会完整经过路由上的中间件（JWT 认证等）；
+//! 浏览器无法为 WebSocket 设置 `Authorization` header，认证依赖 `webshelf_jwt` cookie。
+//! 由于 cookie 会随跨站页面发起的握手一起发送，升级前还会校验 `Origin`
+//! （见 [`WebSocketOrigins`]），默认只接受同源页面。
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::{Sink, SinkExt, StreamExt};
+use serde::Deserialize;
+
+use crate::{BoxError, HttpError, RequestContext};
+
+/// Cross-origin pages allowed to open a WebSocket (`server.websocket_origins`), in addition to
+/// same-origin ones.
+///
+/// Adapters read it from request-scoped data (axum extensions / salvo depot); when it is
+/// absent or empty, only same-origin upgrades are accepted. Entries are full origins such as
+/// `https://app.example.com`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(from = "Vec<String>")]
+pub struct WebSocketOrigins(Vec<String>);
+
+impl WebSocketOrigins {
+    pub fn new<I, S>(origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self(origins.into_iter().map(Into::into).collect())
+    }
+
+    /// Whether a handshake with `origin` sent to `host` may proceed.
+    ///
+    /// Requests without `Origin` come from non-browser clients and are allowed; browsers
+    /// always send it on WebSocket handshakes.
+    pub fn allows(&self, origin: Option<&str>, host: Option<&str>) -> bool {
+        let Some(origin) = origin.map(|o| o.trim_end_matches('/')) else {
+            return true;
+        };
+        let same_origin = origin
+            .split_once("://")
+            .zip(host)
+            .is_some_and(|((_, authority), host)| authority.eq_ignore_ascii_case(host));
+        same_origin
+            || self
+                .0
+                .iter()
+                .any(|o| o.trim_end_matches('/').eq_ignore_ascii_case(origin))
+    }
+}
+
+impl From<Vec<String>> for WebSocketOrigins {
+    fn from(origins: Vec<String>) -> Self {
+        Self(origins)
+    }
+}
+
+/// Reject a WebSocket handshake from a disallowed `Origin` with 403.
+///
+/// Called by the adapters' `upgrade_websocket` before upgrading.
+pub fn check_origin<R: RequestContext + ?Sized>(req: &R) -> Result<(), HttpError> {
+    let default = WebSocketOrigins::default();
+    let origins = req.get_data_ref::<WebSocketOrigins>().unwrap_or(&default);
+    if origins.allows(req.header("origin"), req.header("host")) {
+        Ok(())
+    } else {
+        Err(HttpError::forbidden("WebSocket origin not allowed"))
+    }
+}
+
+/// WebSocket message, independent of the underlying framework's message type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Bytes),
+    Ping(Bytes),
+    Pong(Bytes),
+    Close(Option<CloseFrame>),
+}
+
+/// Close frame payload (RFC 6455 §5.5.1).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    pub code: u16,
+    pub reason: String,
+}
+
+impl Message {
+    pub fn text(text: impl Into<String>) -> Self {
+        Message::Text(text.into())
+    }
+
+    pub fn binary(bytes: impl Into<Bytes>) -> Self {
+        Message::Binary(bytes.into())
+    }
+
+    /// Serialize `value` as a JSON text message.
+    pub fn json(value: &impl serde::Serialize) -> Result<Self, serde_json::Error> {
+        serde_json::to_string(value).map(Message::Text)
+    }
+
+    pub fn is_close(&self) -> bool {
+        matches!(self, Message::Close(_))
+    }
+}
+
+type BoxSink = Pin<Box<dyn Sink<Message, Error = BoxError> + Send>>;
+type BoxStream = Pin<Box<dyn Stream<Item = Result<Message, BoxError>> + Send>>;
+
+/// An upgraded WebSocket connection. Use [`split`](Self::split) to read and write concurrently.
+pub struct WebSocket {
+    sender: WsSender,
+    receiver: WsReceiver,
+}
+
+impl WebSocket {
+    /// Build from a sink/stream pair — used by the adapters to wrap their native socket.
+    pub fn new<Si, St, E>(sink: Si, stream: St) -> Self
+    where
+        Si: Sink<Message, Error = E> + Send + 'static,
+        St: Stream<Item = Result<Message, E>> + Send + 'static,
+        E: Into<BoxError> + 'static,
+    {
+        Self {
+            sender: WsSender(Box::pin(sink.sink_map_err(Into::into))),
+            receiver: WsReceiver(Box::pin(stream.map(|item| item.map_err(Into::into)))),
+        }
+    }
+
+    pub async fn send(&mut self, msg: Message) -> Result<(), BoxError> {
+        self.sender.send(msg).await
+    }
+
+    /// Next message; `None` once the connection is closed.
+    pub async fn recv(&mut self) -> Option<Result<Message, BoxError>> {
+        self.receiver.recv().await
+    }
+
+    /// Send a close frame and flush.
+    pub async fn close(self) -> Result<(), BoxError> {
+        self.sender.close().await
+    }
+
+    pub fn split(self) -> (WsSender, WsReceiver) {
+        (self.sender, self.receiver)
+    }
+}
+
+/// Write half of a [`WebSocket`].
+pub struct WsSender(BoxSink);
+
+impl WsSender {
+    pub async fn send(&mut self, msg: Message) -> Result<(), BoxError> {
+        SinkExt::send(&mut self.0, msg).await
+    }
+
+    pub async fn close(mut self) -> Result<(), BoxError> {
+        SinkExt::close(&mut self.0).await
+    }
+}
+
+impl Sink<Message> for WsSender {
+    type Error = BoxError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.0.as_mut().poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), BoxError> {
+        self.0.as_mut().start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.0.as_mut().poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.0.as_mut().poll_close(cx)
+    }
+}
+
+/// Read half of a [`WebSocket`].
+pub struct WsReceiver(BoxStream);
+
+impl WsReceiver {
+    pub async fn recv(&mut self) -> Option<Result<Message, BoxError>> {
+        self.0.next().await
+    }
+}
+
+impl Stream for WsReceiver {
+    type Item = Result<Message, BoxError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.as_mut().poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    /// In-memory loopback socket: everything sent is received back.
+    fn loopback() -> WebSocket {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Message>();
+        let sink = futures_util::sink::unfold(tx, |tx, msg: Message| async move {
+            let _ = tx.send(msg);
+            Ok::<_, Infallible>(tx)
+        });
+        let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|msg| (Ok::<_, Infallible>(msg), rx))
+        });
+        WebSocket::new(sink, stream)
+    }
+
+    #[tokio::test]
+    async fn send_and_recv_round_trip() {
+        let mut ws = loopback();
+        ws.send(Message::text("hello")).await.unwrap();
+        assert_eq!(ws.recv().await.unwrap().unwrap(), Message::text("hello"));
+    }
+
+    #[tokio::test]
+    async fn split_halves_work_independently() {
+        let (mut tx, mut rx) = loopback().split();
+        let writer = tokio::spawn(async move {
+            tx.send(Message::binary(Bytes::from_static(b"\x01\x02")))
+                .await
+                .unwrap();
+            tx.send(Message::Close(None)).await.unwrap();
+        });
+        assert_eq!(
+            rx.recv().await.unwrap().unwrap(),
+            Message::Binary(Bytes::from_static(b"\x01\x02"))
+        );
+        assert!(rx.recv().await.unwrap().unwrap().is_close());
+        writer.await.unwrap();
+    }
+
+    #[test]
+    fn same_origin_is_allowed_by_default() {
+        let origins = WebSocketOrigins::default();
+        assert!(origins.allows(Some("https://app.example.com"), Some("app.example.com")));
+        assert!(origins.allows(Some("http://LOCALHOST:3000"), Some("localhost:3000")));
+        assert!(origins.allows(None, Some("app.example.com")));
+    }
+
+    #[test]
+    fn cross_origin_needs_an_allowlist_entry() {
+        let host = Some("api.example.com");
+        assert!(!WebSocketOrigins::default().allows(Some("https://evil.example"), host));
+        assert!(!WebSocketOrigins::default().allows(Some("null"), host));
+        // Port is part of the origin.
+        assert!(!WebSocketOrigins::default().allows(Some("http://api.example.com:8080"), host));
+
+        let origins = WebSocketOrigins::new(["https://app.example.com/"]);
+        assert!(origins.allows(Some("https://app.example.com"), host));
+        assert!(!origins.allows(Some("http://app.example.com"), host));
+    }
+
+    #[test]
+    fn json_message_is_text() {
+        let msg = Message::json(&serde_json::json!({"balance": 10})).unwrap();
+        assert_eq!(msg, Message::text("{\"balance\":10}"));
+    }
+}
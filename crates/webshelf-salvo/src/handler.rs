@@ -1,8 +1,9 @@
 use std::future::Future;
 
 use bytes::Bytes;
-use salvo::http::StatusCode;
+use salvo::http::ParseError;
 use salvo::{Depot, FlowCtrl, Handler, Request, Response};
+use webshelf_runtime::multipart::is_multipart;
 use webshelf_runtime::{HttpError, Response as UnifiedResponse};
 
 use crate::{UnifiedRequest, render_response};
@@ -15,12 +16,23 @@ use crate::{UnifiedRequest, render_response};
 #[derive(Clone)]
 pub(crate) struct CachedBody(pub Bytes);
 
+/// Map a `req.payload()` failure to an error response.
+///
+/// 超过 `secure_max_size`（由 `middleware::max_body_size` 设置）时返回 413，与 axum 端一致。
+pub(crate) fn body_read_error(err: &ParseError) -> HttpError {
+    match err {
+        ParseError::PayloadTooLarge => HttpError::payload_too_large("Request body is too large"),
+        _ => HttpError::bad_request("Failed to read request body"),
+    }
+}
+
 /// Handler 包装器 —— 将统一风格的 async handler 包装为 salvo::Handler
 ///
 /// 支持签名：`async fn(UnifiedRequest) -> Result<Response, HttpError>`
 ///
 /// **Eager Buffering**：在 handle() 中 async 预读 body 后再创建 UnifiedRequest。
 /// **GET/HEAD 优化**：无 body 的请求跳过 body 读取。
+/// **multipart 上传**：不预读，由 `parse_multipart` 从 body 流中逐段解析。
 pub struct UnifiedHandler<H, F>(pub H)
 where
     H: Fn(UnifiedRequest) -> F + Send + Sync + 'static,
@@ -39,6 +51,18 @@ where
         res: &mut Response,
         _ctrl: &mut FlowCtrl,
     ) {
+        // 与 axum 端一致：进入 handler 时记录匹配的路由模板（请求 span 的 `route` 字段）
+        webshelf_runtime::request_id::record_route(req.method().as_str(), req.matched_path());
+        let cached = depot.obtain::<CachedBody>().is_ok();
+        let multipart = req
+            .content_type()
+            .is_some_and(|mime| is_multipart(mime.essence_str()));
+        if multipart && !cached {
+            // SAFETY: 同下
+            let unified_req = unsafe { UnifiedRequest::unbuffered(req, depot) };
+            return self.respond(unified_req, res).await;
+        }
+
         // ⚡ Eager Buffering：预读 body
         // 先检查 Depot 中是否有上游中间件缓存的 body（例如 RateLimitMiddleware
         // 在 email 限流时预读了 body），若无则从 req.payload() 读取。
@@ -73,11 +97,7 @@ where
                                 "Failed to read request body in UnifiedHandler: {:?}",
                                 e
                             );
-                            res.status_code(StatusCode::BAD_REQUEST);
-                            res.render(salvo::writing::Json(serde_json::json!({
-                                "error": "bad_request",
-                                "message": "Failed to read request body"
-                            })));
+                            render_response(body_read_error(&e).into(), res);
                             return;
                         }
                     }
@@ -86,6 +106,16 @@ where
         };
         // SAFETY: UnifiedRequest 的 req/depot 指针在此 handle() 调用期间有效
         let unified_req = unsafe { UnifiedRequest::new(req, depot, cached_body) };
+        self.respond(unified_req, res).await;
+    }
+}
+
+impl<H, F> UnifiedHandler<H, F>
+where
+    H: Fn(UnifiedRequest) -> F + Send + Sync + 'static,
+    F: Future<Output = Result<UnifiedResponse, HttpError>> + Send + 'static,
+{
+    async fn respond(&self, unified_req: UnifiedRequest, res: &mut Response) {
         match (self.0)(unified_req).await {
             Ok(unified_res) => render_response(unified_res, res),
             Err(err) => {
@@ -101,6 +131,7 @@ mod tests {
     use super::*;
     use bytes::Bytes;
     use salvo::Depot;
+    use salvo::http::StatusCode;
     use std::sync::Arc;
     use webshelf_runtime::RequestContext;
 
@@ -5,14 +5,15 @@ mod request;
 mod route;
 mod router;
 mod runtime;
+mod ws;
 
 pub use router::SalvoRouter;
 pub use runtime::SalvoRuntime;
 
 // 统一 Request/Response/Handler 适配
 pub use handler::UnifiedHandler;
-pub use middleware::with_rate_limit_hoop;
-pub use render_response::render_response;
+pub use middleware::{UnifiedMiddleware, with_middleware_hoop, with_rate_limit_hoop};
+pub use render_response::{render_response, take_response};
 pub use request::UnifiedRequest;
 pub use route::{delete, get, post, put};
 
@@ -159,7 +160,13 @@ mod tests {
 
     #[test]
     fn salvo_runtime_serve_returns_send_future() {
-        let fut = SalvoRuntime::<TestState>::serve(SalvoRouter::new(), TestState, "0.0.0.0:0");
+        let fut = SalvoRuntime::<TestState>::serve(
+            SalvoRouter::new(),
+            TestState,
+            "0.0.0.0:0",
+            None,
+            Default::default(),
+        );
         fn assert_send<T: Send>(_t: T) {}
         assert_send(fut);
     }
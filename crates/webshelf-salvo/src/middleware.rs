@@ -1,7 +1,8 @@
 //! Salvo 生态中间件构建器。
 //!
 //! 提供与 `webshelf-axum`（tower-http）能力等价的中间件工厂函数。
-//! 提供对称的认证、鉴权、限流中间件。
+//! 提供对称的请求 ID、指标、安全响应头、认证、鉴权、限流中间件（逻辑实现于 `webshelf_runtime`，此处为桥接）。
+//! 自定义 [`webshelf_runtime::Middleware`] 通过 [`UnifiedMiddleware`] / [`with_middleware_hoop`] 挂载。
 //! 服务端通过 `webshelf_salvo::middleware::*` 使用，不直接依赖 `salvo` crate。
 //!
 //! # CORS 注意事项
@@ -11,17 +12,22 @@
 //! 当前实现将其应用在顶级 Router，对于已知路由能正常处理 CORS。
 //! 若出现 CORS preflight 问题，需要将 CORS 配置传递到 `serve()` 方法。
 
-use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use salvo::http::Method;
-use salvo::http::StatusCode;
 use salvo::{Depot, FlowCtrl, Handler, Request, Response};
 
-use crate::handler::CachedBody;
-use webshelf_runtime::RateLimitGuard;
-use webshelf_runtime::auth::{AuthUser, validate_jwt};
-use webshelf_runtime::middleware::MiddlewareState;
+use crate::handler::{CachedBody, body_read_error};
+use crate::render_response::take_response;
+use crate::{UnifiedRequest, render_response};
+use webshelf_runtime::{
+    AdminGuard, AuthGuard, MatchedRoute, MetricsMiddleware, Middleware, MiddlewareState, Next,
+    RateLimitGuard, RequestIdMiddleware, SecurityHeadersConfig, SecurityHeadersMiddleware,
+    TrustedProxies, WebSocketOrigins,
+};
 
 /// CORS 配置，与 axum 的 CorsLayer 语义等价
 ///
@@ -149,44 +155,22 @@ pub fn logger() -> impl salvo::Handler {
     salvo::logging::Logger::new()
 }
 
-/// 创建请求体大小限制中间件 handler
-pub fn max_body_size(max_bytes: u64) -> impl salvo::Handler {
-    salvo::size_limiter::max_size(max_bytes)
-}
-
-/// 创建 panic 捕获中间件 handler
-pub fn catch_panic() -> impl salvo::Handler {
-    salvo::catch_panic::CatchPanic::new()
+/// 创建请求 ID 中间件 handler（`X-Request-Id` + 关联的 tracing span，见 [`RequestIdMiddleware`]）
+pub fn request_id() -> impl salvo::Handler {
+    UnifiedMiddleware(RequestIdMiddleware)
 }
 
-// ── 认证中间件 ─────────────────────────────────────
-
-/// 认证中间件 —— 验证 JWT token 的签名/过期/issuer/audience，
-/// 并检查 token_version 是否与数据库中当前版本匹配（实现 logout-all 功能）。
-///
-/// 与 axum 版本的 `auth_middleware` 对称：使用 `MiddlewareState` 抽象
-/// 获取 jwt_secret 和 token_version 校验，业务逻辑委托给 `webshelf_runtime`。
-///
-/// 泛型参数 `S` 为共享状态类型（必须实现 `MiddlewareState` trait）。
-pub struct AuthMiddleware<S>(PhantomData<S>);
-
-impl<S> Default for AuthMiddleware<S> {
-    fn default() -> Self {
-        Self::new()
-    }
+/// 创建 HTTP 指标中间件 handler（请求数 / 延迟，见 [`MetricsMiddleware`]）
+pub fn metrics() -> impl salvo::Handler {
+    Metrics(UnifiedMiddleware(MetricsMiddleware))
 }
 
-impl<S> AuthMiddleware<S> {
-    pub fn new() -> Self {
-        Self(PhantomData)
-    }
-}
+/// hoop 执行前 salvo 已完成路由匹配；`UnifiedRequest::matched_route_pattern()` 返回 `None`，
+/// 因此把匹配到的路由模板以 [`MatchedRoute`] 存入 Depot 交给 `MetricsMiddleware`。
+struct Metrics(UnifiedMiddleware<MetricsMiddleware>);
 
 #[async_trait]
-impl<S> Handler for AuthMiddleware<S>
-where
-    S: MiddlewareState + Send + Sync + 'static,
-{
+impl Handler for Metrics {
     async fn handle(
         &self,
         req: &mut Request,
@@ -194,81 +178,36 @@ where
         res: &mut Response,
         ctrl: &mut FlowCtrl,
     ) {
-        let path = req.uri().path();
-        if path == "/health" || path == "/api/health" {
-            ctrl.call_next(req, depot, res).await;
-            return;
-        }
-
-        let state: S = match depot.obtain::<S>() {
-            Ok(s) => s.clone(),
-            Err(_) => {
-                tracing::error!("AuthMiddleware: state not found in Depot");
-                res.status_code(StatusCode::INTERNAL_SERVER_ERROR)
-                    .render(salvo::writing::Json(serde_json::json!({"error": "internal_error", "message": "An unexpected error occurred"})));
-                return;
-            }
-        };
-
-        let token = match extract_bearer_token(req).or_else(|| extract_jwt_cookie(req)) {
-            Some(t) => t,
-            None => {
-                res.status_code(StatusCode::UNAUTHORIZED)
-                    .render(salvo::writing::Json(serde_json::json!({"error": "unauthorized", "message": "Missing or invalid Authorization header"})));
-                return;
-            }
-        };
-
-        match validate_jwt(&token, state.jwt_secret()) {
-            Ok(claims) => {
-                let user_id: i64 = match claims.sub.parse() {
-                    Ok(id) => id,
-                    Err(_) => {
-                        tracing::warn!("Invalid user ID format in token: {}", claims.sub);
-                        res.status_code(StatusCode::UNAUTHORIZED)
-                            .render(salvo::writing::Json(serde_json::json!({"error": "unauthorized", "message": "Invalid or expired token"})));
-                        return;
-                    }
-                };
-
-                match state
-                    .check_token_version(user_id, claims.token_version)
-                    .await
-                {
-                    Ok(()) => {
-                        depot.inject(AuthUser::from(claims));
-                        ctrl.call_next(req, depot, res).await;
-                    }
-                    Err(e) => {
-                        tracing::warn!("Token version validation failed: {}", e);
-                        res.status_code(StatusCode::UNAUTHORIZED)
-                            .render(salvo::writing::Json(serde_json::json!({"error": "unauthorized", "message": "Invalid or expired token"})));
-                    }
-                }
-            }
-            Err(e) => {
-                tracing::warn!("Token validation failed: {}", e);
-                res.status_code(StatusCode::UNAUTHORIZED)
-                    .render(salvo::writing::Json(serde_json::json!({"error": "unauthorized", "message": "Invalid or expired token"})));
-            }
+        if !req.matched_path().is_empty() {
+            depot.inject(MatchedRoute(req.matched_path().to_string()));
         }
+        self.0.handle(req, depot, res, ctrl).await;
     }
 }
 
-impl<S> Clone for AuthMiddleware<S> {
-    fn clone(&self) -> Self {
-        Self(PhantomData)
-    }
+/// 创建安全响应头中间件 handler（HSTS / CSP 等，见 [`SecurityHeadersMiddleware`]）
+pub fn security_headers(config: &SecurityHeadersConfig) -> impl salvo::Handler {
+    UnifiedMiddleware(SecurityHeadersMiddleware::new(config))
 }
 
-// ── 管理员权限守卫 ─────────────────────────────────
+/// 创建请求体大小限制中间件 handler
+///
+/// 除按 Content-Length 拒绝超限请求外，还把 `req.payload()` 的读取上限设为 `max_bytes`
+/// —— salvo 默认仅 64 KiB，否则较大的上传（multipart）会在读取 body 时失败。
+pub fn max_body_size(max_bytes: u64) -> impl salvo::Handler {
+    MaxBodySize {
+        max_bytes,
+        limiter: salvo::size_limiter::max_size(max_bytes),
+    }
+}
 
-/// Admin 角色守卫 —— 检查当前用户是否为 admin/system。
-/// 必须位于 `AuthMiddleware` 之后（需要 Depot 中有 AuthUser）。
-pub struct RequireAdmin;
+struct MaxBodySize {
+    max_bytes: u64,
+    limiter: salvo::size_limiter::MaxSize,
+}
 
 #[async_trait]
-impl Handler for RequireAdmin {
+impl Handler for MaxBodySize {
     async fn handle(
         &self,
         req: &mut Request,
@@ -276,45 +215,74 @@ impl Handler for RequireAdmin {
         res: &mut Response,
         ctrl: &mut FlowCtrl,
     ) {
-        let auth_user = match depot.obtain::<AuthUser>() {
-            Ok(u) => u.clone(),
-            Err(_) => {
-                res.status_code(StatusCode::UNAUTHORIZED)
-                    .render(salvo::writing::Json(serde_json::json!({"error": "unauthorized", "message": "Authentication required"})));
-                return;
-            }
-        };
+        req.set_secure_max_size(usize::try_from(self.max_bytes).unwrap_or(usize::MAX));
+        self.limiter.handle(req, depot, res, ctrl).await;
+    }
+}
 
-        if auth_user.role != "admin" && auth_user.role != "system" {
-            res.status_code(StatusCode::FORBIDDEN)
-                .render(salvo::writing::Json(serde_json::json!({"error": "forbidden", "message": "Admin privileges required"})));
-            return;
-        }
+/// 创建 panic 捕获中间件 handler
+pub fn catch_panic() -> impl salvo::Handler {
+    salvo::catch_panic::CatchPanic::new()
+}
 
-        ctrl.call_next(req, depot, res).await;
-    }
+/// 将可信代理列表注入 Depot，供 `client_ip()` 解析转发头（axum 端对应 `Extension` layer）
+pub fn trusted_proxies(proxies: TrustedProxies) -> impl salvo::Handler {
+    salvo::affix_state::inject(proxies)
 }
 
-// ── 限流中间件 ─────────────────────────────────────
+/// 将 WebSocket 允许的跨站 Origin 注入 Depot，供 `upgrade_websocket` 校验（axum 端对应 `Extension` layer）
+pub fn websocket_origins(origins: WebSocketOrigins) -> impl salvo::Handler {
+    salvo::affix_state::inject(origins)
+}
 
-/// Apply rate-limit middleware to a route (salvo equivalent of axum's `with_rate_limit_layer`).
+// ── 统一中间件桥接 ─────────────────────────────────
+
+/// 将框架无关的 [`Middleware`] 包装为 salvo Handler（axum 端对应 `run_middleware`）。
 ///
-/// Server route builders can use this single function regardless of
-/// framework, eliminating duplicated route registration code.
-pub fn with_rate_limit_hoop(
-    route: crate::SalvoRouter,
-    guard: RateLimitGuard,
-) -> crate::SalvoRouter {
-    route.hoop(RateLimitMiddleware { guard })
+/// 仅当 [`Middleware::needs_body`] 为 true 时按 `UnifiedHandler` 的方式预读 body（GET/HEAD 跳过）
+/// 并以 `CachedBody` 存入 Depot，下游 handler 读取到的 body 与中间件一致；否则中间件读到空 body，
+/// 请求 body 原样留给下游。`set_data` 直接写入 Depot，对下游可见。
+///
+/// 中间件调用 `next.run(req)` 时执行 `ctrl.call_next`，随后通过 [`take_response`]
+/// 将下游写入的响应转换为统一 `Response` 交还给中间件；中间件最终返回的
+/// `Response` 再经 `render_response` 写回 salvo Response。
+///
+/// 未调用 `next` 即返回（短路）时执行 `ctrl.skip_rest()`：salvo 只在 3xx/4xx/5xx 时
+/// 自动停止后续 handler，2xx 短路（缓存命中、CORS preflight 的 204）需要显式跳过。
+pub struct UnifiedMiddleware<M>(pub M);
+
+/// 跨 `Next` 闭包传递 handle() 参数的裸指针。
+///
+/// SAFETY: 与 `UnifiedRequest` 相同的不变式 —— 指针仅在 `UnifiedMiddleware::handle()`
+/// 调用期间使用，闭包不会逃逸该调用；调用下游前已 drop `UnifiedRequest`，
+/// 不存在同时活跃的可变别名。
+struct HandlePtr<T>(NonNull<T>);
+
+unsafe impl<T> Send for HandlePtr<T> {}
+
+impl<T> Clone for HandlePtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
 }
 
-/// 通用限流中间件 Handler（对称于 axum 版本的 `rate_limit_middleware`）。
-pub struct RateLimitMiddleware {
-    pub guard: RateLimitGuard,
+impl<T> Copy for HandlePtr<T> {}
+
+impl<T> HandlePtr<T> {
+    fn new(value: &mut T) -> Self {
+        Self(NonNull::from(value))
+    }
+
+    /// # Safety
+    /// 见 struct 上的说明。
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn get(&self) -> &mut T {
+        unsafe { &mut *self.0.as_ptr() }
+    }
 }
 
 #[async_trait]
-impl Handler for RateLimitMiddleware {
+impl<M: Middleware> Handler for UnifiedMiddleware<M> {
     async fn handle(
         &self,
         req: &mut Request,
@@ -322,374 +290,291 @@ impl Handler for RateLimitMiddleware {
         res: &mut Response,
         ctrl: &mut FlowCtrl,
     ) {
-        if !self.guard.limiter.is_available() {
-            ctrl.call_next(req, depot, res).await;
-            return;
-        }
-
-        let ip = extract_client_ip(req.headers()).or_else(|| extract_peer_ip(req));
-        if let Some(ip) = ip {
-            let ip_key = format!("{}:ip:{}", self.guard.key_prefix, ip);
-            match self
-                .guard
-                .limiter
-                .check(
-                    &ip_key,
-                    self.guard.ip_max_requests,
-                    self.guard.ip_window_seconds,
-                )
-                .await
-            {
-                Ok(true) => {}
-                Ok(false) => {
-                    tracing::warn!(
-                        "Rate limit exceeded (IP) for {}: {}",
-                        self.guard.key_prefix,
-                        ip
-                    );
-                    res.status_code(StatusCode::TOO_MANY_REQUESTS)
-                        .render(salvo::writing::Json(serde_json::json!({"error": "rate_limited", "message": "Too many requests. Please try again later."})));
-                    return;
+        // 不读取 body 的中间件不缓冲，body 留给下游 handler 读取
+        let cached_body = if !self.0.needs_body()
+            || req.method() == Method::GET
+            || req.method() == Method::HEAD
+        {
+            Bytes::new()
+        } else if let Ok(cached) = depot.obtain::<CachedBody>() {
+            cached.0.clone()
+        } else {
+            match req.payload().await {
+                Ok(bytes) => {
+                    let bytes = bytes.clone();
+                    depot.inject(CachedBody(bytes.clone()));
+                    bytes
                 }
                 Err(e) => {
-                    tracing::error!(
-                        "Rate-limit Redis error (IP) for {}: {:?}",
-                        self.guard.key_prefix,
-                        e
-                    );
-                    if !self.guard.limiter.fail_open() {
-                        res.status_code(StatusCode::INTERNAL_SERVER_ERROR)
-                            .render(salvo::writing::Json(serde_json::json!({"error": "internal_error", "message": "An unexpected error occurred"})));
-                        return;
-                    }
+                    tracing::warn!("Failed to read request body in middleware: {:?}", e);
+                    render_response(body_read_error(&e).into(), res);
+                    return;
                 }
             }
-        }
+        };
 
-        if let Some(email_max) = self.guard.email_max_requests {
-            let bytes = match req.payload().await {
-                Ok(b) => b.clone(),
-                Err(e) => {
-                    tracing::error!(
-                        "Failed to read body for rate limiting ({}): {:?}",
-                        self.guard.key_prefix,
-                        e
-                    );
-                    res.status_code(StatusCode::INTERNAL_SERVER_ERROR)
-                        .render(salvo::writing::Json(serde_json::json!({"error": "internal_error", "message": "An unexpected error occurred"})));
-                    return;
-                }
+        let ran_next = AtomicBool::new(false);
+        let (req_ptr, depot_ptr) = (HandlePtr::new(req), HandlePtr::new(depot));
+        let (res_ptr, ctrl_ptr) = (HandlePtr::new(res), HandlePtr::new(ctrl));
+
+        // SAFETY: req/depot 在整个 handle() 调用期间有效。
+        let unified_req =
+            unsafe { UnifiedRequest::new(req_ptr.get(), depot_ptr.get(), cached_body) };
+        let ran_next_ref = &ran_next;
+        let next = Next::new(move |unified_req: UnifiedRequest| async move {
+            ran_next_ref.store(true, Ordering::Relaxed);
+            drop(unified_req);
+            // SAFETY: 见 HandlePtr —— 闭包在 handle() 返回前执行完毕。
+            let (req, depot, res, ctrl) = unsafe {
+                (
+                    req_ptr.get(),
+                    depot_ptr.get(),
+                    res_ptr.get(),
+                    ctrl_ptr.get(),
+                )
             };
-
-            // 将 body 存入 Depot，使下游 UnifiedHandler 能从中读取而非从
-            // req.payload() 再读（后者已被此中间件消耗）。
-            depot.inject(CachedBody(bytes.clone()));
-
-            if let Some(email) = extract_email_from_body(&bytes) {
-                let email_key = format!("{}:email:{}", self.guard.key_prefix, email);
-                match self
-                    .guard
-                    .limiter
-                    .check(&email_key, email_max, self.guard.email_window_seconds)
-                    .await
-                {
-                    Ok(true) => {}
-                    Ok(false) => {
-                        tracing::warn!(
-                            "Rate limit exceeded (email) for {}: {}",
-                            self.guard.key_prefix,
-                            email
-                        );
-                        res.status_code(StatusCode::TOO_MANY_REQUESTS)
-                            .render(salvo::writing::Json(serde_json::json!({"error": "rate_limited", "message": "Too many requests. Please try again later."})));
-                        return;
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            "Rate-limit Redis error (email) for {}: {:?}",
-                            self.guard.key_prefix,
-                            e
-                        );
-                        if !self.guard.limiter.fail_open() {
-                            res.status_code(StatusCode::INTERNAL_SERVER_ERROR)
-                                .render(salvo::writing::Json(serde_json::json!({"error": "internal_error", "message": "An unexpected error occurred"})));
-                            return;
-                        }
-                    }
-                }
-            }
+            ctrl.call_next(req, depot, res).await;
+            take_response(res).await
+        });
+
+        let response = self.0.handle(unified_req, next).await;
+        // SAFETY: 中间件已返回，Next 闭包（若被调用）已结束。
+        render_response(response, unsafe { res_ptr.get() });
+        if !ran_next.load(Ordering::Relaxed) {
+            // SAFETY: 同上。
+            unsafe { ctrl_ptr.get() }.skip_rest();
         }
-
-        ctrl.call_next(req, depot, res).await;
     }
 }
 
-// ── 辅助函数 ───────────────────────────────────────
+/// Apply a framework-agnostic middleware to a route (salvo equivalent of axum's `with_middleware`).
+pub fn with_middleware_hoop<M: Middleware>(
+    route: crate::SalvoRouter,
+    middleware: M,
+) -> crate::SalvoRouter {
+    route.hoop(UnifiedMiddleware(middleware))
+}
 
-const BEARER_PREFIX: &[u8] = b"bearer ";
+// ── 认证中间件 ─────────────────────────────────────
 
-fn extract_bearer_token(req: &Request) -> Option<String> {
-    let auth_header = req.headers().get(http::header::AUTHORIZATION)?;
-    let auth_value = auth_header.to_str().ok()?;
-    if auth_value.len() <= BEARER_PREFIX.len() {
-        return None;
-    }
-    if !auth_value.as_bytes()[..BEARER_PREFIX.len()].eq_ignore_ascii_case(BEARER_PREFIX) {
-        return None;
-    }
-    Some(auth_value[BEARER_PREFIX.len()..].to_string())
-}
+/// 认证中间件 —— 验证 JWT token 的签名/过期/issuer/audience，
+/// 并检查 token_version 是否与数据库中当前版本匹配（实现 logout-all 功能）。
+///
+/// 与 axum 版本的 `auth_middleware` 对称：业务逻辑委托给 `webshelf_runtime::AuthGuard`，
+/// 共享状态由 `SalvoRuntime::serve()` 注入 Depot，`AuthGuard` 通过 `get_data` 读取。
+///
+/// 泛型参数 `S` 为共享状态类型（必须实现 `MiddlewareState` trait）。
+pub struct AuthMiddleware<S>(UnifiedMiddleware<AuthGuard<S>>);
 
-fn extract_jwt_cookie(req: &Request) -> Option<String> {
-    let cookie_header = req.headers().get(http::header::COOKIE)?;
-    let cookie_str = cookie_header.to_str().ok()?;
-    cookie_str
-        .split(';')
-        .map(str::trim)
-        .filter_map(|s| cookie::Cookie::parse(s).ok())
-        .find(|c| c.name() == "webshelf_jwt")
-        .map(|c| c.value().to_string())
+impl<S> Default for AuthMiddleware<S> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-fn extract_client_ip(headers: &salvo::http::HeaderMap) -> Option<String> {
-    if let Some(value) = headers.get("x-forwarded-for")
-        && let Ok(value) = value.to_str()
-        && let Some(ip) = value.split(',').next()
-        && !ip.trim().is_empty()
-    {
-        return Some(ip.trim().to_string());
-    }
-    if let Some(value) = headers.get("x-real-ip")
-        && let Ok(value) = value.to_str()
-        && !value.trim().is_empty()
-    {
-        return Some(value.trim().to_string());
+impl<S> AuthMiddleware<S> {
+    pub fn new() -> Self {
+        Self(UnifiedMiddleware(AuthGuard::new()))
     }
-    None
 }
 
-fn extract_peer_ip(req: &Request) -> Option<String> {
-    match req.remote_addr() {
-        salvo::conn::SocketAddr::IPv4(addr) => Some(addr.ip().to_string()),
-        salvo::conn::SocketAddr::IPv6(addr) => Some(addr.ip().to_string()),
-        _ => None,
+#[async_trait]
+impl<S: MiddlewareState> Handler for AuthMiddleware<S> {
+    async fn handle(
+        &self,
+        req: &mut Request,
+        depot: &mut Depot,
+        res: &mut Response,
+        ctrl: &mut FlowCtrl,
+    ) {
+        self.0.handle(req, depot, res, ctrl).await;
     }
 }
 
-fn extract_email_from_body(bytes: &[u8]) -> Option<String> {
-    if let Ok(val) = serde_json::from_slice::<serde_json::Value>(bytes)
-        && let Some(email) = val.get("email")?.as_str()
-    {
-        return Some(email.to_lowercase());
+impl<S> Clone for AuthMiddleware<S> {
+    fn clone(&self) -> Self {
+        Self::new()
     }
-    serde_urlencoded::from_bytes::<std::collections::HashMap<String, String>>(bytes)
-        .ok()?
-        .remove("email")
-        .map(|e| e.to_lowercase())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    // ── extract_bearer_token tests ────────────────────────────────
-
-    #[test]
-    fn extract_bearer_token_success() {
-        let mut req = Request::new();
-        // 直接操作 headers
-        req.headers_mut()
-            .insert("authorization", "Bearer my-token".parse().unwrap());
-        assert_eq!(extract_bearer_token(&req), Some("my-token".to_string()));
-    }
+// ── 管理员权限守卫 ─────────────────────────────────
 
-    #[test]
-    fn extract_bearer_token_lowercase_bearer() {
-        let mut req = Request::new();
-        req.headers_mut()
-            .insert("authorization", "bearer my-token".parse().unwrap());
-        assert_eq!(extract_bearer_token(&req), Some("my-token".to_string()));
-    }
+/// Admin 角色守卫 —— 检查当前用户是否为 admin/system。
+/// 必须位于 `AuthMiddleware` 之后（需要 Depot 中有 AuthUser）。
+pub struct RequireAdmin;
 
-    #[test]
-    fn extract_bearer_token_mixed_case() {
-        let mut req = Request::new();
-        req.headers_mut()
-            .insert("authorization", "BEARER token-value".parse().unwrap());
-        assert_eq!(extract_bearer_token(&req), Some("token-value".to_string()));
+#[async_trait]
+impl Handler for RequireAdmin {
+    async fn handle(
+        &self,
+        req: &mut Request,
+        depot: &mut Depot,
+        res: &mut Response,
+        ctrl: &mut FlowCtrl,
+    ) {
+        UnifiedMiddleware(AdminGuard)
+            .handle(req, depot, res, ctrl)
+            .await;
     }
+}
 
-    #[test]
-    fn extract_bearer_token_missing_header() {
-        let req = Request::new();
-        assert!(extract_bearer_token(&req).is_none());
-    }
+// ── 限流中间件 ─────────────────────────────────────
 
-    #[test]
-    fn extract_bearer_token_wrong_scheme() {
-        let mut req = Request::new();
-        req.headers_mut()
-            .insert("authorization", "Basic dXNlcjpwYXNz".parse().unwrap());
-        assert!(extract_bearer_token(&req).is_none());
-    }
+/// Apply rate-limit middleware to a route (salvo equivalent of axum's `with_rate_limit_layer`).
+///
+/// Server route builders can use this single function regardless of
+/// framework, eliminating duplicated route registration code.
+pub fn with_rate_limit_hoop(
+    route: crate::SalvoRouter,
+    guard: RateLimitGuard,
+) -> crate::SalvoRouter {
+    with_middleware_hoop(route, guard)
+}
 
-    #[test]
-    fn extract_bearer_token_empty_value() {
-        let mut req = Request::new();
-        req.headers_mut()
-            .insert("authorization", "Bearer ".parse().unwrap());
-        assert!(extract_bearer_token(&req).is_none());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use salvo::http::StatusCode;
+    use std::sync::Arc;
+    use webshelf_runtime::HttpError;
+    use webshelf_runtime::{RequestContext, Response as UnifiedResponse};
+
+    /// Injects a marker value and stamps a header on the downstream response.
+    struct Marker;
+
+    #[async_trait]
+    impl Middleware for Marker {
+        async fn handle<R: RequestContext + 'static>(
+            &self,
+            mut req: R,
+            next: Next<'_, R>,
+        ) -> UnifiedResponse {
+            if req.header("x-block").is_some() {
+                return HttpError::forbidden("blocked").into();
+            }
+            if req.header("x-cached").is_some() {
+                let mut resp = UnifiedResponse::new();
+                resp.set_text_body("cached");
+                return resp;
+            }
+            req.set_data(String::from("from-middleware"));
+            let mut resp = next.run(req).await;
+            resp.insert_header("x-marker", "1");
+            resp
+        }
     }
 
-    // ── extract_jwt_cookie tests ──────────────────────────────────
-
-    #[test]
-    fn extract_jwt_cookie_success() {
-        let mut req = Request::new();
-        req.headers_mut().insert(
-            "cookie",
-            "webshelf_jwt=my-jwt-token; other=cookie".parse().unwrap(),
-        );
-        assert_eq!(extract_jwt_cookie(&req), Some("my-jwt-token".to_string()));
-    }
+    /// Declares it does not read the body; reports what it would have seen.
+    struct Peek;
+
+    #[async_trait]
+    impl Middleware for Peek {
+        async fn handle<R: RequestContext + 'static>(
+            &self,
+            mut req: R,
+            next: Next<'_, R>,
+        ) -> UnifiedResponse {
+            let seen = req.read_body_bytes().await.unwrap_or_default().len();
+            let mut resp = next.run(req).await;
+            resp.insert_header("x-seen", seen);
+            resp
+        }
 
-    #[test]
-    fn extract_jwt_cookie_multiple_cookies() {
-        let mut req = Request::new();
-        req.headers_mut().insert(
-            "cookie",
-            "session=abc; webshelf_jwt=found-token; lang=en"
-                .parse()
-                .unwrap(),
-        );
-        assert_eq!(extract_jwt_cookie(&req), Some("found-token".to_string()));
+        fn needs_body(&self) -> bool {
+            false
+        }
     }
 
-    #[test]
-    fn extract_jwt_cookie_missing_header() {
-        let req = Request::new();
-        assert!(extract_jwt_cookie(&req).is_none());
+    async fn echo(mut req: UnifiedRequest) -> Result<UnifiedResponse, HttpError> {
+        let marker: String = req.get_data().unwrap_or_default();
+        let body = req.read_body_bytes().await.map_err(HttpError::internal)?;
+        let mut resp = UnifiedResponse::new();
+        resp.set_text_body(format!("{marker}|{}", String::from_utf8_lossy(&body)));
+        Ok(resp)
     }
 
-    #[test]
-    fn extract_jwt_cookie_no_jwt_cookie() {
+    fn post_request(body: &'static str, extra_header: Option<(&str, &str)>) -> Request {
+        let mut builder = salvo::hyper::Request::builder().method("POST");
+        if let Some((name, value)) = extra_header {
+            builder = builder.header(name, value);
+        }
+        let hyper_req = builder
+            .body(salvo::http::body::ReqBody::Once(Bytes::from(body)))
+            .unwrap();
         let mut req = Request::new();
-        req.headers_mut()
-            .insert("cookie", "session=abc".parse().unwrap());
-        assert!(extract_jwt_cookie(&req).is_none());
+        req.merge_hyper(hyper_req);
+        req
     }
 
-    // ── extract_client_ip tests ─────────────────────────────────
-
-    #[test]
-    fn extract_client_ip_from_x_forwarded_for() {
-        let mut headers = salvo::http::HeaderMap::new();
-        headers.insert("x-forwarded-for", "203.0.113.1".parse().unwrap());
-        assert_eq!(extract_client_ip(&headers), Some("203.0.113.1".to_string()));
-    }
-
-    #[test]
-    fn extract_client_ip_from_x_forwarded_for_chain() {
-        let mut headers = salvo::http::HeaderMap::new();
-        headers.insert(
-            "x-forwarded-for",
-            "198.51.100.1, 10.0.0.1, 192.168.1.1".parse().unwrap(),
-        );
-        // Should take the leftmost (client) IP
-        assert_eq!(
-            extract_client_ip(&headers),
-            Some("198.51.100.1".to_string())
-        );
-    }
-
-    #[test]
-    fn extract_client_ip_from_x_real_ip() {
-        let mut headers = salvo::http::HeaderMap::new();
-        headers.insert("x-real-ip", "192.168.1.42".parse().unwrap());
-        assert_eq!(
-            extract_client_ip(&headers),
-            Some("192.168.1.42".to_string())
-        );
-    }
-
-    #[test]
-    fn extract_client_ip_x_forwarded_for_takes_priority() {
-        let mut headers = salvo::http::HeaderMap::new();
-        headers.insert("x-forwarded-for", "10.0.0.1".parse().unwrap());
-        headers.insert("x-real-ip", "192.168.1.100".parse().unwrap());
-        assert_eq!(extract_client_ip(&headers), Some("10.0.0.1".to_string()));
-    }
-
-    #[test]
-    fn extract_client_ip_missing_headers_returns_none() {
-        let headers = salvo::http::HeaderMap::new();
-        assert!(extract_client_ip(&headers).is_none());
-    }
-
-    #[test]
-    fn extract_client_ip_empty_x_forwarded_for_ignored() {
-        let mut headers = salvo::http::HeaderMap::new();
-        headers.insert("x-forwarded-for", "".parse().unwrap());
-        assert!(extract_client_ip(&headers).is_none());
-    }
-
-    // ── extract_email_from_body tests ───────────────────────────
-
-    #[test]
-    fn extract_email_from_json_body() {
-        let body = b"{\"email\": \"user@example.com\"}";
-        assert_eq!(
-            extract_email_from_body(body),
-            Some("user@example.com".to_string())
-        );
-    }
-
-    #[test]
-    fn extract_email_from_json_body_normalizes_case() {
-        let body = b"{\"email\": \"User@Example.COM\"}";
-        assert_eq!(
-            extract_email_from_body(body),
-            Some("user@example.com".to_string())
-        );
-    }
-
-    #[test]
-    fn extract_email_from_form_body() {
-        let body = b"email=user@example.com&password=secret";
-        assert_eq!(
-            extract_email_from_body(body),
-            Some("user@example.com".to_string())
-        );
-    }
-
-    #[test]
-    fn extract_email_from_form_body_normalizes_case() {
-        let body = b"email=User@Example.COM&password=secret";
-        assert_eq!(
-            extract_email_from_body(body),
-            Some("user@example.com".to_string())
-        );
-    }
-
-    #[test]
-    fn extract_email_no_email_field_returns_none() {
-        let body = b"{\"name\": \"test\"}";
-        assert!(extract_email_from_body(body).is_none());
-    }
-
-    #[test]
-    fn extract_email_from_empty_body_returns_none() {
-        let body = b"";
-        assert!(extract_email_from_body(body).is_none());
+    fn body_string(res: &Response) -> String {
+        match &res.body {
+            salvo::http::body::ResBody::Once(bytes) => String::from_utf8(bytes.to_vec()).unwrap(),
+            salvo::http::body::ResBody::None => String::new(),
+            _ => panic!("unexpected ResBody variant"),
+        }
     }
 
-    #[test]
-    fn extract_email_from_invalid_json_tries_form_fallback() {
-        // Not valid JSON but valid form-encoded
-        let body = b"email=fallback@example.com";
-        assert_eq!(
-            extract_email_from_body(body),
-            Some("fallback@example.com".to_string())
-        );
+    async fn run_chain(mut req: Request, first: Arc<dyn Handler>) -> (Depot, Response) {
+        let mut depot = Depot::new();
+        let mut res = Response::new();
+        let handlers: Vec<Arc<dyn Handler>> = vec![first, Arc::new(crate::UnifiedHandler(echo))];
+        let mut ctrl = FlowCtrl::new(handlers);
+        ctrl.call_next(&mut req, &mut depot, &mut res).await;
+        (depot, res)
+    }
+
+    #[tokio::test]
+    async fn bridge_passes_data_and_body_downstream() {
+        let (depot, res) = run_chain(
+            post_request("payload", None),
+            Arc::new(UnifiedMiddleware(Marker)),
+        )
+        .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        assert_eq!(res.headers().get("x-marker").unwrap(), "1");
+        assert_eq!(body_string(&res), "from-middleware|payload");
+        assert!(depot.obtain::<CachedBody>().is_ok());
+    }
+
+    #[tokio::test]
+    async fn bridge_short_circuits_without_calling_handler() {
+        let (_, res) = run_chain(
+            post_request("payload", Some(("x-block", "1"))),
+            Arc::new(UnifiedMiddleware(Marker)),
+        )
+        .await;
+        assert_eq!(res.status_code, Some(StatusCode::FORBIDDEN));
+        assert!(res.headers().get("x-marker").is_none());
+        assert!(body_string(&res).contains("blocked"));
+    }
+
+    #[tokio::test]
+    async fn bridge_short_circuits_with_success_status() {
+        let (_, res) = run_chain(
+            post_request("payload", Some(("x-cached", "1"))),
+            Arc::new(UnifiedMiddleware(Marker)),
+        )
+        .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        assert!(res.headers().get("x-marker").is_none());
+        assert_eq!(body_string(&res), "cached");
+    }
+
+    #[tokio::test]
+    async fn bridge_streams_body_past_middleware_that_does_not_need_it() {
+        let (depot, res) = run_chain(
+            post_request("payload", None),
+            Arc::new(UnifiedMiddleware(Peek)),
+        )
+        .await;
+        assert_eq!(res.headers().get("x-seen").unwrap(), "0");
+        assert_eq!(body_string(&res), "|payload");
+        assert!(depot.obtain::<CachedBody>().is_err());
+    }
+
+    #[tokio::test]
+    async fn require_admin_without_auth_user_is_unauthorized() {
+        let (_, res) = run_chain(post_request("", None), Arc::new(RequireAdmin)).await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
     }
 }
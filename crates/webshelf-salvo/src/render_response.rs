@@ -1,7 +1,10 @@
+use http_body_util::BodyExt;
 use salvo::Response as SalvoResponse;
+use salvo::http::Body as _;
 use salvo::http::HeaderName;
 use salvo::http::StatusCode;
-use webshelf_runtime::{Response, ResponseBody};
+use salvo::http::body::ResBody;
+use webshelf_runtime::{HttpError, Response, ResponseBody};
 
 pub fn render_response(mut resp: Response, res: &mut SalvoResponse) {
     res.status_code(resp.status());
@@ -20,7 +23,10 @@ pub fn render_response(mut resp: Response, res: &mut SalvoResponse) {
     let ct_value = salvo::http::HeaderValue::from_str(content_type).unwrap_or(
         salvo::http::HeaderValue::from_static("text/plain; charset=utf-8"),
     );
-    res.headers_mut().insert(ct_name, ct_value);
+    // 101 Switching Protocols（WebSocket 升级）没有 body，不设置 Content-Type
+    if resp.status() != StatusCode::SWITCHING_PROTOCOLS {
+        res.headers_mut().insert(ct_name, ct_value);
+    }
 
     // 2. Set all other headers (cookie 已存储在 headers 中)。
     //    过滤 Content-Type 以免与上面的显式设置冲突。
@@ -37,20 +43,65 @@ pub fn render_response(mut resp: Response, res: &mut SalvoResponse) {
 
     // 3. Write body — use write_body for raw bytes (no UTF-8 corruption).
     //    write_body handles all ResBody variants (None → Once(bytes)).
-    match resp.read_bytes() {
-        Ok(bytes) => {
-            if !bytes.is_empty()
-                && let Err(e) = res.write_body(bytes)
-            {
-                tracing::error!("Failed to write response body: {}", e);
+    //    流式 body（大文件导出 / SSE）直接交给 res.stream，不做缓冲。
+    match resp.take_body() {
+        ResponseBody::Stream(stream) => res.stream(stream),
+        body => match body.to_bytes() {
+            Ok(bytes) => {
+                if !bytes.is_empty()
+                    && let Err(e) = res.write_body(bytes)
+                {
+                    tracing::error!("Failed to write response body: {}", e);
+                    res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to serialize response body: {}", e);
                 res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
             }
+        },
+    }
+}
+
+/// 将 salvo Response 的状态码、headers、body 取出，转换为统一 Response
+/// （`render_response` 的逆操作，用于中间件桥接读取下游响应）。
+///
+/// 长度已知的 body 被完整读取；流式 body（`ResBody::Stream` 等）转为
+/// `ResponseBody::Stream` 透传，不做缓冲。
+/// 取出后 `res` 的 status/headers/body 被清空，可再次 `render_response`。
+/// Cookie jar 不做转换，保留在 `res` 上。
+/// `ResBody::Error` 仅保留其状态码（body 为空，交由 Catcher 渲染）。
+pub async fn take_response(res: &mut SalvoResponse) -> Response {
+    let headers = std::mem::take(res.headers_mut());
+    let mut unified = Response::with_status(res.status_code.take().unwrap_or(StatusCode::OK));
+    for (name, value) in &headers {
+        let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+        if name == salvo::http::header::CONTENT_TYPE {
+            unified.set_content_type(value);
+        } else {
+            unified.append_header(name.clone(), value);
         }
-        Err(e) => {
-            tracing::error!("Failed to serialize response body: {}", e);
-            res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    match res.take_body() {
+        ResBody::Error(e) => unified.set_status(e.code),
+        body if body.size_hint().exact().is_none() => {
+            unified.set_stream_body(body.into_data_stream());
         }
+        body => match body.collect().await {
+            Ok(collected) => {
+                let bytes = collected.to_bytes();
+                if !bytes.is_empty() {
+                    unified.set_bytes_body(bytes);
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to read downstream response body: {}", e);
+                return HttpError::internal("An unexpected error occurred").into();
+            }
+        },
     }
+    unified
 }
 
 #[cfg(test)]
@@ -217,4 +268,49 @@ mod tests {
             _ => panic!("Expected ResBody::Once"),
         }
     }
+
+    #[tokio::test]
+    async fn take_response_round_trip_preserves_parts() {
+        let mut unified = Response::with_status(StatusCode::CREATED);
+        unified.set_content_type("application/x-custom");
+        unified.append_header(HeaderName::from_static("set-cookie"), "a=1");
+        unified.append_header(HeaderName::from_static("set-cookie"), "b=2");
+        unified.set_bytes_body(Bytes::from_static(b"payload"));
+
+        let mut salvo_res = SalvoResponse::new();
+        render_response(unified, &mut salvo_res);
+        let taken = take_response(&mut salvo_res).await;
+
+        assert_eq!(taken.status(), StatusCode::CREATED);
+        assert_eq!(taken.content_type(), Some("application/x-custom"));
+        assert_eq!(taken.header("set-cookie"), Some("a=1"));
+        assert_eq!(taken.read_bytes().unwrap(), Bytes::from_static(b"payload"));
+        assert!(salvo_res.headers().is_empty());
+        assert!(salvo_res.body.is_none());
+    }
+
+    #[tokio::test]
+    async fn render_stream_response_is_not_buffered() {
+        use futures_util::TryStreamExt;
+        use webshelf_runtime::{Event, Sse};
+
+        let events = futures_util::stream::iter(vec![Event::default().data("1")]);
+        let mut salvo_res = SalvoResponse::new();
+        render_response(Sse::new(events).into_response(), &mut salvo_res);
+
+        assert!(matches!(salvo_res.body, ResBody::Stream(_)));
+        let ct = salvo_res
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok());
+        assert_eq!(ct, Some("text/event-stream"));
+
+        // take_response keeps the body streaming and yields the same bytes.
+        let mut taken = take_response(&mut salvo_res).await;
+        let ResponseBody::Stream(stream) = taken.take_body() else {
+            panic!("expected stream body");
+        };
+        let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+        assert_eq!(chunks.concat(), b"data: 1\n\n");
+    }
 }
@@ -1,8 +1,15 @@
 use bytes::Bytes;
+use futures_util::TryStreamExt;
+use http_body_util::{BodyExt, LengthLimitError, Limited};
 use salvo::Depot;
 use salvo::http::Request as SalvoRequest;
 use serde::de::DeserializeOwned;
-use webshelf_runtime::RequestContext;
+use std::future::Future;
+use webshelf_runtime::{
+    BodyError, HttpError, RequestBodyStream, RequestContext, Response, TrustedProxies, WebSocket,
+};
+
+use crate::take_response;
 
 /// 统一请求上下文（Salvo 端）
 ///
@@ -16,9 +23,11 @@ use webshelf_runtime::RequestContext;
 ///    且在同一调用栈帧内同步使用，不会逃逸到其他任务或被 `tokio::spawn`。
 /// 2. `handle(&self, req: &mut Request, depot: &mut Depot, ...)` 的参数 `req`/`depot`
 ///    的生命周期覆盖整个 `handle()` 调用（包括 `.await` 点）。
-/// 3. Body 已被 Eager Buffered 为 `cached_body: Bytes`，`UnifiedRequest` 的所有
-///    方法仅通过裸指针读取（不写入）`req`/`depot`。
-/// 4. `cached_body` 是 owned `Bytes`，满足 `Send` + `Sync`。
+/// 3. Body 通常已被 Eager Buffered 为 `cached_body`（multipart 上传除外，见
+///    [`unbuffered`](Self::unbuffered)），`UnifiedRequest` 的方法仅通过裸指针读取 `req`；
+///    写入仅限 `&mut self` 方法（`set_data` 写 `depot`，`upgrade_websocket` 移除 `req`
+///    的 OnUpgrade extension，未缓冲时的 body 读取 / `take_body_stream` 取走 `req` 的 body）。
+/// 4. `cached_body` 是 owned `Option<Bytes>`，满足 `Send` + `Sync`。
 ///
 /// # 维护警告：新增 RequestContext 方法时的同步义务
 ///
@@ -37,7 +46,8 @@ use webshelf_runtime::RequestContext;
 pub struct UnifiedRequest {
     req: std::ptr::NonNull<SalvoRequest>,
     depot: std::ptr::NonNull<Depot>,
-    cached_body: Bytes,
+    /// `None`：body 仍在 `req` 中未读取
+    cached_body: Option<Bytes>,
 }
 
 // SAFETY: 见 struct 上的安全性论证。
@@ -55,8 +65,31 @@ impl UnifiedRequest {
         Self {
             req: std::ptr::NonNull::from(req),
             depot: std::ptr::NonNull::from(depot),
-            cached_body,
+            cached_body: Some(cached_body),
+        }
+    }
+
+    /// 创建 body 尚未读取的 UnifiedRequest（multipart 上传），body 留给
+    /// `take_body_stream` 流式解析，或在首次读取时再缓冲。
+    ///
+    /// # Safety
+    /// 同 [`new`](Self::new)。
+    pub unsafe fn unbuffered(req: &mut SalvoRequest, depot: &mut Depot) -> Self {
+        Self {
+            req: std::ptr::NonNull::from(req),
+            depot: std::ptr::NonNull::from(depot),
+            cached_body: None,
+        }
+    }
+
+    async fn buffered(&mut self) -> Result<&Bytes, String> {
+        if self.cached_body.is_none() {
+            // SAFETY: 见不变式 3
+            let req = unsafe { &mut *self.req.as_ptr() };
+            let bytes = req.payload().await.map_err(|e| e.to_string())?.clone();
+            self.cached_body = Some(bytes);
         }
+        Ok(self.cached_body.get_or_insert_default())
     }
 }
 
@@ -70,30 +103,16 @@ impl RequestContext for UnifiedRequest {
     }
 
     fn client_ip(&self) -> Option<std::net::IpAddr> {
-        // Check X-Forwarded-For header first (proxy / load balancer support)
-        if let Some(ip) = self
-            .header("x-forwarded-for")
-            .and_then(|v| v.split(',').next().map(|s| s.trim()))
-            .and_then(|s| s.parse::<std::net::IpAddr>().ok())
-        {
-            return Some(ip);
-        }
-
-        // Then X-Real-IP
-        if let Some(ip) = self
-            .header("x-real-ip")
-            .and_then(|v| v.trim().parse::<std::net::IpAddr>().ok())
-        {
-            return Some(ip);
-        }
-
-        // Fall back to peer IP
-        unsafe {
-            match (*self.req.as_ptr()).remote_addr() {
-                salvo::conn::SocketAddr::IPv4(addr) => Some(std::net::IpAddr::V4(*addr.ip())),
-                salvo::conn::SocketAddr::IPv6(addr) => Some(std::net::IpAddr::V6(*addr.ip())),
-                _ => None,
-            }
+        // 对端地址；仅当对端属于可信代理（Depot 中的 `TrustedProxies`）时才解析转发头
+        let req = unsafe { &*self.req.as_ptr() };
+        let peer = match req.remote_addr() {
+            salvo::conn::SocketAddr::IPv4(addr) => Some(std::net::IpAddr::V4(*addr.ip())),
+            salvo::conn::SocketAddr::IPv6(addr) => Some(std::net::IpAddr::V6(*addr.ip())),
+            _ => None,
+        };
+        match self.get_data_ref::<TrustedProxies>() {
+            Some(trusted) => trusted.client_ip(peer, req.headers()),
+            None => peer,
         }
     }
 
@@ -154,14 +173,281 @@ impl RequestContext for UnifiedRequest {
     }
 
     async fn parse_json<T: DeserializeOwned>(&mut self) -> Result<T, String> {
-        serde_json::from_slice(&self.cached_body).map_err(|e| e.to_string())
+        serde_json::from_slice(self.buffered().await?).map_err(|e| e.to_string())
     }
 
     async fn read_body_bytes(&mut self) -> Result<Bytes, String> {
-        Ok(self.cached_body.clone())
+        self.buffered().await.cloned()
     }
 
     async fn parse_form<T: DeserializeOwned>(&mut self) -> Result<T, String> {
-        serde_urlencoded::from_bytes(&self.cached_body).map_err(|e| e.to_string())
+        serde_urlencoded::from_bytes(self.buffered().await?).map_err(|e| e.to_string())
+    }
+
+    fn take_body_stream(&mut self) -> Option<RequestBodyStream> {
+        if self.cached_body.is_some() {
+            return None;
+        }
+        self.cached_body = Some(Bytes::new());
+        // SAFETY: 见不变式 3
+        let req = unsafe { &mut *self.req.as_ptr() };
+        // 与 req.payload() 相同的 secure_max_size 限制（由 `middleware::max_body_size` 设置）
+        let body = Limited::new(req.take_body(), req.secure_max_size());
+        let stream = TryStreamExt::map_err(body.into_data_stream(), |e| {
+            if e.is::<LengthLimitError>() {
+                BodyError::TooLarge
+            } else {
+                BodyError::Read(e.to_string())
+            }
+        });
+        Some(Box::pin(stream))
+    }
+
+    async fn upgrade_websocket<F, Fut>(&mut self, on_upgrade: F) -> Result<Response, HttpError>
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        webshelf_runtime::ws::check_origin(self)?;
+        // salvo 的升级 API 写入原生 Response；使用临时 Response 再转换为统一 Response。
+        let mut res = salvo::Response::new();
+        // SAFETY: 唯一写入 req 的方法（移除 OnUpgrade extension），见 struct 上的不变式 3。
+        let req = unsafe { &mut *self.req.as_ptr() };
+        salvo::websocket::WebSocketUpgrade::new()
+            .upgrade(req, &mut res, move |socket| {
+                on_upgrade(crate::ws::into_unified(socket))
+            })
+            .await
+            .map_err(|e| {
+                let mut err = HttpError::bad_request(e.brief);
+                err.status = e.code;
+                err
+            })?;
+        Ok(take_response(&mut res).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use salvo::{FlowCtrl, Handler};
+    use std::sync::Arc;
+
+    fn client_ip_with(
+        peer: &str,
+        trusted: Option<&[&str]>,
+        headers: &[(&'static str, &str)],
+    ) -> Option<std::net::IpAddr> {
+        let mut req = SalvoRequest::new();
+        *req.remote_addr_mut() = peer.parse::<std::net::SocketAddr>().unwrap().into();
+        for (name, value) in headers {
+            req.headers_mut().append(*name, value.parse().unwrap());
+        }
+        let mut depot = Depot::new();
+        if let Some(trusted) = trusted {
+            depot.inject(TrustedProxies::parse(trusted).unwrap());
+        }
+        // SAFETY: req/depot 在 UnifiedRequest 使用期间有效
+        let unified = unsafe { UnifiedRequest::new(&mut req, &mut depot, Bytes::new()) };
+        unified.client_ip()
+    }
+
+    #[test]
+    fn client_ip_ignores_proxy_headers_without_trusted_proxies() {
+        assert_eq!(
+            client_ip_with(
+                "192.0.2.7:4000",
+                None,
+                &[
+                    ("x-forwarded-for", "198.51.100.1"),
+                    ("x-real-ip", "10.0.0.1")
+                ]
+            ),
+            Some("192.0.2.7".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn client_ip_ignores_proxy_headers_from_untrusted_peer() {
+        assert_eq!(
+            client_ip_with(
+                "192.0.2.7:4000",
+                Some(&["10.0.0.0/8"]),
+                &[("x-forwarded-for", "198.51.100.1")]
+            ),
+            Some("192.0.2.7".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn client_ip_from_x_forwarded_for_chain() {
+        // Rightmost untrusted hop — the spoofed leftmost entry is ignored
+        assert_eq!(
+            client_ip_with(
+                "10.0.0.3:4000",
+                Some(&["10.0.0.0/8"]),
+                &[("x-forwarded-for", "6.6.6.6, 198.51.100.1, 10.0.0.1")]
+            ),
+            Some("198.51.100.1".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn client_ip_from_x_real_ip() {
+        assert_eq!(
+            client_ip_with(
+                "127.0.0.1:4000",
+                Some(&["127.0.0.1"]),
+                &[("x-real-ip", "192.168.1.42")]
+            ),
+            Some("192.168.1.42".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn client_ip_empty_x_forwarded_for_ignored() {
+        assert_eq!(
+            client_ip_with(
+                "127.0.0.1:4000",
+                Some(&["127.0.0.1"]),
+                &[("x-forwarded-for", ""), ("x-real-ip", "192.168.1.100")]
+            ),
+            Some("192.168.1.100".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn client_ip_from_forwarded_header() {
+        assert_eq!(
+            client_ip_with(
+                "127.0.0.1:4000",
+                Some(&["127.0.0.1"]),
+                &[("forwarded", "for=198.51.100.9;proto=https")]
+            ),
+            Some("198.51.100.9".parse().unwrap())
+        );
+    }
+
+    // ── multipart / body limit ──────────────────────────────────
+
+    /// Lists each field as `name:len`.
+    async fn upload(
+        mut req: UnifiedRequest,
+    ) -> Result<webshelf_runtime::Response, webshelf_runtime::HttpError> {
+        let limits = webshelf_runtime::MultipartLimits::default().field("small", 8);
+        let mut form = req.parse_multipart(limits).await?;
+        let mut out = Vec::new();
+        while let Some(field) = form.next_field().await? {
+            let name = field.name().unwrap_or_default().to_owned();
+            out.push(format!("{name}:{}", field.bytes().await?.len()));
+        }
+        let mut resp = webshelf_runtime::Response::new();
+        resp.set_text_body(out.join(","));
+        Ok(resp)
+    }
+
+    fn multipart_body(name: &str, size: usize) -> Bytes {
+        let mut body = format!(
+            "--XB\r\nContent-Disposition: form-data; name=\"{name}\"; filename=\"f.bin\"\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend(std::iter::repeat_n(b'x', size));
+        body.extend_from_slice(b"\r\n--XB--\r\n");
+        Bytes::from(body)
+    }
+
+    async fn post_upload(body: Bytes, max_body: u64) -> salvo::Response {
+        let hyper_req = salvo::hyper::Request::builder()
+            .method("POST")
+            .header("content-type", "multipart/form-data; boundary=XB")
+            .body(salvo::http::body::ReqBody::Once(body))
+            .unwrap();
+        let mut req = SalvoRequest::new();
+        req.merge_hyper(hyper_req);
+        let mut depot = Depot::new();
+        let mut res = salvo::Response::new();
+        let handlers: Vec<Arc<dyn Handler>> = vec![
+            Arc::new(crate::middleware::max_body_size(max_body)),
+            Arc::new(crate::UnifiedHandler(upload)),
+        ];
+        FlowCtrl::new(handlers)
+            .call_next(&mut req, &mut depot, &mut res)
+            .await;
+        res
+    }
+
+    #[tokio::test]
+    async fn multipart_upload_larger_than_salvo_default_limit() {
+        // salvo caps req.payload() at 64 KiB unless max_body_size raises it.
+        let res = post_upload(multipart_body("file", 100 * 1024), 10 * 1024 * 1024).await;
+        assert_eq!(res.status_code, Some(salvo::http::StatusCode::OK));
+        match &res.body {
+            salvo::http::body::ResBody::Once(bytes) => assert_eq!(&bytes[..], b"file:102400"),
+            _ => panic!("unexpected ResBody variant"),
+        }
+    }
+
+    #[tokio::test]
+    async fn multipart_parts_stream_before_the_body_ends() {
+        use futures_util::StreamExt;
+
+        /// Returns the first field without waiting for the rest of the upload.
+        async fn first_field(
+            mut req: UnifiedRequest,
+        ) -> Result<webshelf_runtime::Response, webshelf_runtime::HttpError> {
+            let mut form = req.parse_multipart(Default::default()).await?;
+            let field = form.next_field().await?.unwrap_or_else(|| unreachable!());
+            let mut resp = webshelf_runtime::Response::new();
+            resp.set_text_body(field.text().await?);
+            Ok(resp)
+        }
+
+        // The client never finishes sending: an eager payload() read would hang here.
+        let head = "--XB\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\nhi\r\n--XB\r\n";
+        let frames = futures_util::stream::iter(vec![Ok::<_, salvo::BoxedError>(
+            salvo::hyper::body::Frame::data(Bytes::from(head)),
+        )])
+        .chain(futures_util::stream::pending());
+        let body = salvo::http::body::ReqBody::Boxed {
+            inner: Box::pin(http_body_util::StreamBody::new(frames)),
+            fusewire: None,
+        };
+        let hyper_req = salvo::hyper::Request::builder()
+            .method("POST")
+            .header("content-type", "multipart/form-data; boundary=XB")
+            .body(body)
+            .unwrap();
+        let mut req = SalvoRequest::new();
+        req.merge_hyper(hyper_req);
+        let mut depot = Depot::new();
+        let mut res = salvo::Response::new();
+        let mut ctrl = FlowCtrl::new(Vec::<Arc<dyn Handler>>::new());
+        let handled =
+            crate::UnifiedHandler(first_field).handle(&mut req, &mut depot, &mut res, &mut ctrl);
+        tokio::time::timeout(std::time::Duration::from_secs(5), handled)
+            .await
+            .expect("multipart body was buffered instead of streamed");
+        match &res.body {
+            salvo::http::body::ResBody::Once(bytes) => assert_eq!(&bytes[..], b"hi"),
+            _ => panic!("unexpected ResBody variant"),
+        }
+    }
+
+    #[tokio::test]
+    async fn multipart_field_limit_is_413() {
+        let res = post_upload(multipart_body("small", 64), 1024).await;
+        assert_eq!(
+            res.status_code,
+            Some(salvo::http::StatusCode::PAYLOAD_TOO_LARGE)
+        );
+    }
+
+    #[tokio::test]
+    async fn body_over_max_body_size_is_413() {
+        let res = post_upload(multipart_body("file", 2048), 1024).await;
+        assert_eq!(
+            res.status_code,
+            Some(salvo::http::StatusCode::PAYLOAD_TOO_LARGE)
+        );
     }
 }
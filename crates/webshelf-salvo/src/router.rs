@@ -81,6 +81,51 @@ impl SalvoRouter {
     pub fn route_layer<H: salvo::Handler>(self, handler: H) -> Self {
         self.hoop(handler)
     }
+
+    /// Registered `(METHOD, path)` pairs with nested prefixes joined, e.g.
+    /// `("GET", "/api/users/{id}")`; handlers without a method filter are listed as `*`.
+    ///
+    /// Lets tests check the route table against documentation such as the OpenAPI spec.
+    pub fn endpoints(&self) -> Vec<(String, String)> {
+        let guard = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = Vec::new();
+        collect_endpoints(&guard, "", None, &mut out);
+        out
+    }
+}
+
+/// Walk the salvo router tree; filters only expose themselves through `Debug`
+/// (`path:/users/{id}`, `method:GET`).
+fn collect_endpoints(
+    router: &salvo::Router,
+    prefix: &str,
+    method: Option<&str>,
+    out: &mut Vec<(String, String)>,
+) {
+    let mut path = prefix.to_string();
+    let mut method = method.map(str::to_owned);
+    for filter in router.filters() {
+        let info = format!("{filter:?}");
+        if let Some(segment) = info.strip_prefix("path:") {
+            let segment = segment.trim_matches('/');
+            if !segment.is_empty() {
+                path = format!("{path}/{segment}");
+            }
+        } else if let Some(m) = info.strip_prefix("method:") {
+            method = Some(m.to_string());
+        }
+    }
+    if router.goal.is_some() {
+        let full = if path.is_empty() {
+            "/".to_string()
+        } else {
+            path.clone()
+        };
+        out.push((method.clone().unwrap_or_else(|| "*".to_string()), full));
+    }
+    for child in router.routers() {
+        collect_endpoints(child, &path, method.as_deref(), out);
+    }
 }
 
 impl Default for SalvoRouter {
@@ -94,3 +139,34 @@ impl std::fmt::Debug for SalvoRouter {
         f.debug_tuple("SalvoRouter").finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route::{get, post};
+    use webshelf_runtime::{HttpError, Response};
+
+    async fn ok(_req: crate::UnifiedRequest) -> Result<Response, HttpError> {
+        Ok(Response::new())
+    }
+
+    #[test]
+    fn endpoints_join_nested_paths() {
+        let api = SalvoRouter::new()
+            .route("/users/{id}", get(ok))
+            .route("/users", post(ok));
+        let router = SalvoRouter::new()
+            .nest("/api", api)
+            .merge(SalvoRouter::new().route("/livez", get(ok)));
+        let mut endpoints = router.endpoints();
+        endpoints.sort();
+        assert_eq!(
+            endpoints,
+            [
+                ("GET".to_string(), "/api/users/{id}".to_string()),
+                ("GET".to_string(), "/livez".to_string()),
+                ("POST".to_string(), "/api/users".to_string()),
+            ]
+        );
+    }
+}
@@ -1,7 +1,11 @@
+use futures_util::StreamExt;
+use futures_util::stream::BoxStream;
 use salvo::Listener;
-use salvo::conn::TcpListener;
-use std::time::Duration;
-use webshelf_runtime::Runtime;
+use salvo::conn::rustls::ServerConfig;
+use salvo::conn::{Acceptor, IntoConfigStream, TcpListener};
+use std::sync::Arc;
+use tokio::sync::watch;
+use webshelf_runtime::{Runtime, Shutdown, TlsConfig, TlsReloader};
 
 use crate::SalvoRouter;
 
@@ -53,8 +57,11 @@ impl<S: Clone + Send + Sync + 'static> Runtime for SalvoRuntime<S> {
         router: Self::Router,
         state: Self::State,
         addr: &str,
+        tls: Option<&TlsConfig>,
+        shutdown: Shutdown,
     ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send {
         let addr = addr.to_string();
+        let tls = tls.cloned();
         async move {
             let inner_router = router.into_inner();
 
@@ -64,25 +71,59 @@ impl<S: Clone + Send + Sync + 'static> Runtime for SalvoRuntime<S> {
                 .hoop(salvo::affix_state::inject(state))
                 .push(inner_router);
 
-            let acceptor = TcpListener::new(addr.clone()).bind().await;
+            match tls {
+                Some(tls) => {
+                    let reloader = TlsReloader::start(&tls, &[b"h2", b"http/1.1"])?;
+                    let acceptor = TcpListener::new(addr.clone())
+                        .rustls(ReloadStream(reloader.subscribe()))
+                        .bind()
+                        .await;
+                    tracing::info!("Server is ready to accept connections on https://{}", addr);
+                    run(acceptor, router, shutdown).await;
+                }
+                None => {
+                    let acceptor = TcpListener::new(addr.clone()).bind().await;
+                    tracing::info!("Server is ready to accept connections on {}", addr);
+                    run(acceptor, router, shutdown).await;
+                }
+            }
 
-            tracing::info!("Server is ready to accept connections on {}", addr);
+            tracing::info!("Server stopped");
+            Ok(())
+        }
+    }
+}
 
-            let server = salvo::Server::new(acceptor);
-            let signal = server.handle();
+async fn run<A: Acceptor + Send>(acceptor: A, router: salvo::Router, shutdown: Shutdown) {
+    let server = salvo::Server::new(acceptor);
+    let handle = server.handle();
 
-            // Spawn shutdown watcher — when signal arrives, stop the server gracefully.
-            tokio::spawn(async move {
-                webshelf_runtime::shutdown_signal().await;
-                tracing::info!("Received shutdown signal, stopping server gracefully");
-                signal.stop_graceful(Duration::from_secs(10));
-            });
+    // Stopping 阶段关闭监听；drain_timeout 后 salvo 强制关闭剩余连接。
+    shutdown.listen_for_signals();
+    tokio::spawn(async move {
+        shutdown.stopping().await;
+        handle.stop_graceful(shutdown.drain_timeout());
+    });
 
-            server.serve(router).await;
+    server.serve(router).await;
+}
 
-            tracing::info!("Server stopped");
-            Ok(())
-        }
+/// 将 [`TlsReloader`] 的更新转为 `RustlsListener` 的配置流：
+/// 先产出当前配置，之后每次证书重载产出一项，新握手即使用新证书。
+struct ReloadStream(watch::Receiver<Arc<ServerConfig>>);
+
+impl IntoConfigStream<ServerConfig> for ReloadStream {
+    type Stream = BoxStream<'static, ServerConfig>;
+
+    fn into_stream(self) -> Self::Stream {
+        futures_util::stream::unfold((self.0, true), |(mut rx, first)| async move {
+            if !first {
+                rx.changed().await.ok()?;
+            }
+            let config = ServerConfig::clone(&rx.borrow_and_update());
+            Some((config, (rx, false)))
+        })
+        .boxed()
     }
 }
 
@@ -92,3 +133,176 @@ impl<S: Clone + Send + Sync + 'static> Default for SalvoRuntime<S> {
         Self(std::marker::PhantomData)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+    use tokio_rustls::rustls::{self, RootCertStore};
+    use webshelf_runtime::{HttpError, Response};
+
+    #[derive(Clone)]
+    struct TestState;
+
+    async fn hello(_req: crate::UnifiedRequest) -> Result<Response, HttpError> {
+        let mut resp = Response::new();
+        resp.set_text_body("hello");
+        Ok(resp)
+    }
+
+    /// Write a fresh self-signed `localhost` certificate, returning its PEM.
+    fn write_cert(dir: &Path) -> String {
+        let issued = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        std::fs::write(dir.join("cert.pem"), issued.cert.pem()).unwrap();
+        std::fs::write(dir.join("key.pem"), issued.signing_key.serialize_pem()).unwrap();
+        issued.cert.pem()
+    }
+
+    /// HTTPS GET trusting only `trusted_pem`; `None` if the handshake fails.
+    async fn https_get(addr: &str, trusted_pem: &str) -> Option<String> {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from_pem_slice(trusted_pem.as_bytes()).unwrap())
+            .unwrap();
+        let mut config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        let tcp = TcpStream::connect(addr).await.ok()?;
+        let mut stream = tokio_rustls::TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), tcp)
+            .await
+            .ok()?;
+        stream
+            .write_all(b"GET /hello HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .ok()?;
+        let mut buf = String::new();
+        let _ = stream.read_to_string(&mut buf).await;
+        Some(buf)
+    }
+
+    #[tokio::test]
+    async fn serves_https_and_picks_up_rotated_certificate() {
+        let dir = std::env::temp_dir().join(format!("webshelf-salvo-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let first_pem = write_cert(&dir);
+        let config = TlsConfig {
+            enabled: true,
+            cert_path: dir.join("cert.pem").display().to_string(),
+            key_path: dir.join("key.pem").display().to_string(),
+            reload_interval_secs: 1,
+            ..Default::default()
+        };
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+
+        type R = SalvoRuntime<TestState>;
+        let router = R::with_route(R::new_router(), "/hello", crate::get(hello));
+        let (serve_addr, serve_config) = (addr.clone(), config.clone());
+        tokio::spawn(async move {
+            R::serve(
+                router,
+                TestState,
+                &serve_addr,
+                Some(&serve_config),
+                Default::default(),
+            )
+            .await
+        });
+
+        let mut body = None;
+        for _ in 0..50 {
+            body = https_get(&addr, &first_pem).await;
+            if body.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let body = body.expect("HTTPS server started");
+        assert!(body.starts_with("HTTP/1.1 200"), "{body}");
+        assert!(body.ends_with("hello"));
+
+        // mtime 粒度可能为 1s
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let second_pem = write_cert(&dir);
+        let mut rotated = None;
+        for _ in 0..50 {
+            rotated = https_get(&addr, &second_pem).await;
+            if rotated.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(rotated.expect("new certificate served").ends_with("hello"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    async fn slow(_req: crate::UnifiedRequest) -> Result<Response, HttpError> {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let mut resp = Response::new();
+        resp.set_text_body("done");
+        Ok(resp)
+    }
+
+    #[tokio::test]
+    async fn in_flight_requests_complete_after_shutdown() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let shutdown = Shutdown::new(webshelf_runtime::ShutdownConfig {
+            pre_stop_delay_secs: 0,
+            drain_timeout_secs: 5,
+            ..Default::default()
+        });
+
+        type R = SalvoRuntime<TestState>;
+        let router = R::with_route(R::new_router(), "/slow", crate::get(slow));
+        let server = tokio::spawn({
+            let (addr, shutdown) = (addr.clone(), shutdown.clone());
+            async move { R::serve(router, TestState, &addr, None, shutdown).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let in_flight = tokio::spawn({
+            let addr = addr.clone();
+            async move {
+                let mut stream = TcpStream::connect(&addr).await.unwrap();
+                stream
+                    .write_all(b"GET /slow HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n")
+                    .await
+                    .unwrap();
+                let mut buf = String::new();
+                let _ = stream.read_to_string(&mut buf).await;
+                buf
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.trigger();
+
+        let response = in_flight.await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("done"));
+        tokio::time::timeout(Duration::from_secs(2), server)
+            .await
+            .expect("server stopped after draining")
+            .unwrap()
+            .unwrap();
+    }
+}
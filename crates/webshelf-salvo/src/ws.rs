@@ -0,0 +1,179 @@
+//! salvo WebSocket ↔ `webshelf_runtime::WebSocket` 转换。
+
+use bytes::Bytes;
+use futures_util::{SinkExt, StreamExt};
+use salvo::websocket::{self as ws, WebSocket as SalvoWebSocket};
+use webshelf_runtime::{CloseFrame, Message, WebSocket};
+
+/// 将 salvo 原生 WebSocket 包装为统一 WebSocket。
+pub(crate) fn into_unified(socket: SalvoWebSocket) -> WebSocket {
+    let (sink, stream) = socket.split();
+    let sink = sink.with(|msg: Message| async move { Ok::<_, salvo::Error>(to_salvo(msg)) });
+    let stream = stream.map(|item| item.map(from_salvo));
+    WebSocket::new(sink, stream)
+}
+
+fn to_salvo(msg: Message) -> ws::Message {
+    match msg {
+        Message::Text(text) => ws::Message::text(text),
+        Message::Binary(bytes) => ws::Message::binary(bytes),
+        Message::Ping(bytes) => ws::Message::ping(bytes),
+        Message::Pong(bytes) => ws::Message::pong(bytes),
+        Message::Close(None) => ws::Message::close(),
+        Message::Close(Some(frame)) => ws::Message::close_with(frame.code, frame.reason),
+    }
+}
+
+/// salvo 的 Message 是不透明结构体，只能通过 `is_*` 判断类型。
+/// 原始帧（tungstenite `Frame`，读取时不会出现）按 Binary 处理。
+fn from_salvo(msg: ws::Message) -> Message {
+    let payload = || Bytes::copy_from_slice(msg.as_bytes());
+    if let Ok(text) = msg.as_str() {
+        Message::Text(text.to_owned())
+    } else if msg.is_close() {
+        Message::Close(msg.close_frame().map(|(code, reason)| CloseFrame {
+            code,
+            reason: reason.to_owned(),
+        }))
+    } else if msg.is_ping() {
+        Message::Ping(payload())
+    } else if msg.is_pong() {
+        Message::Pong(payload())
+    } else {
+        Message::Binary(payload())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use salvo::conn::{Acceptor, Listener, TcpListener};
+    use tokio_tungstenite::tungstenite;
+    use webshelf_runtime::{
+        HttpError, Middleware, Next, RequestContext, Response, WebSocketOrigins,
+    };
+
+    use super::*;
+    use crate::UnifiedRequest;
+
+    async fn echo(mut req: UnifiedRequest) -> Result<Response, HttpError> {
+        req.upgrade_websocket(|mut ws| async move {
+            while let Some(Ok(msg)) = ws.recv().await {
+                if msg.is_close() || ws.send(msg).await.is_err() {
+                    break;
+                }
+            }
+        })
+        .await
+    }
+
+    /// Stand-in for the JWT guard: rejects upgrades without a session cookie.
+    struct RequireSession;
+
+    #[async_trait::async_trait]
+    impl Middleware for RequireSession {
+        async fn handle<R: RequestContext + 'static>(&self, req: R, next: Next<'_, R>) -> Response {
+            if req.cookie("webshelf_jwt").is_none() {
+                return HttpError::unauthorized("Missing session").into();
+            }
+            next.run(req).await
+        }
+    }
+
+    async fn spawn_server() -> SocketAddr {
+        let router = salvo::Router::with_path("ws")
+            .hoop(crate::middleware::websocket_origins(WebSocketOrigins::new(
+                [ALLOWED_ORIGIN],
+            )))
+            .hoop(crate::UnifiedMiddleware(RequireSession))
+            .get(crate::UnifiedHandler(echo));
+        let acceptor = TcpListener::new("127.0.0.1:0").bind().await;
+        let addr = acceptor.holdings()[0]
+            .local_addr
+            .clone()
+            .into_std()
+            .unwrap();
+        tokio::spawn(salvo::Server::new(acceptor).serve(router));
+        addr
+    }
+
+    const ALLOWED_ORIGIN: &str = "https://app.example.com";
+
+    fn ws_request(
+        addr: SocketAddr,
+        cookie: Option<&str>,
+    ) -> tungstenite::handshake::client::Request {
+        use tungstenite::client::IntoClientRequest;
+        let mut req = format!("ws://{addr}/ws").into_client_request().unwrap();
+        if let Some(cookie) = cookie {
+            req.headers_mut().insert("cookie", cookie.parse().unwrap());
+        }
+        req
+    }
+
+    #[tokio::test]
+    async fn upgrade_through_middleware_echoes_messages() {
+        let addr = spawn_server().await;
+        let (mut client, resp) =
+            tokio_tungstenite::connect_async(ws_request(addr, Some("webshelf_jwt=t")))
+                .await
+                .unwrap();
+        assert_eq!(resp.status(), http::StatusCode::SWITCHING_PROTOCOLS);
+
+        client
+            .send(tungstenite::Message::text("ping"))
+            .await
+            .unwrap();
+        let reply = client.next().await.unwrap().unwrap();
+        assert_eq!(reply, tungstenite::Message::text("ping"));
+
+        client
+            .send(tungstenite::Message::binary(vec![1u8, 2]))
+            .await
+            .unwrap();
+        let reply = client.next().await.unwrap().unwrap();
+        assert_eq!(reply, tungstenite::Message::binary(vec![1u8, 2]));
+    }
+
+    #[tokio::test]
+    async fn middleware_rejection_blocks_upgrade() {
+        let addr = spawn_server().await;
+        let err = tokio_tungstenite::connect_async(ws_request(addr, None))
+            .await
+            .unwrap_err();
+        match err {
+            tungstenite::Error::Http(resp) => {
+                assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED)
+            }
+            other => panic!("expected HTTP rejection, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn upgrade_checks_origin() {
+        let addr = spawn_server().await;
+        let with_origin = |origin: &str| {
+            let mut req = ws_request(addr, Some("webshelf_jwt=t"));
+            req.headers_mut().insert("origin", origin.parse().unwrap());
+            req
+        };
+
+        match tokio_tungstenite::connect_async(with_origin("https://evil.example"))
+            .await
+            .unwrap_err()
+        {
+            tungstenite::Error::Http(resp) => {
+                assert_eq!(resp.status(), http::StatusCode::FORBIDDEN)
+            }
+            other => panic!("expected HTTP rejection, got {other:?}"),
+        }
+        // Same-origin pages and configured origins may connect.
+        for origin in [format!("http://{addr}"), ALLOWED_ORIGIN.to_owned()] {
+            let (_, resp) = tokio_tungstenite::connect_async(with_origin(&origin))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), http::StatusCode::SWITCHING_PROTOCOLS);
+        }
+    }
+}
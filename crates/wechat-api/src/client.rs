@@ -5,13 +5,16 @@
 //! - Customer-service text message sending (used to push captcha codes)
 //!
 //! This module is behind the `client` feature flag so the SDK can be used
-//! in callback-only mode without pulling in an HTTP client.
+//! in callback-only mode without pulling in an HTTP client. With the `otel`
+//! feature, [`ReqwestTransport`] runs each call in a client span and sends the
+//! W3C `traceparent` header of the current trace.
 
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use serde::Deserialize;
+use tracing::Instrument;
 
 use crate::config::WechatConfig;
 use crate::error::{WechatError, WechatResult};
@@ -51,6 +54,60 @@ impl Default for ReqwestTransport {
     }
 }
 
+impl ReqwestTransport {
+    async fn send(&self, request: reqwest::RequestBuilder) -> WechatResult<serde_json::Value> {
+        #[cfg(feature = "otel")]
+        let request = request.headers(trace_headers());
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| WechatError::ApiRequest(e.to_string()))?;
+        resp.json::<serde_json::Value>()
+            .await
+            .map_err(|e| WechatError::ApiRequest(e.to_string()))
+    }
+}
+
+/// `traceparent` / `tracestate` of the current span, via the global propagator.
+#[cfg(feature = "otel")]
+fn trace_headers() -> reqwest::header::HeaderMap {
+    use opentelemetry::propagation::Injector;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    struct HeaderInjector(reqwest::header::HeaderMap);
+
+    impl Injector for HeaderInjector {
+        fn set(&mut self, key: &str, value: String) {
+            if let (Ok(name), Ok(value)) = (
+                reqwest::header::HeaderName::from_bytes(key.as_bytes()),
+                reqwest::header::HeaderValue::from_str(&value),
+            ) {
+                self.0.insert(name, value);
+            }
+        }
+    }
+
+    let context = tracing::Span::current().context();
+    let mut injector = HeaderInjector(reqwest::header::HeaderMap::new());
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut injector)
+    });
+    injector.0
+}
+
+/// Client span for one API call. The query string (which carries the access
+/// token) is left out of the recorded URL.
+fn api_span(method: &'static str, url: &str) -> tracing::Span {
+    let path = url.split('?').next().unwrap_or(url);
+    tracing::info_span!(
+        "wechat.api",
+        "otel.name" = %format!("{method} {path}"),
+        "otel.kind" = "client",
+        "http.request.method" = method,
+        "url.full" = path,
+    )
+}
+
 #[async_trait]
 impl HttpTransport for ReqwestTransport {
     async fn post_json(
@@ -58,28 +115,15 @@ impl HttpTransport for ReqwestTransport {
         url: &str,
         body: &serde_json::Value,
     ) -> WechatResult<serde_json::Value> {
-        let resp = self
-            .inner
-            .post(url)
-            .json(body)
-            .send()
+        self.send(self.inner.post(url).json(body))
+            .instrument(api_span("POST", url))
             .await
-            .map_err(|e| WechatError::ApiRequest(e.to_string()))?;
-        resp.json::<serde_json::Value>()
-            .await
-            .map_err(|e| WechatError::ApiRequest(e.to_string()))
     }
 
     async fn get_json(&self, url: &str) -> WechatResult<serde_json::Value> {
-        let resp = self
-            .inner
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| WechatError::ApiRequest(e.to_string()))?;
-        resp.json::<serde_json::Value>()
+        self.send(self.inner.get(url))
+            .instrument(api_span("GET", url))
             .await
-            .map_err(|e| WechatError::ApiRequest(e.to_string()))
     }
 }
 
@@ -339,3 +383,30 @@ mod tests {
         assert!(check_api_error(&resp).is_ok());
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "otel")]
+mod otel_tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_trace_headers_carry_current_trace() {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = api_span("GET", "https://api.weixin.qq.com/cgi-bin/token?secret=s");
+            let _entered = span.enter();
+            let traceparent = trace_headers()["traceparent"].to_str().unwrap().to_owned();
+            // 00-{trace_id}-{span_id}-01
+            assert!(traceparent.starts_with("00-"));
+            assert_eq!(traceparent.len(), 55);
+        });
+    }
+}
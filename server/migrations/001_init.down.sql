@@ -0,0 +1,4 @@
+-- Drops the whole baseline schema, including all user data.
+DROP TABLE IF EXISTS snowflake_worker;
+DROP TABLE IF EXISTS refresh_tokens;
+DROP TABLE IF EXISTS users;
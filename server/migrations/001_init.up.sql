@@ -1,5 +1,5 @@
--- 系统尚未上线，无需兼容旧数据，所有字段直接定义在 CREATE TABLE 中。
--- 如需增加新字段，直接在此文件修改即可，无需编写单独的 ALTER TABLE 迁移脚本。
+-- 基线 schema。语句保持幂等（IF NOT EXISTS），以便版本化之前创建的数据库直接采纳本迁移。
+-- 已应用的迁移不可再修改（校验和会失配）；schema 变更请新增 NNN_*.up.sql / .down.sql。
 -- Create users table if not exists
 CREATE TABLE IF NOT EXISTS users (
     id BIGINT PRIMARY KEY,
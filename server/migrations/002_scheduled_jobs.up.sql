@@ -0,0 +1,24 @@
+-- Scheduled background jobs (services::scheduler).
+-- One row per job registered in code; the schedule and enabled flag are synced from
+-- [scheduler] config on startup. A replica claims a due job with a conditional UPDATE
+-- (status <> 'running' or lease expired), so each run happens on exactly one replica;
+-- locked_until is renewed together with the Redis job lock while the job runs.
+CREATE TABLE scheduled_jobs (
+    name VARCHAR(100) PRIMARY KEY,
+    schedule TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    status VARCHAR(20) NOT NULL DEFAULT 'idle'
+        CHECK (status IN ('idle', 'running', 'succeeded', 'failed')),
+    next_run_at TIMESTAMPTZ,
+    locked_until TIMESTAMPTZ,
+    last_started_at TIMESTAMPTZ,
+    last_finished_at TIMESTAMPTZ,
+    last_duration_ms BIGINT,
+    last_output TEXT,
+    last_error TEXT,
+    last_run_by TEXT,
+    run_count BIGINT NOT NULL DEFAULT 0,
+    failure_count BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
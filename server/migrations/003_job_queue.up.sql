@@ -0,0 +1,25 @@
+-- Durable job queue (services::queue).
+-- Workers claim due rows with SELECT ... FOR UPDATE SKIP LOCKED and hide them until
+-- locked_until (visibility timeout); a running row whose timeout passed is claimable
+-- again. Failed jobs return to 'queued' with an exponential backoff in run_at until
+-- max_attempts is reached, then stay in 'dead' until retried via the admin API.
+CREATE TABLE job_queue (
+    id BIGSERIAL PRIMARY KEY,
+    kind VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'succeeded', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ,
+    locked_by TEXT,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+-- Due lookups only scan unfinished rows
+CREATE INDEX idx_job_queue_due ON job_queue (run_at) WHERE status IN ('queued', 'running');
+CREATE INDEX idx_job_queue_status ON job_queue (status, created_at DESC);
@@ -0,0 +1,32 @@
+-- WebAuthn passkeys (services::webauthn).
+-- public_key is the credential's COSE_Key as sent by the authenticator, algorithm its COSE
+-- algorithm identifier. sign_count is the authenticator's signature counter: an assertion
+-- whose counter does not move forward points to a cloned authenticator and is rejected
+-- (authenticators that always report 0, like most synced passkeys, are exempt).
+CREATE TABLE passkey_credentials (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    credential_id BYTEA NOT NULL UNIQUE,
+    public_key BYTEA NOT NULL,
+    algorithm INTEGER NOT NULL,
+    sign_count BIGINT NOT NULL DEFAULT 0,
+    transports JSONB NOT NULL DEFAULT '[]',
+    name VARCHAR(64) NOT NULL,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_passkey_credentials_user_id ON passkey_credentials (user_id);
+
+-- Outstanding registration / authentication ceremonies, keyed by the SHA-256 hash of the
+-- random challenge. Each challenge is deleted when its ceremony is completed, so it works
+-- once. user_id is set for registration only; passkey login does not know the user yet.
+CREATE TABLE webauthn_challenges (
+    challenge_hash VARCHAR(64) PRIMARY KEY,
+    purpose VARCHAR(16) NOT NULL,
+    user_id BIGINT REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webauthn_challenges_expires_at ON webauthn_challenges (expires_at);
@@ -0,0 +1,17 @@
+-- External identities from OpenID Connect providers (services::oidc).
+-- (provider, subject) is the stable identifier an OIDC provider issues for an account —
+-- the `sub` claim, unique per issuer. provider is the key of [oidc.providers.<name>];
+-- renaming a provider in the config orphans its identities. email is the address the
+-- provider reported at the last login, kept for display only.
+CREATE TABLE user_identities (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider VARCHAR(64) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    email VARCHAR(255),
+    last_login_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (provider, subject)
+);
+
+CREATE INDEX idx_user_identities_user_id ON user_identities (user_id);
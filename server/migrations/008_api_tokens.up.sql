@@ -0,0 +1,19 @@
+-- Personal access tokens (services::api_token): user-owned API keys for scripts and other
+-- services. token_hash is the SHA-256 hash of the whole token; only prefix (the start of
+-- the token, e.g. "wst_3f9a1c2b") is kept in clear to tell keys apart. scopes limit what
+-- the key may call. last_used_at / last_used_ip are refreshed at most once a minute per
+-- address.
+CREATE TABLE api_tokens (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(64) NOT NULL,
+    prefix VARCHAR(16) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes JSONB NOT NULL DEFAULT '[]',
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    last_used_ip VARCHAR(45),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_api_tokens_user_id ON api_tokens (user_id);
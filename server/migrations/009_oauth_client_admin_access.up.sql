@@ -0,0 +1,3 @@
+-- Client-credentials tokens act as the client's owner but without admin rights unless the
+-- client is explicitly granted admin_access (services::oauth).
+ALTER TABLE oauth_clients ADD COLUMN admin_access BOOLEAN NOT NULL DEFAULT FALSE;
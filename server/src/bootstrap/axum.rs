@@ -1,14 +1,20 @@
 //! Axum-specific bootstrap: CORS configuration and router construction.
 
 use crate::handlers::wechat::{wechat_callback_get, wechat_callback_post};
-use crate::middlewares::{auth_middleware, panic_middleware};
-use crate::routes::{api_routes, auth_routes};
+use crate::middlewares::{
+    auth_middleware, metrics_middleware, panic_middleware, request_id_middleware,
+    security_headers_middleware,
+};
+use crate::routes::{
+    api_routes, auth_routes, health_routes, metrics_routes, oauth_routes, openapi_routes,
+};
 use crate::{AppRouter, AppState};
 use distributed_ratelimit::RedisRateLimiter;
 use webshelf_axum::{
-    Any, CompressionLayer, CorsLayer, HeaderValue, Method, RequestBodyLimitLayer, TraceLayer,
+    Any, CompressionLayer, CorsLayer, Extension, HeaderValue, Method, RequestBodyLimitLayer,
     from_fn, from_fn_with_state, get, post,
 };
+use webshelf_runtime::SecurityHeadersMiddleware;
 
 /// Configure CORS layer (Axum mode)
 pub fn configure_cors(allowed_origins: &[String], env: &str) -> CorsLayer {
@@ -105,6 +111,7 @@ pub fn build_app_router(state: AppState, env: &str, rate_limiter: RedisRateLimit
     let allowed_origins = state.config.server.allowed_origins.clone();
     let cors = configure_cors(&allowed_origins, env);
     let compression = CompressionLayer::new();
+    let security_headers = SecurityHeadersMiddleware::new(&state.config.server.security_headers);
 
     AppRouter::new()
         .nest(
@@ -114,7 +121,11 @@ pub fn build_app_router(state: AppState, env: &str, rate_limiter: RedisRateLimit
                 auth_middleware::<AppState>,
             )),
         )
-        .nest("/api/public/auth", auth_routes(rate_limiter))
+        .nest("/api/public/auth", auth_routes(rate_limiter.clone()))
+        .nest("/api/public/oauth", oauth_routes(rate_limiter))
+        .merge(health_routes())
+        .merge(openapi_routes(&state.config.openapi))
+        .merge(metrics_routes(&state.config.metrics))
         // Conditionally register WeChat callback routes.
         .merge(if state.wechat.is_some() {
             AppRouter::new().route(
@@ -125,8 +136,17 @@ pub fn build_app_router(state: AppState, env: &str, rate_limiter: RedisRateLimit
             AppRouter::new()
         })
         .layer(from_fn(panic_middleware))
-        .layer(TraceLayer::new_for_http())
+        .layer(from_fn(metrics_middleware))
+        .layer(from_fn(request_id_middleware))
+        .layer(from_fn_with_state(
+            security_headers,
+            security_headers_middleware,
+        ))
         .layer(cors)
         .layer(compression)
         .layer(RequestBodyLimitLayer::new(10 * 1024 * 1024))
+        .layer(Extension(state.config.server.websocket_origins.clone()))
+        // Outermost so every UnifiedRequest (handlers, auth and rate-limit middleware)
+        // resolves client_ip() against the same trusted proxy list.
+        .layer(Extension(state.config.server.trusted_proxies.clone()))
 }
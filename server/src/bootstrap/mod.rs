@@ -5,7 +5,7 @@
 //! - `salvo.rs`: Salvo-specific router construction.
 //!
 //! Shared bootstrapping logic (config loading, DB init, state creation, server start)
-//! lives in this module.
+//! lives in this module; `commands.rs` runs the one-shot CLI subcommands.
 
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -15,10 +15,13 @@ use crate::{
     AppConfig, AppState, AutoRouter, migrations,
     repositories::user::{Column, Entity as UserEntity},
     routes::helpers::create_rate_limiter,
-    utils::{db_router::connect_db, init_logger, load_config},
+    utils::{db_router::connect_db, init_logger, load_config, logger::LogGuard},
 };
 use crate::{AppRouter, AppRuntime, Runtime};
 
+mod commands;
+pub use commands::{Command, MigrateCommand, run_command};
+
 // ── Runtime-specific submodules ──────────────────────────
 #[cfg(not(feature = "webshelf-salvo"))]
 pub mod axum;
@@ -59,14 +62,14 @@ pub struct CliArgs {
     #[arg(short = 'P', long)]
     pub port: Option<u16>,
     /// Environment (development, staging, production)
-    #[arg(short = 'E', long, default_value = "development")]
+    #[arg(short = 'E', long, default_value = "development", global = true)]
     pub env: String,
     /// Configuration file path
-    #[arg(short = 'C', long, default_value = "config.toml")]
+    #[arg(short = 'C', long, default_value = "config.toml", global = true)]
     pub config: String,
-    /// Log level (trace, debug, info, warn, error)
-    #[arg(short = 'L', long, default_value = "info")]
-    pub log_level: String,
+    /// Run a one-shot command instead of starting the server
+    #[command(subcommand)]
+    pub command: Option<Command>,
 }
 
 /// Application bootstrap result containing all initialized components
@@ -74,12 +77,15 @@ pub struct BootstrapResult {
     pub app: AppRouter,
     pub state: AppState,
     pub bind_addr: String,
-    pub _worker_handle: crate::snowflake::WorkerHandle,
 }
 
-/// Initialize application logger
-pub fn init_logging(log_level: &str) {
-    init_logger(log_level);
+/// Initialize application logger from `[logging]` (and OpenTelemetry export when
+/// `[telemetry]` is enabled).
+///
+/// Runs after [`load_app_config`] so outputs and the export layer are in place from the
+/// first log line. Keep the returned guard until exit; dropping it flushes file outputs.
+pub fn init_logging(config: &AppConfig) -> Result<LogGuard> {
+    init_logger(&config.logging, &config.telemetry)
 }
 
 /// Setup panic hook for graceful panic handling.
@@ -132,8 +138,17 @@ pub async fn init_database(config: &AppConfig) -> Result<sea_orm::DatabaseConnec
     Ok(db)
 }
 
-/// Run database migrations
-pub async fn run_database_migrations(db: &sea_orm::DatabaseConnection) -> Result<()> {
+/// Apply pending migrations, or with `database.auto_migrate = false` only check that none
+/// are pending.
+pub async fn run_database_migrations(
+    db: &sea_orm::DatabaseConnection,
+    config: &crate::utils::config::DatabaseConfig,
+) -> Result<()> {
+    if !config.auto_migrate {
+        return migrations::require_up_to_date(db)
+            .await
+            .context("Database schema is not up to date");
+    }
     tracing::info!("Running database migrations...");
     migrations::run_migrations(db)
         .await
@@ -151,55 +166,204 @@ pub fn create_app_state(
     let email_service = emailserver::EmailService::new(config.email.clone());
     let wechat =
         crate::services::wechat::init_wechat_components(&config.wechat, &cache, db.clone());
+    let shutdown = webshelf_runtime::Shutdown::new(config.server.shutdown.clone());
     AppState {
         db,
         cache,
         config: Arc::new(config),
         email: email_service,
         wechat,
+        shutdown,
     }
 }
 
-/// Bootstrap the entire application
-pub async fn bootstrap(cli_args: CliArgs) -> Result<BootstrapResult> {
-    tracing::info!("Starting webshelf in {} mode", cli_args.env);
-
-    let app_config = load_app_config(&cli_args)?;
+/// Reject invalid database pool, two-factor, passkey, OpenID Connect and OAuth settings, and
+/// default secrets and credentials outside `development` (also run by `webshelf check-config`).
+pub fn validate_config(env: &str, config: &AppConfig) -> Result<()> {
+    // otpauth:// 标签格式为 "issuer:account"，issuer 本身不能含冒号
+    let two_factor = &config.two_factor;
+    if two_factor.issuer.trim().is_empty() || two_factor.issuer.contains(':') {
+        anyhow::bail!(
+            "two_factor.issuer must be non-empty and must not contain ':' (current: {:?})",
+            two_factor.issuer
+        );
+    }
+    if two_factor.challenge_ttl_secs == 0 || two_factor.max_attempts == 0 {
+        anyhow::bail!("two_factor.challenge_ttl_secs and two_factor.max_attempts must be > 0");
+    }
+    validate_database(&config.database)?;
+    validate_webauthn(&config.webauthn)?;
+    validate_oidc(&config.oidc)?;
+    validate_oauth(&config.oauth)?;
 
-    if cli_args.env != "development" {
-        let is_default = app_config.jwt_secret == "REPLACE_ME_WITH_A_STRONG_SECRET";
+    if env != "development" {
+        let is_default = config.jwt_secret == "REPLACE_ME_WITH_A_STRONG_SECRET";
         if is_default {
             anyhow::bail!(
                 "JWT secret must be changed from the default value in {} environment! \
                  Set WEBSHELF_JWT_SECRET environment variable or update config.toml.",
-                cli_args.env
+                env
             );
         }
-        if app_config.jwt_secret.len() < 32 {
+        if config.jwt_secret.len() < 32 {
             anyhow::bail!(
                 "JWT secret must be at least 32 characters long in {} environment (current: {}). \
                  Generate a strong secret with: openssl rand -base64 64",
-                cli_args.env,
-                app_config.jwt_secret.len()
+                env,
+                config.jwt_secret.len()
             );
         }
-        if app_config.system_admin_email.to_lowercase() == "admin@webshelf.local"
-            || app_config.system_admin_password == "change-me-admin-password"
+        if config.system_admin_email.to_lowercase() == "admin@webshelf.local"
+            || config.system_admin_password == "change-me-admin-password"
         {
             anyhow::bail!(
                 "System admin credentials must not use default values in {} environment! \
                  Set WEBSHELF_SYSTEM_ADMIN_EMAIL and WEBSHELF_SYSTEM_ADMIN_PASSWORD environment variables \
                  or update config.toml.",
-                cli_args.env
+                env
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The migration runner holds its advisory lock on one pooled connection while it applies
+/// migrations on another (`migrations::acquire_lock`), so a single-connection pool would wait
+/// on itself until the acquire timeout.
+pub(crate) fn validate_database(database: &crate::utils::config::DatabaseConfig) -> Result<()> {
+    if database.max_connections < 2 {
+        anyhow::bail!(
+            "database.max_connections must be at least 2: migrations hold a lock on one \
+             connection while running on another (current: {})",
+            database.max_connections
+        );
+    }
+    Ok(())
+}
+
+/// Passkeys only work when every origin belongs to the relying party ID (WebAuthn §5.1.3).
+fn validate_webauthn(webauthn: &crate::utils::config::WebAuthnConfig) -> Result<()> {
+    let rp_id = webauthn.rp_id.as_str();
+    if rp_id.is_empty() || rp_id.contains(['/', ':']) {
+        anyhow::bail!(
+            "webauthn.rp_id must be a bare domain without scheme or port (current: {:?})",
+            rp_id
+        );
+    }
+    if webauthn.origins.is_empty() || webauthn.challenge_ttl_secs == 0 {
+        anyhow::bail!(
+            "webauthn.origins must not be empty and webauthn.challenge_ttl_secs must be > 0"
+        );
+    }
+    for origin in &webauthn.origins {
+        let uri: http::Uri = origin
+            .parse()
+            .with_context(|| format!("webauthn.origins: invalid origin {origin:?}"))?;
+        let host = uri.host().unwrap_or_default();
+        // 浏览器只在 HTTPS（localhost 除外）下提供 WebAuthn
+        let secure = uri.scheme_str() == Some("https")
+            || (uri.scheme_str() == Some("http") && host == "localhost");
+        let in_scope = host == rp_id || host.ends_with(&format!(".{rp_id}"));
+        if !secure || !in_scope || uri.path() != "/" || origin.ends_with('/') {
+            anyhow::bail!(
+                "webauthn.origins: {origin:?} must be an https:// origin (http:// only for \
+                 localhost) without path, on {rp_id:?} or a subdomain of it"
             );
         }
     }
+    Ok(())
+}
+
+/// Provider keys end up in URLs; issuers must be HTTPS since their keys vouch for logins.
+fn validate_oidc(oidc: &crate::utils::config::OidcConfig) -> Result<()> {
+    if oidc.flow_ttl_secs == 0 || oidc.metadata_cache_secs == 0 || oidc.http_timeout_ms == 0 {
+        anyhow::bail!(
+            "oidc.flow_ttl_secs, oidc.metadata_cache_secs and oidc.http_timeout_ms must be > 0"
+        );
+    }
+    for (name, url) in [
+        ("public_url", &oidc.public_url),
+        ("success_redirect", &oidc.success_redirect),
+        ("login_redirect", &oidc.login_redirect),
+    ] {
+        let parsed =
+            url::Url::parse(url).with_context(|| format!("oidc.{name}: invalid URL {url:?}"))?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.fragment().is_some() {
+            anyhow::bail!("oidc.{name}: {url:?} must be an http(s) URL without fragment");
+        }
+    }
+
+    for (key, provider) in &oidc.providers {
+        let valid_key = !key.is_empty()
+            && key.len() <= 64
+            && key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid_key {
+            anyhow::bail!(
+                "oidc.providers: key {key:?} must be 1-64 characters of a-z, 0-9, '-' or '_'"
+            );
+        }
+        let issuer = url::Url::parse(&provider.issuer)
+            .with_context(|| format!("oidc.providers.{key}.issuer: invalid URL"))?;
+        // 仅本地测试的提供方可以用 http
+        let local = matches!(issuer.host_str(), Some("localhost" | "127.0.0.1"));
+        if !(issuer.scheme() == "https" || (issuer.scheme() == "http" && local)) {
+            anyhow::bail!(
+                "oidc.providers.{key}.issuer must be an https:// URL (http:// only for localhost)"
+            );
+        }
+        if provider.client_id.trim().is_empty() {
+            anyhow::bail!("oidc.providers.{key}.client_id must not be empty");
+        }
+        if !provider.scopes.iter().any(|scope| scope == "openid") {
+            anyhow::bail!("oidc.providers.{key}.scopes must include \"openid\"");
+        }
+    }
+    Ok(())
+}
+
+/// The issuer and consent page end up in tokens and redirects handed to clients.
+fn validate_oauth(oauth: &crate::utils::config::OAuthConfig) -> Result<()> {
+    if oauth.access_token_ttl_secs == 0
+        || oauth.refresh_token_ttl_secs == 0
+        || oauth.code_ttl_secs == 0
+        || oauth.request_ttl_secs == 0
+    {
+        anyhow::bail!(
+            "oauth.access_token_ttl_secs, oauth.refresh_token_ttl_secs, oauth.code_ttl_secs and \
+             oauth.request_ttl_secs must be > 0"
+        );
+    }
+    for (name, url) in [
+        ("public_url", &oauth.public_url),
+        ("consent_url", &oauth.consent_url),
+    ] {
+        let parsed =
+            url::Url::parse(url).with_context(|| format!("oauth.{name}: invalid URL {url:?}"))?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.fragment().is_some() {
+            anyhow::bail!("oauth.{name}: {url:?} must be an http(s) URL without fragment");
+        }
+    }
+    // issuer 必须与令牌中的 iss 逐字节一致，尾部斜杠会导致客户端校验失败
+    if oauth.public_url.ends_with('/') {
+        anyhow::bail!("oauth.public_url must not end with '/'");
+    }
+    Ok(())
+}
+
+/// Bootstrap the entire application from the configuration loaded by [`load_app_config`]
+pub async fn bootstrap(cli_args: CliArgs, app_config: AppConfig) -> Result<BootstrapResult> {
+    tracing::info!("Starting webshelf in {} mode", cli_args.env);
+
+    validate_config(&cli_args.env, &app_config)?;
 
     let host = cli_args
         .host
         .unwrap_or_else(|| app_config.server.host.clone());
     let port = cli_args.port.unwrap_or(app_config.server.port);
 
+    let mut health_check = None;
     let db = if app_config.database_read_urls.is_empty() {
         let write_db = init_database(&app_config).await?;
         AutoRouter::single(write_db)
@@ -215,31 +379,52 @@ pub async fn bootstrap(cli_args: CliArgs) -> Result<BootstrapResult> {
         .context("Failed to initialize AutoRouter with read replicas")?;
 
         if app_config.database_routing.health_check_interval_secs > 0 {
-            db.clone()
-                .start_health_check(std::time::Duration::from_secs(
-                    app_config.database_routing.health_check_interval_secs,
-                ));
+            health_check = Some(
+                db.clone()
+                    .start_health_check(std::time::Duration::from_secs(
+                        app_config.database_routing.health_check_interval_secs,
+                    )),
+            );
         }
         db
     };
 
-    run_database_migrations(db.write_conn()).await?;
+    run_database_migrations(db.write_conn().inner(), &app_config.database).await?;
 
-    if let Err(e) = crate::services::auth::cleanup_expired_refresh_tokens(db.write_conn()).await {
-        tracing::warn!(
-            "Failed to cleanup expired refresh tokens (non-fatal): {:?}",
-            e
-        );
-    }
-
-    let _worker_handle = crate::snowflake::init(db.write_conn()).await?;
-    seed_system_admin(db.write_conn(), &app_config).await?;
+    let worker_handle = crate::snowflake::init(db.write_conn().inner()).await?;
+    seed_system_admin(db.write_conn().inner(), &app_config).await?;
 
     let cache =
         crate::services::CacheService::new(&app_config.redis_url, app_config.cache_max_connections)
             .await;
 
     let state = create_app_state(db, cache, app_config);
+
+    // 清理过期令牌/验证码等后台任务（间隔任务在首次注册时立即到期）
+    let scheduler = crate::services::Scheduler::start(state.clone())
+        .await
+        .context("Failed to start scheduler")?;
+
+    // 邮件等持久化队列任务
+    let queue = crate::services::JobQueue::start(state.clone());
+
+    // 停止顺序：先停调度器与队列 worker（等待进行中的任务），再停副本健康检查，
+    // 最后停 snowflake 心跳并注销 worker（仍需写库）。
+    state.shutdown.on_stop("scheduler", scheduler.shutdown());
+    state.shutdown.on_stop("job queue", queue.shutdown());
+    if let Some(health_check) = health_check {
+        state
+            .shutdown
+            .on_stop("replica health check", async move { health_check.abort() });
+    }
+    state
+        .shutdown
+        .on_stop("snowflake heartbeat", worker_handle.shutdown());
+    // 最后刷新 trace 导出，前面的停止过程产生的 span 也能发出
+    state
+        .shutdown
+        .on_stop("trace exporter", crate::utils::telemetry::shutdown());
+
     let app = build_app_router(state.clone(), &cli_args.env);
 
     let bind_addr = format!("{}:{}", host, port);
@@ -248,7 +433,6 @@ pub async fn bootstrap(cli_args: CliArgs) -> Result<BootstrapResult> {
         app,
         state,
         bind_addr,
-        _worker_handle,
     })
 }
 
@@ -333,15 +517,27 @@ async fn seed_system_admin(db: &sea_orm::DatabaseConnection, config: &AppConfig)
 }
 
 /// Start HTTP server with graceful shutdown
+///
+/// On SIGTERM the readiness endpoint fails first, in-flight requests are drained, then the
+/// background tasks registered in [`bootstrap`] are stopped in order.
 pub async fn start_server(bootstrap_result: BootstrapResult) -> Result<()> {
     let BootstrapResult {
         app,
         state,
         bind_addr,
-        _worker_handle,
     } = bootstrap_result;
     tracing::info!("Starting server on {}", bind_addr);
-    AppRuntime::serve(app, state, &bind_addr).await?;
+    let tls = state.config.server.tls.clone();
+    let shutdown = state.shutdown.clone();
+    AppRuntime::serve(
+        app,
+        state,
+        &bind_addr,
+        tls.enabled.then_some(&tls),
+        shutdown.clone(),
+    )
+    .await?;
+    shutdown.stop_background_tasks().await;
     tracing::info!("Server shutdown completed");
     Ok(())
 }
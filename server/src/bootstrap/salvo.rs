@@ -3,10 +3,15 @@
 use crate::handlers::wechat::{wechat_callback_get, wechat_callback_post};
 use crate::middlewares::AuthMiddleware;
 use crate::routes::helpers::{get, post};
-use crate::routes::{api_routes, auth_routes};
+use crate::routes::{
+    api_routes, auth_routes, health_routes, metrics_routes, oauth_routes, openapi_routes,
+};
 use crate::{AppRouter, AppState};
 use distributed_ratelimit::RedisRateLimiter;
-use webshelf_salvo::middleware::{CorsConfig, catch_panic, compression, logger, max_body_size};
+use webshelf_salvo::middleware::{
+    CorsConfig, catch_panic, compression, max_body_size, metrics, request_id, security_headers,
+    trusted_proxies, websocket_origins,
+};
 
 /// Build application router — Salvo version
 ///
@@ -17,19 +22,25 @@ pub fn build_app_router(state: AppState, env: &str, rate_limiter: RedisRateLimit
     let allowed_origins = state.config.server.allowed_origins.clone();
     let cors_config = CorsConfig::from_origins(&allowed_origins, env);
     let cors_handler = cors_config.into_handler();
+    let trusted = state.config.server.trusted_proxies.clone();
 
     // 与 axum 版本保持一致的中间件链顺序（从外到内）：
-    //   max_body_size → compression → cors → logger → catch_panic
+    //   trusted_proxies → websocket_origins → max_body_size → compression → cors
+    //   → security_headers → request_id → metrics → catch_panic
     //   → route matching → AuthMiddleware
     //
     // 注意: Salvo 的 hoop 按插入顺序执行（先添加 = 先处理请求 = 最外层），
     //       与 Axum 的 layer（后添加 = 最外层）相反。
     //       这里将外层中间件先添加，以匹配 Axum 的请求处理管道顺序。
     //       AuthMiddleware 通过 nest 内部的 hoop 只对 /api 路径生效，
-    //       不影响 /api/public/auth 路径。
+    //       不影响 /api/public/auth 与 /api/public/oauth 路径。
     AppRouter::new()
         .nest("/api", api_routes().hoop(AuthMiddleware::<AppState>::new()))
-        .nest("/api/public/auth", auth_routes(rate_limiter))
+        .nest("/api/public/auth", auth_routes(rate_limiter.clone()))
+        .nest("/api/public/oauth", oauth_routes(rate_limiter))
+        .merge(health_routes())
+        .merge(openapi_routes(&state.config.openapi))
+        .merge(metrics_routes(&state.config.metrics))
         // Conditionally register WeChat callback routes.
         .merge(if state.wechat.is_some() {
             AppRouter::new()
@@ -38,9 +49,15 @@ pub fn build_app_router(state: AppState, env: &str, rate_limiter: RedisRateLimit
         } else {
             AppRouter::new()
         })
+        .hoop(trusted_proxies(trusted))
+        .hoop(websocket_origins(
+            state.config.server.websocket_origins.clone(),
+        ))
         .hoop(max_body_size(10 * 1024 * 1024))
         .hoop(compression())
         .hoop(cors_handler)
-        .hoop(logger())
+        .hoop(security_headers(&state.config.server.security_headers))
+        .hoop(request_id())
+        .hoop(metrics())
         .hoop(catch_panic())
 }
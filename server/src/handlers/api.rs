@@ -1,9 +1,10 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use validator::{Validate, ValidationError};
 
 use crate::AppState;
 use crate::handlers::auth::{expiry_cookie, token_cookie, unix_timestamp_from_now};
-use crate::handlers::helpers::extract_handler_context;
+use crate::handlers::helpers::{extract_handler_context, extract_scoped_context};
 use crate::middlewares::{AuthUser, JWT_COOKIE, REFRESH_COOKIE};
 use crate::repositories::user::{CreateUserInput, UpdateUserInput, UserResponse};
 use crate::services::auth::AuthService;
@@ -20,7 +21,7 @@ fn to_http<E: Into<ApiError>>(e: E) -> HttpError {
 }
 
 /// Health check response
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct HealthResponse {
     status: String,
     version: String,
@@ -36,7 +37,7 @@ pub async fn health_check(_req: crate::ServerRequest) -> Result<Response, HttpEr
 }
 
 /// Query parameters for listing users
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ListUsersQuery {
     #[serde(default = "default_page")]
     page: u64,
@@ -53,7 +54,7 @@ fn default_per_page() -> u64 {
 }
 
 /// Paginated users response
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct PaginatedUsersResponse {
     pub items: Vec<UserResponse>,
     pub total: u64,
@@ -76,7 +77,7 @@ impl From<PaginatedResponse<UserResponse>> for PaginatedUsersResponse {
 
 /// List users with pagination
 pub async fn list_users(req: crate::ServerRequest) -> Result<Response, HttpError> {
-    let (state, auth_user) = extract_handler_context(&req)?;
+    let (state, auth_user) = extract_scoped_context(&req, "users:read")?;
     let query: ListUsersQuery = req.parse_query().map_err(HttpError::bad_request)?;
 
     let service = UserService::new(state.db.clone(), state.cache.clone());
@@ -95,7 +96,7 @@ pub async fn list_users(req: crate::ServerRequest) -> Result<Response, HttpError
 }
 
 /// Create user request with validation
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
 pub struct CreateUserRequest {
     #[validate(email(message = "must be a valid email address"))]
     email: String,
@@ -125,19 +126,17 @@ pub struct CreateUserRequest {
 /// unverified); the request succeeds with `email_verified: false` so the
 /// client is not left guessing whether the user exists.
 pub async fn create_user(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
-    let (state, auth_user) = extract_handler_context(&req)?;
+    let (state, auth_user) = extract_scoped_context(&req, "users:write")?;
     let payload: CreateUserRequest = req
         .parse_json_or_form()
         .await
         .map_err(HttpError::bad_request)?;
 
     // Validate request payload
-    payload
-        .validate()
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    payload.validate().map_err(to_http)?;
 
     // Validate password strength (complexity rules)
-    check_password_strength(&payload.password).map_err(to_http)?;
+    check_password_strength("password", &payload.password).map_err(to_http)?;
 
     // Normalize email to lowercase once at the entry point
     let email = payload.email.to_lowercase();
@@ -191,7 +190,7 @@ pub async fn create_user(mut req: crate::ServerRequest) -> Result<Response, Http
 
 /// Get current user profile — `GET /api/users/me` (any authenticated user)
 pub async fn get_me(req: crate::ServerRequest) -> Result<Response, HttpError> {
-    let (state, auth_user) = extract_handler_context(&req)?;
+    let (state, auth_user) = extract_scoped_context(&req, "users:read")?;
 
     let user_id: i64 = auth_user.user_id.parse().map_err(|_| {
         tracing::error!("Invalid user ID in auth token: {}", auth_user.user_id);
@@ -209,7 +208,7 @@ pub async fn get_me(req: crate::ServerRequest) -> Result<Response, HttpError> {
 }
 
 /// Change password request body
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
 pub struct ChangePasswordRequest {
     #[validate(length(min = 1, message = "current password is required"))]
     pub current_password: String,
@@ -218,7 +217,7 @@ pub struct ChangePasswordRequest {
 }
 
 /// Change password response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ChangePasswordResponse {
     pub message: String,
     pub new_token: String,
@@ -250,7 +249,7 @@ async fn change_my_password_inner(
     payload: &ChangePasswordRequest,
 ) -> Result<(ChangePasswordResponse, Vec<cookie::Cookie<'static>>), ApiError> {
     payload.validate()?;
-    check_password_strength(&payload.new_password)?;
+    check_password_strength("new_password", &payload.new_password)?;
 
     let user_id: i64 = auth_user.user_id.parse().map_err(|_| {
         tracing::error!("Invalid user ID in auth token: {}", auth_user.user_id);
@@ -260,7 +259,8 @@ async fn change_my_password_inner(
     if payload.current_password == payload.new_password {
         return Err(ApiError::BadRequest(
             "New password must be different from current password".to_string(),
-        ));
+        )
+        .with_code("same_password"));
     }
 
     let service = UserService::new(state.db.clone(), state.cache.clone());
@@ -315,7 +315,7 @@ async fn change_my_password_inner(
 }
 
 /// Logout-all response
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct LogoutAllResponse {
     pub message: String,
 }
@@ -337,10 +337,11 @@ pub async fn logout_all(req: crate::ServerRequest) -> Result<Response, HttpError
         state.config.refresh_token_expiry_seconds,
     );
 
-    service
+    let api_tokens = service
         .revoke_all_sessions(user_id)
         .await
         .map_err(|_| HttpError::internal("Failed to revoke all sessions"))?;
+    tracing::info!(user_id, api_tokens, "All sessions and API tokens revoked");
 
     let token_cache_key = format!("user:token_version:{}", user_id);
     if let Err(e) = state.cache.invalidate(&token_cache_key).await {
@@ -367,7 +368,7 @@ pub async fn logout_all(req: crate::ServerRequest) -> Result<Response, HttpError
 
 /// Get a user by ID
 pub async fn get_user(req: crate::ServerRequest) -> Result<Response, HttpError> {
-    let (state, auth_user) = extract_handler_context(&req)?;
+    let (state, auth_user) = extract_scoped_context(&req, "users:read")?;
     let id: i64 = req
         .parse_param("id")
         .map_err(|_| HttpError::bad_request("Invalid or missing user ID"))?;
@@ -383,7 +384,7 @@ pub async fn get_user(req: crate::ServerRequest) -> Result<Response, HttpError>
 }
 
 /// Update user request with validation
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
 pub struct UpdateUserRequest {
     #[validate(email(message = "must be a valid email address"))]
     email: Option<String>,
@@ -413,7 +414,7 @@ fn validate_role(role: &str) -> Result<(), ValidationError> {
 
 /// Update a user
 pub async fn update_user(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
-    let (state, auth_user) = extract_handler_context(&req)?;
+    let (state, auth_user) = extract_scoped_context(&req, "users:write")?;
     let id: i64 = req
         .parse_param("id")
         .map_err(|_| HttpError::bad_request("Invalid or missing user ID"))?;
@@ -422,9 +423,7 @@ pub async fn update_user(mut req: crate::ServerRequest) -> Result<Response, Http
         .await
         .map_err(HttpError::bad_request)?;
 
-    payload
-        .validate()
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    payload.validate().map_err(to_http)?;
 
     if payload.email.is_none() && payload.name.is_none() && payload.role.is_none() {
         return Err(HttpError::bad_request(
@@ -456,14 +455,14 @@ pub async fn update_user(mut req: crate::ServerRequest) -> Result<Response, Http
 }
 
 /// Delete user response
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct DeleteUserResponse {
     pub message: String,
 }
 
 /// Delete a user
 pub async fn delete_user(req: crate::ServerRequest) -> Result<Response, HttpError> {
-    let (state, auth_user) = extract_handler_context(&req)?;
+    let (state, auth_user) = extract_scoped_context(&req, "users:write")?;
     let id: i64 = req
         .parse_param("id")
         .map_err(|_| HttpError::bad_request("Invalid or missing user ID"))?;
@@ -485,13 +484,13 @@ pub async fn delete_user(req: crate::ServerRequest) -> Result<Response, HttpErro
 }
 
 /// Set balance request body
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SetBalanceRequest {
     pub balance: i64,
 }
 
 /// Set balance response
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct SetBalanceResponse {
     pub balance: i64,
     pub display_balance: f64,
@@ -500,7 +499,7 @@ pub struct SetBalanceResponse {
 
 /// Set a user's balance — `PUT /api/users/{id}/balance` (admin/system only).
 pub async fn set_balance(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
-    let (state, auth_user) = extract_handler_context(&req)?;
+    let (state, auth_user) = extract_scoped_context(&req, "users:write")?;
     let id: i64 = req
         .parse_param("id")
         .map_err(|_| HttpError::bad_request("Invalid or missing user ID"))?;
@@ -525,13 +524,13 @@ pub async fn set_balance(mut req: crate::ServerRequest) -> Result<Response, Http
 }
 
 /// Adjust balance request body (delta amount, positive = increase, negative = decrease)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct AdjustBalanceRequest {
     pub amount: i64,
 }
 
 /// Adjust balance response
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct AdjustBalanceResponse {
     pub balance: i64,
     pub display_balance: f64,
@@ -540,7 +539,7 @@ pub struct AdjustBalanceResponse {
 
 /// Adjust a user's balance — `POST /api/users/{id}/balance/adjust` (admin/system only).
 pub async fn adjust_balance(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
-    let (state, auth_user) = extract_handler_context(&req)?;
+    let (state, auth_user) = extract_scoped_context(&req, "users:write")?;
     let id: i64 = req
         .parse_param("id")
         .map_err(|_| HttpError::bad_request("Invalid or missing user ID"))?;
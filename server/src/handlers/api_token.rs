@@ -0,0 +1,89 @@
+//! Personal access token endpoints.
+//!
+//! Users create, list and revoke their API keys under `/api/users/me/tokens`. Only a
+//! logged-in session may manage them: a personal access token cannot mint or revoke tokens.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::AppState;
+use crate::handlers::helpers::{extract_handler_context, self_id, to_http};
+use crate::services::api_token::{ApiTokenInfo, ApiTokenService};
+use webshelf_runtime::{HttpError, RequestContext, Response};
+
+/// Create a personal access token.
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+pub struct CreateApiTokenRequest {
+    /// Label shown in the token list
+    #[validate(length(min = 1, max = 64, message = "name must be 1-64 characters"))]
+    name: String,
+
+    /// Granted scopes, e.g. `["users:read"]`
+    scopes: Vec<String>,
+
+    /// Lifetime in days; omit for a token that does not expire
+    #[serde(default)]
+    #[validate(range(min = 1, max = 365, message = "expires_in_days must be 1-365"))]
+    expires_in_days: Option<u32>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ApiTokenListResponse {
+    pub tokens: Vec<ApiTokenInfo>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DeleteApiTokenResponse {
+    message: String,
+}
+
+fn service(state: &AppState) -> ApiTokenService {
+    ApiTokenService::new(state.db.clone())
+}
+
+/// Personal access tokens of the current user
+pub async fn list_api_tokens(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let user_id = self_id(&auth_user)?;
+    let tokens = service(&state).list(user_id).await.map_err(to_http)?;
+    Response::json(&ApiTokenListResponse { tokens })
+}
+
+/// Create a token; the secret is only returned in this response
+pub async fn create_api_token(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: CreateApiTokenRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+    payload.validate().map_err(to_http)?;
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(HttpError::bad_request("name must be 1-64 characters"));
+    }
+
+    let user_id = self_id(&auth_user)?;
+    let token = service(&state)
+        .create(user_id, name, &payload.scopes, payload.expires_in_days)
+        .await
+        .map_err(to_http)?;
+    Response::json(&token)
+}
+
+/// Revoke one of the current user's tokens
+pub async fn delete_api_token(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let token_id: i64 = req
+        .parse_param("id")
+        .map_err(|_| HttpError::bad_request("Invalid or missing token ID"))?;
+
+    let user_id = self_id(&auth_user)?;
+    service(&state)
+        .revoke(user_id, token_id)
+        .await
+        .map_err(to_http)?;
+    Response::json(&DeleteApiTokenResponse {
+        message: "Token revoked".to_string(),
+    })
+}
@@ -1,14 +1,19 @@
+use http::StatusCode;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
 use crate::AppState;
-use crate::handlers::helpers::extract_state;
+use crate::handlers::helpers::{extract_state, to_http};
 use crate::middlewares::{EXPIRY_COOKIE, JWT_COOKIE, REFRESH_COOKIE};
 use crate::repositories::user::CreateUserInput;
-use crate::services::auth::{AuthService, LoginRequest, LoginResponse};
+use crate::services::auth::{AuthService, LoginOutcome, LoginRequest, LoginResponse};
+use crate::services::oidc::{OidcCallback, OidcError, OidcProviderInfo, OidcService};
 use crate::services::password_reset::{PasswordResetError, PasswordResetService};
+use crate::services::two_factor::{TwoFactorService, has_two_factor};
 use crate::services::user::UserService;
 use crate::services::verification::{VerificationError, VerificationService};
+use crate::services::webauthn::{AuthenticationCredential, WebAuthnService};
 use crate::utils::error::ApiError;
 use crate::utils::validator::check_password_strength;
 use sha2::Digest;
@@ -72,7 +77,7 @@ pub(crate) fn clear_auth_cookies(secure: bool) -> Vec<cookie::Cookie<'static>> {
 }
 
 /// Login request with validation
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
 pub struct LoginRequestBody {
     #[validate(email(message = "must be a valid email address"))]
     email: String,
@@ -96,20 +101,78 @@ pub async fn login(mut req: crate::ServerRequest) -> Result<Response, HttpError>
         .await
         .map_err(HttpError::bad_request)?;
 
-    let result = login_inner(&state, &payload).await?;
-    let (login_resp, cookies) = result;
+    login_inner(&state, &payload).await?.into_response()
+}
 
-    let mut response = Response::json(&login_resp)?;
-    for cookie in cookies {
-        response.set_cookie(cookie);
+/// Password step accepted for an account with two-factor authentication.
+///
+/// Returned with `202 Accepted` and without cookies; the client finishes the
+/// login with the challenge token and a code at `POST /login/2fa`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TwoFactorChallengeResponse {
+    /// Always `true`; tells this body apart from a login response
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+    /// Seconds the challenge token stays valid
+    pub expires_in: u64,
+}
+
+/// Result of an endpoint that logs the user in: either the session (body +
+/// auth cookies) or, for accounts with two-factor authentication, a challenge.
+pub(crate) enum AuthStep<T> {
+    Session(T, Vec<cookie::Cookie<'static>>),
+    Challenge(TwoFactorChallengeResponse),
+}
+
+impl<T: Serialize> AuthStep<T> {
+    pub(crate) fn into_response(self) -> Result<Response, HttpError> {
+        match self {
+            AuthStep::Session(body, cookies) => {
+                let mut response = Response::json(&body)?;
+                for cookie in cookies {
+                    response.set_cookie(cookie);
+                }
+                Ok(response)
+            }
+            AuthStep::Challenge(challenge) => {
+                let mut response = Response::json(&challenge)?;
+                response.set_status(StatusCode::ACCEPTED);
+                Ok(response)
+            }
+        }
     }
-    Ok(response)
+}
+
+/// Create the second-step challenge for `user_id`.
+pub(crate) async fn two_factor_challenge(
+    state: &AppState,
+    user_id: i64,
+    remember: bool,
+) -> Result<TwoFactorChallengeResponse, ApiError> {
+    let challenge = TwoFactorService::new(state.db.clone(), state.config.two_factor.clone())
+        .create_challenge(user_id, remember)
+        .await?;
+    Ok(TwoFactorChallengeResponse {
+        two_factor_required: true,
+        challenge_token: challenge.token,
+        expires_in: challenge.expires_in,
+    })
+}
+
+/// Whether `user_id` must pass the two-factor step before getting a session.
+pub(crate) async fn requires_two_factor(state: &AppState, user_id: i64) -> Result<bool, ApiError> {
+    has_two_factor(state.db.write_conn(), user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(user_id, "Two-factor lookup failed: {:?}", e);
+            ApiError::Internal("An unexpected error occurred".to_string())
+        })
 }
 
 async fn login_inner(
     state: &AppState,
     payload: &LoginRequestBody,
-) -> Result<(LoginResponse, Vec<cookie::Cookie<'static>>), ApiError> {
+) -> Result<AuthStep<LoginResponse>, ApiError> {
     payload.validate()?;
 
     // ── WeChat captcha verification (must precede password login) ──────
@@ -181,28 +244,48 @@ async fn login_inner(
         state.config.refresh_token_expiry_seconds,
     );
 
-    let result = service
+    let outcome = service
         .login(LoginRequest {
             email: payload.email.to_lowercase(),
             password: payload.password.clone(),
             remember: payload.remember,
         })
         .await?;
+    let user_id = match &outcome {
+        LoginOutcome::LoggedIn(result) => result.user_id.clone(),
+        LoginOutcome::TwoFactorRequired { user_id, .. } => user_id.to_string(),
+    };
 
     // ── Post-login captcha-bound user check ──────────────────────────
     if let Some(captcha_user_id) = captcha_user_id {
-        if captcha_user_id.to_string() != result.user_id {
+        if captcha_user_id.to_string() != user_id {
             return Err(ApiError::BadRequest(
                 "Invalid or expired captcha code".to_string(),
             ));
         }
 
         tracing::debug!(
-            user_id = %result.user_id,
+            user_id = %user_id,
             "WeChat captcha verified for email+password login"
         );
     }
 
+    match outcome {
+        LoginOutcome::LoggedIn(result) => {
+            let cookies = session_cookies(state, &result)?;
+            Ok(AuthStep::Session(result, cookies))
+        }
+        LoginOutcome::TwoFactorRequired { user_id, remember } => Ok(AuthStep::Challenge(
+            two_factor_challenge(state, user_id, remember).await?,
+        )),
+    }
+}
+
+/// Auth cookies for a session issued by [`AuthService`].
+fn session_cookies(
+    state: &AppState,
+    result: &LoginResponse,
+) -> Result<Vec<cookie::Cookie<'static>>, ApiError> {
     let jwt_max_age = result.expires_in;
     let jwt_expires_at_unix = unix_timestamp_from_now(jwt_max_age)?;
 
@@ -221,7 +304,7 @@ async fn login_inner(
         )
     };
 
-    let cookies = vec![
+    Ok(vec![
         token_cookie(
             JWT_COOKIE,
             &result.token,
@@ -234,9 +317,297 @@ async fn login_inner(
             result.refresh_expires_in.max(jwt_max_age),
             state.config.cookie_secure,
         ),
-    ];
+    ])
+}
+
+/// Second login step for accounts with two-factor authentication
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+pub struct TwoFactorLoginRequestBody {
+    /// Token from the `202` response of the password step
+    #[validate(length(min = 1, max = 128, message = "challenge_token is required"))]
+    challenge_token: String,
+
+    /// 6-digit code from the authenticator app, or a recovery code
+    #[validate(length(min = 1, max = 32, message = "code is required"))]
+    code: String,
+}
+
+/// Two-factor login endpoint — `POST /api/public/auth/login/2fa`.
+///
+/// Consumes the challenge and issues the session the password step withheld,
+/// with the same cookies as [`login`].
+pub async fn login_two_factor(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state: AppState = extract_state(&req)?;
+    let payload: TwoFactorLoginRequestBody = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+
+    login_two_factor_inner(&state, &payload)
+        .await?
+        .into_response()
+}
+
+async fn login_two_factor_inner(
+    state: &AppState,
+    payload: &TwoFactorLoginRequestBody,
+) -> Result<AuthStep<LoginResponse>, ApiError> {
+    payload.validate()?;
+
+    let outcome = TwoFactorService::new(state.db.clone(), state.config.two_factor.clone())
+        .complete_challenge(&payload.challenge_token, &payload.code)
+        .await?;
+
+    let service = AuthService::new(
+        state.db.clone(),
+        state.config.jwt_secret.clone(),
+        state.config.jwt_expiry_seconds,
+        state.config.jwt_remember_expiry_seconds,
+        state.config.refresh_token_expiry_seconds,
+    );
+    let result = service
+        .complete_login(outcome.user_id, outcome.remember)
+        .await?;
+    let cookies = session_cookies(state, &result)?;
+    Ok(AuthStep::Session(result, cookies))
+}
+
+/// Passkey login: the browser's response to `navigator.credentials.get()`
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PasskeyLoginRequestBody {
+    /// `credential.toJSON()` of the assertion
+    credential: AuthenticationCredential,
+
+    #[serde(default)]
+    remember: bool,
+}
+
+/// Passkey login options — `POST /api/public/auth/passkey/options`.
+///
+/// Returns a fresh challenge for `navigator.credentials.get()`; no user is named, the
+/// authenticator offers the passkeys it holds for this site.
+pub async fn passkey_login_options(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state: AppState = extract_state(&req)?;
+    let options = WebAuthnService::new(state.db.clone(), state.config.webauthn.clone())
+        .authentication_options()
+        .await
+        .map_err(ApiError::from)?;
+    Response::json(&options)
+}
+
+/// Passkey login endpoint — `POST /api/public/auth/passkey/login`.
+///
+/// Issues the same session and cookies as [`login`]. An account with two-factor
+/// authentication still gets a challenge unless the authenticator verified the user
+/// (PIN or biometrics), which already makes the passkey two factors.
+pub async fn passkey_login(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state: AppState = extract_state(&req)?;
+    let payload: PasskeyLoginRequestBody = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+
+    passkey_login_inner(&state, &payload).await?.into_response()
+}
+
+async fn passkey_login_inner(
+    state: &AppState,
+    payload: &PasskeyLoginRequestBody,
+) -> Result<AuthStep<LoginResponse>, ApiError> {
+    let login = WebAuthnService::new(state.db.clone(), state.config.webauthn.clone())
+        .finish_authentication(&payload.credential)
+        .await?;
+
+    if !login.user_verified && requires_two_factor(state, login.user_id).await? {
+        return Ok(AuthStep::Challenge(
+            two_factor_challenge(state, login.user_id, payload.remember).await?,
+        ));
+    }
+
+    let service = AuthService::new(
+        state.db.clone(),
+        state.config.jwt_secret.clone(),
+        state.config.jwt_expiry_seconds,
+        state.config.jwt_remember_expiry_seconds,
+        state.config.refresh_token_expiry_seconds,
+    );
+    let result = service
+        .complete_login(login.user_id, payload.remember)
+        .await?;
+    tracing::info!(user_id = login.user_id, "Logged in with a passkey");
+    let cookies = session_cookies(state, &result)?;
+    Ok(AuthStep::Session(result, cookies))
+}
+
+/// Cookie carrying the OpenID Connect flow token between authorize and callback.
+pub(crate) const OIDC_FLOW_COOKIE: &str = "webshelf_oidc";
+const OIDC_COOKIE_PATH: &str = "/api/public/auth/oidc";
 
-    Ok((result, cookies))
+fn oidc_service(state: &AppState) -> OidcService {
+    OidcService::new(
+        state.db.clone(),
+        state.cache.clone(),
+        state.config.oidc.clone(),
+        &state.config.jwt_secret,
+    )
+}
+
+/// Flow cookie; `Lax` because the provider's redirect back is a cross-site navigation.
+fn oidc_flow_cookie(value: &str, max_age_secs: u64, secure: bool) -> cookie::Cookie<'static> {
+    let mut c = cookie::Cookie::new(OIDC_FLOW_COOKIE, value.to_owned());
+    c.set_path(OIDC_COOKIE_PATH);
+    c.set_max_age(cookie::time::Duration::seconds(max_age_secs as i64));
+    c.set_http_only(true);
+    c.set_same_site(cookie::SameSite::Lax);
+    if secure {
+        c.set_secure(true);
+    }
+    c
+}
+
+fn redirect(location: &str) -> Response {
+    let mut response = Response::with_status(StatusCode::FOUND);
+    response.insert_header("location", location);
+    response.insert_header("cache-control", "no-store");
+    response
+}
+
+/// Send the browser back to the login page with `#<key>=<value>`.
+fn login_page_redirect(state: &AppState, key: &str, value: &str) -> Response {
+    let value: String = url::form_urlencoded::byte_serialize(value.as_bytes()).collect();
+    redirect(&format!(
+        "{}#{key}={value}",
+        state.config.oidc.login_redirect
+    ))
+}
+
+/// Login providers offered on the login page
+#[derive(Serialize, JsonSchema)]
+pub struct OidcProvidersResponse {
+    pub providers: Vec<OidcProviderInfo>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct OidcAuthorizeQuery {
+    /// Keep the session after the browser closes (same as `remember` of `/login`)
+    #[serde(default)]
+    pub remember: bool,
+}
+
+/// Enabled OpenID Connect providers — `GET /api/public/auth/oidc/providers`.
+pub async fn oidc_providers(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state: AppState = extract_state(&req)?;
+    Response::json(&OidcProvidersResponse {
+        providers: oidc_service(&state).providers(),
+    })
+}
+
+/// Start logging in with a provider — `GET /api/public/auth/oidc/{provider}/authorize`.
+///
+/// A browser navigation: redirects to the provider, or back to the login page with
+/// `#oidc_error=<code>`.
+pub async fn oidc_authorize(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state: AppState = extract_state(&req)?;
+    let provider: String = req
+        .parse_param("provider")
+        .map_err(|_| HttpError::bad_request("Invalid or missing provider"))?;
+    let query: OidcAuthorizeQuery = req.parse_query().unwrap_or_default();
+
+    match oidc_service(&state).begin(&provider, query.remember).await {
+        Ok(flow) => {
+            let mut response = redirect(&flow.url);
+            response.set_cookie(oidc_flow_cookie(
+                &flow.flow_token,
+                state.config.oidc.flow_ttl_secs,
+                state.config.cookie_secure,
+            ));
+            Ok(response)
+        }
+        Err(e) => Ok(oidc_error_redirect(&state, &provider, e)),
+    }
+}
+
+/// Back to the login page with `#oidc_error=<code>`.
+fn oidc_error_redirect(state: &AppState, provider: &str, e: OidcError) -> Response {
+    match &e {
+        OidcError::Internal(inner) => tracing::error!(provider, "OIDC login failed: {:?}", inner),
+        OidcError::Provider(_) => tracing::warn!(provider, "OIDC login failed: {}", e),
+        _ => tracing::info!(provider, "OIDC login rejected: {}", e),
+    }
+    login_page_redirect(state, "oidc_error", e.code())
+}
+
+/// Provider redirect back — `GET /api/public/auth/oidc/{provider}/callback`.
+///
+/// Sets the same cookies as [`login`] and redirects to `success_redirect`. Accounts with
+/// two-factor authentication land on the login page with `#two_factor=<challenge>`.
+pub async fn oidc_callback(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state: AppState = extract_state(&req)?;
+    let provider: String = req
+        .parse_param("provider")
+        .map_err(|_| HttpError::bad_request("Invalid or missing provider"))?;
+    let callback: OidcCallback = req.parse_query().unwrap_or_default();
+    let flow_token = req.cookie(OIDC_FLOW_COOKIE);
+
+    let mut response = match oidc_callback_inner(&state, &provider, &callback, flow_token).await {
+        Ok(AuthStep::Session((), cookies)) => {
+            let mut response = redirect(&state.config.oidc.success_redirect);
+            for cookie in cookies {
+                response.set_cookie(cookie);
+            }
+            response
+        }
+        Ok(AuthStep::Challenge(challenge)) => {
+            login_page_redirect(&state, "two_factor", &challenge.challenge_token)
+        }
+        Err(e) => oidc_error_redirect(&state, &provider, e),
+    };
+    // 流程令牌只能使用一次
+    response.set_cookie(oidc_flow_cookie("", 0, state.config.cookie_secure));
+    Ok(response)
+}
+
+async fn oidc_callback_inner(
+    state: &AppState,
+    provider: &str,
+    callback: &OidcCallback,
+    flow_token: Option<String>,
+) -> Result<AuthStep<()>, OidcError> {
+    let login = oidc_service(state)
+        .finish(provider, callback, flow_token.as_deref())
+        .await?;
+    let internal = |e: ApiError| OidcError::Internal(anyhow::Error::new(e));
+
+    // 提供方的认证不算第二因素，开启了 2FA 的账号仍需验证码
+    if requires_two_factor(state, login.user_id)
+        .await
+        .map_err(internal)?
+    {
+        let challenge = two_factor_challenge(state, login.user_id, login.remember)
+            .await
+            .map_err(internal)?;
+        return Ok(AuthStep::Challenge(challenge));
+    }
+
+    let service = AuthService::new(
+        state.db.clone(),
+        state.config.jwt_secret.clone(),
+        state.config.jwt_expiry_seconds,
+        state.config.jwt_remember_expiry_seconds,
+        state.config.refresh_token_expiry_seconds,
+    );
+    let result = service
+        .complete_login(login.user_id, login.remember)
+        .await
+        .map_err(|e| internal(e.into()))?;
+    tracing::info!(
+        user_id = login.user_id,
+        provider,
+        created = login.created,
+        "Logged in with OpenID Connect"
+    );
+    let cookies = session_cookies(state, &result).map_err(internal)?;
+    Ok(AuthStep::Session((), cookies))
 }
 
 /// Compute the Unix timestamp `seconds_from_now` seconds in the future.
@@ -252,7 +623,7 @@ pub(crate) fn unix_timestamp_from_now(seconds_from_now: u64) -> Result<i64, ApiE
 }
 
 /// Register request with validation
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
 pub struct RegisterRequestBody {
     #[validate(email(message = "must be a valid email address"))]
     email: String,
@@ -280,7 +651,7 @@ pub struct RegisterRequestBody {
 }
 
 /// Register response
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RegisterResponse {
     message: String,
     user_id: String,
@@ -297,21 +668,25 @@ pub async fn register(mut req: crate::ServerRequest) -> Result<Response, HttpErr
         .await
         .map_err(HttpError::bad_request)?;
 
-    // Manually convert ValidationErrors to HttpError (orphan rule prevents direct From impl)
     payload
         .validate()
         .inspect_err(|e| {
             tracing::warn!("Registration validation failed: {:?}", e);
         })
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+        .map_err(to_http)?;
 
     // 二次密码校验：确认前端传入的 password_confirm 与 password 一致
     if payload.password != payload.password_confirm {
-        return Err(HttpError::bad_request("passwords do not match"));
+        return Err(ApiError::invalid_field(
+            "password_confirm",
+            "mismatch",
+            "passwords do not match",
+        )
+        .into());
     }
 
     // check_password_strength returns ApiError; ? converts to HttpError via From<ApiError> for HttpError
-    check_password_strength(&payload.password)?;
+    check_password_strength("password", &payload.password)?;
 
     // Normalize email to lowercase once at the entry point to avoid
     // redundant normalization in multiple downstream call sites.
@@ -330,7 +705,7 @@ pub async fn register(mut req: crate::ServerRequest) -> Result<Response, HttpErr
             "user",
         )
         .await
-        .map_err(|e| HttpError::from(ApiError::from(e)))?;
+        .map_err(to_http)?;
 
     let verification = VerificationService::new(state.db.clone(), state.email.clone());
     let (message, email_verified) = match verification.send_verification_email(&email).await {
@@ -346,12 +721,13 @@ pub async fn register(mut req: crate::ServerRequest) -> Result<Response, HttpErr
             ("User registered successfully".to_string(), true)
         }
         Err(e) => {
-            // When SMTP is configured but the send fails (transient network error,
-            // etc.), auto-verify the user as a fallback instead of deleting the
-            // account.  This avoids orphan accounts that can neither log in
-            // (email_verified=false) nor re-register (email already taken), and
-            // eliminates the crash window between insert and manual DELETE.
-            tracing::error!("Failed to send verification email: {:?}", e);
+            // The email is sent by the job queue (which retries SMTP failures), so
+            // an error here means the job could not be queued.  Auto-verify the
+            // user as a fallback instead of deleting the account.  This avoids
+            // orphan accounts that can neither log in (email_verified=false) nor
+            // re-register (email already taken), and eliminates the crash window
+            // between insert and manual DELETE.
+            tracing::error!("Failed to queue verification email: {:?}", e);
             if let Err(verify_err) = verification.auto_verify(&email).await {
                 tracing::error!(
                     "Failed to auto-verify after email failure: {:?}",
@@ -374,7 +750,7 @@ pub async fn register(mut req: crate::ServerRequest) -> Result<Response, HttpErr
 }
 
 /// Verify email request
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
 pub struct VerifyEmailRequestBody {
     #[validate(email(message = "must be a valid email address"))]
     email: String,
@@ -384,7 +760,7 @@ pub struct VerifyEmailRequestBody {
 }
 
 /// Verify email response
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct VerifyEmailResponse {
     message: String,
 }
@@ -397,10 +773,7 @@ pub async fn verify_email(mut req: crate::ServerRequest) -> Result<Response, Htt
         .await
         .map_err(HttpError::bad_request)?;
 
-    // Manually convert ValidationErrors to HttpError
-    payload
-        .validate()
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    payload.validate().map_err(to_http)?;
 
     // Reject non-numeric codes early to avoid wasting Argon2 CPU
     // on obviously invalid inputs.
@@ -416,7 +789,7 @@ pub async fn verify_email(mut req: crate::ServerRequest) -> Result<Response, Htt
     service
         .verify_email(&email, &payload.code)
         .await
-        .map_err(|e| HttpError::from(ApiError::from(e)))?;
+        .map_err(to_http)?;
 
     Response::json(&VerifyEmailResponse {
         message: "Email verified successfully".to_string(),
@@ -424,14 +797,14 @@ pub async fn verify_email(mut req: crate::ServerRequest) -> Result<Response, Htt
 }
 
 /// Resend verification code request
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
 pub struct ResendCodeRequestBody {
     #[validate(email(message = "must be a valid email address"))]
     email: String,
 }
 
 /// Resend code response
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ResendCodeResponse {
     message: String,
 }
@@ -444,10 +817,7 @@ pub async fn resend_code(mut req: crate::ServerRequest) -> Result<Response, Http
         .await
         .map_err(HttpError::bad_request)?;
 
-    // Manually convert ValidationErrors to HttpError
-    payload
-        .validate()
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    payload.validate().map_err(to_http)?;
 
     // Normalize email to lowercase at the entry point.
     let email = payload.email.to_lowercase();
@@ -467,7 +837,7 @@ pub async fn resend_code(mut req: crate::ServerRequest) -> Result<Response, Http
                 email
             );
         } else {
-            return Err(HttpError::from(ApiError::from(e)));
+            return Err(to_http(e));
         }
     }
 
@@ -484,13 +854,13 @@ pub async fn resend_code(mut req: crate::ServerRequest) -> Result<Response, Http
 /// that attackers cannot distinguish registered from unregistered emails
 /// by sending a second request within the cooldown window. SMTP
 /// configuration failures surface as 503 only for registered emails.
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
 pub struct ForgotPasswordRequestBody {
     #[validate(email(message = "must be a valid email address"))]
     email: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ForgotPasswordResponse {
     message: String,
 }
@@ -502,10 +872,7 @@ pub async fn forgot_password(mut req: crate::ServerRequest) -> Result<Response,
         .await
         .map_err(HttpError::bad_request)?;
 
-    // Manually convert ValidationErrors to HttpError
-    payload
-        .validate()
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    payload.validate().map_err(to_http)?;
 
     let email = payload.email.to_lowercase();
 
@@ -524,7 +891,7 @@ pub async fn forgot_password(mut req: crate::ServerRequest) -> Result<Response,
                 email
             );
         } else {
-            return Err(HttpError::from(ApiError::from(e)));
+            return Err(to_http(e));
         }
     }
 
@@ -537,8 +904,9 @@ pub async fn forgot_password(mut req: crate::ServerRequest) -> Result<Response,
 /// Reset-password request — consume the verification code sent in the
 /// reset email and replace the user's password.
 ///
-/// On success, returns a fresh JWT so the user is auto-logged-in.
-#[derive(Debug, Deserialize, Validate)]
+/// On success, returns a fresh JWT so the user is auto-logged-in — unless the
+/// account has two-factor authentication, which gets a `202` challenge instead.
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
 pub struct ResetPasswordRequestBody {
     #[validate(email(message = "must be a valid email address"))]
     email: String,
@@ -550,7 +918,7 @@ pub struct ResetPasswordRequestBody {
     new_password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ResetPasswordResponse {
     message: String,
     /// Fresh JWT issued after the password is replaced.
@@ -568,22 +936,17 @@ pub async fn reset_password(mut req: crate::ServerRequest) -> Result<Response, H
         .await
         .map_err(HttpError::bad_request)?;
 
-    let result = reset_password_inner(&state, &payload).await?;
-    let (resp, cookies) = result;
-
-    let mut response = Response::json(&resp)?;
-    for cookie in cookies {
-        response.set_cookie(cookie);
-    }
-    Ok(response)
+    reset_password_inner(&state, &payload)
+        .await?
+        .into_response()
 }
 
 async fn reset_password_inner(
     state: &AppState,
     payload: &ResetPasswordRequestBody,
-) -> Result<(ResetPasswordResponse, Vec<cookie::Cookie<'static>>), ApiError> {
+) -> Result<AuthStep<ResetPasswordResponse>, ApiError> {
     payload.validate()?;
-    check_password_strength(&payload.new_password)?;
+    check_password_strength("new_password", &payload.new_password)?;
 
     // Reject non-numeric codes early to avoid wasting Argon2 CPU
     // on obviously invalid inputs.
@@ -598,6 +961,18 @@ async fn reset_password_inner(
         .reset_password(&email, &payload.code, &payload.new_password)
         .await?;
 
+    // The emailed code proves control of the mailbox only — accounts with
+    // two-factor authentication still need a code before getting a session.
+    if requires_two_factor(state, outcome.user_id).await? {
+        tracing::info!(
+            "Password reset completed for user {}, two-factor code required",
+            outcome.user_id
+        );
+        return Ok(AuthStep::Challenge(
+            two_factor_challenge(state, outcome.user_id, false).await?,
+        ));
+    }
+
     let new_token = crate::middlewares::generate_token(
         &outcome.user_id.to_string(),
         &outcome.role,
@@ -631,7 +1006,7 @@ async fn reset_password_inner(
     ];
 
     tracing::info!("Password reset completed for user {}", outcome.user_id);
-    Ok((
+    Ok(AuthStep::Session(
         ResetPasswordResponse {
             message: "Password reset successfully".to_string(),
             token: new_token,
@@ -649,7 +1024,7 @@ async fn reset_password_inner(
 /// The refresh token is read from the `webshelf_refresh` httpOnly cookie.
 /// On success, the old refresh token is deleted and a new one is issued
 /// (rotation), and new cookies are set.
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RefreshResponse {
     pub token: String,
     pub token_type: String,
@@ -775,7 +1150,7 @@ async fn refresh_inner(
 /// to delete, so a frontend can always log itself out even after its
 /// in-memory JWT has expired. Idempotent: missing cookie or already-revoked
 /// row both still return 200 and clear cookies.
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct LogoutResponse {
     pub message: String,
 }
@@ -0,0 +1,32 @@
+//! OpenAPI document and API reference UI handlers.
+
+use std::sync::OnceLock;
+
+use crate::AppState;
+use crate::handlers::helpers::extract_state;
+use webshelf_runtime::{CspNonce, HttpError, RequestContext, Response, openapi};
+
+/// The document is static for a given build — render it once.
+static OPENAPI_JSON: OnceLock<serde_json::Value> = OnceLock::new();
+
+/// GET `[openapi].path` — the OpenAPI 3.1 document.
+pub async fn openapi_json(_req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let doc = OPENAPI_JSON.get_or_init(|| crate::routes::openapi_doc().to_json());
+    Response::json(doc)
+}
+
+/// GET `[openapi].ui_path` — HTML page rendering the document.
+///
+/// The page's scripts carry the per-request CSP nonce, if the security headers set one.
+pub async fn docs_ui(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state: AppState = extract_state(&req)?;
+    let nonce = req.get_data_ref::<CspNonce>().map(CspNonce::as_str);
+    let mut response = Response::new();
+    response.set_content_type("text/html; charset=utf-8");
+    response.set_text_body(openapi::ui_html(
+        "webshelf API",
+        &state.config.openapi.path,
+        nonce,
+    ));
+    Ok(response)
+}
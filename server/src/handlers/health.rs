@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+
+use http::StatusCode;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::handlers::helpers::{extract_handler_context, extract_state};
+use crate::services::health::{self, CheckStatus, ComponentHealth};
+use crate::utils::config::HealthComponent;
+use webshelf_runtime::{HttpError, Response};
+
+/// Liveness probe response
+#[derive(Serialize, JsonSchema)]
+pub struct LivenessResponse {
+    status: &'static str,
+}
+
+/// Readiness probe response
+#[derive(Serialize, JsonSchema)]
+pub struct ReadinessResponse {
+    /// `ready`, `not_ready` (a required dependency is down) or `draining` (graceful shutdown)
+    status: &'static str,
+    /// Per-component status; omitted while draining
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    checks: BTreeMap<HealthComponent, CheckStatus>,
+}
+
+/// Detailed health report (admin only)
+#[derive(Serialize, JsonSchema)]
+pub struct HealthReportResponse {
+    /// Same values as the readiness probe
+    status: &'static str,
+    version: &'static str,
+    /// Components whose `down` status fails readiness (`[health].readiness_requires`)
+    readiness_requires: Vec<HealthComponent>,
+    components: BTreeMap<HealthComponent, ComponentHealth>,
+}
+
+fn with_status(mut resp: Response, ok: bool) -> Response {
+    if !ok {
+        resp.set_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    resp
+}
+
+/// Liveness probe: the process is up and serving. Touches no dependency, so an outage of
+/// the database or Redis never gets the pod restarted.
+pub async fn liveness_check(_req: crate::ServerRequest) -> Result<Response, HttpError> {
+    Response::json(&LivenessResponse { status: "alive" })
+}
+
+/// Readiness probe: 503 as soon as graceful shutdown starts, or while a dependency listed in
+/// `[health].readiness_requires` is down.
+pub async fn readiness_check(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state = extract_state(&req)?;
+    if !state.shutdown.is_ready() {
+        let resp = Response::json(&ReadinessResponse {
+            status: "draining",
+            checks: BTreeMap::new(),
+        })?;
+        return Ok(with_status(resp, false));
+    }
+
+    let report = health::check(&state).await;
+    let resp = Response::json(&ReadinessResponse {
+        status: if report.ready { "ready" } else { "not_ready" },
+        checks: report.statuses(),
+    })?;
+    Ok(with_status(resp, report.ready))
+}
+
+/// Detailed dependency report: latencies, errors, replica circuit breakers, worker id.
+pub async fn admin_health(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, _auth_user) = extract_handler_context(&req)?;
+    let report = health::check(&state).await;
+    let ready = report.ready && state.shutdown.is_ready();
+    let status = match (state.shutdown.is_ready(), report.ready) {
+        (false, _) => "draining",
+        (true, true) => "ready",
+        (true, false) => "not_ready",
+    };
+    let resp = Response::json(&HealthReportResponse {
+        status,
+        version: env!("CARGO_PKG_VERSION"),
+        readiness_requires: state.config.health.readiness_requires.clone(),
+        components: report.components,
+    })?;
+    Ok(with_status(resp, ready))
+}
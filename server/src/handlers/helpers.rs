@@ -13,6 +13,7 @@
 
 use crate::AppState;
 use crate::middlewares::AuthUser;
+use crate::utils::error::ApiError;
 use webshelf_runtime::{HttpError, RequestContext};
 
 /// Extract `AppState` from a request context.
@@ -29,9 +30,33 @@ pub fn extract_state(req: &crate::ServerRequest) -> Result<AppState, HttpError>
 /// Intended for **authenticated** handlers. Returns:
 /// - `HttpError::internal` if AppState is missing (should never happen at runtime)
 /// - `HttpError::unauthorized` if AuthUser is missing (request did not pass auth middleware)
+/// - `HttpError::forbidden` (`insufficient_scope`) for delegated tokens such as OAuth access
+///   tokens — endpoints open to them use [`extract_scoped_context`] instead
 pub fn extract_handler_context(
     req: &crate::ServerRequest,
 ) -> Result<(AppState, AuthUser), HttpError> {
+    let (state, auth_user) = extract_context(req)?;
+    if auth_user.scopes.is_some() {
+        return Err(insufficient_scope());
+    }
+    Ok((state, auth_user))
+}
+
+/// Like [`extract_handler_context`], but also accepts delegated tokens granted `scope`.
+///
+/// First-party sessions are not restricted by scopes.
+pub fn extract_scoped_context(
+    req: &crate::ServerRequest,
+    scope: &str,
+) -> Result<(AppState, AuthUser), HttpError> {
+    let (state, auth_user) = extract_context(req)?;
+    if !auth_user.has_scope(scope) {
+        return Err(insufficient_scope());
+    }
+    Ok((state, auth_user))
+}
+
+fn extract_context(req: &crate::ServerRequest) -> Result<(AppState, AuthUser), HttpError> {
     let state: AppState = req
         .get_data()
         .ok_or_else(|| HttpError::internal("AppState not available"))?;
@@ -40,3 +65,25 @@ pub fn extract_handler_context(
         .ok_or_else(|| HttpError::unauthorized("Authentication required"))?;
     Ok((state, auth_user))
 }
+
+/// The authenticated user's numeric ID.
+///
+/// The auth middleware only admits tokens for existing users, so a subject that does not
+/// parse is a server bug and maps to 500.
+pub fn self_id(auth_user: &AuthUser) -> Result<i64, HttpError> {
+    auth_user.user_id.parse().map_err(|_| {
+        tracing::error!("Invalid user ID in auth token: {}", auth_user.user_id);
+        HttpError::internal("An unexpected error occurred")
+    })
+}
+
+/// Convert a typed service error into an `HttpError` via its [`ApiError`] mapping
+/// (for use with `map_err`).
+pub fn to_http<E: Into<ApiError>>(e: E) -> HttpError {
+    HttpError::from(e.into())
+}
+
+fn insufficient_scope() -> HttpError {
+    HttpError::forbidden("The access token does not grant access to this endpoint")
+        .with_code("insufficient_scope")
+}
@@ -0,0 +1,57 @@
+//! Linked external accounts (OpenID Connect identities).
+//!
+//! Users list and unlink them under `/api/users/me/identities`; linking happens when
+//! logging in with a provider at `/api/public/auth/oidc/{provider}/authorize` in
+//! [`crate::handlers::auth`].
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::AppState;
+use crate::handlers::helpers::{extract_handler_context, self_id, to_http};
+use crate::services::oidc::{IdentityInfo, OidcService};
+use webshelf_runtime::{HttpError, RequestContext, Response};
+
+#[derive(Serialize, JsonSchema)]
+pub struct IdentityListResponse {
+    pub identities: Vec<IdentityInfo>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DeleteIdentityResponse {
+    message: String,
+}
+
+fn service(state: &AppState) -> OidcService {
+    OidcService::new(
+        state.db.clone(),
+        state.cache.clone(),
+        state.config.oidc.clone(),
+        &state.config.jwt_secret,
+    )
+}
+
+/// External accounts linked to the current user
+pub async fn list_identities(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let user_id = self_id(&auth_user)?;
+    let identities = service(&state).identities(user_id).await.map_err(to_http)?;
+    Response::json(&IdentityListResponse { identities })
+}
+
+/// Unlink one of the current user's external accounts
+pub async fn delete_identity(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let identity_id: i64 = req
+        .parse_param("id")
+        .map_err(|_| HttpError::bad_request("Invalid or missing identity ID"))?;
+
+    let user_id = self_id(&auth_user)?;
+    service(&state)
+        .unlink(user_id, identity_id)
+        .await
+        .map_err(to_http)?;
+    Response::json(&DeleteIdentityResponse {
+        message: "Account unlinked".to_string(),
+    })
+}
@@ -0,0 +1,48 @@
+use http::StatusCode;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::handlers::helpers::extract_handler_context;
+use crate::services::scheduler::{JobStatus, Scheduler, SchedulerError};
+use crate::utils::error::ApiError;
+use webshelf_runtime::{HttpError, RequestContext, Response};
+
+/// Scheduled jobs response
+#[derive(Serialize, JsonSchema)]
+pub struct JobListResponse {
+    pub jobs: Vec<JobStatus>,
+}
+
+fn to_http(err: SchedulerError) -> HttpError {
+    HttpError::from(ApiError::from(err))
+}
+
+/// List the built-in jobs with their schedule and last run.
+pub async fn list_jobs(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, _auth_user) = extract_handler_context(&req)?;
+    let jobs = Scheduler::new(state)
+        .map_err(to_http)?
+        .list()
+        .await
+        .map_err(to_http)?;
+    Response::json(&JobListResponse { jobs })
+}
+
+/// Start a run of one job now; it executes in the background on this instance.
+pub async fn run_job(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let name: String = req
+        .parse_param("name")
+        .map_err(|_| HttpError::bad_request("Invalid or missing job name"))?;
+
+    let status = Scheduler::new(state)
+        .map_err(to_http)?
+        .trigger(&name)
+        .await
+        .map_err(to_http)?;
+    tracing::info!(user_id = %auth_user.user_id, job = %name, "Job run triggered manually");
+
+    let mut resp = Response::json(&status)?;
+    resp.set_status(StatusCode::ACCEPTED);
+    Ok(resp)
+}
@@ -0,0 +1,96 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+use validator::Validate;
+
+use crate::handlers::helpers::{extract_handler_context, to_http};
+use crate::middlewares::AuthUser;
+use crate::services::log_level::{LogLevelService, LogLevelStatus};
+use webshelf_runtime::{HttpError, RequestContext, Response};
+
+/// Set log filter request
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct SetLogLevelRequest {
+    /// `EnvFilter` directives, e.g. `debug` or `info,webshelf_server::services=trace`
+    pub filter: String,
+    /// Revert to the startup filter after this many seconds; omit to keep until reset
+    #[serde(default)]
+    #[validate(range(min = 1, max = 604800, message = "ttl_secs must be 1-604800 (7 days)"))]
+    pub ttl_secs: Option<u64>,
+}
+
+/// The endpoint changes what every request on the instance logs, so it is reserved to the
+/// `system` role (the admin guard also lets `admin` through).
+fn require_system(auth_user: &AuthUser) -> Result<(), HttpError> {
+    if auth_user.role == "system" {
+        Ok(())
+    } else {
+        Err(HttpError::forbidden("System role required"))
+    }
+}
+
+fn respond(
+    result: Result<LogLevelStatus, crate::services::LogLevelError>,
+) -> Result<Response, HttpError> {
+    let status = result.map_err(to_http)?;
+    Response::json(&status)
+}
+
+/// Current log filter of this instance.
+pub async fn get_log_level(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (_state, auth_user) = extract_handler_context(&req)?;
+    require_system(&auth_user)?;
+    respond(LogLevelService::global().map(|service| service.status()))
+}
+
+/// Replace the log filter of this instance, optionally for `ttl_secs` only.
+pub async fn set_log_level(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (_state, auth_user) = extract_handler_context(&req)?;
+    require_system(&auth_user)?;
+    let payload: SetLogLevelRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+    payload.validate().map_err(to_http)?;
+
+    let ttl = payload.ttl_secs.map(Duration::from_secs);
+    let result = LogLevelService::global().and_then(|service| service.set(&payload.filter, ttl));
+    if let Ok(status) = &result {
+        tracing::warn!(
+            user_id = %auth_user.user_id,
+            filter = %status.filter,
+            ttl_secs = payload.ttl_secs,
+            "Log filter changed at runtime"
+        );
+    }
+    respond(result)
+}
+
+/// Restore the startup log filter of this instance.
+pub async fn reset_log_level(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (_state, auth_user) = extract_handler_context(&req)?;
+    require_system(&auth_user)?;
+    let result = LogLevelService::global().and_then(|service| service.reset());
+    if let Ok(status) = &result {
+        tracing::warn!(user_id = %auth_user.user_id, filter = %status.filter, "Log filter reset");
+    }
+    respond(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ttl_secs_is_bounded() {
+        let request = |ttl_secs| SetLogLevelRequest {
+            filter: "debug".into(),
+            ttl_secs,
+        };
+        assert!(request(None).validate().is_ok());
+        assert!(request(Some(600)).validate().is_ok());
+        assert!(request(Some(0)).validate().is_err());
+        assert!(request(Some(604_801)).validate().is_err());
+        assert!(request(Some(u64::MAX)).validate().is_err());
+    }
+}
@@ -0,0 +1,11 @@
+//! Prometheus scrape endpoint.
+
+use webshelf_runtime::{HttpError, Response, metrics};
+
+/// GET `[metrics].path` — all metric families in the OpenMetrics text format.
+pub async fn metrics_export(_req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let mut response = Response::new();
+    response.set_content_type(metrics::CONTENT_TYPE);
+    response.set_text_body(metrics::render());
+    Ok(response)
+}
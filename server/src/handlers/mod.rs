@@ -1,6 +1,17 @@
 pub mod api;
+pub mod api_token;
 pub mod auth;
+pub mod docs;
+pub mod health;
 pub mod helpers;
+pub mod identity;
+pub mod jobs;
+pub mod log_level;
+pub mod metrics;
+pub mod oauth;
+pub mod passkey;
+pub mod queue;
+pub mod two_factor;
 pub mod wechat;
 
 pub use api::{
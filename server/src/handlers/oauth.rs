@@ -0,0 +1,269 @@
+//! OAuth 2.0 / OpenID Connect authorization server endpoints.
+//!
+//! - Public, under `/api/public/oauth`: discovery, JWKS, the authorization endpoint (which
+//!   sends the browser to the web app's consent page) and the token endpoint.
+//! - Session only, under `/api/oauth`: the consent page's details and decision calls, plus
+//!   userinfo (which also accepts access tokens with the `openid` scope).
+//! - Admin, under `/api/admin/oauth/clients`: client registration.
+
+use http::StatusCode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::handlers::helpers::{
+    extract_handler_context, extract_scoped_context, extract_state, self_id, to_http,
+};
+use crate::services::oauth::{
+    AuthorizeParams, ClientInfo, ClientSettings, OAuthError, OAuthService, TokenParams,
+    basic_credentials,
+};
+use webshelf_runtime::{HttpError, RequestContext, Response};
+
+/// A pending authorization request, identified by the `request` parameter of the consent page.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AuthorizationRequest {
+    pub request: String,
+}
+
+/// The user's answer on the consent page.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AuthorizationDecisionRequest {
+    pub request: String,
+    /// True to allow, false to deny
+    pub approve: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AuthorizationDecisionResponse {
+    /// Client redirect URI with the authorization code, or `error=access_denied`
+    pub redirect_to: String,
+}
+
+/// Register a client.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateOAuthClientRequest {
+    #[serde(flatten)]
+    pub settings: ClientSettings,
+    /// Server-side app that can keep a secret (default: true); false for browser and native
+    /// apps
+    #[serde(default = "default_confidential")]
+    pub confidential: bool,
+}
+
+fn default_confidential() -> bool {
+    true
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct OAuthClientListResponse {
+    pub clients: Vec<ClientInfo>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DeleteOAuthClientResponse {
+    message: String,
+}
+
+/// Error body of the token endpoint (RFC 6749 §5.2).
+#[derive(Serialize, JsonSchema)]
+pub struct TokenErrorResponse {
+    pub error: &'static str,
+    pub error_description: String,
+}
+
+fn service(state: &AppState) -> OAuthService {
+    OAuthService::new(
+        state.db.clone(),
+        state.config.oauth.clone(),
+        &state.config.jwt_secret,
+    )
+}
+
+fn no_store(mut response: Response) -> Response {
+    response.insert_header("cache-control", "no-store");
+    response
+}
+
+/// OpenID Provider metadata — `GET /api/public/oauth/.well-known/openid-configuration`.
+pub async fn openid_configuration(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state = extract_state(&req)?;
+    Response::json(&service(&state).metadata())
+}
+
+/// Public signing keys — `GET /api/public/oauth/jwks`.
+pub async fn jwks(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state = extract_state(&req)?;
+    let jwks = service(&state).jwks().await.map_err(to_http)?;
+    Response::json(&jwks)
+}
+
+/// Authorization endpoint — `GET /api/public/oauth/authorize`.
+///
+/// Valid requests continue at the consent page; errors after the client and redirect URI
+/// were verified go back to the client.
+pub async fn authorize(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state = extract_state(&req)?;
+    let params: AuthorizeParams = req.parse_query().map_err(HttpError::bad_request)?;
+    let location = service(&state).authorize(&params).await.map_err(to_http)?;
+
+    let mut response = Response::with_status(StatusCode::FOUND);
+    response.insert_header("location", location);
+    Ok(no_store(response))
+}
+
+/// Token endpoint — `POST /api/public/oauth/token`.
+///
+/// Errors use the RFC 6749 body `{"error", "error_description"}` rather than problem+json.
+pub async fn token(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let state = extract_state(&req)?;
+    let basic = req.header("authorization").and_then(basic_credentials);
+    let result = match req.parse_json_or_form::<TokenParams>().await {
+        Ok(params) => service(&state).token(&params, basic.clone()).await,
+        Err(_) => Err(OAuthError::InvalidRequest("malformed token request")),
+    };
+
+    match result {
+        Ok(tokens) => {
+            let mut response = Response::json(&tokens)?;
+            response.insert_header("pragma", "no-cache");
+            Ok(no_store(response))
+        }
+        Err(e) => {
+            let status = match &e {
+                OAuthError::InvalidClient => StatusCode::UNAUTHORIZED,
+                OAuthError::Internal(e) => {
+                    tracing::error!("OAuth token endpoint error: {:?}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+                _ => StatusCode::BAD_REQUEST,
+            };
+            tracing::info!(error = e.code(), "OAuth token request rejected: {}", e);
+            let description = match &e {
+                OAuthError::Internal(_) => "An unexpected error occurred".to_string(),
+                other => other.to_string(),
+            };
+            let mut response = Response::with_status(status);
+            response.set_json_body(TokenErrorResponse {
+                error: e.code(),
+                error_description: description,
+            });
+            // 使用 Basic 认证失败时按 RFC 6749 §5.2 提示认证方式
+            if status == StatusCode::UNAUTHORIZED && basic.is_some() {
+                response.insert_header("www-authenticate", "Basic realm=\"webshelf\"");
+            }
+            Ok(no_store(response))
+        }
+    }
+}
+
+/// Consent page data — `POST /api/oauth/authorize/details`.
+pub async fn authorization_details(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: AuthorizationRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+
+    let user_id = self_id(&auth_user)?;
+    let details = service(&state)
+        .authorization_details(user_id, &payload.request)
+        .await
+        .map_err(to_http)?;
+    Response::json(&details)
+}
+
+/// Allow or deny an authorization request — `POST /api/oauth/authorize/decision`.
+pub async fn authorization_decision(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: AuthorizationDecisionRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+
+    let user_id = self_id(&auth_user)?;
+    let redirect_to = service(&state)
+        .decide(user_id, &payload.request, payload.approve)
+        .await
+        .map_err(to_http)?;
+    Response::json(&AuthorizationDecisionResponse { redirect_to })
+}
+
+/// Claims about the current user — `GET /api/oauth/userinfo`.
+pub async fn userinfo(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_scoped_context(&req, "openid")?;
+    let info = service(&state)
+        .userinfo(&auth_user)
+        .await
+        .map_err(to_http)?;
+    Ok(no_store(Response::json(&info)?))
+}
+
+/// Registered clients — `GET /api/admin/oauth/clients`.
+pub async fn list_oauth_clients(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, _auth_user) = extract_handler_context(&req)?;
+    let clients = service(&state).list_clients().await.map_err(to_http)?;
+    Response::json(&OAuthClientListResponse { clients })
+}
+
+/// Register a client — `POST /api/admin/oauth/clients`.
+pub async fn create_oauth_client(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: CreateOAuthClientRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+
+    let owner_id = self_id(&auth_user)?;
+    let created = service(&state)
+        .create_client(owner_id, &payload.settings, payload.confidential)
+        .await
+        .map_err(to_http)?;
+    Ok(no_store(Response::json(&created)?))
+}
+
+/// Replace a client's settings — `PUT /api/admin/oauth/clients/{client_id}`.
+pub async fn update_oauth_client(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, _auth_user) = extract_handler_context(&req)?;
+    let client_id = client_id_param(&req)?;
+    let settings: ClientSettings = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+
+    let client = service(&state)
+        .update_client(&client_id, &settings)
+        .await
+        .map_err(to_http)?;
+    Response::json(&client)
+}
+
+/// Delete a client — `DELETE /api/admin/oauth/clients/{client_id}`.
+pub async fn delete_oauth_client(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, _auth_user) = extract_handler_context(&req)?;
+    let client_id = client_id_param(&req)?;
+    service(&state)
+        .delete_client(&client_id)
+        .await
+        .map_err(to_http)?;
+    Response::json(&DeleteOAuthClientResponse {
+        message: "OAuth client deleted".to_string(),
+    })
+}
+
+/// Issue a new client secret — `POST /api/admin/oauth/clients/{client_id}/secret`.
+pub async fn rotate_oauth_client_secret(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, _auth_user) = extract_handler_context(&req)?;
+    let client_id = client_id_param(&req)?;
+    let secret = service(&state)
+        .rotate_secret(&client_id)
+        .await
+        .map_err(to_http)?;
+    Ok(no_store(Response::json(&secret)?))
+}
+
+fn client_id_param(req: &crate::ServerRequest) -> Result<String, HttpError> {
+    req.get_param("client_id")
+        .map(str::to_string)
+        .ok_or_else(|| HttpError::bad_request("Missing client ID"))
+}
@@ -0,0 +1,113 @@
+//! Passkey (WebAuthn) management endpoints.
+//!
+//! Users register and revoke passkeys under `/api/users/me/passkeys`; logging in with a
+//! passkey is `POST /api/public/auth/passkey/{options,login}` in [`crate::handlers::auth`].
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::AppState;
+use crate::handlers::helpers::{extract_handler_context, self_id, to_http};
+use crate::services::webauthn::{PasskeyInfo, RegistrationCredential, WebAuthnService};
+use webshelf_runtime::{HttpError, RequestContext, Response};
+
+/// Start registering a passkey; the password is re-checked.
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+pub struct PasskeyOptionsRequest {
+    #[validate(length(min = 1, message = "password is required"))]
+    password: String,
+}
+
+/// Finish registering a passkey.
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+pub struct RegisterPasskeyRequest {
+    /// Label shown in the passkey list (default: "Passkey")
+    #[serde(default)]
+    #[validate(length(max = 64, message = "name must be at most 64 characters"))]
+    name: Option<String>,
+
+    /// `credential.toJSON()` of the created credential
+    credential: RegistrationCredential,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PasskeyListResponse {
+    pub passkeys: Vec<PasskeyInfo>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DeletePasskeyResponse {
+    message: String,
+}
+
+fn service(state: &AppState) -> WebAuthnService {
+    WebAuthnService::new(state.db.clone(), state.config.webauthn.clone())
+}
+
+/// Passkeys of the current user
+pub async fn list_passkeys(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let user_id = self_id(&auth_user)?;
+    let passkeys = service(&state).list(user_id).await.map_err(to_http)?;
+    Response::json(&PasskeyListResponse { passkeys })
+}
+
+/// Creation options for `navigator.credentials.create()`
+pub async fn passkey_registration_options(
+    mut req: crate::ServerRequest,
+) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: PasskeyOptionsRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+    payload.validate().map_err(to_http)?;
+
+    let user_id = self_id(&auth_user)?;
+    let options = service(&state)
+        .registration_options(user_id, &payload.password)
+        .await
+        .map_err(to_http)?;
+    Response::json(&options)
+}
+
+/// Verify the created credential and store the passkey
+pub async fn register_passkey(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: RegisterPasskeyRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+    payload.validate().map_err(to_http)?;
+
+    let user_id = self_id(&auth_user)?;
+    let name = payload
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("Passkey");
+    let passkey = service(&state)
+        .finish_registration(user_id, name, &payload.credential)
+        .await
+        .map_err(to_http)?;
+    Response::json(&passkey)
+}
+
+/// Revoke one of the current user's passkeys
+pub async fn delete_passkey(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let passkey_id: i64 = req
+        .parse_param("id")
+        .map_err(|_| HttpError::bad_request("Invalid or missing passkey ID"))?;
+
+    let user_id = self_id(&auth_user)?;
+    service(&state)
+        .revoke(user_id, passkey_id)
+        .await
+        .map_err(to_http)?;
+    Response::json(&DeletePasskeyResponse {
+        message: "Passkey revoked".to_string(),
+    })
+}
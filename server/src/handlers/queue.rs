@@ -0,0 +1,101 @@
+use http::StatusCode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::handlers::helpers::extract_handler_context;
+use crate::services::queue::{JobFilter, JobQueue, JobRecord, QueueError, STATUSES};
+use crate::utils::error::ApiError;
+use webshelf_runtime::{HttpError, RequestContext, Response};
+
+/// Query parameters for listing queue jobs
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListQueueQuery {
+    /// `queued`, `running`, `succeeded` or `dead`
+    status: Option<String>,
+    /// Job kind, e.g. `email.verification_code`
+    kind: Option<String>,
+    #[serde(default = "default_page")]
+    page: u64,
+    #[serde(default = "default_per_page")]
+    per_page: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_per_page() -> u64 {
+    20
+}
+
+/// Paginated queue jobs response
+#[derive(Serialize, JsonSchema)]
+pub struct PaginatedJobsResponse {
+    pub items: Vec<JobRecord>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub total_pages: u64,
+}
+
+fn to_http(err: QueueError) -> HttpError {
+    HttpError::from(ApiError::from(err))
+}
+
+fn job_id(req: &crate::ServerRequest) -> Result<i64, HttpError> {
+    req.parse_param("id")
+        .map_err(|_| HttpError::bad_request("Invalid or missing job ID"))
+}
+
+/// List queue jobs, newest first.
+pub async fn list_queue_jobs(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, _auth_user) = extract_handler_context(&req)?;
+    let query: ListQueueQuery = req.parse_query().map_err(HttpError::bad_request)?;
+    if let Some(status) = &query.status
+        && !STATUSES.contains(&status.as_str())
+    {
+        return Err(HttpError::bad_request(format!(
+            "Invalid status, expected one of: {}",
+            STATUSES.join(", ")
+        )));
+    }
+
+    let page = JobQueue::new(state)
+        .list(
+            JobFilter {
+                status: query.status,
+                kind: query.kind,
+            },
+            query.page,
+            query.per_page,
+        )
+        .await
+        .map_err(to_http)?;
+    Response::json(&PaginatedJobsResponse {
+        items: page.items,
+        total: page.total,
+        page: page.page,
+        per_page: page.per_page,
+        total_pages: page.total_pages,
+    })
+}
+
+/// Get one queue job, including its payload and last error.
+pub async fn get_queue_job(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, _auth_user) = extract_handler_context(&req)?;
+    let id = job_id(&req)?;
+    let job = JobQueue::new(state).get(id).await.map_err(to_http)?;
+    Response::json(&job)
+}
+
+/// Re-queue a dead job with a fresh set of attempts.
+pub async fn retry_queue_job(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let id = job_id(&req)?;
+    let job = JobQueue::new(state).retry(id).await.map_err(to_http)?;
+    tracing::info!(user_id = %auth_user.user_id, job_id = id, kind = %job.kind, "Dead job re-queued");
+
+    let mut resp = Response::json(&job)?;
+    resp.set_status(StatusCode::ACCEPTED);
+    Ok(resp)
+}
@@ -0,0 +1,158 @@
+//! Two-factor authentication endpoints.
+//!
+//! Self-service enrollment lives under `/api/users/me/2fa`; admins reset a
+//! user's 2FA (lost device) via `DELETE /api/users/{id}/2fa`. The second
+//! login step itself is `POST /api/public/auth/login/2fa` in [`crate::handlers::auth`].
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::AppState;
+use crate::handlers::helpers::{extract_handler_context, self_id, to_http};
+use crate::services::two_factor::TwoFactorService;
+use webshelf_runtime::{HttpError, RequestContext, Response};
+
+/// Start enrollment; the password is re-checked.
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+pub struct EnrollTwoFactorRequest {
+    #[validate(length(min = 1, message = "password is required"))]
+    password: String,
+}
+
+/// A code from the authenticator app.
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+pub struct TwoFactorCodeRequest {
+    #[validate(length(min = 1, max = 32, message = "code is required"))]
+    code: String,
+}
+
+/// Turn 2FA off; needs the password and a TOTP or recovery code.
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+pub struct DisableTwoFactorRequest {
+    #[validate(length(min = 1, message = "password is required"))]
+    password: String,
+    #[validate(length(min = 1, max = 32, message = "code is required"))]
+    code: String,
+}
+
+/// One-time recovery codes — shown once, only their hashes are stored.
+#[derive(Serialize, JsonSchema)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DisableTwoFactorResponse {
+    message: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ResetTwoFactorResponse {
+    /// `false` when the user had no 2FA (or pending enrollment) to remove
+    pub removed: bool,
+}
+
+fn service(state: &AppState) -> TwoFactorService {
+    TwoFactorService::new(state.db.clone(), state.config.two_factor.clone())
+}
+
+/// Two-factor status of the current user
+pub async fn get_two_factor(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let user_id = self_id(&auth_user)?;
+    let status = service(&state).status(user_id).await.map_err(to_http)?;
+    Response::json(&status)
+}
+
+/// Start enrollment: returns the secret and `otpauth://` URI for the authenticator app
+pub async fn enroll_two_factor(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: EnrollTwoFactorRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+    payload.validate().map_err(to_http)?;
+
+    let user_id = self_id(&auth_user)?;
+    let enrollment = service(&state)
+        .begin_enrollment(user_id, &payload.password)
+        .await
+        .map_err(to_http)?;
+    Response::json(&enrollment)
+}
+
+/// Confirm enrollment with a first code; returns the recovery codes
+pub async fn confirm_two_factor(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: TwoFactorCodeRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+    payload.validate().map_err(to_http)?;
+
+    let user_id = self_id(&auth_user)?;
+    let recovery_codes = service(&state)
+        .confirm_enrollment(user_id, &payload.code)
+        .await
+        .map_err(to_http)?;
+    Response::json(&RecoveryCodesResponse { recovery_codes })
+}
+
+/// Turn two-factor authentication off
+pub async fn disable_two_factor(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: DisableTwoFactorRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+    payload.validate().map_err(to_http)?;
+
+    let user_id = self_id(&auth_user)?;
+    service(&state)
+        .disable(user_id, &payload.password, &payload.code)
+        .await
+        .map_err(to_http)?;
+    Response::json(&DisableTwoFactorResponse {
+        message: "Two-factor authentication disabled".to_string(),
+    })
+}
+
+/// Replace the recovery codes; needs a current TOTP code
+pub async fn regenerate_recovery_codes(
+    mut req: crate::ServerRequest,
+) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let payload: TwoFactorCodeRequest = req
+        .parse_json_or_form()
+        .await
+        .map_err(HttpError::bad_request)?;
+    payload.validate().map_err(to_http)?;
+
+    let user_id = self_id(&auth_user)?;
+    let recovery_codes = service(&state)
+        .regenerate_recovery_codes(user_id, &payload.code)
+        .await
+        .map_err(to_http)?;
+    Response::json(&RecoveryCodesResponse { recovery_codes })
+}
+
+/// Admin: remove a user's two-factor authentication (admin only)
+pub async fn reset_two_factor(req: crate::ServerRequest) -> Result<Response, HttpError> {
+    let (state, auth_user) = extract_handler_context(&req)?;
+    let user_id: i64 = req
+        .parse_param("id")
+        .map_err(|_| HttpError::bad_request("Invalid or missing user ID"))?;
+
+    let removed = service(&state)
+        .reset(user_id, &auth_user.role)
+        .await
+        .map_err(to_http)?;
+    tracing::info!(
+        actor_id = %auth_user.user_id,
+        target_user_id = user_id,
+        removed,
+        "Admin two-factor reset"
+    );
+    Response::json(&ResetTwoFactorResponse { removed })
+}
@@ -6,11 +6,15 @@
 
 use std::collections::HashMap;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use wechat_api::callback::CallbackQuery;
 
 use crate::AppState;
-use crate::handlers::auth::{expiry_cookie, token_cookie, unix_timestamp_from_now};
+use crate::handlers::auth::{
+    AuthStep, expiry_cookie, requires_two_factor, token_cookie, two_factor_challenge,
+    unix_timestamp_from_now,
+};
 use crate::handlers::helpers::extract_state;
 use crate::middlewares::{JWT_COOKIE, REFRESH_COOKIE};
 use crate::services::wechat::WechatComponents;
@@ -19,13 +23,13 @@ use webshelf_runtime::{HttpError, RequestContext, Response};
 
 // ── wx-login endpoint ─────────────────────────────────────────────────────
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct WxLoginRequestBody {
     /// The captcha code received from the WeChat Official Account.
     pub code: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct WxLoginResponse {
     pub token: String,
     pub token_type: String,
@@ -39,7 +43,8 @@ pub struct WxLoginResponse {
 /// Verify a WeChat captcha code and issue a JWT.
 /// The code is obtained by sending a trigger keyword to the WeChat Official
 /// Account. The openid must already be bound to a user account (via the
-/// user settings page or by admin assignment).
+/// user settings page or by admin assignment). Accounts with two-factor
+/// authentication get a `202` challenge instead of the JWT.
 pub async fn wx_login(mut req: crate::ServerRequest) -> Result<Response, HttpError> {
     let state: AppState = extract_state(&req)?;
     let payload: WxLoginRequestBody = req
@@ -52,21 +57,16 @@ pub async fn wx_login(mut req: crate::ServerRequest) -> Result<Response, HttpErr
         .as_ref()
         .ok_or_else(|| HttpError::bad_request("WeChat login is not configured"))?;
 
-    let result = wx_login_inner(&state, wechat, &payload).await?;
-    let (resp, cookies) = result;
-
-    let mut response = Response::json(&resp)?;
-    for cookie in cookies {
-        response.set_cookie(cookie);
-    }
-    Ok(response)
+    wx_login_inner(&state, wechat, &payload)
+        .await?
+        .into_response()
 }
 
 async fn wx_login_inner(
     state: &AppState,
     wechat: &WechatComponents,
     payload: &WxLoginRequestBody,
-) -> Result<(WxLoginResponse, Vec<cookie::Cookie<'static>>), ApiError> {
+) -> Result<AuthStep<WxLoginResponse>, ApiError> {
     let account_id = &wechat.config.account_id;
 
     // 1. Look up openid from the reverse index (code → openid).
@@ -95,10 +95,17 @@ async fn wx_login_inner(
         }
     };
 
-    // 3. Look up the user's role and token_version.
+    // 3. A captcha replaces the password only — the second factor still applies.
+    if requires_two_factor(state, user_id).await? {
+        return Ok(AuthStep::Challenge(
+            two_factor_challenge(state, user_id, false).await?,
+        ));
+    }
+
+    // 4. Look up the user's role and token_version.
     let (role, token_version) = lookup_user_role_and_version(state, user_id).await?;
 
-    // 4. Issue JWT.
+    // 5. Issue JWT.
     let jwt_expiry = state.config.jwt_expiry_seconds;
     let token = crate::middlewares::generate_token(
         &user_id.to_string(),
@@ -124,7 +131,7 @@ async fn wx_login_inner(
 
     tracing::debug!(user_id, "WeChat captcha login successful");
 
-    Ok((
+    Ok(AuthStep::Session(
         WxLoginResponse {
             token,
             token_type: "Bearer".to_string(),
@@ -158,7 +165,7 @@ async fn lookup_user_role_and_version(
 
 // ── WeChat configuration status endpoint ───────────────────────────────────
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct WechatEnabledResponse {
     pub enabled: bool,
 }
@@ -44,6 +44,8 @@ pub struct AppState {
     pub email: emailserver::EmailService,
     /// WeChat captcha-login components (None when disabled).
     pub wechat: Option<crate::services::wechat::WechatComponents>,
+    /// Graceful shutdown coordinator (readiness, drain deadline, background-task stop hooks).
+    pub shutdown: webshelf_runtime::Shutdown,
 }
 
 #[async_trait]
@@ -61,6 +63,43 @@ impl MiddlewareState for AppState {
             .await
             .map_err(|e| e.to_string())
     }
+
+    /// Personal access tokens (`wst_…`) and OAuth access tokens; the latter, like sessions,
+    /// die with the user's `token_version`.
+    async fn authenticate_token(
+        &self,
+        token: &str,
+        client_ip: Option<std::net::IpAddr>,
+    ) -> Option<Result<webshelf_runtime::AuthUser, String>> {
+        // 个人访问令牌：独立于 token_version，撤销或过期前一直有效
+        if crate::services::api_token::is_api_token(token) {
+            let service = crate::services::ApiTokenService::new(self.db.clone());
+            return Some(
+                service
+                    .authenticate(token, client_ip)
+                    .await
+                    .map_err(|e| e.to_string()),
+            );
+        }
+        let service = crate::services::OAuthService::new(
+            self.db.clone(),
+            self.config.oauth.clone(),
+            &self.config.jwt_secret,
+        );
+        let user = match service.verify_access_token(token).await? {
+            Ok(user) => user,
+            Err(e) => return Some(Err(e.to_string())),
+        };
+        let Ok(user_id) = user.user_id.parse::<i64>() else {
+            return Some(Err("Invalid subject in access token".to_string()));
+        };
+        Some(
+            verify_token_version(&self.db, &self.cache, user_id, user.token_version)
+                .await
+                .map(|_| user)
+                .map_err(|e| e.to_string()),
+        )
+    }
 }
 
 /// Verify token_version matches the user's current version.
@@ -7,10 +7,16 @@ use webshelf_server::bootstrap::CliArgs;
 async fn main() -> Result<()> {
     let cli_args = CliArgs::parse();
 
-    webshelf_server::bootstrap::init_logging(&cli_args.log_level);
+    let app_config = webshelf_server::bootstrap::load_app_config(&cli_args)?;
+
+    let _log_guard = webshelf_server::bootstrap::init_logging(&app_config)?;
     webshelf_server::bootstrap::setup_panic_handler();
 
-    let bootstrap_result = webshelf_server::bootstrap::bootstrap(cli_args).await?;
+    if let Some(command) = cli_args.command.clone() {
+        return webshelf_server::bootstrap::run_command(command, &cli_args.env, &app_config).await;
+    }
+
+    let bootstrap_result = webshelf_server::bootstrap::bootstrap(cli_args, app_config).await?;
     webshelf_server::bootstrap::start_server(bootstrap_result).await?;
 
     Ok(())
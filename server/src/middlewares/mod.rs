@@ -4,8 +4,8 @@ pub(crate) const JWT_COOKIE: &str = "webshelf_jwt";
 pub(crate) const REFRESH_COOKIE: &str = "webshelf_refresh";
 pub(crate) const EXPIRY_COOKIE: &str = "webshelf_exp";
 
-// Re-export AuthUser and RateLimitGuard from webshelf-runtime
-pub use webshelf_runtime::{AuthUser, RateLimitGuard};
+// Re-export AuthUser, RateLimitGuard and the Middleware trait from webshelf-runtime
+pub use webshelf_runtime::{AuthUser, Middleware, Next, RateLimitGuard};
 
 // Re-export generate_token for backward compatibility
 pub use crate::utils::jwt::generate_token;
@@ -13,9 +13,10 @@ pub use crate::utils::jwt::generate_token;
 // Axum mode: re-export middleware from the adapter
 #[cfg(not(feature = "webshelf-salvo"))]
 pub use webshelf_axum::middleware::{
-    auth_middleware, panic_middleware, rate_limit_middleware, require_admin,
+    auth_middleware, metrics_middleware, panic_middleware, rate_limit_middleware,
+    request_id_middleware, require_admin, security_headers_middleware,
 };
 
 // Salvo mode: re-export middleware from the adapter
 #[cfg(feature = "webshelf-salvo")]
-pub use webshelf_salvo::middleware::{AuthMiddleware, RateLimitMiddleware, RequireAdmin};
+pub use webshelf_salvo::middleware::{AuthMiddleware, RequireAdmin, UnifiedMiddleware};
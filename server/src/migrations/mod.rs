@@ -0,0 +1,564 @@
+//! Versioned database migrations.
+//!
+//! Each migration is a pair of files `server/migrations/NNN_name.up.sql` /
+//! `NNN_name.down.sql`, embedded at build time and listed in [`MIGRATIONS`]. Applied
+//! versions are recorded in `_webshelf_migrations` together with a SHA-256 checksum of the
+//! up script:
+//!
+//! - every migration runs once, in one transaction with its tracking row;
+//! - an applied migration whose file was edited afterwards is reported as
+//!   [`MigrationState::Modified`] and blocks further migrations — add a new migration instead;
+//! - changes run under a PostgreSQL advisory lock, so replicas starting together apply each
+//!   migration once while the others wait.
+//!
+//! Startup applies pending migrations (`database.auto_migrate`); `webshelf migrate
+//! up|down|status|verify` runs the same operations from the command line.
+
+mod split;
+
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ConnectionTrait, DatabaseBackend, DatabaseConnection, DatabaseTransaction, Statement,
+    TransactionTrait,
+};
+use sha2::{Digest, Sha256};
+
+pub use split::split_statements;
+
+/// Tracking table name.
+pub const MIGRATIONS_TABLE: &str = "_webshelf_migrations";
+
+/// Advisory lock key held while migrating (`"webshelf"` in ASCII).
+const LOCK_KEY: i64 = 0x7765_6273_6865_6c66;
+
+const CREATE_MIGRATIONS_TABLE: &str = "CREATE TABLE IF NOT EXISTS _webshelf_migrations (
+    version BIGINT PRIMARY KEY,
+    name TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    execution_ms BIGINT NOT NULL
+)";
+
+macro_rules! migration {
+    ($file:literal) => {
+        Migration {
+            file: $file,
+            up: include_str!(concat!("../../migrations/", $file, ".up.sql")),
+            down: include_str!(concat!("../../migrations/", $file, ".down.sql")),
+        }
+    };
+}
+
+/// All migrations, in version order.
+pub const MIGRATIONS: &[Migration] = &[
+    migration!("001_init"),
+    migration!("002_scheduled_jobs"),
+    migration!("003_job_queue"),
+    migration!("004_two_factor"),
+    migration!("005_passkeys"),
+    migration!("006_user_identities"),
+    migration!("007_oauth"),
+    migration!("008_api_tokens"),
+    migration!("009_oauth_client_admin_access"),
+];
+
+/// An embedded migration.
+#[derive(Debug)]
+pub struct Migration {
+    /// File stem, `NNN_name`
+    pub file: &'static str,
+    pub up: &'static str,
+    /// Rollback script; one without statements marks the migration irreversible
+    pub down: &'static str,
+}
+
+impl Migration {
+    pub fn version(&self) -> i64 {
+        self.file
+            .split_once('_')
+            .and_then(|(version, _)| version.parse().ok())
+            .expect("migration file names start with a numeric version")
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.file
+            .split_once('_')
+            .map_or(self.file, |(_, name)| name)
+    }
+
+    /// Hex SHA-256 of the up script.
+    pub fn checksum(&self) -> String {
+        hex::encode(Sha256::digest(self.up.as_bytes()))
+    }
+}
+
+/// A row of `_webshelf_migrations`.
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: DateTime<Utc>,
+    pub execution_ms: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the embedded up script no longer matches the recorded checksum
+    Modified,
+    /// Recorded as applied, but not part of this build (e.g. applied by a newer release)
+    Missing,
+}
+
+impl MigrationState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Applied => "applied",
+            Self::Pending => "pending",
+            Self::Modified => "modified",
+            Self::Missing => "missing",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub state: MigrationState,
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+impl MigrationStatus {
+    /// `NNN_name`, as the file stem.
+    pub fn label(&self) -> String {
+        format!("{:03}_{}", self.version, self.name)
+    }
+}
+
+/// Compare embedded migrations with the tracking rows, ordered by version.
+pub fn plan(migrations: &[Migration], applied: &[AppliedMigration]) -> Vec<MigrationStatus> {
+    let mut statuses: Vec<MigrationStatus> = migrations
+        .iter()
+        .map(|m| {
+            let row = applied.iter().find(|a| a.version == m.version());
+            let state = match row {
+                None => MigrationState::Pending,
+                Some(a) if a.checksum == m.checksum() => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+            };
+            MigrationStatus {
+                version: m.version(),
+                name: m.name().to_string(),
+                state,
+                applied_at: row.map(|a| a.applied_at),
+            }
+        })
+        .collect();
+    statuses.extend(
+        applied
+            .iter()
+            .filter(|a| !migrations.iter().any(|m| m.version() == a.version))
+            .map(|a| MigrationStatus {
+                version: a.version,
+                name: a.name.clone(),
+                state: MigrationState::Missing,
+                applied_at: Some(a.applied_at),
+            }),
+    );
+    statuses.sort_by_key(|s| s.version);
+    statuses
+}
+
+/// Apply all pending migrations (used on startup and by the integration tests).
+pub async fn run_migrations(db: &DatabaseConnection) -> Result<()> {
+    migrate_up(db, None).await.map(|_| ())
+}
+
+/// Apply pending migrations up to and including `target` (all when `None`).
+pub async fn migrate_up(
+    db: &DatabaseConnection,
+    target: Option<i64>,
+) -> Result<Vec<&'static Migration>> {
+    let lock = acquire_lock(db).await?;
+    db.execute_unprepared(CREATE_MIGRATIONS_TABLE)
+        .await
+        .context("Failed to create migrations table")?;
+
+    let statuses = plan(MIGRATIONS, &load_applied(db).await?);
+    ensure_unmodified(&statuses)?;
+    for status in statuses
+        .iter()
+        .filter(|s| s.state == MigrationState::Missing)
+    {
+        tracing::warn!(
+            "Migration {} is applied but not part of this build",
+            status.label()
+        );
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS {
+        let pending = statuses
+            .iter()
+            .any(|s| s.version == migration.version() && s.state == MigrationState::Pending);
+        if !pending || target.is_some_and(|target| migration.version() > target) {
+            continue;
+        }
+        apply(db, migration).await?;
+        applied.push(migration);
+    }
+    lock.commit()
+        .await
+        .context("Failed to release migration lock")?;
+
+    if applied.is_empty() {
+        tracing::info!("Database schema is up to date");
+    }
+    Ok(applied)
+}
+
+/// Roll back the last `steps` applied migrations, newest first.
+pub async fn migrate_down(
+    db: &DatabaseConnection,
+    steps: usize,
+) -> Result<Vec<&'static Migration>> {
+    let lock = acquire_lock(db).await?;
+    let applied = if table_exists(db).await? {
+        load_applied(db).await?
+    } else {
+        Vec::new()
+    };
+
+    let mut reverted = Vec::new();
+    for row in applied.iter().rev().take(steps) {
+        let label = format!("{:03}_{}", row.version, row.name);
+        let Some(migration) = MIGRATIONS.iter().find(|m| m.version() == row.version) else {
+            bail!("Migration {label} is not part of this build and cannot be rolled back");
+        };
+        if migration.checksum() != row.checksum {
+            bail!("Migration {label} was modified after it was applied; refusing to roll back");
+        }
+        if split_statements(migration.down).is_empty() {
+            bail!("Migration {label} is irreversible (empty down script)");
+        }
+        revert(db, migration).await?;
+        reverted.push(migration);
+    }
+    lock.commit()
+        .await
+        .context("Failed to release migration lock")?;
+    Ok(reverted)
+}
+
+/// State of every known migration. Read-only; does not create the tracking table.
+pub async fn status(db: &DatabaseConnection) -> Result<Vec<MigrationStatus>> {
+    let applied = if table_exists(db).await? {
+        load_applied(db).await?
+    } else {
+        Vec::new()
+    };
+    Ok(plan(MIGRATIONS, &applied))
+}
+
+/// Check that every applied migration matches this build: none modified or missing.
+pub async fn verify(db: &DatabaseConnection) -> Result<Vec<MigrationStatus>> {
+    let statuses = status(db).await?;
+    let problems: Vec<String> = statuses
+        .iter()
+        .filter_map(|s| match s.state {
+            MigrationState::Modified => Some(format!(
+                "{}: checksum differs from the applied version",
+                s.label()
+            )),
+            MigrationState::Missing => {
+                Some(format!("{}: applied but not part of this build", s.label()))
+            }
+            MigrationState::Applied | MigrationState::Pending => None,
+        })
+        .collect();
+    if !problems.is_empty() {
+        bail!(
+            "Migration verification failed:\n  {}",
+            problems.join("\n  ")
+        );
+    }
+    Ok(statuses)
+}
+
+/// Fail unless every migration is applied and unmodified (startup with
+/// `database.auto_migrate = false`).
+pub async fn require_up_to_date(db: &DatabaseConnection) -> Result<()> {
+    let statuses = status(db).await?;
+    ensure_unmodified(&statuses)?;
+    let pending: Vec<String> = statuses
+        .iter()
+        .filter(|s| s.state == MigrationState::Pending)
+        .map(MigrationStatus::label)
+        .collect();
+    if !pending.is_empty() {
+        bail!(
+            "Pending migrations: {}. Run `webshelf migrate up` or enable database.auto_migrate",
+            pending.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn ensure_unmodified(statuses: &[MigrationStatus]) -> Result<()> {
+    if let Some(modified) = statuses
+        .iter()
+        .find(|s| s.state == MigrationState::Modified)
+    {
+        bail!(
+            "Migration {} was modified after it was applied; restore the file and add a new \
+             migration instead (see `webshelf migrate verify`)",
+            modified.label()
+        );
+    }
+    Ok(())
+}
+
+/// Take the migration advisory lock, held until the returned transaction ends. Migrations
+/// themselves run on other pooled connections so each one commits independently.
+async fn acquire_lock(db: &DatabaseConnection) -> Result<DatabaseTransaction> {
+    let lock = db
+        .begin()
+        .await
+        .context("Failed to begin lock transaction")?;
+    let locked = lock
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            "SELECT pg_try_advisory_xact_lock($1) AS locked",
+            [LOCK_KEY.into()],
+        ))
+        .await?
+        .map(|row| row.try_get::<bool>("", "locked"))
+        .transpose()?
+        .unwrap_or(false);
+    if !locked {
+        tracing::info!("Another instance is running migrations; waiting for it to finish");
+        lock.execute(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            "SELECT pg_advisory_xact_lock($1)",
+            [LOCK_KEY.into()],
+        ))
+        .await
+        .context("Failed to acquire migration lock")?;
+    }
+    Ok(lock)
+}
+
+async fn table_exists(db: &DatabaseConnection) -> Result<bool> {
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            "SELECT to_regclass($1) IS NOT NULL AS present",
+            [MIGRATIONS_TABLE.into()],
+        ))
+        .await?;
+    Ok(match row {
+        Some(row) => row.try_get("", "present")?,
+        None => false,
+    })
+}
+
+async fn load_applied(db: &DatabaseConnection) -> Result<Vec<AppliedMigration>> {
+    let rows = db
+        .query_all(Statement::from_string(
+            DatabaseBackend::Postgres,
+            "SELECT version, name, checksum, applied_at, execution_ms \
+             FROM _webshelf_migrations ORDER BY version",
+        ))
+        .await
+        .context("Failed to read applied migrations")?;
+    rows.iter()
+        .map(|row| {
+            Ok(AppliedMigration {
+                version: row.try_get("", "version")?,
+                name: row.try_get("", "name")?,
+                checksum: row.try_get("", "checksum")?,
+                applied_at: row.try_get("", "applied_at")?,
+                execution_ms: row.try_get("", "execution_ms")?,
+            })
+        })
+        .collect()
+}
+
+async fn apply(db: &DatabaseConnection, migration: &Migration) -> Result<()> {
+    tracing::info!("Applying migration {}", migration.file);
+    let started = Instant::now();
+    let txn = db.begin().await.context("Failed to begin migration")?;
+    run_script(&txn, migration.file, migration.up).await?;
+    txn.execute(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        "INSERT INTO _webshelf_migrations (version, name, checksum, execution_ms) \
+         VALUES ($1, $2, $3, $4)",
+        [
+            migration.version().into(),
+            migration.name().into(),
+            migration.checksum().into(),
+            (started.elapsed().as_millis() as i64).into(),
+        ],
+    ))
+    .await
+    .context("Failed to record migration")?;
+    txn.commit()
+        .await
+        .with_context(|| format!("Failed to commit migration {}", migration.file))?;
+    tracing::info!(
+        "Applied migration {} in {} ms",
+        migration.file,
+        started.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+async fn revert(db: &DatabaseConnection, migration: &Migration) -> Result<()> {
+    tracing::warn!("Rolling back migration {}", migration.file);
+    let txn = db.begin().await.context("Failed to begin rollback")?;
+    run_script(&txn, migration.file, migration.down).await?;
+    txn.execute(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        "DELETE FROM _webshelf_migrations WHERE version = $1",
+        [migration.version().into()],
+    ))
+    .await
+    .context("Failed to remove migration record")?;
+    txn.commit()
+        .await
+        .with_context(|| format!("Failed to commit rollback of {}", migration.file))?;
+    Ok(())
+}
+
+async fn run_script(txn: &DatabaseTransaction, file: &str, sql: &str) -> Result<()> {
+    for (i, statement) in split_statements(sql).into_iter().enumerate() {
+        txn.execute_unprepared(statement).await.with_context(|| {
+            // 报错时给出语句序号与首个非注释行，便于定位
+            let head = statement
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty() && !line.starts_with("--"))
+                .unwrap_or_default();
+            format!("Migration {file} failed at statement {}: {head}", i + 1)
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(migration: &Migration, checksum: String) -> AppliedMigration {
+        AppliedMigration {
+            version: migration.version(),
+            name: migration.name().to_string(),
+            checksum,
+            applied_at: Utc::now(),
+            execution_ms: 1,
+        }
+    }
+
+    #[test]
+    fn test_migrations_list() {
+        assert_eq!(MIGRATIONS[0].version(), 1);
+        assert_eq!(MIGRATIONS[0].name(), "init");
+        assert!(
+            MIGRATIONS[0]
+                .up
+                .contains("CREATE TABLE IF NOT EXISTS users")
+        );
+        assert!(MIGRATIONS[0].up.contains("token_version"));
+        assert!(
+            MIGRATIONS
+                .windows(2)
+                .all(|w| w[0].version() < w[1].version())
+        );
+    }
+
+    #[test]
+    fn every_migration_file_is_registered_in_pairs() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+        let mut stems: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter_map(|name| {
+                name.strip_suffix(".up.sql")
+                    .or_else(|| name.strip_suffix(".down.sql"))
+                    .map(str::to_string)
+            })
+            .collect();
+        stems.sort();
+        let registered: Vec<String> = MIGRATIONS
+            .iter()
+            .flat_map(|m| [m.file.to_string(), m.file.to_string()])
+            .collect();
+        assert_eq!(stems, registered);
+    }
+
+    #[test]
+    fn migration_scripts_split_into_statements() {
+        for migration in MIGRATIONS {
+            assert!(!split_statements(migration.up).is_empty());
+            assert!(
+                split_statements(migration.up)
+                    .iter()
+                    .all(|s| !s.trim_end().ends_with(';'))
+            );
+        }
+    }
+
+    #[test]
+    fn plan_detects_pending_modified_and_missing() {
+        let migrations = [
+            Migration {
+                file: "001_init",
+                up: "CREATE TABLE a (id INT);",
+                down: "DROP TABLE a;",
+            },
+            Migration {
+                file: "002_books",
+                up: "CREATE TABLE b (id INT);",
+                down: "",
+            },
+        ];
+        let statuses = plan(&migrations, &[]);
+        assert!(
+            statuses
+                .iter()
+                .all(|s| s.state == MigrationState::Pending && s.applied_at.is_none())
+        );
+
+        let rows = [
+            applied(&migrations[0], migrations[0].checksum()),
+            applied(&migrations[1], "0".repeat(64)),
+            AppliedMigration {
+                version: 3,
+                name: "future".into(),
+                checksum: String::new(),
+                applied_at: Utc::now(),
+                execution_ms: 1,
+            },
+        ];
+        let states: Vec<(String, MigrationState)> = plan(&migrations, &rows)
+            .iter()
+            .map(|s| (s.label(), s.state))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                ("001_init".to_string(), MigrationState::Applied),
+                ("002_books".to_string(), MigrationState::Modified),
+                ("003_future".to_string(), MigrationState::Missing),
+            ]
+        );
+        assert!(ensure_unmodified(&plan(&migrations, &rows)).is_err());
+        assert!(ensure_unmodified(&plan(&migrations, &rows[..1])).is_ok());
+    }
+}
@@ -0,0 +1,210 @@
+//! PostgreSQL statement splitter for migration files.
+//!
+//! Splits on top-level `;` only, skipping over:
+//!
+//! - `'…'` literals (`''` escapes) and `E'…'` literals (backslash escapes);
+//! - `"…"` quoted identifiers;
+//! - `$$…$$` / `$tag$…$tag$` dollar-quoted bodies;
+//! - `-- …` line comments and (nested) `/* … */` block comments.
+//!
+//! SQL-standard `BEGIN ATOMIC … END` function bodies are not recognised — write function
+//! bodies dollar-quoted.
+
+/// Split `sql` into statements, without the terminating `;`. Statements consisting only of
+/// whitespace and comments are dropped.
+pub fn split_statements(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_code = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = memchr(bytes, i, b'\n').map_or(bytes.len(), |end| end + 1);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = skip_block_comment(bytes, i);
+                continue;
+            }
+            b';' => {
+                if has_code {
+                    statements.push(sql[start..i].trim());
+                }
+                start = i + 1;
+                has_code = false;
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        if !b.is_ascii_whitespace() {
+            has_code = true;
+        }
+        i = match b {
+            b'\'' => {
+                let backslash_escapes = i > 0
+                    && matches!(bytes[i - 1], b'E' | b'e')
+                    && (i == 1 || !is_ident_byte(bytes[i - 2]));
+                skip_quoted(bytes, i, b'\'', backslash_escapes)
+            }
+            b'"' => skip_quoted(bytes, i, b'"', false),
+            b'$' if i == 0 || !is_ident_byte(bytes[i - 1]) => match dollar_tag(bytes, i) {
+                Some(tag) => {
+                    find(bytes, i + tag.len(), tag).map_or(bytes.len(), |end| end + tag.len())
+                }
+                None => i + 1,
+            },
+            // 标识符整体跳过，避免把 `a$b$` 之类的名字误认为美元引用；
+            // 紧随其后的 `'` 由引号分支根据前一个字节判断是否为 `E'…'`
+            _ if is_ident_byte(b) => {
+                let mut end = i + 1;
+                while end < bytes.len() && (is_ident_byte(bytes[end]) || bytes[end] == b'$') {
+                    end += 1;
+                }
+                end
+            }
+            _ => i + 1,
+        };
+    }
+
+    if has_code {
+        statements.push(sql[start..].trim());
+    }
+    statements
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+/// Index just past the literal opened by `quote` at `open`.
+fn skip_quoted(bytes: &[u8], open: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut i = open + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if backslash_escapes => i += 2,
+            b if b == quote => {
+                // 连续两个引号是转义
+                if bytes.get(i + 1) == Some(&quote) {
+                    i += 2;
+                } else {
+                    return i + 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Index just past the (possibly nested) block comment opened at `open`.
+fn skip_block_comment(bytes: &[u8], open: usize) -> usize {
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            depth += 1;
+            i += 2;
+        } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// The `$tag$` opening at `open`, if any. Tags follow identifier rules, so `$1` is not one.
+fn dollar_tag(bytes: &[u8], open: usize) -> Option<&[u8]> {
+    let mut i = open + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'$' => return Some(&bytes[open..=i]),
+            b if b.is_ascii_digit() && i == open + 1 => return None,
+            b if is_ident_byte(b) => i += 1,
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn memchr(bytes: &[u8], from: usize, needle: u8) -> Option<usize> {
+    bytes[from..]
+        .iter()
+        .position(|&b| b == needle)
+        .map(|p| from + p)
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| from + p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_top_level_semicolons() {
+        let sql = "CREATE TABLE a (id INT);\n\nCREATE INDEX i ON a(id) ;  \n";
+        assert_eq!(
+            split_statements(sql),
+            ["CREATE TABLE a (id INT)", "CREATE INDEX i ON a(id)"]
+        );
+        assert_eq!(split_statements("SELECT 1"), ["SELECT 1"]);
+    }
+
+    #[test]
+    fn semicolons_inside_literals_and_identifiers_are_kept() {
+        let sql = r#"INSERT INTO t VALUES ('a;b', 'it''s;', E'x\';y', "odd;name");SELECT 2"#;
+        assert_eq!(
+            split_statements(sql),
+            [
+                r#"INSERT INTO t VALUES ('a;b', 'it''s;', E'x\';y', "odd;name")"#,
+                "SELECT 2"
+            ]
+        );
+        // 非 E 前缀的字符串里反斜杠不是转义
+        assert_eq!(
+            split_statements(r"SELECT 'C:\';SELECT 3"),
+            [r"SELECT 'C:\'", "SELECT 3"]
+        );
+        assert_eq!(
+            split_statements("SELECT name'x;y' FROM t;SELECT 4").len(),
+            2
+        );
+    }
+
+    #[test]
+    fn dollar_quoted_bodies_are_kept_whole() {
+        let sql = "CREATE FUNCTION f() RETURNS trigger AS $$\nBEGIN\n  NEW.updated_at := NOW();\n  RETURN NEW;\nEND;\n$$ LANGUAGE plpgsql;\n\
+                   DO $body$ BEGIN PERFORM 'x$$;'; END $body$;\n\
+                   PREPARE p AS SELECT $1::int;";
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].ends_with("$$ LANGUAGE plpgsql"));
+        assert_eq!(statements[1], "DO $body$ BEGIN PERFORM 'x$$;'; END $body$");
+        assert_eq!(statements[2], "PREPARE p AS SELECT $1::int");
+    }
+
+    #[test]
+    fn comments_are_skipped_and_comment_only_chunks_dropped() {
+        let sql = "-- leading; comment\nCREATE TABLE a (id INT); /* block; /* nested; */ still; */\n-- trailing;\n";
+        assert_eq!(
+            split_statements(sql),
+            ["-- leading; comment\nCREATE TABLE a (id INT)"]
+        );
+        assert!(split_statements("-- nothing here;\n  ").is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+use sea_orm::entity::prelude::*;
+
+/// Personal access token (API key) owned by a user.
+///
+/// Only the SHA-256 hash of the token is stored; `prefix` identifies it in listings.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "api_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+
+    pub user_id: i64,
+
+    /// Label chosen by the user
+    pub name: String,
+
+    /// Start of the token, shown in the token list
+    pub prefix: String,
+
+    /// SHA-256 (hex) of the whole token
+    #[sea_orm(unique)]
+    pub token_hash: String,
+
+    /// Granted scopes, as a JSON array
+    pub scopes: Json,
+
+    /// `None` for tokens that do not expire
+    pub expires_at: Option<DateTimeUtc>,
+
+    pub last_used_at: Option<DateTimeUtc>,
+
+    /// Client address of the last use
+    pub last_used_ip: Option<String>,
+
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
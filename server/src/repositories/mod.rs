@@ -1,15 +1,85 @@
+pub mod api_token;
+pub mod oauth_authorization_code;
+pub mod oauth_client;
+pub mod oauth_consent;
+pub mod oauth_refresh_token;
+pub mod oauth_signing_key;
+pub mod passkey_credential;
+pub mod queued_job;
+pub mod recovery_code;
 pub mod refresh_token;
+pub mod scheduled_job;
 pub mod snowflake_worker;
+pub mod two_factor_challenge;
 pub mod user;
+pub mod user_identity;
+pub mod user_totp;
+pub mod webauthn_challenge;
 
+pub use api_token::{
+    ActiveModel as ApiTokenActiveModel, Column as ApiTokenColumn, Entity as ApiTokenEntity,
+    Model as ApiTokenModel,
+};
+pub use oauth_authorization_code::{
+    ActiveModel as OAuthAuthorizationCodeActiveModel, Column as OAuthAuthorizationCodeColumn,
+    Entity as OAuthAuthorizationCodeEntity, Model as OAuthAuthorizationCodeModel,
+};
+pub use oauth_client::{
+    ActiveModel as OAuthClientActiveModel, Column as OAuthClientColumn,
+    Entity as OAuthClientEntity, Model as OAuthClientModel,
+};
+pub use oauth_consent::{
+    ActiveModel as OAuthConsentActiveModel, Column as OAuthConsentColumn,
+    Entity as OAuthConsentEntity, Model as OAuthConsentModel,
+};
+pub use oauth_refresh_token::{
+    ActiveModel as OAuthRefreshTokenActiveModel, Column as OAuthRefreshTokenColumn,
+    Entity as OAuthRefreshTokenEntity, Model as OAuthRefreshTokenModel,
+};
+pub use oauth_signing_key::{
+    ActiveModel as OAuthSigningKeyActiveModel, Column as OAuthSigningKeyColumn,
+    Entity as OAuthSigningKeyEntity, Model as OAuthSigningKeyModel,
+};
+pub use passkey_credential::{
+    ActiveModel as PasskeyCredentialActiveModel, Column as PasskeyCredentialColumn,
+    Entity as PasskeyCredentialEntity, Model as PasskeyCredentialModel,
+};
+pub use queued_job::{
+    ActiveModel as QueuedJobActiveModel, Column as QueuedJobColumn, Entity as QueuedJobEntity,
+    Model as QueuedJobModel,
+};
+pub use recovery_code::{
+    ActiveModel as RecoveryCodeActiveModel, Column as RecoveryCodeColumn,
+    Entity as RecoveryCodeEntity, Model as RecoveryCodeModel,
+};
 pub use refresh_token::{
     ActiveModel as RefreshTokenActiveModel, Column as RefreshTokenColumn,
     Entity as RefreshTokenEntity, Model as RefreshTokenModel,
 };
+pub use scheduled_job::{
+    ActiveModel as ScheduledJobActiveModel, Column as ScheduledJobColumn,
+    Entity as ScheduledJobEntity, Model as ScheduledJobModel,
+};
 pub use snowflake_worker::{
     ActiveModel as SnowflakeWorkerActiveModel, Column as SnowflakeWorkerColumn,
     Entity as SnowflakeWorkerEntity, Model as SnowflakeWorkerModel,
 };
+pub use two_factor_challenge::{
+    ActiveModel as TwoFactorChallengeActiveModel, Column as TwoFactorChallengeColumn,
+    Entity as TwoFactorChallengeEntity, Model as TwoFactorChallengeModel,
+};
 pub use user::{
     ActiveModel as UserActiveModel, Column as UserColumn, Entity as UserEntity, Model as UserModel,
 };
+pub use user_identity::{
+    ActiveModel as UserIdentityActiveModel, Column as UserIdentityColumn,
+    Entity as UserIdentityEntity, Model as UserIdentityModel,
+};
+pub use user_totp::{
+    ActiveModel as UserTotpActiveModel, Column as UserTotpColumn, Entity as UserTotpEntity,
+    Model as UserTotpModel,
+};
+pub use webauthn_challenge::{
+    ActiveModel as WebauthnChallengeActiveModel, Column as WebauthnChallengeColumn,
+    Entity as WebauthnChallengeEntity, Model as WebauthnChallengeModel,
+};
@@ -0,0 +1,44 @@
+use sea_orm::entity::prelude::*;
+
+/// Authorization code waiting to be redeemed at the token endpoint.
+///
+/// Only the SHA-256 hash of the code is stored; redeeming deletes the row.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "oauth_authorization_codes")]
+pub struct Model {
+    /// SHA-256 hash of the raw code
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub code_hash: String,
+
+    pub client_id: String,
+
+    pub user_id: i64,
+
+    /// Redirect URI of the authorization request; the token request must repeat it
+    pub redirect_uri: String,
+
+    /// Granted scopes, space-separated
+    pub scope: String,
+
+    /// `nonce` of the authorization request, copied into the ID token
+    pub nonce: Option<String>,
+
+    /// PKCE S256 code challenge
+    pub code_challenge: String,
+
+    pub expires_at: DateTimeUtc,
+
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
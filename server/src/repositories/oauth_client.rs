@@ -0,0 +1,55 @@
+use sea_orm::entity::prelude::*;
+
+/// Application registered to log users in with this server (OAuth 2.0 client).
+///
+/// `client_id` is public; only the SHA-256 hash of the secret is stored, and public clients
+/// (browser / native apps) have none.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "oauth_clients")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+
+    #[sea_orm(unique)]
+    pub client_id: String,
+
+    /// SHA-256 hash of the client secret; `None` for public clients
+    pub client_secret_hash: Option<String>,
+
+    /// Shown on the consent page
+    pub name: String,
+
+    /// Exact redirect URIs allowed in authorization requests, as a JSON array
+    pub redirect_uris: Json,
+
+    /// Allowed grant types (`authorization_code`, `refresh_token`, `client_credentials`)
+    pub grant_types: Json,
+
+    /// Scopes the client may request, as a JSON array
+    pub scopes: Json,
+
+    /// First-party client: users are not asked for consent
+    pub skip_consent: bool,
+
+    /// Access tokens keep the user's admin role (otherwise they act as a plain user)
+    pub admin_access: bool,
+
+    /// Admin who registered the client
+    pub owner_id: i64,
+
+    pub created_at: DateTimeUtc,
+
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::OwnerId",
+        to = "super::user::Column::Id"
+    )]
+    Owner,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+
+/// Scopes a user has approved for an OAuth client.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "oauth_consents")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: i64,
+
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub client_id: String,
+
+    /// Approved scopes, space-separated
+    pub scope: String,
+
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,41 @@
+use sea_orm::entity::prelude::*;
+
+/// Refresh token issued to an OAuth client.
+///
+/// Stores only the SHA-256 hash; each use deletes the row and issues a new token.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "oauth_refresh_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+
+    /// SHA-256 hash of the raw refresh token
+    #[sea_orm(unique)]
+    pub token_hash: String,
+
+    pub client_id: String,
+
+    pub user_id: i64,
+
+    /// Granted scopes, space-separated
+    pub scope: String,
+
+    /// The user's `token_version` at issuance; a newer version revokes the token
+    pub token_version: i32,
+
+    pub expires_at: DateTimeUtc,
+
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+/// ES256 key signing the authorization server's ID and access tokens.
+///
+/// The newest key signs; all keys are published at the JWKS endpoint so tokens signed
+/// by an older key stay verifiable.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "oauth_signing_keys")]
+pub struct Model {
+    /// Key ID (`kid` header of the tokens it signs)
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub kid: String,
+
+    /// PKCS#8 document of the P-256 private key
+    pub private_key: Vec<u8>,
+
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,49 @@
+use sea_orm::entity::prelude::*;
+
+/// WebAuthn passkey registered by a user.
+///
+/// The credential ID and COSE public key come from the authenticator at registration;
+/// the private key never leaves it.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "passkey_credentials")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+
+    pub user_id: i64,
+
+    /// Raw credential ID chosen by the authenticator
+    #[sea_orm(unique)]
+    pub credential_id: Vec<u8>,
+
+    /// COSE_Key public key
+    pub public_key: Vec<u8>,
+
+    /// COSE algorithm identifier (-7 ES256, -8 EdDSA, -257 RS256)
+    pub algorithm: i32,
+
+    /// Last signature counter reported by the authenticator
+    pub sign_count: i64,
+
+    /// Transport hints (`usb`, `nfc`, `ble`, `internal`, `hybrid`), as a JSON array
+    pub transports: Json,
+
+    /// Label chosen by the user
+    pub name: String,
+
+    pub last_used_at: Option<DateTimeUtc>,
+
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
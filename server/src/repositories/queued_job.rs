@@ -0,0 +1,50 @@
+use sea_orm::entity::prelude::*;
+
+/// A job in the durable queue ([`crate::services::queue`]).
+///
+/// Rows are inserted by [`crate::services::queue::enqueue`] and updated by whichever
+/// worker claims them; finished rows are purged by the `purge_job_queue` scheduled job.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "job_queue")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+
+    /// Job type, see [`crate::services::queue::Job::KIND`]
+    pub kind: String,
+
+    /// Serialized job
+    pub payload: Json,
+
+    /// `queued`, `running`, `succeeded` or `dead`
+    pub status: String,
+
+    /// Runs started so far, including the current one
+    pub attempts: i32,
+
+    pub max_attempts: i32,
+
+    /// Earliest time of the next run (backoff after a failure)
+    pub run_at: DateTimeUtc,
+
+    /// Visibility timeout of the current run; a `running` row whose timeout passed is
+    /// claimable again
+    pub locked_until: Option<DateTimeUtc>,
+
+    /// `host/pid` of the worker that claimed the job last
+    pub locked_by: Option<String>,
+
+    /// Error of the last failed run
+    pub last_error: Option<String>,
+
+    pub created_at: DateTimeUtc,
+
+    pub updated_at: DateTimeUtc,
+
+    pub finished_at: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
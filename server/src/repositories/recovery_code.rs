@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+
+/// Two-factor recovery code.
+///
+/// Only the SHA-256 hash is stored; the plain codes are shown to the user once when they
+/// are generated. Each code works once (`used_at` is set when it is consumed).
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "recovery_codes")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+
+    pub user_id: i64,
+
+    /// SHA-256 hash of the normalized code
+    pub code_hash: String,
+
+    pub used_at: Option<DateTimeUtc>,
+
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
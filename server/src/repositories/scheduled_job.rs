@@ -0,0 +1,53 @@
+use sea_orm::entity::prelude::*;
+
+/// Scheduled job state, one row per job known to [`crate::services::scheduler`].
+///
+/// `schedule` / `enabled` mirror the effective `[scheduler]` configuration; the remaining
+/// columns are written by whichever replica runs the job.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "scheduled_jobs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+
+    /// `every <n><s|m|h|d>` or a cron expression
+    pub schedule: String,
+
+    pub enabled: bool,
+
+    /// `idle` (never run), `running`, `succeeded` or `failed`
+    pub status: String,
+
+    pub next_run_at: Option<DateTimeUtc>,
+
+    /// Lease of the current run; a `running` row whose lease passed is claimable again
+    pub locked_until: Option<DateTimeUtc>,
+
+    pub last_started_at: Option<DateTimeUtc>,
+
+    pub last_finished_at: Option<DateTimeUtc>,
+
+    pub last_duration_ms: Option<i64>,
+
+    /// Summary returned by the last successful run
+    pub last_output: Option<String>,
+
+    /// Error of the last failed run
+    pub last_error: Option<String>,
+
+    /// `host/pid` of the instance that ran the job last
+    pub last_run_by: Option<String>,
+
+    pub run_count: i64,
+
+    pub failure_count: i64,
+
+    pub created_at: DateTimeUtc,
+
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
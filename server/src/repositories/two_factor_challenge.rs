@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+
+/// Pending second login step.
+///
+/// Created when the password step succeeds for an account with two-factor authentication;
+/// the raw challenge token goes to the client, only its SHA-256 hash is stored.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "two_factor_challenges")]
+pub struct Model {
+    /// SHA-256 hash of the raw challenge token
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub token_hash: String,
+
+    pub user_id: i64,
+
+    /// "Remember me" choice of the password step, applied when the session is issued
+    pub remember: bool,
+
+    /// Codes tried against this challenge
+    pub attempts: i32,
+
+    pub expires_at: DateTimeUtc,
+
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,5 @@
 use crate::snowflake::SnowflakeId;
+use schemars::JsonSchema;
 use sea_orm::entity::prelude::*;
 use serde::Deserialize;
 use serde::Serialize as SerializeTrait;
@@ -110,7 +111,7 @@ pub struct UpdateUserInput {
 /// type.  If adding new fields with `#[serde(skip)]`, ensure they also have
 /// default values or `#[serde(default)]` so JSON deserialization does not
 /// fail unexpectedly.
-#[derive(Debug, SerializeTrait, Deserialize)]
+#[derive(Debug, SerializeTrait, Deserialize, JsonSchema)]
 pub struct UserResponse {
     pub id: SnowflakeId,
     pub email: String,
@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+
+/// Account at an external OpenID Connect provider linked to a user.
+///
+/// `(provider, subject)` identifies the account at the provider; logging in with it logs in
+/// as `user_id`.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_identities")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+
+    pub user_id: i64,
+
+    /// Provider key from `[oidc.providers.<name>]`
+    pub provider: String,
+
+    /// The provider's `sub` claim
+    pub subject: String,
+
+    /// Email reported by the provider at the last login (display only)
+    pub email: Option<String>,
+
+    pub last_login_at: Option<DateTimeUtc>,
+
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+
+/// TOTP (RFC 6238) secret of a user.
+///
+/// A row with `enabled_at = None` is an unconfirmed enrollment: it is replaced by the
+/// next enrollment and ignored at login until the first code confirms it.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_totp")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: i64,
+
+    /// Base32-encoded shared secret
+    pub secret: String,
+
+    /// When the enrollment was confirmed; `None` while pending
+    pub enabled_at: Option<DateTimeUtc>,
+
+    /// Time step of the last accepted code (replay protection)
+    pub last_used_step: Option<i64>,
+
+    pub created_at: DateTimeUtc,
+
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
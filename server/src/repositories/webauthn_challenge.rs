@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+
+/// Outstanding WebAuthn ceremony.
+///
+/// The random challenge goes to the browser and comes back inside the signed client data;
+/// only its SHA-256 hash is stored, and the row is deleted when the ceremony completes.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "webauthn_challenges")]
+pub struct Model {
+    /// SHA-256 hash of the raw challenge bytes
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub challenge_hash: String,
+
+    /// `registration` or `authentication`
+    pub purpose: String,
+
+    /// User adding a passkey; `None` for login, where the user is not known yet
+    pub user_id: Option<i64>,
+
+    pub expires_at: DateTimeUtc,
+
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
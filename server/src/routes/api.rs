@@ -1,7 +1,56 @@
+use http::StatusCode;
+use webshelf_runtime::{OpenApi, Operation};
+
 use crate::AppRouter;
+use crate::handlers::api_token::{
+    ApiTokenListResponse, CreateApiTokenRequest, DeleteApiTokenResponse, create_api_token,
+    delete_api_token, list_api_tokens,
+};
+use crate::handlers::health::{HealthReportResponse, admin_health};
+use crate::handlers::identity::{
+    DeleteIdentityResponse, IdentityListResponse, delete_identity, list_identities,
+};
+use crate::handlers::jobs::{JobListResponse, list_jobs, run_job};
+use crate::handlers::log_level::{
+    SetLogLevelRequest, get_log_level, reset_log_level, set_log_level,
+};
+use crate::handlers::oauth::{
+    AuthorizationDecisionRequest, AuthorizationDecisionResponse, AuthorizationRequest,
+    CreateOAuthClientRequest, DeleteOAuthClientResponse, OAuthClientListResponse,
+    authorization_decision, authorization_details, create_oauth_client, delete_oauth_client,
+    list_oauth_clients, rotate_oauth_client_secret, update_oauth_client, userinfo,
+};
+use crate::handlers::passkey::{
+    DeletePasskeyResponse, PasskeyListResponse, PasskeyOptionsRequest, RegisterPasskeyRequest,
+    delete_passkey, list_passkeys, passkey_registration_options, register_passkey,
+};
+use crate::handlers::queue::{
+    ListQueueQuery, PaginatedJobsResponse, get_queue_job, list_queue_jobs, retry_queue_job,
+};
+use crate::handlers::two_factor::{
+    DisableTwoFactorRequest, DisableTwoFactorResponse, EnrollTwoFactorRequest,
+    RecoveryCodesResponse, ResetTwoFactorResponse, TwoFactorCodeRequest, confirm_two_factor,
+    disable_two_factor, enroll_two_factor, get_two_factor, regenerate_recovery_codes,
+    reset_two_factor,
+};
+use crate::repositories::user::UserResponse;
 use crate::routes::helpers::{apply_admin_guard, delete, get, post, put};
+use crate::routes::openapi::{authenticated, scoped};
+use crate::services::api_token::CreatedApiToken;
+use crate::services::log_level::LogLevelStatus;
+use crate::services::oauth::{
+    AuthorizationDetails, ClientInfo, ClientSecret, ClientSettings, CreatedClient, UserInfo,
+};
+use crate::services::queue::JobRecord;
+use crate::services::scheduler::JobStatus;
+use crate::services::two_factor::{Enrollment, TwoFactorStatus};
+use crate::services::webauthn::{PasskeyCreationOptions, PasskeyInfo};
+use crate::snowflake::SnowflakeId;
 
 use crate::handlers::api::{
+    AdjustBalanceRequest, AdjustBalanceResponse, ChangePasswordRequest, ChangePasswordResponse,
+    CreateUserRequest, DeleteUserResponse, HealthResponse, ListUsersQuery, LogoutAllResponse,
+    PaginatedUsersResponse, SetBalanceRequest, SetBalanceResponse, UpdateUserRequest,
     adjust_balance, change_my_password, create_user, delete_user, get_me, get_user, health_check,
     list_users, logout_all, set_balance, update_user,
 };
@@ -16,7 +65,28 @@ pub fn api_routes() -> AppRouter {
             .route("/users/{id}", put(update_user))
             .route("/users/{id}", delete(delete_user))
             .route("/users/{id}/balance", put(set_balance))
-            .route("/users/{id}/balance/adjust", post(adjust_balance)),
+            .route("/users/{id}/balance/adjust", post(adjust_balance))
+            .route("/users/{id}/2fa", delete(reset_two_factor))
+            .route("/admin/health", get(admin_health))
+            .route("/admin/jobs", get(list_jobs))
+            .route("/admin/jobs/{name}/run", post(run_job))
+            .route("/admin/queue", get(list_queue_jobs))
+            .route("/admin/queue/{id}", get(get_queue_job))
+            .route("/admin/queue/{id}/retry", post(retry_queue_job))
+            .route("/admin/log-level", get(get_log_level))
+            .route("/admin/log-level", put(set_log_level))
+            .route("/admin/log-level", delete(reset_log_level))
+            .route("/admin/oauth/clients", get(list_oauth_clients))
+            .route("/admin/oauth/clients", post(create_oauth_client))
+            .route("/admin/oauth/clients/{client_id}", put(update_oauth_client))
+            .route(
+                "/admin/oauth/clients/{client_id}",
+                delete(delete_oauth_client),
+            )
+            .route(
+                "/admin/oauth/clients/{client_id}/secret",
+                post(rotate_oauth_client_secret),
+            ),
     );
 
     // Self-service routes for any authenticated user (no admin role required).
@@ -24,10 +94,616 @@ pub fn api_routes() -> AppRouter {
     let self_routes = AppRouter::new()
         .route("/users/me", get(get_me))
         .route("/users/me/password", post(change_my_password))
-        .route("/users/me/logout-all", post(logout_all));
+        .route("/users/me/logout-all", post(logout_all))
+        .route("/users/me/2fa", get(get_two_factor))
+        .route("/users/me/2fa/enroll", post(enroll_two_factor))
+        .route("/users/me/2fa/confirm", post(confirm_two_factor))
+        .route("/users/me/2fa/disable", post(disable_two_factor))
+        .route(
+            "/users/me/2fa/recovery-codes",
+            post(regenerate_recovery_codes),
+        )
+        .route("/users/me/passkeys", get(list_passkeys))
+        .route("/users/me/passkeys", post(register_passkey))
+        .route(
+            "/users/me/passkeys/options",
+            post(passkey_registration_options),
+        )
+        .route("/users/me/passkeys/{id}", delete(delete_passkey))
+        .route("/users/me/tokens", get(list_api_tokens))
+        .route("/users/me/tokens", post(create_api_token))
+        .route("/users/me/tokens/{id}", delete(delete_api_token))
+        .route("/users/me/identities", get(list_identities))
+        .route("/users/me/identities/{id}", delete(delete_identity))
+        .route("/oauth/authorize/details", post(authorization_details))
+        .route("/oauth/authorize/decision", post(authorization_decision))
+        .route("/oauth/userinfo", get(userinfo));
 
     AppRouter::new()
         .route("/health", get(health_check))
         .merge(self_routes)
         .merge(admin_routes)
 }
+
+/// OpenAPI description of [`api_routes`] (paths relative to the `/api` nest).
+pub fn api_docs() -> OpenApi {
+    let admin = |op: Operation| {
+        authenticated(op.tag("admin")).error(StatusCode::FORBIDDEN, "Admin role required")
+    };
+    // Admin endpoints that OAuth access tokens of admins may also call
+    let admin_scoped = |op: Operation, scope: &str| {
+        scoped(op.tag("admin"), scope).error(
+            StatusCode::FORBIDDEN,
+            "Admin role required, or access token lacks the scope",
+        )
+    };
+    let system = |op: Operation| {
+        authenticated(op.tag("admin")).error(StatusCode::FORBIDDEN, "System role required")
+    };
+    let user_id = |op: Operation| {
+        op.path_param::<SnowflakeId>("id", "User ID")
+            .error(StatusCode::NOT_FOUND, "User not found")
+    };
+
+    OpenApi::default()
+        .get(
+            "/health",
+            Operation::new("Health check")
+                .operation_id("healthCheck")
+                .tag("system")
+                .response::<HealthResponse>(StatusCode::OK, "Service is healthy"),
+        )
+        .get(
+            "/users/me",
+            scoped(
+                Operation::new("Get current user")
+                    .operation_id("getMe")
+                    .tag("users")
+                    .response::<UserResponse>(StatusCode::OK, "Current user profile"),
+                "users:read",
+            ),
+        )
+        .post(
+            "/users/me/password",
+            authenticated(
+                Operation::new("Change password")
+                    .operation_id("changeMyPassword")
+                    .tag("users")
+                    .description("Invalidates all other sessions and sets fresh auth cookies.")
+                    .request_body::<ChangePasswordRequest>()
+                    .response::<ChangePasswordResponse>(StatusCode::OK, "Password changed")
+                    .error(
+                        StatusCode::BAD_REQUEST,
+                        "Validation failed or wrong current password",
+                    ),
+            ),
+        )
+        .post(
+            "/users/me/logout-all",
+            authenticated(
+                Operation::new("Log out from all devices")
+                    .operation_id("logoutAll")
+                    .tag("users")
+                    .response::<LogoutAllResponse>(StatusCode::OK, "All sessions revoked"),
+            ),
+        )
+        .get(
+            "/users/me/2fa",
+            authenticated(
+                Operation::new("Get two-factor status")
+                    .operation_id("getTwoFactor")
+                    .tag("users")
+                    .response::<TwoFactorStatus>(StatusCode::OK, "Two-factor status"),
+            ),
+        )
+        .post(
+            "/users/me/2fa/enroll",
+            authenticated(
+                Operation::new("Start two-factor enrollment")
+                    .operation_id("enrollTwoFactor")
+                    .tag("users")
+                    .description(
+                        "Stores a new pending TOTP secret (replacing an unconfirmed one) and \
+                         returns it with the `otpauth://` URI for the authenticator app.",
+                    )
+                    .request_body::<EnrollTwoFactorRequest>()
+                    .response::<Enrollment>(StatusCode::OK, "Pending secret")
+                    .error(
+                        StatusCode::BAD_REQUEST,
+                        "Validation failed or wrong password",
+                    )
+                    .error(
+                        StatusCode::CONFLICT,
+                        "Two-factor authentication already enabled",
+                    ),
+            ),
+        )
+        .post(
+            "/users/me/2fa/confirm",
+            authenticated(
+                Operation::new("Confirm two-factor enrollment")
+                    .operation_id("confirmTwoFactor")
+                    .tag("users")
+                    .description("Enables 2FA and returns one-time recovery codes, shown once.")
+                    .request_body::<TwoFactorCodeRequest>()
+                    .response::<RecoveryCodesResponse>(StatusCode::OK, "Two-factor enabled")
+                    .error(StatusCode::BAD_REQUEST, "Invalid code")
+                    .error(
+                        StatusCode::CONFLICT,
+                        "No pending enrollment or already enabled",
+                    ),
+            ),
+        )
+        .post(
+            "/users/me/2fa/disable",
+            authenticated(
+                Operation::new("Disable two-factor authentication")
+                    .operation_id("disableTwoFactor")
+                    .tag("users")
+                    .request_body::<DisableTwoFactorRequest>()
+                    .response::<DisableTwoFactorResponse>(StatusCode::OK, "Two-factor disabled")
+                    .error(StatusCode::BAD_REQUEST, "Wrong password or invalid code")
+                    .error(
+                        StatusCode::CONFLICT,
+                        "Two-factor authentication not enabled",
+                    ),
+            ),
+        )
+        .post(
+            "/users/me/2fa/recovery-codes",
+            authenticated(
+                Operation::new("Regenerate recovery codes")
+                    .operation_id("regenerateRecoveryCodes")
+                    .tag("users")
+                    .description("Replaces all recovery codes; needs a current authenticator code.")
+                    .request_body::<TwoFactorCodeRequest>()
+                    .response::<RecoveryCodesResponse>(StatusCode::OK, "New recovery codes")
+                    .error(StatusCode::BAD_REQUEST, "Invalid code")
+                    .error(
+                        StatusCode::CONFLICT,
+                        "Two-factor authentication not enabled",
+                    ),
+            ),
+        )
+        .get(
+            "/users/me/passkeys",
+            authenticated(
+                Operation::new("List passkeys")
+                    .operation_id("listPasskeys")
+                    .tag("users")
+                    .response::<PasskeyListResponse>(StatusCode::OK, "Registered passkeys"),
+            ),
+        )
+        .post(
+            "/users/me/passkeys/options",
+            authenticated(
+                Operation::new("Start passkey registration")
+                    .operation_id("passkeyRegistrationOptions")
+                    .tag("users")
+                    .description(
+                        "Returns `PublicKeyCredentialCreationOptionsJSON` with a fresh challenge \
+                         for `navigator.credentials.create()`.",
+                    )
+                    .request_body::<PasskeyOptionsRequest>()
+                    .response::<PasskeyCreationOptions>(StatusCode::OK, "Creation options")
+                    .error(
+                        StatusCode::BAD_REQUEST,
+                        "Validation failed or wrong password",
+                    ),
+            ),
+        )
+        .post(
+            "/users/me/passkeys",
+            authenticated(
+                Operation::new("Register passkey")
+                    .operation_id("registerPasskey")
+                    .tag("users")
+                    .description(
+                        "Verifies the created credential (`credential.toJSON()`) against the \
+                         challenge from `/users/me/passkeys/options` and stores the passkey.",
+                    )
+                    .request_body::<RegisterPasskeyRequest>()
+                    .response::<PasskeyInfo>(StatusCode::OK, "Passkey registered")
+                    .error(
+                        StatusCode::BAD_REQUEST,
+                        "Invalid challenge, origin or credential, or unsupported algorithm",
+                    )
+                    .error(
+                        StatusCode::CONFLICT,
+                        "Passkey already registered or passkey limit reached",
+                    ),
+            ),
+        )
+        .delete(
+            "/users/me/passkeys/{id}",
+            authenticated(
+                Operation::new("Revoke passkey")
+                    .operation_id("deletePasskey")
+                    .tag("users")
+                    .path_param::<i64>("id", "Passkey ID")
+                    .response::<DeletePasskeyResponse>(StatusCode::OK, "Passkey revoked")
+                    .error(StatusCode::NOT_FOUND, "Passkey not found"),
+            ),
+        )
+        .get(
+            "/users/me/tokens",
+            authenticated(
+                Operation::new("List personal access tokens")
+                    .operation_id("listApiTokens")
+                    .tag("users")
+                    .description("Token secrets are never returned after creation.")
+                    .response::<ApiTokenListResponse>(StatusCode::OK, "Personal access tokens"),
+            ),
+        )
+        .post(
+            "/users/me/tokens",
+            authenticated(
+                Operation::new("Create personal access token")
+                    .operation_id("createApiToken")
+                    .tag("users")
+                    .description(
+                        "Returns the token once; send it as `Authorization: Bearer <token>` to \
+                         operations that accept one of its scopes. Requires a session: personal \
+                         access tokens cannot manage tokens.",
+                    )
+                    .request_body::<CreateApiTokenRequest>()
+                    .response::<CreatedApiToken>(StatusCode::OK, "Token created")
+                    .error(
+                        StatusCode::BAD_REQUEST,
+                        "Validation failed or unknown scope",
+                    )
+                    .error(StatusCode::CONFLICT, "Token limit reached"),
+            ),
+        )
+        .delete(
+            "/users/me/tokens/{id}",
+            authenticated(
+                Operation::new("Revoke personal access token")
+                    .operation_id("deleteApiToken")
+                    .tag("users")
+                    .path_param::<i64>("id", "Token ID")
+                    .response::<DeleteApiTokenResponse>(StatusCode::OK, "Token revoked")
+                    .error(StatusCode::NOT_FOUND, "Token not found"),
+            ),
+        )
+        .get(
+            "/users/me/identities",
+            authenticated(
+                Operation::new("List linked accounts")
+                    .operation_id("listIdentities")
+                    .tag("users")
+                    .response::<IdentityListResponse>(
+                        StatusCode::OK,
+                        "External accounts linked by logging in with an OpenID Connect provider",
+                    ),
+            ),
+        )
+        .delete(
+            "/users/me/identities/{id}",
+            authenticated(
+                Operation::new("Unlink account")
+                    .operation_id("deleteIdentity")
+                    .tag("users")
+                    .path_param::<i64>("id", "Linked account ID")
+                    .response::<DeleteIdentityResponse>(StatusCode::OK, "Account unlinked")
+                    .error(StatusCode::NOT_FOUND, "Linked account not found"),
+            ),
+        )
+        .get(
+            "/users",
+            admin_scoped(
+                Operation::new("List users")
+                    .operation_id("listUsers")
+                    .query::<ListUsersQuery>()
+                    .response::<PaginatedUsersResponse>(StatusCode::OK, "One page of users"),
+                "users:read",
+            ),
+        )
+        .post(
+            "/users",
+            admin_scoped(
+                Operation::new("Create user")
+                    .operation_id("createUser")
+                    .description("Admin-created users are auto-verified.")
+                    .request_body::<CreateUserRequest>()
+                    .response::<UserResponse>(StatusCode::OK, "User created")
+                    .error(StatusCode::BAD_REQUEST, "Validation failed")
+                    .error(StatusCode::CONFLICT, "Email already registered"),
+                "users:write",
+            ),
+        )
+        .get(
+            "/users/{id}",
+            admin_scoped(
+                user_id(
+                    Operation::new("Get user")
+                        .operation_id("getUser")
+                        .response::<UserResponse>(StatusCode::OK, "User"),
+                ),
+                "users:read",
+            ),
+        )
+        .put(
+            "/users/{id}",
+            admin_scoped(
+                user_id(
+                    Operation::new("Update user")
+                        .operation_id("updateUser")
+                        .request_body::<UpdateUserRequest>()
+                        .response::<UserResponse>(StatusCode::OK, "Updated user")
+                        .error(StatusCode::BAD_REQUEST, "Validation failed"),
+                ),
+                "users:write",
+            ),
+        )
+        .delete(
+            "/users/{id}",
+            admin_scoped(
+                user_id(
+                    Operation::new("Delete user")
+                        .operation_id("deleteUser")
+                        .response::<DeleteUserResponse>(StatusCode::OK, "User deleted"),
+                ),
+                "users:write",
+            ),
+        )
+        .put(
+            "/users/{id}/balance",
+            admin_scoped(
+                user_id(
+                    Operation::new("Set balance")
+                        .operation_id("setBalance")
+                        .request_body::<SetBalanceRequest>()
+                        .response::<SetBalanceResponse>(StatusCode::OK, "Balance updated"),
+                ),
+                "users:write",
+            ),
+        )
+        .post(
+            "/users/{id}/balance/adjust",
+            admin_scoped(
+                user_id(
+                    Operation::new("Adjust balance")
+                        .operation_id("adjustBalance")
+                        .description(
+                            "Positive amounts increase the balance, negative amounts decrease it.",
+                        )
+                        .request_body::<AdjustBalanceRequest>()
+                        .response::<AdjustBalanceResponse>(StatusCode::OK, "Balance adjusted"),
+                ),
+                "users:write",
+            ),
+        )
+        .delete(
+            "/users/{id}/2fa",
+            admin(user_id(
+                Operation::new("Reset two-factor authentication")
+                    .operation_id("resetTwoFactor")
+                    .description(
+                        "Removes the user's TOTP secret, recovery codes and pending login \
+                         challenges, e.g. after a lost device.",
+                    )
+                    .response::<ResetTwoFactorResponse>(StatusCode::OK, "Two-factor removed"),
+            )),
+        )
+        .get(
+            "/admin/health",
+            admin(
+                Operation::new("Dependency health report")
+                    .operation_id("adminHealth")
+                    .description(
+                        "Checks every dependency; returns 503 when the instance is not ready.",
+                    )
+                    .response::<HealthReportResponse>(StatusCode::OK, "Instance is ready")
+                    .response::<HealthReportResponse>(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "A required dependency is down, or shutdown in progress",
+                    ),
+            ),
+        )
+        .get(
+            "/admin/jobs",
+            admin(
+                Operation::new("List scheduled jobs")
+                    .operation_id("listJobs")
+                    .description("Schedule, status and last run of every background job.")
+                    .response::<JobListResponse>(StatusCode::OK, "Scheduled jobs"),
+            ),
+        )
+        .post(
+            "/admin/jobs/{name}/run",
+            admin(
+                Operation::new("Run job now")
+                    .operation_id("runJob")
+                    .description(
+                        "Starts the job in the background on the instance serving the request, \
+                         regardless of its schedule.",
+                    )
+                    .path_param::<String>("name", "Job name")
+                    .response::<JobStatus>(StatusCode::ACCEPTED, "Run started")
+                    .error(StatusCode::NOT_FOUND, "Unknown job")
+                    .error(StatusCode::CONFLICT, "Job is already running"),
+            ),
+        )
+        .get(
+            "/admin/queue",
+            admin(
+                Operation::new("List queue jobs")
+                    .operation_id("listQueueJobs")
+                    .description("Jobs of the durable job queue, newest first.")
+                    .query::<ListQueueQuery>()
+                    .response::<PaginatedJobsResponse>(StatusCode::OK, "One page of jobs")
+                    .error(StatusCode::BAD_REQUEST, "Invalid status filter"),
+            ),
+        )
+        .get(
+            "/admin/queue/{id}",
+            admin(
+                Operation::new("Get queue job")
+                    .operation_id("getQueueJob")
+                    .path_param::<i64>("id", "Job ID")
+                    .response::<JobRecord>(StatusCode::OK, "Job")
+                    .error(StatusCode::NOT_FOUND, "Job not found"),
+            ),
+        )
+        .post(
+            "/admin/queue/{id}/retry",
+            admin(
+                Operation::new("Retry dead job")
+                    .operation_id("retryQueueJob")
+                    .description("Moves a dead job back to the queue with a fresh set of attempts.")
+                    .path_param::<i64>("id", "Job ID")
+                    .response::<JobRecord>(StatusCode::ACCEPTED, "Job re-queued")
+                    .error(StatusCode::NOT_FOUND, "Job not found")
+                    .error(StatusCode::CONFLICT, "Job is not dead"),
+            ),
+        )
+        .get(
+            "/admin/log-level",
+            system(
+                Operation::new("Get log filter")
+                    .operation_id("getLogLevel")
+                    .response::<LogLevelStatus>(StatusCode::OK, "Active filter of this instance"),
+            ),
+        )
+        .put(
+            "/admin/log-level",
+            system(
+                Operation::new("Set log filter")
+                    .operation_id("setLogLevel")
+                    .description(
+                        "Replaces the tracing filter of the instance serving the request; \
+                         with `ttl_secs` it reverts to the startup filter afterwards.",
+                    )
+                    .request_body::<SetLogLevelRequest>()
+                    .response::<LogLevelStatus>(StatusCode::OK, "Filter applied")
+                    .error(StatusCode::BAD_REQUEST, "Invalid filter directive"),
+            ),
+        )
+        .delete(
+            "/admin/log-level",
+            system(
+                Operation::new("Reset log filter")
+                    .operation_id("resetLogLevel")
+                    .response::<LogLevelStatus>(StatusCode::OK, "Startup filter restored"),
+            ),
+        )
+        .post(
+            "/oauth/authorize/details",
+            authenticated(
+                Operation::new("Get authorization request")
+                    .operation_id("oauthAuthorizationDetails")
+                    .tag("oauth")
+                    .description(
+                        "Client and scopes of the request the consent page was opened with \
+                         (`?request=` from `/api/public/oauth/authorize`).",
+                    )
+                    .request_body::<AuthorizationRequest>()
+                    .response::<AuthorizationDetails>(StatusCode::OK, "Pending request")
+                    .error(
+                        StatusCode::BAD_REQUEST,
+                        "Invalid or expired request, or client deleted",
+                    ),
+            ),
+        )
+        .post(
+            "/oauth/authorize/decision",
+            authenticated(
+                Operation::new("Allow or deny authorization request")
+                    .operation_id("oauthAuthorizationDecision")
+                    .tag("oauth")
+                    .description(
+                        "Approving issues an authorization code and remembers the consent. The \
+                         browser should navigate to `redirect_to`.",
+                    )
+                    .request_body::<AuthorizationDecisionRequest>()
+                    .response::<AuthorizationDecisionResponse>(
+                        StatusCode::OK,
+                        "Client redirect with the code or `error=access_denied`",
+                    )
+                    .error(
+                        StatusCode::BAD_REQUEST,
+                        "Invalid or expired request, or client deleted",
+                    ),
+            ),
+        )
+        .get(
+            "/oauth/userinfo",
+            scoped(
+                Operation::new("OpenID Connect userinfo")
+                    .operation_id("oauthUserinfo")
+                    .tag("oauth")
+                    .description("`name` needs the `profile` scope, `email` the `email` scope.")
+                    .response::<UserInfo>(StatusCode::OK, "Claims about the user")
+                    .error(
+                        StatusCode::FORBIDDEN,
+                        "Access token lacks the `openid` scope",
+                    ),
+                "openid",
+            ),
+        )
+        .get(
+            "/admin/oauth/clients",
+            admin(
+                Operation::new("List OAuth clients")
+                    .operation_id("listOAuthClients")
+                    .response::<OAuthClientListResponse>(StatusCode::OK, "Registered clients"),
+            ),
+        )
+        .post(
+            "/admin/oauth/clients",
+            admin(
+                Operation::new("Register OAuth client")
+                    .operation_id("createOAuthClient")
+                    .description(
+                        "Confidential clients get a `client_secret`, returned only here. \
+                         Client-credentials tokens act as the registering admin. Tokens of \
+                         every grant have a plain user's role unless `admin_access` is set.",
+                    )
+                    .request_body::<CreateOAuthClientRequest>()
+                    .response::<CreatedClient>(StatusCode::OK, "Client registered")
+                    .error(
+                        StatusCode::BAD_REQUEST,
+                        "Invalid redirect URI, grant type or scope",
+                    ),
+            ),
+        )
+        .put(
+            "/admin/oauth/clients/{client_id}",
+            admin(
+                Operation::new("Update OAuth client")
+                    .operation_id("updateOAuthClient")
+                    .path_param::<String>("client_id", "Client ID")
+                    .request_body::<ClientSettings>()
+                    .response::<ClientInfo>(StatusCode::OK, "Updated client")
+                    .error(
+                        StatusCode::BAD_REQUEST,
+                        "Invalid redirect URI, grant type or scope",
+                    )
+                    .error(StatusCode::NOT_FOUND, "Client not found"),
+            ),
+        )
+        .delete(
+            "/admin/oauth/clients/{client_id}",
+            admin(
+                Operation::new("Delete OAuth client")
+                    .operation_id("deleteOAuthClient")
+                    .description("Also revokes its codes, refresh tokens and consents.")
+                    .path_param::<String>("client_id", "Client ID")
+                    .response::<DeleteOAuthClientResponse>(StatusCode::OK, "Client deleted")
+                    .error(StatusCode::NOT_FOUND, "Client not found"),
+            ),
+        )
+        .post(
+            "/admin/oauth/clients/{client_id}/secret",
+            admin(
+                Operation::new("Rotate OAuth client secret")
+                    .operation_id("rotateOAuthClientSecret")
+                    .description("The old secret stops working immediately.")
+                    .path_param::<String>("client_id", "Client ID")
+                    .response::<ClientSecret>(StatusCode::OK, "New secret, shown once")
+                    .error(StatusCode::BAD_REQUEST, "Public clients have no secret")
+                    .error(StatusCode::NOT_FOUND, "Client not found"),
+            ),
+        )
+}
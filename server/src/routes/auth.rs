@@ -2,11 +2,24 @@ use crate::AppRouter;
 use crate::routes::helpers::{apply_rate_limit, get, post};
 
 use crate::handlers::auth::{
-    forgot_password, login, logout, refresh, register, resend_code, reset_password, verify_email,
+    ForgotPasswordRequestBody, ForgotPasswordResponse, LoginRequestBody, LogoutResponse,
+    OidcAuthorizeQuery, OidcProvidersResponse, PasskeyLoginRequestBody, RefreshResponse,
+    RegisterRequestBody, RegisterResponse, ResendCodeRequestBody, ResendCodeResponse,
+    ResetPasswordRequestBody, ResetPasswordResponse, TwoFactorChallengeResponse,
+    TwoFactorLoginRequestBody, VerifyEmailRequestBody, VerifyEmailResponse, forgot_password, login,
+    login_two_factor, logout, oidc_authorize, oidc_callback, oidc_providers, passkey_login,
+    passkey_login_options, refresh, register, resend_code, reset_password, verify_email,
+};
+use crate::handlers::wechat::{
+    WechatEnabledResponse, WxLoginRequestBody, WxLoginResponse, wechat_enabled, wx_login,
 };
-use crate::handlers::wechat::{wechat_enabled, wx_login};
 use crate::middlewares::RateLimitGuard;
+use crate::services::auth::LoginResponse;
+use crate::services::oidc::OidcCallback;
+use crate::services::webauthn::PasskeyRequestOptions;
 use distributed_ratelimit::RedisRateLimiter;
+use http::StatusCode;
+use webshelf_runtime::{OpenApi, Operation};
 
 /// Build auth routes with rate limiting.
 ///
@@ -32,6 +45,30 @@ pub fn auth_routes(rate_limiter: RedisRateLimiter) -> AppRouter {
             AppRouter::new().route("/login", post(login)),
             make_guard("login", 20, Some(5)),
         ))
+        .merge(apply_rate_limit(
+            AppRouter::new().route("/login/2fa", post(login_two_factor)),
+            make_guard("login-2fa", 20, None),
+        ))
+        .merge(apply_rate_limit(
+            AppRouter::new().route("/passkey/options", post(passkey_login_options)),
+            make_guard("passkey-options", 30, None),
+        ))
+        .merge(apply_rate_limit(
+            AppRouter::new().route("/passkey/login", post(passkey_login)),
+            make_guard("passkey-login", 20, None),
+        ))
+        .merge(apply_rate_limit(
+            AppRouter::new().route("/oidc/providers", get(oidc_providers)),
+            make_guard("oidc-providers", 60, None),
+        ))
+        .merge(apply_rate_limit(
+            AppRouter::new().route("/oidc/{provider}/authorize", get(oidc_authorize)),
+            make_guard("oidc-authorize", 30, None),
+        ))
+        .merge(apply_rate_limit(
+            AppRouter::new().route("/oidc/{provider}/callback", get(oidc_callback)),
+            make_guard("oidc-callback", 30, None),
+        ))
         .merge(apply_rate_limit(
             AppRouter::new().route("/register", post(register)),
             make_guard("register", 10, None),
@@ -69,3 +106,180 @@ pub fn auth_routes(rate_limiter: RedisRateLimiter) -> AppRouter {
             make_guard("wx-login", 20, None),
         ))
 }
+
+/// OpenAPI description of [`auth_routes`] (paths relative to the `/api/public/auth` nest).
+pub fn auth_docs() -> OpenApi {
+    // Every auth endpoint sits behind a rate limiter.
+    let auth = |summary: &str, id: &str| {
+        Operation::new(summary)
+            .operation_id(id)
+            .tag("auth")
+            .error(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded")
+    };
+
+    OpenApi::default()
+        .post(
+            "/login",
+            auth("Log in", "login")
+                .description("Sets the JWT, refresh-token and expiry cookies on success.")
+                .request_body::<LoginRequestBody>()
+                .response::<LoginResponse>(StatusCode::OK, "Logged in")
+                .response::<TwoFactorChallengeResponse>(
+                    StatusCode::ACCEPTED,
+                    "Password accepted; two-factor code required at `/login/2fa`",
+                )
+                .error(StatusCode::BAD_REQUEST, "Validation failed")
+                .error(
+                    StatusCode::UNAUTHORIZED,
+                    "Invalid credentials or unverified email",
+                ),
+        )
+        .post(
+            "/login/2fa",
+            auth("Complete two-factor login", "loginTwoFactor")
+                .description(
+                    "Exchanges the challenge token from a `202` login response and an \
+                     authenticator or recovery code for the session cookies.",
+                )
+                .request_body::<TwoFactorLoginRequestBody>()
+                .response::<LoginResponse>(StatusCode::OK, "Logged in")
+                .error(StatusCode::BAD_REQUEST, "Invalid code")
+                .error(
+                    StatusCode::UNAUTHORIZED,
+                    "Challenge expired, already used or out of attempts",
+                ),
+        )
+        .post(
+            "/passkey/options",
+            auth("Start passkey login", "passkeyLoginOptions")
+                .description(
+                    "Returns `PublicKeyCredentialRequestOptionsJSON` with a fresh challenge for \
+                     `navigator.credentials.get()`; any discoverable passkey of the site is accepted.",
+                )
+                .response::<PasskeyRequestOptions>(StatusCode::OK, "Request options"),
+        )
+        .post(
+            "/passkey/login",
+            auth("Log in with a passkey", "passkeyLogin")
+                .description(
+                    "Verifies the assertion (`credential.toJSON()`) and sets the same cookies as \
+                     `/login`. Accounts with two-factor authentication get a `202` challenge \
+                     unless the authenticator verified the user.",
+                )
+                .request_body::<PasskeyLoginRequestBody>()
+                .response::<LoginResponse>(StatusCode::OK, "Logged in")
+                .response::<TwoFactorChallengeResponse>(
+                    StatusCode::ACCEPTED,
+                    "Passkey accepted; two-factor code required at `/login/2fa`",
+                )
+                .error(
+                    StatusCode::BAD_REQUEST,
+                    "Invalid challenge, origin, signature or signature counter",
+                )
+                .error(StatusCode::UNAUTHORIZED, "Unknown passkey"),
+        )
+        .get(
+            "/oidc/providers",
+            auth("List login providers", "oidcProviders")
+                .response::<OidcProvidersResponse>(StatusCode::OK, "Enabled OpenID Connect providers"),
+        )
+        .get(
+            "/oidc/{provider}/authorize",
+            auth("Log in with a provider", "oidcAuthorize")
+                .description(
+                    "Browser navigation: sets the short-lived `webshelf_oidc` flow cookie and \
+                     redirects to the provider's authorization endpoint (code flow with PKCE). \
+                     Errors redirect to the login page with `#oidc_error=<code>`.",
+                )
+                .path_param::<String>("provider", "Provider key from `[oidc.providers.<key>]`")
+                .query::<OidcAuthorizeQuery>()
+                .response_empty(StatusCode::FOUND, "Redirect to the provider"),
+        )
+        .get(
+            "/oidc/{provider}/callback",
+            auth("Provider callback", "oidcCallback")
+                .description(
+                    "Redirect URI registered at the provider. Verifies the ID token, links or \
+                     creates the account, sets the same cookies as `/login` and redirects to \
+                     `oidc.success_redirect`. Accounts with two-factor authentication are sent \
+                     to the login page with `#two_factor=<challenge_token>`, failures with \
+                     `#oidc_error=<code>`.",
+                )
+                .path_param::<String>("provider", "Provider key from `[oidc.providers.<key>]`")
+                .query::<OidcCallback>()
+                .response_empty(StatusCode::FOUND, "Redirect to the web app"),
+        )
+        .post(
+            "/register",
+            auth("Register", "register")
+                .request_body::<RegisterRequestBody>()
+                .response::<RegisterResponse>(StatusCode::OK, "Account created")
+                .error(StatusCode::BAD_REQUEST, "Validation failed")
+                .error(StatusCode::CONFLICT, "Email already registered"),
+        )
+        .post(
+            "/verify-email",
+            auth("Verify email", "verifyEmail")
+                .request_body::<VerifyEmailRequestBody>()
+                .response::<VerifyEmailResponse>(StatusCode::OK, "Email verified")
+                .error(StatusCode::BAD_REQUEST, "Invalid or expired code"),
+        )
+        .post(
+            "/resend-code",
+            auth("Resend verification code", "resendCode")
+                .request_body::<ResendCodeRequestBody>()
+                .response::<ResendCodeResponse>(StatusCode::OK, "Code sent if the account exists")
+                .error(StatusCode::SERVICE_UNAVAILABLE, "Email service not configured"),
+        )
+        .post(
+            "/forgot-password",
+            auth("Request password reset", "forgotPassword")
+                .request_body::<ForgotPasswordRequestBody>()
+                .response::<ForgotPasswordResponse>(
+                    StatusCode::OK,
+                    "Reset code sent if the account exists",
+                )
+                .error(StatusCode::SERVICE_UNAVAILABLE, "Email service not configured"),
+        )
+        .post(
+            "/reset-password",
+            auth("Reset password", "resetPassword")
+                .description("Consumes the emailed code and logs the user in.")
+                .request_body::<ResetPasswordRequestBody>()
+                .response::<ResetPasswordResponse>(StatusCode::OK, "Password replaced")
+                .response::<TwoFactorChallengeResponse>(
+                    StatusCode::ACCEPTED,
+                    "Password replaced; two-factor code required at `/login/2fa`",
+                )
+                .error(StatusCode::BAD_REQUEST, "Invalid or expired code"),
+        )
+        .post(
+            "/refresh",
+            auth("Refresh JWT", "refresh")
+                .description("Exchanges the `webshelf_refresh` cookie for a new JWT (rotating the refresh token).")
+                .response::<RefreshResponse>(StatusCode::OK, "New JWT issued")
+                .error(StatusCode::UNAUTHORIZED, "Missing, expired or revoked refresh token"),
+        )
+        .post(
+            "/logout",
+            auth("Log out", "logout")
+                .description("Revokes the refresh token and clears auth cookies. Idempotent.")
+                .response::<LogoutResponse>(StatusCode::OK, "Logged out"),
+        )
+        .get(
+            "/wechat-enabled",
+            auth("WeChat login status", "wechatEnabled")
+                .response::<WechatEnabledResponse>(StatusCode::OK, "Whether WeChat login is enabled"),
+        )
+        .post(
+            "/wx-login",
+            auth("Log in with WeChat captcha", "wxLogin")
+                .request_body::<WxLoginRequestBody>()
+                .response::<WxLoginResponse>(StatusCode::OK, "Logged in")
+                .response::<TwoFactorChallengeResponse>(
+                    StatusCode::ACCEPTED,
+                    "Two-factor code required at `/login/2fa`",
+                )
+                .error(StatusCode::BAD_REQUEST, "WeChat login disabled or invalid captcha"),
+        )
+}
@@ -0,0 +1,37 @@
+use crate::AppRouter;
+use crate::handlers::health::{
+    LivenessResponse, ReadinessResponse, liveness_check, readiness_check,
+};
+use crate::routes::helpers::get;
+use http::StatusCode;
+use webshelf_runtime::{OpenApi, Operation};
+
+/// Orchestrator probes, mounted at the root (outside `/api` and its auth middleware).
+pub fn health_routes() -> AppRouter {
+    AppRouter::new()
+        .route("/livez", get(liveness_check))
+        .route("/readyz", get(readiness_check))
+}
+
+/// OpenAPI description of [`health_routes`].
+pub fn health_docs() -> OpenApi {
+    OpenApi::default()
+        .get(
+            "/livez",
+            Operation::new("Liveness probe")
+                .operation_id("livenessCheck")
+                .tag("system")
+                .response::<LivenessResponse>(StatusCode::OK, "Process is alive"),
+        )
+        .get(
+            "/readyz",
+            Operation::new("Readiness probe")
+                .operation_id("readinessCheck")
+                .tag("system")
+                .response::<ReadinessResponse>(StatusCode::OK, "Instance accepts traffic")
+                .response::<ReadinessResponse>(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "A required dependency is down, or graceful shutdown in progress",
+                ),
+        )
+}
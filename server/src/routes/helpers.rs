@@ -25,6 +25,22 @@ pub fn apply_rate_limit(route: AppRouter, guard: RateLimitGuard) -> AppRouter {
     return webshelf_salvo::with_rate_limit_hoop(route, guard);
 }
 
+// ── Unified custom middleware application ─────────────────────
+
+/// Apply a framework-agnostic [`Middleware`](crate::middlewares::Middleware) to a router.
+///
+/// The same middleware value runs unchanged on axum (`with_middleware`) and salvo
+/// (`with_middleware_hoop`).
+pub fn apply_middleware<M: crate::middlewares::Middleware>(
+    route: AppRouter,
+    middleware: M,
+) -> AppRouter {
+    #[cfg(not(feature = "webshelf-salvo"))]
+    return webshelf_axum::with_middleware(route, middleware);
+    #[cfg(feature = "webshelf-salvo")]
+    return webshelf_salvo::with_middleware_hoop(route, middleware);
+}
+
 // ── Unified admin guard application ──────────────────────────
 
 /// Apply admin-role middleware guard to a router.
@@ -0,0 +1,14 @@
+use crate::AppRouter;
+use crate::handlers::metrics::metrics_export;
+use crate::routes::helpers::get;
+use crate::utils::config::MetricsConfig;
+
+/// Prometheus scrape route (empty router when disabled).
+///
+/// Not part of the OpenAPI document, like the document routes themselves.
+pub fn metrics_routes(config: &MetricsConfig) -> AppRouter {
+    if !config.enabled {
+        return AppRouter::new();
+    }
+    AppRouter::new().route(&config.path, get(metrics_export))
+}
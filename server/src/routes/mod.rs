@@ -1,6 +1,14 @@
 pub mod api;
 pub mod auth;
+pub mod health;
 pub mod helpers;
+pub mod metrics;
+pub mod oauth;
+pub mod openapi;
 
 pub use api::api_routes;
 pub use auth::auth_routes;
+pub use health::health_routes;
+pub use metrics::metrics_routes;
+pub use oauth::oauth_routes;
+pub use openapi::{openapi_doc, openapi_routes};
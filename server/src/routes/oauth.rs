@@ -0,0 +1,102 @@
+use crate::AppRouter;
+use crate::routes::helpers::{apply_rate_limit, get, post};
+
+use crate::handlers::oauth::{TokenErrorResponse, authorize, jwks, openid_configuration, token};
+use crate::middlewares::RateLimitGuard;
+use crate::services::oauth::{
+    AuthorizeParams, JwkSetDocument, ProviderMetadata, TokenParams, TokenResponse,
+};
+use distributed_ratelimit::RedisRateLimiter;
+use http::StatusCode;
+use webshelf_runtime::{OpenApi, Operation};
+
+/// Build the public endpoints of the OAuth 2.0 / OpenID Connect authorization server.
+///
+/// Same 600s windows as [`crate::routes::auth_routes`].
+pub fn oauth_routes(rate_limiter: RedisRateLimiter) -> AppRouter {
+    let make_guard = |key_prefix: &'static str, ip_max_requests: u64| RateLimitGuard {
+        limiter: rate_limiter.clone(),
+        ip_max_requests,
+        ip_window_seconds: 600,
+        email_max_requests: None,
+        email_window_seconds: 600,
+        key_prefix,
+    };
+
+    AppRouter::new()
+        .merge(apply_rate_limit(
+            AppRouter::new()
+                .route(
+                    "/.well-known/openid-configuration",
+                    get(openid_configuration),
+                )
+                .route("/jwks", get(jwks)),
+            make_guard("oauth-metadata", 300),
+        ))
+        .merge(apply_rate_limit(
+            AppRouter::new().route("/authorize", get(authorize)),
+            make_guard("oauth-authorize", 60),
+        ))
+        .merge(apply_rate_limit(
+            AppRouter::new().route("/token", post(token)),
+            make_guard("oauth-token", 120),
+        ))
+}
+
+/// OpenAPI description of [`oauth_routes`] (paths relative to the `/api/public/oauth` nest).
+pub fn oauth_docs() -> OpenApi {
+    let oauth = |summary: &str, id: &str| {
+        Operation::new(summary)
+            .operation_id(id)
+            .tag("oauth")
+            .error(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded")
+    };
+
+    OpenApi::default()
+        .get(
+            "/.well-known/openid-configuration",
+            oauth("OpenID Provider metadata", "oauthDiscovery")
+                .response::<ProviderMetadata>(StatusCode::OK, "Discovery document"),
+        )
+        .get(
+            "/jwks",
+            oauth("Token signing keys", "oauthJwks")
+                .response::<JwkSetDocument>(StatusCode::OK, "Public keys of ID and access tokens"),
+        )
+        .get(
+            "/authorize",
+            oauth("Authorization endpoint", "oauthAuthorize")
+                .description(
+                    "Browser navigation (code flow, PKCE S256 required). Valid requests redirect \
+                     to `oauth.consent_url?request=<token>`; errors after the client and \
+                     redirect URI were verified redirect to the client with `error`, `state` \
+                     and `iss`.",
+                )
+                .query::<AuthorizeParams>()
+                .response_empty(
+                    StatusCode::FOUND,
+                    "Redirect to the consent page or the client",
+                )
+                .error(
+                    StatusCode::BAD_REQUEST,
+                    "Unknown client or unregistered redirect URI",
+                ),
+        )
+        .post(
+            "/token",
+            oauth("Token endpoint", "oauthToken")
+                .description(
+                    "Form-encoded (JSON also accepted). Grants: `authorization_code` (with \
+                     `code_verifier`), `refresh_token` (rotated on use) and \
+                     `client_credentials`. Confidential clients authenticate with HTTP Basic \
+                     or `client_secret` in the form. Errors follow RFC 6749 §5.2.",
+                )
+                .request_body::<TokenParams>()
+                .response::<TokenResponse>(StatusCode::OK, "Tokens issued")
+                .response::<TokenErrorResponse>(StatusCode::BAD_REQUEST, "Invalid grant or request")
+                .response::<TokenErrorResponse>(
+                    StatusCode::UNAUTHORIZED,
+                    "Client authentication failed",
+                ),
+        )
+}
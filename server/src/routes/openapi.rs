@@ -0,0 +1,81 @@
+//! OpenAPI document for the whole server.
+//!
+//! Each route module describes its own operations (`api::api_docs`, `auth::auth_docs`,
+//! `oauth::oauth_docs`, `health::health_docs`) with
+//! paths relative to its router; this module nests them under the same prefixes that
+//! `bootstrap::build_app_router` uses, so the document mirrors the registered routes.
+//! The OpenAPI integration tests check both directions: every documented operation is
+//! routed, and every registered route (listed from the salvo route tree) is documented.
+
+use http::StatusCode;
+use serde_json::json;
+use webshelf_runtime::{OpenApi, Operation};
+
+use crate::AppRouter;
+use crate::handlers::docs::{docs_ui, openapi_json};
+use crate::middlewares::JWT_COOKIE;
+use crate::routes::helpers::get;
+use crate::routes::{api, auth, health, oauth};
+use crate::utils::config::OpenApiConfig;
+
+const BEARER_AUTH: &str = "bearerAuth";
+const COOKIE_AUTH: &str = "cookieAuth";
+const OAUTH2_AUTH: &str = "oauth2";
+
+/// Mark an operation as requiring a JWT (Bearer header or `webshelf_jwt` cookie).
+///
+/// OAuth access tokens and personal access tokens are only accepted where the operation
+/// says so (see [`scoped`]).
+pub(crate) fn authenticated(op: Operation) -> Operation {
+    op.security(BEARER_AUTH)
+        .security(COOKIE_AUTH)
+        .error(StatusCode::UNAUTHORIZED, "Missing or invalid credentials")
+}
+
+/// Mark an operation as requiring a JWT, or an OAuth / personal access token granted `scope`.
+pub(crate) fn scoped(op: Operation, scope: &str) -> Operation {
+    authenticated(op).security_scopes(OAUTH2_AUTH, &[scope])
+}
+
+/// Build the complete OpenAPI document.
+pub fn openapi_doc() -> OpenApi {
+    OpenApi::new("webshelf", env!("CARGO_PKG_VERSION"))
+        .description("The best way to develop your web service with one click.")
+        .security_scheme(
+            BEARER_AUTH,
+            json!({
+                "type": "http",
+                "scheme": "bearer",
+                "bearerFormat": "JWT",
+                "description": "Session JWT, or a personal access token (`wst_…`) from \
+                                `/api/users/me/tokens` on operations that accept its scope",
+            }),
+        )
+        .security_scheme(
+            COOKIE_AUTH,
+            json!({ "type": "apiKey", "in": "cookie", "name": JWT_COOKIE }),
+        )
+        .security_scheme(
+            OAUTH2_AUTH,
+            json!({
+                "type": "openIdConnect",
+                "openIdConnectUrl": "/api/public/oauth/.well-known/openid-configuration",
+            }),
+        )
+        .nest("/api", api::api_docs())
+        .nest("/api/public/auth", auth::auth_docs())
+        .nest("/api/public/oauth", oauth::oauth_docs())
+        .merge(health::health_docs())
+}
+
+/// Routes serving the document and the UI page (empty router when disabled).
+///
+/// The configured paths must not collide with application routes.
+pub fn openapi_routes(config: &OpenApiConfig) -> AppRouter {
+    if !config.enabled {
+        return AppRouter::new();
+    }
+    AppRouter::new()
+        .route(&config.path, get(openapi_json))
+        .route(&config.ui_path, get(docs_ui))
+}
@@ -0,0 +1,324 @@
+//! Personal access tokens: user-owned API keys for scripts and service-to-service calls.
+//!
+//! - A token is `wst_` followed by 64 hex characters. Only its SHA-256 hash is stored, like
+//!   refresh tokens in [`crate::services::auth`]; the first characters are kept as `prefix`
+//!   so users can tell their keys apart. The full token is shown once, at creation.
+//! - Tokens carry API scopes ([`API_SCOPES`]) and are accepted as bearer tokens by
+//!   `auth_middleware`, which then sets [`AuthUser::scopes`]: endpoints that do not accept
+//!   the scope reject them, as they do OAuth access tokens.
+//! - Tokens do not carry the user's `token_version`, so bumping it does not reach them.
+//!   Signing out everywhere (`logout-all`, `webshelf revoke-sessions`) and the operator
+//!   `webshelf reset-password` delete them instead; a self-service password change keeps them.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use schemars::JsonSchema;
+use sea_orm::{
+    ActiveValue::NotSet, ColumnTrait, ConnectionTrait, DatabaseBackend, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, Statement,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use webshelf_runtime::AuthUser;
+
+use crate::repositories::api_token::{
+    ActiveModel as ApiTokenActiveModel, Column as ApiTokenColumn, Entity as ApiTokenEntity,
+    Model as ApiTokenModel,
+};
+use crate::repositories::user::Entity as UserEntity;
+use crate::services::oauth::API_SCOPES;
+use crate::utils::db_router::AutoRouter;
+
+/// Tokens a single account may hold.
+pub const MAX_TOKENS_PER_USER: u64 = 50;
+
+/// Every personal access token starts with this.
+pub const TOKEN_PREFIX: &str = "wst_";
+
+const SECRET_BYTES: usize = 32;
+
+/// Hex characters of the secret kept in clear as `prefix`.
+const PREFIX_HEX_CHARS: usize = 8;
+
+/// `last_used_at` / `last_used_ip` are written at most this often for the same address.
+const LAST_USED_INTERVAL_SECS: i64 = 60;
+
+/// Typed errors for personal access token operations
+#[derive(Debug, thiserror::Error)]
+pub enum ApiTokenError {
+    #[error("At least one scope is required")]
+    MissingScope,
+    #[error("Unknown scope: {0}")]
+    InvalidScope(String),
+    #[error("Token limit of {MAX_TOKENS_PER_USER} reached")]
+    TooManyTokens,
+    #[error("Token not found")]
+    NotFound,
+    #[error("Invalid or expired token")]
+    InvalidToken,
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+/// A personal access token as listed to its owner (never includes the secret).
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApiTokenInfo {
+    pub id: i64,
+    pub name: String,
+    /// Start of the token, e.g. `wst_3f9a1c2b`
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    /// Absent for tokens that do not expire
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub last_used_ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiTokenModel> for ApiTokenInfo {
+    fn from(row: ApiTokenModel) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            prefix: row.prefix,
+            scopes: serde_json::from_value(row.scopes).unwrap_or_default(),
+            expires_at: row.expires_at,
+            last_used_at: row.last_used_at,
+            last_used_ip: row.last_used_ip,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// A newly created token, including the secret (shown only this once).
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub info: ApiTokenInfo,
+    /// Send as `Authorization: Bearer <token>`
+    pub token: String,
+}
+
+/// Personal access token service.
+#[derive(Clone)]
+pub struct ApiTokenService {
+    db: Arc<AutoRouter>,
+}
+
+impl ApiTokenService {
+    pub fn new(db: Arc<AutoRouter>) -> Self {
+        Self { db }
+    }
+
+    /// Tokens of `user_id`, oldest first.
+    pub async fn list(&self, user_id: i64) -> Result<Vec<ApiTokenInfo>, ApiTokenError> {
+        let rows = ApiTokenEntity::find()
+            .filter(ApiTokenColumn::UserId.eq(user_id))
+            .order_by_asc(ApiTokenColumn::Id)
+            .all(self.db.write_conn())
+            .await
+            .context("Failed to query API tokens")?;
+        Ok(rows.into_iter().map(ApiTokenInfo::from).collect())
+    }
+
+    /// Create a token for `user_id`; `expires_in_days: None` never expires.
+    pub async fn create(
+        &self,
+        user_id: i64,
+        name: &str,
+        scopes: &[String],
+        expires_in_days: Option<u32>,
+    ) -> Result<CreatedApiToken, ApiTokenError> {
+        let scopes = normalize_scopes(scopes)?;
+        let count = ApiTokenEntity::find()
+            .filter(ApiTokenColumn::UserId.eq(user_id))
+            .count(self.db.write_conn())
+            .await
+            .context("Failed to count API tokens")?;
+        if count >= MAX_TOKENS_PER_USER {
+            return Err(ApiTokenError::TooManyTokens);
+        }
+
+        let (token, prefix) = generate_token();
+        let now = Utc::now();
+        let row = ApiTokenEntity::insert(ApiTokenActiveModel {
+            id: NotSet,
+            user_id: Set(user_id),
+            name: Set(name.to_string()),
+            prefix: Set(prefix),
+            token_hash: Set(hash_token(&token)),
+            scopes: Set(serde_json::json!(scopes)),
+            expires_at: Set(expires_in_days.map(|days| now + Duration::days(days.into()))),
+            last_used_at: Set(None),
+            last_used_ip: Set(None),
+            created_at: Set(now),
+        })
+        .exec_with_returning(self.db.write_conn())
+        .await
+        .context("Failed to store API token")?;
+
+        tracing::info!(user_id, token_id = row.id, "API token created");
+        Ok(CreatedApiToken {
+            info: row.into(),
+            token,
+        })
+    }
+
+    /// Delete one of `user_id`'s tokens; it stops working immediately.
+    pub async fn revoke(&self, user_id: i64, token_id: i64) -> Result<(), ApiTokenError> {
+        let result = ApiTokenEntity::delete_many()
+            .filter(ApiTokenColumn::Id.eq(token_id))
+            .filter(ApiTokenColumn::UserId.eq(user_id))
+            .exec(self.db.write_conn())
+            .await
+            .context("Failed to delete API token")?;
+        if result.rows_affected == 0 {
+            return Err(ApiTokenError::NotFound);
+        }
+        tracing::info!(user_id, token_id, "API token revoked");
+        Ok(())
+    }
+
+    /// Resolve a bearer token to its owner, limited to the token's scopes.
+    ///
+    /// Records when and from where the token was used.
+    pub async fn authenticate(
+        &self,
+        token: &str,
+        client_ip: Option<IpAddr>,
+    ) -> Result<AuthUser, ApiTokenError> {
+        // 查询走主库：撤销后立即失效
+        let row = ApiTokenEntity::find()
+            .filter(ApiTokenColumn::TokenHash.eq(hash_token(token)))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query API token")?
+            .ok_or(ApiTokenError::InvalidToken)?;
+        let now = Utc::now();
+        if row.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(ApiTokenError::InvalidToken);
+        }
+        let user = UserEntity::find_by_id(row.user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user")?
+            .ok_or(ApiTokenError::InvalidToken)?;
+
+        // 记录使用失败不影响本次请求
+        if let Err(e) = self.touch(row.id, client_ip).await {
+            tracing::warn!(token_id = row.id, "Failed to record API token use: {:?}", e);
+        }
+
+        Ok(AuthUser {
+            user_id: user.id.to_string(),
+            role: user.role,
+            exp: row
+                .expires_at
+                .map_or(0, |expires_at| expires_at.timestamp().max(0) as u64),
+            iat: row.created_at.timestamp().max(0) as u64,
+            token_version: user.token_version,
+            remember: false,
+            scopes: Some(serde_json::from_value(row.scopes).unwrap_or_default()),
+        })
+    }
+
+    /// Update `last_used_at` / `last_used_ip`, skipping writes for repeated calls from the
+    /// same address within [`LAST_USED_INTERVAL_SECS`].
+    async fn touch(&self, token_id: i64, client_ip: Option<IpAddr>) -> anyhow::Result<()> {
+        self.db
+            .write_conn()
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "UPDATE api_tokens SET last_used_at = NOW(), last_used_ip = $2 \
+                 WHERE id = $1 AND (last_used_at IS NULL \
+                 OR last_used_at < NOW() - make_interval(secs => $3) \
+                 OR last_used_ip IS DISTINCT FROM $2)",
+                [
+                    token_id.into(),
+                    client_ip.map(|ip| ip.to_string()).into(),
+                    (LAST_USED_INTERVAL_SECS as f64).into(),
+                ],
+            ))
+            .await
+            .context("Failed to update API token usage")?;
+        Ok(())
+    }
+}
+
+/// Whether a bearer token is a personal access token (by its prefix).
+pub fn is_api_token(token: &str) -> bool {
+    token.starts_with(TOKEN_PREFIX)
+}
+
+/// A fresh token and the prefix kept in clear.
+fn generate_token() -> (String, String) {
+    let mut bytes = [0u8; SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token = format!("{TOKEN_PREFIX}{}", hex::encode(bytes));
+    let prefix = token[..TOKEN_PREFIX.len() + PREFIX_HEX_CHARS].to_string();
+    (token, prefix)
+}
+
+fn hash_token(raw: &str) -> String {
+    hex::encode(Sha256::digest(raw.as_bytes()))
+}
+
+/// Check against [`API_SCOPES`], deduplicate and sort into catalogue order.
+fn normalize_scopes(scopes: &[String]) -> Result<Vec<String>, ApiTokenError> {
+    if let Some(unknown) = scopes.iter().find(|s| !API_SCOPES.contains(&s.as_str())) {
+        return Err(ApiTokenError::InvalidScope(unknown.clone()));
+    }
+    let scopes: Vec<String> = API_SCOPES
+        .iter()
+        .filter(|known| scopes.iter().any(|scope| scope == *known))
+        .map(|scope| scope.to_string())
+        .collect();
+    if scopes.is_empty() {
+        return Err(ApiTokenError::MissingScope);
+    }
+    Ok(scopes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_tokens_have_prefix_and_unique_secrets() {
+        let (token, prefix) = generate_token();
+        assert!(is_api_token(&token));
+        assert_eq!(token.len(), TOKEN_PREFIX.len() + SECRET_BYTES * 2);
+        assert!(token.starts_with(&prefix));
+        assert_eq!(prefix.len(), 12);
+        assert_ne!(generate_token().0, token);
+        assert_eq!(hash_token(&token).len(), 64);
+    }
+
+    #[test]
+    fn is_api_token_checks_prefix() {
+        assert!(is_api_token("wst_0123"));
+        assert!(!is_api_token("eyJhbGciOiJIUzI1NiJ9.e30.sig"));
+        assert!(!is_api_token("WST_0123"));
+    }
+
+    #[test]
+    fn normalize_scopes_validates_and_orders() {
+        let scopes = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            normalize_scopes(&scopes(&["users:write", "users:read", "users:write"])).unwrap(),
+            scopes(&["users:read", "users:write"])
+        );
+        assert!(matches!(
+            normalize_scopes(&scopes(&["openid"])),
+            Err(ApiTokenError::InvalidScope(s)) if s == "openid"
+        ));
+        assert!(matches!(
+            normalize_scopes(&[]),
+            Err(ApiTokenError::MissingScope)
+        ));
+    }
+}
@@ -1,13 +1,11 @@
-use crate::repositories::user::Entity as UserEntity;
+use crate::repositories::user::{Entity as UserEntity, Model as UserModel};
 use crate::utils::db_router::AutoRouter;
 use crate::utils::jwt::generate_token;
 use crate::utils::password::{hash_password, verify_password};
 use anyhow::Context;
 use rand::RngCore;
-use sea_orm::{
-    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
-    TransactionTrait,
-};
+use schemars::JsonSchema;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::sync::Arc;
@@ -40,8 +38,19 @@ pub struct LoginRequest {
     pub remember: bool,
 }
 
+/// Result of [`AuthService::login`]
+#[derive(Debug)]
+pub enum LoginOutcome {
+    LoggedIn(LoginResponse),
+    /// Password accepted; the account has two-factor authentication enabled
+    TwoFactorRequired {
+        user_id: i64,
+        remember: bool,
+    },
+}
+
 /// Login response with token
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct LoginResponse {
     pub token: String,
     pub token_type: String,
@@ -77,10 +86,10 @@ impl AuthService {
     /// regardless of whether the user exists, to prevent timing-based email
     /// enumeration attacks.
     ///
-    /// When `remember` is true, the JWT expiry is extended to
-    /// `jwt_remember_expiry_seconds` (default 30 days) instead of the
-    /// standard `jwt_expiry_seconds` (default 1 hour).
-    pub async fn login(&self, request: LoginRequest) -> Result<LoginResponse, AuthError> {
+    /// Accounts with two-factor authentication get
+    /// [`LoginOutcome::TwoFactorRequired`] instead of a session; the caller
+    /// issues it with [`AuthService::complete_login`] once the code is verified.
+    pub async fn login(&self, request: LoginRequest) -> Result<LoginOutcome, AuthError> {
         let email_normalized = request.email.to_lowercase();
         let user_result = UserEntity::find()
             .filter(crate::repositories::user::Column::Email.eq(&email_normalized))
@@ -128,7 +137,47 @@ impl AuthService {
             return Err(AuthError::InvalidCredentials);
         }
 
-        let jwt_expiry = if request.remember {
+        if crate::services::two_factor::has_two_factor(self.db.write_conn(), user.id).await? {
+            tracing::info!(
+                "Password accepted for user {}, two-factor code required",
+                user.id
+            );
+            return Ok(LoginOutcome::TwoFactorRequired {
+                user_id: user.id,
+                remember: request.remember,
+            });
+        }
+
+        self.issue_session(&user, request.remember)
+            .await
+            .map(LoginOutcome::LoggedIn)
+    }
+
+    /// Issue the session of a login whose second factor was verified.
+    pub async fn complete_login(
+        &self,
+        user_id: i64,
+        remember: bool,
+    ) -> Result<LoginResponse, AuthError> {
+        let user = UserEntity::find_by_id(user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user")?
+            .ok_or(AuthError::InvalidCredentials)?;
+        self.issue_session(&user, remember).await
+    }
+
+    /// Generate the JWT and, for "remember me" logins, the refresh token.
+    ///
+    /// When `remember` is true, the JWT expiry is extended to
+    /// `jwt_remember_expiry_seconds` (default 30 days) instead of the
+    /// standard `jwt_expiry_seconds` (default 1 hour).
+    async fn issue_session(
+        &self,
+        user: &UserModel,
+        remember: bool,
+    ) -> Result<LoginResponse, AuthError> {
+        let jwt_expiry = if remember {
             self.jwt_remember_expiry_seconds
         } else {
             self.jwt_expiry_seconds
@@ -139,7 +188,7 @@ impl AuthService {
             &user.role,
             &self.jwt_secret,
             jwt_expiry,
-            request.remember,
+            remember,
             user.token_version,
         )
         .map_err(|e| {
@@ -156,7 +205,7 @@ impl AuthService {
         // themselves logged in for months via the refresh endpoint). The
         // empty-string + zero-expires signals to the handler "do not set
         // a refresh cookie" without requiring a separate response variant.
-        let (raw_refresh_token, refresh_expires_in) = if request.remember {
+        let (raw_refresh_token, refresh_expires_in) = if remember {
             let (raw, hash) = Self::generate_refresh_token();
             tracing::info!("Refresh token generated for user {}", user.id);
             let now = SystemTime::now()
@@ -198,7 +247,7 @@ impl AuthService {
         tracing::info!(
             "User {} logged in successfully (remember={})",
             user.id,
-            request.remember
+            remember
         );
 
         Ok(LoginResponse {
@@ -206,7 +255,7 @@ impl AuthService {
             token_type: "Bearer".to_string(),
             expires_in: jwt_expiry,
             user_id: user.id.to_string(),
-            role: user.role,
+            role: user.role.clone(),
             refresh_token: raw_refresh_token,
             refresh_expires_in,
         })
@@ -308,7 +357,10 @@ impl AuthService {
     /// Used by `logout_all` to simultaneously invalidate:
     /// - Existing JWTs (via token_version increment)
     /// - Existing refresh tokens (via DELETE)
-    pub async fn revoke_all_sessions(&self, user_id: i64) -> Result<(), AuthError> {
+    /// - Personal access tokens (via DELETE), which do not carry a token_version
+    ///
+    /// Returns the number of personal access tokens deleted.
+    pub async fn revoke_all_sessions(&self, user_id: i64) -> Result<u64, AuthError> {
         use crate::repositories::refresh_token::Entity as RefreshTokenEntity;
         use sea_orm::{ColumnTrait, ConnectionTrait, DatabaseBackend, Statement, TransactionTrait};
 
@@ -334,11 +386,22 @@ impl AuthService {
         .await
         .context("Failed to increment token_version")?;
 
+        // 3. Delete personal access tokens — they are not bound to token_version
+        let api_tokens = txn
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "DELETE FROM api_tokens WHERE user_id = $1",
+                [user_id.into()],
+            ))
+            .await
+            .context("Failed to delete API tokens")?
+            .rows_affected();
+
         txn.commit()
             .await
             .context("Failed to commit revoke_all_sessions transaction")?;
 
-        Ok(())
+        Ok(api_tokens)
     }
 
     /// Atomically rotate a refresh token: validate the old one, delete it,
@@ -418,10 +481,10 @@ impl AuthService {
 
 /// Delete all expired refresh tokens from the database.
 ///
-/// Called once during server startup to prevent accumulation of stale rows.
+/// Run by the scheduled job `cleanup_refresh_tokens` to prevent accumulation of stale rows.
 /// Expired rows are never queried (all queries filter `expires_at > now()`),
 /// so cleanup is purely an operational concern to limit table bloat.
-pub async fn cleanup_expired_refresh_tokens(db: &DatabaseConnection) -> Result<u64, AuthError> {
+pub async fn cleanup_expired_refresh_tokens(db: &impl ConnectionTrait) -> Result<u64, AuthError> {
     use crate::repositories::refresh_token::{Column, Entity as RefreshTokenEntity};
 
     let now = chrono::Utc::now();
@@ -12,6 +12,8 @@
 //! - `connection_manager()` accessor allows `distributed-ratelimit` and the
 //!   lock service to **share the same connection pool**, eliminating the
 //!   previous fragmentation of three independent Redis connection lifecycles.
+//! - Each Redis operation runs in a `cache.*` client span (exported when
+//!   `[telemetry]` is enabled, see `utils::telemetry`).
 //!
 //! ## Graceful Degradation
 //!
@@ -26,6 +28,7 @@ use bb8_redis::RedisConnectionManager;
 use serde::{Serialize, de::DeserializeOwned};
 
 use crate::services::lock::AcquireResult;
+use crate::utils::metrics;
 
 /// Redis connection pool type alias.
 type RedisPool = Pool<RedisConnectionManager>;
@@ -183,6 +186,16 @@ impl CacheService {
     /// Returns `Ok(None)` when:
     /// - The key does not exist in Redis.
     /// - Redis is not available (no-op mode).
+    #[tracing::instrument(
+        name = "cache.get",
+        skip_all,
+        fields(
+            otel.kind = "client",
+            db.system = "redis",
+            cache.key = %key,
+            cache.hit = tracing::field::Empty
+        )
+    )]
     pub async fn get<T: DeserializeOwned>(&self, key: &str) -> CacheResult<Option<T>> {
         let mut conn = match self.conn().await {
             Some(c) => c,
@@ -194,6 +207,8 @@ impl CacheService {
             .query_async(&mut *conn)
             .await?;
 
+        metrics::cache_lookup(raw.is_some());
+        tracing::Span::current().record("cache.hit", raw.is_some());
         match raw {
             Some(s) => {
                 let val: T = serde_json::from_str(&s)
@@ -240,6 +255,7 @@ impl CacheService {
             }
             // 2. Check negative-cache marker (entity does not exist)
             if self.exists(&format!("{}:null", key)).await? {
+                metrics::cache_negative_hit();
                 return Err(CacheError::FallbackFailed(
                     "Entity does not exist (negative cache)".to_string(),
                 ));
@@ -336,6 +352,7 @@ impl CacheService {
         // 1b. Check negative-cache marker (entity does not exist)
         //     Prevents repeated lock acquisition for non-existent keys.
         if self.is_available() && self.exists(&format!("{}:null", key)).await? {
+            metrics::cache_negative_hit();
             return Err(CacheError::FallbackFailed(
                 "Entity does not exist (negative cache)".to_string(),
             ));
@@ -408,6 +425,11 @@ impl CacheService {
     // ── set / set_null / invalidate ──────────────────────────────────────
 
     /// Store a value with TTL.
+    #[tracing::instrument(
+        name = "cache.set",
+        skip_all,
+        fields(otel.kind = "client", db.system = "redis", cache.key = %key)
+    )]
     pub async fn set<T: Serialize>(&self, key: &str, val: &T, ttl: Duration) -> CacheResult<()> {
         let mut conn = match self.conn().await {
             Some(c) => c,
@@ -430,6 +452,11 @@ impl CacheService {
     /// Used when a database query confirms an entity does not exist. Prevents
     /// repeated cache-penetration queries for non-existent keys (e.g., deleted
     /// users).
+    #[tracing::instrument(
+        name = "cache.set_null",
+        skip_all,
+        fields(otel.kind = "client", db.system = "redis", cache.key = %key)
+    )]
     pub async fn set_null(&self, key: &str, ttl: Duration) -> CacheResult<()> {
         let mut conn = match self.conn().await {
             Some(c) => c,
@@ -445,6 +472,11 @@ impl CacheService {
     }
 
     /// Delete a key and its negative-cache marker.
+    #[tracing::instrument(
+        name = "cache.invalidate",
+        skip_all,
+        fields(otel.kind = "client", db.system = "redis", cache.key = %key)
+    )]
     pub async fn invalidate(&self, key: &str) -> CacheResult<()> {
         let mut conn = match self.conn().await {
             Some(c) => c,
@@ -504,6 +536,11 @@ impl CacheService {
     }
 
     /// Check if a key exists in the cache.
+    #[tracing::instrument(
+        name = "cache.exists",
+        skip_all,
+        fields(otel.kind = "client", db.system = "redis", cache.key = %key)
+    )]
     pub async fn exists(&self, key: &str) -> CacheResult<bool> {
         let mut conn = match self.conn().await {
             Some(c) => c,
@@ -0,0 +1,274 @@
+//! Dependency health checks behind `/readyz` and `/api/admin/health`.
+//!
+//! Every check yields a [`CheckStatus`]; `[health].readiness_requires` lists the components
+//! whose `down` status makes the instance not-ready. Optional dependencies that are not
+//! configured report `disabled`, partially available ones (e.g. some replicas circuit-broken)
+//! report `degraded` — neither fails readiness.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::AppState;
+use crate::utils::config::HealthComponent;
+
+/// Outcome of a single dependency check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Up,
+    /// Working with reduced capacity
+    Degraded,
+    Down,
+    /// Not configured
+    Disabled,
+}
+
+/// Result of one component check.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ComponentHealth {
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+impl ComponentHealth {
+    fn new(status: CheckStatus) -> Self {
+        Self {
+            status,
+            latency_ms: None,
+            error: None,
+            details: Value::Null,
+        }
+    }
+
+    fn with_details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+/// All component checks plus the readiness verdict.
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    pub ready: bool,
+    pub components: BTreeMap<HealthComponent, ComponentHealth>,
+}
+
+impl HealthReport {
+    /// Component statuses only, for the unauthenticated `/readyz` body.
+    pub fn statuses(&self) -> BTreeMap<HealthComponent, CheckStatus> {
+        self.components
+            .iter()
+            .map(|(&component, health)| (component, health.status))
+            .collect()
+    }
+}
+
+/// Run all checks concurrently and apply the readiness policy.
+pub async fn check(state: &AppState) -> HealthReport {
+    let timeout = Duration::from_millis(state.config.health.check_timeout_ms);
+    let (database, cache, email) = tokio::join!(
+        timed(timeout, state.db.ping_write()),
+        check_cache(state, timeout),
+        check_email(state),
+    );
+
+    let mut components = BTreeMap::new();
+    components.insert(HealthComponent::Database, database);
+    components.insert(HealthComponent::Replicas, check_replicas(state));
+    components.insert(HealthComponent::RateLimiter, check_rate_limiter(&cache));
+    components.insert(HealthComponent::Cache, cache);
+    components.insert(HealthComponent::Email, email);
+    components.insert(HealthComponent::Wechat, check_wechat(state));
+    components.insert(HealthComponent::Snowflake, check_snowflake());
+
+    let ready = is_ready(&components, &state.config.health.readiness_requires);
+    HealthReport { ready, components }
+}
+
+/// Readiness policy: not ready when any required component is `down`.
+fn is_ready(
+    components: &BTreeMap<HealthComponent, ComponentHealth>,
+    requires: &[HealthComponent],
+) -> bool {
+    requires.iter().all(|required| {
+        components
+            .get(required)
+            .is_none_or(|health| health.status != CheckStatus::Down)
+    })
+}
+
+async fn timed<E: Display>(
+    timeout: Duration,
+    check: impl Future<Output = Result<(), E>>,
+) -> ComponentHealth {
+    let started = Instant::now();
+    let (status, error) = match tokio::time::timeout(timeout, check).await {
+        Ok(Ok(())) => (CheckStatus::Up, None),
+        Ok(Err(e)) => (CheckStatus::Down, Some(e.to_string())),
+        Err(_) => (
+            CheckStatus::Down,
+            Some(format!("timed out after {}ms", timeout.as_millis())),
+        ),
+    };
+    ComponentHealth {
+        status,
+        latency_ms: Some(started.elapsed().as_millis() as u64),
+        error,
+        details: Value::Null,
+    }
+}
+
+async fn check_cache(state: &AppState, timeout: Duration) -> ComponentHealth {
+    if state.cache.redis_client().is_none() {
+        return ComponentHealth::new(CheckStatus::Disabled);
+    }
+    timed(timeout, state.cache.ping()).await
+}
+
+/// Down only when every replica is circuit-broken; reads then fall back to the writer
+/// (`database_routing.fallback_to_write`) or fail.
+fn check_replicas(state: &AppState) -> ComponentHealth {
+    let replicas = state.db.replica_health();
+    if replicas.is_empty() {
+        return ComponentHealth::new(CheckStatus::Disabled);
+    }
+    let open = replicas
+        .iter()
+        .filter(|r| r.circuit_open_ms.is_some())
+        .count();
+    let status = match open {
+        0 => CheckStatus::Up,
+        n if n == replicas.len() => CheckStatus::Down,
+        _ => CheckStatus::Degraded,
+    };
+    ComponentHealth::new(status).with_details(json!({ "replicas": replicas }))
+}
+
+/// The rate limiter shares the cache's `redis::Client` (`create_rate_limiter`): without Redis
+/// it is disabled, otherwise it is as available as Redis itself.
+fn check_rate_limiter(cache: &ComponentHealth) -> ComponentHealth {
+    let fail_open = distributed_ratelimit::RateLimitConfig::default().fail_open;
+    let mut health = ComponentHealth::new(cache.status);
+    health.error = cache.error.clone();
+    health.with_details(json!({ "fail_open": fail_open }))
+}
+
+async fn check_email(state: &AppState) -> ComponentHealth {
+    if !state.email.is_configured().await {
+        return ComponentHealth::new(CheckStatus::Disabled);
+    }
+    let config = state.email.config().await;
+    ComponentHealth::new(CheckStatus::Up).with_details(json!({
+        "smtp_host": config.smtp_host,
+        "smtp_port": config.smtp_port,
+    }))
+}
+
+/// Captcha codes are delivered as passive replies to the callback, so no access token is
+/// cached; the check reports whether the credentials for API calls are present.
+fn check_wechat(state: &AppState) -> ComponentHealth {
+    let Some(wechat) = &state.wechat else {
+        return ComponentHealth::new(CheckStatus::Disabled);
+    };
+    let status = if wechat.config.is_api_configured() {
+        CheckStatus::Up
+    } else {
+        CheckStatus::Degraded
+    };
+    ComponentHealth::new(status).with_details(json!({
+        "account_id": wechat.config.account_id,
+        "safe_mode": wechat.config.is_safe_mode(),
+    }))
+}
+
+fn check_snowflake() -> ComponentHealth {
+    match crate::snowflake::worker_id() {
+        Some(worker_id) => {
+            ComponentHealth::new(CheckStatus::Up).with_details(json!({ "worker_id": worker_id }))
+        }
+        None => ComponentHealth {
+            error: Some("generator not initialized".into()),
+            ..ComponentHealth::new(CheckStatus::Down)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn components(
+        statuses: &[(HealthComponent, CheckStatus)],
+    ) -> BTreeMap<HealthComponent, ComponentHealth> {
+        statuses
+            .iter()
+            .map(|&(component, status)| (component, ComponentHealth::new(status)))
+            .collect()
+    }
+
+    #[test]
+    fn only_required_components_fail_readiness() {
+        let checks = components(&[
+            (HealthComponent::Database, CheckStatus::Up),
+            (HealthComponent::Cache, CheckStatus::Down),
+            (HealthComponent::Replicas, CheckStatus::Degraded),
+        ]);
+        assert!(is_ready(&checks, &[HealthComponent::Database]));
+        assert!(is_ready(
+            &checks,
+            &[HealthComponent::Database, HealthComponent::Replicas]
+        ));
+        assert!(!is_ready(
+            &checks,
+            &[HealthComponent::Database, HealthComponent::Cache]
+        ));
+    }
+
+    #[test]
+    fn disabled_components_do_not_fail_readiness() {
+        let checks = components(&[(HealthComponent::Cache, CheckStatus::Disabled)]);
+        assert!(is_ready(&checks, &[HealthComponent::Cache]));
+    }
+
+    #[tokio::test]
+    async fn timed_reports_errors_and_timeouts() {
+        let ok = timed(Duration::from_secs(1), async { Ok::<_, String>(()) }).await;
+        assert_eq!(ok.status, CheckStatus::Up);
+        assert!(ok.latency_ms.is_some());
+
+        let failed = timed(Duration::from_secs(1), async { Err("refused") }).await;
+        assert_eq!(failed.status, CheckStatus::Down);
+        assert_eq!(failed.error.as_deref(), Some("refused"));
+
+        let slow = timed(
+            Duration::from_millis(10),
+            std::future::pending::<Result<(), String>>(),
+        )
+        .await;
+        assert_eq!(slow.status, CheckStatus::Down);
+        assert!(slow.error.unwrap().contains("timed out"));
+    }
+
+    #[test]
+    fn report_serializes_component_keys_in_snake_case() {
+        let report = HealthReport {
+            ready: true,
+            components: components(&[(HealthComponent::RateLimiter, CheckStatus::Up)]),
+        };
+        assert_eq!(
+            serde_json::to_value(report.statuses()).unwrap(),
+            json!({ "rate_limiter": "up" })
+        );
+    }
+}
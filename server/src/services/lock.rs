@@ -34,7 +34,8 @@
 //! K8s 多副本环境下，如果每个 pod 都独立运行定时任务（如清理过期数据、
 //! 批量发送通知），需要分布式锁确保同一时刻只有一个副本执行。
 //! - **正确性**：如果任务不可重入/非幂等，则必须
-//! - **实现**：[`LockGuard::acquire`] / [`LockGuard::acquire_with_client`]
+//! - **实现**：[`crate::services::scheduler`]（[`LockGuard::acquire_with_client`] +
+//!   [`LockGuard::renew`] 续约）
 //!
 //! #### 3. 跨副本资源初始化（保护非幂等操作）
 //!
@@ -64,6 +65,8 @@ use std::time::Duration;
 use tokio::time::sleep;
 use uuid::Uuid;
 
+use crate::utils::metrics;
+
 /// Error type for Redis not available
 #[derive(Debug, thiserror::Error)]
 pub enum LockError {
@@ -89,6 +92,20 @@ else
 end
 "#;
 
+/// SAFE RENEW Lua script: extends the TTL only while the lock still holds our value.
+///
+/// KEYS[1] = lock key
+/// ARGV[1] = expected lock value (UUID)
+/// ARGV[2] = new expiry in seconds
+/// Returns: 1 if extended, 0 if the lock expired or belongs to another holder
+const SAFE_RENEW_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("EXPIRE", KEYS[1], ARGV[2])
+else
+    return 0
+end
+"#;
+
 /// Acquire a distributed lock with retry mechanism.
 ///
 /// # 适用场景
@@ -174,9 +191,11 @@ async fn try_acquire_with_retry(
                 lock_key,
                 attempt + 1
             );
+            metrics::lock_acquisition(true);
             return Ok(true);
         }
 
+        metrics::lock_retry();
         // Non-blocking retry with delay
         if attempt < max_retries - 1 {
             tracing::trace!(
@@ -193,6 +212,7 @@ async fn try_acquire_with_retry(
         lock_key,
         max_retries
     );
+    metrics::lock_acquisition(false);
     Ok(false)
 }
 
@@ -465,6 +485,33 @@ impl LockGuard {
         }
     }
 
+    /// Extend the lock's TTL to `expiry_seconds` from now (lease renewal).
+    ///
+    /// Long-running holders (e.g. scheduled jobs) acquire with a short TTL and renew
+    /// periodically, so a crashed holder frees the lock quickly. Returns `Ok(false)` when the
+    /// lock has already expired or been taken by someone else — the holder must stop
+    /// treating the critical section as exclusive.
+    pub async fn renew(&self, expiry_seconds: u64) -> Result<bool> {
+        let Some(client) = &self.client else {
+            return Ok(false);
+        };
+        let mut conn = client
+            .get_connection_manager()
+            .await
+            .context("Failed to get async Redis connection")?;
+        let renewed: i32 = redis::Script::new(SAFE_RENEW_SCRIPT)
+            .key(&self.lock_key)
+            .arg(&self.lock_value)
+            .arg(expiry_seconds)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to execute safe renew script")?;
+        if renewed == 0 {
+            tracing::warn!("Lock renewal failed for key: {} — lock lost", self.lock_key);
+        }
+        Ok(renewed == 1)
+    }
+
     /// Release the lock explicitly (with ownership verification).
     ///
     /// Note: The lock is also released automatically when the guard is dropped.
@@ -545,4 +592,27 @@ mod tests {
             .await
             .unwrap();
     }
+
+    // Note: requires a running Redis instance
+    #[tokio::test]
+    #[ignore]
+    async fn test_renew_only_while_held() {
+        let client = Client::open("redis://127.0.0.1:6379").unwrap();
+        let lock_key = "test:lock:renew";
+
+        let Some(AcquireResult::Acquired(guard)) =
+            LockGuard::acquire_with_client(Some(&client), lock_key, 1, 1, Duration::ZERO)
+                .await
+                .unwrap()
+        else {
+            panic!("lock not acquired");
+        };
+        assert!(guard.renew(10).await.unwrap());
+
+        // 锁被他人接管后续约失败
+        release_lock(Some(&client), lock_key, &guard.lock_value)
+            .await
+            .unwrap();
+        assert!(!guard.renew(10).await.unwrap());
+    }
 }
@@ -0,0 +1,224 @@
+//! Runtime log level changes behind `/api/admin/log-level`.
+//!
+//! The global `EnvFilter` sits in a reload layer (`utils::logger`); this service swaps its
+//! directives and, when a TTL is given, reverts to the startup filter (`[logging]` or
+//! `RUST_LOG`) once it expires. Changes apply to this instance only — with several replicas,
+//! repeat the call against each pod.
+
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+static GLOBAL: OnceLock<Arc<LogLevelService>> = OnceLock::new();
+
+#[derive(Debug, thiserror::Error)]
+pub enum LogLevelError {
+    #[error("Invalid filter directive: {0}")]
+    InvalidFilter(String),
+    #[error("TTL out of range")]
+    InvalidTtl,
+    #[error("Runtime log level control is not available")]
+    Unavailable,
+}
+
+/// Active filter of this instance.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct LogLevelStatus {
+    /// Directives in effect, e.g. `info,sqlx::query=warn`
+    pub filter: String,
+    /// Startup filter restored by a reset or when `expires_at` passes
+    pub default: String,
+    /// When the current override reverts; absent without TTL or when on the default
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+struct State {
+    filter: String,
+    expires_at: Option<DateTime<Utc>>,
+    /// Bumped on every change, so a pending revert only applies to the change that set it.
+    generation: u64,
+}
+
+pub struct LogLevelService {
+    handle: reload::Handle<EnvFilter, Registry>,
+    default: String,
+    state: Mutex<State>,
+}
+
+impl LogLevelService {
+    pub fn new(handle: reload::Handle<EnvFilter, Registry>, default: String) -> Arc<Self> {
+        Arc::new(Self {
+            handle,
+            state: Mutex::new(State {
+                filter: default.clone(),
+                expires_at: None,
+                generation: 0,
+            }),
+            default,
+        })
+    }
+
+    /// Register the instance controlling the global subscriber (called by `init_logger`).
+    pub fn install(service: Arc<Self>) {
+        let _ = GLOBAL.set(service);
+    }
+
+    pub fn global() -> Result<Arc<Self>, LogLevelError> {
+        GLOBAL.get().cloned().ok_or(LogLevelError::Unavailable)
+    }
+
+    pub fn status(&self) -> LogLevelStatus {
+        let state = self.lock();
+        LogLevelStatus {
+            filter: state.filter.clone(),
+            default: self.default.clone(),
+            expires_at: state.expires_at,
+        }
+    }
+
+    /// Replace the filter; with `ttl`, revert to the default afterwards. Must be called
+    /// inside a Tokio runtime when `ttl` is set.
+    pub fn set(
+        self: &Arc<Self>,
+        directives: &str,
+        ttl: Option<Duration>,
+    ) -> Result<LogLevelStatus, LogLevelError> {
+        let directives = directives.trim();
+        if directives.is_empty() {
+            return Err(LogLevelError::InvalidFilter("empty filter".into()));
+        }
+        let filter = EnvFilter::try_new(directives)
+            .map_err(|e| LogLevelError::InvalidFilter(e.to_string()))?;
+        let expires_at = ttl.map(expiry).transpose()?;
+        let generation = self.apply(filter, directives.to_string(), expires_at)?;
+
+        if let Some(ttl) = ttl {
+            let this = Arc::clone(self);
+            tokio::spawn(async move {
+                tokio::time::sleep(ttl).await;
+                this.revert(generation);
+            });
+        }
+        Ok(self.status())
+    }
+
+    /// Restore the startup filter.
+    pub fn reset(&self) -> Result<LogLevelStatus, LogLevelError> {
+        let filter = EnvFilter::try_new(&self.default)
+            .map_err(|e| LogLevelError::InvalidFilter(e.to_string()))?;
+        self.apply(filter, self.default.clone(), None)?;
+        Ok(self.status())
+    }
+
+    fn apply(
+        &self,
+        filter: EnvFilter,
+        directives: String,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<u64, LogLevelError> {
+        let mut state = self.lock();
+        self.handle
+            .reload(filter)
+            .map_err(|_| LogLevelError::Unavailable)?;
+        state.filter = directives;
+        state.expires_at = expires_at;
+        state.generation += 1;
+        Ok(state.generation)
+    }
+
+    fn revert(&self, generation: u64) {
+        if self.lock().generation != generation {
+            return;
+        }
+        match self.reset() {
+            Ok(status) => tracing::warn!(filter = %status.filter, "Log level override expired"),
+            Err(e) => tracing::error!("Failed to revert log level override: {}", e),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// When an override set now with `ttl` expires; `InvalidTtl` if that is not representable.
+fn expiry(ttl: Duration) -> Result<DateTime<Utc>, LogLevelError> {
+    i64::try_from(ttl.as_secs())
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+        .ok_or(LogLevelError::InvalidTtl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn service() -> (Arc<LogLevelService>, impl tracing::Subscriber) {
+        let (layer, handle) = reload::Layer::new(EnvFilter::new("info"));
+        let subscriber = tracing_subscriber::registry().with(layer);
+        (LogLevelService::new(handle, "info".into()), subscriber)
+    }
+
+    #[test]
+    fn set_changes_the_active_filter() {
+        let (service, subscriber) = service();
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!tracing::enabled!(tracing::Level::DEBUG));
+            let status = service.set("debug,hyper=warn", None).unwrap();
+            assert_eq!(status.filter, "debug,hyper=warn");
+            assert!(status.expires_at.is_none());
+            assert!(tracing::enabled!(tracing::Level::DEBUG));
+
+            service.reset().unwrap();
+            assert!(!tracing::enabled!(tracing::Level::DEBUG));
+            assert_eq!(service.status().filter, "info");
+        });
+    }
+
+    #[test]
+    fn invalid_directives_are_rejected() {
+        let (service, _subscriber) = service();
+        assert!(matches!(
+            service.set("sqlx=loud", None),
+            Err(LogLevelError::InvalidFilter(_))
+        ));
+        assert!(matches!(
+            service.set("  ", None),
+            Err(LogLevelError::InvalidFilter(_))
+        ));
+        assert_eq!(service.status().filter, "info");
+    }
+
+    #[test]
+    fn out_of_range_ttl_is_rejected() {
+        let (service, _subscriber) = service();
+        assert!(matches!(
+            service.set("debug", Some(Duration::from_secs(u64::MAX))),
+            Err(LogLevelError::InvalidTtl)
+        ));
+        assert_eq!(service.status().filter, "info");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn override_reverts_after_ttl_unless_replaced() {
+        let (service, _subscriber) = service();
+        let status = service.set("debug", Some(Duration::from_secs(60))).unwrap();
+        assert!(status.expires_at.is_some());
+
+        tokio::time::sleep(Duration::from_secs(61)).await;
+        assert_eq!(service.status().filter, "info");
+        assert!(service.status().expires_at.is_none());
+
+        // 新的修改使旧的定时回退失效
+        service.set("debug", Some(Duration::from_secs(60))).unwrap();
+        service.set("trace", None).unwrap();
+        tokio::time::sleep(Duration::from_secs(61)).await;
+        assert_eq!(service.status().filter, "trace");
+    }
+}
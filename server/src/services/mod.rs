@@ -1,17 +1,34 @@
+pub mod api_token;
 pub mod auth;
 pub mod cache;
+pub mod health;
 pub mod lock;
+pub mod log_level;
+pub mod oauth;
+pub mod oidc;
 pub mod password_reset;
+pub mod queue;
+pub mod scheduler;
+pub mod two_factor;
 pub mod user;
 pub mod verification;
+pub mod webauthn;
 pub mod wechat;
 
+pub use api_token::{ApiTokenError, ApiTokenService};
 pub use auth::{AuthError, AuthService};
 pub use cache::CacheService;
 pub use lock::{
     AcquireResult, LockGuard, acquire_lock, acquire_lock_with_client, release_lock,
     release_lock_with_client,
 };
+pub use log_level::{LogLevelError, LogLevelService};
+pub use oauth::{OAuthError, OAuthService};
+pub use oidc::{OidcError, OidcService};
 pub use password_reset::{PasswordResetError, PasswordResetOutcome, PasswordResetService};
+pub use queue::{JobQueue, QueueError, QueueHandle};
+pub use scheduler::{Scheduler, SchedulerError, SchedulerHandle};
+pub use two_factor::{TwoFactorError, TwoFactorService};
 pub use user::{UserError, UserService};
 pub use verification::{VerificationError, VerificationService};
+pub use webauthn::{WebAuthnError, WebAuthnService};
@@ -0,0 +1,1614 @@
+//! OAuth 2.0 / OpenID Connect authorization server for third-party apps.
+//!
+//! - Clients are registered by admins. Confidential clients authenticate at the token endpoint
+//!   with `client_secret_basic` or `client_secret_post`; only the SHA-256 hash of the secret is
+//!   stored. Public clients have no secret.
+//! - Authorization code flow with mandatory PKCE (S256) for every client. The authorization
+//!   endpoint checks the request and hands it to the web app's consent page as a short-lived
+//!   request token — an HS256 JWT under a key derived from `jwt_secret` — so pending requests
+//!   need no storage. Approving stores a single-use code (hash only) and remembers the consent.
+//! - Refresh tokens (with `offline_access`) are stored hashed, rotate on every use and carry the
+//!   user's `token_version`, so changing the password or logging out everywhere revokes them.
+//!   The client credentials grant acts as the admin who registered the client, limited to API
+//!   scopes.
+//! - Whatever the grant, access tokens carry a plain user's role unless the client has
+//!   `admin_access`: an admin consenting to `users:write` does not hand the client admin rights.
+//! - Access and ID tokens are ES256 JWTs. Signing keys live in `oauth_signing_keys` (created on
+//!   first use, all published at the JWKS endpoint). Access tokens have the `at+jwt` type
+//!   (RFC 9068); [`OAuthService::verify_access_token`] turns them into an [`AuthUser`] whose
+//!   scopes restrict what the token may do.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use ring::rand::SystemRandom;
+use ring::signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair};
+use schemars::JsonSchema;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend, EntityTrait, QueryFilter,
+    QueryOrder, Set, Statement,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::middlewares::AuthUser;
+use crate::repositories::oauth_authorization_code::{
+    ActiveModel as CodeActiveModel, Column as CodeColumn, Entity as CodeEntity,
+};
+use crate::repositories::oauth_client::{
+    ActiveModel as ClientActiveModel, Column as ClientColumn, Entity as ClientEntity,
+    Model as ClientModel,
+};
+use crate::repositories::oauth_consent::{
+    ActiveModel as ConsentActiveModel, Column as ConsentColumn, Entity as ConsentEntity,
+};
+use crate::repositories::oauth_refresh_token::{
+    ActiveModel as RefreshActiveModel, Column as RefreshColumn, Entity as RefreshEntity,
+};
+use crate::repositories::oauth_signing_key::{
+    ActiveModel as SigningKeyActiveModel, Column as SigningKeyColumn, Entity as SigningKeyEntity,
+};
+use crate::repositories::user::{Entity as UserEntity, Model as UserModel};
+use crate::services::auth::AuthService;
+use crate::services::oidc::{pkce_challenge, random_token};
+use crate::snowflake::SnowflakeId;
+use crate::utils::config::OAuthConfig;
+use crate::utils::db_router::AutoRouter;
+
+/// Scopes clients can be granted, in display order.
+pub const SCOPES: &[&str] = &[
+    "openid",
+    "profile",
+    "email",
+    "offline_access",
+    "users:read",
+    "users:write",
+];
+
+/// Scopes that unlock REST API endpoints; the others only shape ID tokens and refresh.
+pub const API_SCOPES: &[&str] = &["users:read", "users:write"];
+
+/// Grant types clients can be registered for.
+pub const GRANT_TYPES: &[&str] = &["authorization_code", "refresh_token", "client_credentials"];
+
+/// `typ` header of access tokens (RFC 9068), which tells them apart from other JWTs.
+const ACCESS_TOKEN_TYPE: &str = "at+jwt";
+
+/// Audience of access tokens: this API.
+const ACCESS_TOKEN_AUDIENCE: &str = "webshelf";
+
+const MAX_REDIRECT_URIS: usize = 10;
+const MAX_REDIRECT_URI_LEN: usize = 2048;
+const MAX_CLIENT_NAME_CHARS: usize = 100;
+const MAX_NONCE_LEN: usize = 255;
+
+/// Typed errors for the authorization server.
+///
+/// [`code`](OAuthError::code) is the RFC 6749 error code returned by the token endpoint and
+/// in authorization error redirects.
+#[derive(Debug, thiserror::Error)]
+pub enum OAuthError {
+    #[error("Unknown client or invalid client credentials")]
+    InvalidClient,
+    #[error("redirect_uri is not registered for this client")]
+    InvalidRedirectUri,
+    #[error("Invalid request: {0}")]
+    InvalidRequest(&'static str),
+    #[error("Only response_type=code is supported")]
+    UnsupportedResponseType,
+    #[error("Invalid grant: {0}")]
+    InvalidGrant(&'static str),
+    #[error("Unsupported grant type")]
+    UnsupportedGrantType,
+    #[error("The client is not allowed to use this grant type")]
+    UnauthorizedClient,
+    #[error("Requested scope is not allowed for this client")]
+    InvalidScope,
+    #[error("Invalid access token: {0}")]
+    InvalidToken(&'static str),
+    #[error("Invalid or expired authorization request")]
+    InvalidAuthorizationRequest,
+    #[error("Invalid client settings: {0}")]
+    InvalidClientMetadata(String),
+    #[error("OAuth client not found")]
+    NotFound,
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl OAuthError {
+    /// Stable code; the RFC 6749 / 6750 name where one exists.
+    pub fn code(&self) -> &'static str {
+        match self {
+            OAuthError::InvalidClient => "invalid_client",
+            OAuthError::InvalidRedirectUri => "invalid_redirect_uri",
+            OAuthError::InvalidRequest(_) => "invalid_request",
+            OAuthError::UnsupportedResponseType => "unsupported_response_type",
+            OAuthError::InvalidGrant(_) => "invalid_grant",
+            OAuthError::UnsupportedGrantType => "unsupported_grant_type",
+            OAuthError::UnauthorizedClient => "unauthorized_client",
+            OAuthError::InvalidScope => "invalid_scope",
+            OAuthError::InvalidToken(_) => "invalid_token",
+            OAuthError::InvalidAuthorizationRequest => "invalid_authorization_request",
+            OAuthError::InvalidClientMetadata(_) => "invalid_client_metadata",
+            OAuthError::NotFound => "not_found",
+            OAuthError::Internal(_) => "server_error",
+        }
+    }
+}
+
+/// Query parameters of the authorization endpoint.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct AuthorizeParams {
+    pub response_type: Option<String>,
+    pub client_id: Option<String>,
+    /// Must exactly match a registered redirect URI
+    pub redirect_uri: Option<String>,
+    /// Space-separated; defaults to every scope the client is registered for
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    /// Copied into the ID token
+    pub nonce: Option<String>,
+    /// PKCE challenge (required)
+    pub code_challenge: Option<String>,
+    /// Must be `S256`
+    pub code_challenge_method: Option<String>,
+}
+
+/// Form of the token endpoint. Client credentials may instead come as HTTP Basic auth.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TokenParams {
+    /// `authorization_code`, `refresh_token` or `client_credentials`
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    /// Narrow the scope (refresh and client credentials grants)
+    pub scope: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// Successful token response (RFC 6749 §5.1).
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TokenResponse {
+    pub access_token: String,
+    /// Always `Bearer`
+    pub token_type: &'static str,
+    pub expires_in: u64,
+    /// Granted scopes, space-separated
+    pub scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Present when `openid` was granted with the authorization code grant
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
+
+/// Pending authorization request, as shown on the consent page.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AuthorizationDetails {
+    pub client_id: String,
+    pub client_name: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    /// False when the client skips consent or the user already approved these scopes
+    pub consent_required: bool,
+}
+
+/// Claims returned by the userinfo endpoint.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UserInfo {
+    pub sub: String,
+    /// With the `profile` scope
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// With the `email` scope
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+}
+
+/// OpenID Provider metadata (OpenID Connect Discovery §3).
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub scopes_supported: Vec<&'static str>,
+    pub response_types_supported: Vec<&'static str>,
+    pub grant_types_supported: Vec<&'static str>,
+    pub subject_types_supported: Vec<&'static str>,
+    pub id_token_signing_alg_values_supported: Vec<&'static str>,
+    pub token_endpoint_auth_methods_supported: Vec<&'static str>,
+    pub code_challenge_methods_supported: Vec<&'static str>,
+    pub claims_supported: Vec<&'static str>,
+    pub authorization_response_iss_parameter_supported: bool,
+}
+
+/// Public signing keys (RFC 7517).
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct JwkSetDocument {
+    pub keys: Vec<PublicJwk>,
+}
+
+/// P-256 public key of a signing key.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PublicJwk {
+    pub kty: &'static str,
+    pub crv: &'static str,
+    pub alg: &'static str,
+    #[serde(rename = "use")]
+    pub key_use: &'static str,
+    pub kid: String,
+    pub x: String,
+    pub y: String,
+}
+
+/// Client settings an admin can change.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ClientSettings {
+    /// Shown on the consent page (1-100 characters)
+    pub name: String,
+    /// Exact redirect URIs: https, or http for localhost; required for the authorization code
+    /// grant
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+    /// Default: `["authorization_code", "refresh_token"]`
+    #[serde(default = "default_grant_types")]
+    pub grant_types: Vec<String>,
+    /// Scopes the client may request
+    pub scopes: Vec<String>,
+    /// Trusted first-party app: users are not asked for consent
+    #[serde(default)]
+    pub skip_consent: bool,
+    /// Access tokens keep the user's admin role; without it they can only call endpoints open
+    /// to every user, whoever consented.
+    #[serde(default)]
+    pub admin_access: bool,
+}
+
+fn default_grant_types() -> Vec<String> {
+    vec![
+        "authorization_code".to_string(),
+        "refresh_token".to_string(),
+    ]
+}
+
+/// A registered client.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClientInfo {
+    pub client_id: String,
+    pub name: String,
+    /// Has a client secret
+    pub confidential: bool,
+    pub redirect_uris: Vec<String>,
+    pub grant_types: Vec<String>,
+    pub scopes: Vec<String>,
+    pub skip_consent: bool,
+    pub admin_access: bool,
+    pub owner_id: SnowflakeId,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A newly registered client; the secret is only returned here.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CreatedClient {
+    #[serde(flatten)]
+    pub client: ClientInfo,
+    /// `None` for public clients
+    pub client_secret: Option<String>,
+}
+
+/// A rotated client secret, shown once.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClientSecret {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Claims of the request token handed to the consent page.
+#[derive(Debug, Serialize, Deserialize)]
+struct RequestClaims {
+    client_id: String,
+    redirect_uri: String,
+    scope: String,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    nonce: Option<String>,
+    code_challenge: String,
+    exp: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AccessClaims {
+    iss: String,
+    sub: String,
+    aud: String,
+    client_id: String,
+    scope: String,
+    jti: String,
+    role: String,
+    token_version: i32,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct IdTokenClaims<'a> {
+    iss: &'a str,
+    sub: String,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email_verified: Option<bool>,
+}
+
+/// The current signing key.
+struct SigningKey {
+    kid: String,
+    encoding: EncodingKey,
+}
+
+/// Verification keys by `kid`. Keys never change once created, so entries stay valid; an
+/// unknown `kid` is looked up in the database (a key created by another replica).
+fn verification_keys() -> &'static RwLock<HashMap<String, DecodingKey>> {
+    static KEYS: OnceLock<RwLock<HashMap<String, DecodingKey>>> = OnceLock::new();
+    KEYS.get_or_init(Default::default)
+}
+
+pub struct OAuthService {
+    db: Arc<AutoRouter>,
+    config: OAuthConfig,
+    request_key: [u8; 32],
+}
+
+impl OAuthService {
+    pub fn new(db: Arc<AutoRouter>, config: OAuthConfig, jwt_secret: &str) -> Self {
+        // 独立于会话 JWT 的签名密钥，请求令牌不能被当作访问令牌使用
+        let request_key = Sha256::digest(format!("oauth-request:{jwt_secret}")).into();
+        Self {
+            db,
+            config,
+            request_key,
+        }
+    }
+
+    /// `iss` of every token and the base of the public endpoints.
+    pub fn issuer(&self) -> String {
+        format!("{}/api/public/oauth", self.config.public_url)
+    }
+
+    /// Discovery document.
+    pub fn metadata(&self) -> ProviderMetadata {
+        let issuer = self.issuer();
+        ProviderMetadata {
+            authorization_endpoint: format!("{issuer}/authorize"),
+            token_endpoint: format!("{issuer}/token"),
+            userinfo_endpoint: format!("{}/api/oauth/userinfo", self.config.public_url),
+            jwks_uri: format!("{issuer}/jwks"),
+            issuer,
+            scopes_supported: SCOPES.to_vec(),
+            response_types_supported: vec!["code"],
+            grant_types_supported: GRANT_TYPES.to_vec(),
+            subject_types_supported: vec!["public"],
+            id_token_signing_alg_values_supported: vec!["ES256"],
+            token_endpoint_auth_methods_supported: vec![
+                "client_secret_basic",
+                "client_secret_post",
+                "none",
+            ],
+            code_challenge_methods_supported: vec!["S256"],
+            claims_supported: vec![
+                "iss",
+                "sub",
+                "aud",
+                "exp",
+                "iat",
+                "nonce",
+                "name",
+                "email",
+                "email_verified",
+            ],
+            authorization_response_iss_parameter_supported: true,
+        }
+    }
+
+    /// Public keys of every signing key (creating the first one if needed).
+    pub async fn jwks(&self) -> Result<JwkSetDocument, OAuthError> {
+        self.current_signing_key().await?;
+        let rows = SigningKeyEntity::find()
+            .order_by_desc(SigningKeyColumn::CreatedAt)
+            .all(self.db.write_conn())
+            .await
+            .context("Failed to query OAuth signing keys")?;
+        let keys = rows
+            .into_iter()
+            .map(|row| {
+                let (x, y) = public_coordinates(&row.private_key)?;
+                Ok(PublicJwk {
+                    kty: "EC",
+                    crv: "P-256",
+                    alg: "ES256",
+                    key_use: "sig",
+                    kid: row.kid,
+                    x,
+                    y,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(JwkSetDocument { keys })
+    }
+
+    /// Check an authorization request; returns where to send the browser.
+    ///
+    /// An unknown client or redirect URI is an error shown to the user. Every later problem is
+    /// reported to the client by redirecting to its redirect URI (RFC 6749 §4.1.2.1); a valid
+    /// request continues at the consent page.
+    pub async fn authorize(&self, params: &AuthorizeParams) -> Result<String, OAuthError> {
+        let client_id = params
+            .client_id
+            .as_deref()
+            .ok_or(OAuthError::InvalidClient)?;
+        let client = self
+            .find_client(client_id)
+            .await?
+            .ok_or(OAuthError::InvalidClient)?;
+        let redirect_uri = params
+            .redirect_uri
+            .as_deref()
+            .filter(|uri| strings(&client.redirect_uris).iter().any(|r| r == uri))
+            .ok_or(OAuthError::InvalidRedirectUri)?;
+
+        match self.request_token(&client, redirect_uri, params) {
+            Ok(request) => {
+                let mut url = url::Url::parse(&self.config.consent_url)
+                    .context("Invalid oauth.consent_url")?;
+                url.query_pairs_mut().append_pair("request", &request);
+                Ok(url.into())
+            }
+            Err(e) => {
+                tracing::info!(client_id, "OAuth authorization request rejected: {}", e);
+                self.client_redirect(
+                    redirect_uri,
+                    &[("error", e.code()), ("error_description", &e.to_string())],
+                    params.state.as_deref(),
+                )
+            }
+        }
+    }
+
+    /// The pending request behind `request`, for the consent page of `user_id`.
+    pub async fn authorization_details(
+        &self,
+        user_id: i64,
+        request: &str,
+    ) -> Result<AuthorizationDetails, OAuthError> {
+        let claims = self.decode_request(request)?;
+        let client = self
+            .find_client(&claims.client_id)
+            .await?
+            .ok_or(OAuthError::InvalidAuthorizationRequest)?;
+        let scopes = split_scope(&claims.scope);
+        let consent_required = !client.skip_consent
+            && !self
+                .has_consent(user_id, &client.client_id, &scopes)
+                .await?;
+        Ok(AuthorizationDetails {
+            client_id: client.client_id,
+            client_name: client.name,
+            redirect_uri: claims.redirect_uri,
+            scopes,
+            consent_required,
+        })
+    }
+
+    /// Approve or deny the request behind `request`; returns the client redirect carrying the
+    /// authorization code or `error=access_denied`.
+    pub async fn decide(
+        &self,
+        user_id: i64,
+        request: &str,
+        approve: bool,
+    ) -> Result<String, OAuthError> {
+        let claims = self.decode_request(request)?;
+        let client = self
+            .find_client(&claims.client_id)
+            .await?
+            .ok_or(OAuthError::InvalidAuthorizationRequest)?;
+        if !approve {
+            tracing::info!(user_id, client_id = %client.client_id, "OAuth authorization denied");
+            return self.client_redirect(
+                &claims.redirect_uri,
+                &[("error", "access_denied")],
+                claims.state.as_deref(),
+            );
+        }
+
+        let code = random_token();
+        let now = Utc::now();
+        CodeActiveModel {
+            code_hash: Set(hash_token(&code)),
+            client_id: Set(client.client_id.clone()),
+            user_id: Set(user_id),
+            redirect_uri: Set(claims.redirect_uri.clone()),
+            scope: Set(claims.scope.clone()),
+            nonce: Set(claims.nonce.clone()),
+            code_challenge: Set(claims.code_challenge.clone()),
+            expires_at: Set(now + chrono::Duration::seconds(self.config.code_ttl_secs as i64)),
+            created_at: Set(now),
+        }
+        .insert(self.db.write_conn())
+        .await
+        .context("Failed to store authorization code")?;
+        self.remember_consent(user_id, &client.client_id, &split_scope(&claims.scope))
+            .await?;
+
+        tracing::info!(user_id, client_id = %client.client_id, "OAuth authorization granted");
+        self.client_redirect(
+            &claims.redirect_uri,
+            &[("code", &code)],
+            claims.state.as_deref(),
+        )
+    }
+
+    /// Token endpoint. `basic` holds the credentials of an HTTP Basic `Authorization` header.
+    pub async fn token(
+        &self,
+        params: &TokenParams,
+        basic: Option<(String, String)>,
+    ) -> Result<TokenResponse, OAuthError> {
+        let client = self.authenticate_client(params, basic).await?;
+        let grant_type = params.grant_type.as_deref().unwrap_or_default();
+        if !GRANT_TYPES.contains(&grant_type) {
+            return Err(OAuthError::UnsupportedGrantType);
+        }
+        if !strings(&client.grant_types).iter().any(|g| g == grant_type) {
+            return Err(OAuthError::UnauthorizedClient);
+        }
+        match grant_type {
+            "authorization_code" => self.redeem_code(&client, params).await,
+            "refresh_token" => self.refresh(&client, params).await,
+            _ => self.client_credentials(&client, params).await,
+        }
+    }
+
+    /// Claims about the token's user, limited by its scopes.
+    pub async fn userinfo(&self, auth_user: &AuthUser) -> Result<UserInfo, OAuthError> {
+        let user_id: i64 = auth_user
+            .user_id
+            .parse()
+            .map_err(|_| OAuthError::InvalidToken("malformed subject"))?;
+        let user = self.find_user(user_id).await?.ok_or(OAuthError::NotFound)?;
+        let profile = auth_user.has_scope("profile");
+        let email = auth_user.has_scope("email");
+        Ok(UserInfo {
+            sub: user.id.to_string(),
+            name: profile.then_some(user.name),
+            email: email.then_some(user.email),
+            email_verified: email.then_some(user.email_verified),
+        })
+    }
+
+    /// Verify an access token issued here.
+    ///
+    /// Returns `None` for tokens without the `at+jwt` type, which are not ours. The caller
+    /// still has to check `token_version` against the user.
+    pub async fn verify_access_token(&self, token: &str) -> Option<Result<AuthUser, OAuthError>> {
+        let header = jsonwebtoken::decode_header(token).ok()?;
+        if header.typ.as_deref() != Some(ACCESS_TOKEN_TYPE) {
+            return None;
+        }
+        Some(self.decode_access_token(&header, token).await)
+    }
+
+    // ── Client administration ──────────────────────────────
+
+    /// Every registered client, oldest first.
+    pub async fn list_clients(&self) -> Result<Vec<ClientInfo>, OAuthError> {
+        let rows = ClientEntity::find()
+            .order_by_asc(ClientColumn::Id)
+            .all(self.db.write_conn())
+            .await
+            .context("Failed to query OAuth clients")?;
+        Ok(rows.into_iter().map(client_info).collect())
+    }
+
+    /// Register a client owned by `owner_id`; confidential clients get a secret.
+    pub async fn create_client(
+        &self,
+        owner_id: i64,
+        settings: &ClientSettings,
+        confidential: bool,
+    ) -> Result<CreatedClient, OAuthError> {
+        let settings = validate_settings(settings, confidential)?;
+        let mut id_bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut id_bytes);
+        let client_secret = confidential.then(random_token);
+        let now = Utc::now();
+
+        let client = ClientActiveModel {
+            client_id: Set(hex::encode(id_bytes)),
+            client_secret_hash: Set(client_secret.as_deref().map(hash_token)),
+            name: Set(settings.name),
+            redirect_uris: Set(serde_json::json!(settings.redirect_uris)),
+            grant_types: Set(serde_json::json!(settings.grant_types)),
+            scopes: Set(serde_json::json!(settings.scopes)),
+            skip_consent: Set(settings.skip_consent),
+            admin_access: Set(settings.admin_access),
+            owner_id: Set(owner_id),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(self.db.write_conn())
+        .await
+        .context("Failed to store OAuth client")?;
+
+        tracing::info!(owner_id, client_id = %client.client_id, "OAuth client registered");
+        Ok(CreatedClient {
+            client: client_info(client),
+            client_secret,
+        })
+    }
+
+    /// Replace a client's settings.
+    pub async fn update_client(
+        &self,
+        client_id: &str,
+        settings: &ClientSettings,
+    ) -> Result<ClientInfo, OAuthError> {
+        let client = self
+            .find_client(client_id)
+            .await?
+            .ok_or(OAuthError::NotFound)?;
+        let settings = validate_settings(settings, client.client_secret_hash.is_some())?;
+
+        let mut active: ClientActiveModel = client.into();
+        active.name = Set(settings.name);
+        active.redirect_uris = Set(serde_json::json!(settings.redirect_uris));
+        active.grant_types = Set(serde_json::json!(settings.grant_types));
+        active.scopes = Set(serde_json::json!(settings.scopes));
+        active.skip_consent = Set(settings.skip_consent);
+        active.admin_access = Set(settings.admin_access);
+        active.updated_at = Set(Utc::now());
+        let client = active
+            .update(self.db.write_conn())
+            .await
+            .context("Failed to update OAuth client")?;
+        Ok(client_info(client))
+    }
+
+    /// Delete a client with its codes, refresh tokens and consents.
+    pub async fn delete_client(&self, client_id: &str) -> Result<(), OAuthError> {
+        let result = ClientEntity::delete_many()
+            .filter(ClientColumn::ClientId.eq(client_id))
+            .exec(self.db.write_conn())
+            .await
+            .context("Failed to delete OAuth client")?;
+        if result.rows_affected == 0 {
+            return Err(OAuthError::NotFound);
+        }
+        tracing::info!(client_id, "OAuth client deleted");
+        Ok(())
+    }
+
+    /// Replace a confidential client's secret; the old one stops working immediately.
+    pub async fn rotate_secret(&self, client_id: &str) -> Result<ClientSecret, OAuthError> {
+        let client = self
+            .find_client(client_id)
+            .await?
+            .ok_or(OAuthError::NotFound)?;
+        if client.client_secret_hash.is_none() {
+            return Err(OAuthError::InvalidClientMetadata(
+                "public clients have no secret".to_string(),
+            ));
+        }
+        let client_secret = random_token();
+        let mut active: ClientActiveModel = client.into();
+        active.client_secret_hash = Set(Some(hash_token(&client_secret)));
+        active.updated_at = Set(Utc::now());
+        active
+            .update(self.db.write_conn())
+            .await
+            .context("Failed to rotate OAuth client secret")?;
+        tracing::info!(client_id, "OAuth client secret rotated");
+        Ok(ClientSecret {
+            client_id: client_id.to_string(),
+            client_secret,
+        })
+    }
+
+    // ── Authorization ──────────────────────────────
+
+    fn request_token(
+        &self,
+        client: &ClientModel,
+        redirect_uri: &str,
+        params: &AuthorizeParams,
+    ) -> Result<String, OAuthError> {
+        if params.response_type.as_deref() != Some("code") {
+            return Err(OAuthError::UnsupportedResponseType);
+        }
+        if !strings(&client.grant_types)
+            .iter()
+            .any(|g| g == "authorization_code")
+        {
+            return Err(OAuthError::UnauthorizedClient);
+        }
+        // 所有客户端都必须使用 PKCE（OAuth 2.1），仅支持 S256
+        let code_challenge = params
+            .code_challenge
+            .as_deref()
+            .filter(|challenge| is_pkce_value(challenge))
+            .ok_or(OAuthError::InvalidRequest(
+                "code_challenge (43-128 base64url characters) is required",
+            ))?;
+        if params.code_challenge_method.as_deref() != Some("S256") {
+            return Err(OAuthError::InvalidRequest(
+                "code_challenge_method must be S256",
+            ));
+        }
+        if params
+            .nonce
+            .as_deref()
+            .is_some_and(|nonce| nonce.len() > MAX_NONCE_LEN)
+        {
+            return Err(OAuthError::InvalidRequest("nonce is too long"));
+        }
+        let scopes = parse_scope(params.scope.as_deref(), &strings(&client.scopes))?;
+
+        let claims = RequestClaims {
+            client_id: client.client_id.clone(),
+            redirect_uri: redirect_uri.to_string(),
+            scope: scopes.join(" "),
+            state: params.state.clone(),
+            nonce: params.nonce.clone(),
+            code_challenge: code_challenge.to_string(),
+            exp: Utc::now().timestamp() + self.config.request_ttl_secs as i64,
+        };
+        jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(&self.request_key),
+        )
+        .map_err(|e| OAuthError::Internal(anyhow::anyhow!("Failed to sign request token: {e}")))
+    }
+
+    fn decode_request(&self, token: &str) -> Result<RequestClaims, OAuthError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+        validation.set_required_spec_claims(&["exp"]);
+        jsonwebtoken::decode::<RequestClaims>(
+            token,
+            &DecodingKey::from_secret(&self.request_key),
+            &validation,
+        )
+        .map(|data| data.claims)
+        .map_err(|_| OAuthError::InvalidAuthorizationRequest)
+    }
+
+    /// `redirect_uri` with `params`, `state` and `iss` (RFC 9207) added to the query.
+    fn client_redirect(
+        &self,
+        redirect_uri: &str,
+        params: &[(&str, &str)],
+        state: Option<&str>,
+    ) -> Result<String, OAuthError> {
+        let mut url = url::Url::parse(redirect_uri).context("Invalid stored redirect URI")?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.extend_pairs(params);
+            if let Some(state) = state {
+                query.append_pair("state", state);
+            }
+            query.append_pair("iss", &self.issuer());
+        }
+        Ok(url.into())
+    }
+
+    async fn has_consent(
+        &self,
+        user_id: i64,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<bool, OAuthError> {
+        let consent = ConsentEntity::find()
+            .filter(ConsentColumn::UserId.eq(user_id))
+            .filter(ConsentColumn::ClientId.eq(client_id))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query OAuth consent")?;
+        Ok(consent.is_some_and(|consent| {
+            let approved = split_scope(&consent.scope);
+            scopes.iter().all(|scope| approved.contains(scope))
+        }))
+    }
+
+    /// Add `scopes` to the user's consent for the client.
+    async fn remember_consent(
+        &self,
+        user_id: i64,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthError> {
+        let existing = ConsentEntity::find()
+            .filter(ConsentColumn::UserId.eq(user_id))
+            .filter(ConsentColumn::ClientId.eq(client_id))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query OAuth consent")?;
+        let mut approved = existing
+            .map(|consent| split_scope(&consent.scope))
+            .unwrap_or_default();
+        approved.extend(scopes.iter().cloned());
+
+        ConsentEntity::insert(ConsentActiveModel {
+            user_id: Set(user_id),
+            client_id: Set(client_id.to_string()),
+            scope: Set(normalize_scopes(approved).join(" ")),
+            updated_at: Set(Utc::now()),
+        })
+        .on_conflict(
+            OnConflict::columns([ConsentColumn::UserId, ConsentColumn::ClientId])
+                .update_columns([ConsentColumn::Scope, ConsentColumn::UpdatedAt])
+                .to_owned(),
+        )
+        .exec(self.db.write_conn())
+        .await
+        .context("Failed to store OAuth consent")?;
+        Ok(())
+    }
+
+    // ── Token endpoint ──────────────────────────────
+
+    async fn authenticate_client(
+        &self,
+        params: &TokenParams,
+        basic: Option<(String, String)>,
+    ) -> Result<ClientModel, OAuthError> {
+        let (client_id, secret) = match basic {
+            Some(_) if params.client_secret.is_some() => {
+                return Err(OAuthError::InvalidRequest(
+                    "use only one client authentication method",
+                ));
+            }
+            Some((id, secret)) => {
+                if params.client_id.as_deref().is_some_and(|form| form != id) {
+                    return Err(OAuthError::InvalidClient);
+                }
+                (id, Some(secret))
+            }
+            None => (
+                params.client_id.clone().ok_or(OAuthError::InvalidClient)?,
+                params.client_secret.clone(),
+            ),
+        };
+        let client = self
+            .find_client(&client_id)
+            .await?
+            .ok_or(OAuthError::InvalidClient)?;
+        // 机密客户端必须提供正确的密钥，公开客户端不能提供密钥
+        match (&client.client_secret_hash, secret) {
+            (Some(hash), Some(secret)) if *hash == hash_token(&secret) => Ok(client),
+            (None, None) => Ok(client),
+            _ => Err(OAuthError::InvalidClient),
+        }
+    }
+
+    async fn redeem_code(
+        &self,
+        client: &ClientModel,
+        params: &TokenParams,
+    ) -> Result<TokenResponse, OAuthError> {
+        let code = params
+            .code
+            .as_deref()
+            .filter(|code| !code.is_empty())
+            .ok_or(OAuthError::InvalidRequest("code is required"))?;
+        // 先删除再校验：授权码无论校验是否通过都只能使用一次
+        let row = CodeEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "DELETE FROM oauth_authorization_codes WHERE code_hash = $1 RETURNING *",
+                [hash_token(code).into()],
+            ))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to redeem authorization code")?
+            .ok_or(OAuthError::InvalidGrant("unknown or already used code"))?;
+        if row.client_id != client.client_id || row.expires_at <= Utc::now() {
+            return Err(OAuthError::InvalidGrant(
+                "code expired or issued to another client",
+            ));
+        }
+        if params.redirect_uri.as_deref() != Some(row.redirect_uri.as_str()) {
+            return Err(OAuthError::InvalidGrant("redirect_uri mismatch"));
+        }
+        let verifier = params
+            .code_verifier
+            .as_deref()
+            .filter(|verifier| is_pkce_value(verifier))
+            .ok_or(OAuthError::InvalidRequest("code_verifier is required"))?;
+        if pkce_challenge(verifier) != row.code_challenge {
+            return Err(OAuthError::InvalidGrant("PKCE verification failed"));
+        }
+        let user = self
+            .find_user(row.user_id)
+            .await?
+            .ok_or(OAuthError::InvalidGrant("user no longer exists"))?;
+
+        let scopes = split_scope(&row.scope);
+        let key = self.current_signing_key().await?;
+        let mut response = self.access_token(&key, client, &user, &scopes)?;
+        if scopes.iter().any(|s| s == "openid") {
+            response.id_token =
+                Some(self.id_token(&key, client, &user, &scopes, row.nonce.as_deref())?);
+        }
+        let refresh_allowed = strings(&client.grant_types)
+            .iter()
+            .any(|g| g == "refresh_token");
+        if refresh_allowed && scopes.iter().any(|s| s == "offline_access") {
+            response.refresh_token =
+                Some(self.store_refresh_token(client, &user, &row.scope).await?);
+        }
+        Ok(response)
+    }
+
+    async fn refresh(
+        &self,
+        client: &ClientModel,
+        params: &TokenParams,
+    ) -> Result<TokenResponse, OAuthError> {
+        let raw = params
+            .refresh_token
+            .as_deref()
+            .filter(|token| !token.is_empty())
+            .ok_or(OAuthError::InvalidRequest("refresh_token is required"))?;
+        // 轮换：旧令牌在此删除，重放会失败
+        let row = RefreshEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "DELETE FROM oauth_refresh_tokens WHERE token_hash = $1 RETURNING *",
+                [hash_token(raw).into()],
+            ))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to redeem refresh token")?
+            .ok_or(OAuthError::InvalidGrant(
+                "unknown or already used refresh token",
+            ))?;
+        if row.client_id != client.client_id || row.expires_at <= Utc::now() {
+            return Err(OAuthError::InvalidGrant(
+                "refresh token expired or issued to another client",
+            ));
+        }
+        let user = self
+            .find_user(row.user_id)
+            .await?
+            .filter(|user| user.token_version == row.token_version)
+            .ok_or(OAuthError::InvalidGrant("refresh token was revoked"))?;
+
+        // 可缩小访问令牌的范围；新刷新令牌保留原范围（RFC 6749 §6）
+        let scopes = parse_scope(params.scope.as_deref(), &split_scope(&row.scope))?;
+        let key = self.current_signing_key().await?;
+        let mut response = self.access_token(&key, client, &user, &scopes)?;
+        response.refresh_token = Some(self.store_refresh_token(client, &user, &row.scope).await?);
+        Ok(response)
+    }
+
+    async fn client_credentials(
+        &self,
+        client: &ClientModel,
+        params: &TokenParams,
+    ) -> Result<TokenResponse, OAuthError> {
+        if client.client_secret_hash.is_none() {
+            return Err(OAuthError::UnauthorizedClient);
+        }
+        let allowed: Vec<String> = strings(&client.scopes)
+            .into_iter()
+            .filter(|scope| API_SCOPES.contains(&scope.as_str()))
+            .collect();
+        let scopes = parse_scope(params.scope.as_deref(), &allowed)?;
+        if scopes.is_empty() {
+            return Err(OAuthError::InvalidScope);
+        }
+        // 客户端凭据令牌代表注册该客户端的管理员
+        let owner = self
+            .find_user(client.owner_id)
+            .await?
+            .ok_or(OAuthError::UnauthorizedClient)?;
+        let key = self.current_signing_key().await?;
+        self.access_token(&key, client, &owner, &scopes)
+    }
+
+    fn access_token(
+        &self,
+        key: &SigningKey,
+        client: &ClientModel,
+        user: &UserModel,
+        scopes: &[String],
+    ) -> Result<TokenResponse, OAuthError> {
+        let now = Utc::now().timestamp() as u64;
+        let scope = scopes.join(" ");
+        let claims = AccessClaims {
+            iss: self.issuer(),
+            sub: user.id.to_string(),
+            aud: ACCESS_TOKEN_AUDIENCE.to_string(),
+            client_id: client.client_id.clone(),
+            scope: scope.clone(),
+            jti: random_token(),
+            // 未开启 admin_access 的客户端只有普通用户权限，与授权方式和同意者无关
+            role: if client.admin_access {
+                user.role.clone()
+            } else {
+                "user".to_string()
+            },
+            token_version: user.token_version,
+            iat: now,
+            exp: now + self.config.access_token_ttl_secs,
+        };
+        let mut header = Header::new(Algorithm::ES256);
+        header.typ = Some(ACCESS_TOKEN_TYPE.to_string());
+        header.kid = Some(key.kid.clone());
+        let access_token = jsonwebtoken::encode(&header, &claims, &key.encoding)
+            .context("Failed to sign access token")?;
+        Ok(TokenResponse {
+            access_token,
+            token_type: "Bearer",
+            expires_in: self.config.access_token_ttl_secs,
+            scope,
+            refresh_token: None,
+            id_token: None,
+        })
+    }
+
+    fn id_token(
+        &self,
+        key: &SigningKey,
+        client: &ClientModel,
+        user: &UserModel,
+        scopes: &[String],
+        nonce: Option<&str>,
+    ) -> Result<String, OAuthError> {
+        let now = Utc::now().timestamp() as u64;
+        let issuer = self.issuer();
+        let profile = scopes.iter().any(|s| s == "profile");
+        let email = scopes.iter().any(|s| s == "email");
+        let claims = IdTokenClaims {
+            iss: &issuer,
+            sub: user.id.to_string(),
+            aud: &client.client_id,
+            iat: now,
+            exp: now + self.config.access_token_ttl_secs,
+            nonce,
+            name: profile.then_some(user.name.as_str()),
+            email: email.then_some(user.email.as_str()),
+            email_verified: email.then_some(user.email_verified),
+        };
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(key.kid.clone());
+        Ok(jsonwebtoken::encode(&header, &claims, &key.encoding)
+            .context("Failed to sign ID token")?)
+    }
+
+    async fn store_refresh_token(
+        &self,
+        client: &ClientModel,
+        user: &UserModel,
+        scope: &str,
+    ) -> Result<String, OAuthError> {
+        let (raw, hash) = AuthService::generate_refresh_token();
+        let now = Utc::now();
+        RefreshActiveModel {
+            token_hash: Set(hash),
+            client_id: Set(client.client_id.clone()),
+            user_id: Set(user.id),
+            scope: Set(scope.to_string()),
+            token_version: Set(user.token_version),
+            expires_at: Set(
+                now + chrono::Duration::seconds(self.config.refresh_token_ttl_secs as i64)
+            ),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(self.db.write_conn())
+        .await
+        .context("Failed to store OAuth refresh token")?;
+        Ok(raw)
+    }
+
+    async fn decode_access_token(
+        &self,
+        header: &Header,
+        token: &str,
+    ) -> Result<AuthUser, OAuthError> {
+        if header.alg != Algorithm::ES256 {
+            return Err(OAuthError::InvalidToken("unsupported algorithm"));
+        }
+        let kid = header
+            .kid
+            .as_deref()
+            .ok_or(OAuthError::InvalidToken("missing kid"))?;
+        let key = self.verification_key(kid).await?;
+
+        let mut validation = Validation::new(Algorithm::ES256);
+        validation.leeway = 5;
+        validation.set_issuer(&[self.issuer()]);
+        validation.set_audience(&[ACCESS_TOKEN_AUDIENCE]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = jsonwebtoken::decode::<AccessClaims>(token, &key, &validation)
+            .map_err(|_| OAuthError::InvalidToken("signature or claims rejected"))?
+            .claims;
+        Ok(AuthUser {
+            user_id: claims.sub,
+            role: claims.role,
+            exp: claims.exp,
+            iat: claims.iat,
+            token_version: claims.token_version,
+            remember: false,
+            scopes: Some(split_scope(&claims.scope)),
+        })
+    }
+
+    // ── Keys and lookups ──────────────────────────────
+
+    /// Newest signing key; the first call on an empty table creates one.
+    async fn current_signing_key(&self) -> Result<SigningKey, OAuthError> {
+        let newest = SigningKeyEntity::find()
+            .order_by_desc(SigningKeyColumn::CreatedAt)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query OAuth signing key")?;
+        let row = match newest {
+            Some(row) => row,
+            None => {
+                let pkcs8 = EcdsaKeyPair::generate_pkcs8(
+                    &ECDSA_P256_SHA256_FIXED_SIGNING,
+                    &SystemRandom::new(),
+                )
+                .map_err(|_| anyhow::anyhow!("Failed to generate OAuth signing key"))?;
+                let mut kid = [0u8; 12];
+                rand::thread_rng().fill_bytes(&mut kid);
+                let row = SigningKeyActiveModel {
+                    kid: Set(URL_SAFE_NO_PAD.encode(kid)),
+                    private_key: Set(pkcs8.as_ref().to_vec()),
+                    created_at: Set(Utc::now()),
+                }
+                .insert(self.db.write_conn())
+                .await
+                .context("Failed to store OAuth signing key")?;
+                tracing::info!(kid = %row.kid, "OAuth signing key created");
+                row
+            }
+        };
+        Ok(SigningKey {
+            encoding: EncodingKey::from_ec_der(&row.private_key),
+            kid: row.kid,
+        })
+    }
+
+    async fn verification_key(&self, kid: &str) -> Result<DecodingKey, OAuthError> {
+        if let Some(key) = verification_keys()
+            .read()
+            .ok()
+            .and_then(|keys| keys.get(kid).cloned())
+        {
+            return Ok(key);
+        }
+        let row = SigningKeyEntity::find_by_id(kid.to_string())
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query OAuth signing key")?
+            .ok_or(OAuthError::InvalidToken("unknown signing key"))?;
+        let (x, y) = public_coordinates(&row.private_key)?;
+        let key = DecodingKey::from_ec_components(&x, &y)
+            .context("Failed to load OAuth verification key")?;
+        if let Ok(mut keys) = verification_keys().write() {
+            keys.insert(row.kid, key.clone());
+        }
+        Ok(key)
+    }
+
+    async fn find_client(&self, client_id: &str) -> Result<Option<ClientModel>, OAuthError> {
+        Ok(ClientEntity::find()
+            .filter(ClientColumn::ClientId.eq(client_id))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query OAuth client")?)
+    }
+
+    async fn find_user(&self, user_id: i64) -> Result<Option<UserModel>, OAuthError> {
+        Ok(UserEntity::find_by_id(user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user")?)
+    }
+}
+
+/// Credentials of an HTTP Basic `Authorization` header. Both parts are form-urlencoded
+/// (RFC 6749 §2.3.1).
+pub fn basic_credentials(header: &str) -> Option<(String, String)> {
+    let (scheme, encoded) = header.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (id, secret) = decoded.split_once(':')?;
+    let decode = |value: &str| {
+        url::form_urlencoded::parse(format!("v={value}").as_bytes())
+            .next()
+            .map(|(_, value)| value.into_owned())
+    };
+    Some((decode(id)?, decode(secret)?))
+}
+
+/// Delete authorization codes past their expiry.
+pub async fn cleanup_expired_codes(db: &impl ConnectionTrait) -> Result<u64, OAuthError> {
+    let result = CodeEntity::delete_many()
+        .filter(CodeColumn::ExpiresAt.lte(Utc::now()))
+        .exec(db)
+        .await
+        .context("Failed to cleanup expired authorization codes")?;
+    Ok(result.rows_affected)
+}
+
+/// Delete OAuth refresh tokens past their expiry.
+pub async fn cleanup_expired_refresh_tokens(db: &impl ConnectionTrait) -> Result<u64, OAuthError> {
+    let result = RefreshEntity::delete_many()
+        .filter(RefreshColumn::ExpiresAt.lte(Utc::now()))
+        .exec(db)
+        .await
+        .context("Failed to cleanup expired OAuth refresh tokens")?;
+    Ok(result.rows_affected)
+}
+
+fn hash_token(raw: &str) -> String {
+    hex::encode(Sha256::digest(raw.as_bytes()))
+}
+
+/// PKCE verifiers and S256 challenges: 43-128 characters of the unreserved set (RFC 7636 §4.1).
+fn is_pkce_value(value: &str) -> bool {
+    (43..=128).contains(&value.len())
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~'))
+}
+
+/// `x` and `y` (base64url) of the P-256 key in a PKCS#8 document.
+fn public_coordinates(pkcs8: &[u8]) -> anyhow::Result<(String, String)> {
+    let pair = EcdsaKeyPair::from_pkcs8(
+        &ECDSA_P256_SHA256_FIXED_SIGNING,
+        pkcs8,
+        &SystemRandom::new(),
+    )
+    .map_err(|e| anyhow::anyhow!("Invalid OAuth signing key: {e}"))?;
+    // 未压缩点：0x04 || x || y
+    let point = pair.public_key().as_ref();
+    Ok((
+        URL_SAFE_NO_PAD.encode(&point[1..33]),
+        URL_SAFE_NO_PAD.encode(&point[33..]),
+    ))
+}
+
+fn strings(value: &serde_json::Value) -> Vec<String> {
+    serde_json::from_value(value.clone()).unwrap_or_default()
+}
+
+fn split_scope(scope: &str) -> Vec<String> {
+    scope.split_whitespace().map(String::from).collect()
+}
+
+/// Deduplicate and sort into catalogue order.
+fn normalize_scopes(scopes: Vec<String>) -> Vec<String> {
+    SCOPES
+        .iter()
+        .filter(|known| scopes.iter().any(|scope| scope == *known))
+        .map(|scope| scope.to_string())
+        .collect()
+}
+
+/// Requested scopes, each of which must be in `allowed`; no request means all of `allowed`.
+fn parse_scope(requested: Option<&str>, allowed: &[String]) -> Result<Vec<String>, OAuthError> {
+    let requested = match requested.map(split_scope) {
+        Some(requested) if !requested.is_empty() => requested,
+        _ => return Ok(normalize_scopes(allowed.to_vec())),
+    };
+    if requested.iter().any(|scope| !allowed.contains(scope)) {
+        return Err(OAuthError::InvalidScope);
+    }
+    Ok(normalize_scopes(requested))
+}
+
+/// Check and normalize client settings.
+fn validate_settings(
+    settings: &ClientSettings,
+    confidential: bool,
+) -> Result<ClientSettings, OAuthError> {
+    let invalid = |message: &str| Err(OAuthError::InvalidClientMetadata(message.to_string()));
+
+    let name = settings.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_CLIENT_NAME_CHARS {
+        return invalid("name must be 1-100 characters");
+    }
+
+    let mut grant_types = Vec::new();
+    for grant in &settings.grant_types {
+        if !GRANT_TYPES.contains(&grant.as_str()) {
+            return Err(OAuthError::InvalidClientMetadata(format!(
+                "unknown grant type {grant:?}"
+            )));
+        }
+        if !grant_types.contains(grant) {
+            grant_types.push(grant.clone());
+        }
+    }
+    let has = |grant: &str| grant_types.iter().any(|g| g == grant);
+    if grant_types.is_empty() {
+        return invalid("grant_types must not be empty");
+    }
+    if has("refresh_token") && !has("authorization_code") {
+        return invalid("refresh_token requires the authorization_code grant");
+    }
+    if has("client_credentials") && !confidential {
+        return invalid("client_credentials requires a confidential client");
+    }
+
+    if settings.redirect_uris.len() > MAX_REDIRECT_URIS {
+        return invalid("at most 10 redirect URIs");
+    }
+    for uri in &settings.redirect_uris {
+        let parsed = url::Url::parse(uri)
+            .ok()
+            .filter(|_| uri.len() <= MAX_REDIRECT_URI_LEN);
+        let valid = parsed.is_some_and(|url| {
+            // 仅本机回调（原生应用、开发环境）可以用 http
+            let local = matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+            (url.scheme() == "https" || (url.scheme() == "http" && local))
+                && url.fragment().is_none()
+        });
+        if !valid {
+            return Err(OAuthError::InvalidClientMetadata(format!(
+                "redirect URI {uri:?} must be an https:// URL (http:// only for localhost) \
+                 without fragment"
+            )));
+        }
+    }
+    if has("authorization_code") && settings.redirect_uris.is_empty() {
+        return invalid("the authorization_code grant needs at least one redirect URI");
+    }
+
+    if let Some(scope) = settings
+        .scopes
+        .iter()
+        .find(|scope| !SCOPES.contains(&scope.as_str()))
+    {
+        return Err(OAuthError::InvalidClientMetadata(format!(
+            "unknown scope {scope:?}"
+        )));
+    }
+    let scopes = normalize_scopes(settings.scopes.clone());
+    if scopes.is_empty() {
+        return invalid("scopes must not be empty");
+    }
+
+    let mut redirect_uris: Vec<String> = Vec::new();
+    for uri in &settings.redirect_uris {
+        if !redirect_uris.contains(uri) {
+            redirect_uris.push(uri.clone());
+        }
+    }
+    Ok(ClientSettings {
+        name: name.to_string(),
+        redirect_uris,
+        grant_types,
+        scopes,
+        skip_consent: settings.skip_consent,
+        admin_access: settings.admin_access,
+    })
+}
+
+fn client_info(row: ClientModel) -> ClientInfo {
+    ClientInfo {
+        confidential: row.client_secret_hash.is_some(),
+        redirect_uris: strings(&row.redirect_uris),
+        grant_types: strings(&row.grant_types),
+        scopes: strings(&row.scopes),
+        client_id: row.client_id,
+        name: row.name,
+        skip_consent: row.skip_consent,
+        admin_access: row.admin_access,
+        owner_id: SnowflakeId::new(row.owner_id),
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(value: serde_json::Value) -> ClientSettings {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn service(secret: &str) -> OAuthService {
+        OAuthService::new(
+            AutoRouter::single(sea_orm::DatabaseConnection::Disconnected),
+            OAuthConfig::default(),
+            secret,
+        )
+    }
+
+    fn owned(scopes: &[&str]) -> Vec<String> {
+        scopes.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_scope_defaults_and_subsets() {
+        let allowed = owned(&["users:read", "openid", "email"]);
+        assert_eq!(
+            parse_scope(None, &allowed).unwrap(),
+            owned(&["openid", "email", "users:read"])
+        );
+        assert_eq!(
+            parse_scope(Some("  "), &allowed).unwrap(),
+            owned(&["openid", "email", "users:read"])
+        );
+        assert_eq!(
+            parse_scope(Some("users:read openid users:read"), &allowed).unwrap(),
+            owned(&["openid", "users:read"])
+        );
+        assert!(matches!(
+            parse_scope(Some("openid users:write"), &allowed),
+            Err(OAuthError::InvalidScope)
+        ));
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let web = settings(serde_json::json!({
+            "name": " Reader ",
+            "redirect_uris": ["https://reader.example.com/cb", "http://localhost:3000/cb"],
+            "scopes": ["users:read", "openid", "openid"],
+        }));
+        let normalized = validate_settings(&web, false).unwrap();
+        assert_eq!(normalized.name, "Reader");
+        assert_eq!(normalized.grant_types, default_grant_types());
+        assert_eq!(normalized.scopes, owned(&["openid", "users:read"]));
+
+        let rejected = [
+            serde_json::json!({"name": "x", "redirect_uris": ["http://example.com/cb"], "scopes": ["openid"]}),
+            serde_json::json!({"name": "x", "redirect_uris": ["https://example.com/cb#frag"], "scopes": ["openid"]}),
+            serde_json::json!({"name": "x", "redirect_uris": [], "scopes": ["openid"]}),
+            serde_json::json!({"name": "x", "redirect_uris": ["https://a.test/cb"], "scopes": ["admin"]}),
+            serde_json::json!({"name": "", "redirect_uris": ["https://a.test/cb"], "scopes": ["openid"]}),
+            serde_json::json!({"name": "x", "grant_types": ["refresh_token"], "scopes": ["openid"]}),
+            serde_json::json!({"name": "x", "grant_types": ["password"], "scopes": ["openid"]}),
+        ];
+        for value in rejected {
+            assert!(
+                matches!(
+                    validate_settings(&settings(value.clone()), true),
+                    Err(OAuthError::InvalidClientMetadata(_))
+                ),
+                "{value}"
+            );
+        }
+
+        let machine = settings(serde_json::json!({
+            "name": "Sync job",
+            "grant_types": ["client_credentials"],
+            "scopes": ["users:read"],
+        }));
+        assert!(validate_settings(&machine, true).is_ok());
+        assert!(validate_settings(&machine, false).is_err());
+    }
+
+    #[test]
+    fn test_basic_credentials_are_form_decoded() {
+        let header = format!("Basic {}", STANDARD.encode("my%20app:s%3Acret+x"));
+        assert_eq!(
+            basic_credentials(&header),
+            Some(("my app".to_string(), "s:cret x".to_string()))
+        );
+        assert_eq!(basic_credentials("Bearer abc"), None);
+        assert_eq!(basic_credentials("Basic !!!"), None);
+    }
+
+    #[test]
+    fn test_is_pkce_value() {
+        assert!(is_pkce_value(&"a".repeat(43)));
+        assert!(is_pkce_value("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"));
+        assert!(!is_pkce_value(&"a".repeat(42)));
+        assert!(!is_pkce_value(&"a".repeat(129)));
+        assert!(!is_pkce_value(&format!("{}+", "a".repeat(43))));
+    }
+
+    #[test]
+    fn test_public_coordinates_round_trip() {
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+                .unwrap();
+        let (x, y) = public_coordinates(pkcs8.as_ref()).unwrap();
+        let claims = serde_json::json!({"sub": "1", "exp": Utc::now().timestamp() + 60});
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::ES256),
+            &claims,
+            &EncodingKey::from_ec_der(pkcs8.as_ref()),
+        )
+        .unwrap();
+        let key = DecodingKey::from_ec_components(&x, &y).unwrap();
+        let mut validation = Validation::new(Algorithm::ES256);
+        validation.set_required_spec_claims(&["exp"]);
+        assert!(jsonwebtoken::decode::<serde_json::Value>(&token, &key, &validation).is_ok());
+    }
+
+    #[test]
+    fn test_request_token_round_trip_and_key_separation() {
+        let claims = RequestClaims {
+            client_id: "abc".to_string(),
+            redirect_uri: "https://a.test/cb".to_string(),
+            scope: "openid".to_string(),
+            state: Some("xyz".to_string()),
+            nonce: None,
+            code_challenge: "c".repeat(43),
+            exp: Utc::now().timestamp() + 60,
+        };
+        let service = service("secret");
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(&service.request_key),
+        )
+        .unwrap();
+        assert_eq!(
+            service.decode_request(&token).unwrap().state.as_deref(),
+            Some("xyz")
+        );
+        assert!(matches!(
+            self::service("other").decode_request(&token),
+            Err(OAuthError::InvalidAuthorizationRequest)
+        ));
+        // 会话 JWT 的密钥不能签发请求令牌
+        let session_signed = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        assert!(service.decode_request(&session_signed).is_err());
+    }
+
+    #[test]
+    fn test_client_redirect_appends_state_and_issuer() {
+        let url = service("secret")
+            .client_redirect("https://a.test/cb?keep=1", &[("code", "abc")], Some("s t"))
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://a.test/cb?keep=1&code=abc&state=s+t\
+             &iss=http%3A%2F%2Flocalhost%3A8080%2Fapi%2Fpublic%2Foauth"
+        );
+    }
+}
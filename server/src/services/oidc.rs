@@ -0,0 +1,805 @@
+//! Login with external OpenID Connect providers (relying party side).
+//!
+//! - Authorization code flow with PKCE (S256). `state`, `nonce` and the PKCE verifier travel
+//!   in a short-lived flow token — an HS256 JWT under a key derived from `jwt_secret` — that
+//!   the handler keeps in an httpOnly cookie, so no server-side flow storage is needed.
+//! - Provider endpoints come from the discovery document; it and the JWKS are cached in Redis
+//!   for `metadata_cache_secs`. An unknown `kid` refetches the JWKS once (key rotation).
+//! - ID tokens must be signed with an asymmetric key from the JWKS and carry the discovered
+//!   issuer, our client ID as audience, and the flow's nonce.
+//! - Identities are linked to users in `user_identities` by `(provider, sub)`. An unknown
+//!   identity is linked to the account with the same email only when both sides verified
+//!   that email; otherwise logging in cannot take over an account registered by someone else.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use chrono::Utc;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use schemars::JsonSchema;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::repositories::user::{Column as UserColumn, Entity as UserEntity};
+use crate::repositories::user_identity::{
+    ActiveModel as IdentityActiveModel, Column as IdentityColumn, Entity as IdentityEntity,
+    Model as IdentityModel,
+};
+use crate::services::cache::CacheService;
+use crate::services::user::{UserError, UserService};
+use crate::utils::config::{OidcConfig, OidcProviderConfig};
+use crate::utils::db_router::AutoRouter;
+
+/// Longest name taken over from the provider for a new account.
+const MAX_NAME_CHARS: usize = 50;
+
+/// Typed errors for OpenID Connect login
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("Unknown login provider")]
+    UnknownProvider,
+    #[error("Invalid or expired login state")]
+    InvalidState,
+    #[error("Login was cancelled at the provider")]
+    Denied,
+    #[error("Login provider request failed: {0}")]
+    Provider(String),
+    #[error("Invalid ID token: {0}")]
+    InvalidIdToken(&'static str),
+    #[error("The provider did not confirm a verified email address")]
+    EmailNotVerified,
+    #[error("An account with this email already exists")]
+    EmailInUse,
+    #[error("Sign-up with this provider is disabled")]
+    SignupDisabled,
+    #[error("Linked account not found")]
+    NotFound,
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl OidcError {
+    /// Stable code; also passed to the web app as `#oidc_error=<code>`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            OidcError::UnknownProvider => "unknown_oidc_provider",
+            OidcError::InvalidState => "invalid_oidc_state",
+            OidcError::Denied => "oidc_denied",
+            OidcError::Provider(_) => "oidc_provider_error",
+            OidcError::InvalidIdToken(_) => "invalid_id_token",
+            OidcError::EmailNotVerified => "oidc_email_not_verified",
+            OidcError::EmailInUse => "oidc_email_in_use",
+            OidcError::SignupDisabled => "oidc_signup_disabled",
+            OidcError::NotFound => "not_found",
+            OidcError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+/// A provider offered on the login page.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct OidcProviderInfo {
+    /// Key of `[oidc.providers.<id>]`, used in the authorize URL
+    pub id: String,
+    pub name: String,
+}
+
+/// External account linked to the current user.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct IdentityInfo {
+    pub id: i64,
+    pub provider: String,
+    /// Display name of the provider (the key when the provider was removed from the config)
+    pub provider_name: String,
+    pub email: Option<String>,
+    pub last_login_at: Option<chrono::DateTime<Utc>>,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+/// Query parameters the provider redirects back with.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct OidcCallback {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// Where to send the browser, plus the flow token to keep until the callback.
+#[derive(Debug)]
+pub struct AuthorizationRedirect {
+    pub url: String,
+    pub flow_token: String,
+}
+
+/// Result of a successful callback.
+#[derive(Debug)]
+pub struct OidcLogin {
+    pub user_id: i64,
+    pub remember: bool,
+    /// A new account was created for the identity
+    pub created: bool,
+}
+
+/// Claims of the flow token.
+#[derive(Debug, Serialize, Deserialize)]
+struct FlowClaims {
+    provider: String,
+    state: String,
+    nonce: String,
+    verifier: String,
+    remember: bool,
+    exp: i64,
+}
+
+/// The parts of the discovery document we use.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    #[serde(default)]
+    token_endpoint_auth_methods_supported: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    #[serde(default)]
+    nonce: Option<String>,
+    #[serde(default)]
+    azp: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+    /// Some providers send `"true"` as a string
+    #[serde(default)]
+    email_verified: Option<serde_json::Value>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    preferred_username: Option<String>,
+}
+
+impl IdTokenClaims {
+    fn email_verified(&self) -> bool {
+        match &self.email_verified {
+            Some(serde_json::Value::Bool(verified)) => *verified,
+            Some(serde_json::Value::String(verified)) => verified == "true",
+            _ => false,
+        }
+    }
+}
+
+/// Shared client; timeouts are set per request from the config.
+fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+pub struct OidcService {
+    db: Arc<AutoRouter>,
+    cache: CacheService,
+    config: OidcConfig,
+    flow_key: [u8; 32],
+}
+
+impl OidcService {
+    pub fn new(
+        db: Arc<AutoRouter>,
+        cache: CacheService,
+        config: OidcConfig,
+        jwt_secret: &str,
+    ) -> Self {
+        // 独立于会话 JWT 的签名密钥，流程令牌不能被当作访问令牌使用
+        let flow_key = Sha256::digest(format!("oidc-flow:{jwt_secret}")).into();
+        Self {
+            db,
+            cache,
+            config,
+            flow_key,
+        }
+    }
+
+    /// Enabled providers, sorted by key.
+    pub fn providers(&self) -> Vec<OidcProviderInfo> {
+        self.config
+            .providers
+            .iter()
+            .filter(|(_, provider)| provider.enabled)
+            .map(|(id, provider)| OidcProviderInfo {
+                id: id.clone(),
+                name: display_name(id, provider),
+            })
+            .collect()
+    }
+
+    /// Start logging in with `provider`: the authorization URL and the flow token.
+    pub async fn begin(
+        &self,
+        provider: &str,
+        remember: bool,
+    ) -> Result<AuthorizationRedirect, OidcError> {
+        let settings = self.provider(provider)?;
+        let metadata = self.metadata(settings).await?;
+
+        let verifier = random_token();
+        let claims = FlowClaims {
+            provider: provider.to_string(),
+            state: random_token(),
+            nonce: random_token(),
+            verifier,
+            remember,
+            exp: Utc::now().timestamp() + self.config.flow_ttl_secs as i64,
+        };
+
+        let mut url = url::Url::parse(&metadata.authorization_endpoint)
+            .context("Invalid authorization_endpoint")?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &settings.client_id)
+            .append_pair("redirect_uri", &self.redirect_uri(provider))
+            .append_pair("scope", &settings.scopes.join(" "))
+            .append_pair("state", &claims.state)
+            .append_pair("nonce", &claims.nonce)
+            .append_pair("code_challenge", &pkce_challenge(&claims.verifier))
+            .append_pair("code_challenge_method", "S256");
+
+        let flow_token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(&self.flow_key),
+        )
+        .context("Failed to sign OIDC flow token")?;
+
+        Ok(AuthorizationRedirect {
+            url: url.into(),
+            flow_token,
+        })
+    }
+
+    /// Finish logging in: check the flow, redeem the code, verify the ID token and find or
+    /// create the user.
+    pub async fn finish(
+        &self,
+        provider: &str,
+        callback: &OidcCallback,
+        flow_token: Option<&str>,
+    ) -> Result<OidcLogin, OidcError> {
+        let settings = self.provider(provider)?;
+        let flow = self.decode_flow(flow_token.ok_or(OidcError::InvalidState)?)?;
+        if flow.provider != provider || callback.state.as_deref() != Some(flow.state.as_str()) {
+            return Err(OidcError::InvalidState);
+        }
+
+        if let Some(error) = callback.error.as_deref() {
+            tracing::info!(
+                provider,
+                error,
+                description = callback.error_description.as_deref().unwrap_or(""),
+                "OIDC provider returned an error"
+            );
+            return Err(match error {
+                "access_denied" => OidcError::Denied,
+                _ => OidcError::Provider(error.to_string()),
+            });
+        }
+        let code = callback
+            .code
+            .as_deref()
+            .filter(|code| !code.is_empty())
+            .ok_or_else(|| OidcError::Provider("callback without code".to_string()))?;
+
+        let metadata = self.metadata(settings).await?;
+        let id_token = self
+            .exchange_code(provider, settings, &metadata, code, &flow.verifier)
+            .await?;
+        let claims = self
+            .verify_id_token(settings, &metadata, &id_token, &flow.nonce)
+            .await?;
+
+        let (user_id, created) = self.resolve_user(provider, settings, &claims).await?;
+        Ok(OidcLogin {
+            user_id,
+            remember: flow.remember,
+            created,
+        })
+    }
+
+    /// External accounts linked to `user_id`.
+    pub async fn identities(&self, user_id: i64) -> Result<Vec<IdentityInfo>, OidcError> {
+        let rows = IdentityEntity::find()
+            .filter(IdentityColumn::UserId.eq(user_id))
+            .order_by_asc(IdentityColumn::Id)
+            .all(self.db.write_conn())
+            .await
+            .context("Failed to query linked identities")?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let provider_name = self
+                    .config
+                    .providers
+                    .get(&row.provider)
+                    .map(|provider| display_name(&row.provider, provider))
+                    .unwrap_or_else(|| row.provider.clone());
+                IdentityInfo {
+                    id: row.id,
+                    provider: row.provider,
+                    provider_name,
+                    email: row.email,
+                    last_login_at: row.last_login_at,
+                    created_at: row.created_at,
+                }
+            })
+            .collect())
+    }
+
+    /// Unlink one of `user_id`'s external accounts.
+    pub async fn unlink(&self, user_id: i64, identity_id: i64) -> Result<(), OidcError> {
+        let result = IdentityEntity::delete_many()
+            .filter(IdentityColumn::Id.eq(identity_id))
+            .filter(IdentityColumn::UserId.eq(user_id))
+            .exec(self.db.write_conn())
+            .await
+            .context("Failed to delete linked identity")?;
+        if result.rows_affected == 0 {
+            return Err(OidcError::NotFound);
+        }
+        tracing::info!(user_id, identity_id, "External identity unlinked");
+        Ok(())
+    }
+
+    fn provider(&self, provider: &str) -> Result<&OidcProviderConfig, OidcError> {
+        self.config
+            .providers
+            .get(provider)
+            .filter(|settings| settings.enabled)
+            .ok_or(OidcError::UnknownProvider)
+    }
+
+    fn redirect_uri(&self, provider: &str) -> String {
+        format!(
+            "{}/api/public/auth/oidc/{provider}/callback",
+            self.config.public_url.trim_end_matches('/')
+        )
+    }
+
+    fn decode_flow(&self, token: &str) -> Result<FlowClaims, OidcError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+        validation.set_required_spec_claims(&["exp"]);
+        jsonwebtoken::decode::<FlowClaims>(
+            token,
+            &DecodingKey::from_secret(&self.flow_key),
+            &validation,
+        )
+        .map(|data| data.claims)
+        .map_err(|_| OidcError::InvalidState)
+    }
+
+    /// Discovery document, cached.
+    async fn metadata(&self, settings: &OidcProviderConfig) -> Result<ProviderMetadata, OidcError> {
+        let key = format!("oidc:discovery:{}", settings.issuer);
+        if let Some(metadata) = self.cached::<ProviderMetadata>(&key).await {
+            return Ok(metadata);
+        }
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            settings.issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self.fetch_json(&url).await?;
+        // 发现文档必须属于配置的 issuer（OIDC Discovery §4.3）
+        if metadata.issuer.trim_end_matches('/') != settings.issuer.trim_end_matches('/') {
+            return Err(OidcError::Provider(format!(
+                "discovery issuer {} does not match {}",
+                metadata.issuer, settings.issuer
+            )));
+        }
+        self.store(&key, &metadata).await;
+        Ok(metadata)
+    }
+
+    /// JWKS, cached; `refresh` bypasses the cache.
+    async fn jwks(&self, jwks_uri: &str, refresh: bool) -> Result<JwkSet, OidcError> {
+        let key = format!("oidc:jwks:{jwks_uri}");
+        if !refresh && let Some(jwks) = self.cached::<JwkSet>(&key).await {
+            return Ok(jwks);
+        }
+        let jwks: JwkSet = self.fetch_json(jwks_uri).await?;
+        self.store(&key, &jwks).await;
+        Ok(jwks)
+    }
+
+    async fn cached<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.cache.get(key).await.unwrap_or_else(|e| {
+            tracing::warn!(key, "OIDC metadata cache read failed: {}", e);
+            None
+        })
+    }
+
+    async fn store<T: Serialize>(&self, key: &str, value: &T) {
+        let ttl = Duration::from_secs(self.config.metadata_cache_secs);
+        if let Err(e) = self.cache.set(key, value, ttl).await {
+            tracing::warn!(key, "OIDC metadata cache write failed: {}", e);
+        }
+    }
+
+    async fn fetch_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, OidcError> {
+        let response = http_client()
+            .get(url)
+            .timeout(Duration::from_millis(self.config.http_timeout_ms))
+            .send()
+            .await
+            .map_err(|e| OidcError::Provider(format!("GET {url}: {e}")))?;
+        if !response.status().is_success() {
+            return Err(OidcError::Provider(format!(
+                "GET {url}: HTTP {}",
+                response.status()
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| OidcError::Provider(format!("GET {url}: {e}")))
+    }
+
+    /// Redeem the authorization code; returns the ID token.
+    async fn exchange_code(
+        &self,
+        provider: &str,
+        settings: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        code: &str,
+        verifier: &str,
+    ) -> Result<String, OidcError> {
+        let redirect_uri = self.redirect_uri(provider);
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("code_verifier", verifier),
+        ];
+        let mut request = http_client()
+            .post(&metadata.token_endpoint)
+            .timeout(Duration::from_millis(self.config.http_timeout_ms))
+            .header(http::header::ACCEPT, "application/json");
+
+        // 默认 client_secret_basic（RFC 6749 §2.3.1），仅当提供方只支持 post 时放进表单
+        let methods = &metadata.token_endpoint_auth_methods_supported;
+        if settings.client_secret.is_empty() {
+            form.push(("client_id", &settings.client_id));
+        } else if methods.is_empty() || methods.iter().any(|m| m == "client_secret_basic") {
+            let credentials = format!(
+                "{}:{}",
+                form_encode(&settings.client_id),
+                form_encode(&settings.client_secret)
+            );
+            request = request.header(
+                http::header::AUTHORIZATION,
+                format!("Basic {}", STANDARD.encode(credentials)),
+            );
+        } else {
+            form.push(("client_id", &settings.client_id));
+            form.push(("client_secret", &settings.client_secret));
+        }
+
+        let body = serde_urlencoded::to_string(&form).context("Failed to encode token request")?;
+        let response = request
+            .header(
+                http::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| OidcError::Provider(format!("token request: {e}")))?;
+
+        let status = response.status();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| OidcError::Provider(format!("token request: {e}")))?;
+        if !status.is_success() {
+            let error = serde_json::from_slice::<TokenErrorResponse>(&bytes)
+                .map(|body| body.error)
+                .unwrap_or_else(|_| format!("HTTP {status}"));
+            return Err(OidcError::Provider(format!("token request: {error}")));
+        }
+        serde_json::from_slice::<TokenResponse>(&bytes)
+            .map_err(|e| OidcError::Provider(format!("token response: {e}")))?
+            .id_token
+            .ok_or_else(|| OidcError::Provider("token response without id_token".to_string()))
+    }
+
+    async fn verify_id_token(
+        &self,
+        settings: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let header = jsonwebtoken::decode_header(id_token)
+            .map_err(|_| OidcError::InvalidIdToken("malformed"))?;
+        // 对称算法会把 client_secret 当作验签密钥，只接受 JWKS 中的公钥
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(OidcError::InvalidIdToken("unsupported algorithm"));
+        }
+
+        let jwk = self
+            .signing_key(&metadata.jwks_uri, header.kid.as_deref())
+            .await?;
+        let key =
+            DecodingKey::from_jwk(&jwk).map_err(|_| OidcError::InvalidIdToken("unusable key"))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&settings.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = jsonwebtoken::decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| {
+                tracing::info!("ID token rejected: {}", e);
+                OidcError::InvalidIdToken("signature or claims rejected")
+            })?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(OidcError::InvalidIdToken("nonce mismatch"));
+        }
+        if claims
+            .azp
+            .as_deref()
+            .is_some_and(|azp| azp != settings.client_id)
+        {
+            return Err(OidcError::InvalidIdToken("authorized party mismatch"));
+        }
+        Ok(claims)
+    }
+
+    /// The JWK for `kid`; a miss refetches the JWKS once in case the keys rotated.
+    async fn signing_key(&self, jwks_uri: &str, kid: Option<&str>) -> Result<Jwk, OidcError> {
+        for refresh in [false, true] {
+            let jwks = self.jwks(jwks_uri, refresh).await?;
+            let found = match kid {
+                Some(kid) => jwks.find(kid).cloned(),
+                // 无 kid 时仅在 JWKS 只有一把密钥时可用
+                None if jwks.keys.len() == 1 => jwks.keys.into_iter().next(),
+                None => None,
+            };
+            if let Some(jwk) = found {
+                return Ok(jwk);
+            }
+        }
+        Err(OidcError::InvalidIdToken("unknown signing key"))
+    }
+
+    /// Find the user for the identity, linking or creating an account on first login.
+    async fn resolve_user(
+        &self,
+        provider: &str,
+        settings: &OidcProviderConfig,
+        claims: &IdTokenClaims,
+    ) -> Result<(i64, bool), OidcError> {
+        let email = claims
+            .email
+            .as_deref()
+            .map(|email| email.trim().to_lowercase())
+            .filter(|email| !email.is_empty());
+
+        let existing = IdentityEntity::find()
+            .filter(IdentityColumn::Provider.eq(provider))
+            .filter(IdentityColumn::Subject.eq(&claims.sub))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query linked identity")?;
+        if let Some(identity) = existing {
+            let user_id = identity.user_id;
+            let mut active: IdentityActiveModel = identity.into();
+            active.last_login_at = Set(Some(Utc::now()));
+            if email.is_some() {
+                active.email = Set(email);
+            }
+            active
+                .update(self.db.write_conn())
+                .await
+                .context("Failed to update linked identity")?;
+            return Ok((user_id, false));
+        }
+
+        let email = email
+            .filter(|_| settings.trust_email || claims.email_verified())
+            .ok_or(OidcError::EmailNotVerified)?;
+
+        let user = UserEntity::find()
+            .filter(UserColumn::Email.eq(&email))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user by email")?;
+        let (user_id, created) = match user {
+            // 本地账号也必须验证过邮箱，否则可能是他人抢注的账号
+            Some(user) if settings.link_by_email && user.email_verified => (user.id, false),
+            Some(_) => return Err(OidcError::EmailInUse),
+            None if settings.allow_signup => {
+                let name = account_name(claims, &email);
+                let user = UserService::new(self.db.clone(), self.cache.clone())
+                    .create_external_user(&email, &name)
+                    .await
+                    .map_err(|e| match e {
+                        UserError::EmailConflict => OidcError::EmailInUse,
+                        other => OidcError::Internal(anyhow::anyhow!(other)),
+                    })?;
+                (user.id.as_i64(), true)
+            }
+            None => return Err(OidcError::SignupDisabled),
+        };
+
+        self.link(user_id, provider, &claims.sub, &email).await?;
+        tracing::info!(user_id, provider, created, "External identity linked");
+        Ok((user_id, created))
+    }
+
+    async fn link(
+        &self,
+        user_id: i64,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> Result<IdentityModel, OidcError> {
+        let now = Utc::now();
+        let identity = IdentityActiveModel {
+            user_id: Set(user_id),
+            provider: Set(provider.to_string()),
+            subject: Set(subject.to_string()),
+            email: Set(Some(email.to_string())),
+            last_login_at: Set(Some(now)),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(self.db.write_conn())
+        .await
+        .context("Failed to store linked identity")?;
+        Ok(identity)
+    }
+}
+
+fn display_name(id: &str, provider: &OidcProviderConfig) -> String {
+    provider
+        .display_name
+        .clone()
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// 32 random bytes, base64url — used for state, nonce and the PKCE verifier.
+pub(crate) fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// `code_challenge` for the S256 method (RFC 7636 §4.2).
+pub(crate) fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+fn form_encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// Name for a new account: `name`, else `preferred_username`, else the email's local part.
+fn account_name(claims: &IdTokenClaims, email: &str) -> String {
+    let name = [claims.name.as_deref(), claims.preferred_username.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|name| !name.is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(email));
+    name.chars().take(MAX_NAME_CHARS).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(value: serde_json::Value) -> IdTokenClaims {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_pkce_challenge_matches_rfc7636_example() {
+        // RFC 7636 Appendix B
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_email_verified_accepts_bool_and_string() {
+        assert!(claims(serde_json::json!({"sub": "1", "email_verified": true})).email_verified());
+        assert!(claims(serde_json::json!({"sub": "1", "email_verified": "true"})).email_verified());
+        assert!(
+            !claims(serde_json::json!({"sub": "1", "email_verified": "false"})).email_verified()
+        );
+        assert!(!claims(serde_json::json!({"sub": "1"})).email_verified());
+    }
+
+    #[test]
+    fn test_account_name_fallbacks() {
+        let full =
+            claims(serde_json::json!({"sub": "1", "name": " Ada ", "preferred_username": "ada"}));
+        assert_eq!(account_name(&full, "ada@example.com"), "Ada");
+        let username =
+            claims(serde_json::json!({"sub": "1", "name": "", "preferred_username": "ada"}));
+        assert_eq!(account_name(&username, "ada@example.com"), "ada");
+        let bare = claims(serde_json::json!({"sub": "1"}));
+        assert_eq!(account_name(&bare, "ada.l@example.com"), "ada.l");
+        let long = claims(serde_json::json!({"sub": "1", "name": "x".repeat(80)}));
+        assert_eq!(account_name(&long, "a@b.c").chars().count(), MAX_NAME_CHARS);
+    }
+
+    #[tokio::test]
+    async fn test_flow_token_round_trip_and_key_separation() {
+        let config = OidcConfig::default();
+        let claims = FlowClaims {
+            provider: "corp".to_string(),
+            state: random_token(),
+            nonce: random_token(),
+            verifier: random_token(),
+            remember: true,
+            exp: Utc::now().timestamp() + 60,
+        };
+        let flow_key: [u8; 32] = Sha256::digest("oidc-flow:secret").into();
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(&flow_key),
+        )
+        .unwrap();
+
+        let cache = CacheService::new("", 1).await;
+        let service = |secret: &str| {
+            OidcService::new(
+                AutoRouter::single(sea_orm::DatabaseConnection::Disconnected),
+                cache.clone(),
+                config.clone(),
+                secret,
+            )
+        };
+        let decoded = service("secret").decode_flow(&token).unwrap();
+        assert_eq!(decoded.state, claims.state);
+        assert!(decoded.remember);
+        assert!(matches!(
+            service("other").decode_flow(&token),
+            Err(OidcError::InvalidState)
+        ));
+        // 会话 JWT 的密钥不能签发流程令牌
+        let session_signed = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        assert!(service("secret").decode_flow(&session_signed).is_err());
+    }
+}
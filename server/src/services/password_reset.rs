@@ -1,5 +1,8 @@
+use crate::AppState;
 use crate::repositories::user::{Column, Entity as UserEntity};
+use crate::services::queue::{self, Job};
 use crate::utils::db_router::AutoRouter;
+use crate::utils::metrics;
 use crate::utils::password::hash_password;
 use anyhow::Context;
 use argon2::password_hash::PasswordHash;
@@ -11,6 +14,7 @@ use sea_orm::{
     ColumnTrait, ConnectionTrait, DatabaseBackend, EntityTrait, QueryFilter, Statement,
     TransactionTrait, sea_query::Expr,
 };
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 const CODE_EXPIRY_MINUTES: i64 = 10;
@@ -21,7 +25,7 @@ const MAX_FAILED_ATTEMPTS: i32 = 5;
 /// not exist.  Performing an Argon2 verification against this hash costs the
 /// same CPU time as verifying a real code, preventing attackers from
 /// enumerating registered emails by measuring response times on the
-/// forgot-password and reset-password endpoints.
+/// reset-password endpoint.
 fn dummy_code_hash() -> &'static str {
     static DUMMY_HASH: std::sync::OnceLock<String> = std::sync::OnceLock::new();
     DUMMY_HASH.get_or_init(|| hash_code("000000").expect("Failed to build dummy code hash"))
@@ -72,6 +76,26 @@ fn verify_code(code: &str, hash: &str) -> anyhow::Result<bool> {
         .is_ok())
 }
 
+/// Queue job: issue a fresh password reset code and mail it.
+///
+/// Like [`crate::services::verification::VerificationCodeEmail`], the code only exists
+/// while the job runs; every retry issues a new code.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordResetCodeEmail {
+    pub user_id: i64,
+}
+
+#[async_trait::async_trait]
+impl Job for PasswordResetCodeEmail {
+    const KIND: &'static str = "email.password_reset_code";
+
+    async fn run(self, state: &AppState) -> anyhow::Result<()> {
+        PasswordResetService::new(state.db.clone(), state.email.clone())
+            .deliver_code(self.user_id)
+            .await
+    }
+}
+
 pub struct PasswordResetService {
     db: Arc<AutoRouter>,
     email: EmailService,
@@ -96,30 +120,29 @@ impl PasswordResetService {
 
     /// Request a password-reset verification code sent to the user's email.
     ///
+    /// The code is issued and mailed by a queued [`PasswordResetCodeEmail`]; SMTP
+    /// failures are retried by the queue and never reach the caller.
+    ///
     /// Anti-enumeration:
-    /// - For non-existing users, performs a dummy Argon2 hash so the timing
-    ///   matches the existent-user path and returns `Ok(())` — regardless of
-    ///   whether the email service is configured.  This prevents attackers
-    ///   from inferring user existence via a 503 response.
+    /// - For non-existing users, returns `Ok(())` regardless of whether the email
+    ///   service is configured.  This prevents attackers from inferring user
+    ///   existence via a 503 response.  No dummy work is needed: the
+    ///   existent-user path does not hash or send anything in the request.
     /// - When the email service is not configured and the user exists, returns
     ///   `EmailNotConfigured` (mapped to 503) AFTER the user lookup, so the
     ///   503 only surfaces for registered emails.
     /// - For existing users within cooldown, returns `TooSoon`.
-    /// - For existing users past cooldown, stores a new code hash and sends
-    ///   the verification email.
+    /// - For existing users past cooldown, records the request time and queues
+    ///   the email in one transaction.
     pub async fn request_reset(&self, email: &str) -> Result<(), PasswordResetError> {
         let email_normalized = email.to_lowercase();
 
-        // Anti-enumeration: if the user does not exist, perform a dummy
-        // Argon2 verify (same CPU cost as the real path) and return Ok(())
+        // Anti-enumeration: if the user does not exist, return Ok(())
         // WITHOUT checking email service configuration first.  This ensures
         // non-existent emails always receive 200 regardless of SMTP state.
         let user = match self.find_user_by_email(&email_normalized).await? {
             Some(u) => u,
-            None => {
-                let _ = verify_code("000000", dummy_code_hash());
-                return Ok(());
-            }
+            None => return Ok(()),
         };
 
         // Only check email service configuration AFTER confirming the user
@@ -131,12 +154,11 @@ impl PasswordResetService {
 
         let now = Utc::now();
         let cooldown_threshold = now - Duration::seconds(RESEND_COOLDOWN_SECONDS);
-
-        // Generate the code eagerly so that creation cost is always paid
-        // before we open any DB write.
-        let code = generate_code();
-        let code_hash = hash_code(&code)?;
-        let expires_at = now + Duration::minutes(CODE_EXPIRY_MINUTES);
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction for password-reset request")?;
 
         // ── Atomic cooldown enforcement ──────────────────────────────────
         // Single UPDATE ... WHERE predicate; rows_affected == 0 means the
@@ -144,43 +166,65 @@ impl PasswordResetService {
         // two concurrent requests could both observe an old sent_at and both
         // issue a new code.
         let result = UserEntity::update_many()
-            .col_expr(Column::PasswordResetTokenHash, Expr::value(code_hash))
-            .col_expr(Column::PasswordResetExpiresAt, Expr::value(expires_at))
             .col_expr(Column::PasswordResetSentAt, Expr::value(now))
-            .col_expr(Column::PasswordResetFailedAttempts, Expr::value(0))
             .filter(Column::Id.eq(user.id))
             .filter(
                 Column::PasswordResetSentAt
                     .is_null()
                     .or(Column::PasswordResetSentAt.lte(cooldown_threshold)),
             )
-            .exec(&*self.db)
+            .exec(&txn)
             .await
-            .context("Failed to store password-reset code")?;
+            .context("Failed to record password-reset request")?;
 
         if result.rows_affected == 0 {
             return Err(PasswordResetError::TooSoon);
         }
 
-        // Send synchronously so the caller learns about failures immediately.
-        self.email
-            .send_password_reset_code_email(&email_normalized, &code, CODE_EXPIRY_MINUTES)
+        queue::enqueue(&txn, &PasswordResetCodeEmail { user_id: user.id })
             .await
-            .inspect_err(|e| {
-                tracing::error!(
-                    "Failed to send password-reset code email to {}: {:?}",
-                    email_normalized,
-                    e
-                );
-            })
-            .map_err(|e| {
-                PasswordResetError::Internal(anyhow::anyhow!(
-                    "Failed to send password-reset code email: {}",
-                    e
-                ))
-            })?;
-
-        tracing::info!("Password-reset code sent to {}", email_normalized);
+            .context("Failed to queue password-reset code email")?;
+        txn.commit()
+            .await
+            .context("Failed to commit password-reset request")?;
+
+        tracing::info!("Password-reset code queued for {}", email_normalized);
+        Ok(())
+    }
+
+    /// Issue a new reset code and mail it (job [`PasswordResetCodeEmail`]); deleted
+    /// users are skipped.
+    async fn deliver_code(&self, user_id: i64) -> anyhow::Result<()> {
+        let Some(user) = UserEntity::find_by_id(user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user")?
+        else {
+            return Ok(());
+        };
+
+        let code = generate_code();
+        let code_hash = hash_code(&code)?;
+        let expires_at = Utc::now() + Duration::minutes(CODE_EXPIRY_MINUTES);
+        UserEntity::update_many()
+            .col_expr(Column::PasswordResetTokenHash, Expr::value(code_hash))
+            .col_expr(Column::PasswordResetExpiresAt, Expr::value(expires_at))
+            .col_expr(Column::PasswordResetFailedAttempts, Expr::value(0))
+            .filter(Column::Id.eq(user.id))
+            .exec(&*self.db)
+            .await
+            .context("Failed to store password-reset code")?;
+
+        let result = self
+            .email
+            .send_password_reset_code_email(&user.email, &code, CODE_EXPIRY_MINUTES)
+            .await;
+        metrics::email_sent("password_reset_code", &result);
+        result.with_context(|| {
+            format!("Failed to send password-reset code email to {}", user.email)
+        })?;
+
+        tracing::info!("Password-reset code sent to {}", user.email);
         Ok(())
     }
 
@@ -346,6 +390,25 @@ impl PasswordResetService {
     }
 }
 
+/// Clear password reset codes whose expiry has passed (scheduled job `cleanup_expired_codes`).
+///
+/// `sent_at` is kept so the request cooldown still applies.
+pub async fn cleanup_expired_reset_codes(
+    db: &impl sea_orm::ConnectionTrait,
+) -> Result<u64, PasswordResetError> {
+    let result = UserEntity::update_many()
+        .col_expr(Column::PasswordResetTokenHash, Expr::value(None::<String>))
+        .col_expr(
+            Column::PasswordResetExpiresAt,
+            Expr::value(None::<chrono::DateTime<Utc>>),
+        )
+        .filter(Column::PasswordResetExpiresAt.lte(Utc::now()))
+        .exec(db)
+        .await
+        .context("Failed to cleanup expired password reset codes")?;
+    Ok(result.rows_affected)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,568 @@
+//! Durable job queue backed by the `job_queue` table.
+//!
+//! Work that should not run inside a request — sending email, for instance — is enqueued
+//! as a typed [`Job`] and executed by worker tasks in the server process:
+//!
+//! - [`enqueue`] inserts a row. It takes any connection, so a job can be enqueued in the
+//!   same transaction as the change that makes it necessary.
+//! - Workers claim due rows with `SELECT … FOR UPDATE SKIP LOCKED`, so replicas never pick
+//!   the same job at once. A claimed row is hidden until `locked_until`
+//!   (`[queue].visibility_timeout_secs`); a run exceeding it is cancelled, and a job whose
+//!   worker died becomes visible again and counts as a failed attempt.
+//! - A failed run is retried after `retry_base_secs * 2^(attempt - 1)` (capped at
+//!   `retry_max_secs`, plus up to 10% jitter). After [`Job::MAX_ATTEMPTS`] runs, or when
+//!   the payload cannot be decoded, the job moves to `dead`.
+//! - `GET /api/admin/queue` lists jobs; `POST /api/admin/queue/{id}/retry` re-queues a
+//!   dead one.
+//!
+//! Delivery is at-least-once: a job may run again after its worker lost the claim, so
+//! handlers must tolerate duplicates.
+
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use rand::Rng;
+use schemars::JsonSchema;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set, Statement,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::sync::Notify;
+use tracing::Instrument;
+
+use crate::AppState;
+use crate::repositories::queued_job::{ActiveModel, Column, Entity as QueuedJobEntity, Model};
+use crate::services::password_reset::PasswordResetCodeEmail;
+use crate::services::user::PaginatedResponse;
+use crate::services::verification::{VerificationCodeEmail, WelcomeEmail};
+use crate::utils::config::QueueConfig;
+use crate::utils::metrics;
+
+/// Values of `job_queue.status`.
+pub const STATUSES: &[&str] = &["queued", "running", "succeeded", "dead"];
+
+/// A job type. The value is stored as JSON in `job_queue.payload`.
+#[async_trait::async_trait]
+pub trait Job: Serialize + DeserializeOwned + Send + 'static {
+    /// Stored in `job_queue.kind`; must stay stable while jobs of the type may be queued
+    const KIND: &'static str;
+
+    /// Runs before the job is moved to `dead`
+    const MAX_ATTEMPTS: i32 = 5;
+
+    async fn run(self, state: &AppState) -> anyhow::Result<()>;
+}
+
+enum RunError {
+    /// Counts as an attempt; retried until `max_attempts`
+    Retry(anyhow::Error),
+    /// Retrying cannot help (unknown kind, undecodable payload)
+    Permanent(anyhow::Error),
+}
+
+type RunFuture = BoxFuture<'static, Result<(), RunError>>;
+
+/// Decodes and runs one job type.
+pub struct JobHandler {
+    pub kind: &'static str,
+    run: fn(AppState, serde_json::Value) -> RunFuture,
+}
+
+impl JobHandler {
+    pub const fn of<J: Job>() -> Self {
+        Self {
+            kind: J::KIND,
+            run: run_typed::<J>,
+        }
+    }
+}
+
+fn run_typed<J: Job>(state: AppState, payload: serde_json::Value) -> RunFuture {
+    Box::pin(async move {
+        let job: J = serde_json::from_value(payload)
+            .map_err(|e| RunError::Permanent(anyhow::anyhow!("Invalid job payload: {e}")))?;
+        job.run(&state).await.map_err(RunError::Retry)
+    })
+}
+
+/// Every job type the workers can run.
+pub const HANDLERS: &[JobHandler] = &[
+    JobHandler::of::<VerificationCodeEmail>(),
+    JobHandler::of::<PasswordResetCodeEmail>(),
+    JobHandler::of::<WelcomeEmail>(),
+];
+
+/// Wakes idle workers of this instance when a job is enqueued or retried here.
+static WAKE: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+#[derive(Debug, thiserror::Error)]
+pub enum QueueError {
+    #[error("Job {0} not found")]
+    NotFound(i64),
+    #[error("Job {id} is {status}; only dead jobs can be retried")]
+    NotRetryable { id: i64, status: String },
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl From<sea_orm::DbErr> for QueueError {
+    fn from(err: sea_orm::DbErr) -> Self {
+        QueueError::Internal(err.into())
+    }
+}
+
+/// Add `job` to the queue, due immediately; returns the job ID.
+pub async fn enqueue<J: Job>(db: &impl ConnectionTrait, job: &J) -> Result<i64, QueueError> {
+    let payload = serde_json::to_value(job)
+        .with_context(|| format!("Failed to serialize {} job", J::KIND))?;
+    let row = ActiveModel {
+        kind: Set(J::KIND.to_string()),
+        payload: Set(payload),
+        max_attempts: Set(J::MAX_ATTEMPTS),
+        run_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .with_context(|| format!("Failed to enqueue {} job", J::KIND))?;
+    // 在事务中入队时，唤醒的 worker 可能还看不到该行，最迟下次轮询时执行
+    WAKE.notify_one();
+    tracing::debug!(job_id = row.id, kind = J::KIND, "Job enqueued");
+    Ok(row.id)
+}
+
+/// A queued job as listed by `GET /api/admin/queue`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct JobRecord {
+    pub id: i64,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    /// `queued`, `running`, `succeeded` or `dead`
+    pub status: String,
+    /// Runs started so far
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Earliest time of the next run
+    pub run_at: DateTime<Utc>,
+    /// End of the visibility timeout while `running`
+    pub locked_until: Option<DateTime<Utc>>,
+    /// `host/pid` of the worker that claimed the job last
+    pub locked_by: Option<String>,
+    /// Error of the last failed run
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl From<Model> for JobRecord {
+    fn from(row: Model) -> Self {
+        Self {
+            id: row.id,
+            kind: row.kind,
+            payload: row.payload,
+            status: row.status,
+            attempts: row.attempts,
+            max_attempts: row.max_attempts,
+            run_at: row.run_at,
+            locked_until: row.locked_until,
+            locked_by: row.locked_by,
+            last_error: row.last_error,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            finished_at: row.finished_at,
+        }
+    }
+}
+
+/// Filter of [`JobQueue::list`].
+#[derive(Debug, Default)]
+pub struct JobFilter {
+    pub status: Option<String>,
+    pub kind: Option<String>,
+}
+
+/// Running jobs whose visibility timeout passed on their last attempt.
+const EXPIRE_EXHAUSTED: &str = "UPDATE job_queue SET
+    status = 'dead',
+    locked_until = NULL,
+    last_error = 'Visibility timeout exceeded on the last attempt',
+    finished_at = NOW(),
+    updated_at = NOW()
+WHERE status = 'running' AND locked_until < NOW() AND attempts >= max_attempts";
+
+const CLAIM_JOBS: &str = "UPDATE job_queue SET
+    status = 'running',
+    attempts = attempts + 1,
+    locked_until = $1,
+    locked_by = $2,
+    updated_at = NOW()
+WHERE id IN (
+    SELECT id FROM job_queue
+    WHERE (status = 'queued' AND run_at <= NOW())
+        OR (status = 'running' AND locked_until < NOW() AND attempts < max_attempts)
+    ORDER BY run_at, id
+    LIMIT $3
+    FOR UPDATE SKIP LOCKED
+)
+RETURNING *";
+
+/// Claims and runs queued jobs. Cheap to construct; the admin handlers build one per
+/// request.
+#[derive(Clone)]
+pub struct JobQueue {
+    state: AppState,
+    config: Arc<QueueConfig>,
+    instance: Arc<str>,
+}
+
+impl JobQueue {
+    pub fn new(state: AppState) -> Self {
+        let config = Arc::new(state.config.queue.clone());
+        let instance = format!("{}/{}", crate::snowflake::hostname(), std::process::id());
+        Self {
+            state,
+            config,
+            instance: instance.into(),
+        }
+    }
+
+    fn visibility_timeout(&self) -> Duration {
+        Duration::from_secs(self.config.visibility_timeout_secs.max(1))
+    }
+
+    /// One page of jobs, newest first.
+    pub async fn list(
+        &self,
+        filter: JobFilter,
+        page: u64,
+        per_page: u64,
+    ) -> Result<PaginatedResponse<JobRecord>, QueueError> {
+        let page = page.clamp(1, 1_000_000);
+        let per_page = per_page.clamp(1, 100);
+
+        let mut query = QueuedJobEntity::find().order_by_desc(Column::Id);
+        if let Some(status) = filter.status {
+            query = query.filter(Column::Status.eq(status));
+        }
+        if let Some(kind) = filter.kind {
+            query = query.filter(Column::Kind.eq(kind));
+        }
+        let paginator = query.paginate(self.state.db.write_conn(), per_page);
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(page - 1).await?;
+        Ok(PaginatedResponse {
+            items: items.into_iter().map(JobRecord::from).collect(),
+            total,
+            page,
+            per_page,
+            total_pages: total.div_ceil(per_page),
+        })
+    }
+
+    pub async fn get(&self, id: i64) -> Result<JobRecord, QueueError> {
+        QueuedJobEntity::find_by_id(id)
+            .one(self.state.db.write_conn())
+            .await?
+            .map(JobRecord::from)
+            .ok_or(QueueError::NotFound(id))
+    }
+
+    /// Move a dead job back to `queued` with a fresh set of attempts.
+    pub async fn retry(&self, id: i64) -> Result<JobRecord, QueueError> {
+        let now = Utc::now();
+        let result = QueuedJobEntity::update_many()
+            .col_expr(Column::Status, Expr::value("queued"))
+            .col_expr(Column::Attempts, Expr::value(0))
+            .col_expr(Column::RunAt, Expr::value(now))
+            .col_expr(Column::FinishedAt, Expr::value(None::<DateTime<Utc>>))
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(Column::Id.eq(id))
+            .filter(Column::Status.eq("dead"))
+            .exec(self.state.db.write_conn())
+            .await?;
+        let job = self.get(id).await?;
+        if result.rows_affected == 0 {
+            return Err(QueueError::NotRetryable {
+                id,
+                status: job.status,
+            });
+        }
+        WAKE.notify_one();
+        Ok(job)
+    }
+
+    /// Claim up to `limit` due jobs for this instance.
+    async fn claim(&self, limit: usize) -> Result<Vec<Model>, QueueError> {
+        let db = self.state.db.write_conn();
+        let expired = db
+            .execute_unprepared(EXPIRE_EXHAUSTED)
+            .await?
+            .rows_affected();
+        if expired > 0 {
+            tracing::error!(count = expired, "Jobs timed out on their last attempt");
+        }
+        let locked_until =
+            Utc::now() + chrono::Duration::seconds(self.visibility_timeout().as_secs() as i64);
+        let jobs = QueuedJobEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                CLAIM_JOBS,
+                [
+                    locked_until.into(),
+                    self.instance.to_string().into(),
+                    (limit as i64).into(),
+                ],
+            ))
+            .all(db)
+            .await?;
+        Ok(jobs)
+    }
+
+    /// Claim up to `[queue].concurrency` due jobs and run them to completion; returns the
+    /// number of jobs run. The workers started by [`JobQueue::start`] do this continuously.
+    pub async fn run_due(&self) -> Result<usize, QueueError> {
+        let jobs = self.claim(self.config.concurrency.max(1)).await?;
+        let count = jobs.len();
+        futures::future::join_all(jobs.into_iter().map(|job| self.clone().execute(job))).await;
+        Ok(count)
+    }
+
+    async fn execute(self, job: Model) {
+        let span = tracing::info_span!(
+            "queue_job",
+            job_id = job.id,
+            kind = %job.kind,
+            attempt = job.attempts
+        );
+        async move {
+            let result = match HANDLERS.iter().find(|handler| handler.kind == job.kind) {
+                Some(handler) => {
+                    let run = (handler.run)(self.state.clone(), job.payload.clone());
+                    let timeout = self.visibility_timeout();
+                    tokio::time::timeout(timeout, run)
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(RunError::Retry(anyhow::anyhow!(
+                                "Cancelled after the visibility timeout of {}s",
+                                timeout.as_secs()
+                            )))
+                        })
+                }
+                None => Err(RunError::Permanent(anyhow::anyhow!(
+                    "No handler registered for job kind {}",
+                    job.kind
+                ))),
+            };
+            if let Err(e) = self.finish(&job, result).await {
+                tracing::error!("Failed to record job result: {e:#}");
+            }
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Record the outcome of a run. `(id, attempts)` identifies the claim, so a run that
+    /// lost its claim cannot overwrite the state of the run that took over.
+    async fn finish(&self, job: &Model, result: Result<(), RunError>) -> Result<(), QueueError> {
+        let now = Utc::now();
+        let mut update = QueuedJobEntity::update_many()
+            .col_expr(Column::LockedUntil, Expr::value(None::<DateTime<Utc>>))
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(Column::Id.eq(job.id))
+            .filter(Column::Status.eq("running"))
+            .filter(Column::Attempts.eq(job.attempts));
+
+        let outcome = match result {
+            Ok(()) => {
+                tracing::info!("Job succeeded");
+                update = update
+                    .col_expr(Column::Status, Expr::value("succeeded"))
+                    .col_expr(Column::FinishedAt, Expr::value(now));
+                "succeeded"
+            }
+            Err(err) => {
+                let (err, permanent) = match err {
+                    RunError::Retry(err) => (err, false),
+                    RunError::Permanent(err) => (err, true),
+                };
+                update = update.col_expr(Column::LastError, Expr::value(format!("{err:#}")));
+                if permanent || job.attempts >= job.max_attempts {
+                    tracing::error!(max_attempts = job.max_attempts, "Job dead: {err:#}");
+                    update = update
+                        .col_expr(Column::Status, Expr::value("dead"))
+                        .col_expr(Column::FinishedAt, Expr::value(now));
+                    "dead"
+                } else {
+                    let delay = with_jitter(retry_delay(&self.config, job.attempts));
+                    tracing::warn!(
+                        retry_in_secs = delay.as_secs(),
+                        "Job failed, will retry: {err:#}"
+                    );
+                    let run_at = now
+                        + chrono::Duration::from_std(delay)
+                            .unwrap_or(chrono::Duration::seconds(60));
+                    update = update
+                        .col_expr(Column::Status, Expr::value("queued"))
+                        .col_expr(Column::RunAt, Expr::value(run_at));
+                    "retried"
+                }
+            }
+        };
+        metrics::queue_job_run(&job.kind, outcome);
+
+        let recorded = update.exec(self.state.db.write_conn()).await?.rows_affected == 1;
+        if !recorded {
+            tracing::warn!("Job claim lost before the result was recorded");
+        }
+        Ok(())
+    }
+
+    /// Start the workers of this instance (unless `[queue].enabled` is false). Keep the
+    /// handle until shutdown; [`QueueHandle::shutdown`] waits for running jobs.
+    pub fn start(state: AppState) -> QueueHandle {
+        let queue = JobQueue::new(state);
+        if !queue.config.enabled {
+            tracing::info!("Queue workers disabled on this instance ([queue].enabled = false)");
+            return QueueHandle {
+                stop_tx: None,
+                task: None,
+            };
+        }
+        let concurrency = queue.config.concurrency.max(1);
+        let poll = Duration::from_millis(queue.config.poll_interval_ms.max(10));
+        tracing::info!(concurrency, "Queue workers started");
+
+        let (stop_tx, mut stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let mut runs = tokio::task::JoinSet::new();
+            loop {
+                let free = concurrency - runs.len();
+                if free > 0 {
+                    match queue.claim(free).await {
+                        Ok(jobs) => {
+                            for job in jobs {
+                                runs.spawn(queue.clone().execute(job));
+                            }
+                        }
+                        Err(e) => tracing::warn!("Queue poll failed: {e:#}"),
+                    }
+                }
+                // 队列空闲时等待轮询间隔或本实例的入队通知；有任务完成时立即补充
+                tokio::select! {
+                    _ = tokio::time::sleep(poll) => {}
+                    _ = WAKE.notified() => {}
+                    Some(_) = runs.join_next(), if !runs.is_empty() => {}
+                    _ = &mut stop_rx => break,
+                }
+            }
+            // 停止认领后等待进行中的任务（受 shutdown.task_timeout_secs 限制）；
+            // 被中断的任务在可见性超时后由其他实例重试
+            while runs.join_next().await.is_some() {}
+        });
+
+        QueueHandle {
+            stop_tx: Some(stop_tx),
+            task: Some(task),
+        }
+    }
+}
+
+/// Delay before retrying a job whose `attempt`-th run failed.
+fn retry_delay(config: &QueueConfig, attempt: i32) -> Duration {
+    let base = config.retry_base_secs.max(1);
+    let exponent = attempt.saturating_sub(1).clamp(0, 32) as u32;
+    let secs = base
+        .saturating_mul(1u64 << exponent)
+        .min(config.retry_max_secs.max(base));
+    Duration::from_secs(secs)
+}
+
+/// Spread retries of jobs that failed together (e.g. during an SMTP outage).
+fn with_jitter(delay: Duration) -> Duration {
+    let max_jitter = delay.as_millis() as u64 / 10;
+    delay + Duration::from_millis(rand::thread_rng().gen_range(0..=max_jitter))
+}
+
+/// Delete succeeded jobs finished more than `[queue].retention_days` ago (scheduled job
+/// `purge_job_queue`). Dead jobs are kept for inspection.
+pub async fn purge_finished(
+    db: &impl ConnectionTrait,
+    config: &QueueConfig,
+) -> Result<u64, QueueError> {
+    let cutoff = Utc::now() - chrono::Duration::days(i64::from(config.retention_days));
+    let result = QueuedJobEntity::delete_many()
+        .filter(Column::Status.eq("succeeded"))
+        .filter(Column::FinishedAt.lt(cutoff))
+        .exec(db)
+        .await
+        .context("Failed to purge finished jobs")?;
+    Ok(result.rows_affected)
+}
+
+/// Worker task handle returned by [`JobQueue::start`].
+#[derive(Debug)]
+pub struct QueueHandle {
+    stop_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl Drop for QueueHandle {
+    fn drop(&mut self) {
+        if let Some(tx) = self.stop_tx.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+impl QueueHandle {
+    /// Stop claiming jobs and wait for the ones this instance is running.
+    pub async fn shutdown(mut self) {
+        if let Some(tx) = self.stop_tx.take() {
+            let _ = tx.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        let config = QueueConfig {
+            retry_base_secs: 10,
+            retry_max_secs: 100,
+            ..QueueConfig::default()
+        };
+        let delays: Vec<u64> = (1..=6)
+            .map(|attempt| retry_delay(&config, attempt).as_secs())
+            .collect();
+        assert_eq!(delays, [10, 20, 40, 80, 100, 100]);
+        // 极端的重试次数不会溢出
+        assert_eq!(retry_delay(&config, i32::MAX).as_secs(), 100);
+        assert_eq!(retry_delay(&config, 0).as_secs(), 10);
+
+        for _ in 0..100 {
+            let delay = with_jitter(Duration::from_secs(10));
+            assert!(delay >= Duration::from_secs(10) && delay <= Duration::from_secs(11));
+        }
+    }
+
+    #[test]
+    fn handler_kinds_are_unique() {
+        let mut kinds: Vec<_> = HANDLERS.iter().map(|handler| handler.kind).collect();
+        kinds.sort_unstable();
+        kinds.dedup();
+        assert_eq!(kinds.len(), HANDLERS.len());
+    }
+}
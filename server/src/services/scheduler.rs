@@ -0,0 +1,790 @@
+//! Background job scheduler.
+//!
+//! Built-in jobs are listed in [`JOBS`]; `[scheduler.jobs.<name>]` can override their
+//! schedule or disable them. Every replica polls `scheduled_jobs` for due rows and runs a
+//! due job only after winning both
+//!
+//! 1. the Redis lock `scheduler:job:<name>` ([`LockGuard`]), and
+//! 2. a conditional claim of the job row (`status <> 'running'` or its lease expired, and
+//!    `next_run_at <= now()` for scheduled runs).
+//!
+//! The lock keeps replicas from racing on the same tick; the claim keeps a late replica
+//! from re-running a job another replica has just finished, and is the only guard when
+//! Redis is not configured. While the job runs, the lock TTL and the row's `locked_until`
+//! are renewed every `lease_secs / 3`; if either is lost the job is cancelled, and a
+//! crashed runner frees the job after one lease.
+//!
+//! Outcome, duration and the next run time are written back to the row, which
+//! `GET /api/admin/jobs` lists; `POST /api/admin/jobs/{name}/run` starts a manual run.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use chrono::{DateTime, SubsecRound, Utc};
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseBackend, EntityTrait, QueryFilter, Statement,
+};
+use serde::Serialize;
+use tracing::Instrument;
+
+use crate::AppState;
+use crate::repositories::scheduled_job::{Column, Entity as ScheduledJobEntity, Model};
+use crate::services::lock::{AcquireResult, LockGuard};
+use crate::utils::config::SchedulerConfig;
+use crate::utils::metrics;
+
+/// Future returned by a job; the `Ok` string is stored as `last_output`.
+pub type JobFuture = BoxFuture<'static, anyhow::Result<String>>;
+
+/// A built-in job.
+pub struct JobDef {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Default schedule, see [`Schedule`]
+    pub schedule: &'static str,
+    pub run: fn(AppState) -> JobFuture,
+}
+
+impl std::fmt::Debug for JobDef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobDef")
+            .field("name", &self.name)
+            .field("schedule", &self.schedule)
+            .finish()
+    }
+}
+
+/// All built-in jobs.
+pub const JOBS: &[JobDef] = &[
+    JobDef {
+        name: "cleanup_refresh_tokens",
+        description: "Delete expired session and OAuth refresh tokens",
+        schedule: "every 1h",
+        run: cleanup_refresh_tokens,
+    },
+    JobDef {
+        name: "cleanup_expired_codes",
+        description: "Clear expired verification, password reset, two-factor, passkey and OAuth codes",
+        schedule: "every 15m",
+        run: cleanup_expired_codes,
+    },
+    JobDef {
+        name: "purge_job_queue",
+        description: "Delete succeeded queue jobs older than [queue].retention_days",
+        schedule: "every 1h",
+        run: purge_job_queue,
+    },
+];
+
+fn cleanup_refresh_tokens(state: AppState) -> JobFuture {
+    Box::pin(async move {
+        let db = state.db.write_conn();
+        let deleted = crate::services::auth::cleanup_expired_refresh_tokens(db).await?;
+        let oauth = crate::services::oauth::cleanup_expired_refresh_tokens(db).await?;
+        Ok(format!(
+            "deleted {deleted} expired refresh tokens, {oauth} OAuth refresh tokens"
+        ))
+    })
+}
+
+fn cleanup_expired_codes(state: AppState) -> JobFuture {
+    Box::pin(async move {
+        let db = state.db.write_conn();
+        let verification =
+            crate::services::verification::cleanup_expired_verification_codes(db).await?;
+        let reset = crate::services::password_reset::cleanup_expired_reset_codes(db).await?;
+        let challenges = crate::services::two_factor::cleanup_expired_challenges(db).await?;
+        let passkey = crate::services::webauthn::cleanup_expired_challenges(db).await?;
+        let oauth = crate::services::oauth::cleanup_expired_codes(db).await?;
+        Ok(format!(
+            "cleared {verification} verification codes, {reset} password reset codes, \
+             {challenges} two-factor challenges, {passkey} passkey challenges, \
+             {oauth} OAuth authorization codes"
+        ))
+    })
+}
+
+fn purge_job_queue(state: AppState) -> JobFuture {
+    Box::pin(async move {
+        let deleted =
+            crate::services::queue::purge_finished(state.db.write_conn(), &state.config.queue)
+                .await?;
+        Ok(format!("deleted {deleted} finished queue jobs"))
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SchedulerError {
+    #[error("Invalid schedule for job {job}: {message}")]
+    InvalidSchedule { job: String, message: String },
+    #[error("Unknown job: {0}")]
+    UnknownJob(String),
+    #[error("Job {0} is already running")]
+    AlreadyRunning(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl From<sea_orm::DbErr> for SchedulerError {
+    fn from(err: sea_orm::DbErr) -> Self {
+        SchedulerError::Internal(err.into())
+    }
+}
+
+/// When a job is due.
+///
+/// - `every <n><s|m|h|d>`, e.g. `every 90s`, `every 1h` — `n` after the previous run ends;
+/// - a cron expression in UTC, either 5 fields (`min hour day month weekday`) or the
+///   6–7 field form of the `cron` crate (leading seconds, optional trailing year).
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    Every(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let expr = expr.trim();
+        if let Some(every) = expr.strip_prefix("every ") {
+            let every = every.trim();
+            let split = every
+                .find(|c: char| !c.is_ascii_digit())
+                .ok_or_else(|| format!("missing unit in `{expr}` (use s, m, h or d)"))?;
+            let (count, unit) = every.split_at(split);
+            let count: u64 = count
+                .parse()
+                .map_err(|_| format!("invalid interval `{expr}`"))?;
+            let unit_secs = match unit.trim() {
+                "s" => 1,
+                "m" => 60,
+                "h" => 3600,
+                "d" => 86_400,
+                other => return Err(format!("unknown unit `{other}` (use s, m, h or d)")),
+            };
+            if count == 0 {
+                return Err("interval must be positive".to_string());
+            }
+            return Ok(Schedule::Every(Duration::from_secs(count * unit_secs)));
+        }
+
+        // 5 段的标准 cron 表达式补上秒字段
+        let expr = if expr.split_whitespace().count() == 5 {
+            format!("0 {expr}")
+        } else {
+            expr.to_string()
+        };
+        cron::Schedule::from_str(&expr)
+            .map(|schedule| Schedule::Cron(Box::new(schedule)))
+            .map_err(|e| format!("invalid cron expression: {e}"))
+    }
+}
+
+impl Schedule {
+    /// First due time strictly after `after`; `None` when a cron schedule has no further
+    /// occurrence.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Every(interval) => Some(after + chrono::Duration::from_std(*interval).ok()?),
+            Schedule::Cron(schedule) => schedule.after(&after).next(),
+        }
+    }
+}
+
+/// A built-in job combined with its `[scheduler.jobs]` override.
+#[derive(Debug, Clone)]
+pub struct JobSpec {
+    pub def: &'static JobDef,
+    /// Effective schedule expression as written in config (or the default)
+    pub expr: String,
+    pub schedule: Schedule,
+    pub enabled: bool,
+}
+
+/// Resolve [`JOBS`] against `config`, rejecting invalid schedules and overrides of unknown
+/// jobs (also run by `webshelf check-config`).
+pub fn configured_jobs(config: &SchedulerConfig) -> Result<Vec<JobSpec>, SchedulerError> {
+    if let Some(name) = config
+        .jobs
+        .keys()
+        .find(|name| !JOBS.iter().any(|def| def.name == name.as_str()))
+    {
+        return Err(SchedulerError::UnknownJob(name.clone()));
+    }
+    JOBS.iter()
+        .map(|def| {
+            let overrides = config.jobs.get(def.name);
+            let expr = overrides
+                .and_then(|o| o.schedule.clone())
+                .unwrap_or_else(|| def.schedule.to_string());
+            let schedule = expr
+                .parse()
+                .map_err(|message| SchedulerError::InvalidSchedule {
+                    job: def.name.to_string(),
+                    message,
+                })?;
+            Ok(JobSpec {
+                def,
+                expr,
+                schedule,
+                enabled: overrides.and_then(|o| o.enabled).unwrap_or(true),
+            })
+        })
+        .collect()
+}
+
+/// State of one job as listed by `GET /api/admin/jobs`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct JobStatus {
+    pub name: String,
+    pub description: String,
+    pub schedule: String,
+    /// Whether scheduled runs happen; manual runs are always possible
+    pub enabled: bool,
+    /// `idle` (never run), `running`, `succeeded` or `failed`
+    pub status: String,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<i64>,
+    /// Summary of the last run when it succeeded
+    pub last_output: Option<String>,
+    /// Error of the last run when it failed
+    pub last_error: Option<String>,
+    /// `host/pid` of the instance that ran the job last
+    pub last_run_by: Option<String>,
+    pub run_count: i64,
+    pub failure_count: i64,
+}
+
+impl JobStatus {
+    fn new(spec: &JobSpec, row: Option<Model>) -> Self {
+        let row = row.unwrap_or_else(|| Model {
+            name: spec.def.name.to_string(),
+            schedule: spec.expr.clone(),
+            enabled: spec.enabled,
+            status: "idle".to_string(),
+            next_run_at: None,
+            locked_until: None,
+            last_started_at: None,
+            last_finished_at: None,
+            last_duration_ms: None,
+            last_output: None,
+            last_error: None,
+            last_run_by: None,
+            run_count: 0,
+            failure_count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        });
+        Self {
+            name: row.name,
+            description: spec.def.description.to_string(),
+            schedule: row.schedule,
+            enabled: row.enabled,
+            status: row.status,
+            next_run_at: row.next_run_at,
+            last_started_at: row.last_started_at,
+            last_finished_at: row.last_finished_at,
+            last_duration_ms: row.last_duration_ms,
+            last_output: row.last_output,
+            last_error: row.last_error,
+            last_run_by: row.last_run_by,
+            run_count: row.run_count,
+            failure_count: row.failure_count,
+        }
+    }
+}
+
+const UPSERT_JOB: &str = "INSERT INTO scheduled_jobs (name, schedule, enabled, next_run_at)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (name) DO UPDATE SET
+    schedule = EXCLUDED.schedule,
+    enabled = EXCLUDED.enabled,
+    next_run_at = CASE
+        WHEN scheduled_jobs.schedule = EXCLUDED.schedule THEN
+            COALESCE(scheduled_jobs.next_run_at, EXCLUDED.next_run_at)
+        ELSE EXCLUDED.next_run_at
+    END,
+    updated_at = NOW()
+WHERE scheduled_jobs.schedule <> EXCLUDED.schedule
+    OR scheduled_jobs.enabled <> EXCLUDED.enabled
+    OR scheduled_jobs.next_run_at IS NULL";
+
+/// Runs [`JOBS`] against the application state. Cheap to construct; the admin handlers
+/// build one per request.
+#[derive(Clone)]
+pub struct Scheduler {
+    state: AppState,
+    jobs: Arc<Vec<JobSpec>>,
+    lease: Duration,
+    instance: Arc<str>,
+}
+
+impl Scheduler {
+    pub fn new(state: AppState) -> Result<Self, SchedulerError> {
+        let jobs = configured_jobs(&state.config.scheduler)?;
+        let lease = Duration::from_secs(state.config.scheduler.lease_secs.max(3));
+        let instance = format!("{}/{}", crate::snowflake::hostname(), std::process::id());
+        Ok(Self {
+            state,
+            jobs: Arc::new(jobs),
+            lease,
+            instance: instance.into(),
+        })
+    }
+
+    fn job(&self, name: &str) -> Result<&JobSpec, SchedulerError> {
+        self.jobs
+            .iter()
+            .find(|spec| spec.def.name == name)
+            .ok_or_else(|| SchedulerError::UnknownJob(name.to_string()))
+    }
+
+    /// Write the configured schedules to `scheduled_jobs`. A new or rescheduled job gets a
+    /// fresh `next_run_at`: interval jobs are due immediately, cron jobs at their next
+    /// occurrence.
+    pub async fn sync(&self) -> Result<(), SchedulerError> {
+        for spec in self.jobs.iter() {
+            self.register(spec).await?;
+        }
+        Ok(())
+    }
+
+    async fn register(&self, spec: &JobSpec) -> Result<(), SchedulerError> {
+        let now = Utc::now();
+        let first_run = match spec.schedule {
+            Schedule::Every(_) => Some(now),
+            Schedule::Cron(_) => spec.schedule.next_after(now),
+        };
+        self.state
+            .db
+            .write_conn()
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                UPSERT_JOB,
+                [
+                    spec.def.name.into(),
+                    spec.expr.clone().into(),
+                    spec.enabled.into(),
+                    first_run.into(),
+                ],
+            ))
+            .await
+            .with_context(|| format!("Failed to register job {}", spec.def.name))?;
+        Ok(())
+    }
+
+    /// All jobs with their recorded state.
+    pub async fn list(&self) -> Result<Vec<JobStatus>, SchedulerError> {
+        let mut rows: HashMap<String, Model> = ScheduledJobEntity::find()
+            .all(self.state.db.write_conn())
+            .await?
+            .into_iter()
+            .map(|row| (row.name.clone(), row))
+            .collect();
+        Ok(self
+            .jobs
+            .iter()
+            .map(|spec| JobStatus::new(spec, rows.remove(spec.def.name)))
+            .collect())
+    }
+
+    /// Start a manual run of `name` in the background, regardless of its schedule and
+    /// `enabled` flag. Fails with [`SchedulerError::AlreadyRunning`] while any replica runs it.
+    pub async fn trigger(&self, name: &str) -> Result<JobStatus, SchedulerError> {
+        let spec = self.job(name)?.clone();
+        // 行可能尚不存在（本实例未运行调度器且从未同步过）
+        self.register(&spec).await?;
+        let run = self
+            .claim(&spec, false)
+            .await?
+            .ok_or_else(|| SchedulerError::AlreadyRunning(name.to_string()))?;
+        tracing::info!(job = name, "Manual job run started");
+        tokio::spawn(run.execute());
+        let row = ScheduledJobEntity::find_by_id(name)
+            .one(self.state.db.write_conn())
+            .await?;
+        Ok(JobStatus::new(&spec, row))
+    }
+
+    /// Claim and spawn every due job; claims lost to another replica are skipped.
+    async fn spawn_due(&self, runs: &mut tokio::task::JoinSet<()>) -> Result<(), SchedulerError> {
+        let due = ScheduledJobEntity::find()
+            .filter(Column::Enabled.eq(true))
+            .filter(Column::NextRunAt.lte(Utc::now()))
+            .all(self.state.db.write_conn())
+            .await?;
+        for row in due {
+            let Some(spec) = self.jobs.iter().find(|spec| spec.def.name == row.name) else {
+                continue;
+            };
+            if let Some(run) = self.claim(spec, true).await? {
+                runs.spawn(run.execute());
+            }
+        }
+        Ok(())
+    }
+
+    /// Take the job lock and the job row; `None` when another run holds either.
+    async fn claim(
+        &self,
+        spec: &JobSpec,
+        scheduled: bool,
+    ) -> Result<Option<JobRun>, SchedulerError> {
+        let name = spec.def.name;
+        // 未配置 Redis 时只依赖数据库认领
+        let guard = match self.state.cache.redis_client() {
+            Some(client) => match LockGuard::acquire_with_client(
+                Some(client),
+                &format!("scheduler:job:{name}"),
+                self.lease.as_secs(),
+                1,
+                Duration::ZERO,
+            )
+            .await?
+            {
+                Some(AcquireResult::Acquired(guard)) => Some(guard),
+                Some(AcquireResult::Contended) | None => return Ok(None),
+            },
+            None => None,
+        };
+
+        // 数据库存储微秒精度，截断后才能用 last_started_at 识别本次运行
+        let now = Utc::now().trunc_subsecs(6);
+        let mut claim = ScheduledJobEntity::update_many()
+            .col_expr(Column::Status, Expr::value("running"))
+            .col_expr(Column::LockedUntil, Expr::value(now + self.lease_chrono()))
+            .col_expr(Column::LastStartedAt, Expr::value(now))
+            .col_expr(Column::LastRunBy, Expr::value(self.instance.to_string()))
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(Column::Name.eq(name))
+            .filter(
+                Condition::any()
+                    .add(Column::Status.ne("running"))
+                    .add(Column::LockedUntil.lt(now)),
+            );
+        if scheduled {
+            claim = claim
+                .filter(Column::Enabled.eq(true))
+                .filter(Column::NextRunAt.lte(now));
+        }
+        let claimed = claim.exec(self.state.db.write_conn()).await?.rows_affected == 1;
+        if !claimed {
+            if let Some(guard) = guard
+                && let Err(e) = guard.release().await
+            {
+                tracing::warn!(job = name, "Failed to release job lock: {e:#}");
+            }
+            return Ok(None);
+        }
+
+        Ok(Some(JobRun {
+            scheduler: self.clone(),
+            spec: spec.clone(),
+            guard,
+            started_at: now,
+        }))
+    }
+
+    fn lease_chrono(&self) -> chrono::Duration {
+        chrono::Duration::from_std(self.lease).unwrap_or(chrono::Duration::seconds(60))
+    }
+
+    /// Rows of the current run: `(last_run_by, last_started_at)` identifies it, so a run
+    /// whose lease expired cannot overwrite the state of the run that took over.
+    fn own_run(&self, name: &str, started_at: DateTime<Utc>) -> Condition {
+        Condition::all()
+            .add(Column::Name.eq(name))
+            .add(Column::Status.eq("running"))
+            .add(Column::LastRunBy.eq(self.instance.to_string()))
+            .add(Column::LastStartedAt.eq(started_at))
+    }
+
+    /// Sync the jobs table and start polling for due jobs (unless `[scheduler].enabled` is
+    /// false). Keep the handle until shutdown; [`SchedulerHandle::shutdown`] waits for
+    /// running jobs.
+    pub async fn start(state: AppState) -> Result<SchedulerHandle, SchedulerError> {
+        let scheduler = Scheduler::new(state)?;
+        scheduler.sync().await?;
+        let config = &scheduler.state.config.scheduler;
+        if !config.enabled {
+            tracing::info!("Scheduler disabled on this instance ([scheduler].enabled = false)");
+            return Ok(SchedulerHandle {
+                stop_tx: None,
+                task: None,
+            });
+        }
+        tracing::info!(
+            jobs = scheduler.jobs.iter().filter(|spec| spec.enabled).count(),
+            "Scheduler started"
+        );
+
+        let poll = Duration::from_secs(config.poll_interval_secs.max(1));
+        let (stop_tx, mut stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(poll);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut runs = tokio::task::JoinSet::new();
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        while runs.try_join_next().is_some() {}
+                        if let Err(e) = scheduler.spawn_due(&mut runs).await {
+                            tracing::warn!("Scheduler poll failed: {e:#}");
+                        }
+                    }
+                    _ = &mut stop_rx => break,
+                }
+            }
+            // 停止轮询后等待进行中的任务结束（受 shutdown.task_timeout_secs 限制）
+            while runs.join_next().await.is_some() {}
+        });
+
+        Ok(SchedulerHandle {
+            stop_tx: Some(stop_tx),
+            task: Some(task),
+        })
+    }
+}
+
+/// Polling task handle returned by [`Scheduler::start`].
+#[derive(Debug)]
+pub struct SchedulerHandle {
+    stop_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl Drop for SchedulerHandle {
+    fn drop(&mut self) {
+        if let Some(tx) = self.stop_tx.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+impl SchedulerHandle {
+    /// Stop polling and wait for the jobs started by this instance's poller.
+    pub async fn shutdown(mut self) {
+        if let Some(tx) = self.stop_tx.take() {
+            let _ = tx.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+/// A claimed run of one job.
+struct JobRun {
+    scheduler: Scheduler,
+    spec: JobSpec,
+    guard: Option<Box<LockGuard>>,
+    started_at: DateTime<Utc>,
+}
+
+impl JobRun {
+    async fn execute(self) {
+        let name = self.spec.def.name;
+        let span = tracing::info_span!("scheduled_job", job = name);
+        async move {
+            let started = Instant::now();
+            let job = (self.spec.def.run)(self.scheduler.state.clone());
+            let result = tokio::select! {
+                result = job => result,
+                lost = self.keep_lease() => Err(lost),
+            };
+            let duration = started.elapsed();
+            metrics::scheduled_job_run(name, result.is_ok());
+            match &result {
+                Ok(output) => tracing::info!(
+                    duration_ms = duration.as_millis() as u64,
+                    "Job succeeded: {output}"
+                ),
+                Err(e) => tracing::error!(
+                    duration_ms = duration.as_millis() as u64,
+                    "Job failed: {e:#}"
+                ),
+            }
+            if let Err(e) = self.finish(result, duration).await {
+                tracing::error!("Failed to record job result: {e:#}");
+            }
+            if let Some(guard) = self.guard
+                && let Err(e) = guard.release().await
+            {
+                tracing::warn!("Failed to release job lock: {e:#}");
+            }
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Renew the lock and the row lease until one of them is lost; only returns then.
+    async fn keep_lease(&self) -> anyhow::Error {
+        let scheduler = &self.scheduler;
+        let mut interval = tokio::time::interval(scheduler.lease / 3);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Some(guard) = &self.guard {
+                match guard.renew(scheduler.lease.as_secs()).await {
+                    Ok(true) => {}
+                    Ok(false) => return anyhow::anyhow!("job lock lost, run cancelled"),
+                    // Redis 瞬时故障：继续运行，由数据库租约兜底
+                    Err(e) => tracing::warn!("Failed to renew job lock: {e:#}"),
+                }
+            }
+            let renewed = ScheduledJobEntity::update_many()
+                .col_expr(
+                    Column::LockedUntil,
+                    Expr::value(Utc::now() + scheduler.lease_chrono()),
+                )
+                .filter(scheduler.own_run(self.spec.def.name, self.started_at))
+                .exec(scheduler.state.db.write_conn())
+                .await;
+            match renewed {
+                Ok(result) if result.rows_affected == 0 => {
+                    return anyhow::anyhow!("job lease lost, run cancelled");
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to renew job lease: {e}"),
+            }
+        }
+    }
+
+    async fn finish(
+        &self,
+        result: anyhow::Result<String>,
+        duration: Duration,
+    ) -> Result<(), SchedulerError> {
+        let now = Utc::now();
+        let failed = result.is_err();
+        let (output, error) = match result {
+            Ok(output) => (Some(output), None),
+            Err(e) => (None, Some(format!("{e:#}"))),
+        };
+        ScheduledJobEntity::update_many()
+            .col_expr(
+                Column::Status,
+                Expr::value(if failed { "failed" } else { "succeeded" }),
+            )
+            .col_expr(Column::LockedUntil, Expr::value(None::<DateTime<Utc>>))
+            .col_expr(Column::LastFinishedAt, Expr::value(now))
+            .col_expr(
+                Column::LastDurationMs,
+                Expr::value(duration.as_millis() as i64),
+            )
+            .col_expr(Column::LastOutput, Expr::value(output))
+            .col_expr(Column::LastError, Expr::value(error))
+            .col_expr(Column::RunCount, Expr::col(Column::RunCount).add(1))
+            .col_expr(
+                Column::FailureCount,
+                Expr::col(Column::FailureCount).add(i64::from(failed)),
+            )
+            .col_expr(
+                Column::NextRunAt,
+                Expr::value(self.spec.schedule.next_after(now)),
+            )
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(self.scheduler.own_run(self.spec.def.name, self.started_at))
+            .exec(self.scheduler.state.db.write_conn())
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::config::ScheduledJobConfig;
+    use chrono::TimeZone;
+
+    #[test]
+    fn parses_intervals() {
+        let every = |expr: &str| match expr.parse::<Schedule>().unwrap() {
+            Schedule::Every(interval) => interval.as_secs(),
+            Schedule::Cron(_) => panic!("{expr} parsed as cron"),
+        };
+        assert_eq!(every("every 90s"), 90);
+        assert_eq!(every("every 15m"), 900);
+        assert_eq!(every(" every 2 h "), 7200);
+        assert_eq!(every("every 1d"), 86_400);
+        for bad in ["every 0m", "every 5", "every 5w", "every m"] {
+            assert!(bad.parse::<Schedule>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn cron_next_run_is_in_utc() {
+        let at = Utc.with_ymd_and_hms(2026, 3, 1, 3, 30, 0).unwrap();
+        // 5 段表达式：每天 04:00
+        let daily: Schedule = "0 4 * * *".parse().unwrap();
+        assert_eq!(
+            daily.next_after(at),
+            Some(Utc.with_ymd_and_hms(2026, 3, 1, 4, 0, 0).unwrap())
+        );
+        // 6 段表达式：每 30 秒
+        let seconds: Schedule = "*/30 * * * * *".parse().unwrap();
+        assert_eq!(
+            seconds.next_after(at),
+            Some(Utc.with_ymd_and_hms(2026, 3, 1, 3, 30, 30).unwrap())
+        );
+        let every: Schedule = "every 1h".parse().unwrap();
+        assert_eq!(
+            every.next_after(at),
+            Some(Utc.with_ymd_and_hms(2026, 3, 1, 4, 30, 0).unwrap())
+        );
+        assert!("61 * * * *".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn config_overrides_are_validated() {
+        let mut config = SchedulerConfig::default();
+        let jobs = configured_jobs(&config).unwrap();
+        assert_eq!(jobs.len(), JOBS.len());
+        assert!(jobs.iter().all(|spec| spec.enabled));
+
+        config.jobs.insert(
+            "cleanup_refresh_tokens".to_string(),
+            ScheduledJobConfig {
+                schedule: Some("0 3 * * *".to_string()),
+                enabled: Some(false),
+            },
+        );
+        let spec = configured_jobs(&config)
+            .unwrap()
+            .into_iter()
+            .find(|spec| spec.def.name == "cleanup_refresh_tokens")
+            .unwrap();
+        assert_eq!(spec.expr, "0 3 * * *");
+        assert!(!spec.enabled);
+
+        config.jobs.insert(
+            "cleanup_expired_codes".to_string(),
+            ScheduledJobConfig {
+                schedule: Some("sometimes".to_string()),
+                enabled: None,
+            },
+        );
+        assert!(matches!(
+            configured_jobs(&config),
+            Err(SchedulerError::InvalidSchedule { .. })
+        ));
+
+        let mut config = SchedulerConfig::default();
+        config
+            .jobs
+            .insert("typo_job".to_string(), ScheduledJobConfig::default());
+        assert!(matches!(
+            configured_jobs(&config),
+            Err(SchedulerError::UnknownJob(name)) if name == "typo_job"
+        ));
+    }
+}
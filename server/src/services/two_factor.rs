@@ -0,0 +1,681 @@
+//! TOTP (RFC 6238) two-factor authentication.
+//!
+//! - Enrollment takes two steps: [`TwoFactorService::begin_enrollment`] stores a fresh,
+//!   still pending secret and returns the `otpauth://` URI, and
+//!   [`TwoFactorService::confirm_enrollment`] checks a first code, enables 2FA and returns
+//!   one-time recovery codes.
+//! - Login for an enrolled account is split in two: the password step creates a challenge
+//!   ([`TwoFactorService::create_challenge`]) and the code step consumes it
+//!   ([`TwoFactorService::complete_challenge`]) before the session is issued.
+//! - Every accepted TOTP code moves `user_totp.last_used_step` forward, so a code cannot be
+//!   used twice; recovery codes are marked used.
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use rand::{Rng, RngCore};
+use schemars::JsonSchema;
+use sea_orm::{
+    ActiveValue::NotSet, ColumnTrait, ConnectionTrait, DatabaseBackend, EntityTrait,
+    PaginatorTrait, QueryFilter, Set, Statement, TransactionTrait, sea_query::Expr,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::repositories::recovery_code::{
+    ActiveModel as RecoveryCodeActiveModel, Column as RecoveryCodeColumn,
+    Entity as RecoveryCodeEntity,
+};
+use crate::repositories::two_factor_challenge::{
+    ActiveModel as ChallengeActiveModel, Column as ChallengeColumn, Entity as ChallengeEntity,
+};
+use crate::repositories::user::{Entity as UserEntity, Model as UserModel};
+use crate::repositories::user_totp::{
+    Column as UserTotpColumn, Entity as UserTotpEntity, Model as UserTotpModel,
+};
+use crate::utils::config::TwoFactorConfig;
+use crate::utils::db_router::AutoRouter;
+use crate::utils::password::verify_password;
+
+/// Recovery codes issued per enrollment / regeneration.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// 160-bit secrets, as recommended by RFC 4226.
+const SECRET_BYTES: usize = 20;
+const CODE_DIGITS: usize = 6;
+const STEP_SECS: u64 = 30;
+/// Accepted clock drift, in time steps either way.
+const SKEW_STEPS: u64 = 1;
+
+/// Recovery code characters: lowercase letters and digits without look-alikes (0/o, 1/l/i).
+const RECOVERY_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+/// Characters per recovery code, shown as two groups of five.
+const RECOVERY_CODE_LEN: usize = 10;
+
+/// Typed errors for two-factor operations
+#[derive(Debug, thiserror::Error)]
+pub enum TwoFactorError {
+    #[error("Invalid two-factor code")]
+    InvalidCode,
+    #[error("Invalid or expired two-factor challenge")]
+    InvalidChallenge,
+    #[error("Incorrect password")]
+    InvalidPassword,
+    #[error("Two-factor authentication is already enabled")]
+    AlreadyEnabled,
+    #[error("Two-factor authentication is not enabled")]
+    NotEnabled,
+    #[error("No two-factor enrollment in progress")]
+    NoPendingEnrollment,
+    #[error("User not found")]
+    UserNotFound,
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+/// Two-factor state of the current user.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    /// An enrollment was started but not confirmed with a code yet
+    pub pending_enrollment: bool,
+    pub recovery_codes_remaining: u64,
+}
+
+/// Secret of a started enrollment, to be added to an authenticator app.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Enrollment {
+    /// Base32-encoded secret for manual entry
+    pub secret: String,
+    /// `otpauth://totp/...` URI, usually rendered as a QR code
+    pub otpauth_uri: String,
+}
+
+/// Challenge handed to the client after the password step.
+pub struct Challenge {
+    pub token: String,
+    pub expires_in: u64,
+}
+
+/// A challenge completed with a valid code.
+#[derive(Debug)]
+pub struct ChallengeOutcome {
+    pub user_id: i64,
+    pub remember: bool,
+    /// The code was a recovery code rather than a TOTP code
+    pub used_recovery_code: bool,
+}
+
+/// Two-factor enrollment, verification and login challenges.
+pub struct TwoFactorService {
+    db: Arc<AutoRouter>,
+    config: TwoFactorConfig,
+}
+
+impl TwoFactorService {
+    pub fn new(db: Arc<AutoRouter>, config: TwoFactorConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// Whether `user_id` has confirmed two-factor authentication.
+    pub async fn is_enabled(&self, user_id: i64) -> Result<bool, TwoFactorError> {
+        Ok(has_two_factor(self.db.write_conn(), user_id).await?)
+    }
+
+    pub async fn status(&self, user_id: i64) -> Result<TwoFactorStatus, TwoFactorError> {
+        let row = UserTotpEntity::find_by_id(user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query TOTP state")?;
+        let enabled_at = row.as_ref().and_then(|row| row.enabled_at);
+        let recovery_codes_remaining = if enabled_at.is_some() {
+            RecoveryCodeEntity::find()
+                .filter(RecoveryCodeColumn::UserId.eq(user_id))
+                .filter(RecoveryCodeColumn::UsedAt.is_null())
+                .count(self.db.write_conn())
+                .await
+                .context("Failed to count recovery codes")?
+        } else {
+            0
+        };
+        Ok(TwoFactorStatus {
+            enabled: enabled_at.is_some(),
+            enabled_at,
+            pending_enrollment: row.is_some() && enabled_at.is_none(),
+            recovery_codes_remaining,
+        })
+    }
+
+    /// Start an enrollment: store a new pending secret (replacing an unconfirmed one).
+    pub async fn begin_enrollment(
+        &self,
+        user_id: i64,
+        password: &str,
+    ) -> Result<Enrollment, TwoFactorError> {
+        let user = self.check_password(user_id, password).await?;
+
+        let mut bytes = [0u8; SECRET_BYTES];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let secret = match Secret::Raw(bytes.to_vec()).to_encoded() {
+            Secret::Encoded(secret) => secret,
+            Secret::Raw(_) => unreachable!("to_encoded always returns Secret::Encoded"),
+        };
+        let otpauth_uri = self.totp(&secret, &user.email)?.get_url();
+
+        // 已确认的记录不会被覆盖：WHERE 条件让冲突更新在已启用时影响 0 行
+        let result = self
+            .db
+            .write_conn()
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "INSERT INTO user_totp (user_id, secret) VALUES ($1, $2) \
+                 ON CONFLICT (user_id) DO UPDATE \
+                 SET secret = EXCLUDED.secret, last_used_step = NULL, \
+                     created_at = NOW(), updated_at = NOW() \
+                 WHERE user_totp.enabled_at IS NULL",
+                [user_id.into(), secret.clone().into()],
+            ))
+            .await
+            .context("Failed to store TOTP secret")?;
+        if result.rows_affected() == 0 {
+            return Err(TwoFactorError::AlreadyEnabled);
+        }
+
+        tracing::info!(user_id, "Two-factor enrollment started");
+        Ok(Enrollment {
+            secret,
+            otpauth_uri,
+        })
+    }
+
+    /// Confirm the pending enrollment with a first code; returns the recovery codes.
+    pub async fn confirm_enrollment(
+        &self,
+        user_id: i64,
+        code: &str,
+    ) -> Result<Vec<String>, TwoFactorError> {
+        let row = UserTotpEntity::find_by_id(user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query TOTP state")?
+            .ok_or(TwoFactorError::NoPendingEnrollment)?;
+        if row.enabled_at.is_some() {
+            return Err(TwoFactorError::AlreadyEnabled);
+        }
+        let step = self.accepted_step(&row, code)?;
+
+        let codes = generate_recovery_codes();
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction for two-factor enrollment")?;
+        // secret 条件：确认期间若被新的 enroll 覆盖，则该码对应的是旧密钥
+        let result = txn
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "UPDATE user_totp SET enabled_at = NOW(), last_used_step = $2, updated_at = NOW() \
+                 WHERE user_id = $1 AND secret = $3 AND enabled_at IS NULL",
+                [user_id.into(), step.into(), row.secret.into()],
+            ))
+            .await
+            .context("Failed to enable two-factor authentication")?;
+        if result.rows_affected() == 0 {
+            return Err(TwoFactorError::NoPendingEnrollment);
+        }
+        replace_recovery_codes(&txn, user_id, &codes).await?;
+        txn.commit()
+            .await
+            .context("Failed to commit two-factor enrollment")?;
+
+        tracing::info!(user_id, "Two-factor authentication enabled");
+        Ok(codes)
+    }
+
+    /// Turn two-factor authentication off; needs the password and a TOTP or recovery code.
+    pub async fn disable(
+        &self,
+        user_id: i64,
+        password: &str,
+        code: &str,
+    ) -> Result<(), TwoFactorError> {
+        self.check_password(user_id, password).await?;
+        let row = self
+            .enabled_totp(user_id)
+            .await?
+            .ok_or(TwoFactorError::NotEnabled)?;
+        self.consume_code(&row, code).await?;
+        self.remove(user_id).await?;
+        tracing::info!(user_id, "Two-factor authentication disabled");
+        Ok(())
+    }
+
+    /// Replace all recovery codes; needs a current TOTP code.
+    pub async fn regenerate_recovery_codes(
+        &self,
+        user_id: i64,
+        code: &str,
+    ) -> Result<Vec<String>, TwoFactorError> {
+        let row = self
+            .enabled_totp(user_id)
+            .await?
+            .ok_or(TwoFactorError::NotEnabled)?;
+        self.consume_totp(&row, code).await?;
+
+        let codes = generate_recovery_codes();
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction for recovery codes")?;
+        replace_recovery_codes(&txn, user_id, &codes).await?;
+        txn.commit()
+            .await
+            .context("Failed to commit recovery codes")?;
+
+        tracing::info!(user_id, "Recovery codes regenerated");
+        Ok(codes)
+    }
+
+    /// Admin reset of a user's two-factor authentication (lost device).
+    ///
+    /// Scope follows user deletion: admins may only reset `user` accounts, the system
+    /// account may reset anyone. Out-of-scope targets are reported as not found.
+    /// Returns whether anything was removed.
+    pub async fn reset(&self, user_id: i64, actor_role: &str) -> Result<bool, TwoFactorError> {
+        let target = UserEntity::find_by_id(user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user")?
+            .ok_or(TwoFactorError::UserNotFound)?;
+        if actor_role != "system" && target.role != "user" {
+            tracing::warn!(
+                target_user_id = %user_id,
+                actor_role = %actor_role,
+                target_role = %target.role,
+                "Two-factor reset of a non-user account refused — returning NotFound"
+            );
+            return Err(TwoFactorError::UserNotFound);
+        }
+
+        let removed = self.remove(user_id).await?;
+        if removed {
+            tracing::info!(user_id, actor_role, "Two-factor authentication reset");
+        }
+        Ok(removed)
+    }
+
+    /// Create the challenge the client completes with a code after the password step.
+    pub async fn create_challenge(
+        &self,
+        user_id: i64,
+        remember: bool,
+    ) -> Result<Challenge, TwoFactorError> {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+        let now = Utc::now();
+
+        ChallengeEntity::insert(ChallengeActiveModel {
+            token_hash: Set(sha256_hex(&token)),
+            user_id: Set(user_id),
+            remember: Set(remember),
+            attempts: Set(0),
+            expires_at: Set(now + Duration::seconds(self.config.challenge_ttl_secs as i64)),
+            created_at: Set(now),
+        })
+        .exec(self.db.write_conn())
+        .await
+        .context("Failed to store two-factor challenge")?;
+
+        Ok(Challenge {
+            token,
+            expires_in: self.config.challenge_ttl_secs,
+        })
+    }
+
+    /// Complete a challenge with a TOTP or recovery code; the challenge is consumed.
+    pub async fn complete_challenge(
+        &self,
+        token: &str,
+        code: &str,
+    ) -> Result<ChallengeOutcome, TwoFactorError> {
+        let token_hash = sha256_hex(token);
+
+        // 先计数再校验：并发猜测同样受 max_attempts 约束
+        let challenge = self
+            .db
+            .write_conn()
+            .query_one(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "UPDATE two_factor_challenges SET attempts = attempts + 1 \
+                 WHERE token_hash = $1 AND expires_at > NOW() AND attempts < $2 \
+                 RETURNING user_id, remember",
+                [
+                    token_hash.clone().into(),
+                    (self.config.max_attempts as i32).into(),
+                ],
+            ))
+            .await
+            .context("Failed to load two-factor challenge")?
+            .ok_or(TwoFactorError::InvalidChallenge)?;
+        let user_id: i64 = challenge
+            .try_get("", "user_id")
+            .context("Failed to read challenge user")?;
+        let remember: bool = challenge
+            .try_get("", "remember")
+            .context("Failed to read challenge remember flag")?;
+
+        // 2FA 在 challenge 有效期内被关闭/重置：要求重新登录
+        let row = self
+            .enabled_totp(user_id)
+            .await?
+            .ok_or(TwoFactorError::InvalidChallenge)?;
+        let used_recovery_code = self.consume_code(&row, code).await?;
+
+        // 只有一个请求能删除成功，保证 challenge 只换取一次会话
+        let deleted = ChallengeEntity::delete_many()
+            .filter(ChallengeColumn::TokenHash.eq(&token_hash))
+            .exec(self.db.write_conn())
+            .await
+            .context("Failed to consume two-factor challenge")?;
+        if deleted.rows_affected == 0 {
+            return Err(TwoFactorError::InvalidChallenge);
+        }
+
+        if used_recovery_code {
+            tracing::info!(user_id, "Two-factor login completed with a recovery code");
+        }
+        Ok(ChallengeOutcome {
+            user_id,
+            remember,
+            used_recovery_code,
+        })
+    }
+
+    async fn enabled_totp(&self, user_id: i64) -> Result<Option<UserTotpModel>, TwoFactorError> {
+        Ok(UserTotpEntity::find_by_id(user_id)
+            .filter(UserTotpColumn::EnabledAt.is_not_null())
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query TOTP state")?)
+    }
+
+    async fn check_password(
+        &self,
+        user_id: i64,
+        password: &str,
+    ) -> Result<UserModel, TwoFactorError> {
+        let user = UserEntity::find_by_id(user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user")?
+            .ok_or(TwoFactorError::UserNotFound)?;
+        let is_valid =
+            verify_password(password, &user.password_hash).context("Failed to verify password")?;
+        if !is_valid {
+            return Err(TwoFactorError::InvalidPassword);
+        }
+        Ok(user)
+    }
+
+    /// Accept a TOTP code (6 digits) or, failing the format, a recovery code.
+    /// Returns whether a recovery code was used.
+    async fn consume_code(&self, row: &UserTotpModel, code: &str) -> Result<bool, TwoFactorError> {
+        if is_totp_format(code.trim()) {
+            self.consume_totp(row, code).await?;
+            Ok(false)
+        } else {
+            self.consume_recovery_code(row.user_id, code).await?;
+            Ok(true)
+        }
+    }
+
+    async fn consume_totp(&self, row: &UserTotpModel, code: &str) -> Result<(), TwoFactorError> {
+        let step = self.accepted_step(row, code)?;
+        // 条件更新防重放：同一时间步的码只能成功一次（含并发请求）
+        let result = self
+            .db
+            .write_conn()
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "UPDATE user_totp SET last_used_step = $2, updated_at = NOW() \
+                 WHERE user_id = $1 AND enabled_at IS NOT NULL \
+                 AND (last_used_step IS NULL OR last_used_step < $2)",
+                [row.user_id.into(), step.into()],
+            ))
+            .await
+            .context("Failed to record TOTP use")?;
+        if result.rows_affected() == 0 {
+            return Err(TwoFactorError::InvalidCode);
+        }
+        Ok(())
+    }
+
+    async fn consume_recovery_code(&self, user_id: i64, code: &str) -> Result<(), TwoFactorError> {
+        let normalized = normalize_recovery_code(code);
+        if normalized.len() != RECOVERY_CODE_LEN {
+            return Err(TwoFactorError::InvalidCode);
+        }
+        let result = RecoveryCodeEntity::update_many()
+            .col_expr(RecoveryCodeColumn::UsedAt, Expr::value(Utc::now()))
+            .filter(RecoveryCodeColumn::UserId.eq(user_id))
+            .filter(RecoveryCodeColumn::CodeHash.eq(sha256_hex(&normalized)))
+            .filter(RecoveryCodeColumn::UsedAt.is_null())
+            .exec(self.db.write_conn())
+            .await
+            .context("Failed to consume recovery code")?;
+        if result.rows_affected == 0 {
+            return Err(TwoFactorError::InvalidCode);
+        }
+        Ok(())
+    }
+
+    /// Time step matched by `code`, rejecting steps at or before the last accepted one.
+    fn accepted_step(&self, row: &UserTotpModel, code: &str) -> Result<i64, TwoFactorError> {
+        let code = code.trim();
+        if !is_totp_format(code) {
+            return Err(TwoFactorError::InvalidCode);
+        }
+        let totp = self.totp(&row.secret, "")?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("Failed to get current time")?
+            .as_secs();
+        let step = matching_step(&totp, code, now).ok_or(TwoFactorError::InvalidCode)? as i64;
+        if row.last_used_step.is_some_and(|last| step <= last) {
+            return Err(TwoFactorError::InvalidCode);
+        }
+        Ok(step)
+    }
+
+    fn totp(&self, secret: &str, account: &str) -> Result<TOTP, TwoFactorError> {
+        let bytes = Secret::Encoded(secret.to_string())
+            .to_bytes()
+            .map_err(|e| anyhow::anyhow!("Invalid stored TOTP secret: {e}"))?;
+        let totp = TOTP::new(
+            Algorithm::SHA1,
+            CODE_DIGITS,
+            0,
+            STEP_SECS,
+            bytes,
+            Some(self.config.issuer.clone()),
+            account.to_string(),
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to build TOTP: {e}"))?;
+        Ok(totp)
+    }
+
+    /// Delete the secret, recovery codes and open challenges of `user_id`.
+    async fn remove(&self, user_id: i64) -> Result<bool, TwoFactorError> {
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction for two-factor removal")?;
+        let removed = UserTotpEntity::delete_by_id(user_id)
+            .exec(&txn)
+            .await
+            .context("Failed to delete TOTP secret")?
+            .rows_affected;
+        RecoveryCodeEntity::delete_many()
+            .filter(RecoveryCodeColumn::UserId.eq(user_id))
+            .exec(&txn)
+            .await
+            .context("Failed to delete recovery codes")?;
+        ChallengeEntity::delete_many()
+            .filter(ChallengeColumn::UserId.eq(user_id))
+            .exec(&txn)
+            .await
+            .context("Failed to delete two-factor challenges")?;
+        txn.commit()
+            .await
+            .context("Failed to commit two-factor removal")?;
+        Ok(removed > 0)
+    }
+}
+
+/// Whether `user_id` has confirmed two-factor authentication (used by the password step).
+pub async fn has_two_factor(db: &impl ConnectionTrait, user_id: i64) -> anyhow::Result<bool> {
+    let row = UserTotpEntity::find_by_id(user_id)
+        .filter(UserTotpColumn::EnabledAt.is_not_null())
+        .one(db)
+        .await
+        .context("Failed to query TOTP state")?;
+    Ok(row.is_some())
+}
+
+/// Delete expired login challenges.
+pub async fn cleanup_expired_challenges(db: &impl ConnectionTrait) -> Result<u64, TwoFactorError> {
+    let result = ChallengeEntity::delete_many()
+        .filter(ChallengeColumn::ExpiresAt.lte(Utc::now()))
+        .exec(db)
+        .await
+        .context("Failed to cleanup expired two-factor challenges")?;
+    Ok(result.rows_affected)
+}
+
+async fn replace_recovery_codes(
+    db: &impl ConnectionTrait,
+    user_id: i64,
+    codes: &[String],
+) -> Result<(), TwoFactorError> {
+    RecoveryCodeEntity::delete_many()
+        .filter(RecoveryCodeColumn::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .context("Failed to delete old recovery codes")?;
+    let now = Utc::now();
+    RecoveryCodeEntity::insert_many(codes.iter().map(|code| RecoveryCodeActiveModel {
+        id: NotSet,
+        user_id: Set(user_id),
+        code_hash: Set(sha256_hex(&normalize_recovery_code(code))),
+        used_at: Set(None),
+        created_at: Set(now),
+    }))
+    .exec(db)
+    .await
+    .context("Failed to store recovery codes")?;
+    Ok(())
+}
+
+/// First time step within the allowed skew whose code equals `code`.
+fn matching_step(totp: &TOTP, code: &str, now_secs: u64) -> Option<u64> {
+    let current = now_secs / STEP_SECS;
+    (current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+        .find(|step| totp.check(code, step * STEP_SECS))
+}
+
+fn is_totp_format(code: &str) -> bool {
+    code.len() == CODE_DIGITS && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Recovery codes in `xxxxx-xxxxx` form.
+fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..RECOVERY_CODE_LEN)
+                .map(|_| RECOVERY_ALPHABET[rng.gen_range(0..RECOVERY_ALPHABET.len())] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+/// Case, spaces and dashes do not matter when a recovery code is typed in.
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn sha256_hex(value: &str) -> String {
+    hex::encode(Sha256::digest(value.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_totp() -> TOTP {
+        // RFC 6238 附录 B 的 SHA-1 测试密钥
+        TOTP::new(
+            Algorithm::SHA1,
+            8,
+            0,
+            STEP_SECS,
+            b"12345678901234567890".to_vec(),
+            Some("WebShelf".to_string()),
+            "user@example.com".to_string(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rfc6238_vector() {
+        assert_eq!(test_totp().generate(59), "94287082");
+        assert_eq!(test_totp().generate(1111111109), "07081804");
+    }
+
+    #[test]
+    fn test_matching_step_accepts_one_step_of_skew() {
+        let totp = test_totp();
+        let now = 1_111_111_109;
+        let current = now / STEP_SECS;
+        let previous = totp.generate((current - 1) * STEP_SECS);
+        let next = totp.generate((current + 1) * STEP_SECS);
+        let too_old = totp.generate((current - 2) * STEP_SECS);
+
+        assert_eq!(matching_step(&totp, &previous, now), Some(current - 1));
+        assert_eq!(matching_step(&totp, &next, now), Some(current + 1));
+        assert_eq!(matching_step(&totp, &too_old, now), None);
+    }
+
+    #[test]
+    fn test_recovery_codes_format_and_normalization() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        for code in &codes {
+            assert_eq!(code.len(), RECOVERY_CODE_LEN + 1);
+            assert_eq!(&code[5..6], "-");
+            assert_eq!(normalize_recovery_code(code).len(), RECOVERY_CODE_LEN);
+        }
+        assert_eq!(normalize_recovery_code(" ABCDE-fghjk "), "abcdefghjk");
+        assert!(is_totp_format("012345"));
+        assert!(!is_totp_format("abcde-fghjk"));
+        assert!(!is_totp_format("12345"));
+    }
+
+    #[test]
+    fn test_otpauth_uri_carries_issuer_and_account() {
+        let uri = test_totp().get_url();
+        assert!(uri.starts_with("otpauth://totp/WebShelf:user%40example.com?"));
+        assert!(uri.contains("issuer=WebShelf"));
+    }
+}
@@ -10,6 +10,7 @@ use crate::utils::password::{hash_password, verify_password};
 use crate::utils::validator::require_password;
 use anyhow::Context;
 use chrono::Utc;
+use rand::RngCore;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend, EntityTrait, PaginatorTrait,
     QueryFilter, QueryOrder, QuerySelect, Set, Statement, TransactionTrait,
@@ -150,20 +151,55 @@ impl UserService {
             _ => "user".to_string(),
         };
 
+        self.insert_user(&input.email, password_hash, input.name, role, false)
+            .await
+    }
+
+    /// Create a user who signs up through an external OpenID Connect provider.
+    ///
+    /// The provider vouched for the email, so it starts out verified. The random password is
+    /// never revealed; the user can set one with "forgot password".
+    pub async fn create_external_user(
+        &self,
+        email: &str,
+        name: &str,
+    ) -> Result<UserResponse, UserError> {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let password_hash =
+            hash_password(&hex::encode(secret)).context("Failed to hash password")?;
+        self.insert_user(
+            email,
+            password_hash,
+            name.to_string(),
+            "user".to_string(),
+            true,
+        )
+        .await
+    }
+
+    async fn insert_user(
+        &self,
+        email: &str,
+        password_hash: String,
+        name: String,
+        role: String,
+        email_verified: bool,
+    ) -> Result<UserResponse, UserError> {
         let now = Utc::now();
         let user = ActiveModel {
             id: Set(crate::snowflake::generate_id()),
             // Email is already normalized to lowercase by the handler (the
             // caller's responsibility). The .to_lowercase() here is idempotent
             // and serves as defense-in-depth.
-            email: Set(input.email.to_lowercase()),
+            email: Set(email.to_lowercase()),
             password_hash: Set(password_hash),
-            name: Set(input.name),
+            name: Set(name),
             role: Set(role),
             created_at: Set(now),
             updated_at: Set(now),
             token_version: Set(1),
-            email_verified: Set(false),
+            email_verified: Set(email_verified),
             verification_code_hash: Set(None),
             verification_code_expires_at: Set(None),
             verification_code_sent_at: Set(None),
@@ -596,6 +632,100 @@ impl UserService {
         Ok((UserResponse::from(updated), new_version))
     }
 
+    /// Set a new password without the current one (operator reset via the CLI).
+    ///
+    /// Like [`Self::change_password`], increments `token_version` and revokes all
+    /// refresh tokens in one transaction; pending password-reset tokens are cleared too.
+    /// Personal access tokens are deleted as well; returns how many there were.
+    pub async fn set_password(&self, id: i64, new_password: &str) -> Result<u64, UserError> {
+        require_password(new_password).map_err(UserError::WeakPassword)?;
+        let new_hash = hash_password(new_password).context("Failed to hash password")?;
+
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction for password reset")?;
+        let updated = txn
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "UPDATE users SET password_hash = $2, \
+                     token_version = token_version + 1, \
+                     password_reset_token_hash = NULL, \
+                     password_reset_expires_at = NULL, \
+                     password_reset_sent_at = NULL, \
+                     password_reset_failed_attempts = 0, \
+                     updated_at = NOW() \
+                 WHERE id = $1",
+                [id.into(), new_hash.into()],
+            ))
+            .await
+            .context("Failed to update password")?;
+        if updated.rows_affected() == 0 {
+            return Err(UserError::NotFound);
+        }
+        txn.execute(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            "DELETE FROM refresh_tokens WHERE user_id = $1",
+            [id.into()],
+        ))
+        .await
+        .context("Failed to revoke refresh tokens during password reset")?;
+        let api_tokens = txn
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "DELETE FROM api_tokens WHERE user_id = $1",
+                [id.into()],
+            ))
+            .await
+            .context("Failed to revoke API tokens during password reset")?
+            .rows_affected();
+        txn.commit()
+            .await
+            .context("Failed to commit password-reset transaction")?;
+
+        tracing::info!("Password of user {} was reset", id);
+        self.invalidate_user_caches(id).await;
+        Ok(api_tokens)
+    }
+
+    /// Mark the email address as verified (accounts created by an operator).
+    pub async fn set_email_verified(&self, id: i64) -> Result<(), UserError> {
+        let updated = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "UPDATE users SET email_verified = TRUE, verification_code_hash = NULL, \
+                     verification_code_expires_at = NULL, updated_at = NOW() \
+                 WHERE id = $1",
+                [id.into()],
+            ))
+            .await
+            .context("Failed to mark email as verified")?;
+        if updated.rows_affected() == 0 {
+            return Err(UserError::NotFound);
+        }
+        if let Err(e) = self.cache.invalidate(&Self::user_cache_key(id)).await {
+            tracing::warn!("Failed to invalidate cache for user {}: {:?}", id, e);
+        }
+        Ok(())
+    }
+
+    /// Drop the cached profile and `token_version`, so the auth middleware sees a bump at once.
+    async fn invalidate_user_caches(&self, id: i64) {
+        if let Err(e) = self.cache.invalidate(&Self::user_cache_key(id)).await {
+            tracing::warn!("Failed to invalidate cache for user {}: {:?}", id, e);
+        }
+        let token_cache_key = format!("user:token_version:{}", id);
+        if let Err(e) = self.cache.invalidate(&token_cache_key).await {
+            tracing::warn!(
+                "Failed to invalidate token_version cache for user {}: {:?}",
+                id,
+                e
+            );
+        }
+    }
+
     /// Delete user
     pub async fn delete_user(
         &self,
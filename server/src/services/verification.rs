@@ -1,33 +1,82 @@
+use crate::AppState;
 use crate::repositories::user::{ActiveModel, Column, Entity as UserEntity};
+use crate::services::queue::{self, Job};
 use crate::utils::db_router::AutoRouter;
+use crate::utils::metrics;
 use anyhow::Context;
 use argon2::{Argon2, PasswordHasher, PasswordVerifier, password_hash::SaltString};
 use chrono::{Duration, Utc};
 use emailserver::EmailService;
 use rand::Rng;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, sea_query::Expr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
 use std::sync::{Arc, OnceLock};
-use tokio::sync::Semaphore;
 
 const CODE_EXPIRY_MINUTES: i64 = 10;
 const RESEND_COOLDOWN_SECONDS: i64 = 60;
 const MAX_FAILED_ATTEMPTS: i32 = 5;
-const MAX_CONCURRENT_EMAIL_SENDS: usize = 10;
 
 /// Dummy Argon2 hash used for constant-time comparisons when a user
 /// does not exist.  Performing an Argon2 verification against this
 /// hash costs the same CPU time as verifying a real code, preventing
 /// attackers from enumerating registered emails by measuring response
-/// times on the verify-email and resend-code endpoints.
+/// times on the verify-email endpoint.
 fn dummy_code_hash() -> &'static str {
     static DUMMY_HASH: OnceLock<String> = OnceLock::new();
     DUMMY_HASH.get_or_init(|| VerificationService::hash_code("000000").unwrap())
 }
 
-/// Global shared semaphore to limit concurrent email sends across all requests.
-fn global_email_send_limiter() -> &'static Arc<Semaphore> {
-    static LIMITER: OnceLock<Arc<Semaphore>> = OnceLock::new();
-    LIMITER.get_or_init(|| Arc::new(Semaphore::new(MAX_CONCURRENT_EMAIL_SENDS)))
+/// Queue job: issue a fresh verification code to an unverified user and mail it.
+///
+/// The code is generated when the job runs, so no plaintext code is ever stored in the
+/// queue; every retry issues a new code.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerificationCodeEmail {
+    pub user_id: i64,
+}
+
+#[async_trait::async_trait]
+impl Job for VerificationCodeEmail {
+    const KIND: &'static str = "email.verification_code";
+
+    async fn run(self, state: &AppState) -> anyhow::Result<()> {
+        VerificationService::new(state.db.clone(), state.email.clone())
+            .deliver_code(self.user_id)
+            .await
+    }
+}
+
+/// Queue job: welcome email after a successful verification.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WelcomeEmail {
+    pub user_id: i64,
+}
+
+#[async_trait::async_trait]
+impl Job for WelcomeEmail {
+    const KIND: &'static str = "email.welcome";
+    const MAX_ATTEMPTS: i32 = 3;
+
+    async fn run(self, state: &AppState) -> anyhow::Result<()> {
+        // 用户已被删除时无需发送
+        let Some(user) = UserEntity::find_by_id(self.user_id)
+            .one(state.db.write_conn())
+            .await
+            .context("Failed to query user")?
+        else {
+            return Ok(());
+        };
+        let result = state
+            .email
+            .send_welcome_email(&user.email, Some(&user.name))
+            .await;
+        metrics::email_sent("welcome", &result);
+        result.with_context(|| format!("Failed to send welcome email to {}", user.email))?;
+        tracing::info!("Welcome email sent to {}", user.email);
+        Ok(())
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -47,16 +96,11 @@ pub enum VerificationError {
 pub struct VerificationService {
     db: Arc<AutoRouter>,
     email: EmailService,
-    email_send_limiter: Arc<Semaphore>,
 }
 
 impl VerificationService {
     pub fn new(db: Arc<AutoRouter>, email: EmailService) -> Self {
-        Self {
-            db,
-            email,
-            email_send_limiter: global_email_send_limiter().clone(),
-        }
+        Self { db, email }
     }
 
     fn generate_code() -> String {
@@ -95,80 +139,105 @@ impl VerificationService {
             .map_err(Into::into)
     }
 
-    async fn store_verification_code(
-        &self,
-        user: crate::repositories::user::Model,
-    ) -> Result<(crate::repositories::user::Model, String), VerificationError> {
+    /// Start the resend cooldown and queue a [`VerificationCodeEmail`] in one transaction.
+    ///
+    /// With `cooldown`, nothing is queued (and [`VerificationError::TooSoon`] returned) when
+    /// the previous code was requested less than `RESEND_COOLDOWN_SECONDS` ago.
+    async fn queue_code(&self, user_id: i64, cooldown: bool) -> Result<(), VerificationError> {
+        let now = Utc::now();
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+
+        // ── Atomic cooldown enforcement ──────────────────────────────────
+        // The previous read → check → write pattern had a TOCTOU race:
+        // two concurrent requests could both observe an old sent_at,
+        // both pass the 60 s check, and both issue a new code.
+        //
+        // We now fold the cooldown predicate into a single UPDATE ... WHERE
+        // statement.  SeaORM reports rows_affected = 0 when the WHERE
+        // clause eliminates the row, which means either the row was
+        // deleted or the cooldown predicate did not hold.
+        let mut claim = UserEntity::update_many()
+            .col_expr(Column::VerificationCodeSentAt, Expr::value(now))
+            .filter(Column::Id.eq(user_id));
+        if cooldown {
+            let cooldown_threshold = now - Duration::seconds(RESEND_COOLDOWN_SECONDS);
+            claim = claim.filter(
+                Column::VerificationCodeSentAt
+                    .is_null()
+                    .or(Column::VerificationCodeSentAt.lte(cooldown_threshold)),
+            );
+        }
+        let result = claim
+            .exec(&txn)
+            .await
+            .context("Failed to record verification code request")?;
+        if result.rows_affected == 0 {
+            return Err(VerificationError::TooSoon);
+        }
+
+        queue::enqueue(&txn, &VerificationCodeEmail { user_id })
+            .await
+            .context("Failed to queue verification email")?;
+        txn.commit()
+            .await
+            .context("Failed to commit verification code request")?;
+        Ok(())
+    }
+
+    /// Issue a new code and mail it (job [`VerificationCodeEmail`]). Users that were
+    /// deleted or verified in the meantime are skipped.
+    async fn deliver_code(&self, user_id: i64) -> anyhow::Result<()> {
+        let Some(user) = UserEntity::find_by_id(user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user")?
+        else {
+            return Ok(());
+        };
+        if user.email_verified {
+            return Ok(());
+        }
+
         let code = Self::generate_code();
         let code_hash = Self::hash_code(&code)?;
         let now = Utc::now();
         let expires_at = now + Duration::minutes(CODE_EXPIRY_MINUTES);
 
-        let mut active_model: ActiveModel = user.into();
-        active_model.verification_code_hash = Set(Some(code_hash));
-        active_model.verification_code_expires_at = Set(Some(expires_at));
-        active_model.verification_code_sent_at = Set(Some(now));
         // Reset failed attempts when issuing a new code.
         // The 60-second RESEND_COOLDOWN is the primary rate limiter;
         // resetting here gives legitimate users a fresh set of 5 attempts
         // per new code, while the attacker still gets at most ~5 attempts
         // per minute — negligible for a 6-digit (1M combos) search space.
-        active_model.verification_failed_attempts = Set(0);
-        active_model.updated_at = Set(now);
-        let updated = active_model
-            .update(&*self.db)
+        UserEntity::update_many()
+            .col_expr(Column::VerificationCodeHash, Expr::value(code_hash))
+            .col_expr(Column::VerificationCodeExpiresAt, Expr::value(expires_at))
+            .col_expr(Column::VerificationFailedAttempts, Expr::value(0))
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(Column::Id.eq(user.id))
+            .filter(Column::EmailVerified.eq(false))
+            .exec(&*self.db)
             .await
             .context("Failed to store verification code")?;
 
-        Ok((updated, code))
-    }
+        let result = self
+            .email
+            .send_registration_code_email(&user.email, &code, CODE_EXPIRY_MINUTES)
+            .await;
+        metrics::email_sent("registration_code", &result);
+        result.with_context(|| format!("Failed to send verification email to {}", user.email))?;
 
-    fn send_welcome_email_background(&self, email: &str, name: &str) {
-        let email = email.to_string();
-        let name = name.to_string();
-        let email_service = self.email.clone();
-        let limiter = self.email_send_limiter.clone();
-        tokio::spawn(async move {
-            let _permit = match limiter.acquire().await {
-                Ok(p) => p,
-                Err(e) => {
-                    tracing::error!("Email send semaphore closed: {:?}", e);
-                    return;
-                }
-            };
-            let mut attempts = 0;
-            let max_attempts = 2;
-            while attempts < max_attempts {
-                match email_service.send_welcome_email(&email, Some(&name)).await {
-                    Ok(()) => {
-                        tracing::info!("Welcome email sent to {}", email);
-                        return;
-                    }
-                    Err(e) => {
-                        attempts += 1;
-                        if attempts < max_attempts {
-                            tracing::warn!(
-                                "Failed to send welcome email to {} (attempt {}/{}): {:?}",
-                                email,
-                                attempts,
-                                max_attempts,
-                                e
-                            );
-                            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                        } else {
-                            tracing::warn!(
-                                "Failed to send welcome email to {} after {} attempts: {:?}",
-                                email,
-                                max_attempts,
-                                e
-                            );
-                        }
-                    }
-                }
-            }
-        });
+        tracing::info!("Verification code sent to {}", user.email);
+        Ok(())
     }
 
+    /// Queue the verification email of a newly registered user.
+    ///
+    /// The email is sent by the job queue, so SMTP failures are retried there instead of
+    /// failing the request; an error here means the job could not be queued.
     pub async fn send_verification_email(&self, email: &str) -> Result<(), VerificationError> {
         let email_normalized = email.to_lowercase();
         let user = match self.find_user_by_email(&email_normalized).await? {
@@ -187,30 +256,8 @@ impl VerificationService {
             return Ok(());
         }
 
-        let (_updated, code) = self.store_verification_code(user).await?;
-
-        // Send synchronously so the caller can handle failures.
-        let _permit = self.email_send_limiter.acquire().await.map_err(|e| {
-            VerificationError::Internal(anyhow::anyhow!("Email send semaphore closed: {:?}", e))
-        })?;
-        self.email
-            .send_registration_code_email(&email_normalized, &code, CODE_EXPIRY_MINUTES)
-            .await
-            .inspect_err(|e| {
-                tracing::error!(
-                    "Failed to send verification email to {}: {:?}",
-                    email_normalized,
-                    e
-                );
-            })
-            .map_err(|e| {
-                VerificationError::Internal(anyhow::anyhow!(
-                    "Failed to send verification email: {}",
-                    e
-                ))
-            })?;
-
-        tracing::info!("Verification code sent to {}", email_normalized);
+        self.queue_code(user.id, false).await?;
+        tracing::info!("Verification email queued for {}", email_normalized);
         Ok(())
     }
 
@@ -219,9 +266,8 @@ impl VerificationService {
     /// This is used in two scenarios:
     /// 1. When the email service is not configured (dev/test environments), as a
     ///    graceful degradation path so registration succeeds without SMTP.
-    /// 2. As a fallback when the SMTP service is configured but the send fails
-    ///    (transient network errors), to avoid orphan accounts that can neither
-    ///    log in nor re-register.
+    /// 2. As a fallback when the verification email cannot be queued, to avoid
+    ///    orphan accounts that can neither log in nor re-register.
     pub async fn auto_verify(&self, email: &str) -> Result<(), VerificationError> {
         let email_normalized = email.to_lowercase();
         let user = match self.find_user_by_email(&email_normalized).await? {
@@ -328,8 +374,20 @@ impl VerificationService {
             .await
             .context("Failed to verify email")?;
 
-        if self.email.is_configured().await {
-            self.send_welcome_email_background(&email_normalized, &updated_user.name);
+        // 欢迎邮件尽力而为，入队失败不影响验证结果
+        if self.email.is_configured().await
+            && let Err(e) = queue::enqueue(
+                &*self.db,
+                &WelcomeEmail {
+                    user_id: updated_user.id,
+                },
+            )
+            .await
+        {
+            tracing::warn!(
+                "Failed to queue welcome email for {}: {e:#}",
+                email_normalized
+            );
         }
 
         tracing::info!("Email verified for {}", email_normalized);
@@ -359,6 +417,11 @@ impl VerificationService {
         Ok(())
     }
 
+    /// Queue a new verification code, at most once per `RESEND_COOLDOWN_SECONDS`.
+    ///
+    /// Nothing is hashed or sent in the request — the code is issued by the queued
+    /// [`VerificationCodeEmail`] — so the existent-user path costs two small writes and
+    /// needs no dummy work on the non-existent-user path.
     pub async fn resend_code(&self, email: &str) -> Result<(), VerificationError> {
         let email_normalized = email.to_lowercase();
 
@@ -368,13 +431,7 @@ impl VerificationService {
         // user existence from a 503 response.
         let user = match self.find_user_by_email(&email_normalized).await? {
             Some(u) => u,
-            None => {
-                // Anti-timing-attack: perform a dummy Argon2 hash to
-                // match the timing profile of the existent-user path
-                // (which always calls hash_code).
-                let _ = Self::hash_code("000000");
-                return Ok(());
-            }
+            None => return Ok(()),
         };
 
         // Already-verified users don't need another code.
@@ -392,70 +449,32 @@ impl VerificationService {
             return Err(VerificationError::EmailNotConfigured);
         }
 
-        let now = Utc::now();
-
-        // Generate the code eagerly so that code-creation cost is always
-        // paid before we open any DB transaction or lock.
-        let code = Self::generate_code();
-        let code_hash = Self::hash_code(&code)?;
-        let expires_at = now + Duration::minutes(CODE_EXPIRY_MINUTES);
-
-        // ── Atomic cooldown enforcement ──────────────────────────────────
-        // The previous read → check → write pattern had a TOCTOU race:
-        // two concurrent requests could both observe an old sent_at,
-        // both pass the 60 s check, and both issue a new code.
-        //
-        // We now fold the cooldown predicate into a single UPDATE ... WHERE
-        // statement.  SeaORM reports rows_affected = 0 when the WHERE
-        // clause eliminates the row, which means either the row was
-        // deleted or the cooldown predicate did not hold.
-        let cooldown_threshold = now - Duration::seconds(RESEND_COOLDOWN_SECONDS);
-
-        let result = UserEntity::update_many()
-            .col_expr(Column::VerificationCodeHash, Expr::value(code_hash))
-            .col_expr(Column::VerificationCodeExpiresAt, Expr::value(expires_at))
-            .col_expr(Column::VerificationCodeSentAt, Expr::value(now))
-            .col_expr(Column::VerificationFailedAttempts, Expr::value(0))
-            .filter(Column::Id.eq(user.id))
-            .filter(
-                Column::VerificationCodeSentAt
-                    .is_null()
-                    .or(Column::VerificationCodeSentAt.lte(cooldown_threshold)),
-            )
-            .exec(&*self.db)
-            .await
-            .context("Failed to store verification code")?;
-
-        if result.rows_affected == 0 {
-            return Err(VerificationError::TooSoon);
-        }
-
-        // Send synchronously so the caller can handle failures.
-        let _permit = self.email_send_limiter.acquire().await.map_err(|e| {
-            VerificationError::Internal(anyhow::anyhow!("Email send semaphore closed: {:?}", e))
-        })?;
-        self.email
-            .send_registration_code_email(&email_normalized, &code, CODE_EXPIRY_MINUTES)
-            .await
-            .inspect_err(|e| {
-                tracing::error!(
-                    "Failed to send verification email to {}: {:?}",
-                    email_normalized,
-                    e
-                );
-            })
-            .map_err(|e| {
-                VerificationError::Internal(anyhow::anyhow!(
-                    "Failed to send verification email: {}",
-                    e
-                ))
-            })?;
-
-        tracing::info!("Verification code resent to {}", email_normalized);
+        self.queue_code(user.id, true).await?;
+        tracing::info!("Verification code resend queued for {}", email_normalized);
         Ok(())
     }
 }
 
+/// Clear verification codes whose expiry has passed (scheduled job `cleanup_expired_codes`).
+///
+/// Expired codes are already rejected by [`VerificationService::verify_email`]; this only
+/// drops the stale hashes. `sent_at` is kept so the resend cooldown still applies.
+pub async fn cleanup_expired_verification_codes(
+    db: &impl sea_orm::ConnectionTrait,
+) -> Result<u64, VerificationError> {
+    let result = UserEntity::update_many()
+        .col_expr(Column::VerificationCodeHash, Expr::value(None::<String>))
+        .col_expr(
+            Column::VerificationCodeExpiresAt,
+            Expr::value(None::<chrono::DateTime<Utc>>),
+        )
+        .filter(Column::VerificationCodeExpiresAt.lte(Utc::now()))
+        .exec(db)
+        .await
+        .context("Failed to cleanup expired verification codes")?;
+    Ok(result.rows_affected)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,904 @@
+//! WebAuthn passkeys: registration and passwordless login.
+//!
+//! - Both ceremonies start with options for `navigator.credentials.create()` / `.get()` in
+//!   the WebAuthn Level 3 JSON form (`PublicKeyCredential.parse*OptionsFromJSON`) and end
+//!   with the browser's `credential.toJSON()`.
+//! - Challenges are random, stored hashed in `webauthn_challenges` and deleted when the
+//!   ceremony completes, so each one works once.
+//! - No attestation is requested (`"none"`): the public key is trusted because it is
+//!   registered by the logged-in user. Supported algorithms: ES256, EdDSA (Ed25519), RS256.
+//! - Login uses discoverable credentials: the options name no user, the authenticator
+//!   offers its passkeys for the site and returns the user handle (the big-endian user ID).
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use base64::Engine;
+use base64::alphabet::URL_SAFE;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use ring::signature::{
+    ECDSA_P256_SHA256_ASN1, ED25519, RSA_PKCS1_2048_8192_SHA256, RsaPublicKeyComponents,
+    UnparsedPublicKey,
+};
+use schemars::JsonSchema;
+use sea_orm::{
+    ActiveValue::NotSet, ColumnTrait, ConnectionTrait, DatabaseBackend, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, Statement,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::repositories::passkey_credential::{
+    ActiveModel as PasskeyActiveModel, Column as PasskeyColumn, Entity as PasskeyEntity,
+    Model as PasskeyModel,
+};
+use crate::repositories::user::Entity as UserEntity;
+use crate::repositories::webauthn_challenge::{
+    ActiveModel as ChallengeActiveModel, Column as ChallengeColumn, Entity as ChallengeEntity,
+};
+use crate::utils::config::WebAuthnConfig;
+use crate::utils::db_router::AutoRouter;
+use crate::utils::password::verify_password;
+
+/// Passkeys a single account may register.
+pub const MAX_PASSKEYS_PER_USER: u64 = 20;
+
+const CHALLENGE_BYTES: usize = 32;
+
+/// COSE algorithm identifiers, in order of preference.
+const ALG_ES256: i64 = -7;
+const ALG_EDDSA: i64 = -8;
+const ALG_RS256: i64 = -257;
+
+const PURPOSE_REGISTRATION: &str = "registration";
+const PURPOSE_AUTHENTICATION: &str = "authentication";
+
+/// Authenticator data flags (WebAuthn §6.1).
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+/// base64url as used by the WebAuthn JSON forms; padding is tolerated on input.
+const BASE64URL: GeneralPurpose = GeneralPurpose::new(
+    &URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Typed errors for passkey operations
+#[derive(Debug, thiserror::Error)]
+pub enum WebAuthnError {
+    #[error("Invalid or expired passkey challenge")]
+    InvalidChallenge,
+    #[error("Passkey verification failed: {0}")]
+    Verification(&'static str),
+    #[error("Unsupported passkey algorithm {0}")]
+    UnsupportedAlgorithm(i64),
+    #[error("Unknown passkey")]
+    UnknownCredential,
+    #[error("Incorrect password")]
+    InvalidPassword,
+    #[error("This passkey is already registered")]
+    AlreadyRegistered,
+    #[error("Passkey limit of {MAX_PASSKEYS_PER_USER} reached")]
+    TooManyPasskeys,
+    #[error("Passkey not found")]
+    NotFound,
+    #[error("User not found")]
+    UserNotFound,
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+/// Relying party (this site) as named in the creation options.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+/// Account the new passkey is created for.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyUser {
+    /// base64url user handle (the big-endian user ID)
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CredentialParameters {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// COSE algorithm identifier
+    pub alg: i64,
+}
+
+/// Reference to an existing credential.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CredentialDescriptor {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// base64url credential ID
+    pub id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transports: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelection {
+    pub resident_key: String,
+    pub require_resident_key: bool,
+    pub user_verification: String,
+}
+
+/// `PublicKeyCredentialCreationOptionsJSON` for `navigator.credentials.create()`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyCreationOptions {
+    pub rp: RelyingParty,
+    pub user: PasskeyUser,
+    /// base64url challenge
+    pub challenge: String,
+    pub pub_key_cred_params: Vec<CredentialParameters>,
+    /// Milliseconds
+    pub timeout: u64,
+    /// The user's passkeys, so the same authenticator is not registered twice
+    pub exclude_credentials: Vec<CredentialDescriptor>,
+    pub authenticator_selection: AuthenticatorSelection,
+    pub attestation: String,
+}
+
+/// `PublicKeyCredentialRequestOptionsJSON` for `navigator.credentials.get()`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyRequestOptions {
+    /// base64url challenge
+    pub challenge: String,
+    /// Milliseconds
+    pub timeout: u64,
+    pub rp_id: String,
+    /// Empty: any discoverable passkey of this site
+    pub allow_credentials: Vec<CredentialDescriptor>,
+    pub user_verification: String,
+}
+
+/// `AuthenticatorAttestationResponseJSON`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+    #[serde(default)]
+    pub transports: Vec<String>,
+}
+
+/// `RegistrationResponseJSON` — `credential.toJSON()` after `navigator.credentials.create()`.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RegistrationCredential {
+    /// base64url credential ID
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub response: AttestationResponse,
+}
+
+/// `AuthenticatorAssertionResponseJSON`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    #[serde(default)]
+    pub user_handle: Option<String>,
+}
+
+/// `AuthenticationResponseJSON` — `credential.toJSON()` after `navigator.credentials.get()`.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AuthenticationCredential {
+    /// base64url credential ID
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub response: AssertionResponse,
+}
+
+/// A registered passkey as listed in the settings page.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PasskeyInfo {
+    pub id: i64,
+    pub name: String,
+    pub transports: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<PasskeyModel> for PasskeyInfo {
+    fn from(row: PasskeyModel) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            transports: serde_json::from_value(row.transports).unwrap_or_default(),
+            created_at: row.created_at,
+            last_used_at: row.last_used_at,
+        }
+    }
+}
+
+/// A verified passkey assertion.
+#[derive(Debug)]
+pub struct PasskeyLogin {
+    pub user_id: i64,
+    /// The authenticator verified the user (PIN / biometrics), not just their presence
+    pub user_verified: bool,
+}
+
+/// Passkey registration, login and management.
+pub struct WebAuthnService {
+    db: Arc<AutoRouter>,
+    config: WebAuthnConfig,
+}
+
+impl WebAuthnService {
+    pub fn new(db: Arc<AutoRouter>, config: WebAuthnConfig) -> Self {
+        Self { db, config }
+    }
+
+    pub async fn list(&self, user_id: i64) -> Result<Vec<PasskeyInfo>, WebAuthnError> {
+        let rows = PasskeyEntity::find()
+            .filter(PasskeyColumn::UserId.eq(user_id))
+            .order_by_asc(PasskeyColumn::Id)
+            .all(self.db.write_conn())
+            .await
+            .context("Failed to query passkeys")?;
+        Ok(rows.into_iter().map(PasskeyInfo::from).collect())
+    }
+
+    /// Start registering a passkey for `user_id`; the password is re-checked.
+    pub async fn registration_options(
+        &self,
+        user_id: i64,
+        password: &str,
+    ) -> Result<PasskeyCreationOptions, WebAuthnError> {
+        let user = UserEntity::find_by_id(user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user")?
+            .ok_or(WebAuthnError::UserNotFound)?;
+        let is_valid =
+            verify_password(password, &user.password_hash).context("Failed to verify password")?;
+        if !is_valid {
+            return Err(WebAuthnError::InvalidPassword);
+        }
+        let existing = PasskeyEntity::find()
+            .filter(PasskeyColumn::UserId.eq(user_id))
+            .all(self.db.write_conn())
+            .await
+            .context("Failed to query passkeys")?;
+        let challenge = self
+            .create_challenge(PURPOSE_REGISTRATION, Some(user_id))
+            .await?;
+
+        Ok(PasskeyCreationOptions {
+            rp: RelyingParty {
+                id: self.config.rp_id.clone(),
+                name: self.config.rp_name.clone(),
+            },
+            user: PasskeyUser {
+                id: BASE64URL.encode(user_handle(user_id)),
+                name: user.email,
+                display_name: user.name,
+            },
+            challenge,
+            pub_key_cred_params: [ALG_ES256, ALG_EDDSA, ALG_RS256]
+                .into_iter()
+                .map(|alg| CredentialParameters {
+                    kind: "public-key".to_string(),
+                    alg,
+                })
+                .collect(),
+            timeout: self.config.challenge_ttl_secs * 1000,
+            exclude_credentials: existing.into_iter().map(descriptor).collect(),
+            authenticator_selection: AuthenticatorSelection {
+                // 可发现凭据：登录时无需先输入邮箱
+                resident_key: "required".to_string(),
+                require_resident_key: true,
+                user_verification: "preferred".to_string(),
+            },
+            attestation: "none".to_string(),
+        })
+    }
+
+    /// Verify the browser's registration response and store the passkey.
+    pub async fn finish_registration(
+        &self,
+        user_id: i64,
+        name: &str,
+        credential: &RegistrationCredential,
+    ) -> Result<PasskeyInfo, WebAuthnError> {
+        if credential.kind != "public-key" {
+            return Err(WebAuthnError::Verification("unexpected credential type"));
+        }
+        let client_data = decode(&credential.response.client_data_json)?;
+        let challenge = check_client_data(&self.config, &client_data, "webauthn.create")?;
+        if self
+            .consume_challenge(PURPOSE_REGISTRATION, &challenge)
+            .await?
+            != Some(user_id)
+        {
+            return Err(WebAuthnError::InvalidChallenge);
+        }
+
+        let auth_data =
+            parse_attestation_object(&decode(&credential.response.attestation_object)?)?;
+        let auth_data = parse_authenticator_data(&auth_data)?;
+        self.check_authenticator_data(&auth_data)?;
+        let attested = auth_data
+            .attested
+            .ok_or(WebAuthnError::Verification("no attested credential data"))?;
+        if attested.credential_id != decode(&credential.id)? {
+            return Err(WebAuthnError::Verification("credential ID mismatch"));
+        }
+        let (_, algorithm) = PublicKey::from_cose(&attested.public_key)?;
+
+        let count = PasskeyEntity::find()
+            .filter(PasskeyColumn::UserId.eq(user_id))
+            .count(self.db.write_conn())
+            .await
+            .context("Failed to count passkeys")?;
+        if count >= MAX_PASSKEYS_PER_USER {
+            return Err(WebAuthnError::TooManyPasskeys);
+        }
+        let duplicate = PasskeyEntity::find()
+            .filter(PasskeyColumn::CredentialId.eq(attested.credential_id.clone()))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query passkeys")?;
+        if duplicate.is_some() {
+            return Err(WebAuthnError::AlreadyRegistered);
+        }
+
+        let row = PasskeyEntity::insert(PasskeyActiveModel {
+            id: NotSet,
+            user_id: Set(user_id),
+            credential_id: Set(attested.credential_id),
+            public_key: Set(attested.public_key),
+            algorithm: Set(algorithm as i32),
+            sign_count: Set(auth_data.sign_count.into()),
+            transports: Set(serde_json::json!(credential.response.transports)),
+            name: Set(name.to_string()),
+            last_used_at: Set(None),
+            created_at: Set(Utc::now()),
+        })
+        .exec_with_returning(self.db.write_conn())
+        .await
+        .context("Failed to store passkey")?;
+
+        tracing::info!(
+            user_id,
+            passkey_id = row.id,
+            algorithm,
+            "Passkey registered"
+        );
+        Ok(row.into())
+    }
+
+    /// Start a passkey login.
+    pub async fn authentication_options(&self) -> Result<PasskeyRequestOptions, WebAuthnError> {
+        let challenge = self.create_challenge(PURPOSE_AUTHENTICATION, None).await?;
+        Ok(PasskeyRequestOptions {
+            challenge,
+            timeout: self.config.challenge_ttl_secs * 1000,
+            rp_id: self.config.rp_id.clone(),
+            allow_credentials: Vec::new(),
+            user_verification: "preferred".to_string(),
+        })
+    }
+
+    /// Verify the browser's login response; returns the user it authenticates.
+    pub async fn finish_authentication(
+        &self,
+        credential: &AuthenticationCredential,
+    ) -> Result<PasskeyLogin, WebAuthnError> {
+        if credential.kind != "public-key" {
+            return Err(WebAuthnError::Verification("unexpected credential type"));
+        }
+        let client_data = decode(&credential.response.client_data_json)?;
+        let challenge = check_client_data(&self.config, &client_data, "webauthn.get")?;
+        self.consume_challenge(PURPOSE_AUTHENTICATION, &challenge)
+            .await?;
+
+        let row = PasskeyEntity::find()
+            .filter(PasskeyColumn::CredentialId.eq(decode(&credential.id)?))
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query passkey")?
+            .ok_or(WebAuthnError::UnknownCredential)?;
+        if let Some(handle) = &credential.response.user_handle
+            && !handle.is_empty()
+            && decode(handle)? != user_handle(row.user_id)
+        {
+            return Err(WebAuthnError::UnknownCredential);
+        }
+
+        let raw_auth_data = decode(&credential.response.authenticator_data)?;
+        let auth_data = parse_authenticator_data(&raw_auth_data)?;
+        self.check_authenticator_data(&auth_data)?;
+
+        // 签名覆盖 authenticatorData || SHA-256(clientDataJSON)
+        let mut message = raw_auth_data.clone();
+        message.extend_from_slice(&Sha256::digest(&client_data));
+        let (public_key, _) = PublicKey::from_cose(&row.public_key)?;
+        if !public_key.verify(&message, &decode(&credential.response.signature)?) {
+            return Err(WebAuthnError::Verification("invalid signature"));
+        }
+
+        // 计数器必须递增（始终为 0 的认证器除外）；条件更新同时防止并发重放
+        let sign_count = i64::from(auth_data.sign_count);
+        let result = self
+            .db
+            .write_conn()
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "UPDATE passkey_credentials SET sign_count = $2, last_used_at = NOW() \
+                 WHERE id = $1 AND (sign_count < $2 OR (sign_count = 0 AND $2 = 0))",
+                [row.id.into(), sign_count.into()],
+            ))
+            .await
+            .context("Failed to update passkey counter")?;
+        if result.rows_affected() == 0 {
+            tracing::warn!(
+                user_id = row.user_id,
+                passkey_id = row.id,
+                stored = row.sign_count,
+                received = sign_count,
+                "Passkey signature counter did not increase — possible cloned authenticator"
+            );
+            return Err(WebAuthnError::Verification(
+                "signature counter did not increase",
+            ));
+        }
+
+        let user = UserEntity::find_by_id(row.user_id)
+            .one(self.db.write_conn())
+            .await
+            .context("Failed to query user")?
+            .ok_or(WebAuthnError::UnknownCredential)?;
+        if !user.email_verified {
+            tracing::info!(
+                user_id = user.id,
+                "Passkey login rejected: email not verified"
+            );
+            return Err(WebAuthnError::UnknownCredential);
+        }
+
+        Ok(PasskeyLogin {
+            user_id: user.id,
+            user_verified: auth_data.flags & FLAG_USER_VERIFIED != 0,
+        })
+    }
+
+    /// Delete one of the user's passkeys.
+    pub async fn revoke(&self, user_id: i64, passkey_id: i64) -> Result<(), WebAuthnError> {
+        let result = PasskeyEntity::delete_many()
+            .filter(PasskeyColumn::Id.eq(passkey_id))
+            .filter(PasskeyColumn::UserId.eq(user_id))
+            .exec(self.db.write_conn())
+            .await
+            .context("Failed to delete passkey")?;
+        if result.rows_affected == 0 {
+            return Err(WebAuthnError::NotFound);
+        }
+        tracing::info!(user_id, passkey_id, "Passkey revoked");
+        Ok(())
+    }
+
+    /// Store a new challenge; returns it base64url-encoded.
+    async fn create_challenge(
+        &self,
+        purpose: &str,
+        user_id: Option<i64>,
+    ) -> Result<String, WebAuthnError> {
+        let mut bytes = [0u8; CHALLENGE_BYTES];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let now = Utc::now();
+
+        ChallengeEntity::insert(ChallengeActiveModel {
+            challenge_hash: Set(sha256_hex(&bytes)),
+            purpose: Set(purpose.to_string()),
+            user_id: Set(user_id),
+            expires_at: Set(now + Duration::seconds(self.config.challenge_ttl_secs as i64)),
+            created_at: Set(now),
+        })
+        .exec(self.db.write_conn())
+        .await
+        .context("Failed to store passkey challenge")?;
+
+        Ok(BASE64URL.encode(bytes))
+    }
+
+    /// Delete the challenge echoed in the client data; returns the user it was issued to.
+    async fn consume_challenge(
+        &self,
+        purpose: &str,
+        challenge: &[u8],
+    ) -> Result<Option<i64>, WebAuthnError> {
+        let row = self
+            .db
+            .write_conn()
+            .query_one(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "DELETE FROM webauthn_challenges \
+                 WHERE challenge_hash = $1 AND purpose = $2 AND expires_at > NOW() \
+                 RETURNING user_id",
+                [sha256_hex(challenge).into(), purpose.into()],
+            ))
+            .await
+            .context("Failed to consume passkey challenge")?
+            .ok_or(WebAuthnError::InvalidChallenge)?;
+        Ok(row
+            .try_get("", "user_id")
+            .context("Failed to read challenge user")?)
+    }
+
+    fn check_authenticator_data(&self, data: &AuthenticatorData) -> Result<(), WebAuthnError> {
+        if data.rp_id_hash != Sha256::digest(self.config.rp_id.as_bytes()).as_slice() {
+            return Err(WebAuthnError::Verification("relying party ID mismatch"));
+        }
+        if data.flags & FLAG_USER_PRESENT == 0 {
+            return Err(WebAuthnError::Verification("user not present"));
+        }
+        Ok(())
+    }
+}
+
+/// Delete expired registration / login challenges.
+pub async fn cleanup_expired_challenges(db: &impl ConnectionTrait) -> Result<u64, WebAuthnError> {
+    let result = ChallengeEntity::delete_many()
+        .filter(ChallengeColumn::ExpiresAt.lte(Utc::now()))
+        .exec(db)
+        .await
+        .context("Failed to cleanup expired passkey challenges")?;
+    Ok(result.rows_affected)
+}
+
+/// User handle stored in the passkey: the user ID, big-endian.
+fn user_handle(user_id: i64) -> [u8; 8] {
+    user_id.to_be_bytes()
+}
+
+fn descriptor(row: PasskeyModel) -> CredentialDescriptor {
+    CredentialDescriptor {
+        kind: "public-key".to_string(),
+        id: BASE64URL.encode(&row.credential_id),
+        transports: serde_json::from_value(row.transports).unwrap_or_default(),
+    }
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, WebAuthnError> {
+    BASE64URL
+        .decode(value)
+        .map_err(|_| WebAuthnError::Verification("malformed base64url"))
+}
+
+fn sha256_hex(value: &[u8]) -> String {
+    hex::encode(Sha256::digest(value))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+    origin: String,
+    #[serde(default)]
+    cross_origin: bool,
+}
+
+/// Check the ceremony type and origin of `clientDataJSON`; returns the raw challenge.
+fn check_client_data(
+    config: &WebAuthnConfig,
+    raw: &[u8],
+    expected_type: &str,
+) -> Result<Vec<u8>, WebAuthnError> {
+    let client_data: ClientData = serde_json::from_slice(raw)
+        .map_err(|_| WebAuthnError::Verification("malformed client data"))?;
+    if client_data.kind != expected_type {
+        return Err(WebAuthnError::Verification("unexpected ceremony type"));
+    }
+    if !config.origins.contains(&client_data.origin) {
+        tracing::warn!(origin = %client_data.origin, "Passkey ceremony from unexpected origin");
+        return Err(WebAuthnError::Verification("origin not allowed"));
+    }
+    if client_data.cross_origin {
+        return Err(WebAuthnError::Verification("cross-origin ceremony"));
+    }
+    decode(&client_data.challenge)
+}
+
+/// `authData` of an attestation object. The attestation statement is not verified since
+/// none is requested.
+fn parse_attestation_object(raw: &[u8]) -> Result<Vec<u8>, WebAuthnError> {
+    let value: ciborium::Value = ciborium::de::from_reader(raw)
+        .map_err(|_| WebAuthnError::Verification("malformed attestation object"))?;
+    let map = value
+        .into_map()
+        .map_err(|_| WebAuthnError::Verification("malformed attestation object"))?;
+    map.into_iter()
+        .find(|(key, _)| key.as_text() == Some("authData"))
+        .and_then(|(_, value)| value.into_bytes().ok())
+        .ok_or(WebAuthnError::Verification(
+            "attestation object without authData",
+        ))
+}
+
+struct AttestedCredential {
+    credential_id: Vec<u8>,
+    /// COSE_Key bytes as sent by the authenticator
+    public_key: Vec<u8>,
+}
+
+struct AuthenticatorData {
+    rp_id_hash: Vec<u8>,
+    flags: u8,
+    sign_count: u32,
+    attested: Option<AttestedCredential>,
+}
+
+/// Parse authenticator data (WebAuthn §6.1): rpIdHash, flags, signCount and, when the AT
+/// flag is set, the attested credential data. Extensions after it are ignored.
+fn parse_authenticator_data(data: &[u8]) -> Result<AuthenticatorData, WebAuthnError> {
+    const TRUNCATED: WebAuthnError = WebAuthnError::Verification("truncated authenticator data");
+    if data.len() < 37 {
+        return Err(TRUNCATED);
+    }
+    let flags = data[32];
+    let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+
+    let attested = if flags & FLAG_ATTESTED_CREDENTIAL != 0 {
+        // aaguid(16) || credentialIdLength(2) || credentialId || credentialPublicKey
+        let rest = data.get(37 + 16..).ok_or(TRUNCATED)?;
+        let id_len = rest.get(..2).ok_or(TRUNCATED)?;
+        let id_len = u16::from_be_bytes([id_len[0], id_len[1]]) as usize;
+        let credential_id = rest.get(2..2 + id_len).ok_or(TRUNCATED)?.to_vec();
+        let key_start = &rest[2 + id_len..];
+        // 只读一个 CBOR 项，剩余字节属于扩展
+        let mut reader = key_start;
+        ciborium::de::from_reader::<ciborium::Value, _>(&mut reader)
+            .map_err(|_| WebAuthnError::Verification("malformed credential public key"))?;
+        let key_len = key_start.len() - reader.len();
+        Some(AttestedCredential {
+            credential_id,
+            public_key: key_start[..key_len].to_vec(),
+        })
+    } else {
+        None
+    };
+
+    Ok(AuthenticatorData {
+        rp_id_hash: data[..32].to_vec(),
+        flags,
+        sign_count,
+        attested,
+    })
+}
+
+/// Credential public key in the form `ring` verifies against.
+enum PublicKey {
+    /// Uncompressed SEC1 P-256 point
+    Es256(Vec<u8>),
+    Ed25519(Vec<u8>),
+    Rs256 {
+        n: Vec<u8>,
+        e: Vec<u8>,
+    },
+}
+
+impl PublicKey {
+    /// Decode a COSE_Key (RFC 9053); returns the key and its algorithm.
+    fn from_cose(raw: &[u8]) -> Result<(Self, i64), WebAuthnError> {
+        const MALFORMED: WebAuthnError =
+            WebAuthnError::Verification("malformed credential public key");
+        let value: ciborium::Value = ciborium::de::from_reader(raw).map_err(|_| MALFORMED)?;
+        let map = value.into_map().map_err(|_| MALFORMED)?;
+        let get = |label: i64| {
+            map.iter()
+                .find(|(key, _)| {
+                    key.as_integer()
+                        .is_some_and(|key| i128::from(key) == i128::from(label))
+                })
+                .map(|(_, value)| value)
+        };
+        let int = |label: i64| {
+            get(label)
+                .and_then(ciborium::Value::as_integer)
+                .and_then(|value| i64::try_from(value).ok())
+        };
+        let bytes = |label: i64| {
+            get(label)
+                .and_then(ciborium::Value::as_bytes)
+                .cloned()
+                .ok_or(MALFORMED)
+        };
+
+        let kty = int(1).ok_or(MALFORMED)?;
+        let alg = int(3).ok_or(MALFORMED)?;
+        let key = match (kty, alg) {
+            // EC2, P-256
+            (2, ALG_ES256) if int(-1) == Some(1) => {
+                let (x, y) = (bytes(-2)?, bytes(-3)?);
+                if x.len() != 32 || y.len() != 32 {
+                    return Err(MALFORMED);
+                }
+                let mut point = vec![0x04];
+                point.extend_from_slice(&x);
+                point.extend_from_slice(&y);
+                PublicKey::Es256(point)
+            }
+            // OKP, Ed25519
+            (1, ALG_EDDSA) if int(-1) == Some(6) => {
+                let x = bytes(-2)?;
+                if x.len() != 32 {
+                    return Err(MALFORMED);
+                }
+                PublicKey::Ed25519(x)
+            }
+            // RSA, at least 2048 bits
+            (3, ALG_RS256) => {
+                let (n, e) = (bytes(-1)?, bytes(-2)?);
+                if n.len() < 256 || e.is_empty() {
+                    return Err(MALFORMED);
+                }
+                PublicKey::Rs256 { n, e }
+            }
+            _ => return Err(WebAuthnError::UnsupportedAlgorithm(alg)),
+        };
+        Ok((key, alg))
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            PublicKey::Es256(point) => UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, point)
+                .verify(message, signature)
+                .is_ok(),
+            PublicKey::Ed25519(key) => UnparsedPublicKey::new(&ED25519, key)
+                .verify(message, signature)
+                .is_ok(),
+            PublicKey::Rs256 { n, e } => RsaPublicKeyComponents { n, e }
+                .verify(&RSA_PKCS1_2048_8192_SHA256, message, signature)
+                .is_ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair, KeyPair};
+
+    fn test_config() -> WebAuthnConfig {
+        WebAuthnConfig {
+            rp_id: "example.com".to_string(),
+            origins: vec!["https://app.example.com".to_string()],
+            ..WebAuthnConfig::default()
+        }
+    }
+
+    fn cose_es256(point: &[u8]) -> Vec<u8> {
+        use ciborium::Value;
+        let key = Value::Map(vec![
+            (Value::from(1), Value::from(2)),
+            (Value::from(3), Value::from(ALG_ES256)),
+            (Value::from(-1), Value::from(1)),
+            (Value::from(-2), Value::Bytes(point[1..33].to_vec())),
+            (Value::from(-3), Value::Bytes(point[33..].to_vec())),
+        ]);
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(&key, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_es256_registration_and_assertion_round_trip() {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+        let cose = cose_es256(pair.public_key().as_ref());
+
+        // 注册：authData 带凭据，后随扩展字节
+        let mut auth_data = Sha256::digest(b"example.com").to_vec();
+        auth_data.push(FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL | 0x80);
+        auth_data.extend_from_slice(&7u32.to_be_bytes());
+        auth_data.extend_from_slice(&[0u8; 16]);
+        auth_data.extend_from_slice(&3u16.to_be_bytes());
+        auth_data.extend_from_slice(b"abc");
+        auth_data.extend_from_slice(&cose);
+        auth_data.extend_from_slice(&[0xa0]);
+        let parsed = parse_authenticator_data(&auth_data).unwrap();
+        assert_eq!(parsed.sign_count, 7);
+        let attested = parsed.attested.unwrap();
+        assert_eq!(attested.credential_id, b"abc");
+        assert_eq!(attested.public_key, cose);
+
+        let (key, alg) = PublicKey::from_cose(&attested.public_key).unwrap();
+        assert_eq!(alg, ALG_ES256);
+        let message = b"authenticator data || client data hash";
+        let signature = pair.sign(&rng, message).unwrap();
+        assert!(key.verify(message, signature.as_ref()));
+        assert!(!key.verify(b"tampered", signature.as_ref()));
+    }
+
+    #[test]
+    fn test_truncated_authenticator_data_is_rejected() {
+        assert!(parse_authenticator_data(&[0u8; 36]).is_err());
+        let mut data = vec![0u8; 37];
+        data[32] = FLAG_ATTESTED_CREDENTIAL;
+        data.extend_from_slice(&[0u8; 16]);
+        data.extend_from_slice(&64u16.to_be_bytes());
+        assert!(parse_authenticator_data(&data).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_cose_algorithm() {
+        use ciborium::Value;
+        let key = Value::Map(vec![
+            (Value::from(1), Value::from(2)),
+            (Value::from(3), Value::from(-35)),
+        ]);
+        let mut raw = Vec::new();
+        ciborium::ser::into_writer(&key, &mut raw).unwrap();
+        assert!(matches!(
+            PublicKey::from_cose(&raw),
+            Err(WebAuthnError::UnsupportedAlgorithm(-35))
+        ));
+    }
+
+    #[test]
+    fn test_client_data_checks_type_and_origin() {
+        let config = test_config();
+        let client_data = |kind: &str, origin: &str| {
+            serde_json::to_vec(&serde_json::json!({
+                "type": kind,
+                "challenge": BASE64URL.encode(b"challenge"),
+                "origin": origin,
+                "crossOrigin": false,
+            }))
+            .unwrap()
+        };
+
+        let challenge = check_client_data(
+            &config,
+            &client_data("webauthn.get", "https://app.example.com"),
+            "webauthn.get",
+        )
+        .unwrap();
+        assert_eq!(challenge, b"challenge");
+        assert!(
+            check_client_data(
+                &config,
+                &client_data("webauthn.create", "https://app.example.com"),
+                "webauthn.get",
+            )
+            .is_err()
+        );
+        assert!(
+            check_client_data(
+                &config,
+                &client_data("webauthn.get", "https://evil.example.net"),
+                "webauthn.get",
+            )
+            .is_err()
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use anyhow::{Context, Result};
 use config::{Config, Environment, File};
 use serde::Deserialize;
+use webshelf_runtime::{
+    SecurityHeadersConfig, ShutdownConfig, TlsConfig, TrustedProxies, WebSocketOrigins,
+};
 
 /// Application configuration structure
 #[derive(Debug, Deserialize, Clone)]
@@ -73,6 +76,50 @@ pub struct AppConfig {
     /// WeChat Official Account configuration (optional)
     #[serde(default)]
     pub wechat: WechatAccountConfig,
+
+    /// OpenAPI document / API reference UI
+    #[serde(default)]
+    pub openapi: OpenApiConfig,
+
+    /// Dependency health checks and readiness policy
+    #[serde(default)]
+    pub health: HealthConfig,
+
+    /// Prometheus metrics endpoint
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    /// OpenTelemetry trace export
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+
+    /// Log format, outputs and levels
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    /// Background job scheduler
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+
+    /// Durable job queue workers
+    #[serde(default)]
+    pub queue: QueueConfig,
+
+    /// TOTP two-factor authentication
+    #[serde(default)]
+    pub two_factor: TwoFactorConfig,
+
+    /// WebAuthn passkey login
+    #[serde(default)]
+    pub webauthn: WebAuthnConfig,
+
+    /// Login with external OpenID Connect providers
+    #[serde(default)]
+    pub oidc: OidcConfig,
+
+    /// OAuth 2.0 / OpenID Connect authorization server for third-party apps
+    #[serde(default)]
+    pub oauth: OAuthConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -88,12 +135,36 @@ pub struct ServerConfig {
     /// Allowed CORS origins (empty = allow Any, but logs a warning in production)
     #[serde(default)]
     pub allowed_origins: Vec<String>,
+
+    /// Reverse proxies (CIDRs or addresses) whose `X-Forwarded-For` / `Forwarded` /
+    /// `X-Real-IP` headers are honoured for client IP resolution.
+    /// Empty = headers are ignored and the TCP peer address is used.
+    #[serde(default)]
+    pub trusted_proxies: TrustedProxies,
+
+    /// Cross-origin pages allowed to open WebSocket connections, e.g. `https://app.example.com`.
+    /// Empty = same-origin only; other handshakes are rejected with 403.
+    #[serde(default)]
+    pub websocket_origins: WebSocketOrigins,
+
+    /// Security response headers (HSTS, CSP, X-Frame-Options, …) added by the server itself
+    #[serde(default)]
+    pub security_headers: SecurityHeadersConfig,
+
+    /// Native HTTPS (rustls) with certificate hot-reload; disabled by default
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    /// Graceful shutdown: readiness drain delay and in-flight request deadline
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
 }
 
 /// Database connection pool configuration
 #[derive(Debug, Deserialize, Clone)]
 pub struct DatabaseConfig {
-    /// Maximum number of connections in the pool
+    /// Maximum number of connections in the pool (at least 2: the migration runner holds its
+    /// lock on one connection while it migrates on another)
     #[serde(default = "default_max_connections")]
     pub max_connections: u32,
 
@@ -112,6 +183,11 @@ pub struct DatabaseConfig {
     /// Acquire timeout in seconds
     #[serde(default = "default_acquire_timeout")]
     pub acquire_timeout_secs: u64,
+
+    /// Apply pending migrations on startup. When disabled, startup fails if any migration
+    /// is pending (apply them with `webshelf migrate up`).
+    #[serde(default = "default_auto_migrate")]
+    pub auto_migrate: bool,
 }
 
 fn default_max_connections() -> u32 {
@@ -129,6 +205,9 @@ fn default_idle_timeout() -> u64 {
 fn default_acquire_timeout() -> u64 {
     30
 }
+fn default_auto_migrate() -> bool {
+    true
+}
 
 impl Default for DatabaseConfig {
     fn default() -> Self {
@@ -138,6 +217,7 @@ impl Default for DatabaseConfig {
             connect_timeout_secs: default_connect_timeout(),
             idle_timeout_secs: default_idle_timeout(),
             acquire_timeout_secs: default_acquire_timeout(),
+            auto_migrate: default_auto_migrate(),
         }
     }
 }
@@ -311,10 +391,740 @@ impl Default for ServerConfig {
             host: default_host(),
             port: default_port(),
             allowed_origins: Vec::new(),
+            trusted_proxies: TrustedProxies::default(),
+            websocket_origins: WebSocketOrigins::default(),
+            security_headers: SecurityHeadersConfig::default(),
+            tls: TlsConfig::default(),
+            shutdown: ShutdownConfig::default(),
         }
     }
 }
 
+/// OpenAPI document serving.
+///
+/// Both paths are registered as public (unauthenticated) routes and must not
+/// collide with application routes.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OpenApiConfig {
+    /// Whether the document and UI routes are registered (default: true).
+    #[serde(default = "default_openapi_enabled")]
+    pub enabled: bool,
+
+    /// Path of the OpenAPI 3.1 JSON document.
+    #[serde(default = "default_openapi_path")]
+    pub path: String,
+
+    /// Path of the API reference UI page.
+    #[serde(default = "default_openapi_ui_path")]
+    pub ui_path: String,
+}
+
+impl Default for OpenApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_openapi_enabled(),
+            path: default_openapi_path(),
+            ui_path: default_openapi_ui_path(),
+        }
+    }
+}
+
+fn default_openapi_enabled() -> bool {
+    true
+}
+fn default_openapi_path() -> String {
+    "/api/public/openapi.json".to_string()
+}
+fn default_openapi_ui_path() -> String {
+    "/api/public/docs".to_string()
+}
+
+/// Prometheus metrics endpoint.
+///
+/// Off by default. When enabled, the path is registered on the main listener as a public
+/// (unauthenticated) route; restrict it to the scraper at the network level (ingress /
+/// NetworkPolicy) when the service is exposed.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MetricsConfig {
+    /// Whether the scrape route is registered (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Path of the scrape endpoint (default: `/metrics`).
+    #[serde(default = "default_metrics_path")]
+    pub path: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_metrics_path(),
+        }
+    }
+}
+
+fn default_metrics_path() -> String {
+    "/metrics".to_string()
+}
+
+/// `[logging]` configuration.
+///
+/// `RUST_LOG`, when set, replaces `level` + `levels` (same directive syntax).
+#[derive(Debug, Deserialize, Clone)]
+pub struct LoggingConfig {
+    /// Default level: trace, debug, info, warn, error (default: info).
+    #[serde(default = "default_log_level")]
+    pub level: String,
+
+    /// Per-module `EnvFilter` directives, e.g. `["sqlx::query=warn", "webshelf_server=debug"]`
+    /// (default: `["sqlx::query=warn"]`, which hides the per-query sqlx logs).
+    #[serde(default = "default_log_levels")]
+    pub levels: Vec<String>,
+
+    /// Default format of the outputs (default: text).
+    #[serde(default)]
+    pub format: LogFormat,
+
+    /// Where logs go (default: all events to stdout).
+    #[serde(default = "default_log_outputs")]
+    pub outputs: Vec<LogOutput>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_log_level(),
+            levels: default_log_levels(),
+            format: LogFormat::default(),
+            outputs: default_log_outputs(),
+        }
+    }
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+fn default_log_levels() -> Vec<String> {
+    vec!["sqlx::query=warn".to_string()]
+}
+fn default_log_outputs() -> Vec<LogOutput> {
+    vec![LogOutput::default()]
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line (see `utils::logger` for the field names)
+    Json,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogOutputKind {
+    #[default]
+    Stdout,
+    File,
+}
+
+/// Which events an output receives.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogStream {
+    #[default]
+    All,
+    /// Everything except access logs
+    App,
+    /// Only the per-request access log (`request completed`, target `access`)
+    Access,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    #[default]
+    Daily,
+    Hourly,
+    /// Rotate on size only (`max_size_mb`), or never when that is 0
+    Never,
+}
+
+/// One `[[logging.outputs]]` entry.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LogOutput {
+    #[serde(default)]
+    pub kind: LogOutputKind,
+
+    #[serde(default)]
+    pub stream: LogStream,
+
+    /// Overrides `logging.format` for this output.
+    #[serde(default)]
+    pub format: Option<LogFormat>,
+
+    /// File path (`file` only, default: `logs/webshelf.log`); rotated files get `.1`, `.2`, …
+    #[serde(default = "default_log_path")]
+    pub path: String,
+
+    /// Time-based rotation (`file` only, default: daily).
+    #[serde(default)]
+    pub rotation: LogRotation,
+
+    /// Also rotate once the file exceeds this size; 0 = no size limit (default: 0).
+    #[serde(default)]
+    pub max_size_mb: u64,
+
+    /// Rotated files kept besides the active one (default: 7).
+    #[serde(default = "default_log_max_files")]
+    pub max_files: usize,
+}
+
+impl Default for LogOutput {
+    fn default() -> Self {
+        Self {
+            kind: LogOutputKind::default(),
+            stream: LogStream::default(),
+            format: None,
+            path: default_log_path(),
+            rotation: LogRotation::default(),
+            max_size_mb: 0,
+            max_files: default_log_max_files(),
+        }
+    }
+}
+
+fn default_log_path() -> String {
+    "logs/webshelf.log".to_string()
+}
+fn default_log_max_files() -> usize {
+    7
+}
+
+/// OpenTelemetry trace export over OTLP/HTTP (protobuf).
+///
+/// When enabled, request, database, cache and WeChat API spans are exported to `endpoint`
+/// and W3C `traceparent` headers are continued on incoming and sent on outgoing requests.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TelemetryConfig {
+    /// Whether spans are exported (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// OTLP/HTTP traces endpoint (default: `http://localhost:4318/v1/traces`).
+    #[serde(default = "default_telemetry_endpoint")]
+    pub endpoint: String,
+
+    /// `service.name` resource attribute (default: `webshelf-server`).
+    #[serde(default = "default_telemetry_service_name")]
+    pub service_name: String,
+
+    /// Sampler, named as in `OTEL_TRACES_SAMPLER`: `always_on`, `always_off`, `traceidratio`,
+    /// `parentbased_always_on`, `parentbased_always_off`, `parentbased_traceidratio`
+    /// (default: `parentbased_always_on`).
+    #[serde(default = "default_telemetry_sampler")]
+    pub sampler: String,
+
+    /// Sampling ratio for the `*traceidratio` samplers (default: 1.0).
+    #[serde(default = "default_telemetry_sampler_ratio")]
+    pub sampler_ratio: f64,
+
+    /// Timeout of one export request in milliseconds (default: 10000).
+    #[serde(default = "default_telemetry_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_telemetry_endpoint(),
+            service_name: default_telemetry_service_name(),
+            sampler: default_telemetry_sampler(),
+            sampler_ratio: default_telemetry_sampler_ratio(),
+            timeout_ms: default_telemetry_timeout_ms(),
+        }
+    }
+}
+
+fn default_telemetry_endpoint() -> String {
+    "http://localhost:4318/v1/traces".to_string()
+}
+fn default_telemetry_service_name() -> String {
+    "webshelf-server".to_string()
+}
+fn default_telemetry_sampler() -> String {
+    "parentbased_always_on".to_string()
+}
+fn default_telemetry_sampler_ratio() -> f64 {
+    1.0
+}
+fn default_telemetry_timeout_ms() -> u64 {
+    10_000
+}
+
+/// Background job scheduler (`[scheduler]`).
+///
+/// Every replica polls for due jobs; a per-job Redis lock plus a conditional DB claim make
+/// sure each run happens on one replica only. Replicas must share the same job settings.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SchedulerConfig {
+    /// Whether this instance runs due jobs (default: true). Manual runs via the admin API
+    /// work either way.
+    #[serde(default = "default_scheduler_enabled")]
+    pub enabled: bool,
+
+    /// How often due jobs are looked up, in seconds (default: 5)
+    #[serde(default = "default_scheduler_poll_interval")]
+    pub poll_interval_secs: u64,
+
+    /// Lease of a running job in seconds (default: 60). The job lock and DB claim are
+    /// renewed every third of it; a crashed runner frees the job after one lease.
+    #[serde(default = "default_scheduler_lease")]
+    pub lease_secs: u64,
+
+    /// Per-job overrides keyed by job name, e.g. `[scheduler.jobs.cleanup_refresh_tokens]`
+    #[serde(default)]
+    pub jobs: std::collections::BTreeMap<String, ScheduledJobConfig>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_scheduler_enabled(),
+            poll_interval_secs: default_scheduler_poll_interval(),
+            lease_secs: default_scheduler_lease(),
+            jobs: Default::default(),
+        }
+    }
+}
+
+/// Override of one built-in job's schedule.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ScheduledJobConfig {
+    /// `every <n><s|m|h|d>` (e.g. `every 30m`) or a cron expression with 5 fields
+    /// (`min hour day month weekday`, UTC) or 6–7 fields (leading seconds, trailing year)
+    pub schedule: Option<String>,
+
+    /// Set to false to stop scheduled runs of this job (manual runs stay available)
+    pub enabled: Option<bool>,
+}
+
+fn default_scheduler_enabled() -> bool {
+    true
+}
+fn default_scheduler_poll_interval() -> u64 {
+    5
+}
+fn default_scheduler_lease() -> u64 {
+    60
+}
+
+/// Durable job queue (`[queue]`).
+///
+/// Jobs live in the `job_queue` table, so any replica may run any job; workers claim rows
+/// with `FOR UPDATE SKIP LOCKED`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct QueueConfig {
+    /// Whether this instance runs queue workers (default: true). Jobs are enqueued either
+    /// way and picked up by the replicas that do.
+    #[serde(default = "default_queue_enabled")]
+    pub enabled: bool,
+
+    /// Jobs run concurrently by this instance (default: 4)
+    #[serde(default = "default_queue_concurrency")]
+    pub concurrency: usize,
+
+    /// How often an idle worker looks for due jobs, in milliseconds (default: 1000).
+    /// Jobs enqueued on the same instance wake the workers immediately.
+    #[serde(default = "default_queue_poll_interval")]
+    pub poll_interval_ms: u64,
+
+    /// Seconds a claimed job stays hidden from other workers (default: 300). A job still
+    /// running after this is considered lost and handed to another worker.
+    #[serde(default = "default_queue_visibility_timeout")]
+    pub visibility_timeout_secs: u64,
+
+    /// Delay before the first retry in seconds, doubled per further attempt (default: 10)
+    #[serde(default = "default_queue_retry_base")]
+    pub retry_base_secs: u64,
+
+    /// Upper bound of the retry delay in seconds (default: 3600)
+    #[serde(default = "default_queue_retry_max")]
+    pub retry_max_secs: u64,
+
+    /// Days succeeded jobs are kept before `purge_job_queue` deletes them (default: 7).
+    /// Dead jobs are kept until retried or deleted manually.
+    #[serde(default = "default_queue_retention_days")]
+    pub retention_days: u32,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_queue_enabled(),
+            concurrency: default_queue_concurrency(),
+            poll_interval_ms: default_queue_poll_interval(),
+            visibility_timeout_secs: default_queue_visibility_timeout(),
+            retry_base_secs: default_queue_retry_base(),
+            retry_max_secs: default_queue_retry_max(),
+            retention_days: default_queue_retention_days(),
+        }
+    }
+}
+
+fn default_queue_enabled() -> bool {
+    true
+}
+fn default_queue_concurrency() -> usize {
+    4
+}
+fn default_queue_poll_interval() -> u64 {
+    1000
+}
+fn default_queue_visibility_timeout() -> u64 {
+    300
+}
+fn default_queue_retry_base() -> u64 {
+    10
+}
+fn default_queue_retry_max() -> u64 {
+    3600
+}
+fn default_queue_retention_days() -> u32 {
+    7
+}
+
+/// TOTP two-factor authentication (`[two_factor]`).
+///
+/// Users opt in from their settings; accounts with a confirmed TOTP secret log in in two
+/// steps (password, then code).
+#[derive(Debug, Deserialize, Clone)]
+pub struct TwoFactorConfig {
+    /// Issuer shown by authenticator apps next to the account (default: "WebShelf").
+    /// Must not contain `:`.
+    #[serde(default = "default_two_factor_issuer")]
+    pub issuer: String,
+
+    /// Seconds the challenge token of the password step stays valid (default: 300)
+    #[serde(default = "default_two_factor_challenge_ttl")]
+    pub challenge_ttl_secs: u64,
+
+    /// Wrong codes accepted per challenge before the user has to log in again (default: 5)
+    #[serde(default = "default_two_factor_max_attempts")]
+    pub max_attempts: u32,
+}
+
+impl Default for TwoFactorConfig {
+    fn default() -> Self {
+        Self {
+            issuer: default_two_factor_issuer(),
+            challenge_ttl_secs: default_two_factor_challenge_ttl(),
+            max_attempts: default_two_factor_max_attempts(),
+        }
+    }
+}
+
+fn default_two_factor_issuer() -> String {
+    "WebShelf".to_string()
+}
+fn default_two_factor_challenge_ttl() -> u64 {
+    300
+}
+fn default_two_factor_max_attempts() -> u32 {
+    5
+}
+
+/// WebAuthn passkeys (`[webauthn]`).
+///
+/// Passkeys are bound to the relying party ID — the site's registrable domain — so
+/// changing `rp_id` later invalidates every registered passkey.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebAuthnConfig {
+    /// Relying party ID: the domain the web app is served from, without scheme or port
+    /// (default: "localhost")
+    #[serde(default = "default_webauthn_rp_id")]
+    pub rp_id: String,
+
+    /// Name shown by the browser / authenticator (default: "WebShelf")
+    #[serde(default = "default_webauthn_rp_name")]
+    pub rp_name: String,
+
+    /// Origins allowed to run the ceremonies, e.g. `https://app.example.com`; each host
+    /// must be `rp_id` or a subdomain of it (default: `["http://localhost:8080"]`)
+    #[serde(default = "default_webauthn_origins")]
+    pub origins: Vec<String>,
+
+    /// Seconds a registration / login challenge stays valid (default: 300)
+    #[serde(default = "default_webauthn_challenge_ttl")]
+    pub challenge_ttl_secs: u64,
+}
+
+impl Default for WebAuthnConfig {
+    fn default() -> Self {
+        Self {
+            rp_id: default_webauthn_rp_id(),
+            rp_name: default_webauthn_rp_name(),
+            origins: default_webauthn_origins(),
+            challenge_ttl_secs: default_webauthn_challenge_ttl(),
+        }
+    }
+}
+
+fn default_webauthn_rp_id() -> String {
+    "localhost".to_string()
+}
+fn default_webauthn_rp_name() -> String {
+    "WebShelf".to_string()
+}
+fn default_webauthn_origins() -> Vec<String> {
+    vec!["http://localhost:8080".to_string()]
+}
+fn default_webauthn_challenge_ttl() -> u64 {
+    300
+}
+
+/// Login with external OpenID Connect providers (`[oidc]`).
+///
+/// Each `[oidc.providers.<name>]` adds a "Sign in with …" option. The provider must have
+/// `{public_url}/api/public/auth/oidc/<name>/callback` registered as redirect URI.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcConfig {
+    /// URL the browser reaches this API under, without trailing slash
+    /// (default: "http://localhost:8080", the web dev server proxying `/api`)
+    #[serde(default = "default_oidc_public_url")]
+    pub public_url: String,
+
+    /// Where the browser lands after logging in (default: "http://localhost:8080/dashboard")
+    #[serde(default = "default_oidc_success_redirect")]
+    pub success_redirect: String,
+
+    /// Login page of the web app; failed logins land here with `#oidc_error=<code>`, logins
+    /// that still need a second factor with `#two_factor=<challenge>`
+    /// (default: "http://localhost:8080/")
+    #[serde(default = "default_oidc_login_redirect")]
+    pub login_redirect: String,
+
+    /// Seconds the user has to finish logging in at the provider (default: 600)
+    #[serde(default = "default_oidc_flow_ttl")]
+    pub flow_ttl_secs: u64,
+
+    /// Seconds discovery documents and signing keys are cached in Redis (default: 3600)
+    #[serde(default = "default_oidc_metadata_cache")]
+    pub metadata_cache_secs: u64,
+
+    /// Timeout of each request to a provider in milliseconds (default: 5000)
+    #[serde(default = "default_oidc_http_timeout_ms")]
+    pub http_timeout_ms: u64,
+
+    /// Providers keyed by name, e.g. `[oidc.providers.google]`
+    #[serde(default)]
+    pub providers: std::collections::BTreeMap<String, OidcProviderConfig>,
+}
+
+impl Default for OidcConfig {
+    fn default() -> Self {
+        Self {
+            public_url: default_oidc_public_url(),
+            success_redirect: default_oidc_success_redirect(),
+            login_redirect: default_oidc_login_redirect(),
+            flow_ttl_secs: default_oidc_flow_ttl(),
+            metadata_cache_secs: default_oidc_metadata_cache(),
+            http_timeout_ms: default_oidc_http_timeout_ms(),
+            providers: Default::default(),
+        }
+    }
+}
+
+/// One OpenID Connect provider.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcProviderConfig {
+    /// Label of the login button (default: the provider name)
+    #[serde(default)]
+    pub display_name: Option<String>,
+
+    /// Issuer URL; the discovery document is `{issuer}/.well-known/openid-configuration`
+    pub issuer: String,
+
+    pub client_id: String,
+
+    /// Leave empty for a public client (PKCE only)
+    #[serde(default)]
+    pub client_secret: String,
+
+    /// Requested scopes; must include `openid` (default: `["openid", "email", "profile"]`)
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+
+    /// Set to false to hide the provider without deleting its settings (default: true)
+    #[serde(default = "default_oidc_provider_flag")]
+    pub enabled: bool,
+
+    /// Create an account for an unknown identity whose verified email is not taken
+    /// (default: true)
+    #[serde(default = "default_oidc_provider_flag")]
+    pub allow_signup: bool,
+
+    /// Link an unknown identity to the account with the same verified email (default: true)
+    #[serde(default = "default_oidc_provider_flag")]
+    pub link_by_email: bool,
+
+    /// Treat emails as verified without an `email_verified: true` claim — only for providers
+    /// that verify every address, like a company directory (default: false)
+    #[serde(default)]
+    pub trust_email: bool,
+}
+
+fn default_oidc_public_url() -> String {
+    "http://localhost:8080".to_string()
+}
+fn default_oidc_success_redirect() -> String {
+    "http://localhost:8080/dashboard".to_string()
+}
+fn default_oidc_login_redirect() -> String {
+    "http://localhost:8080/".to_string()
+}
+fn default_oidc_flow_ttl() -> u64 {
+    600
+}
+fn default_oidc_metadata_cache() -> u64 {
+    3600
+}
+fn default_oidc_http_timeout_ms() -> u64 {
+    5000
+}
+fn default_oidc_provider_flag() -> bool {
+    true
+}
+fn default_oidc_scopes() -> Vec<String> {
+    ["openid", "email", "profile"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+/// Authorization server for third-party apps (`[oauth]`).
+///
+/// Clients are registered through `/api/admin/oauth/clients`; the issuer is
+/// `{public_url}/api/public/oauth`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OAuthConfig {
+    /// URL clients reach this API under, without trailing slash
+    /// (default: "http://localhost:8080")
+    #[serde(default = "default_oauth_public_url")]
+    pub public_url: String,
+
+    /// Consent page of the web app; authorization requests land here with `?request=<token>`
+    /// (default: "http://localhost:8080/oauth/consent")
+    #[serde(default = "default_oauth_consent_url")]
+    pub consent_url: String,
+
+    /// Lifetime of access and ID tokens in seconds (default: 3600)
+    #[serde(default = "default_oauth_access_token_ttl")]
+    pub access_token_ttl_secs: u64,
+
+    /// Lifetime of refresh tokens in seconds (default: 2592000, 30 days)
+    #[serde(default = "default_oauth_refresh_token_ttl")]
+    pub refresh_token_ttl_secs: u64,
+
+    /// Seconds an authorization code can be redeemed (default: 60)
+    #[serde(default = "default_oauth_code_ttl")]
+    pub code_ttl_secs: u64,
+
+    /// Seconds the user has to approve an authorization request (default: 600)
+    #[serde(default = "default_oauth_request_ttl")]
+    pub request_ttl_secs: u64,
+}
+
+impl Default for OAuthConfig {
+    fn default() -> Self {
+        Self {
+            public_url: default_oauth_public_url(),
+            consent_url: default_oauth_consent_url(),
+            access_token_ttl_secs: default_oauth_access_token_ttl(),
+            refresh_token_ttl_secs: default_oauth_refresh_token_ttl(),
+            code_ttl_secs: default_oauth_code_ttl(),
+            request_ttl_secs: default_oauth_request_ttl(),
+        }
+    }
+}
+
+fn default_oauth_public_url() -> String {
+    "http://localhost:8080".to_string()
+}
+fn default_oauth_consent_url() -> String {
+    "http://localhost:8080/oauth/consent".to_string()
+}
+fn default_oauth_access_token_ttl() -> u64 {
+    3600
+}
+fn default_oauth_refresh_token_ttl() -> u64 {
+    30 * 24 * 3600
+}
+fn default_oauth_code_ttl() -> u64 {
+    60
+}
+fn default_oauth_request_ttl() -> u64 {
+    600
+}
+
+/// Dependency checked by `/readyz` and `/api/admin/health`.
+#[derive(
+    Debug,
+    Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthComponent {
+    /// Write database
+    Database,
+    /// Read replicas (circuit-breaker state)
+    Replicas,
+    Cache,
+    RateLimiter,
+    Email,
+    Wechat,
+    Snowflake,
+}
+
+/// Dependency health checks (`[health]`).
+#[derive(Debug, Deserialize, Clone)]
+pub struct HealthConfig {
+    /// Components whose `down` status makes `/readyz` return 503 (default: `["database"]`).
+    /// `degraded` never fails readiness.
+    #[serde(default = "default_readiness_requires")]
+    pub readiness_requires: Vec<HealthComponent>,
+
+    /// Timeout for each network check in milliseconds (default: 2000)
+    #[serde(default = "default_health_check_timeout_ms")]
+    pub check_timeout_ms: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            readiness_requires: default_readiness_requires(),
+            check_timeout_ms: default_health_check_timeout_ms(),
+        }
+    }
+}
+
+fn default_readiness_requires() -> Vec<HealthComponent> {
+    vec![HealthComponent::Database]
+}
+fn default_health_check_timeout_ms() -> u64 {
+    2000
+}
+
 /// WeChat Official Account configuration (optional).
 ///
 /// When enabled, users can log in via captcha codes obtained from the
@@ -442,8 +1252,13 @@ pub fn load_config(config_path: &str, env: &str) -> Result<AppConfig> {
                 .try_parsing(true)
                 .list_separator(",")
                 .with_list_parse_key("server.allowed_origins")
+                .with_list_parse_key("server.trusted_proxies")
+                .with_list_parse_key("server.websocket_origins")
                 .with_list_parse_key("database_read_urls")
-                .with_list_parse_key("wechat.trigger_keywords"),
+                .with_list_parse_key("wechat.trigger_keywords")
+                .with_list_parse_key("health.readiness_requires")
+                .with_list_parse_key("logging.levels")
+                .with_list_parse_key("webauthn.origins"),
         )
         .build()
         .context("Failed to build configuration")?;
@@ -465,6 +1280,13 @@ mod tests {
         assert!(server.allowed_origins.is_empty());
     }
 
+    #[test]
+    fn test_metrics_endpoint_is_off_by_default() {
+        let metrics = MetricsConfig::default();
+        assert!(!metrics.enabled);
+        assert_eq!(metrics.path, "/metrics");
+    }
+
     #[test]
     fn test_default_jwt_expiry() {
         assert_eq!(default_jwt_expiry(), 3600);
@@ -486,6 +1308,11 @@ mod tests {
             host: "127.0.0.1".to_string(),
             port: 8080,
             allowed_origins: vec!["http://127.0.0.1:3000".to_string()],
+            trusted_proxies: TrustedProxies::default(),
+            websocket_origins: WebSocketOrigins::default(),
+            security_headers: SecurityHeadersConfig::default(),
+            tls: TlsConfig::default(),
+            shutdown: ShutdownConfig::default(),
         };
         let cloned = config.clone();
         assert_eq!(config.host, cloned.host);
@@ -522,6 +1349,17 @@ mod tests {
             database_read: DatabaseReadConfig::default(),
             email: emailserver::EmailConfig::default(),
             wechat: WechatAccountConfig::default(),
+            openapi: OpenApiConfig::default(),
+            health: HealthConfig::default(),
+            metrics: MetricsConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            logging: LoggingConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            queue: QueueConfig::default(),
+            two_factor: TwoFactorConfig::default(),
+            webauthn: WebAuthnConfig::default(),
+            oidc: OidcConfig::default(),
+            oauth: OAuthConfig::default(),
         };
         let cloned = config.clone();
         assert_eq!(config.database_url, cloned.database_url);
@@ -592,4 +1430,298 @@ mod tests {
             "https://single.example.com"
         );
     }
+
+    /// Verify that `WEBSHELF_SERVER__TRUSTED_PROXIES` is parsed as a CIDR list.
+    #[test]
+    fn test_trusted_proxies_from_env_list() {
+        use config::{Config, Environment};
+        use std::collections::HashMap;
+
+        let mut source = HashMap::new();
+        source.insert(
+            "WEBSHELF_SERVER__TRUSTED_PROXIES".to_string(),
+            "10.0.0.0/8,::1".to_string(),
+        );
+
+        let settings = Config::builder()
+            .add_source(
+                Environment::with_prefix("WEBSHELF")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true)
+                    .list_separator(",")
+                    .with_list_parse_key("server.trusted_proxies")
+                    .source(Some(source)),
+            )
+            .build()
+            .unwrap();
+
+        let config: AppConfig = settings.try_deserialize().unwrap();
+        let trusted = &config.server.trusted_proxies;
+        assert!(trusted.contains("10.1.2.3".parse().unwrap()));
+        assert!(trusted.contains("::1".parse().unwrap()));
+        assert!(!trusted.contains("192.0.2.1".parse().unwrap()));
+    }
+
+    /// An invalid CIDR must fail config loading rather than silently trusting nothing.
+    #[test]
+    fn test_invalid_trusted_proxy_rejected() {
+        use config::{Config, Environment};
+        use std::collections::HashMap;
+
+        let mut source = HashMap::new();
+        source.insert(
+            "WEBSHELF_SERVER__TRUSTED_PROXIES".to_string(),
+            "10.0.0.0/40".to_string(),
+        );
+
+        let settings = Config::builder()
+            .add_source(
+                Environment::with_prefix("WEBSHELF")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true)
+                    .list_separator(",")
+                    .with_list_parse_key("server.trusted_proxies")
+                    .source(Some(source)),
+            )
+            .build()
+            .unwrap();
+
+        assert!(settings.try_deserialize::<AppConfig>().is_err());
+    }
+
+    /// `[server.security_headers]` can be partially overridden; unset keys keep their defaults.
+    #[test]
+    fn test_security_headers_partial_override() {
+        use config::{Config, Environment};
+        use std::collections::HashMap;
+
+        let mut source = HashMap::new();
+        source.insert(
+            "WEBSHELF_SERVER__SECURITY_HEADERS__FRAME_OPTIONS".to_string(),
+            "DENY".to_string(),
+        );
+        source.insert(
+            "WEBSHELF_SERVER__SECURITY_HEADERS__HSTS_MAX_AGE_SECS".to_string(),
+            "0".to_string(),
+        );
+
+        let settings = Config::builder()
+            .add_source(
+                Environment::with_prefix("WEBSHELF")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true)
+                    .source(Some(source)),
+            )
+            .build()
+            .unwrap();
+
+        let config: AppConfig = settings.try_deserialize().unwrap();
+        let headers = &config.server.security_headers;
+        assert!(headers.enabled);
+        assert_eq!(headers.frame_options, "DENY");
+        assert_eq!(headers.hsts_max_age_secs, 0);
+        assert!(headers.content_security_policy.contains("{nonce}"));
+    }
+
+    /// `[server.tls]` env overrides; `MIN_VERSION=1.3` is parsed as a number by the env source.
+    #[test]
+    fn test_tls_env_override() {
+        use config::{Config, Environment};
+        use std::collections::HashMap;
+        use webshelf_runtime::{ClientAuth, TlsVersion};
+
+        let mut source = HashMap::new();
+        source.insert(
+            "WEBSHELF_SERVER__TLS__ENABLED".to_string(),
+            "true".to_string(),
+        );
+        source.insert(
+            "WEBSHELF_SERVER__TLS__MIN_VERSION".to_string(),
+            "1.3".to_string(),
+        );
+        source.insert(
+            "WEBSHELF_SERVER__TLS__CLIENT_AUTH".to_string(),
+            "required".to_string(),
+        );
+
+        let settings = Config::builder()
+            .add_source(
+                Environment::with_prefix("WEBSHELF")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true)
+                    .source(Some(source)),
+            )
+            .build()
+            .unwrap();
+
+        let config: AppConfig = settings.try_deserialize().unwrap();
+        let tls = &config.server.tls;
+        assert!(tls.enabled);
+        assert_eq!(tls.min_version, TlsVersion::Tls13);
+        assert_eq!(tls.client_auth, ClientAuth::Required);
+        assert_eq!(tls.reload_interval_secs, 30);
+    }
+
+    #[test]
+    fn test_shutdown_env_override() {
+        use config::{Config, Environment};
+        use std::collections::HashMap;
+
+        let mut source = HashMap::new();
+        source.insert(
+            "WEBSHELF_SERVER__SHUTDOWN__PRE_STOP_DELAY_SECS".to_string(),
+            "10".to_string(),
+        );
+
+        let settings = Config::builder()
+            .add_source(
+                Environment::with_prefix("WEBSHELF")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true)
+                    .source(Some(source)),
+            )
+            .build()
+            .unwrap();
+
+        let config: AppConfig = settings.try_deserialize().unwrap();
+        assert_eq!(config.server.shutdown.pre_stop_delay_secs, 10);
+        assert_eq!(config.server.shutdown.drain_timeout_secs, 20);
+    }
+
+    #[test]
+    fn test_readiness_requires_from_env_list() {
+        use config::{Config, Environment};
+        use std::collections::HashMap;
+
+        let mut source = HashMap::new();
+        source.insert(
+            "WEBSHELF_HEALTH__READINESS_REQUIRES".to_string(),
+            "database,cache".to_string(),
+        );
+
+        let settings = Config::builder()
+            .add_source(
+                Environment::with_prefix("WEBSHELF")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true)
+                    .list_separator(",")
+                    .with_list_parse_key("health.readiness_requires")
+                    .source(Some(source)),
+            )
+            .build()
+            .unwrap();
+
+        let config: AppConfig = settings.try_deserialize().unwrap();
+        assert_eq!(
+            config.health.readiness_requires,
+            vec![HealthComponent::Database, HealthComponent::Cache]
+        );
+        assert_eq!(config.health.check_timeout_ms, 2000);
+    }
+
+    #[test]
+    fn test_logging_outputs_and_levels() {
+        use config::{Config, Environment, File, FileFormat};
+        use std::collections::HashMap;
+
+        let toml = r#"
+            [logging]
+            format = "json"
+
+            [[logging.outputs]]
+            stream = "app"
+
+            [[logging.outputs]]
+            kind = "file"
+            stream = "access"
+            path = "/var/log/webshelf/access.log"
+            max_size_mb = 100
+        "#;
+        let mut source = HashMap::new();
+        source.insert(
+            "WEBSHELF_LOGGING__LEVELS".to_string(),
+            "sqlx=warn,webshelf_server=debug".to_string(),
+        );
+
+        let settings = Config::builder()
+            .add_source(File::from_str(toml, FileFormat::Toml))
+            .add_source(
+                Environment::with_prefix("WEBSHELF")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true)
+                    .list_separator(",")
+                    .with_list_parse_key("logging.levels")
+                    .source(Some(source)),
+            )
+            .build()
+            .unwrap();
+
+        let logging = settings.try_deserialize::<AppConfig>().unwrap().logging;
+        assert_eq!(logging.level, "info");
+        assert_eq!(logging.levels, vec!["sqlx=warn", "webshelf_server=debug"]);
+        assert_eq!(logging.format, LogFormat::Json);
+        assert_eq!(logging.outputs.len(), 2);
+        assert_eq!(logging.outputs[0].kind, LogOutputKind::Stdout);
+        assert_eq!(logging.outputs[0].stream, LogStream::App);
+        let file = &logging.outputs[1];
+        assert_eq!(file.kind, LogOutputKind::File);
+        assert_eq!(file.rotation, LogRotation::Daily);
+        assert_eq!(file.max_size_mb, 100);
+        assert_eq!(file.max_files, 7);
+    }
+
+    #[test]
+    fn test_oidc_providers_with_env_secret() {
+        use config::{Config, Environment, File, FileFormat};
+        use std::collections::HashMap;
+
+        let toml = r#"
+            [oidc.providers.google]
+            display_name = "Google"
+            issuer = "https://accounts.google.com"
+            client_id = "webshelf.apps.googleusercontent.com"
+
+            [oidc.providers.corp]
+            issuer = "https://sso.example.com/realms/staff"
+            client_id = "webshelf"
+            scopes = ["openid", "email"]
+            trust_email = true
+        "#;
+        let mut source = HashMap::new();
+        source.insert(
+            "WEBSHELF_OIDC__PROVIDERS__GOOGLE__CLIENT_SECRET".to_string(),
+            "from-env".to_string(),
+        );
+
+        let settings = Config::builder()
+            .add_source(File::from_str(toml, FileFormat::Toml))
+            .add_source(
+                Environment::with_prefix("WEBSHELF")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true)
+                    .source(Some(source)),
+            )
+            .build()
+            .unwrap();
+
+        let oidc = settings.try_deserialize::<AppConfig>().unwrap().oidc;
+        assert_eq!(oidc.flow_ttl_secs, 600);
+        let google = &oidc.providers["google"];
+        assert_eq!(google.client_secret, "from-env");
+        assert_eq!(google.scopes, vec!["openid", "email", "profile"]);
+        assert!(google.enabled && google.allow_signup && google.link_by_email);
+        assert!(!google.trust_email);
+        let corp = &oidc.providers["corp"];
+        assert!(corp.client_secret.is_empty());
+        assert_eq!(corp.scopes, vec!["openid", "email"]);
+        assert!(corp.trust_email);
+    }
 }
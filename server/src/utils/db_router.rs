@@ -10,8 +10,10 @@ use std::collections::HashSet;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
+use tracing::Instrument;
 
 use crate::utils::config::{DatabaseConfig, DatabaseReadConfig, DatabaseRoutingConfig};
+use crate::utils::metrics;
 
 /// Read replica selection strategy
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -27,7 +29,6 @@ struct ReadReplica {
     /// Original index in the `database_read_urls` config array.
     /// Used to correctly map configured weights even when some replicas
     /// fail to connect (preventing weight-to-replica misalignment).
-    #[allow(dead_code)]
     original_index: usize,
     weight: u32,
 }
@@ -37,6 +38,16 @@ struct HealthState {
     down_until: Vec<Option<Instant>>,
 }
 
+/// Circuit-breaker snapshot of one connected read replica.
+#[derive(Debug, Clone, serde::Serialize, schemars::JsonSchema)]
+pub struct ReplicaHealth {
+    /// Index in the `database_read_urls` config array.
+    pub index: usize,
+    pub weight: u32,
+    /// Remaining circuit-breaker time; `None` while the replica is in rotation.
+    pub circuit_open_ms: Option<u64>,
+}
+
 /// Application-level router that implements SeaORM's `ConnectionTrait` and
 /// `TransactionTrait` to provide transparent read-write splitting.
 ///
@@ -50,7 +61,7 @@ struct HealthState {
 /// When no read replicas are configured, `AutoRouter` transparently degenerates
 /// into a single-database pass-through.
 pub struct AutoRouter {
-    write: DatabaseConnection,
+    write: WriteConnection,
     reads: Vec<ReadReplica>,
     strategy: ReadStrategy,
     rr_counter: AtomicUsize,
@@ -172,7 +183,7 @@ impl AutoRouter {
     /// Create a single-database router (no read-write splitting).
     pub fn single(write: DatabaseConnection) -> Arc<Self> {
         Arc::new(Self {
-            write,
+            write: WriteConnection(write),
             reads: vec![],
             strategy: ReadStrategy::RoundRobin,
             rr_counter: AtomicUsize::new(0),
@@ -195,7 +206,7 @@ impl AutoRouter {
             _ => ReadStrategy::RoundRobin,
         };
         Self {
-            write,
+            write: WriteConnection(write),
             reads,
             strategy,
             rr_counter: AtomicUsize::new(0),
@@ -214,7 +225,9 @@ impl AutoRouter {
     /// a transaction that modifies data, re-querying the write database
     /// guarantees you see the latest state even if the read replicas are
     /// behind.
-    pub fn write_conn(&self) -> &DatabaseConnection {
+    ///
+    /// Statements on it are traced and counted like routed ones (see [`WriteConnection`]).
+    pub fn write_conn(&self) -> &WriteConnection {
         &self.write
     }
 
@@ -223,17 +236,41 @@ impl AutoRouter {
         self.write.get_database_backend()
     }
 
+    /// Ping the write database.
+    pub async fn ping_write(&self) -> Result<(), DbErr> {
+        self.write.0.ping().await
+    }
+
+    /// Circuit-breaker state of every connected read replica (empty in single-database mode).
+    ///
+    /// An expired breaker counts as closed: the next read re-admits the replica.
+    pub fn replica_health(&self) -> Vec<ReplicaHealth> {
+        let health = self.health.lock();
+        let now = Instant::now();
+        self.reads
+            .iter()
+            .zip(&health.down_until)
+            .map(|(replica, until)| ReplicaHealth {
+                index: replica.original_index,
+                weight: replica.weight,
+                circuit_open_ms: until
+                    .and_then(|until| until.checked_duration_since(now))
+                    .map(|left| left.as_millis() as u64),
+            })
+            .collect()
+    }
+
     /// Start a background health-check task that periodically probes all
     /// currently-down read replicas and removes the circuit breaker when a
-    /// replica recovers.
-    pub fn start_health_check(self: Arc<Self>, interval: Duration) {
+    /// replica recovers. Abort the returned handle to stop it.
+    pub fn start_health_check(self: Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
             let mut ticker = tokio::time::interval(interval);
             loop {
                 ticker.tick().await;
                 self.probe_reads().await;
             }
-        });
+        })
     }
 
     async fn probe_reads(&self) {
@@ -327,7 +364,7 @@ impl AutoRouter {
         Fut: std::future::Future<Output = Result<T, DbErr>>,
     {
         if self.reads.is_empty() {
-            return op(self.write.clone(), stmt).await;
+            return op(self.write.0.clone(), stmt).await;
         }
 
         let mut tried: HashSet<usize> = HashSet::new();
@@ -339,6 +376,7 @@ impl AutoRouter {
                 break;
             };
             tried.insert(idx);
+            self.record_replica(idx);
 
             match op(self.reads[idx].conn.clone(), stmt.clone()).await {
                 Ok(v) => return Ok(v),
@@ -360,6 +398,7 @@ impl AutoRouter {
         if last_err.is_some() && self.retry_attempts > 0 {
             for retry in 0..self.retry_attempts {
                 let idx = (tried.len() + retry) % self.reads.len();
+                self.record_replica(idx);
 
                 match op(self.reads[idx].conn.clone(), stmt.clone()).await {
                     Ok(v) => return Ok(v),
@@ -379,17 +418,26 @@ impl AutoRouter {
         }
 
         if self.fallback_to_write {
+            metrics::db_read_fallback();
+            tracing::Span::current().record("db.target", "write_fallback");
             tracing::warn!("All read replicas failed — falling back to writer");
-            return op(self.write.clone(), stmt).await;
+            return op(self.write.0.clone(), stmt).await;
         }
 
         Err(last_err.unwrap_or_else(|| DbErr::Custom("all read attempts exhausted".into())))
     }
 
+    /// Tag the enclosing `db.query` span with the replica being tried (index in
+    /// `database_read_urls`); retries overwrite it, so the span ends with the last attempt.
+    fn record_replica(&self, idx: usize) {
+        tracing::Span::current().record("db.replica", self.reads[idx].original_index);
+    }
+
     fn mark_down(&self, idx: usize) {
         let mut health = self.health.lock();
         if let Some(slot) = health.down_until.get_mut(idx) {
             *slot = Some(Instant::now() + self.circuit_break);
+            metrics::db_circuit_break(self.reads[idx].original_index);
         }
     }
 }
@@ -420,6 +468,134 @@ fn select_weighted_index(healthy: &[usize], weights: &[u32], rr_counter: &Atomic
     }
 }
 
+/// Client span around one routed statement. `db.target` is `write`, `read` or
+/// `write_fallback`; `db.replica` is recorded by [`AutoRouter::execute_read_retry`].
+///
+/// Statements are prepared, so `db.query.text` carries placeholders, not bound values.
+pub(crate) fn query_span(stmt: &Statement, write: bool) -> tracing::Span {
+    let system = match stmt.db_backend {
+        DbBackend::Postgres => "postgresql",
+        DbBackend::MySql => "mysql",
+        DbBackend::Sqlite => "sqlite",
+    };
+    tracing::info_span!(
+        "db.query",
+        otel.kind = "client",
+        db.system = system,
+        db.target = if write { "write" } else { "read" },
+        db.replica = tracing::field::Empty,
+        db.query.text = %stmt.sql,
+    )
+}
+
+// ---- Write connection ----
+
+/// The write pool as returned by [`AutoRouter::write_conn`]: every statement is counted in
+/// the statement metrics and runs in a `db.query` span (`db.target = "write"`), like the
+/// statements the router itself sends to the writer.
+///
+/// Statements inside a transaction run on the [`DatabaseTransaction`] and are not traced.
+#[derive(Clone)]
+pub struct WriteConnection(DatabaseConnection);
+
+impl WriteConnection {
+    /// The underlying pool, for APIs that need a plain [`DatabaseConnection`]. Statements sent
+    /// through it are not traced.
+    pub fn inner(&self) -> &DatabaseConnection {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl ConnectionTrait for WriteConnection {
+    fn get_database_backend(&self) -> DbBackend {
+        self.0.get_database_backend()
+    }
+
+    async fn execute(&self, stmt: Statement) -> Result<ExecResult, DbErr> {
+        metrics::db_statement(true);
+        let span = query_span(&stmt, true);
+        self.0.execute(stmt).instrument(span).await
+    }
+
+    async fn execute_unprepared(&self, sql: &str) -> Result<ExecResult, DbErr> {
+        metrics::db_statement(true);
+        let span = query_span(
+            &Statement::from_string(self.get_database_backend(), sql),
+            true,
+        );
+        self.0.execute_unprepared(sql).instrument(span).await
+    }
+
+    async fn query_one(&self, stmt: Statement) -> Result<Option<QueryResult>, DbErr> {
+        metrics::db_statement(true);
+        let span = query_span(&stmt, true);
+        self.0.query_one(stmt).instrument(span).await
+    }
+
+    async fn query_all(&self, stmt: Statement) -> Result<Vec<QueryResult>, DbErr> {
+        metrics::db_statement(true);
+        let span = query_span(&stmt, true);
+        self.0.query_all(stmt).instrument(span).await
+    }
+
+    fn support_returning(&self) -> bool {
+        self.0.support_returning()
+    }
+
+    fn is_mock_connection(&self) -> bool {
+        self.0.is_mock_connection()
+    }
+}
+
+#[async_trait]
+impl TransactionTrait for WriteConnection {
+    async fn begin(&self) -> Result<DatabaseTransaction, DbErr> {
+        self.0.begin().await
+    }
+
+    async fn begin_with_config(
+        &self,
+        isolation_level: Option<IsolationLevel>,
+        access_mode: Option<AccessMode>,
+    ) -> Result<DatabaseTransaction, DbErr> {
+        self.0.begin_with_config(isolation_level, access_mode).await
+    }
+
+    async fn transaction<F, T, E>(&self, txn: F) -> Result<T, TransactionError<E>>
+    where
+        F: for<'c> FnOnce(
+                &'c DatabaseTransaction,
+            ) -> std::pin::Pin<
+                Box<dyn std::future::Future<Output = Result<T, E>> + Send + 'c>,
+            > + Send,
+        T: Send,
+        E: std::fmt::Debug + std::fmt::Display + Send,
+    {
+        self.0.transaction(txn).await
+    }
+
+    async fn transaction_with_config<F, T, E>(
+        &self,
+        txn: F,
+        isolation_level: Option<IsolationLevel>,
+        access_mode: Option<AccessMode>,
+    ) -> Result<T, TransactionError<E>>
+    where
+        F: for<'c> FnOnce(
+                &'c DatabaseTransaction,
+            ) -> std::pin::Pin<
+                Box<dyn std::future::Future<Output = Result<T, E>> + Send + 'c>,
+            > + Send,
+        T: Send,
+        E: std::fmt::Debug + std::fmt::Display + Send,
+    {
+        self.0
+            .transaction_with_config(txn, isolation_level, access_mode)
+            .await
+    }
+}
+
 // ---- ConnectionTrait implementation ----
 
 #[async_trait]
@@ -445,8 +621,11 @@ impl ConnectionTrait for AutoRouter {
             tracing::trace!(target = "write", "query_one routed to write");
             return self.write.query_one(stmt).await;
         }
+        metrics::db_statement(false);
         tracing::trace!(target = "read", "query_one routed to read replicas");
+        let span = query_span(&stmt, false);
         self.execute_read_retry(stmt, |conn, s| async move { conn.query_one(s).await })
+            .instrument(span)
             .await
     }
 
@@ -455,8 +634,11 @@ impl ConnectionTrait for AutoRouter {
             tracing::trace!(target = "write", "query_all routed to write");
             return self.write.query_all(stmt).await;
         }
+        metrics::db_statement(false);
         tracing::trace!(target = "read", "query_all routed to read replicas");
+        let span = query_span(&stmt, false);
         self.execute_read_retry(stmt, |conn, s| async move { conn.query_all(s).await })
+            .instrument(span)
             .await
     }
 
@@ -1,5 +1,6 @@
 use thiserror::Error;
-use webshelf_runtime::HttpError;
+use validator::{ValidationErrors, ValidationErrorsKind};
+use webshelf_runtime::{FieldError, FieldErrors, HttpError};
 use wechat_api::WechatError;
 
 /// Unified API error type for HTTP boundary
@@ -20,14 +21,53 @@ pub enum ApiError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
-    #[error("Validation error: {0}")]
-    Validation(String),
+    /// Invalid input; `fields` carries per-field errors so forms can highlight inputs.
+    #[error("Validation error: {message}")]
+    Validation {
+        message: String,
+        fields: FieldErrors,
+    },
 
     #[error("Internal server error: {0}")]
     Internal(String),
 
     #[error("Service unavailable: {0}")]
     ServiceUnavailable(String),
+
+    /// Any of the above tagged with a stable machine-readable code (see [`ApiError::with_code`]).
+    #[error("{inner}")]
+    Coded {
+        code: &'static str,
+        inner: Box<ApiError>,
+    },
+}
+
+impl ApiError {
+    /// Validation error without field details.
+    pub fn validation(message: impl Into<String>) -> Self {
+        ApiError::Validation {
+            message: message.into(),
+            fields: FieldErrors::new(),
+        }
+    }
+
+    /// Validation error of a single field.
+    pub fn invalid_field(field: &str, code: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        let error = FieldError::new(code).with_message(message.clone());
+        ApiError::Validation {
+            message: format!("{field}: {message}"),
+            fields: FieldErrors::from([(field.to_string(), vec![error])]),
+        }
+    }
+
+    /// Attach a stable error code, rendered as the `code` member of the problem body.
+    pub fn with_code(self, code: &'static str) -> Self {
+        ApiError::Coded {
+            code,
+            inner: Box::new(self),
+        }
+    }
 }
 
 // Convert ApiError to HttpError for unified handler support
@@ -39,22 +79,50 @@ impl From<ApiError> for HttpError {
             ApiError::Forbidden(msg) => HttpError::forbidden(msg),
             ApiError::NotFound(msg) => HttpError::not_found(msg),
             ApiError::Conflict(msg) => HttpError::conflict(msg),
-            ApiError::Validation(msg) => {
-                // Validation errors use a specific error_type
-                let mut http_err = HttpError::bad_request(msg);
-                http_err.error_type = "validation_error";
-                http_err
+            ApiError::Validation { message, fields } => {
+                HttpError::validation(message).with_field_errors(fields)
             }
             ApiError::Internal(_) => HttpError::internal("An unexpected error occurred"),
             ApiError::ServiceUnavailable(msg) => HttpError::service_unavailable(msg),
+            ApiError::Coded { code, inner } => HttpError::from(*inner).with_code(code),
+        }
+    }
+}
+
+// Convert validator::ValidationErrors to ApiError, keeping one entry per field
+impl From<ValidationErrors> for ApiError {
+    fn from(err: ValidationErrors) -> Self {
+        let mut fields = FieldErrors::new();
+        collect_field_errors(&err, "", &mut fields);
+        ApiError::Validation {
+            message: err.to_string(),
+            fields,
         }
     }
 }
 
-// Convert validator::ValidationErrors to ApiError
-impl From<validator::ValidationErrors> for ApiError {
-    fn from(err: validator::ValidationErrors) -> Self {
-        ApiError::Validation(err.to_string())
+/// Flatten nested validation errors; nested fields use `parent.child` / `list[0].child` paths.
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, out: &mut FieldErrors) {
+    for (field, kind) in errors.errors() {
+        let path = format!("{prefix}{field}");
+        match kind {
+            ValidationErrorsKind::Field(errs) => {
+                out.entry(path)
+                    .or_default()
+                    .extend(errs.iter().map(|e| FieldError {
+                        code: e.code.to_string(),
+                        message: e.message.as_ref().map(|m| m.to_string()),
+                    }));
+            }
+            ValidationErrorsKind::Struct(nested) => {
+                collect_field_errors(nested, &format!("{path}."), out);
+            }
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_field_errors(nested, &format!("{path}[{index}]."), out);
+                }
+            }
+        }
     }
 }
 
@@ -84,14 +152,19 @@ impl From<crate::services::user::UserError> for ApiError {
                 ApiError::NotFound("User not found".to_string())
             }
             crate::services::user::UserError::EmailConflict => {
-                ApiError::Conflict("Email already registered".to_string())
+                ApiError::Conflict("Email already registered".to_string()).with_code("email_taken")
             }
             crate::services::user::UserError::InvalidCredentials => {
                 ApiError::Unauthorized("Current password is incorrect".to_string())
+                    .with_code("invalid_current_password")
             }
             crate::services::user::UserError::Forbidden(msg) => ApiError::Forbidden(msg),
-            crate::services::user::UserError::WeakPassword(msg) => ApiError::BadRequest(msg),
-            crate::services::user::UserError::SamePassword(msg) => ApiError::BadRequest(msg),
+            crate::services::user::UserError::WeakPassword(msg) => {
+                ApiError::BadRequest(msg).with_code("weak_password")
+            }
+            crate::services::user::UserError::SamePassword(msg) => {
+                ApiError::BadRequest(msg).with_code("same_password")
+            }
             crate::services::user::UserError::NotAllowed(msg) => ApiError::Forbidden(msg),
             crate::services::user::UserError::Internal(e) => {
                 tracing::error!("Internal error: {:?}", e);
@@ -107,6 +180,7 @@ impl From<crate::services::auth::AuthError> for ApiError {
         match err {
             crate::services::auth::AuthError::InvalidCredentials => {
                 ApiError::Unauthorized("Invalid email or password".to_string())
+                    .with_code("invalid_credentials")
             }
             crate::services::auth::AuthError::Internal(e) => {
                 tracing::error!("Auth internal error: {:?}", e);
@@ -122,6 +196,7 @@ impl From<crate::services::verification::VerificationError> for ApiError {
         match err {
             crate::services::verification::VerificationError::InvalidOrExpired => {
                 ApiError::BadRequest("Invalid or expired verification code".to_string())
+                    .with_code("invalid_code")
             }
             crate::services::verification::VerificationError::TooManyAttempts => {
                 // Mapped to 400 (not 403) to prevent user enumeration:
@@ -129,9 +204,11 @@ impl From<crate::services::verification::VerificationError> for ApiError {
                 // not exist" from "user exists but is locked out".
                 tracing::warn!("User exceeded max verification attempts");
                 ApiError::BadRequest("Invalid or expired verification code".to_string())
+                    .with_code("invalid_code")
             }
             crate::services::verification::VerificationError::TooSoon => {
                 ApiError::BadRequest("Please wait before requesting a new code".to_string())
+                    .with_code("resend_too_soon")
             }
             crate::services::verification::VerificationError::EmailNotConfigured => {
                 tracing::warn!("Email service not configured for verification");
@@ -159,13 +236,16 @@ impl From<crate::services::password_reset::PasswordResetError> for ApiError {
         match err {
             crate::services::password_reset::PasswordResetError::InvalidOrExpired => {
                 ApiError::BadRequest("Invalid or expired reset code".to_string())
+                    .with_code("invalid_code")
             }
             crate::services::password_reset::PasswordResetError::TooManyAttempts => {
                 tracing::warn!("User exceeded max password-reset attempts");
                 ApiError::BadRequest("Invalid or expired reset code".to_string())
+                    .with_code("invalid_code")
             }
             crate::services::password_reset::PasswordResetError::TooSoon => {
                 ApiError::BadRequest("Please wait before requesting a new reset code".to_string())
+                    .with_code("resend_too_soon")
             }
             crate::services::password_reset::PasswordResetError::EmailNotConfigured => {
                 tracing::warn!("Email service not configured for password reset");
@@ -218,9 +298,214 @@ impl From<WechatError> for ApiError {
     }
 }
 
+// Convert LogLevelError to ApiError for the runtime log level endpoint
+impl From<crate::services::log_level::LogLevelError> for ApiError {
+    fn from(err: crate::services::log_level::LogLevelError) -> Self {
+        match err {
+            crate::services::log_level::LogLevelError::InvalidFilter(msg) => {
+                ApiError::invalid_field("filter", "invalid_filter", msg)
+            }
+            crate::services::log_level::LogLevelError::InvalidTtl => {
+                ApiError::invalid_field("ttl_secs", "range", "ttl_secs is out of range")
+            }
+            crate::services::log_level::LogLevelError::Unavailable => ApiError::ServiceUnavailable(
+                "Runtime log level control is not available".to_string(),
+            ),
+        }
+    }
+}
+
+// Convert SchedulerError to ApiError for the admin job endpoints
+impl From<crate::services::scheduler::SchedulerError> for ApiError {
+    fn from(err: crate::services::scheduler::SchedulerError) -> Self {
+        match err {
+            crate::services::scheduler::SchedulerError::UnknownJob(name) => {
+                ApiError::NotFound(format!("Unknown job: {name}"))
+            }
+            crate::services::scheduler::SchedulerError::AlreadyRunning(name) => {
+                ApiError::Conflict(format!("Job {name} is already running"))
+                    .with_code("job_running")
+            }
+            err @ (crate::services::scheduler::SchedulerError::InvalidSchedule { .. }
+            | crate::services::scheduler::SchedulerError::Internal(_)) => {
+                tracing::error!("Scheduler error: {:?}", err);
+                ApiError::Internal("An unexpected error occurred".to_string())
+            }
+        }
+    }
+}
+
+// Convert QueueError to ApiError for the admin queue endpoints
+impl From<crate::services::queue::QueueError> for ApiError {
+    fn from(err: crate::services::queue::QueueError) -> Self {
+        match err {
+            crate::services::queue::QueueError::NotFound(id) => {
+                ApiError::NotFound(format!("Job {id} not found"))
+            }
+            err @ crate::services::queue::QueueError::NotRetryable { .. } => {
+                ApiError::Conflict(err.to_string()).with_code("job_not_dead")
+            }
+            crate::services::queue::QueueError::Internal(e) => {
+                tracing::error!("Job queue error: {:?}", e);
+                ApiError::Internal("An unexpected error occurred".to_string())
+            }
+        }
+    }
+}
+
+// Convert TwoFactorError to ApiError for the login and 2FA settings endpoints
+impl From<crate::services::two_factor::TwoFactorError> for ApiError {
+    fn from(err: crate::services::two_factor::TwoFactorError) -> Self {
+        match err {
+            crate::services::two_factor::TwoFactorError::InvalidCode => {
+                ApiError::BadRequest("Invalid two-factor code".to_string())
+                    .with_code("invalid_two_factor_code")
+            }
+            crate::services::two_factor::TwoFactorError::InvalidChallenge => {
+                // 401: the client has to restart from the password step
+                ApiError::Unauthorized("Invalid or expired two-factor challenge".to_string())
+                    .with_code("invalid_challenge")
+            }
+            crate::services::two_factor::TwoFactorError::InvalidPassword => {
+                // 400 rather than 401 so the client does not treat it as an expired session
+                ApiError::BadRequest("Password is incorrect".to_string())
+                    .with_code("invalid_password")
+            }
+            err @ crate::services::two_factor::TwoFactorError::AlreadyEnabled => {
+                ApiError::Conflict(err.to_string()).with_code("two_factor_enabled")
+            }
+            err @ crate::services::two_factor::TwoFactorError::NotEnabled => {
+                ApiError::Conflict(err.to_string()).with_code("two_factor_not_enabled")
+            }
+            err @ crate::services::two_factor::TwoFactorError::NoPendingEnrollment => {
+                ApiError::Conflict(err.to_string()).with_code("no_pending_enrollment")
+            }
+            crate::services::two_factor::TwoFactorError::UserNotFound => {
+                ApiError::NotFound("User not found".to_string())
+            }
+            crate::services::two_factor::TwoFactorError::Internal(e) => {
+                tracing::error!("Two-factor internal error: {:?}", e);
+                ApiError::Internal("An unexpected error occurred".to_string())
+            }
+        }
+    }
+}
+
+// Convert WebAuthnError to ApiError for the passkey login and settings endpoints
+impl From<crate::services::webauthn::WebAuthnError> for ApiError {
+    fn from(err: crate::services::webauthn::WebAuthnError) -> Self {
+        match err {
+            // 400 rather than 401: registration runs inside a logged-in session
+            err @ crate::services::webauthn::WebAuthnError::InvalidChallenge => {
+                ApiError::BadRequest(err.to_string()).with_code("invalid_passkey_challenge")
+            }
+            err @ crate::services::webauthn::WebAuthnError::Verification(_) => {
+                tracing::info!("Passkey ceremony rejected: {}", err);
+                ApiError::BadRequest(err.to_string()).with_code("passkey_verification_failed")
+            }
+            err @ crate::services::webauthn::WebAuthnError::UnsupportedAlgorithm(_) => {
+                ApiError::BadRequest(err.to_string()).with_code("unsupported_passkey_algorithm")
+            }
+            crate::services::webauthn::WebAuthnError::UnknownCredential => {
+                ApiError::Unauthorized("Unknown passkey".to_string()).with_code("unknown_passkey")
+            }
+            crate::services::webauthn::WebAuthnError::InvalidPassword => {
+                ApiError::BadRequest("Password is incorrect".to_string())
+                    .with_code("invalid_password")
+            }
+            err @ crate::services::webauthn::WebAuthnError::AlreadyRegistered => {
+                ApiError::Conflict(err.to_string()).with_code("passkey_exists")
+            }
+            err @ crate::services::webauthn::WebAuthnError::TooManyPasskeys => {
+                ApiError::Conflict(err.to_string()).with_code("too_many_passkeys")
+            }
+            crate::services::webauthn::WebAuthnError::NotFound => {
+                ApiError::NotFound("Passkey not found".to_string())
+            }
+            crate::services::webauthn::WebAuthnError::UserNotFound => {
+                ApiError::NotFound("User not found".to_string())
+            }
+            crate::services::webauthn::WebAuthnError::Internal(e) => {
+                tracing::error!("Passkey internal error: {:?}", e);
+                ApiError::Internal("An unexpected error occurred".to_string())
+            }
+        }
+    }
+}
+
+// Convert OidcError to ApiError for the OpenID Connect and linked-account endpoints
+impl From<crate::services::oidc::OidcError> for ApiError {
+    fn from(err: crate::services::oidc::OidcError) -> Self {
+        use crate::services::oidc::OidcError;
+        let code = err.code();
+        match err {
+            OidcError::UnknownProvider | OidcError::NotFound => {
+                ApiError::NotFound(err.to_string()).with_code(code)
+            }
+            OidcError::InvalidState | OidcError::Denied | OidcError::InvalidIdToken(_) => {
+                tracing::info!("OIDC login rejected: {}", err);
+                ApiError::BadRequest(err.to_string()).with_code(code)
+            }
+            OidcError::Provider(ref detail) => {
+                tracing::warn!("OIDC provider error: {}", detail);
+                ApiError::ServiceUnavailable("Login provider is unavailable".to_string())
+                    .with_code(code)
+            }
+            OidcError::EmailNotVerified | OidcError::SignupDisabled => {
+                ApiError::Forbidden(err.to_string()).with_code(code)
+            }
+            OidcError::EmailInUse => ApiError::Conflict(err.to_string()).with_code(code),
+            OidcError::Internal(e) => {
+                tracing::error!("OIDC internal error: {:?}", e);
+                ApiError::Internal("An unexpected error occurred".to_string())
+            }
+        }
+    }
+}
+
+// Convert OAuthError to ApiError for the authorization server and client admin endpoints
+// (the token endpoint renders RFC 6749 errors itself)
+impl From<crate::services::oauth::OAuthError> for ApiError {
+    fn from(err: crate::services::oauth::OAuthError) -> Self {
+        use crate::services::oauth::OAuthError;
+        let code = err.code();
+        match err {
+            OAuthError::NotFound => ApiError::NotFound(err.to_string()).with_code(code),
+            OAuthError::InvalidToken(_) => ApiError::Unauthorized(err.to_string()).with_code(code),
+            OAuthError::Internal(e) => {
+                tracing::error!("OAuth internal error: {:?}", e);
+                ApiError::Internal("An unexpected error occurred".to_string())
+            }
+            other => ApiError::BadRequest(other.to_string()).with_code(code),
+        }
+    }
+}
+
+// Convert ApiTokenError to ApiError for the personal access token endpoints
+impl From<crate::services::api_token::ApiTokenError> for ApiError {
+    fn from(err: crate::services::api_token::ApiTokenError) -> Self {
+        use crate::services::api_token::ApiTokenError;
+        match err {
+            ApiTokenError::MissingScope | ApiTokenError::InvalidScope(_) => {
+                ApiError::BadRequest(err.to_string()).with_code("invalid_scope")
+            }
+            ApiTokenError::TooManyTokens => {
+                ApiError::Conflict(err.to_string()).with_code("too_many_tokens")
+            }
+            ApiTokenError::NotFound => ApiError::NotFound(err.to_string()),
+            ApiTokenError::InvalidToken => ApiError::Unauthorized(err.to_string()),
+            ApiTokenError::Internal(e) => {
+                tracing::error!("API token internal error: {:?}", e);
+                ApiError::Internal("An unexpected error occurred".to_string())
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use validator::Validate;
     use wechat_api::WechatError;
 
     #[test]
@@ -232,6 +517,83 @@ mod tests {
         assert_eq!(error.to_string(), "Forbidden: Access denied");
     }
 
+    // ── Problem details ──────────────────────────────────────────────────
+
+    #[derive(Validate)]
+    struct Signup {
+        #[validate(email(message = "must be a valid email address"))]
+        email: String,
+        #[validate(length(min = 8), custom(function = "reject_password"))]
+        password: String,
+        #[validate(nested)]
+        profile: Profile,
+    }
+
+    #[derive(Validate)]
+    struct Profile {
+        #[validate(length(min = 2, message = "too short"))]
+        name: String,
+    }
+
+    fn reject_password(_: &str) -> Result<(), validator::ValidationError> {
+        Err(validator::ValidationError::new("weak_password"))
+    }
+
+    #[test]
+    fn test_validation_errors_keep_field_details() {
+        let input = Signup {
+            email: "nope".into(),
+            password: "short".into(),
+            profile: Profile { name: "x".into() },
+        };
+        let http = HttpError::from(ApiError::from(input.validate().unwrap_err()));
+        assert_eq!(http.status.as_u16(), 400);
+        assert_eq!(http.error_type, "validation_error");
+
+        let email = &http.field_errors["email"];
+        assert_eq!(email[0].code, "email");
+        assert_eq!(
+            email[0].message.as_deref(),
+            Some("must be a valid email address")
+        );
+        let mut password: Vec<_> = http.field_errors["password"]
+            .iter()
+            .map(|e| e.code.as_str())
+            .collect();
+        password.sort();
+        assert_eq!(password, ["length", "weak_password"]);
+        assert_eq!(http.field_errors["profile.name"][0].code, "length");
+    }
+
+    #[test]
+    fn test_invalid_field_builds_single_field_error() {
+        let http = HttpError::from(ApiError::invalid_field(
+            "password_confirm",
+            "mismatch",
+            "passwords do not match",
+        ));
+        assert_eq!(http.error_type, "validation_error");
+        assert_eq!(http.message, "password_confirm: passwords do not match");
+        assert_eq!(http.field_errors["password_confirm"][0].code, "mismatch");
+    }
+
+    #[test]
+    fn test_coded_error_keeps_status_and_adds_code() {
+        let api = ApiError::from(crate::services::user::UserError::EmailConflict);
+        assert_eq!(api.to_string(), "Conflict: Email already registered");
+        let http = HttpError::from(api);
+        assert_eq!(http.status.as_u16(), 409);
+        assert_eq!(http.error_type, "conflict");
+        assert_eq!(http.code.as_deref(), Some("email_taken"));
+        assert_eq!(http.to_problem_json()["code"], "email_taken");
+    }
+
+    #[test]
+    fn test_internal_error_detail_is_hidden_when_coded() {
+        let http = HttpError::from(ApiError::Internal("db down".into()).with_code("db"));
+        assert_eq!(http.message, "An unexpected error occurred");
+    }
+
     // ── WechatError → ApiError mapping ────────────────────────────────────
 
     /// All captcha-validation errors share the same generic message to prevent
@@ -1,35 +1,96 @@
-use tracing::Level;
-use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+//! Logging setup (`[logging]`).
+//!
+//! Every entry of `logging.outputs` becomes its own fmt layer: stdout or a rotated file, in
+//! `text` or `json` format, receiving all events, only the access log (target
+//! [`ACCESS_LOG_TARGET`], one `request completed` event per request) or everything else.
+//!
+//! JSON lines have stable top-level keys — `timestamp` (RFC 3339, UTC), `level`, `target`,
+//! `message`, then the event fields — and the fields of the enclosing spans merged under
+//! `span` (innermost wins):
+//!
+//! ```text
+//! {"timestamp":"2026-01-01T00:00:00.000Z","level":"INFO","target":"access","message":"request completed","status":200,"latency_ms":3,"span":{"method":"GET","request_id":"9f0c…","route":"/api/users/{id}"}}
+//! ```
+//!
+//! `otel.*` fields only steer the OpenTelemetry layer and are left out of both formats.
+//!
+//! The global level filter can be replaced at runtime through
+//! [`LogLevelService`](crate::services::log_level::LogLevelService).
+
+use std::fmt::{self as stdfmt, Debug};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use rolling_file::{RollingConditionBasic, RollingFileAppender};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{
+    EnvFilter, Layer, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt,
+};
+use webshelf_runtime::ACCESS_LOG_TARGET;
+
+use crate::services::log_level::LogLevelService;
+use crate::utils::config::{
+    LogFormat, LogOutput, LogOutputKind, LogRotation, LogStream, LoggingConfig, TelemetryConfig,
+};
+use crate::utils::telemetry;
+
+type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync>;
+
+/// Keeps the background writers of file outputs alive; dropping it flushes them.
+#[must_use = "dropping the guard stops file logging"]
+pub struct LogGuard {
+    _workers: Vec<WorkerGuard>,
+}
 
 /// Initialize the tracing subscriber for structured logging
 ///
 /// # Arguments
-/// * `log_level` - Log level string (trace, debug, info, warn, error)
-pub fn init_logger(log_level: &str) {
-    let level = match log_level.to_lowercase().as_str() {
-        "trace" => Level::TRACE,
-        "debug" => Level::DEBUG,
-        "info" => Level::INFO,
-        "warn" => Level::WARN,
-        "error" => Level::ERROR,
-        _ => Level::INFO,
+/// * `logging` - `[logging]` section; `RUST_LOG` overrides its `level` and `levels`
+/// * `telemetry` - `[telemetry]` section; adds the OpenTelemetry export layer when enabled
+pub fn init_logger(logging: &LoggingConfig, telemetry: &TelemetryConfig) -> Result<LogGuard> {
+    let level = parse_level(&logging.level);
+    let (filter, default_directives) = match EnvFilter::try_from_default_env() {
+        Ok(filter) => {
+            let directives = filter.to_string();
+            (filter, directives)
+        }
+        Err(_) => {
+            let directives = directives(level, &logging.levels);
+            let filter =
+                EnvFilter::try_new(&directives).context("Invalid logging.levels directive")?;
+            (filter, directives)
+        }
     };
+    // 过滤器可在运行时替换（/api/admin/log-level）
+    let (filter, filter_handle) = reload::Layer::new(filter);
 
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        // 默认抑制 sqlx 逐查询级别日志（如每 10 秒的心跳 UPDATE）
-        // 如需查看可设置 RUST_LOG=sqlx::query=info 或 RUST_LOG=debug
-        EnvFilter::new(format!("{},sqlx::query=warn", level))
-    });
+    let mut workers = Vec::new();
+    let mut outputs: Vec<BoxedLayer<_>> = Vec::with_capacity(logging.outputs.len());
+    for output in &logging.outputs {
+        let (writer, ansi) = match output.kind {
+            LogOutputKind::Stdout => (BoxMakeWriter::new(std::io::stdout), true),
+            LogOutputKind::File => {
+                let (writer, worker) = tracing_appender::non_blocking(rolling_file(output)?);
+                workers.push(worker);
+                (BoxMakeWriter::new(writer), false)
+            }
+        };
+        outputs.push(output_layer(output, logging.format, writer, ansi));
+    }
 
     if let Err(e) = tracing_subscriber::registry()
         .with(filter)
-        .with(
-            fmt::layer()
-                .with_target(true)
-                .with_thread_ids(true)
-                .with_file(true)
-                .with_line_number(true),
-        )
+        .with(outputs)
+        .with(telemetry::layer(telemetry)?)
         .try_init()
     {
         eprintln!(
@@ -38,111 +99,456 @@ pub fn init_logger(log_level: &str) {
         );
     }
 
+    LogLevelService::install(LogLevelService::new(filter_handle, default_directives));
+
     tracing::info!("Logger initialized with level: {}", level);
+    for output in logging
+        .outputs
+        .iter()
+        .filter(|o| o.kind == LogOutputKind::File)
+    {
+        tracing::info!(path = %output.path, stream = ?output.stream, "Logging to file");
+    }
+    if telemetry.enabled {
+        tracing::info!(
+            endpoint = %telemetry.endpoint,
+            sampler = %telemetry.sampler,
+            "OpenTelemetry trace export enabled"
+        );
+    }
+    Ok(LogGuard { _workers: workers })
+}
+
+/// Parse `logging.level`; unknown values fall back to INFO.
+fn parse_level(level: &str) -> Level {
+    match level.to_lowercase().as_str() {
+        "trace" => Level::TRACE,
+        "debug" => Level::DEBUG,
+        "info" => Level::INFO,
+        "warn" => Level::WARN,
+        "error" => Level::ERROR,
+        _ => Level::INFO,
+    }
+}
+
+fn directives(level: Level, levels: &[String]) -> String {
+    std::iter::once(level.to_string())
+        .chain(levels.iter().map(|d| d.trim().to_string()))
+        .filter(|d| !d.is_empty())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn accepts(stream: LogStream, target: &str) -> bool {
+    match stream {
+        LogStream::All => true,
+        LogStream::App => target != ACCESS_LOG_TARGET,
+        LogStream::Access => target == ACCESS_LOG_TARGET,
+    }
+}
+
+/// One fmt layer per output. Spans always pass the stream filter so access lines keep the
+/// request span fields (`request_id`, `route`, …).
+fn output_layer<S>(
+    output: &LogOutput,
+    default_format: LogFormat,
+    writer: BoxMakeWriter,
+    ansi: bool,
+) -> BoxedLayer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let stream = output.stream;
+    let stream_filter = filter_fn(move |meta| meta.is_span() || accepts(stream, meta.target()));
+    match output.format.unwrap_or(default_format) {
+        LogFormat::Text => fmt::layer()
+            .fmt_fields(LogFields)
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_file(true)
+            .with_line_number(true)
+            .with_ansi(ansi)
+            .with_writer(writer)
+            .with_filter(stream_filter)
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .fmt_fields(JsonFields)
+            .event_format(JsonFormat)
+            .with_writer(writer)
+            .with_filter(stream_filter)
+            .boxed(),
+    }
+}
+
+/// Rotated log file: on the configured schedule and/or once `max_size_mb` is exceeded,
+/// `app.log` becomes `app.log.1` (older files shift up, beyond `max_files` are deleted).
+fn rolling_file(output: &LogOutput) -> Result<RollingFileAppender<RollingConditionBasic>> {
+    let path = Path::new(&output.path);
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+    }
+    let mut condition = match output.rotation {
+        LogRotation::Daily => RollingConditionBasic::new().daily(),
+        LogRotation::Hourly => RollingConditionBasic::new().hourly(),
+        LogRotation::Never => RollingConditionBasic::new(),
+    };
+    if output.max_size_mb > 0 {
+        condition = condition.max_size(output.max_size_mb * 1024 * 1024);
+    }
+    RollingFileAppender::new(path, condition, output.max_files)
+        .with_context(|| format!("Failed to open log file {}", output.path))
+}
+
+/// Default `key=value` field formatting, minus the `otel.*` fields that only steer the
+/// OpenTelemetry layer (span name / kind) and would repeat on every log line.
+struct LogFields;
+
+impl<'w> FormatFields<'w> for LogFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'w>, fields: R) -> stdfmt::Result {
+        let mut visitor = LogFieldVisitor {
+            writer,
+            empty: true,
+            result: Ok(()),
+        };
+        fields.record(&mut visitor);
+        visitor.result
+    }
+}
+
+struct LogFieldVisitor<'w> {
+    writer: Writer<'w>,
+    empty: bool,
+    result: stdfmt::Result,
+}
+
+impl Visit for LogFieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record_debug(field, &format_args!("{value}"));
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if self.result.is_err() || field.name().starts_with("otel.") {
+            return;
+        }
+        let separator = if self.empty { "" } else { " " };
+        self.empty = false;
+        self.result = if field.name() == "message" {
+            write!(self.writer, "{separator}{value:?}")
+        } else {
+            write!(self.writer, "{separator}{}={value:?}", field.name())
+        };
+    }
+}
+
+/// Span fields stored as a JSON object, merged by [`JsonFormat`].
+struct JsonFields;
+
+impl<'w> FormatFields<'w> for JsonFields {
+    fn format_fields<R: RecordFields>(&self, mut writer: Writer<'w>, fields: R) -> stdfmt::Result {
+        let mut visitor = JsonVisitor::default();
+        fields.record(&mut visitor);
+        write!(writer, "{}", Value::Object(visitor.0))
+    }
+
+    fn add_fields(
+        &self,
+        current: &'w mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> stdfmt::Result {
+        let mut visitor = match serde_json::from_str(&current.fields) {
+            Ok(Value::Object(map)) => JsonVisitor(map),
+            _ => JsonVisitor::default(),
+        };
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.0).to_string();
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        if !field.name().starts_with("otel.") {
+            self.0.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.insert(field, value.to_string().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.insert(field, format!("{value:?}").into());
+    }
+}
+
+/// Keys written by [`JsonFormat`] itself; event fields with these names are dropped.
+const RESERVED_KEYS: [&str; 5] = ["timestamp", "level", "target", "message", "span"];
+
+/// JSON lines event format (layout in the module docs).
+struct JsonFormat;
+
+impl<S> FormatEvent<S, JsonFields> for JsonFormat
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, JsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> stdfmt::Result {
+        let meta = event.metadata();
+        let mut fields = JsonVisitor::default();
+        event.record(&mut fields);
+        let message = fields.0.remove("message").unwrap_or_default();
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+        // 手工拼接，保证固定字段在前（serde_json::Map 按 key 排序）
+        write!(
+            writer,
+            "{{\"timestamp\":{},\"level\":{},\"target\":{},\"message\":{}",
+            Value::from(timestamp),
+            Value::from(meta.level().as_str()),
+            Value::from(meta.target()),
+            message,
+        )?;
+        for (key, value) in &fields.0 {
+            if !RESERVED_KEYS.contains(&key.as_str()) {
+                write!(writer, ",{}:{}", Value::from(key.as_str()), value)?;
+            }
+        }
+
+        let mut span = Map::new();
+        for scope in ctx.event_scope().into_iter().flat_map(|s| s.from_root()) {
+            let extensions = scope.extensions();
+            if let Some(stored) = extensions.get::<FormattedFields<JsonFields>>()
+                && let Ok(Value::Object(map)) = serde_json::from_str(&stored.fields)
+            {
+                span.extend(map);
+            }
+        }
+        if !span.is_empty() {
+            write!(writer, ",\"span\":{}", Value::Object(span))?;
+        }
+        writeln!(writer, "}}")
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Buffer {
+        fn writer(&self) -> BoxMakeWriter {
+            let buf = self.clone();
+            BoxMakeWriter::new(move || buf.clone())
+        }
+
+        fn lines(&self) -> Vec<String> {
+            let text = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            text.lines().map(str::to_string).collect()
+        }
+    }
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn output(stream: LogStream, format: LogFormat) -> LogOutput {
+        LogOutput {
+            stream,
+            format: Some(format),
+            ..LogOutput::default()
+        }
+    }
 
     #[test]
     fn test_log_level_parsing() {
-        // Test valid log levels
-        let level = match "trace".to_lowercase().as_str() {
-            "trace" => Level::TRACE,
-            "debug" => Level::DEBUG,
-            "info" => Level::INFO,
-            "warn" => Level::WARN,
-            "error" => Level::ERROR,
-            _ => Level::INFO,
-        };
-        assert_eq!(level, Level::TRACE);
-
-        let level = match "debug".to_lowercase().as_str() {
-            "trace" => Level::TRACE,
-            "debug" => Level::DEBUG,
-            "info" => Level::INFO,
-            "warn" => Level::WARN,
-            "error" => Level::ERROR,
-            _ => Level::INFO,
-        };
-        assert_eq!(level, Level::DEBUG);
-
-        let level = match "info".to_lowercase().as_str() {
-            "trace" => Level::TRACE,
-            "debug" => Level::DEBUG,
-            "info" => Level::INFO,
-            "warn" => Level::WARN,
-            "error" => Level::ERROR,
-            _ => Level::INFO,
-        };
-        assert_eq!(level, Level::INFO);
-
-        let level = match "warn".to_lowercase().as_str() {
-            "trace" => Level::TRACE,
-            "debug" => Level::DEBUG,
-            "info" => Level::INFO,
-            "warn" => Level::WARN,
-            "error" => Level::ERROR,
-            _ => Level::INFO,
-        };
-        assert_eq!(level, Level::WARN);
-
-        let level = match "error".to_lowercase().as_str() {
-            "trace" => Level::TRACE,
-            "debug" => Level::DEBUG,
-            "info" => Level::INFO,
-            "warn" => Level::WARN,
-            "error" => Level::ERROR,
-            _ => Level::INFO,
-        };
-        assert_eq!(level, Level::ERROR);
+        assert_eq!(parse_level("trace"), Level::TRACE);
+        assert_eq!(parse_level("debug"), Level::DEBUG);
+        assert_eq!(parse_level("info"), Level::INFO);
+        assert_eq!(parse_level("warn"), Level::WARN);
+        assert_eq!(parse_level("error"), Level::ERROR);
     }
 
     #[test]
     fn test_log_level_case_insensitive() {
-        let level = match "TRACE".to_lowercase().as_str() {
-            "trace" => Level::TRACE,
-            "debug" => Level::DEBUG,
-            "info" => Level::INFO,
-            "warn" => Level::WARN,
-            "error" => Level::ERROR,
-            _ => Level::INFO,
-        };
-        assert_eq!(level, Level::TRACE);
-
-        let level = match "InFo".to_lowercase().as_str() {
-            "trace" => Level::TRACE,
-            "debug" => Level::DEBUG,
-            "info" => Level::INFO,
-            "warn" => Level::WARN,
-            "error" => Level::ERROR,
-            _ => Level::INFO,
-        };
-        assert_eq!(level, Level::INFO);
+        assert_eq!(parse_level("TRACE"), Level::TRACE);
+        assert_eq!(parse_level("InFo"), Level::INFO);
     }
 
     #[test]
     fn test_log_level_default() {
         // Test invalid/unknown log level defaults to INFO
-        let level = match "invalid".to_lowercase().as_str() {
-            "trace" => Level::TRACE,
-            "debug" => Level::DEBUG,
-            "info" => Level::INFO,
-            "warn" => Level::WARN,
-            "error" => Level::ERROR,
-            _ => Level::INFO,
-        };
-        assert_eq!(level, Level::INFO);
-
-        let level = match "".to_lowercase().as_str() {
-            "trace" => Level::TRACE,
-            "debug" => Level::DEBUG,
-            "info" => Level::INFO,
-            "warn" => Level::WARN,
-            "error" => Level::ERROR,
-            _ => Level::INFO,
+        assert_eq!(parse_level("invalid"), Level::INFO);
+        assert_eq!(parse_level(""), Level::INFO);
+    }
+
+    #[test]
+    fn module_levels_follow_the_default_level() {
+        let levels = vec!["sqlx::query=warn".to_string(), " ".to_string()];
+        assert_eq!(directives(Level::DEBUG, &levels), "DEBUG,sqlx::query=warn");
+        assert!(EnvFilter::try_new(directives(Level::INFO, &levels)).is_ok());
+    }
+
+    #[test]
+    fn otel_fields_are_left_out_of_log_lines() {
+        let buf = Buffer::default();
+        let layer = fmt::layer()
+            .fmt_fields(LogFields)
+            .without_time()
+            .with_ansi(false)
+            .with_writer(buf.writer());
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            let span = tracing::info_span!("request", otel.name = "GET /x", method = "GET");
+            let _entered = span.enter();
+            tracing::info!(otel.kind = "server", status = 200, "done");
+        });
+
+        let line = buf.lines().concat();
+        assert!(line.contains("request{method=\"GET\"}"), "{line}");
+        assert!(line.contains("done status=200"), "{line}");
+        assert!(!line.contains("otel"), "{line}");
+    }
+
+    #[test]
+    fn json_lines_have_stable_keys_and_span_fields() {
+        let buf = Buffer::default();
+        let layer = output_layer(
+            &output(LogStream::All, LogFormat::Json),
+            LogFormat::Text,
+            buf.writer(),
+            false,
+        );
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            let span = tracing::info_span!(
+                "request",
+                method = "GET",
+                route = tracing::field::Empty,
+                otel.name = "GET"
+            );
+            let _entered = span.enter();
+            span.record("route", "/api/users/{id}");
+            let inner = tracing::info_span!("db.query", db.replica = 1);
+            let _inner = inner.enter();
+            tracing::info!(target: ACCESS_LOG_TARGET, status = 200, level = "x", "request completed");
+        });
+
+        let lines = buf.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(
+            lines[0].starts_with("{\"timestamp\":"),
+            "fixed keys come first: {}",
+            lines[0]
+        );
+        let line: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "access");
+        assert_eq!(line["message"], "request completed");
+        assert_eq!(line["status"], 200);
+        assert_eq!(line["span"]["method"], "GET");
+        assert_eq!(line["span"]["route"], "/api/users/{id}");
+        assert_eq!(line["span"]["db.replica"], 1);
+        assert!(line["span"].get("otel.name").is_none());
+    }
+
+    #[test]
+    fn access_and_app_streams_are_split() {
+        let (access, app) = (Buffer::default(), Buffer::default());
+        let subscriber = tracing_subscriber::registry()
+            .with(output_layer(
+                &output(LogStream::Access, LogFormat::Json),
+                LogFormat::Text,
+                access.writer(),
+                false,
+            ))
+            .with(output_layer(
+                &output(LogStream::App, LogFormat::Json),
+                LogFormat::Text,
+                app.writer(),
+                false,
+            ));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", request_id = "abc");
+            let _entered = span.enter();
+            tracing::warn!("cache unavailable");
+            tracing::info!(target: ACCESS_LOG_TARGET, status = 200, "request completed");
+        });
+
+        let (access, app) = (access.lines(), app.lines());
+        assert_eq!(access.len(), 1);
+        assert!(access[0].contains("\"request completed\""));
+        assert!(access[0].contains("\"request_id\":\"abc\""));
+        assert_eq!(app.len(), 1);
+        assert!(app[0].contains("\"cache unavailable\""));
+    }
+
+    #[test]
+    fn file_output_rotates_by_size() {
+        let dir = std::env::temp_dir().join(format!("webshelf-logs-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("nested/app.log");
+        let output = LogOutput {
+            kind: LogOutputKind::File,
+            path: path.to_string_lossy().into_owned(),
+            rotation: LogRotation::Never,
+            max_size_mb: 1,
+            max_files: 2,
+            ..LogOutput::default()
         };
-        assert_eq!(level, Level::INFO);
+
+        let mut file = rolling_file(&output).unwrap();
+        let chunk = vec![b'x'; 512 * 1024];
+        for _ in 0..5 {
+            std::io::Write::write_all(&mut file, &chunk).unwrap();
+        }
+        std::io::Write::flush(&mut file).unwrap();
+        drop(file);
+
+        assert!(path.exists());
+        assert!(path.with_extension("log.1").exists());
+        assert!(path.with_extension("log.2").exists());
+        assert!(!path.with_extension("log.3").exists());
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,146 @@
+//! Application metrics exported on `/metrics` (see [`webshelf_runtime::metrics`]).
+//!
+//! HTTP and rate-limit metrics are recorded by the runtime middleware; this module holds the
+//! server-side families — database routing, cache, distributed locks, outgoing email,
+//! scheduled jobs and the job queue.
+
+use std::sync::LazyLock;
+
+use webshelf_runtime::metrics::{self, CounterVec};
+
+static DB_STATEMENTS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "db_statements_total",
+        "Statements routed by AutoRouter, by target (write / read)",
+        &["target"],
+    )
+});
+
+static DB_READ_FALLBACKS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "db_read_fallbacks_total",
+        "Reads sent to the writer after every replica failed (database_routing.fallback_to_write)",
+        &[],
+    )
+});
+
+static DB_CIRCUIT_BREAKS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "db_replica_circuit_breaks_total",
+        "Read replicas marked down by the circuit breaker, by index in database_read_urls",
+        &["replica"],
+    )
+});
+
+static CACHE_LOOKUPS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "cache_lookups_total",
+        "CacheService lookups by result (hit / miss)",
+        &["result"],
+    )
+});
+
+static CACHE_NEGATIVE_HITS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "cache_negative_hits_total",
+        "Cache misses answered by a negative-cache marker (key:null)",
+        &[],
+    )
+});
+
+static LOCK_ACQUISITIONS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "lock_acquisitions_total",
+        "Distributed lock acquisitions by result (acquired / contended = gave up after retries)",
+        &["result"],
+    )
+});
+
+static LOCK_RETRIES: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "lock_retries_total",
+        "Lock attempts that found the lock held by another owner",
+        &[],
+    )
+});
+
+static EMAILS_SENT: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "emails_sent_total",
+        "Outgoing emails by kind and result (success / failure)",
+        &["kind", "result"],
+    )
+});
+
+static SCHEDULED_JOB_RUNS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "scheduled_job_runs_total",
+        "Scheduled job runs on this instance by job and result (success / failure)",
+        &["job", "result"],
+    )
+});
+
+static QUEUE_JOB_RUNS: LazyLock<&CounterVec> = LazyLock::new(|| {
+    metrics::counter(
+        "queue_job_runs_total",
+        "Queue job runs on this instance by kind and outcome (succeeded / retried / dead)",
+        &["kind", "outcome"],
+    )
+});
+
+pub fn db_statement(write: bool) {
+    DB_STATEMENTS.inc(&[if write { "write" } else { "read" }]);
+}
+
+pub fn db_read_fallback() {
+    DB_READ_FALLBACKS.inc(&[]);
+}
+
+pub fn db_circuit_break(replica: usize) {
+    DB_CIRCUIT_BREAKS.inc(&[&replica.to_string()]);
+}
+
+pub fn cache_lookup(hit: bool) {
+    CACHE_LOOKUPS.inc(&[if hit { "hit" } else { "miss" }]);
+}
+
+pub fn cache_negative_hit() {
+    CACHE_NEGATIVE_HITS.inc(&[]);
+}
+
+pub fn lock_acquisition(acquired: bool) {
+    LOCK_ACQUISITIONS.inc(&[if acquired { "acquired" } else { "contended" }]);
+}
+
+pub fn lock_retry() {
+    LOCK_RETRIES.inc(&[]);
+}
+
+/// Record the outcome of one send attempt; `kind` is e.g. `welcome` or `password_reset_code`.
+pub fn email_sent<T, E>(kind: &'static str, result: &Result<T, E>) {
+    let outcome = if result.is_ok() { "success" } else { "failure" };
+    EMAILS_SENT.inc(&[kind, outcome]);
+}
+
+pub fn scheduled_job_run(job: &'static str, success: bool) {
+    SCHEDULED_JOB_RUNS.inc(&[job, if success { "success" } else { "failure" }]);
+}
+
+pub fn queue_job_run(kind: &str, outcome: &'static str) {
+    QUEUE_JOB_RUNS.inc(&[kind, outcome]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn families_are_rendered() {
+        email_sent::<(), ()>("test_kind", &Err(()));
+        db_circuit_break(7);
+
+        let text = metrics::render();
+        assert!(text.contains("emails_sent_total{kind=\"test_kind\",result=\"failure\"} 1\n"));
+        assert!(text.contains("db_replica_circuit_breaks_total{replica=\"7\"} 1\n"));
+    }
+}
@@ -3,8 +3,10 @@ pub mod db_router;
 pub mod error;
 pub mod jwt;
 pub mod logger;
+pub mod metrics;
 pub mod password;
 pub mod snowflake;
+pub mod telemetry;
 pub mod validator;
 
 pub use config::{AppConfig, load_config};
@@ -168,11 +168,13 @@ pub struct WorkerRecord {
 /// Worker 生命周期句柄。
 ///
 /// 持有此句柄时，后台任务会定期发送心跳。
-/// 句柄被 `Drop` 时自动注销 worker 并停止心跳。
+/// 句柄被 `Drop` 时自动注销 worker 并停止心跳（异步进行，不等待完成）；
+/// 优雅关闭时应调用 [`WorkerHandle::shutdown`] 等待注销写入数据库。
 #[derive(Debug)]
 pub struct WorkerHandle {
     worker_id: i16,
     stop_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    task: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl Drop for WorkerHandle {
@@ -187,6 +189,16 @@ impl WorkerHandle {
     pub fn worker_id(&self) -> i16 {
         self.worker_id
     }
+
+    /// 停止心跳并等待 worker 注销完成。
+    pub async fn shutdown(mut self) {
+        if let Some(tx) = self.stop_tx.take() {
+            let _ = tx.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
 }
 
 /// 在数据库中注册一个新的 Snowflake worker。
@@ -308,7 +320,8 @@ async fn deregister_worker(db: &DatabaseConnection, worker_id: i16) -> Result<()
     Ok(())
 }
 
-fn hostname() -> String {
+/// Host name of this instance (`HOSTNAME`, else `HOST`), also used by the job scheduler.
+pub(crate) fn hostname() -> String {
     std::env::var("HOSTNAME")
         .or_else(|_| std::env::var("HOST"))
         .unwrap_or_else(|_| "unknown".to_string())
@@ -357,6 +370,7 @@ pub async fn init(db: &DatabaseConnection) -> Result<WorkerHandle, SnowflakeErro
         return Ok(WorkerHandle {
             worker_id: 0,
             stop_tx: None,
+            task: None,
         });
     }
 
@@ -378,6 +392,7 @@ pub async fn init(db: &DatabaseConnection) -> Result<WorkerHandle, SnowflakeErro
         return Ok(WorkerHandle {
             worker_id: 0,
             stop_tx: None,
+            task: None,
         });
     }
 
@@ -391,7 +406,7 @@ pub async fn init(db: &DatabaseConnection) -> Result<WorkerHandle, SnowflakeErro
     let db_clone = db.clone();
     let worker_id = record.worker_id;
 
-    tokio::spawn(async move {
+    let task = tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
         // 首次心跳立即执行
         interval.tick().await;
@@ -417,9 +432,15 @@ pub async fn init(db: &DatabaseConnection) -> Result<WorkerHandle, SnowflakeErro
     Ok(WorkerHandle {
         worker_id: record.worker_id,
         stop_tx: Some(stop_tx),
+        task: Some(task),
     })
 }
 
+/// 当前进程注册的 worker ID；`init()` 之前为 `None`。
+pub fn worker_id() -> Option<i16> {
+    GLOBAL_GENERATOR.get().map(|g| g.worker_id as i16)
+}
+
 /// 使用全局生成器生成一个新的 Snowflake ID。
 ///
 /// # Panics
@@ -453,6 +474,26 @@ impl SnowflakeId {
     pub fn as_i64(&self) -> i64 {
         self.0
     }
+
+    /// 生成时刻（Unix 毫秒）。
+    pub fn timestamp_ms(&self) -> i64 {
+        (self.0 >> TIMESTAMP_SHIFT) + CUSTOM_EPOCH as i64
+    }
+
+    /// 生成时刻。
+    pub fn created_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::from_timestamp_millis(self.timestamp_ms())
+    }
+
+    /// 生成该 ID 的 worker。
+    pub fn worker_id(&self) -> u16 {
+        ((self.0 as u64 >> WORKER_ID_SHIFT) & MAX_WORKER_ID) as u16
+    }
+
+    /// 同一毫秒内的序列号。
+    pub fn sequence(&self) -> u16 {
+        (self.0 as u64 & MAX_SEQUENCE) as u16
+    }
 }
 
 impl From<i64> for SnowflakeId {
@@ -524,6 +565,21 @@ impl<'de> Deserialize<'de> for SnowflakeId {
     }
 }
 
+/// OpenAPI schema：对外表现为十进制数字字符串（与 `Serialize` 一致）。
+impl schemars::JsonSchema for SnowflakeId {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "SnowflakeId".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "pattern": "^-?[0-9]+$",
+            "description": "Snowflake ID serialized as a string to avoid JavaScript precision loss",
+        })
+    }
+}
+
 // ──────────────────────────────────────────────
 //  测试
 // ──────────────────────────────────────────────
@@ -562,6 +618,22 @@ mod tests {
         assert_ne!(id0, id1);
     }
 
+    #[test]
+    fn test_snowflake_id_decode() {
+        let ts = CUSTOM_EPOCH as i64 + 86_400_000;
+        let id = SnowflakeId::new(compose_id(ts, 513, 4095));
+        assert_eq!(id.timestamp_ms(), ts);
+        assert_eq!(id.worker_id(), 513);
+        assert_eq!(id.sequence(), 4095);
+        assert_eq!(
+            id.created_at().unwrap().to_rfc3339(),
+            "2024-01-02T00:00:00+00:00"
+        );
+
+        let generated = SnowflakeId::new(SnowflakeGenerator::new(7).generate());
+        assert_eq!(generated.worker_id(), 7);
+    }
+
     #[test]
     fn test_snowflake_id_display() {
         let id = SnowflakeId::new(12345);
@@ -0,0 +1,310 @@
+//! OpenTelemetry trace export (`[telemetry]`).
+//!
+//! When enabled, [`layer`] builds an OTLP/HTTP exporter behind a batch span processor and
+//! installs the W3C trace-context propagator, so that:
+//!
+//! - incoming `traceparent` headers become the parent of the request span
+//!   (`webshelf_runtime::request_id`);
+//! - outgoing `wechat-api` calls carry `traceparent`;
+//! - `AutoRouter` / `write_conn()` (`db.query`) and `CacheService` (`cache.*`) spans are
+//!   exported as children of the request span.
+//!
+//! The provider lives until [`shutdown`] flushes it (registered as the last stop hook).
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider, Tracer};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::{Filtered, LevelFilter, Targets};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::utils::config::TelemetryConfig;
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// The OpenTelemetry layer, without the HTTP client / exporter internals (their own spans
+/// would otherwise be exported on every batch).
+pub type OtelLayer<S> = Filtered<OpenTelemetryLayer<S, Tracer>, Targets, S>;
+
+/// Parse `sampler` / `sampler_ratio` (names as in `OTEL_TRACES_SAMPLER`).
+pub fn sampler(config: &TelemetryConfig) -> Result<Sampler> {
+    let ratio = config.sampler_ratio;
+    let sampler = match config.sampler.as_str() {
+        "always_on" => Sampler::AlwaysOn,
+        "always_off" => Sampler::AlwaysOff,
+        "traceidratio" => Sampler::TraceIdRatioBased(ratio),
+        "parentbased_always_on" => Sampler::ParentBased(Box::new(Sampler::AlwaysOn)),
+        "parentbased_always_off" => Sampler::ParentBased(Box::new(Sampler::AlwaysOff)),
+        "parentbased_traceidratio" => {
+            Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio)))
+        }
+        other => bail!("Unknown telemetry.sampler '{other}'"),
+    };
+    Ok(sampler)
+}
+
+/// Build a tracer provider exporting to `config.endpoint`.
+pub fn build_provider(config: &TelemetryConfig) -> Result<SdkTracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(&config.endpoint)
+        .with_timeout(Duration::from_millis(config.timeout_ms))
+        .build()
+        .context("Failed to build OTLP span exporter")?;
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(sampler(config)?)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build())
+}
+
+/// Wrap `provider` in a tracing layer.
+pub fn otel_layer<S>(provider: &SdkTracerProvider) -> OtelLayer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter_internals = Targets::new()
+        .with_default(LevelFilter::TRACE)
+        .with_target("hyper", LevelFilter::OFF)
+        .with_target("hyper_util", LevelFilter::OFF)
+        .with_target("h2", LevelFilter::OFF)
+        .with_target("reqwest", LevelFilter::OFF)
+        .with_target("opentelemetry", LevelFilter::OFF)
+        .with_target("opentelemetry_sdk", LevelFilter::OFF)
+        .with_target("opentelemetry_otlp", LevelFilter::OFF);
+    tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer("webshelf-server"))
+        .with_filter(exporter_internals)
+}
+
+/// Set up trace export from `[telemetry]`; `None` when disabled.
+pub fn layer<S>(config: &TelemetryConfig) -> Result<Option<OtelLayer<S>>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    if !config.enabled {
+        return Ok(None);
+    }
+    let provider = build_provider(config)?;
+    let layer = otel_layer(&provider);
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    if PROVIDER.set(provider).is_err() {
+        bail!("Telemetry is already initialized");
+    }
+    Ok(Some(layer))
+}
+
+/// Flush pending spans and stop the exporter. No-op when telemetry is disabled.
+pub async fn shutdown() {
+    let Some(provider) = PROVIDER.get() else {
+        return;
+    };
+    let provider = provider.clone();
+    // 批处理器的 shutdown 会阻塞等待后台线程导出完毕
+    match tokio::task::spawn_blocking(move || provider.shutdown()).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("Trace exporter shutdown failed: {}", e),
+        Err(e) => tracing::warn!("Trace exporter shutdown task failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use opentelemetry::propagation::TextMapPropagator;
+    use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+    use opentelemetry_proto::tonic::common::v1::any_value::Value;
+    use opentelemetry_proto::tonic::trace::v1::Span;
+    use prost::Message;
+    use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tracing::Instrument;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::services::CacheService;
+    use crate::utils::db_router::{self, AutoRouter};
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const REMOTE_SPAN_ID: &str = "00f067aa0ba902b7";
+
+    /// In-process OTLP/HTTP collector: decodes each export request and answers 200.
+    async fn collector_stub() -> (String, mpsc::UnboundedReceiver<ExportTraceServiceRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1/traces", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 8192];
+                    let (header_end, len) = loop {
+                        let n = stream.read(&mut chunk).await.unwrap();
+                        if n == 0 {
+                            return;
+                        }
+                        buf.extend_from_slice(&chunk[..n]);
+                        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                            let headers = String::from_utf8_lossy(&buf[..pos]).to_lowercase();
+                            let len = headers
+                                .lines()
+                                .find_map(|l| l.strip_prefix("content-length:"))
+                                .map_or(0, |v| v.trim().parse().unwrap());
+                            break (pos + 4, len);
+                        }
+                    };
+                    while buf.len() < header_end + len {
+                        let n = stream.read(&mut chunk).await.unwrap();
+                        buf.extend_from_slice(&chunk[..n]);
+                    }
+                    let request =
+                        ExportTraceServiceRequest::decode(&buf[header_end..header_end + len])
+                            .unwrap();
+                    stream
+                        .write_all(
+                            b"HTTP/1.1 200 OK\r\ncontent-type: application/x-protobuf\r\n\
+                              content-length: 0\r\nconnection: close\r\n\r\n",
+                        )
+                        .await
+                        .unwrap();
+                    let _ = tx.send(request);
+                });
+            }
+        });
+        (endpoint, rx)
+    }
+
+    fn attribute<'a>(span: &'a Span, key: &str) -> Option<&'a Value> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key == key)
+            .and_then(|kv| kv.value.as_ref()?.value.as_ref())
+    }
+
+    #[tokio::test]
+    async fn spans_are_exported_under_the_incoming_trace() {
+        let (endpoint, mut rx) = collector_stub().await;
+        let config = TelemetryConfig {
+            enabled: true,
+            endpoint,
+            service_name: "webshelf-test".into(),
+            ..TelemetryConfig::default()
+        };
+        let provider = build_provider(&config).unwrap();
+        let subscriber = tracing_subscriber::registry().with(otel_layer(&provider));
+
+        let carrier = HashMap::from([(
+            "traceparent".to_string(),
+            format!("00-{TRACE_ID}-{REMOTE_SPAN_ID}-01"),
+        )]);
+        let remote = TraceContextPropagator::new().extract(&carrier);
+        let cache = CacheService::new("", 1).await;
+        let stmt = Statement::from_string(DbBackend::Postgres, "SELECT 1");
+        let db = AutoRouter::single(DatabaseConnection::Disconnected);
+        {
+            let _default = tracing::subscriber::set_default(subscriber);
+            let request = tracing::info_span!(
+                "request",
+                otel.name = "GET /api/users/{id}",
+                otel.kind = "server"
+            );
+            let _ = request.set_parent(remote);
+            async {
+                cache.get::<String>("user:1").await.unwrap();
+                let query = db_router::query_span(&stmt, false);
+                query.record("db.replica", 2);
+                drop(query);
+                // 绕过路由直接走写库的语句同样有 span（未连接，语句本身失败）
+                let update =
+                    Statement::from_string(DbBackend::Postgres, "UPDATE users SET name = 'x'");
+                assert!(db.write_conn().execute(update).await.is_err());
+            }
+            .instrument(request)
+            .await;
+        }
+        tokio::task::spawn_blocking(move || provider.force_flush())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let export = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let resource = export.resource_spans[0].resource.as_ref().unwrap();
+        assert!(resource.attributes.iter().any(|kv| kv.key == "service.name"
+            && kv.value.as_ref().unwrap().value
+                == Some(Value::StringValue("webshelf-test".into()))));
+
+        let spans: Vec<&Span> = export
+            .resource_spans
+            .iter()
+            .flat_map(|rs| &rs.scope_spans)
+            .flat_map(|ss| &ss.spans)
+            .collect();
+        let by_name = |name: &str| *spans.iter().find(|s| s.name == name).unwrap();
+        let request = by_name("GET /api/users/{id}");
+        let cache_get = by_name("cache.get");
+        let db_query = |target: &str| {
+            *spans
+                .iter()
+                .find(|s| {
+                    s.name == "db.query"
+                        && attribute(s, "db.target") == Some(&Value::StringValue(target.into()))
+                })
+                .unwrap()
+        };
+        let query = db_query("read");
+        let write = db_query("write");
+
+        assert!(spans.iter().all(|s| hex(&s.trace_id) == TRACE_ID));
+        assert_eq!(hex(&request.parent_span_id), REMOTE_SPAN_ID);
+        assert_eq!(cache_get.parent_span_id, request.span_id);
+        assert_eq!(query.parent_span_id, request.span_id);
+        assert_eq!(attribute(query, "db.replica"), Some(&Value::IntValue(2)));
+        assert_eq!(write.parent_span_id, request.span_id);
+        assert_eq!(
+            attribute(write, "db.query.text"),
+            Some(&Value::StringValue("UPDATE users SET name = 'x'".into()))
+        );
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn sampler_names_follow_otel_env_conventions() {
+        let config = |name: &str| TelemetryConfig {
+            sampler: name.into(),
+            ..TelemetryConfig::default()
+        };
+        assert!(matches!(
+            sampler(&config("traceidratio")),
+            Ok(Sampler::TraceIdRatioBased(_))
+        ));
+        assert!(matches!(
+            sampler(&TelemetryConfig::default()),
+            Ok(Sampler::ParentBased(_))
+        ));
+        assert!(sampler(&config("sometimes")).is_err());
+    }
+}
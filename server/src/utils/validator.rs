@@ -32,8 +32,9 @@ pub fn require_password(password: &str) -> Result<(), String> {
 ///
 /// Convenience wrapper around `require_password` that directly returns
 /// `ApiError::Validation`, eliminating repeated error-mapping in handlers.
-pub fn check_password_strength(password: &str) -> Result<(), ApiError> {
-    require_password(password).map_err(ApiError::Validation)
+/// The error is reported on `field` with code `weak_password`.
+pub fn check_password_strength(field: &str, password: &str) -> Result<(), ApiError> {
+    require_password(password).map_err(|msg| ApiError::invalid_field(field, "weak_password", msg))
 }
 
 #[cfg(test)]
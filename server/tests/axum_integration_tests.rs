@@ -29,6 +29,13 @@ async fn create_test_app() -> Router {
 
 /// Create test app and return both Router and AppState for direct cache inspection.
 async fn create_test_app_and_state() -> (Router, webshelf_server::AppState) {
+    create_test_app_with_config(|_| {}).await
+}
+
+/// Create test app with adjusted configuration (e.g. test-only providers).
+async fn create_test_app_with_config(
+    configure: impl FnOnce(&mut webshelf_server::utils::AppConfig),
+) -> (Router, webshelf_server::AppState) {
     use distributed_ratelimit::{RateLimitConfig, RedisRateLimiter};
     use sea_orm::Database;
     use webshelf_server::AutoRouter;
@@ -36,11 +43,12 @@ async fn create_test_app_and_state() -> (Router, webshelf_server::AppState) {
     use webshelf_server::utils::load_config;
     use webshelf_server::{
         AppState,
-        routes::{api_routes, auth_routes},
+        routes::{api_routes, auth_routes, health_routes, oauth_routes},
     };
 
     // Load test configuration
-    let config = load_config("config.toml", "development").expect("Failed to load config");
+    let mut config = load_config("config.toml", "development").expect("Failed to load config");
+    configure(&mut config);
 
     // Connect to test database
     let db = Database::connect(&config.database_url)
@@ -49,12 +57,12 @@ async fn create_test_app_and_state() -> (Router, webshelf_server::AppState) {
     let db = AutoRouter::single(db);
 
     // Run migrations
-    webshelf_server::migrations::run_migrations(db.write_conn())
+    webshelf_server::migrations::run_migrations(db.write_conn().inner())
         .await
         .expect("Failed to run migrations");
 
     // Initialize Snowflake ID generator (idempotent — subsequent calls are no-ops)
-    webshelf_server::snowflake::init(db.write_conn())
+    webshelf_server::snowflake::init(db.write_conn().inner())
         .await
         .expect("Failed to initialize Snowflake generator");
 
@@ -67,6 +75,7 @@ async fn create_test_app_and_state() -> (Router, webshelf_server::AppState) {
         config: Arc::new(config),
         email: emailserver::EmailService::new(emailserver::EmailConfig::default()),
         wechat: None,
+        shutdown: Default::default(),
     };
 
     // Configure CORS
@@ -95,6 +104,11 @@ async fn create_test_app_and_state() -> (Router, webshelf_server::AppState) {
             "/api/public/auth",
             auth_routes(RedisRateLimiter::disabled(RateLimitConfig::default())),
         )
+        .nest(
+            "/api/public/oauth",
+            oauth_routes(RedisRateLimiter::disabled(RateLimitConfig::default())),
+        )
+        .merge(health_routes())
         .layer(from_fn(webshelf_server::middlewares::panic_middleware))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
@@ -303,6 +317,75 @@ async fn test_health_check() {
     assert!(body["version"].is_string());
 }
 
+#[tokio::test]
+async fn test_liveness_check() {
+    let app = create_test_app().await;
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/livez")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_to_json(response.into_body()).await["status"], "alive");
+}
+
+#[tokio::test]
+async fn test_admin_health_requires_authentication() {
+    let app = create_test_app().await;
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/admin/health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_readiness_fails_once_shutdown_starts() {
+    let (app, state) = create_test_app_and_state().await;
+    let readyz = || {
+        Request::builder()
+            .uri("/readyz")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(readyz()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_to_json(response.into_body()).await;
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["checks"]["database"], "up");
+    assert_eq!(body["checks"]["snowflake"], "up");
+
+    state.shutdown.trigger();
+    let response = app.clone().oneshot(readyz()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        body_to_json(response.into_body()).await["status"],
+        "draining"
+    );
+
+    // 摘流期间仍处理请求
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
 #[tokio::test]
 async fn test_user_registration() {
     let app = create_test_app().await;
@@ -357,9 +440,14 @@ async fn test_registration_password_confirm_mismatch() {
         .unwrap();
 
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
 
     let body = body_to_json(response.into_body()).await;
-    assert_eq!(body["error"], "bad_request");
+    assert_eq!(body["error"], "validation_error");
+    assert_eq!(body["errors"]["password_confirm"][0]["code"], "mismatch");
 }
 
 #[tokio::test]
@@ -3109,3 +3197,1924 @@ async fn test_count_cache_invalidated_after_create_system_role() {
 
     let _ = state.cache.invalidate(&count_key).await;
 }
+
+#[tokio::test]
+async fn test_admin_jobs_list_and_manual_run() {
+    use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+    use webshelf_server::repositories::refresh_token::{ActiveModel, Column, Entity};
+
+    let (app, state) = create_test_app_and_state().await;
+    let admin_email = unique_email("jobs_admin");
+    let token = create_admin_and_login(&app, &admin_email).await;
+    let user = webshelf_server::repositories::UserEntity::find()
+        .filter(webshelf_server::repositories::UserColumn::Email.eq(&admin_email))
+        .one(state.db.write_conn())
+        .await
+        .unwrap()
+        .unwrap();
+
+    // 一个已过期的刷新令牌，由 cleanup_refresh_tokens 删除
+    let expired_hash = format!("expired-{}", unique_email("jobs"));
+    ActiveModel {
+        user_id: Set(user.id),
+        token_hash: Set(expired_hash.clone()),
+        expires_at: Set(chrono::Utc::now() - chrono::Duration::hours(1)),
+        created_at: Set(chrono::Utc::now() - chrono::Duration::days(1)),
+        ..Default::default()
+    }
+    .insert(state.db.write_conn())
+    .await
+    .unwrap();
+
+    let request = |method: &str, uri: &str, token: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/admin/jobs", &token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_to_json(response.into_body()).await;
+    let names: Vec<&str> = body["jobs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|job| job["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"cleanup_refresh_tokens"));
+    assert!(names.contains(&"cleanup_expired_codes"));
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/api/admin/jobs/cleanup_refresh_tokens/run",
+            &token,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = body_to_json(response.into_body()).await;
+    assert_eq!(body["name"], "cleanup_refresh_tokens");
+
+    // 任务在后台执行，轮询直到完成
+    let mut job = serde_json::Value::Null;
+    for _ in 0..50 {
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/api/admin/jobs", &token))
+            .await
+            .unwrap();
+        let body = body_to_json(response.into_body()).await;
+        job = body["jobs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|job| job["name"] == "cleanup_refresh_tokens")
+            .unwrap()
+            .clone();
+        if job["status"] != "running" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(job["status"], "succeeded", "{job}");
+    assert!(job["next_run_at"].is_string());
+    assert!(job["last_run_by"].is_string());
+    let remaining = Entity::find()
+        .filter(Column::TokenHash.eq(&expired_hash))
+        .one(state.db.write_conn())
+        .await
+        .unwrap();
+    assert!(remaining.is_none());
+
+    let response = app
+        .clone()
+        .oneshot(request("POST", "/api/admin/jobs/no_such_job/run", &token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let user_token = register_and_login(&app, &unique_email("jobs_user")).await;
+    let response = app
+        .oneshot(request("GET", "/api/admin/jobs", &user_token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_job_queue_retries_dead_letters_and_admin_retry() {
+    use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
+    use webshelf_server::repositories::{QueuedJobEntity, UserColumn, UserEntity};
+    use webshelf_server::services::JobQueue;
+    use webshelf_server::services::queue::enqueue;
+    use webshelf_server::services::verification::{VerificationCodeEmail, WelcomeEmail};
+
+    let (app, state) = create_test_app_and_state().await;
+    let db = state.db.write_conn();
+    let queue = JobQueue::new(state.clone());
+    let admin_email = unique_email("queue_admin");
+    let token = create_admin_and_login(&app, &admin_email).await;
+
+    // 多个 worker 并发认领：SKIP LOCKED 保证每个任务只执行一次
+    let mut ids = Vec::new();
+    for _ in 0..6 {
+        // 用户不存在，任务直接成功
+        ids.push(enqueue(db, &WelcomeEmail { user_id: -1 }).await.unwrap());
+    }
+    let (a, b) = tokio::join!(queue.run_due(), queue.run_due());
+    a.unwrap();
+    b.unwrap();
+    while queue.run_due().await.unwrap() > 0 {}
+    for id in &ids {
+        let job = QueuedJobEntity::find_by_id(*id)
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(job.status, "succeeded", "job {id}");
+        assert_eq!(job.attempts, 1, "job {id}");
+        assert!(job.finished_at.is_some());
+    }
+
+    // 未验证用户 + 未配置 SMTP：发送失败，退避后重试，次数耗尽进入 dead
+    let user_email = unique_email("queue_user");
+    register_and_login(&app, &user_email).await;
+    let user = UserEntity::find()
+        .filter(UserColumn::Email.eq(&user_email))
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    db.execute_unprepared(&format!(
+        "UPDATE users SET email_verified = FALSE WHERE id = {}",
+        user.id
+    ))
+    .await
+    .unwrap();
+    let id = enqueue(db, &VerificationCodeEmail { user_id: user.id })
+        .await
+        .unwrap();
+    db.execute_unprepared(&format!(
+        "UPDATE job_queue SET max_attempts = 2 WHERE id = {id}"
+    ))
+    .await
+    .unwrap();
+
+    let job_row = || async {
+        QueuedJobEntity::find_by_id(id)
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+    };
+    while job_row().await.attempts == 0 && queue.run_due().await.unwrap() > 0 {}
+    let job = job_row().await;
+    assert_eq!(job.status, "queued");
+    assert_eq!(job.attempts, 1);
+    assert!(job.run_at > chrono::Utc::now());
+    assert!(
+        job.last_error
+            .as_deref()
+            .unwrap()
+            .contains("verification email")
+    );
+    // 任务执行时才生成验证码
+    let user = UserEntity::find_by_id(user.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(user.verification_code_hash.is_some());
+
+    db.execute_unprepared(&format!(
+        "UPDATE job_queue SET run_at = NOW() WHERE id = {id}"
+    ))
+    .await
+    .unwrap();
+    while job_row().await.attempts == 1 && queue.run_due().await.unwrap() > 0 {}
+    let job = job_row().await;
+    assert_eq!(job.status, "dead");
+    assert_eq!(job.attempts, 2);
+
+    let request = |method: &str, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/api/admin/queue?status=dead&kind=email.verification_code&per_page=100",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_to_json(response.into_body()).await;
+    let listed = body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|job| job["id"] == id)
+        .expect("dead job listed")
+        .clone();
+    assert_eq!(listed["payload"]["user_id"], user.id);
+    assert!(listed["last_error"].is_string());
+
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/admin/queue?status=bogus"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(request("POST", &format!("/api/admin/queue/{id}/retry")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = body_to_json(response.into_body()).await;
+    assert_eq!(body["status"], "queued");
+    assert_eq!(body["attempts"], 0);
+
+    // 只有 dead 任务可以重试
+    let response = app
+        .clone()
+        .oneshot(request("POST", &format!("/api/admin/queue/{id}/retry")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            &format!("/api/admin/queue/{}", id + 1_000_000),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // 清理，避免后续运行再次认领
+    db.execute_unprepared(&format!("DELETE FROM job_queue WHERE id = {id}"))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_two_factor_enroll_login_recovery_and_admin_reset() {
+    use totp_rs::{Algorithm, Secret, TOTP};
+
+    let app = create_test_app().await;
+    let email = unique_email("two_factor");
+    let token = register_and_login(&app, &email).await;
+    let admin_token = create_admin_and_login(&app, &unique_email("two_factor_admin")).await;
+
+    let send = |method: &str, uri: &str, bearer: Option<&str>, payload: serde_json::Value| {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        if let Some(bearer) = bearer {
+            builder = builder.header("authorization", format!("Bearer {}", bearer));
+        }
+        let request = builder
+            .body(Body::from(serde_json::to_string(&payload).unwrap()))
+            .unwrap();
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let has_cookie = response.headers().contains_key("set-cookie");
+            (status, has_cookie, body_to_json(response.into_body()).await)
+        }
+    };
+    let login_payload = json!({ "email": email, "password": "Password123!" });
+
+    // 密码错误不能开始绑定；400 而非 401，避免前端误登出
+    let (status, _, body) = send(
+        "POST",
+        "/api/users/me/2fa/enroll",
+        Some(&token),
+        json!({ "password": "Wrong123!" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_password");
+
+    let (status, _, enrollment) = send(
+        "POST",
+        "/api/users/me/2fa/enroll",
+        Some(&token),
+        json!({ "password": "Password123!" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        enrollment["otpauth_uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("otpauth://totp/")
+    );
+    let secret = Secret::Encoded(enrollment["secret"].as_str().unwrap().to_string())
+        .to_bytes()
+        .unwrap();
+    let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret, None, String::new()).unwrap();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    // 未确认的绑定不影响登录
+    let (status, _, _) = send(
+        "POST",
+        "/api/public/auth/login",
+        None,
+        login_payload.clone(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, body) = send(
+        "POST",
+        "/api/users/me/2fa/confirm",
+        Some(&token),
+        json!({ "code": totp.generate(now) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let recovery_codes: Vec<String> = body["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(recovery_codes.len(), 10);
+
+    let (status, _, body) = send("GET", "/api/users/me/2fa", Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["enabled"], true);
+    assert_eq!(body["recovery_codes_remaining"], 10);
+
+    // 密码步骤只返回 challenge，不签发会话
+    let (status, has_cookie, body) = send(
+        "POST",
+        "/api/public/auth/login",
+        None,
+        login_payload.clone(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(!has_cookie);
+    assert_eq!(body["two_factor_required"], true);
+    assert!(body.get("token").is_none());
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+
+    // 确认时用过的码不能重放
+    let (status, _, body) = send(
+        "POST",
+        "/api/public/auth/login/2fa",
+        None,
+        json!({ "challenge_token": challenge, "code": totp.generate(now) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_two_factor_code");
+
+    // 下一个时间步的码在允许的时钟偏差内
+    let code = totp.generate(now + 30);
+    let (status, has_cookie, body) = send(
+        "POST",
+        "/api/public/auth/login/2fa",
+        None,
+        json!({ "challenge_token": challenge, "code": code }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(has_cookie);
+    assert!(body["token"].is_string());
+
+    // challenge 只能换取一次会话
+    let (status, _, body) = send(
+        "POST",
+        "/api/public/auth/login/2fa",
+        None,
+        json!({ "challenge_token": challenge, "code": recovery_codes[0] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "invalid_challenge");
+
+    // 恢复码可代替 TOTP，且只能使用一次
+    for expected in [StatusCode::OK, StatusCode::BAD_REQUEST] {
+        let (status, _, body) = send(
+            "POST",
+            "/api/public/auth/login",
+            None,
+            login_payload.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let (status, _, _) = send(
+            "POST",
+            "/api/public/auth/login/2fa",
+            None,
+            json!({
+                "challenge_token": body["challenge_token"],
+                "code": recovery_codes[0].to_lowercase(),
+            }),
+        )
+        .await;
+        assert_eq!(status, expected);
+    }
+
+    let (_, _, body) = send("GET", "/api/users/me/2fa", Some(&token), json!({})).await;
+    assert_eq!(body["recovery_codes_remaining"], 9);
+
+    // 管理员重置后恢复单步登录
+    let user_id = body_to_json(
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/users/me")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .into_body(),
+    )
+    .await["id"]
+        .clone();
+    let reset_uri = format!("/api/users/{}/2fa", user_id.as_str().unwrap());
+
+    let (status, _, _) = send("DELETE", &reset_uri, Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _, body) = send("DELETE", &reset_uri, Some(&admin_token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["removed"], true);
+
+    let (status, _, body) = send("POST", "/api/public/auth/login", None, login_payload).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].is_string());
+}
+
+/// Software WebAuthn authenticator (ES256, `none` attestation, discoverable credential).
+struct SoftAuthenticator {
+    rng: ring::rand::SystemRandom,
+    key: ring::signature::EcdsaKeyPair,
+    credential_id: Vec<u8>,
+    user_handle: Vec<u8>,
+    sign_count: u32,
+}
+
+impl SoftAuthenticator {
+    const ORIGIN: &'static str = "http://localhost:8080";
+
+    fn new() -> Self {
+        use ring::signature::{ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair};
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+        let credential_id = unique_email("credential").into_bytes();
+        Self {
+            rng,
+            key,
+            credential_id,
+            user_handle: Vec::new(),
+            sign_count: 0,
+        }
+    }
+
+    fn b64(bytes: &[u8]) -> String {
+        use base64::Engine;
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    fn unb64(value: &serde_json::Value) -> Vec<u8> {
+        use base64::Engine;
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(value.as_str().unwrap())
+            .unwrap()
+    }
+
+    fn client_data(kind: &str, options: &serde_json::Value, origin: &str) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "type": kind,
+            "challenge": options["challenge"],
+            "origin": origin,
+            "crossOrigin": false,
+        }))
+        .unwrap()
+    }
+
+    /// rpIdHash || flags || signCount (|| attested credential data)
+    fn authenticator_data(&self, rp_id: &str, flags: u8, attested: bool) -> Vec<u8> {
+        use ring::signature::KeyPair;
+        use sha2::Digest;
+        let mut data = sha2::Sha256::digest(rp_id.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend_from_slice(&self.sign_count.to_be_bytes());
+        if attested {
+            use ciborium::Value;
+            let point = self.key.public_key().as_ref();
+            let cose = Value::Map(vec![
+                (Value::from(1), Value::from(2)),
+                (Value::from(3), Value::from(-7)),
+                (Value::from(-1), Value::from(1)),
+                (Value::from(-2), Value::Bytes(point[1..33].to_vec())),
+                (Value::from(-3), Value::Bytes(point[33..].to_vec())),
+            ]);
+            data.extend_from_slice(&[0u8; 16]);
+            data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            data.extend_from_slice(&self.credential_id);
+            ciborium::ser::into_writer(&cose, &mut data).unwrap();
+        }
+        data
+    }
+
+    /// `navigator.credentials.create()` + `toJSON()`
+    fn create(&mut self, options: &serde_json::Value, origin: &str) -> serde_json::Value {
+        use ciborium::Value;
+        self.user_handle = Self::unb64(&options["user"]["id"]);
+        let auth_data = self.authenticator_data(options["rp"]["id"].as_str().unwrap(), 0x45, true);
+        let attestation = Value::Map(vec![
+            (Value::from("fmt"), Value::from("none")),
+            (Value::from("attStmt"), Value::Map(Vec::new())),
+            (Value::from("authData"), Value::Bytes(auth_data)),
+        ]);
+        let mut attestation_object = Vec::new();
+        ciborium::ser::into_writer(&attestation, &mut attestation_object).unwrap();
+        json!({
+            "id": Self::b64(&self.credential_id),
+            "rawId": Self::b64(&self.credential_id),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": Self::b64(&Self::client_data("webauthn.create", options, origin)),
+                "attestationObject": Self::b64(&attestation_object),
+                "transports": ["internal"],
+            },
+        })
+    }
+
+    /// `navigator.credentials.get()` + `toJSON()`; every assertion bumps the counter.
+    fn get(
+        &mut self,
+        options: &serde_json::Value,
+        origin: &str,
+        user_verified: bool,
+    ) -> serde_json::Value {
+        use sha2::Digest;
+        self.sign_count += 1;
+        let flags = if user_verified { 0x05 } else { 0x01 };
+        let auth_data = self.authenticator_data(options["rpId"].as_str().unwrap(), flags, false);
+        let client_data = Self::client_data("webauthn.get", options, origin);
+        let mut message = auth_data.clone();
+        message.extend_from_slice(&sha2::Sha256::digest(&client_data));
+        let signature = self.key.sign(&self.rng, &message).unwrap();
+        json!({
+            "id": Self::b64(&self.credential_id),
+            "rawId": Self::b64(&self.credential_id),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": Self::b64(&client_data),
+                "authenticatorData": Self::b64(&auth_data),
+                "signature": Self::b64(signature.as_ref()),
+                "userHandle": Self::b64(&self.user_handle),
+            },
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_passkey_registration_login_and_revocation() {
+    let app = create_test_app().await;
+    let email = unique_email("passkey");
+    let token = register_and_login(&app, &email).await;
+
+    let send = |method: &str, uri: &str, bearer: Option<&str>, payload: serde_json::Value| {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        if let Some(bearer) = bearer {
+            builder = builder.header("authorization", format!("Bearer {}", bearer));
+        }
+        let request = builder
+            .body(Body::from(serde_json::to_string(&payload).unwrap()))
+            .unwrap();
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let has_cookie = response.headers().contains_key("set-cookie");
+            (status, has_cookie, body_to_json(response.into_body()).await)
+        }
+    };
+    let mut authenticator = SoftAuthenticator::new();
+    let origin = SoftAuthenticator::ORIGIN;
+
+    let (status, _, body) = send(
+        "POST",
+        "/api/users/me/passkeys/options",
+        Some(&token),
+        json!({ "password": "Wrong123!" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_password");
+
+    let (status, _, options) = send(
+        "POST",
+        "/api/users/me/passkeys/options",
+        Some(&token),
+        json!({ "password": "Password123!" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(options["rp"]["id"], "localhost");
+    assert_eq!(options["user"]["name"], email.as_str());
+    assert_eq!(options["authenticatorSelection"]["residentKey"], "required");
+
+    let credential = authenticator.create(&options, origin);
+    let (status, _, passkey) = send(
+        "POST",
+        "/api/users/me/passkeys",
+        Some(&token),
+        json!({ "name": "Laptop", "credential": credential }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{passkey}");
+    assert_eq!(passkey["name"], "Laptop");
+    assert_eq!(passkey["transports"], json!(["internal"]));
+
+    // 注册挑战只能使用一次
+    let (status, _, body) = send(
+        "POST",
+        "/api/users/me/passkeys",
+        Some(&token),
+        json!({ "credential": credential }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_passkey_challenge");
+
+    let (status, _, body) = send("GET", "/api/users/me/passkeys", Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["passkeys"].as_array().unwrap().len(), 1);
+    assert!(body["passkeys"][0]["last_used_at"].is_null());
+
+    // 无密码登录
+    let (status, _, options) =
+        send("POST", "/api/public/auth/passkey/options", None, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(options["allowCredentials"], json!([]));
+    let assertion = authenticator.get(&options, origin, true);
+    let (status, has_cookie, body) = send(
+        "POST",
+        "/api/public/auth/passkey/login",
+        None,
+        json!({ "credential": assertion }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(has_cookie);
+    let (status, _, me) = send(
+        "GET",
+        "/api/users/me",
+        Some(body["token"].as_str().unwrap()),
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["email"], email.as_str());
+
+    // 重放同一断言：挑战已被消费
+    let (status, _, body) = send(
+        "POST",
+        "/api/public/auth/passkey/login",
+        None,
+        json!({ "credential": assertion }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_passkey_challenge");
+
+    // 计数器回退（克隆的认证器）与错误的 origin 都被拒绝
+    for (rewind, origin) in [(true, origin), (false, "https://evil.example.com")] {
+        let (_, _, options) =
+            send("POST", "/api/public/auth/passkey/options", None, json!({})).await;
+        if rewind {
+            authenticator.sign_count -= 1;
+        }
+        let assertion = authenticator.get(&options, origin, true);
+        let (status, _, body) = send(
+            "POST",
+            "/api/public/auth/passkey/login",
+            None,
+            json!({ "credential": assertion }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "passkey_verification_failed");
+    }
+
+    let (_, _, body) = send("GET", "/api/users/me/passkeys", Some(&token), json!({})).await;
+    assert!(body["passkeys"][0]["last_used_at"].is_string());
+    let uri = format!("/api/users/me/passkeys/{}", passkey["id"]);
+    let (status, _, _) = send("DELETE", &uri, Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) = send("DELETE", &uri, Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, _, options) = send("POST", "/api/public/auth/passkey/options", None, json!({})).await;
+    let assertion = authenticator.get(&options, origin, true);
+    let (status, _, body) = send(
+        "POST",
+        "/api/public/auth/passkey/login",
+        None,
+        json!({ "credential": assertion }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "unknown_passkey");
+}
+
+/// In-process OpenID Connect provider: discovery, JWKS and a token endpoint that redeems
+/// codes registered by the test, checking PKCE and the client secret.
+mod mock_oidc {
+    use base64::Engine;
+    use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+    use ring::rand::SystemRandom;
+    use ring::signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair};
+    use salvo::prelude::*;
+    use sha2::{Digest, Sha256};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    pub const CLIENT_ID: &str = "webshelf-test";
+    pub const CLIENT_SECRET: &str = "mock-secret";
+
+    pub struct Provider {
+        pub issuer: String,
+        pkcs8: Vec<u8>,
+        public_key: Vec<u8>,
+        /// code → (code_challenge, ID token claims)
+        codes: Mutex<HashMap<String, (String, serde_json::Value)>>,
+    }
+
+    impl Provider {
+        /// Let the token endpoint redeem `code` once for an ID token with `claims`.
+        pub fn issue_code(&self, code: &str, code_challenge: &str, claims: serde_json::Value) {
+            self.codes
+                .lock()
+                .unwrap()
+                .insert(code.to_string(), (code_challenge.to_string(), claims));
+        }
+
+        fn sign(&self, mut claims: serde_json::Value) -> String {
+            let now = chrono::Utc::now().timestamp();
+            let defaults = serde_json::json!({
+                "iss": self.issuer,
+                "aud": CLIENT_ID,
+                "iat": now,
+                "exp": now + 300,
+            });
+            for (key, value) in defaults.as_object().unwrap() {
+                claims
+                    .as_object_mut()
+                    .unwrap()
+                    .entry(key)
+                    .or_insert(value.clone());
+            }
+            let mut header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::ES256);
+            header.kid = Some("mock-key".to_string());
+            jsonwebtoken::encode(
+                &header,
+                &claims,
+                &jsonwebtoken::EncodingKey::from_ec_der(&self.pkcs8),
+            )
+            .unwrap()
+        }
+    }
+
+    fn provider(depot: &Depot) -> Arc<Provider> {
+        depot.obtain::<Arc<Provider>>().unwrap().clone()
+    }
+
+    #[handler]
+    async fn discovery(depot: &mut Depot, res: &mut Response) {
+        let provider = provider(depot);
+        res.render(Json(serde_json::json!({
+            "issuer": provider.issuer,
+            "authorization_endpoint": format!("{}/authorize", provider.issuer),
+            "token_endpoint": format!("{}/token", provider.issuer),
+            "jwks_uri": format!("{}/jwks", provider.issuer),
+            "token_endpoint_auth_methods_supported": ["client_secret_basic"],
+        })));
+    }
+
+    #[handler]
+    async fn jwks(depot: &mut Depot, res: &mut Response) {
+        let point = &provider(depot).public_key;
+        res.render(Json(serde_json::json!({
+            "keys": [{
+                "kty": "EC",
+                "crv": "P-256",
+                "kid": "mock-key",
+                "use": "sig",
+                "alg": "ES256",
+                "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+                "y": URL_SAFE_NO_PAD.encode(&point[33..]),
+            }]
+        })));
+    }
+
+    #[handler]
+    async fn token(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+        let provider = provider(depot);
+        let expected_auth = format!(
+            "Basic {}",
+            STANDARD.encode(format!("{CLIENT_ID}:{CLIENT_SECRET}"))
+        );
+        let authorized =
+            req.header::<String>("authorization").as_deref() == Some(expected_auth.as_str());
+        let code = req.form::<String>("code").await.unwrap_or_default();
+        let verifier = req
+            .form::<String>("code_verifier")
+            .await
+            .unwrap_or_default();
+        let grant_type = req.form::<String>("grant_type").await.unwrap_or_default();
+
+        let issued = provider.codes.lock().unwrap().remove(&code);
+        let valid = match &issued {
+            Some((challenge, _)) => {
+                URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) == *challenge
+            }
+            None => false,
+        };
+        if !authorized || grant_type != "authorization_code" || !valid {
+            res.status_code(StatusCode::BAD_REQUEST);
+            res.render(Json(serde_json::json!({ "error": "invalid_grant" })));
+            return;
+        }
+        let (_, claims) = issued.unwrap();
+        res.render(Json(serde_json::json!({
+            "access_token": "mock-access-token",
+            "token_type": "Bearer",
+            "id_token": provider.sign(claims),
+        })));
+    }
+
+    /// Start the provider on a random port.
+    pub async fn start() -> Arc<Provider> {
+        let addr = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+        let provider = Arc::new(Provider {
+            issuer: format!("http://{addr}"),
+            pkcs8: pkcs8.as_ref().to_vec(),
+            public_key: pair.public_key().as_ref().to_vec(),
+            codes: Mutex::new(HashMap::new()),
+        });
+
+        let router = Router::new()
+            .hoop(affix_state::inject(provider.clone()))
+            .push(Router::with_path(".well-known/openid-configuration").get(discovery))
+            .push(Router::with_path("jwks").get(jwks))
+            .push(Router::with_path("token").post(token));
+        let acceptor = TcpListener::new(addr).bind().await;
+        tokio::spawn(Server::new(acceptor).serve(router));
+        provider
+    }
+}
+
+#[tokio::test]
+async fn test_oidc_login_signup_linking_and_unlinking() {
+    use sea_orm::ConnectionTrait;
+    use webshelf_server::utils::config::OidcProviderConfig;
+
+    let provider = mock_oidc::start().await;
+    let issuer = provider.issuer.clone();
+    let (app, state) = create_test_app_with_config(move |config| {
+        config.oidc.public_url = "http://app.test".to_string();
+        config.oidc.success_redirect = "http://app.test/dashboard".to_string();
+        config.oidc.login_redirect = "http://app.test/".to_string();
+        config.oidc.providers.insert(
+            "mock".to_string(),
+            OidcProviderConfig {
+                display_name: Some("Mock ID".to_string()),
+                issuer,
+                client_id: mock_oidc::CLIENT_ID.to_string(),
+                client_secret: mock_oidc::CLIENT_SECRET.to_string(),
+                scopes: vec!["openid".to_string(), "email".to_string()],
+                enabled: true,
+                allow_signup: true,
+                link_by_email: true,
+                trust_email: false,
+            },
+        );
+    })
+    .await;
+
+    let get = |uri: String, cookie: Option<String>| {
+        let mut builder = Request::builder().method("GET").uri(uri);
+        if let Some(cookie) = cookie {
+            builder = builder.header("cookie", cookie);
+        }
+        let request = builder.body(Body::empty()).unwrap();
+        let app = app.clone();
+        async move { app.oneshot(request).await.unwrap() }
+    };
+    let cookie = |response: &webshelf_axum::Response, name: &str| -> Option<String> {
+        response
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .find(|value| value.starts_with(&format!("{name}=")))
+            .map(|value| value.split(';').next().unwrap().to_string())
+    };
+    let location = |response: &webshelf_axum::Response| -> String {
+        response.headers()["location"].to_str().unwrap().to_string()
+    };
+
+    // 开始登录：返回 (state, nonce, code_challenge, 流程 cookie)
+    let authorize = || async {
+        let response = get(
+            "/api/public/auth/oidc/mock/authorize?remember=true".to_string(),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::FOUND);
+        let url = url::Url::parse(&location(&response)).unwrap();
+        assert_eq!(url.path(), "/authorize");
+        let params: std::collections::HashMap<String, String> =
+            url.query_pairs().into_owned().collect();
+        assert_eq!(params["client_id"], mock_oidc::CLIENT_ID);
+        assert_eq!(
+            params["redirect_uri"],
+            "http://app.test/api/public/auth/oidc/mock/callback"
+        );
+        assert_eq!(params["scope"], "openid email");
+        assert_eq!(params["code_challenge_method"], "S256");
+        let flow = cookie(&response, "webshelf_oidc").unwrap();
+        (
+            params["state"].clone(),
+            params["nonce"].clone(),
+            params["code_challenge"].clone(),
+            flow,
+        )
+    };
+    let callback = |code: &str, state: &str, flow: Option<String>| {
+        get(
+            format!("/api/public/auth/oidc/mock/callback?code={code}&state={state}"),
+            flow,
+        )
+    };
+    let me = |jwt_cookie: String| async {
+        let response = get("/api/users/me".to_string(), Some(jwt_cookie)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        body_to_json(response.into_body()).await
+    };
+
+    let response = get("/api/public/auth/oidc/providers".to_string(), None).await;
+    let body = body_to_json(response.into_body()).await;
+    assert_eq!(
+        body["providers"],
+        json!([{ "id": "mock", "name": "Mock ID" }])
+    );
+
+    let response = get("/api/public/auth/oidc/nope/authorize".to_string(), None).await;
+    assert_eq!(
+        location(&response),
+        "http://app.test/#oidc_error=unknown_oidc_provider"
+    );
+
+    // 首次登录：创建账号
+    let email = unique_email("oidc_signup");
+    let sub = format!("sub-{email}");
+    let (st, nonce, challenge, flow) = authorize().await;
+    provider.issue_code(
+        "code-1",
+        &challenge,
+        json!({ "sub": sub, "nonce": nonce, "email": email.to_uppercase(), "email_verified": true, "name": "Ada Lovelace" }),
+    );
+    let response = callback("code-1", &st, Some(flow.clone())).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(location(&response), "http://app.test/dashboard");
+    assert!(cookie(&response, "webshelf_refresh").is_some());
+    let cleared = cookie(&response, "webshelf_oidc").unwrap();
+    assert_eq!(cleared, "webshelf_oidc=");
+    let user = me(cookie(&response, "webshelf_jwt").unwrap()).await;
+    assert_eq!(user["email"], email.as_str());
+    assert_eq!(user["name"], "Ada Lovelace");
+
+    // 同一流程 cookie 再用一次：授权码已被兑换
+    let response = callback("code-1", &st, Some(flow)).await;
+    assert_eq!(
+        location(&response),
+        "http://app.test/#oidc_error=oidc_provider_error"
+    );
+
+    // 再次登录：同一个用户
+    let (st, nonce, challenge, flow) = authorize().await;
+    provider.issue_code(
+        "code-2",
+        &challenge,
+        json!({ "sub": sub, "nonce": nonce, "email": email }),
+    );
+    let response = callback("code-2", &st, Some(flow)).await;
+    assert_eq!(location(&response), "http://app.test/dashboard");
+    let again = me(cookie(&response, "webshelf_jwt").unwrap()).await;
+    assert_eq!(again["id"], user["id"]);
+
+    // state 不匹配、缺少 cookie、nonce 不匹配、用户拒绝
+    let (st, nonce, challenge, flow) = authorize().await;
+    provider.issue_code(
+        "code-3",
+        &challenge,
+        json!({ "sub": sub, "nonce": format!("{nonce}x") }),
+    );
+    let response = callback("code-3", "forged", Some(flow.clone())).await;
+    assert_eq!(
+        location(&response),
+        "http://app.test/#oidc_error=invalid_oidc_state"
+    );
+    let response = callback("code-3", &st, None).await;
+    assert_eq!(
+        location(&response),
+        "http://app.test/#oidc_error=invalid_oidc_state"
+    );
+    let response = callback("code-3", &st, Some(flow.clone())).await;
+    assert_eq!(
+        location(&response),
+        "http://app.test/#oidc_error=invalid_id_token"
+    );
+    let response = get(
+        format!("/api/public/auth/oidc/mock/callback?error=access_denied&state={st}"),
+        Some(flow),
+    )
+    .await;
+    assert_eq!(
+        location(&response),
+        "http://app.test/#oidc_error=oidc_denied"
+    );
+
+    // 未验证的邮箱不能注册
+    let (st, nonce, challenge, flow) = authorize().await;
+    let unverified = unique_email("oidc_unverified");
+    provider.issue_code(
+        "code-4",
+        &challenge,
+        json!({ "sub": format!("sub-{unverified}"), "nonce": nonce, "email": unverified, "email_verified": false }),
+    );
+    let response = callback("code-4", &st, Some(flow)).await;
+    assert_eq!(
+        location(&response),
+        "http://app.test/#oidc_error=oidc_email_not_verified"
+    );
+
+    // 已有本地账号：邮箱未验证时拒绝关联，验证后自动关联
+    let local_email = unique_email("oidc_link");
+    let token = register_and_login(&app, &local_email).await;
+    let set_verified = |verified: bool| {
+        let db = state.db.clone();
+        let local_email = local_email.clone();
+        async move {
+            db.write_conn()
+                .execute_unprepared(&format!(
+                    "UPDATE users SET email_verified = {verified} WHERE email = '{local_email}'"
+                ))
+                .await
+                .unwrap();
+        }
+    };
+    let link_claims = |nonce: &str| json!({ "sub": format!("sub-{local_email}"), "nonce": nonce, "email": local_email, "email_verified": true });
+    set_verified(false).await;
+    let (st, nonce, challenge, flow) = authorize().await;
+    provider.issue_code("code-5", &challenge, link_claims(&nonce));
+    let response = callback("code-5", &st, Some(flow)).await;
+    assert_eq!(
+        location(&response),
+        "http://app.test/#oidc_error=oidc_email_in_use"
+    );
+    set_verified(true).await;
+    let (st, nonce, challenge, flow) = authorize().await;
+    provider.issue_code("code-6", &challenge, link_claims(&nonce));
+    let response = callback("code-6", &st, Some(flow)).await;
+    assert_eq!(location(&response), "http://app.test/dashboard");
+    let linked = me(cookie(&response, "webshelf_jwt").unwrap()).await;
+    assert_eq!(linked["email"], local_email.as_str());
+
+    // 已关联账号的列表与解除关联
+    let send = |method: &str, uri: String| {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            (response.status(), body_to_json(response.into_body()).await)
+        }
+    };
+    let (status, body) = send("GET", "/api/users/me/identities".to_string()).await;
+    assert_eq!(status, StatusCode::OK);
+    let identities = body["identities"].as_array().unwrap();
+    assert_eq!(identities.len(), 1);
+    assert_eq!(identities[0]["provider"], "mock");
+    assert_eq!(identities[0]["provider_name"], "Mock ID");
+    assert_eq!(identities[0]["email"], local_email.as_str());
+    let uri = format!("/api/users/me/identities/{}", identities[0]["id"]);
+    let (status, _) = send("DELETE", uri.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = send("DELETE", uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_found");
+}
+
+// ──────────────────────────────────────────────
+//  OAuth 2.0 authorization server
+// ──────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_oauth_authorization_code_refresh_and_client_credentials() {
+    use base64::Engine;
+    use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+    use sha2::{Digest, Sha256};
+
+    let (app, _state) = create_test_app_with_config(|config| {
+        config.oauth.public_url = "http://app.test".to_string();
+        config.oauth.consent_url = "http://app.test/oauth/consent".to_string();
+    })
+    .await;
+
+    // 返回 (状态码, 响应头, JSON 响应体)；响应体为空时为 Null
+    let send =
+        |method: &str, uri: String, bearer: Option<&str>, body: Option<serde_json::Value>| {
+            let mut builder = Request::builder().method(method).uri(uri);
+            if let Some(token) = bearer {
+                builder = builder.header("authorization", format!("Bearer {token}"));
+            }
+            let request = match body {
+                Some(body) => builder
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+                None => builder.body(Body::empty()).unwrap(),
+            };
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let headers = response.headers().clone();
+                let bytes = response.into_body().collect().await.unwrap().to_bytes();
+                let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+                (status, headers, body)
+            }
+        };
+    // 令牌端点：表单编码，可选 Basic 认证
+    let token = |form: Vec<(&str, &str)>, basic: Option<(&str, &str)>| {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(form)
+            .finish();
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/api/public/oauth/token")
+            .header("content-type", "application/x-www-form-urlencoded");
+        if let Some((id, secret)) = basic {
+            builder = builder.header(
+                "authorization",
+                format!("Basic {}", STANDARD.encode(format!("{id}:{secret}"))),
+            );
+        }
+        let request = builder.body(Body::from(body)).unwrap();
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let headers = response.headers().clone();
+            (status, headers, body_to_json(response.into_body()).await)
+        }
+    };
+
+    // 发现文档与签名公钥
+    let (status, _, metadata) = send(
+        "GET",
+        "/api/public/oauth/.well-known/openid-configuration".to_string(),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(metadata["issuer"], "http://app.test/api/public/oauth");
+    assert_eq!(
+        metadata["token_endpoint"],
+        "http://app.test/api/public/oauth/token"
+    );
+    assert_eq!(
+        metadata["code_challenge_methods_supported"],
+        json!(["S256"])
+    );
+    let (status, _, jwks) = send("GET", "/api/public/oauth/jwks".to_string(), None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!jwks["keys"].as_array().unwrap().is_empty());
+    assert_eq!(jwks["keys"][0]["alg"], "ES256");
+
+    // 管理员登记客户端；普通用户不能登记
+    let admin_email = unique_email("oauth_admin");
+    let admin = create_admin_and_login(&app, &admin_email).await;
+    let user_email = unique_email("oauth_user");
+    let user = register_and_login(&app, &user_email).await;
+    let redirect_uri = "https://reader.example.com/callback";
+    let settings = json!({
+        "name": "Reader",
+        "redirect_uris": [redirect_uri],
+        "grant_types": ["authorization_code", "refresh_token", "client_credentials"],
+        "scopes": ["openid", "profile", "offline_access", "users:read"],
+    });
+    let (status, _, _) = send(
+        "POST",
+        "/api/admin/oauth/clients".to_string(),
+        Some(&user),
+        Some(settings.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, created) = send(
+        "POST",
+        "/api/admin/oauth/clients".to_string(),
+        Some(&admin),
+        Some(settings),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let client_id = created["client_id"].as_str().unwrap().to_string();
+    let client_secret = created["client_secret"].as_str().unwrap().to_string();
+    assert_eq!(created["confidential"], true);
+
+    // 授权请求：先跳到同意页
+    let verifier = "v".repeat(20) + &client_id;
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let authorize_uri = |redirect: &str| {
+        format!(
+            "/api/public/oauth/authorize?response_type=code&client_id={client_id}\
+             &redirect_uri={}&scope=openid%20profile%20offline_access%20users%3Aread\
+             &state=xyz&nonce=n-1&code_challenge={challenge}&code_challenge_method=S256",
+            url::form_urlencoded::byte_serialize(redirect.as_bytes()).collect::<String>()
+        )
+    };
+    let (status, _, body) = send(
+        "GET",
+        authorize_uri("https://evil.example.com/callback"),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_redirect_uri");
+
+    let authorize = || async {
+        let (status, headers, _) = send("GET", authorize_uri(redirect_uri), None, None).await;
+        assert_eq!(status, StatusCode::FOUND);
+        let location = url::Url::parse(headers["location"].to_str().unwrap()).unwrap();
+        assert_eq!(location.path(), "/oauth/consent");
+        location
+            .query_pairs()
+            .find(|(k, _)| k == "request")
+            .unwrap()
+            .1
+            .into_owned()
+    };
+    let request = authorize().await;
+
+    // 同意页：详情与同意
+    let (status, _, details) = send(
+        "POST",
+        "/api/oauth/authorize/details".to_string(),
+        Some(&user),
+        Some(json!({ "request": request })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(details["client_name"], "Reader");
+    assert_eq!(details["redirect_uri"], redirect_uri);
+    assert_eq!(
+        details["scopes"],
+        json!(["openid", "profile", "offline_access", "users:read"])
+    );
+    assert_eq!(details["consent_required"], true);
+    let (status, _, body) = send(
+        "POST",
+        "/api/oauth/authorize/decision".to_string(),
+        Some(&user),
+        Some(json!({ "request": request, "approve": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let redirect = url::Url::parse(body["redirect_to"].as_str().unwrap()).unwrap();
+    assert!(redirect.as_str().starts_with(redirect_uri));
+    let params: std::collections::HashMap<String, String> =
+        redirect.query_pairs().into_owned().collect();
+    assert_eq!(params["state"], "xyz");
+    assert_eq!(params["iss"], "http://app.test/api/public/oauth");
+    let code = params["code"].clone();
+
+    // 令牌端点：错误的密钥不消耗授权码
+    let code_form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri),
+        ("code_verifier", verifier.as_str()),
+    ];
+    let (status, headers, body) = token(code_form.clone(), Some((&client_id, "wrong"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"], "invalid_client");
+    assert!(headers.contains_key("www-authenticate"));
+    let (status, headers, tokens) =
+        token(code_form.clone(), Some((&client_id, &client_secret))).await;
+    assert_eq!(status, StatusCode::OK, "{tokens}");
+    assert_eq!(headers["cache-control"], "no-store");
+    assert_eq!(tokens["token_type"], "Bearer");
+    assert_eq!(tokens["scope"], "openid profile offline_access users:read");
+    assert!(tokens["id_token"].is_string());
+    let access_token = tokens["access_token"].as_str().unwrap().to_string();
+    let refresh_token = tokens["refresh_token"].as_str().unwrap().to_string();
+    let (status, _, body) = token(code_form, Some((&client_id, &client_secret))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "invalid_grant");
+
+    // 访问令牌：只能调用授权范围内的端点
+    let (status, _, me) = send(
+        "GET",
+        "/api/users/me".to_string(),
+        Some(&access_token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["email"], user_email.as_str());
+    let (status, _, info) = send(
+        "GET",
+        "/api/oauth/userinfo".to_string(),
+        Some(&access_token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(info["sub"], me["id"]);
+    assert_eq!(info["name"], "Test User");
+    assert!(info.get("email").is_none());
+    let (status, _, body) = send(
+        "GET",
+        "/api/users/me/passkeys".to_string(),
+        Some(&access_token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "insufficient_scope");
+    let (status, _, _) = send(
+        "POST",
+        "/api/oauth/authorize/details".to_string(),
+        Some(&access_token),
+        Some(json!({ "request": request })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 刷新令牌轮换：旧令牌不能再用
+    // 客户端密钥放在表单里（client_secret_post）
+    let refresh_form = vec![
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token.as_str()),
+        ("client_id", client_id.as_str()),
+        ("client_secret", client_secret.as_str()),
+    ];
+    let (status, _, refreshed) = token(refresh_form.clone(), None).await;
+    assert_eq!(status, StatusCode::OK, "{refreshed}");
+    assert_ne!(refreshed["refresh_token"], tokens["refresh_token"]);
+    let (status, _, body) = token(refresh_form, None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "invalid_grant");
+
+    // 已同意过的权限不再询问
+    let request = authorize().await;
+    let (_, _, details) = send(
+        "POST",
+        "/api/oauth/authorize/details".to_string(),
+        Some(&user),
+        Some(json!({ "request": request })),
+    )
+    .await;
+    assert_eq!(details["consent_required"], false);
+
+    // 客户端凭据：以所有者身份调用 API，仅限 API 权限；未开启 admin_access 时没有管理员权限
+    let (status, _, body) = token(
+        vec![("grant_type", "client_credentials"), ("scope", "openid")],
+        Some((&client_id, &client_secret)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "invalid_scope");
+    let (status, _, service) = token(
+        vec![
+            ("grant_type", "client_credentials"),
+            ("scope", "users:read"),
+        ],
+        Some((&client_id, &client_secret)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{service}");
+    assert!(service.get("refresh_token").is_none());
+    let service_token = service["access_token"].as_str().unwrap().to_string();
+    let (status, _, _) = send(
+        "GET",
+        "/api/users?page=1&per_page=5".to_string(),
+        Some(&service_token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, owner) = send(
+        "GET",
+        "/api/users/me".to_string(),
+        Some(&service_token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(owner["email"], admin_email.as_str());
+
+    // 管理员显式开启 admin_access 后，新令牌带管理员权限
+    let (status, _, updated) = send(
+        "PUT",
+        format!("/api/admin/oauth/clients/{client_id}"),
+        Some(&admin),
+        Some(json!({
+            "name": "Reader",
+            "redirect_uris": [redirect_uri],
+            "grant_types": ["authorization_code", "refresh_token", "client_credentials"],
+            "scopes": ["openid", "profile", "offline_access", "users:read"],
+            "admin_access": true,
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{updated}");
+    assert_eq!(updated["admin_access"], true);
+    let (status, _, service) = token(
+        vec![
+            ("grant_type", "client_credentials"),
+            ("scope", "users:read"),
+        ],
+        Some((&client_id, &client_secret)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{service}");
+    let service_token = service["access_token"].as_str().unwrap().to_string();
+    let (status, _, _) = send(
+        "GET",
+        "/api/users?page=1&per_page=5".to_string(),
+        Some(&service_token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, body) = send(
+        "DELETE",
+        format!("/api/users/{}", me["id"].as_str().unwrap()),
+        Some(&service_token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "insufficient_scope");
+
+    // 登出所有设备后访问令牌随会话一起失效
+    let (status, _, _) = send(
+        "POST",
+        "/api/users/me/logout-all".to_string(),
+        Some(&user),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) = send(
+        "GET",
+        "/api/users/me".to_string(),
+        refreshed["access_token"].as_str(),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 删除客户端
+    let (status, _, _) = send(
+        "DELETE",
+        format!("/api/admin/oauth/clients/{client_id}"),
+        Some(&admin),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, body) = token(
+        vec![
+            ("grant_type", "client_credentials"),
+            ("scope", "users:read"),
+        ],
+        Some((&client_id, &client_secret)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"], "invalid_client");
+}
+
+#[tokio::test]
+async fn test_oauth_admin_consent_does_not_grant_admin_role() {
+    use base64::Engine;
+    use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+    use sha2::{Digest, Sha256};
+
+    let (app, _state) = create_test_app_with_config(|config| {
+        config.oauth.public_url = "http://app.test".to_string();
+        config.oauth.consent_url = "http://app.test/oauth/consent".to_string();
+    })
+    .await;
+    let send = |method: &str, uri: String, bearer: &str, body: Option<serde_json::Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", format!("Bearer {bearer}"));
+        let request = match body {
+            Some(body) => builder
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        };
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            (status, body_to_json(response.into_body()).await)
+        }
+    };
+    let token = |form: Vec<(&str, &str)>, client_id: &str, client_secret: &str| {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(form)
+            .finish();
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/public/oauth/token")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header(
+                "authorization",
+                format!(
+                    "Basic {}",
+                    STANDARD.encode(format!("{client_id}:{client_secret}"))
+                ),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            (status, body_to_json(response.into_body()).await)
+        }
+    };
+
+    let admin = create_admin_and_login(&app, &unique_email("oauth_consent_admin")).await;
+    let user = register_and_login(&app, &unique_email("oauth_consent_user")).await;
+    let (_, me) = send("GET", "/api/users/me".to_string(), &user, None).await;
+    let user_id = me["id"].as_str().unwrap().to_string();
+
+    // 第三方客户端申请 users:write，由管理员本人同意
+    let redirect_uri = "https://admin-tool.example.com/callback";
+    let (status, created) = send(
+        "POST",
+        "/api/admin/oauth/clients".to_string(),
+        &admin,
+        Some(json!({
+            "name": "Admin tool",
+            "redirect_uris": [redirect_uri],
+            "scopes": ["offline_access", "users:read", "users:write"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{created}");
+    let client_id = created["client_id"].as_str().unwrap().to_string();
+    let client_secret = created["client_secret"].as_str().unwrap().to_string();
+
+    let verifier = "v".repeat(20) + &client_id;
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let request = Request::builder()
+        .uri(format!(
+            "/api/public/oauth/authorize?response_type=code&client_id={client_id}\
+             &redirect_uri={}&scope=offline_access%20users%3Aread%20users%3Awrite\
+             &state=s&code_challenge={challenge}&code_challenge_method=S256",
+            url::form_urlencoded::byte_serialize(redirect_uri.as_bytes()).collect::<String>()
+        ))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FOUND);
+    let location = url::Url::parse(response.headers()["location"].to_str().unwrap()).unwrap();
+    let request_token = location
+        .query_pairs()
+        .find(|(k, _)| k == "request")
+        .unwrap()
+        .1
+        .into_owned();
+    let (status, decision) = send(
+        "POST",
+        "/api/oauth/authorize/decision".to_string(),
+        &admin,
+        Some(json!({ "request": request_token, "approve": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{decision}");
+    let redirect = url::Url::parse(decision["redirect_to"].as_str().unwrap()).unwrap();
+    let code = redirect
+        .query_pairs()
+        .find(|(k, _)| k == "code")
+        .unwrap()
+        .1
+        .into_owned();
+    let (status, tokens) = token(
+        vec![
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", redirect_uri),
+            ("code_verifier", verifier.as_str()),
+        ],
+        &client_id,
+        &client_secret,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{tokens}");
+    let (status, refreshed) = token(
+        vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", tokens["refresh_token"].as_str().unwrap()),
+        ],
+        &client_id,
+        &client_secret,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{refreshed}");
+
+    // 授权码与刷新得到的令牌都不能管理用户，也不能改角色
+    for access_token in [&tokens["access_token"], &refreshed["access_token"]] {
+        let access_token = access_token.as_str().unwrap();
+        let (status, _) = send(
+            "PUT",
+            format!("/api/users/{user_id}"),
+            access_token,
+            Some(json!({ "role": "admin" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(
+            "GET",
+            "/api/users?page=1&per_page=5".to_string(),
+            access_token,
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+    let (status, target) = send("GET", format!("/api/users/{user_id}"), &admin, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(target["role"], "user");
+}
+
+#[tokio::test]
+async fn test_api_tokens_scopes_expiry_and_revocation() {
+    use sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+    let (app, state) = create_test_app_and_state().await;
+    let email = unique_email("apitoken");
+    let session = register_and_login(&app, &email).await;
+
+    let send = |method: &str, uri: String, bearer: &str, body: Option<serde_json::Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", format!("Bearer {bearer}"));
+        let request = match body {
+            Some(body) => builder
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        };
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let bytes = response.into_body().collect().await.unwrap().to_bytes();
+            let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+            (status, body)
+        }
+    };
+    let tokens_uri = || "/api/users/me/tokens".to_string();
+
+    // 未知 / 缺少权限范围、名称为空
+    for payload in [
+        json!({ "name": "CI", "scopes": ["openid"] }),
+        json!({ "name": "CI", "scopes": [] }),
+    ] {
+        let (status, body) = send("POST", tokens_uri(), &session, Some(payload)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_scope");
+    }
+    let (status, _) = send(
+        "POST",
+        tokens_uri(),
+        &session,
+        Some(json!({ "name": "", "scopes": ["users:read"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, created) = send(
+        "POST",
+        tokens_uri(),
+        &session,
+        Some(json!({ "name": "CI", "scopes": ["users:read"], "expires_in_days": 30 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{created}");
+    let pat = created["token"].as_str().unwrap().to_string();
+    assert!(pat.starts_with("wst_"));
+    assert!(pat.starts_with(created["prefix"].as_str().unwrap()));
+    assert_eq!(created["scopes"], json!(["users:read"]));
+    assert!(created["expires_at"].is_string());
+    let token_id = created["id"].as_i64().unwrap();
+
+    // 令牌可访问其权限范围内的端点
+    let (status, me) = send("GET", "/api/users/me".to_string(), &pat, None).await;
+    assert_eq!(status, StatusCode::OK, "{me}");
+    assert_eq!(me["email"], email.as_str());
+
+    // 不接受委托令牌的端点：令牌不能管理令牌或修改密码
+    let (status, body) = send("GET", tokens_uri(), &pat, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    let (status, _) = send(
+        "POST",
+        tokens_uri(),
+        &pat,
+        Some(json!({ "name": "Nested", "scopes": ["users:read"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(
+        "POST",
+        "/api/users/me/password".to_string(),
+        &pat,
+        Some(json!({
+            "current_password": "Password123!",
+            "new_password": "Password456!",
+            "new_password_confirm": "Password456!",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 列表不含密文，记录了最近使用时间
+    let (status, list) = send("GET", tokens_uri(), &session, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let tokens = list["tokens"].as_array().unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0]["name"], "CI");
+    assert!(tokens[0].get("token").is_none());
+    assert!(tokens[0]["last_used_at"].is_string());
+
+    // 过期令牌被拒绝
+    let (status, expiring) = send(
+        "POST",
+        tokens_uri(),
+        &session,
+        Some(json!({ "name": "Short", "scopes": ["users:read"], "expires_in_days": 1 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    state
+        .db
+        .write_conn()
+        .execute_unprepared(&format!(
+            "UPDATE api_tokens SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = {}",
+            expiring["id"].as_i64().unwrap()
+        ))
+        .await
+        .unwrap();
+    let (status, _) = send(
+        "GET",
+        "/api/users/me".to_string(),
+        expiring["token"].as_str().unwrap(),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 撤销后立即失效；他人的令牌 ID 视为不存在
+    let other = register_and_login(&app, &unique_email("apitoken_other")).await;
+    let (status, _) = send(
+        "DELETE",
+        format!("/api/users/me/tokens/{token_id}"),
+        &other,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, body) = send(
+        "DELETE",
+        format!("/api/users/me/tokens/{token_id}"),
+        &session,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (status, _) = send("GET", "/api/users/me".to_string(), &pat, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(
+        "DELETE",
+        format!("/api/users/me/tokens/{token_id}"),
+        &session,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 伪造的令牌
+    let (status, _) = send(
+        "GET",
+        "/api/users/me".to_string(),
+        &format!("wst_{}", "0".repeat(64)),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    // 退出所有设备会一并删除个人访问令牌
+    let (status, created) = send(
+        "POST",
+        tokens_uri(),
+        &session,
+        Some(json!({ "name": "Deploy", "scopes": ["users:read"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let deploy = created["token"].as_str().unwrap().to_string();
+    let (status, _) = send(
+        "POST",
+        "/api/users/me/logout-all".to_string(),
+        &session,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send("GET", "/api/users/me".to_string(), &deploy, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let remaining: i64 = state
+        .db
+        .write_conn()
+        .query_one(Statement::from_string(
+            DatabaseBackend::Postgres,
+            format!(
+                "SELECT COUNT(*) AS n FROM api_tokens t JOIN users u ON u.id = t.user_id \
+                 WHERE u.email = '{email}'"
+            ),
+        ))
+        .await
+        .unwrap()
+        .unwrap()
+        .try_get("", "n")
+        .unwrap();
+    assert_eq!(remaining, 0);
+}
@@ -42,11 +42,11 @@ async fn create_test_app() -> Router {
         .await
         .expect("Failed to connect to database");
     let db = AutoRouter::single(db);
-    webshelf_server::migrations::run_migrations(db.write_conn())
+    webshelf_server::migrations::run_migrations(db.write_conn().inner())
         .await
         .expect("Failed to run migrations");
 
-    webshelf_server::snowflake::init(db.write_conn())
+    webshelf_server::snowflake::init(db.write_conn().inner())
         .await
         .expect("Failed to initialize Snowflake generator");
 
@@ -58,6 +58,7 @@ async fn create_test_app() -> Router {
         config: Arc::new(config),
         email: emailserver::EmailService::new(emailserver::EmailConfig::default()),
         wechat: None,
+        shutdown: Default::default(),
     };
 
     let cors = CorsLayer::new()
@@ -0,0 +1,65 @@
+#![cfg(not(feature = "webshelf-salvo"))]
+
+//! Prometheus `/metrics` endpoint (axum runtime).
+//!
+//! NOTE: These tests require a running PostgreSQL instance.
+
+mod common;
+use common::axum::{
+    body_bytes, create_app, create_app_with_config, register_and_login, send_request,
+};
+use webshelf_axum::{Body, Method, StatusCode};
+
+/// App with the (off by default) scrape route enabled.
+async fn metrics_app() -> webshelf_axum::Router {
+    let mut config = common::load_test_config();
+    config.metrics.enabled = true;
+    create_app_with_config(config).await
+}
+
+async fn scrape(app: &webshelf_axum::Router) -> String {
+    let response = send_request(app, Method::GET, "/metrics", vec![], Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("application/openmetrics-text; version=1.0.0")
+    );
+    String::from_utf8(body_bytes(response).await.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_requests_are_labelled_by_route_pattern() {
+    let app = metrics_app().await;
+    send_request(&app, Method::GET, "/api/users/12345", vec![], Body::empty()).await;
+    send_request(&app, Method::GET, "/no/such/route", vec![], Body::empty()).await;
+
+    let text = scrape(&app).await;
+    // Rejected by the auth middleware, still labelled with the nested pattern
+    assert!(
+        text.contains(r#"http_requests_total{method="GET",route="/api/users/{id}",status="401"}"#)
+    );
+    assert!(text.contains(r#"http_requests_total{method="GET",route="unmatched",status="404"}"#));
+    assert!(text.contains(
+        r#"http_request_duration_seconds_bucket{le="+Inf",method="GET",route="/api/users/{id}"}"#
+    ));
+    assert!(!text.contains("/api/users/12345"));
+}
+
+#[tokio::test]
+async fn test_database_routing_is_counted() {
+    let app = metrics_app().await;
+    register_and_login(&app, &common::unique_email("metrics")).await;
+
+    let text = scrape(&app).await;
+    assert!(text.contains("# TYPE db_statements counter"));
+    assert!(text.contains(r#"db_statements_total{target="write"}"#));
+}
+
+#[tokio::test]
+async fn test_metrics_route_is_off_by_default() {
+    let app = create_app().await;
+    let response = send_request(&app, Method::GET, "/metrics", vec![], Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,79 @@
+#![cfg(not(feature = "webshelf-salvo"))]
+
+//! OpenAPI document serving (axum runtime).
+//!
+//! NOTE: These tests require a running PostgreSQL instance.
+
+mod common;
+use common::axum::{body_bytes, body_to_json, create_app, register_and_login, send_request};
+use webshelf_axum::{Body, Method, StatusCode};
+
+#[tokio::test]
+async fn test_openapi_document_is_served() {
+    let app = create_app().await;
+    let response = send_request(
+        &app,
+        Method::GET,
+        "/api/public/openapi.json",
+        vec![],
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = body_to_json(response).await;
+    assert_eq!(body["openapi"], "3.1.0");
+    assert_eq!(body, webshelf_server::routes::openapi_doc().to_json());
+    assert!(body["paths"]["/api/users/{id}"]["put"].is_object());
+    assert!(body["paths"]["/api/public/auth/login"]["post"].is_object());
+    assert!(body["components"]["schemas"]["UserResponse"].is_object());
+}
+
+#[tokio::test]
+async fn test_docs_ui_page_is_served() {
+    let app = create_app().await;
+    let response = send_request(&app, Method::GET, "/api/public/docs", vec![], Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    let html = String::from_utf8(body_bytes(response).await.to_vec()).unwrap();
+    assert!(html.contains(r#"data-url="/api/public/openapi.json""#));
+}
+
+#[tokio::test]
+async fn test_every_documented_operation_is_routed() {
+    let app = create_app().await;
+    let token = register_and_login(&app, &common::unique_email("openapi")).await;
+    let auth = format!("Bearer {token}");
+
+    for (method, path) in common::documented_operations() {
+        let method: Method = method.parse().unwrap();
+        let body = if method == Method::GET {
+            Body::empty()
+        } else {
+            Body::from("{}")
+        };
+        let response = send_request(
+            &app,
+            method.clone(),
+            &path,
+            vec![
+                ("authorization", auth.as_str()),
+                ("content-type", "application/json"),
+            ],
+            body,
+        )
+        .await;
+        let status = response.status().as_u16();
+        let bytes = body_bytes(response).await;
+        let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        assert!(
+            common::route_matched(status, &json),
+            "{method} {path} is documented but not routed (status {status})"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+#![cfg(not(feature = "webshelf-salvo"))]
+
+//! X-Request-Id propagation (axum runtime).
+//!
+//! NOTE: These tests require a running PostgreSQL instance.
+
+mod common;
+use common::axum::{body_to_json, create_app, send_request};
+use webshelf_axum::{Body, Method, StatusCode};
+
+#[tokio::test]
+async fn test_incoming_request_id_is_echoed() {
+    let app = create_app().await;
+    let response = send_request(
+        &app,
+        Method::GET,
+        "/api/health",
+        vec![("x-request-id", "client-abc-123")],
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], "client-abc-123");
+}
+
+#[tokio::test]
+async fn test_request_id_is_generated_when_missing() {
+    let app = create_app().await;
+    let first = send_request(&app, Method::GET, "/api/health", vec![], Body::empty()).await;
+    let second = send_request(&app, Method::GET, "/api/health", vec![], Body::empty()).await;
+    let first = first.headers()["x-request-id"].to_str().unwrap().to_owned();
+    let second = second.headers()["x-request-id"]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    assert!(!first.is_empty());
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn test_error_body_carries_request_id() {
+    let app = create_app().await;
+    let response = send_request(
+        &app,
+        Method::GET,
+        "/api/users",
+        vec![("x-request-id", "trace-401")],
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["x-request-id"], "trace-401");
+    let body = body_to_json(response).await;
+    assert_eq!(body["error"], "unauthorized");
+    assert_eq!(body["request_id"], "trace-401");
+}
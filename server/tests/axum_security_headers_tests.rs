@@ -0,0 +1,61 @@
+#![cfg(not(feature = "webshelf-salvo"))]
+
+//! Built-in security response headers (axum runtime).
+//!
+//! NOTE: These tests require a running PostgreSQL instance.
+
+mod common;
+use common::axum::{create_app, send_request};
+use webshelf_axum::{Body, Method, StatusCode};
+
+#[tokio::test]
+async fn test_security_headers_on_api_response() {
+    let app = create_app().await;
+    let response = send_request(&app, Method::GET, "/api/health", vec![], Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(
+        headers["strict-transport-security"],
+        "max-age=31536000; includeSubDomains"
+    );
+    assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+    assert_eq!(
+        headers["referrer-policy"],
+        "strict-origin-when-cross-origin"
+    );
+    assert!(headers.contains_key("permissions-policy"));
+    let csp = headers["content-security-policy"].to_str().unwrap();
+    assert!(csp.starts_with("default-src 'self'"));
+    assert!(!csp.contains("{nonce}"));
+}
+
+#[tokio::test]
+async fn test_security_headers_on_error_response() {
+    let app = create_app().await;
+    let response = send_request(&app, Method::GET, "/api/users", vec![], Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+}
+
+#[tokio::test]
+async fn test_docs_page_scripts_carry_csp_nonce() {
+    let app = create_app().await;
+    let response = send_request(&app, Method::GET, "/api/public/docs", vec![], Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let csp = response.headers()["content-security-policy"]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    let nonce = csp
+        .split("'nonce-")
+        .nth(1)
+        .and_then(|rest| rest.split('\'').next())
+        .expect("nonce in CSP")
+        .to_owned();
+    let bytes = webshelf_axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let html = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(html.contains(&format!(r#"nonce="{nonce}""#)));
+}
@@ -44,10 +44,10 @@ async fn create_test_db() -> Arc<webshelf_server::AutoRouter> {
         .await
         .expect("Failed to connect to database");
     let db = webshelf_server::AutoRouter::single(db);
-    webshelf_server::migrations::run_migrations(db.write_conn())
+    webshelf_server::migrations::run_migrations(db.write_conn().inner())
         .await
         .expect("Failed to run migrations");
-    webshelf_server::snowflake::init(db.write_conn())
+    webshelf_server::snowflake::init(db.write_conn().inner())
         .await
         .expect("Failed to initialize Snowflake generator");
     db
@@ -94,6 +94,7 @@ async fn create_wechat_state_with_len(
         config: Arc::new(app_config),
         email: emailserver::EmailService::new(emailserver::EmailConfig::default()),
         wechat,
+        shutdown: Default::default(),
     }
 }
 
@@ -174,6 +175,7 @@ async fn build_router_without_wechat() -> Router {
         config: Arc::new(config),
         email: emailserver::EmailService::new(emailserver::EmailConfig::default()),
         wechat: None,
+        shutdown: Default::default(),
     };
 
     let cors = CorsLayer::new()
@@ -8,6 +8,7 @@ use std::sync::Arc;
 use tower::ServiceExt;
 use webshelf_axum::{Body, BodyExt, Method, Request, Router, StatusCode};
 use webshelf_server::AppState;
+use webshelf_server::utils::AppConfig;
 
 use crate::common;
 
@@ -16,16 +17,26 @@ pub async fn create_app() -> Router {
     create_app_and_state().await.0
 }
 
+/// Create a test router from an adjusted copy of the test configuration.
+pub async fn create_app_with_config(config: AppConfig) -> Router {
+    create_app_and_state_with_config(config).await.0
+}
+
 /// Create test router and return both Router and AppState for direct state inspection.
+pub async fn create_app_and_state() -> (Router, AppState) {
+    create_app_and_state_with_config(common::load_test_config()).await
+}
+
+/// Create test router and state from the given configuration.
 ///
 /// Uses the production `bootstrap::axum::build_app_router()` to build the
 /// middleware chain. This ensures the test server stays in sync with the
 /// production middleware chain. A disabled rate limiter is injected so that
 /// tests do not hit per-IP rate limits (all requests originate from localhost).
-pub async fn create_app_and_state() -> (Router, AppState) {
+pub async fn create_app_and_state_with_config(config: AppConfig) -> (Router, AppState) {
     let db = common::create_test_db_and_run_migrations().await;
     let cache = common::create_cache_service().await;
-    let config = Arc::new(common::load_test_config());
+    let config = Arc::new(config);
 
     let state = AppState {
         db,
@@ -33,6 +44,7 @@ pub async fn create_app_and_state() -> (Router, AppState) {
         config,
         email: common::default_email_service(),
         wechat: None,
+        shutdown: Default::default(),
     };
 
     // 使用生产级的 build_app_router，注入禁用的 rate limiter
@@ -86,7 +98,7 @@ pub async fn body_to_json(response: webshelf_axum::Response) -> Value {
 }
 
 /// Extract body bytes from axum Response.
-pub async fn body_bytes(response: webshelf_axum::Response) -> bytes::Bytes {
+pub async fn body_bytes(response: webshelf_axum::Response) -> webshelf_axum::body::Bytes {
     response.into_body().collect().await.unwrap().to_bytes()
 }
 
@@ -37,11 +37,11 @@ pub async fn create_test_db_and_run_migrations() -> Arc<webshelf_server::AutoRou
         .expect("Failed to connect to database");
     let db = webshelf_server::AutoRouter::single(db);
 
-    webshelf_server::migrations::run_migrations(db.write_conn())
+    webshelf_server::migrations::run_migrations(db.write_conn().inner())
         .await
         .expect("Failed to run migrations");
 
-    webshelf_server::snowflake::init(db.write_conn())
+    webshelf_server::snowflake::init(db.write_conn().inner())
         .await
         .expect("Failed to initialize Snowflake generator");
 
@@ -75,3 +75,27 @@ pub fn unique_table_name(prefix: &str) -> String {
         .as_nanos();
     format!("_{}_{}", prefix, ts)
 }
+
+/// Every `(method, path)` in the OpenAPI document, with `{param}` segments filled in.
+///
+/// Session-revoking operations (`logout-all`) are moved to the end so the caller's
+/// token stays valid for the other requests.
+pub fn documented_operations() -> Vec<(String, String)> {
+    let doc = webshelf_server::routes::openapi_doc();
+    let mut ops: Vec<(String, String)> = doc
+        .operations()
+        .map(|(method, path)| (method.to_uppercase(), path.replace("{id}", "1")))
+        .collect();
+    ops.sort_by_key(|(_, path)| path.ends_with("/logout-all"));
+    ops
+}
+
+/// Whether a response proves that a route was matched: handler / middleware
+/// errors carry the unified `{"error": "<type>"}` body, router misses do not.
+pub fn route_matched(status: u16, body: &serde_json::Value) -> bool {
+    match status {
+        405 => false,
+        404 => body["error"].is_string(),
+        _ => true,
+    }
+}
@@ -7,6 +7,7 @@ use serde_json::Value;
 use std::net::TcpListener;
 use std::sync::Arc;
 use webshelf_salvo::SalvoRuntime;
+use webshelf_server::utils::AppConfig;
 use webshelf_server::{AppState, Runtime};
 
 use crate::common;
@@ -28,11 +29,16 @@ impl TestServer {
 }
 
 /// Start a test server on a random port and return a TestServer handle.
+pub async fn create_test_server() -> TestServer {
+    create_test_server_with_config(common::load_test_config()).await
+}
+
+/// Start a test server from an adjusted copy of the test configuration.
 ///
 /// Uses the production `bootstrap::salvo::build_app_router()` to build the
 /// middleware chain, then serves via `SalvoRuntime::serve()`. This ensures
 /// the test server stays in sync with the production middleware chain.
-pub async fn create_test_server() -> TestServer {
+pub async fn create_test_server_with_config(config: AppConfig) -> TestServer {
     // 获取随机可用端口后立即释放（std TcpListener 仅用于探测端口），
     // 否则 SalvoTcpListener 后续绑定同一端口会报 EADDRINUSE。
     let (addr, base_url) = {
@@ -44,7 +50,7 @@ pub async fn create_test_server() -> TestServer {
 
     let db = common::create_test_db_and_run_migrations().await;
     let cache = common::create_cache_service().await;
-    let config = Arc::new(common::load_test_config());
+    let config = Arc::new(config);
 
     let state = AppState {
         db,
@@ -52,6 +58,7 @@ pub async fn create_test_server() -> TestServer {
         config,
         email: common::default_email_service(),
         wechat: None,
+        shutdown: Default::default(),
     };
 
     // 使用生产级的 build_app_router，注入禁用的 rate limiter 以避免测试中
@@ -66,14 +73,17 @@ pub async fn create_test_server() -> TestServer {
 
     // 使用 SalvoRuntime::serve() 启动（与生产代码一致的状态注入方式）
     let _server_handle = tokio::spawn(async move {
-        SalvoRuntime::<AppState>::serve(router, state, &addr.to_string())
+        let shutdown = state.shutdown.clone();
+        SalvoRuntime::<AppState>::serve(router, state, &addr.to_string(), None, shutdown)
             .await
             .expect("Salvo test server failed");
     });
 
     // Build the reqwest client
+    // 不跟随重定向：OIDC 登录端点会跳转到外部提供方 / Web 前端
     let client = reqwest::Client::builder()
         .cookie_store(true)
+        .redirect(reqwest::redirect::Policy::none())
         .build()
         .expect("Failed to build reqwest client");
 
@@ -234,6 +244,7 @@ pub async fn create_test_state() -> webshelf_server::AppState {
         config,
         email: crate::common::default_email_service(),
         wechat: None,
+        shutdown: Default::default(),
     }
 }
 
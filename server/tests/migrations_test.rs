@@ -0,0 +1,102 @@
+//! Integration tests for the versioned migration runner.
+//!
+//! Each run creates a scratch database next to the configured one (`database_url`), so the
+//! rollback checks never touch the shared test schema.
+//!
+//! NOTE: These tests require a running PostgreSQL instance whose user may create databases.
+//! Run with: cargo test --test migrations_test
+
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, Statement};
+use webshelf_server::migrations::{self, MIGRATIONS, MigrationState};
+
+/// Create an empty database `name` on the server of `database_url` and return its URL.
+async fn scratch_database(admin: &DatabaseConnection, database_url: &str, name: &str) -> String {
+    admin
+        .execute_unprepared(&format!("DROP DATABASE IF EXISTS {name} WITH (FORCE)"))
+        .await
+        .unwrap();
+    admin
+        .execute_unprepared(&format!("CREATE DATABASE {name}"))
+        .await
+        .unwrap();
+    let (base, _) = database_url.rsplit_once('/').unwrap();
+    format!("{base}/{name}")
+}
+
+async fn table_exists(db: &DatabaseConnection, table: &str) -> bool {
+    db.query_one(Statement::from_string(
+        sea_orm::DatabaseBackend::Postgres,
+        format!("SELECT to_regclass('{table}') IS NOT NULL AS present"),
+    ))
+    .await
+    .unwrap()
+    .unwrap()
+    .try_get("", "present")
+    .unwrap()
+}
+
+#[tokio::test]
+async fn concurrent_up_down_and_checksum_verification() {
+    let config = webshelf_server::utils::load_config("config.toml", "development")
+        .expect("Failed to load config");
+    let admin = Database::connect(&config.database_url)
+        .await
+        .expect("Failed to connect to database");
+    let name = format!("webshelf_migrations_test_{}", std::process::id());
+    let url = scratch_database(&admin, &config.database_url, &name).await;
+
+    // 多个实例同时启动：advisory lock 保证每个迁移只执行一次
+    let runs: Vec<_> = (0..4)
+        .map(|_| {
+            let url = url.clone();
+            tokio::spawn(async move {
+                let db = Database::connect(&url).await.unwrap();
+                migrations::run_migrations(&db).await
+            })
+        })
+        .collect();
+    for run in runs {
+        run.await.unwrap().expect("concurrent migration run failed");
+    }
+
+    let db = Database::connect(&url).await.unwrap();
+    let statuses = migrations::verify(&db).await.unwrap();
+    assert_eq!(statuses.len(), MIGRATIONS.len());
+    assert!(statuses.iter().all(|s| s.state == MigrationState::Applied));
+    assert!(migrations::migrate_up(&db, None).await.unwrap().is_empty());
+    migrations::require_up_to_date(&db).await.unwrap();
+
+    // 回滚最新的迁移后重新应用
+    let last = MIGRATIONS.last().unwrap();
+    let reverted = migrations::migrate_down(&db, 1).await.unwrap();
+    assert_eq!(reverted.len(), 1);
+    assert_eq!(reverted[0].file, last.file);
+    let statuses = migrations::status(&db).await.unwrap();
+    assert_eq!(statuses.last().unwrap().state, MigrationState::Pending);
+    assert!(migrations::require_up_to_date(&db).await.is_err());
+    if MIGRATIONS.len() == 1 {
+        assert!(!table_exists(&db, "users").await);
+    }
+    let applied = migrations::migrate_up(&db, None).await.unwrap();
+    assert_eq!(applied.len(), 1);
+    assert!(table_exists(&db, "users").await);
+
+    // 已应用的迁移被修改：verify 与启动迁移都必须拒绝
+    db.execute_unprepared(&format!(
+        "UPDATE {} SET checksum = 'edited' WHERE version = {}",
+        migrations::MIGRATIONS_TABLE,
+        last.version()
+    ))
+    .await
+    .unwrap();
+    let err = migrations::verify(&db).await.unwrap_err().to_string();
+    assert!(err.contains(last.file), "{err}");
+    assert!(migrations::run_migrations(&db).await.is_err());
+    assert!(migrations::migrate_down(&db, 1).await.is_err());
+
+    drop(db);
+    admin
+        .execute_unprepared(&format!("DROP DATABASE {name} WITH (FORCE)"))
+        .await
+        .unwrap();
+}
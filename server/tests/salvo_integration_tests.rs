@@ -28,6 +28,19 @@ async fn test_health_check() {
     assert_eq!(body["status"], "ok");
 }
 
+#[tokio::test]
+async fn test_readiness_check() {
+    let server = create_server().await;
+    let (status, body) = salvo::get(&server, "/readyz", None).await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["checks"]["database"], "up");
+
+    let (status, body) = salvo::get(&server, "/livez", None).await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_eq!(body["status"], "alive");
+}
+
 // ── 用户注册 ──────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -64,7 +77,8 @@ async fn test_registration_password_confirm_mismatch() {
 
     let (status, body) = salvo::post_json(&server, "/api/public/auth/register", &payload).await;
     assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
-    assert_eq!(body["error"], "bad_request");
+    assert_eq!(body["error"], "validation_error");
+    assert_eq!(body["errors"]["password_confirm"][0]["code"], "mismatch");
 }
 
 #[tokio::test]
@@ -80,8 +94,10 @@ async fn test_user_registration_invalid_email() {
     let (status, body) = salvo::post_json(&server, "/api/public/auth/register", &payload).await;
     // Should be rejected with 400
     assert!(status.is_client_error());
-    // 验证错误通过 HttpError 返回，error_type 为 "bad_request"（非 "validation_error"）
-    assert_eq!(body["error"], "bad_request");
+    // 校验错误以 problem+json 返回，errors 中按字段给出失败规则
+    assert_eq!(body["error"], "validation_error");
+    assert_eq!(body["status"], 400);
+    assert_eq!(body["errors"]["email"][0]["code"], "email");
 }
 
 #[tokio::test]
@@ -0,0 +1,70 @@
+#![cfg(feature = "webshelf-salvo")]
+
+//! Prometheus `/metrics` 端点（salvo 运行时）— 标签必须与 axum 模式一致。
+//!
+//! 需要运行中的 PostgreSQL 实例。
+
+mod common;
+use common::salvo::{self, TestServer};
+
+/// Test server with the (off by default) scrape route enabled.
+async fn metrics_server() -> TestServer {
+    let mut config = common::load_test_config();
+    config.metrics.enabled = true;
+    salvo::create_test_server_with_config(config).await
+}
+
+async fn scrape(server: &TestServer) -> String {
+    let resp = server
+        .client
+        .get(format!("{}/metrics", server.base_url()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::OK);
+    assert!(
+        resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("application/openmetrics-text; version=1.0.0")
+    );
+    resp.text().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_requests_are_labelled_by_route_pattern() {
+    let server = metrics_server().await;
+    salvo::get(&server, "/api/users/12345", None).await;
+
+    let text = scrape(&server).await;
+    // 被认证中间件拒绝的请求同样带有路由模板
+    assert!(
+        text.contains(r#"http_requests_total{method="GET",route="/api/users/{id}",status="401"}"#)
+    );
+    assert!(text.contains(
+        r#"http_request_duration_seconds_bucket{le="+Inf",method="GET",route="/api/users/{id}"}"#
+    ));
+    assert!(!text.contains("/api/users/12345"));
+}
+
+#[tokio::test]
+async fn test_database_routing_is_counted() {
+    let server = metrics_server().await;
+    salvo::register_and_login(&server, &common::unique_email("metrics")).await;
+
+    let text = scrape(&server).await;
+    assert!(text.contains("# TYPE db_statements counter"));
+    assert!(text.contains(r#"db_statements_total{target="write"}"#));
+}
+
+#[tokio::test]
+async fn test_metrics_route_is_off_by_default() {
+    let server = salvo::create_test_server().await;
+    let resp = server
+        .client
+        .get(format!("{}/metrics", server.base_url()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::NOT_FOUND);
+}
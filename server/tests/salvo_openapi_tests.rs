@@ -0,0 +1,104 @@
+#![cfg(feature = "webshelf-salvo")]
+
+//! OpenAPI 文档服务（salvo 运行时）— 输出必须与 axum 模式完全一致。
+//!
+//! 需要运行中的 PostgreSQL 实例。
+
+mod common;
+use common::salvo::{self, TestServer};
+
+async fn create_server() -> TestServer {
+    salvo::create_test_server().await
+}
+
+#[tokio::test]
+async fn test_openapi_document_is_served() {
+    let server = create_server().await;
+    let (status, body) = salvo::get(&server, "/api/public/openapi.json", None).await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_eq!(body["openapi"], "3.1.0");
+    assert_eq!(body, webshelf_server::routes::openapi_doc().to_json());
+    assert!(body["paths"]["/api/users/{id}"]["put"].is_object());
+    assert!(body["paths"]["/api/public/auth/login"]["post"].is_object());
+    assert!(body["components"]["schemas"]["UserResponse"].is_object());
+}
+
+#[tokio::test]
+async fn test_docs_ui_page_is_served() {
+    let server = create_server().await;
+    let resp = server
+        .client
+        .get(format!("{}/api/public/docs", server.base_url()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::OK);
+    assert!(
+        resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    let html = resp.text().await.unwrap();
+    assert!(html.contains(r#"data-url="/api/public/openapi.json""#));
+}
+
+#[tokio::test]
+async fn test_every_documented_operation_is_routed() {
+    let server = create_server().await;
+    let token = salvo::register_and_login(&server, &common::unique_email("openapi")).await;
+
+    for (method, path) in common::documented_operations() {
+        let method: reqwest::Method = method.parse().unwrap();
+        let mut req = server
+            .client
+            .request(method.clone(), format!("{}{}", server.base_url(), path))
+            .bearer_auth(&token);
+        if method != reqwest::Method::GET {
+            req = req.header("content-type", "application/json").body("{}");
+        }
+        let resp = req.send().await.unwrap();
+        let status = resp.status().as_u16();
+        let json = resp.json().await.unwrap_or(serde_json::Value::Null);
+        assert!(
+            common::route_matched(status, &json),
+            "{method} {path} is documented but not routed (status {status})"
+        );
+    }
+}
+
+/// The reverse of `test_every_documented_operation_is_routed`, checked on the salvo route
+/// tree (axum routers cannot be listed); both runtimes register the same route modules.
+#[tokio::test]
+async fn test_every_route_is_documented() {
+    let state = salvo::create_test_state().await;
+    let config = state.config.clone();
+    let router = webshelf_server::bootstrap::salvo::build_app_router(
+        state,
+        "development",
+        common::disabled_rate_limiter(),
+    );
+    let doc = webshelf_server::routes::openapi_doc();
+    let documented: Vec<(String, &str)> = doc
+        .operations()
+        .map(|(method, path)| (method.to_uppercase(), path))
+        .collect();
+    // 文档页与指标端点不属于 API
+    let undocumented = [
+        config.openapi.path.as_str(),
+        config.openapi.ui_path.as_str(),
+        config.metrics.path.as_str(),
+    ];
+
+    let endpoints = router.endpoints();
+    assert!(endpoints.len() > documented.len() / 2, "{endpoints:?}");
+    for (method, path) in endpoints {
+        if undocumented.contains(&path.as_str()) {
+            continue;
+        }
+        assert!(
+            documented.contains(&(method.clone(), path.as_str())),
+            "{method} {path} is routed but not documented"
+        );
+    }
+}
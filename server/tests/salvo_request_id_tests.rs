@@ -0,0 +1,58 @@
+#![cfg(feature = "webshelf-salvo")]
+
+//! X-Request-Id 传播（salvo 运行时）— 行为必须与 axum 模式一致。
+//!
+//! 需要运行中的 PostgreSQL 实例。
+
+mod common;
+use common::salvo::{self, TestServer};
+
+async fn create_server() -> TestServer {
+    salvo::create_test_server().await
+}
+
+#[tokio::test]
+async fn test_incoming_request_id_is_echoed() {
+    let server = create_server().await;
+    let resp = server
+        .client
+        .get(format!("{}/api/health", server.base_url()))
+        .header("x-request-id", "client-abc-123")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::OK);
+    assert_eq!(resp.headers()["x-request-id"], "client-abc-123");
+}
+
+#[tokio::test]
+async fn test_request_id_is_generated_when_missing() {
+    let server = create_server().await;
+    let url = format!("{}/api/health", server.base_url());
+    let first = server.client.get(&url).send().await.unwrap();
+    let second = server.client.get(&url).send().await.unwrap();
+    let first = first.headers()["x-request-id"].to_str().unwrap().to_owned();
+    let second = second.headers()["x-request-id"]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    assert!(!first.is_empty());
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn test_error_body_carries_request_id() {
+    let server = create_server().await;
+    let resp = server
+        .client
+        .get(format!("{}/api/users", server.base_url()))
+        .header("x-request-id", "trace-401")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert_eq!(resp.headers()["x-request-id"], "trace-401");
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"], "unauthorized");
+    assert_eq!(body["request_id"], "trace-401");
+}
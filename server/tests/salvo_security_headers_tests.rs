@@ -0,0 +1,76 @@
+#![cfg(feature = "webshelf-salvo")]
+
+//! 内置安全响应头（salvo 运行时）— 行为必须与 axum 模式一致。
+//!
+//! 需要运行中的 PostgreSQL 实例。
+
+mod common;
+use common::salvo::{self, TestServer};
+
+async fn create_server() -> TestServer {
+    salvo::create_test_server().await
+}
+
+#[tokio::test]
+async fn test_security_headers_on_api_response() {
+    let server = create_server().await;
+    let resp = server
+        .client
+        .get(format!("{}/api/health", server.base_url()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::OK);
+    let headers = resp.headers();
+    assert_eq!(
+        headers["strict-transport-security"],
+        "max-age=31536000; includeSubDomains"
+    );
+    assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+    assert_eq!(
+        headers["referrer-policy"],
+        "strict-origin-when-cross-origin"
+    );
+    assert!(headers.contains_key("permissions-policy"));
+    let csp = headers["content-security-policy"].to_str().unwrap();
+    assert!(csp.starts_with("default-src 'self'"));
+    assert!(!csp.contains("{nonce}"));
+}
+
+#[tokio::test]
+async fn test_security_headers_on_error_response() {
+    let server = create_server().await;
+    let resp = server
+        .client
+        .get(format!("{}/api/users", server.base_url()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert_eq!(resp.headers()["x-content-type-options"], "nosniff");
+}
+
+#[tokio::test]
+async fn test_docs_page_scripts_carry_csp_nonce() {
+    let server = create_server().await;
+    let resp = server
+        .client
+        .get(format!("{}/api/public/docs", server.base_url()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::OK);
+    let csp = resp.headers()["content-security-policy"]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    let nonce = csp
+        .split("'nonce-")
+        .nth(1)
+        .and_then(|rest| rest.split('\'').next())
+        .expect("nonce in CSP")
+        .to_owned();
+    let html = resp.text().await.unwrap();
+    assert!(html.contains(&format!(r#"nonce="{nonce}""#)));
+}